@@ -0,0 +1,307 @@
+// src/agent/async_agent.rs
+
+use crate::agent::{Agent, BasicAgent, ExecutionState};
+use crate::model::Model;
+use crate::protocol::{ExecutionResult, Plan, PlanStep};
+use crate::tools::ToolResult;
+use crate::tools::cancel::run_cancellable_async;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::task::block_in_place;
+use tracing::field::Empty;
+use tracing::{Instrument, info_span};
+
+/// Async agent loop built on tokio.
+///
+/// Consecutive tool calls backed by an `AsyncTool` run concurrently unless one
+/// consumes another's `$output[...]`. Blocking work (planning, approvals, sync
+/// tools) goes through `block_in_place`, so drive this from a multi-threaded
+/// runtime such as the one returned by [`runtime`].
+pub trait AsyncAgent {
+    fn plan_async(&mut self) -> impl Future<Output = Plan> + Send;
+    fn execute_async(&mut self, plan: &Plan) -> impl Future<Output = ExecutionResult> + Send;
+    fn replan_async(&mut self, reflection: &str) -> impl Future<Output = Option<Plan>> + Send;
+}
+
+/// A multi-threaded tokio runtime suitable for `AsyncAgent`.
+pub fn runtime() -> std::io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+}
+
+impl<M: Model + Send> AsyncAgent for BasicAgent<M> {
+    async fn plan_async(&mut self) -> Plan {
+        block_in_place(|| self.plan())
+    }
+
+    async fn execute_async(&mut self, plan: &Plan) -> ExecutionResult {
+        if let Some(rejected) = block_in_place(|| self.reject_invalid_plan(plan)) {
+            return rejected;
+        }
+        let mut state = self.initial_state();
+        let span = info_span!("execute", steps = plan.steps.len());
+        self.execute_steps_async(&plan.steps, &mut state)
+            .instrument(span)
+            .await;
+        block_in_place(|| self.finish_execution(state))
+    }
+
+    async fn replan_async(&mut self, reflection: &str) -> Option<Plan> {
+        block_in_place(|| self.replan(reflection))
+    }
+}
+
+impl<M: Model + Send> BasicAgent<M> {
+    /// Boxed so conditional branches and loop bodies can recurse.
+    fn execute_steps_async<'a>(
+        &'a mut self,
+        steps: &'a [PlanStep],
+        state: &'a mut ExecutionState,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            let mut index = 0;
+
+            while index < steps.len() {
+                if block_in_place(|| self.stop_if_cancelled(state)) {
+                    self.record_cancelled_steps(state, &steps[index..]);
+                    return;
+                }
+                match &steps[index] {
+                    PlanStep::Info(message) => {
+                        self.record_info(state, message);
+                        index += 1;
+                        continue;
+                    }
+                    PlanStep::AskUser { question, variable } => {
+                        block_in_place(|| self.ask_user(state, question, variable));
+                        index += 1;
+                        continue;
+                    }
+                    PlanStep::Conditional {
+                        predicate,
+                        then_steps,
+                        else_steps,
+                    } => {
+                        let holds = block_in_place(|| self.check_predicate(state, predicate));
+                        let branch = if holds { then_steps } else { else_steps };
+                        self.execute_steps_async(branch, state).await;
+                        index += 1;
+                        continue;
+                    }
+                    PlanStep::Loop {
+                        steps: body,
+                        max_iterations,
+                        until,
+                    } => {
+                        let failures_before = state.critical_failures;
+                        for iteration in 1..=*max_iterations {
+                            self.execute_steps_async(body, state).await;
+                            if state.cancelled {
+                                break;
+                            }
+                            let stop = block_in_place(|| {
+                                self.end_loop_iteration(
+                                    state,
+                                    iteration,
+                                    *max_iterations,
+                                    until.as_ref(),
+                                    failures_before,
+                                )
+                            });
+                            if stop {
+                                break;
+                            }
+                        }
+                        index += 1;
+                        continue;
+                    }
+                    PlanStep::ToolCall { .. } => {}
+                }
+
+                let batch = self.concurrent_batch_len(&steps[index..]);
+                let mut pending = Vec::new();
+
+                for step in &steps[index..index + batch] {
+                    let PlanStep::ToolCall {
+                        id,
+                        name,
+                        input,
+                        args,
+                        timeout,
+                        transform,
+                        background,
+                    } = step
+                    else {
+                        continue;
+                    };
+                    let (resolved_input, resolved_args) = state.resolve_call(input, args.as_ref());
+                    if self.context.dry_run {
+                        self.start_step(name, id.as_deref(), &resolved_input);
+                        match self.simulate_tool(name, &resolved_input) {
+                            Some(result) => {
+                                block_in_place(|| {
+                                    self.record_tool_result(
+                                        state,
+                                        name,
+                                        id.as_deref(),
+                                        &resolved_input,
+                                        result,
+                                    )
+                                });
+                            }
+                            None => self.record_missing_tool(state, name),
+                        }
+                        continue;
+                    }
+                    if !block_in_place(|| self.within_limits(state, name)) {
+                        break;
+                    }
+                    if !block_in_place(|| {
+                        self.approve_step(name, &resolved_input, resolved_args.as_ref())
+                    }) {
+                        tracing::info!("⏭️ Skipped {}", name);
+                        self.model
+                            .task_mut()
+                            .skip_step(name, id.as_deref(), &resolved_input);
+                        continue;
+                    }
+                    self.start_step(name, id.as_deref(), &resolved_input);
+
+                    let async_tool = self.context.get_async_tool(name).filter(|_| !*background);
+                    let handle = async_tool.map(|tool| {
+                        let input = self
+                            .context
+                            .resolve_env(&resolved_input, None)
+                            .and_then(|(input, _)| self.context.resolve_secrets(&input, None));
+                        let secrets = self.context.secrets.clone();
+                        let limit = self
+                            .limit_timeout(state, name, *timeout)
+                            .or_else(|| self.context.tool_timeout(name));
+                        let token = self.context.cancel.child(limit);
+                        let span = info_span!("tool_call", tool = %name, success = Empty);
+                        tokio::spawn(
+                            async move {
+                                let result = match input {
+                                    Ok((input, _)) => {
+                                        run_cancellable_async(
+                                            tool.execute_async(&input),
+                                            &token,
+                                            limit,
+                                        )
+                                        .await
+                                    }
+                                    Err(e) => ToolResult::failure(&e),
+                                };
+                                tracing::Span::current().record("success", result.success);
+                                secrets.redact_result(result)
+                            }
+                            .instrument(span),
+                        )
+                    });
+                    pending.push((
+                        id,
+                        name.clone(),
+                        resolved_input,
+                        resolved_args,
+                        self.limit_timeout(state, name, *timeout),
+                        transform,
+                        *background,
+                        handle,
+                    ));
+                }
+
+                for (
+                    id,
+                    name,
+                    resolved_input,
+                    resolved_args,
+                    timeout,
+                    transform,
+                    background,
+                    handle,
+                ) in pending
+                {
+                    let result = match handle {
+                        Some(handle) => handle.await.unwrap_or_else(|e| {
+                            ToolResult::failure(&format!("Tool task failed: {e}"))
+                        }),
+                        None => {
+                            let invoked = block_in_place(|| {
+                                if background {
+                                    let process = id.as_deref().unwrap_or(&name);
+                                    self.context.start_background(
+                                        &name,
+                                        process,
+                                        &resolved_input,
+                                        resolved_args.as_ref(),
+                                    )
+                                } else {
+                                    self.context.invoke_tool(
+                                        &name,
+                                        &resolved_input,
+                                        resolved_args.as_ref(),
+                                        timeout,
+                                    )
+                                }
+                            });
+                            match invoked {
+                                Some(result) => result,
+                                None => {
+                                    self.record_missing_tool(state, &name);
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    let result = result.transformed(transform);
+                    block_in_place(|| {
+                        self.record_tool_result(
+                            state,
+                            &name,
+                            id.as_deref(),
+                            &resolved_input,
+                            result,
+                        )
+                    });
+                }
+
+                index += batch;
+            }
+        })
+    }
+
+    /// Number of leading tool calls in `steps` that may run concurrently (at least 1).
+    fn concurrent_batch_len(&self, steps: &[PlanStep]) -> usize {
+        let mut produced: Vec<&str> = Vec::new();
+
+        for (i, step) in steps.iter().enumerate() {
+            let PlanStep::ToolCall {
+                id,
+                name,
+                input,
+                args,
+                ..
+            } = step
+            else {
+                return i.max(1);
+            };
+            let args_text = args.as_ref().map(Value::to_string).unwrap_or_default();
+            let consumes_batch_output = produced.iter().any(|key| {
+                [format!("$output[{}]", key), format!("$output[{}.", key)]
+                    .iter()
+                    .any(|reference| input.contains(reference) || args_text.contains(reference))
+            });
+            if self.context.get_async_tool(name).is_none() || consumes_batch_output {
+                return i.max(1);
+            }
+            produced.push(name);
+            if let Some(id) = id {
+                produced.push(id);
+            }
+        }
+
+        steps.len().max(1)
+    }
+}
@@ -0,0 +1,167 @@
+// src/agent/builder.rs
+
+use crate::agent::BasicAgent;
+use crate::agent::reviewer::ReviewerAgent;
+use crate::context::Context;
+use crate::model::{RunLimits, TaskModel};
+use crate::policy::ApprovalPolicy;
+use crate::protocol::decomposer::Decomposer;
+use crate::protocol::evaluator::Evaluator;
+use crate::protocol::planner::{LLMPlanner, Planner};
+use crate::protocol::replanner::{LLMReplanner, Replanner};
+use crate::protocol::verifier::Verifier;
+use crate::tools::{LLMTool, Tool};
+use std::sync::Arc;
+
+/// Fluent construction of a `BasicAgent`:
+///
+/// ```no_run
+/// # use agentic_runtime::agent::builder::AgentBuilder;
+/// # use agentic_runtime::policy::ApprovalPolicy;
+/// # use agentic_runtime::tools::{LLMTool, ReflectorTool};
+/// let llm = LLMTool::default();
+/// let agent = AgentBuilder::new()
+///     .goal("make the tests pass")
+///     .llm(llm.clone())
+///     .with_tool(ReflectorTool::new(llm))
+///     .approval(ApprovalPolicy::AskDestructive)
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Defaults: an empty `Context::new()`, default `RunLimits`, and, when an
+/// `llm` is given, an `LLMPlanner` and `LLMReplanner` backed by it for
+/// whichever of the two was not set explicitly.
+#[derive(Default)]
+pub struct AgentBuilder {
+    goal: String,
+    limits: RunLimits,
+    context: Option<Context>,
+    tools: Vec<Arc<dyn Tool + Send + Sync>>,
+    approval: Option<ApprovalPolicy>,
+    llm: Option<LLMTool>,
+    planner: Option<Box<dyn Planner>>,
+    replanner: Option<Box<dyn Replanner>>,
+    verifier: Option<Box<dyn Verifier>>,
+    reviewer: Option<ReviewerAgent>,
+    evaluator: Option<Box<dyn Evaluator>>,
+    decomposer: Option<Box<dyn Decomposer>>,
+}
+
+impl AgentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn goal(mut self, goal: &str) -> Self {
+        self.goal = goal.into();
+        self
+    }
+
+    pub fn limits(mut self, limits: RunLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Starts from `context` instead of `Context::new()`; tools and approval
+    /// set on the builder are applied on top of it.
+    pub fn context(mut self, context: Context) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// The model behind the default planner and replanner.
+    pub fn llm(mut self, llm: LLMTool) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    pub fn approval(mut self, policy: ApprovalPolicy) -> Self {
+        self.approval = Some(policy);
+        self
+    }
+
+    pub fn with_tool<T: Tool + Send + Sync + 'static>(mut self, tool: T) -> Self {
+        self.tools.push(Arc::new(tool));
+        self
+    }
+
+    /// Registers several tools at once, e.g. `[Arc::new(a) as _, Arc::new(b) as _]`.
+    pub fn with_tools(
+        mut self,
+        tools: impl IntoIterator<Item = Arc<dyn Tool + Send + Sync>>,
+    ) -> Self {
+        self.tools.extend(tools);
+        self
+    }
+
+    pub fn with_planner(mut self, planner: impl Planner + 'static) -> Self {
+        self.planner = Some(Box::new(planner));
+        self
+    }
+
+    pub fn with_replanner(mut self, replanner: impl Replanner + 'static) -> Self {
+        self.replanner = Some(Box::new(replanner));
+        self
+    }
+
+    /// See `BasicAgent::with_verifier`.
+    pub fn with_verifier(mut self, verifier: impl Verifier + 'static) -> Self {
+        self.verifier = Some(Box::new(verifier));
+        self
+    }
+
+    /// See `BasicAgent::with_reviewer`.
+    pub fn with_reviewer(mut self, reviewer: ReviewerAgent) -> Self {
+        self.reviewer = Some(reviewer);
+        self
+    }
+
+    /// See `BasicAgent::with_evaluator`.
+    pub fn with_evaluator(mut self, evaluator: impl Evaluator + 'static) -> Self {
+        self.evaluator = Some(Box::new(evaluator));
+        self
+    }
+
+    /// See `BasicAgent::with_decomposer`.
+    pub fn with_decomposer(mut self, decomposer: impl Decomposer + 'static) -> Self {
+        self.decomposer = Some(Box::new(decomposer));
+        self
+    }
+
+    /// Fails without a goal, or without a planner when no `llm` was given.
+    pub fn build(self) -> Result<BasicAgent, String> {
+        if self.goal.trim().is_empty() {
+            return Err("AgentBuilder needs a goal".into());
+        }
+        let planner = match (self.planner, &self.llm) {
+            (Some(planner), _) => planner,
+            (None, Some(llm)) => Box::new(LLMPlanner::new(llm.clone())),
+            (None, None) => return Err("AgentBuilder needs a planner or an llm".into()),
+        };
+        let replanner = self.replanner.or_else(|| {
+            self.llm
+                .map(|llm| Box::new(LLMReplanner::new(llm)) as Box<dyn Replanner>)
+        });
+
+        let mut context = self.context.unwrap_or_default();
+        for tool in self.tools {
+            context.tools.register(tool);
+        }
+        if let Some(policy) = self.approval {
+            context = context.with_approval(policy);
+        }
+
+        let mut agent = BasicAgent::new(
+            TaskModel::new(&self.goal).with_limits(self.limits),
+            context,
+            Some(planner),
+            replanner,
+        );
+        agent.verifier = self.verifier;
+        agent.reviewer = self.reviewer;
+        agent.evaluator = self.evaluator;
+        agent.decomposer = self.decomposer;
+        Ok(agent)
+    }
+}
@@ -0,0 +1,147 @@
+// src/agent/graph.rs
+
+use crate::agent::BasicAgent;
+use crate::model::Model;
+use crate::protocol::graph::PlanGraph;
+use crate::protocol::{ExecutionResult, PlanStep};
+use crate::tools::ToolResult;
+use std::thread;
+
+impl<M: Model> BasicAgent<M> {
+    /// Runs a `PlanGraph` layer by layer. Tool calls within a layer are
+    /// independent and run on parallel threads; other steps run in order.
+    /// Every result is also stored under its node id, so `$output[node_id]`
+    /// resolves across branches.
+    pub fn execute_graph(&mut self, graph: &PlanGraph) -> ExecutionResult {
+        let mut state = self.initial_state();
+
+        let layers = match graph.layers() {
+            Ok(layers) => layers,
+            Err(e) => {
+                self.context
+                    .log("graph", &format!("❌ Invalid plan graph: {}", e));
+                state.critical_failures += 1;
+                state.errors.push(e);
+                return self.finish_execution(state);
+            }
+        };
+
+        for (position, layer) in layers.iter().enumerate() {
+            if self.stop_if_cancelled(&mut state) {
+                let skipped = layers[position..].iter().flatten();
+                self.record_cancelled_steps(&mut state, skipped.map(|&i| &graph.nodes[i].step));
+                break;
+            }
+            let mut calls = Vec::new();
+
+            for &index in layer {
+                let node = &graph.nodes[index];
+                let PlanStep::ToolCall {
+                    id: step_id,
+                    name,
+                    input,
+                    args,
+                    timeout,
+                    transform,
+                    background,
+                } = &node.step
+                else {
+                    self.execute_steps(std::slice::from_ref(&node.step), &mut state);
+                    continue;
+                };
+
+                let (resolved_input, resolved_args) = state.resolve_call(input, args.as_ref());
+                if self.context.dry_run {
+                    self.start_step(name, step_id.as_deref(), &resolved_input);
+                    match self.simulate_tool(name, &resolved_input) {
+                        Some(result) => {
+                            let id = step_id.as_deref().unwrap_or(&node.id);
+                            self.record_tool_result(
+                                &mut state,
+                                name,
+                                Some(id),
+                                &resolved_input,
+                                result,
+                            );
+                            state.alias_result(name, &node.id);
+                        }
+                        None => self.record_missing_tool(&mut state, name),
+                    }
+                    continue;
+                }
+                if !self.within_limits(&mut state, name) {
+                    break;
+                }
+                if !self.approve_step(name, &resolved_input, resolved_args.as_ref()) {
+                    tracing::info!("⏭️ Skipped {}", name);
+                    self.model
+                        .task_mut()
+                        .skip_step(name, step_id.as_deref(), &resolved_input);
+                    continue;
+                }
+                self.start_step(name, step_id.as_deref(), &resolved_input);
+                let timeout = self.limit_timeout(&state, name, *timeout);
+                calls.push((
+                    node,
+                    name.as_str(),
+                    step_id,
+                    resolved_input,
+                    resolved_args,
+                    timeout,
+                    transform,
+                    *background,
+                ));
+            }
+
+            if calls.len() > 1 {
+                self.context.log(
+                    "graph",
+                    &format!("Running {} independent tool calls in parallel", calls.len()),
+                );
+            }
+
+            let context = &self.context;
+            let results: Vec<Option<ToolResult>> = thread::scope(|scope| {
+                let handles: Vec<_> = calls
+                    .iter()
+                    .map(
+                        |(node, name, step_id, input, args, timeout, _, background)| {
+                            scope.spawn(move || {
+                                if *background {
+                                    let id = step_id.as_deref().unwrap_or(&node.id);
+                                    context.start_background(name, id, input, args.as_ref())
+                                } else {
+                                    context.invoke_tool(name, input, args.as_ref(), *timeout)
+                                }
+                            })
+                        },
+                    )
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|_| Some(ToolResult::failure("Tool thread panicked")))
+                    })
+                    .collect()
+            });
+
+            for ((node, name, step_id, input, _, _, transform, _), result) in
+                calls.iter().zip(results)
+            {
+                match result {
+                    Some(result) => {
+                        let id = step_id.as_deref().unwrap_or(&node.id);
+                        let result = result.transformed(transform);
+                        self.record_tool_result(&mut state, name, Some(id), input, result);
+                        state.alias_result(name, &node.id);
+                    }
+                    None => self.record_missing_tool(&mut state, name),
+                }
+            }
+        }
+
+        self.finish_execution(state)
+    }
+}
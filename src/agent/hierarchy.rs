@@ -0,0 +1,125 @@
+// src/agent/hierarchy.rs
+
+use crate::agent::{Agent, BasicAgent};
+use crate::model::TaskModel;
+use crate::model::task_graph::{SubtaskStatus, TaskGraph};
+use crate::protocol::{ExecutionResult, Plan, PlanStep};
+
+impl BasicAgent {
+    /// Decomposes the goal into a `TaskGraph` (once; later calls reuse the
+    /// graph on the model), then plans and executes each pending subtask in
+    /// dependency order. Done subtasks are skipped, so calling this again
+    /// after a failure only retries what is left. Subtasks whose dependencies
+    /// did not finish are left pending. Returns every subtask plan, joined.
+    pub fn execute_subtasks(&mut self) -> (Plan, ExecutionResult) {
+        let mut graph = match self.model.subtasks.take() {
+            Some(graph) => graph,
+            None => self.decompose_goal(),
+        };
+        let order = graph.order().unwrap_or_else(|e| {
+            self.context
+                .log("decomposition", &format!("❌ Invalid task graph: {}", e));
+            graph = TaskGraph::new().with_subtask("goal", &self.model.goal, &[]);
+            vec![0]
+        });
+        self.model.subtasks = Some(graph);
+
+        let mut state = self.initial_state();
+        let mut steps = Vec::new();
+        for index in order {
+            if self.stop_if_cancelled(&mut state) {
+                break;
+            }
+            let graph = self.model.subtasks.as_ref().expect("set above");
+            let subtask = &graph.subtasks[index];
+            if subtask.status == SubtaskStatus::Done {
+                continue;
+            }
+            let (id, description) = (subtask.id.clone(), subtask.description.clone());
+            if !graph.is_ready(index) {
+                self.context.log(
+                    "subtask",
+                    &format!("⏭️ Skipped subtask '{}': dependencies not done", id),
+                );
+                continue;
+            }
+
+            self.context
+                .log("subtask", &format!("🧩 Subtask '{}': {}", id, description));
+            let plan = self.plan_subtask(&description);
+            steps.push(PlanStep::Info(format!("Subtask {}: {}", id, description)));
+            steps.extend(plan.steps.iter().cloned());
+
+            let (failures, output_start) = (state.critical_failures, state.combined_output.len());
+            self.execute_steps(&plan.steps, &mut state);
+            let succeeded = state.critical_failures == failures && !state.cancelled;
+            let output = state.combined_output[output_start..].trim().to_string();
+
+            let subtask = &mut self.model.subtasks.as_mut().expect("set above").subtasks[index];
+            subtask.attempts += 1;
+            subtask.output = (!output.is_empty()).then_some(output);
+            subtask.status = match succeeded {
+                true => SubtaskStatus::Done,
+                false => SubtaskStatus::Failed,
+            };
+            let icon = if succeeded { "✅" } else { "❌" };
+            self.context.log(
+                "subtask",
+                &format!("{} Subtask '{}' {:?}", icon, id, subtask.status),
+            );
+        }
+
+        if let Some(graph) = &self.model.subtasks {
+            self.context.log("subtask_progress", &graph.progress());
+            if !graph.is_complete() && state.critical_failures == 0 {
+                state.critical_failures += 1;
+                state.errors.push("Not every subtask was completed".into());
+            }
+        }
+        (Plan { steps }, self.finish_execution(state))
+    }
+
+    /// The decomposer's graph, or the whole goal as one subtask without one.
+    fn decompose_goal(&mut self) -> TaskGraph {
+        let single = || TaskGraph::new().with_subtask("goal", &self.model.goal, &[]);
+        let Some(decomposer) = &self.decomposer else {
+            return single();
+        };
+        match decomposer.decompose(&self.context, &self.model.goal) {
+            Ok(graph) => {
+                self.context.log(
+                    "decomposition",
+                    &format!(
+                        "🧩 Decomposed goal into {} subtask(s):\n{}",
+                        graph.subtasks.len(),
+                        graph.progress()
+                    ),
+                );
+                graph
+            }
+            Err(e) => {
+                self.context.log(
+                    "decomposition",
+                    &format!("⚠️ {}; planning the goal as a whole", e),
+                );
+                single()
+            }
+        }
+    }
+
+    /// Plans one subtask with the goal's limits and the progress so far,
+    /// then puts the goal's model back.
+    fn plan_subtask(&mut self, description: &str) -> Plan {
+        let mut subtask_model = TaskModel::new(&format!(
+            "{} (part of the overall goal: {})",
+            description, self.model.goal
+        ))
+        .with_limits(self.model.limits.clone());
+        subtask_model.subtasks = self.model.subtasks.clone();
+
+        let goal_model = std::mem::replace(&mut self.model, subtask_model);
+        let plan = self.plan();
+        self.model = goal_model;
+        plan
+    }
+}
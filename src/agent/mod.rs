@@ -1,12 +1,39 @@
 // src/agent/mod.rs
 
-use crate::context::Context;
-use crate::model::TaskModel;
+pub mod async_agent;
+pub mod builder;
+pub mod graph;
+pub mod hierarchy;
+pub mod reviewer;
+pub mod run_loop;
+pub mod runner;
+
+pub use builder::AgentBuilder;
+
+use crate::agent::reviewer::ReviewerAgent;
+use crate::context::{Context, ContextSnapshot, ToolCallRecord};
+use crate::events::AgentEvent;
+use crate::memory::{PLANNER_NAMESPACE, TOOL_DATA_NAMESPACE};
+use crate::model::{Model, TaskModel};
+use crate::policy::ApprovalPolicy;
+use crate::protocol::decomposer::Decomposer;
+use crate::protocol::evaluator::Evaluator;
 use crate::protocol::planner::Planner;
 use crate::protocol::replanner::Replanner;
-use crate::protocol::{ExecutionResult, Feedback, Plan, PlanStep, SimulationResult};
-
-use std::io::{Write, stdin, stdout};
+use crate::protocol::transform::{field_text, json_field};
+use crate::protocol::verifier::Verifier;
+use crate::protocol::{
+    ExecutionResult, Feedback, Plan, PlanStep, Predicate, RiskLevel, SimulationResult, StepPreview,
+    StepResult, input_reference_regex, missing_output, output_reference_regex,
+};
+use crate::risk;
+use crate::tools::ToolResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::field::Empty;
+use tracing::{debug, info, info_span, warn};
 
 pub trait Agent {
     fn plan(&mut self) -> Plan;
@@ -16,16 +43,29 @@ pub trait Agent {
     fn replan(&mut self, reflection: &str) -> Option<Plan>;
 }
 
-pub struct BasicAgent {
-    pub model: TaskModel,
+/// Plans, simulates, executes and replans the goal of its `model`; see
+/// `Model` for driving the loop with a domain model instead of a `TaskModel`.
+pub struct BasicAgent<M: Model = TaskModel> {
+    pub model: M,
     pub context: Context,
     pub planner: Option<Box<dyn Planner>>,
     pub replanner: Option<Box<dyn Replanner>>,
+    pub verifier: Option<Box<dyn Verifier>>,
+    pub reviewer: Option<ReviewerAgent>,
+    pub evaluator: Option<Box<dyn Evaluator>>,
+    pub decomposer: Option<Box<dyn Decomposer>>,
+}
+
+/// A point `BasicAgent::restore` can roll back to.
+#[derive(Clone, Debug)]
+pub struct AgentSnapshot {
+    pub context: ContextSnapshot,
+    pub task: TaskModel,
 }
 
-impl BasicAgent {
+impl<M: Model> BasicAgent<M> {
     pub fn new(
-        model: TaskModel,
+        model: M,
         context: Context,
         planner: Option<Box<dyn Planner>>,
         replanner: Option<Box<dyn Replanner>>,
@@ -35,205 +75,1044 @@ impl BasicAgent {
             context,
             planner,
             replanner,
+            verifier: None,
+            reviewer: None,
+            evaluator: None,
+            decomposer: None,
         }
     }
+
+    /// Check the goal after every execution; the verdict decides `goal_achieved()`.
+    pub fn with_verifier(mut self, verifier: Box<dyn Verifier>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Score executions with `evaluator` (e.g. an `LLMJudge`) in `evaluate`.
+    pub fn with_evaluator(mut self, evaluator: Box<dyn Evaluator>) -> Self {
+        self.evaluator = Some(evaluator);
+        self
+    }
+
+    /// Split the goal into subtasks for `execute_subtasks`.
+    pub fn with_decomposer(mut self, decomposer: Box<dyn Decomposer>) -> Self {
+        self.decomposer = Some(decomposer);
+        self
+    }
+
+    /// Have plans (and follow-up plans) critiqued before they run.
+    pub fn with_reviewer(mut self, reviewer: ReviewerAgent) -> Self {
+        self.reviewer = Some(reviewer);
+        self
+    }
+
+    /// How far the context's memory got, and the task model, for rolling
+    /// back a speculative branch or an abandoned plan with `restore`.
+    pub fn snapshot(&self) -> AgentSnapshot {
+        AgentSnapshot {
+            context: self.context.snapshot(),
+            task: self.model.task().clone(),
+        }
+    }
+
+    /// Drops the memory entries logged since `snapshot` and puts the task
+    /// model back. Only that is rolled back: the tool history, events and
+    /// usage keep what happened, and an `ExecutionState` in progress is the
+    /// caller's to discard.
+    pub fn restore(&mut self, snapshot: &AgentSnapshot) -> Result<(), String> {
+        self.context.restore(&snapshot.context)?;
+        *self.model.task_mut() = snapshot.task.clone();
+        Ok(())
+    }
+
+    /// Runs `plan` past the reviewer, if one is configured.
+    fn review_plan(&mut self, plan: Plan) -> Plan {
+        let Some(reviewer) = &self.reviewer else {
+            return plan;
+        };
+        let _span = info_span!("review", goal = %self.model.goal()).entered();
+        reviewer
+            .review(
+                &mut self.context,
+                self.model.goal(),
+                plan,
+                self.replanner.as_deref(),
+            )
+            .plan
+    }
 }
 
-impl Agent for BasicAgent {
+impl<M: Model> Agent for BasicAgent<M> {
     fn plan(&mut self) -> Plan {
-        if let Some(planner) = &self.planner {
-            self.context.log("planning", "Using dynamic LLM planner");
-            planner.generate_plan(&mut self.context, &self.model.goal)
-        } else {
-            self.context.log("planning", "Using static hardcoded plan");
-            Plan {
-                steps: vec![
-                    PlanStep::Info(format!("Understand goal: {}", self.model.goal)),
-                    PlanStep::ToolCall {
-                        name: "git_status".into(),
-                        input: "Check repo state".into(),
-                    },
-                    PlanStep::ToolCall {
-                        name: "reflect".into(),
-                        input: "Summarize changes".into(),
-                    },
-                    PlanStep::ToolCall {
-                        name: "echo".into(),
-                        input: "Task complete.".into(),
-                    },
-                    PlanStep::Info("Generate output".into()),
-                ],
-            }
-        }
+        let span = info_span!("plan", goal = %self.model.goal(), steps = Empty).entered();
+        let plan = self.generate_plan();
+        let plan = self.review_plan(plan);
+        span.record("steps", plan.steps.len());
+        self.context.emit(AgentEvent::PlanGenerated {
+            goal: self.model.goal().to_string(),
+            plan: plan.to_json(),
+            replan: false,
+        });
+        plan
     }
 
     fn simulate(&self, plan: &Plan) -> SimulationResult {
         let mut warnings = vec![];
         let mut tools_used = vec![];
+        let mut previews = vec![];
 
-        for step in &plan.steps {
-            if let PlanStep::ToolCall { name, .. } = step {
-                if let Some(tool) = self.context.get_tool(name) {
-                    let spec = tool.spec();
-                    tools_used.push(format!(
-                        "[TOOL] {} - {} (hint: {})",
-                        spec.name, spec.description, spec.input_hint
-                    ));
-                } else {
-                    warnings.push(format!("Tool '{}' not registered", name));
-                }
-            }
-        }
+        self.simulate_steps(&plan.steps, &mut warnings, &mut tools_used, &mut previews);
 
+        let mutating = previews
+            .iter()
+            .filter(|preview| !preview.outcome.read_only)
+            .count();
         let predicted = format!(
-            "Plan contains {} step(s) and will attempt {} tool call(s).",
+            "Plan contains {} step(s) and will attempt {} tool call(s), {} of which change state.",
             plan.steps.len(),
-            tools_used.len()
+            tools_used.len(),
+            mutating
         );
 
+        for preview in &previews {
+            if preview.outcome.risk == RiskLevel::High {
+                let why = match preview.outcome.reasons.is_empty() {
+                    true => preview.outcome.summary.clone(),
+                    false => preview.outcome.reasons.join("; "),
+                };
+                warnings.push(format!(
+                    "High-risk step: {} `{}` ({})",
+                    preview.tool, preview.input, why
+                ));
+            }
+        }
         warnings.extend(tools_used);
 
         SimulationResult {
             predicted_outcome: predicted,
             warnings,
+            steps: previews,
         }
     }
 
     fn execute(&mut self, plan: &Plan) -> ExecutionResult {
-        println!("--- PLAN ---\n{:#?}", plan);
+        let _span = info_span!("execute", steps = plan.steps.len()).entered();
+        debug!("plan: {:?}", plan);
         let simulation = self.simulate(plan);
-        println!("--- SIMULATION ---\n{:#?}", simulation);
+        debug!("simulation: {:?}", simulation);
+        if let Some(rejected) = self.reject_invalid_plan(plan) {
+            return rejected;
+        }
 
-        let mut combined_output = String::new();
-        let mut errors = vec![];
-        let mut critical_failures = 0;
-        let mut previous_outputs = std::collections::HashMap::new();
+        let mut state = self.initial_state();
+        self.execute_steps(&plan.steps, &mut state);
+        self.finish_execution(state)
+    }
 
-        for step in &plan.steps {
-            match step {
-                PlanStep::ToolCall { name, input } => {
-                    let resolved_input = if input.starts_with("$output[") && input.ends_with("]") {
-                        let key = &input[8..input.len() - 1];
-                        previous_outputs
-                            .get(key)
-                            .cloned()
-                            .unwrap_or_else(|| format!("(missing output for '{}')", key))
-                    } else {
-                        input.clone()
-                    };
+    fn evaluate(&self, result: &ExecutionResult) -> Feedback {
+        // A cancelled run is not worth a model call to judge.
+        if let Some(evaluator) = &self.evaluator
+            && !self.context.cancel.is_cancelled()
+        {
+            let span = info_span!("evaluate", score = Empty).entered();
+            match evaluator.evaluate_task(&self.model, result) {
+                Ok(feedback) => {
+                    span.record("score", feedback.score);
+                    info!("⚖️ Scored {}/100: {}", feedback.score, feedback.notes);
+                    return feedback;
+                }
+                // Fall back to the verdict or step results below.
+                Err(e) => warn!("⚠️ Evaluation failed: {}", e),
+            }
+        }
+        if let Some(verdict) = &result.verdict {
+            let confidence = (verdict.confidence * 100.0).round() as u8;
+            return Feedback {
+                score: if verdict.achieved {
+                    confidence.max(50)
+                } else {
+                    (100 - confidence).min(40)
+                },
+                notes: verdict.reasoning.clone(),
+                criteria: Vec::new(),
+                critique: verdict.missing.clone(),
+            };
+        }
+        Feedback {
+            score: if result.success { 90 } else { 30 },
+            notes: "Dynamic tool execution complete.".into(),
+            criteria: Vec::new(),
+            critique: result.errors.clone(),
+        }
+    }
+
+    fn replan(&mut self, reflection: &str) -> Option<Plan> {
+        let _span = info_span!("replan", goal = %self.model.goal()).entered();
+        if let Some(replanner) = &self.replanner {
+            self.context.emit(AgentEvent::ReplanTriggered {
+                goal: self.model.goal().to_string(),
+                reason: reflection.to_string(),
+            });
+            self.context.log_in(
+                PLANNER_NAMESPACE,
+                "replanner",
+                "Using reflection-based replanning",
+            );
+            let plan = replanner.replan_task(&mut self.context, &self.model, reflection);
+            if !plan.steps.is_empty() {
+                let plan = self.review_plan(plan);
+                self.context.emit(AgentEvent::PlanGenerated {
+                    goal: self.model.goal().to_string(),
+                    plan: plan.to_json(),
+                    replan: true,
+                });
+                Some(plan)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
+
+/// Mutable bookkeeping shared by the blocking and async executors.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ExecutionState {
+    pub combined_output: String,
+    pub errors: Vec<String>,
+    pub critical_failures: usize,
+    pub previous_outputs: HashMap<String, String>,
+    /// Structured data of the latest successful results, keyed like
+    /// `previous_outputs`.
+    #[serde(default)]
+    pub previous_data: HashMap<String, Value>,
+    /// Latest output (or error) and success flag per tool, for predicates.
+    pub last_results: HashMap<String, (String, bool)>,
+    pub cancelled: bool,
+    pub steps: Vec<StepResult>,
+    /// Tool calls and shell commands so far, counted against `RunLimits`.
+    #[serde(default)]
+    pub tool_calls: usize,
+    #[serde(default)]
+    pub shell_commands: usize,
+    /// When the first tool call started; the wall-time limit counts from here.
+    #[serde(skip)]
+    pub started: Option<Instant>,
+    /// The limit that stopped execution, if one did.
+    #[serde(default)]
+    pub budget_exceeded: Option<String>,
+    /// Answers to `AskUser` steps, by variable.
+    #[serde(default)]
+    pub inputs: HashMap<String, String>,
+}
+
+impl ExecutionState {
+    /// Replaces every `$output[key]` reference, where `key` is a step id or
+    /// (for steps without one) a tool name, with that step's latest output,
+    /// and every `$output[key.field.path]` with that field of its structured
+    /// data. `$input[variable]` references are replaced with the user's
+    /// answers.
+    pub fn resolve_input(&self, input: &str) -> String {
+        let mut input = input.to_string();
+        if input.contains("$output[") {
+            input = output_reference_regex()
+                .replace_all(&input, |caps: &regex::Captures| {
+                    let key = &caps[1];
+                    self.previous_outputs
+                        .get(key)
+                        .cloned()
+                        .or_else(|| self.output_data(key).map(field_text))
+                        .unwrap_or_else(|| missing_output(key))
+                })
+                .into_owned();
+        }
+        if input.contains("$input[") {
+            input = input_reference_regex()
+                .replace_all(&input, |caps: &regex::Captures| {
+                    let key = &caps[1];
+                    self.inputs
+                        .get(key)
+                        .cloned()
+                        .unwrap_or_else(|| format!("(missing input for '{}')", key))
+                })
+                .into_owned();
+        }
+        input
+    }
+
+    /// The structured data `$output[reference]` points at: a step's whole
+    /// payload, or with a dotted path, one field of it.
+    pub fn output_data(&self, reference: &str) -> Option<&Value> {
+        if let Some(data) = self.previous_data.get(reference) {
+            return Some(data);
+        }
+        let (key, path) = reference.split_once('.')?;
+        json_field(self.previous_data.get(key)?, path)
+    }
+
+    /// Applies `resolve_input` to every string inside structured arguments;
+    /// a string that is just one reference to structured data becomes that
+    /// value, keeping its type.
+    pub fn resolve_args(&self, args: &Value) -> Value {
+        match args {
+            Value::String(s) => output_reference_regex()
+                .captures(s)
+                .filter(|caps| caps[0].len() == s.len())
+                .and_then(|caps| self.output_data(&caps[1]))
+                .cloned()
+                .unwrap_or_else(|| Value::String(self.resolve_input(s))),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.resolve_args(v)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), self.resolve_args(v)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Resolved freeform input (the JSON text when `args` are given) and args.
+    pub fn resolve_call(&self, input: &str, args: Option<&Value>) -> (String, Option<Value>) {
+        match args.map(|args| self.resolve_args(args)) {
+            Some(args) => (args.to_string(), Some(args)),
+            None => (self.resolve_input(input), None),
+        }
+    }
+
+    /// Makes the latest result of `tool` also addressable as `alias`.
+    pub fn alias_result(&mut self, tool: &str, alias: &str) {
+        let Some((text, success)) = self.last_results.get(tool).cloned() else {
+            return;
+        };
+        if success {
+            self.previous_outputs
+                .insert(alias.to_string(), text.clone());
+            match self.previous_data.get(tool).cloned() {
+                Some(data) => self.previous_data.insert(alias.to_string(), data),
+                None => self.previous_data.remove(alias),
+            };
+        }
+        self.last_results.insert(alias.to_string(), (text, success));
+    }
 
-                    print!("Execute {}: `{}`? (Y/n): ", name, resolved_input);
-                    stdout().flush().unwrap();
-                    let mut line = String::new();
-                    stdin().read_line(&mut line).unwrap();
-                    let line = line.trim();
-                    if line == "n" || line == "N" {
-                        println!("Skipped {}\n", name);
+    pub fn alias_step_id(&mut self, tool: &str, step_id: Option<&str>) {
+        if let Some(step_id) = step_id {
+            self.alias_result(tool, step_id);
+        }
+    }
+
+    pub fn last_result(&self, tool: &str) -> Option<(&str, bool)> {
+        self.last_results
+            .get(tool)
+            .map(|(text, success)| (text.as_str(), *success))
+    }
+
+    pub fn record_missing_tool(&mut self, name: &str) {
+        let error = format!("Tool not found: {}", name);
+        warn!("{}", error);
+        self.critical_failures += 1;
+        self.errors.push(error.clone());
+        self.steps.push(StepResult {
+            id: None,
+            tool: name.to_string(),
+            input: String::new(),
+            success: false,
+            output: None,
+            error: Some(error),
+            data: None,
+        });
+    }
+}
+
+impl<M: Model> BasicAgent<M> {
+    /// The planner's plan, or a fixed fallback when no planner is set.
+    fn generate_plan(&mut self) -> Plan {
+        if let Some(planner) = &self.planner {
+            self.context.log("planning", "Using dynamic LLM planner");
+            planner.plan_task(&mut self.context, &self.model)
+        } else {
+            self.context.log("planning", "Using static hardcoded plan");
+            Plan {
+                steps: vec![
+                    PlanStep::Info(format!("Understand goal: {}", self.model.goal())),
+                    PlanStep::tool("git_status", "Check repo state"),
+                    PlanStep::tool("reflect", "Summarize changes"),
+                    PlanStep::tool("echo", "Task complete."),
+                    PlanStep::Info("Generate output".into()),
+                ],
+            }
+        }
+    }
+
+    /// A fresh execution state in which `$output[...]` already resolves to
+    /// the results of steps completed by earlier plans for this goal.
+    pub(crate) fn initial_state(&self) -> ExecutionState {
+        ExecutionState {
+            previous_outputs: self.model.task().completed_outputs().into_iter().collect(),
+            previous_data: self.model.task().completed_data().into_iter().collect(),
+            ..ExecutionState::default()
+        }
+    }
+
+    /// Validates `plan` against the registered tools before anything runs.
+    /// Warnings are logged; if there are errors the plan is refused and they
+    /// come back as a failed result.
+    pub(crate) fn reject_invalid_plan(&mut self, plan: &Plan) -> Option<ExecutionResult> {
+        let prior_refs: Vec<String> = self
+            .model
+            .task()
+            .completed_outputs()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        let (errors, warnings): (Vec<_>, Vec<_>) = plan
+            .validate_after(&self.context, &prior_refs)
+            .into_iter()
+            .partition(|error| error.is_error());
+        for warning in &warnings {
+            self.context
+                .log("validation", &format!("⚠️ {}", warning.hint().0));
+        }
+        if errors.is_empty() {
+            return None;
+        }
+
+        warn!(
+            "❌ Refusing to execute plan with {} validation error(s)",
+            errors.len()
+        );
+        let mut state = self.initial_state();
+        for error in &errors {
+            let message = format!("Invalid plan: {:?}: {}", error, error.hint().0);
+            self.context.log("validation", &format!("❌ {}", message));
+            state.errors.push(message);
+        }
+        state.critical_failures += errors.len();
+        Some(self.finish_execution(state))
+    }
+
+    pub(crate) fn execute_steps(&mut self, steps: &[PlanStep], state: &mut ExecutionState) {
+        for (index, step) in steps.iter().enumerate() {
+            if self.stop_if_cancelled(state) {
+                self.record_cancelled_steps(state, &steps[index..]);
+                return;
+            }
+            let _span = info_span!("step", kind = step.kind()).entered();
+            match step {
+                PlanStep::ToolCall {
+                    id,
+                    name,
+                    input,
+                    args,
+                    timeout,
+                    transform,
+                    background,
+                } => {
+                    let (resolved_input, resolved_args) = state.resolve_call(input, args.as_ref());
+                    if self.context.dry_run {
+                        self.start_step(name, id.as_deref(), &resolved_input);
+                        match self.simulate_tool(name, &resolved_input) {
+                            Some(result) => {
+                                let id = id.as_deref();
+                                self.record_tool_result(state, name, id, &resolved_input, result);
+                            }
+                            None => self.record_missing_tool(state, name),
+                        }
                         continue;
                     }
+                    if !self.within_limits(state, name) {
+                        return;
+                    }
+                    if !self.approve_step(name, &resolved_input, resolved_args.as_ref()) {
+                        info!("⏭️ Skipped {}", name);
+                        self.model
+                            .task_mut()
+                            .skip_step(name, id.as_deref(), &resolved_input);
+                        continue;
+                    }
+                    self.start_step(name, id.as_deref(), &resolved_input);
 
-                    match self.context.get_tool(name) {
-                        Some(tool) => {
-                            let result = tool.execute(&resolved_input);
-
-                            self.context.log(
-                                &format!("tool: {}", name),
-                                &format!(
-                                    "[input] {}\n[output] {}",
-                                    resolved_input,
-                                    result.output.clone().unwrap_or_default()
-                                ),
-                            );
-
-                            if result.success {
-                                if let Some(output) = result.output.clone() {
-                                    previous_outputs.insert(name.clone(), output.clone());
-                                    combined_output.push_str(&output);
-                                    combined_output.push('\n');
-                                }
-                            } else {
-                                let error_msg =
-                                    result.error.clone().unwrap_or("Unknown error".to_string());
-                                errors.push(error_msg.clone());
-
-                                // 🎯 DYNAMIC INTELLIGENCE: Classify tool failures by criticality
-                                // Core tools (run_command) are critical, auxiliary tools (reflect) are not
-                                let is_critical = match name.as_str() {
-                                    "run_command" => true,    // Core execution tool
-                                    "reflect" => false,       // Auxiliary analysis tool
-                                    "analyze_error" => false, // Auxiliary analysis tool
-                                    _ => true, // Default to critical for unknown tools
-                                };
-
-                                if is_critical {
-                                    critical_failures += 1;
-                                }
-
-                                // Log detailed error for replanner to see
-                                self.context.log(
-                                    "execution_error",
-                                    &format!("Tool '{}' failed: {}", name, error_msg),
-                                );
-
-                                // Use AI to analyze the error and suggest fixes (only for critical failures)
-                                if is_critical {
-                                    if let Some(analyzer) = self.context.get_tool("analyze_error") {
-                                        let analysis_result = analyzer.execute(&error_msg);
-                                        if analysis_result.success {
-                                            if let Some(analysis) = analysis_result.output {
-                                                self.context.log("error_analysis", &analysis);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                    let result = if *background {
+                        self.context.start_background(
+                            name,
+                            id.as_deref().unwrap_or(name),
+                            &resolved_input,
+                            resolved_args.as_ref(),
+                        )
+                    } else {
+                        self.context.invoke_tool(
+                            name,
+                            &resolved_input,
+                            resolved_args.as_ref(),
+                            self.limit_timeout(state, name, *timeout),
+                        )
+                    };
+                    match result {
+                        Some(result) => {
+                            let id = id.as_deref();
+                            let result = result.transformed(transform);
+                            self.record_tool_result(state, name, id, &resolved_input, result);
                         }
-                        None => {
-                            critical_failures += 1;
-                            errors.push(format!("Tool not found: {}", name));
+                        None => self.record_missing_tool(state, name),
+                    }
+                }
+                PlanStep::Info(message) => self.record_info(state, message),
+                PlanStep::AskUser { question, variable } => {
+                    self.ask_user(state, question, variable)
+                }
+                PlanStep::Conditional {
+                    predicate,
+                    then_steps,
+                    else_steps,
+                } => {
+                    let branch = if self.check_predicate(state, predicate) {
+                        then_steps
+                    } else {
+                        else_steps
+                    };
+                    self.execute_steps(branch, state);
+                }
+                PlanStep::Loop {
+                    steps: body,
+                    max_iterations,
+                    until,
+                } => {
+                    let failures_before = state.critical_failures;
+                    for iteration in 1..=*max_iterations {
+                        self.execute_steps(body, state);
+                        if state.cancelled {
+                            break;
+                        }
+                        let until = until.as_ref();
+                        if self.end_loop_iteration(
+                            state,
+                            iteration,
+                            *max_iterations,
+                            until,
+                            failures_before,
+                        ) {
+                            break;
                         }
                     }
                 }
-                PlanStep::Info(message) => {
-                    combined_output.push_str(&format!("[INFO] {}\n", message));
-                    self.context.log("info", message);
+            }
+        }
+    }
+
+    fn simulate_steps(
+        &self,
+        steps: &[PlanStep],
+        warnings: &mut Vec<String>,
+        tools_used: &mut Vec<String>,
+        previews: &mut Vec<StepPreview>,
+    ) {
+        for step in steps {
+            match step {
+                PlanStep::ToolCall {
+                    name, input, args, ..
+                } => {
+                    if let Some(tool) = self.context.get_tool(name) {
+                        let spec = tool.spec();
+                        tools_used.push(format!(
+                            "[TOOL] {} - {} (hint: {})",
+                            spec.name, spec.description, spec.input_hint
+                        ));
+                        let outcome = match args {
+                            Some(args) => tool.preview_args(args),
+                            None => tool.preview(input),
+                        };
+                        let outcome =
+                            risk::annotate(outcome, risk::runs_shell(tool), input, args.as_ref());
+                        previews.push(StepPreview {
+                            tool: name.clone(),
+                            input: match args {
+                                Some(args) if input.is_empty() => args.to_string(),
+                                _ => input.clone(),
+                            },
+                            outcome,
+                        });
+                    } else {
+                        warnings.push(format!("Tool '{}' not registered", name));
+                    }
+                }
+                PlanStep::Conditional {
+                    then_steps,
+                    else_steps,
+                    ..
+                } => {
+                    self.simulate_steps(then_steps, warnings, tools_used, previews);
+                    self.simulate_steps(else_steps, warnings, tools_used, previews);
+                }
+                PlanStep::Loop { steps, .. } => {
+                    self.simulate_steps(steps, warnings, tools_used, previews)
                 }
+                PlanStep::Info(_) | PlanStep::AskUser { .. } => {}
+            }
+        }
+    }
+
+    pub(crate) fn check_predicate(
+        &mut self,
+        state: &ExecutionState,
+        predicate: &Predicate,
+    ) -> bool {
+        match predicate.evaluate(state.last_result(&predicate.output_of)) {
+            Ok(holds) => {
+                self.context
+                    .log("condition", &format!("{} → {}", predicate, holds));
+                holds
+            }
+            Err(e) => {
+                self.context
+                    .log("condition", &format!("⚠️ {} → false ({})", predicate, e));
+                false
             }
         }
+    }
 
-        self.model.set_output(combined_output.trim().to_string());
+    /// Logs a finished loop iteration and reports whether the loop should stop.
+    /// Once the exit condition holds, failures from earlier attempts are forgiven.
+    pub(crate) fn end_loop_iteration(
+        &mut self,
+        state: &mut ExecutionState,
+        iteration: usize,
+        max_iterations: usize,
+        until: Option<&Predicate>,
+        failures_before: usize,
+    ) -> bool {
+        let Some(until) = until else {
+            self.context.log(
+                "loop",
+                &format!("Iteration {}/{} complete", iteration, max_iterations),
+            );
+            return false;
+        };
 
-        // 🎯 DYNAMIC INTELLIGENCE: Success based on critical tool performance
-        // If core tools succeeded, the plan succeeded even if auxiliary tools failed
-        let success = critical_failures == 0;
+        let done = self.check_predicate(state, until);
+        self.context.log(
+            "loop",
+            &format!(
+                "Iteration {}/{}: exit condition {}",
+                iteration,
+                max_iterations,
+                if done { "met" } else { "not met" }
+            ),
+        );
 
-        ExecutionResult {
-            success,
-            output: Some(self.model.output.clone().unwrap_or_default()),
-            errors,
+        if done {
+            state.critical_failures = failures_before;
+        } else if iteration == max_iterations {
+            self.context.log(
+                "loop",
+                "⚠️ Max iterations reached without meeting exit condition",
+            );
         }
+        done
     }
 
-    fn evaluate(&self, result: &ExecutionResult) -> Feedback {
-        Feedback {
-            score: if result.success { 90 } else { 30 },
-            notes: "Dynamic tool execution complete.".into(),
+    /// Dry-run stand-in for executing `name`; `None` if no such tool is registered.
+    pub(crate) fn simulate_tool(&mut self, name: &str, resolved_input: &str) -> Option<ToolResult> {
+        let result = match self.context.get_tool(name) {
+            Some(tool) => tool.simulate(resolved_input),
+            None => {
+                self.context.get_async_tool(name)?;
+                ToolResult::success(&format!(
+                    "[dry-run] {} would run with input: {}",
+                    name, resolved_input
+                ))
+            }
+        };
+        self.context.log("dry_run", &format!("Simulated {}", name));
+        Some(result)
+    }
+
+    /// Records a cancelled run once; returns true if remaining steps should be skipped.
+    pub(crate) fn stop_if_cancelled(&mut self, state: &mut ExecutionState) -> bool {
+        if state.cancelled {
+            return true;
+        }
+        if !self.context.cancel.is_cancelled() {
+            return false;
         }
+        state.cancelled = true;
+        state.critical_failures += 1;
+        let reason = self
+            .context
+            .usage
+            .budget_exceeded()
+            .unwrap_or_else(|| "Run cancelled".into());
+        warn!("🛑 {}; skipping remaining steps", reason);
+        self.context.log(
+            "execution_error",
+            &format!("🛑 {}; skipping remaining steps", reason),
+        );
+        state.errors.push(reason);
+        true
     }
 
-    fn replan(&mut self, reflection: &str) -> Option<Plan> {
-        if let Some(replanner) = &self.replanner {
-            self.context
-                .log("replanner", "Using reflection-based replanning");
-            let plan =
-                replanner.generate_followup_plan(&mut self.context, &self.model.goal, reflection);
-            if !plan.steps.is_empty() {
-                Some(plan)
-            } else {
-                None
-            }
+    /// Records the tool calls among `steps` that a cancelled run never
+    /// started as skipped, so the partial report lists them.
+    pub(crate) fn record_cancelled_steps<'a>(
+        &mut self,
+        state: &mut ExecutionState,
+        steps: impl IntoIterator<Item = &'a PlanStep>,
+    ) {
+        for step in steps {
+            let PlanStep::ToolCall {
+                id, name, input, ..
+            } = step
+            else {
+                continue;
+            };
+            self.model.task_mut().skip_step(name, id.as_deref(), input);
+            state.steps.push(StepResult {
+                id: id.clone(),
+                tool: name.clone(),
+                input: input.clone(),
+                success: false,
+                output: None,
+                error: Some("Skipped: run cancelled".into()),
+                data: None,
+            });
+        }
+    }
+
+    /// Counts a tool call against the task's `RunLimits`. Once a limit is
+    /// hit, records the overrun as an error and stops the remaining steps.
+    pub(crate) fn within_limits(&mut self, state: &mut ExecutionState, name: &str) -> bool {
+        if state.cancelled {
+            return false;
+        }
+        let limits = &self.model.task().limits;
+        let started = *state.started.get_or_insert_with(Instant::now);
+        let shell = self
+            .context
+            .get_tool(name)
+            .is_some_and(|tool| tool.spec().tags.iter().any(|tag| tag == "shell"));
+
+        let exceeded = if let Some(max) = limits.max_steps
+            && state.tool_calls >= max
+        {
+            Some(format!("{} tool calls", max))
+        } else if let Some(max) = limits.max_shell_commands
+            && shell
+            && state.shell_commands >= max
+        {
+            Some(format!("{} shell commands", max))
+        } else if let Some(max) = limits.max_wall_time
+            && started.elapsed() >= max
+        {
+            Some(format!("{}s of wall time", max.as_secs_f64()))
         } else {
             None
+        };
+
+        let Some(limit) = exceeded else {
+            state.tool_calls += 1;
+            state.shell_commands += shell as usize;
+            return true;
+        };
+        let reason = format!("Budget exceeded: the run is limited to {}", limit);
+        warn!("🛑 {}; skipping remaining steps", reason);
+        self.context.log(
+            "execution_error",
+            &format!("🛑 {}; skipping {} and remaining steps", reason, name),
+        );
+        state.errors.push(reason.clone());
+        state.critical_failures += 1;
+        state.cancelled = true;
+        state.budget_exceeded = Some(reason);
+        false
+    }
+
+    /// `timeout` (or the tool's default) capped at the wall time left.
+    pub(crate) fn limit_timeout(
+        &self,
+        state: &ExecutionState,
+        name: &str,
+        timeout: Option<Duration>,
+    ) -> Option<Duration> {
+        let Some(max) = self.model.task().limits.max_wall_time else {
+            return timeout;
+        };
+        let elapsed = state
+            .started
+            .map_or(Duration::ZERO, |started| started.elapsed());
+        let left = max.saturating_sub(elapsed);
+        let timeout = timeout.or_else(|| self.context.tool_timeout(name));
+        Some(timeout.map_or(left, |timeout| timeout.min(left)))
+    }
+
+    /// Asks the approval policy, except that high-risk calls (see `risk`)
+    /// always need explicit elevated approval. Prompts go to the context's
+    /// `UserInterface` first, then to the policy's terminal prompt.
+    pub(crate) fn approve_step(
+        &self,
+        name: &str,
+        resolved_input: &str,
+        resolved_args: Option<&Value>,
+    ) -> bool {
+        let policy = self.context.approval;
+        let tool = self.context.get_tool(name);
+        let risk = risk::assess_call(tool, name, resolved_input, resolved_args);
+        if risk.level == RiskLevel::High {
+            warn!("⚠️ High-risk step {}: {}", name, risk.reasons.join("; "));
+            if policy == ApprovalPolicy::DenyAll {
+                return false;
+            }
+            let action = format!("Execute {}: `{}`", name, risk.input);
+            return self
+                .context
+                .user
+                .approve(&action, &risk.reasons)
+                .unwrap_or_else(|| policy.approve_elevated(&action, &risk.reasons));
+        }
+        let decided = match (tool, policy) {
+            (Some(tool), _) => policy.decide_without_prompt(&tool.spec()),
+            (None, ApprovalPolicy::AutoApprove) => Some(true),
+            (None, ApprovalPolicy::DenyAll) => Some(false),
+            (None, _) => None,
+        };
+        let action = format!("Execute {}: `{}`", name, resolved_input);
+        decided
+            .or_else(|| self.context.user.approve(&action, &[]))
+            .unwrap_or_else(|| policy.approve(&action))
+    }
+
+    pub(crate) fn start_step(&mut self, name: &str, id: Option<&str>, resolved_input: &str) {
+        self.model.task_mut().begin_step(name, id, resolved_input);
+        self.context.emit(AgentEvent::StepStarted {
+            id: id.map(str::to_string),
+            tool: name.to_string(),
+            input: resolved_input.to_string(),
+        });
+    }
+
+    pub(crate) fn record_missing_tool(&mut self, state: &mut ExecutionState, name: &str) {
+        state.record_missing_tool(name);
+        self.emit_step_finished(state);
+    }
+
+    /// Records the latest step in the task model and tool history, then
+    /// emits `StepFinished` (and `ToolFailed` on failure).
+    fn emit_step_finished(&mut self, state: &ExecutionState) {
+        let Some(step) = state.steps.last() else {
+            return;
+        };
+        let duration = self
+            .model
+            .task_mut()
+            .finish_step(step)
+            .duration()
+            .unwrap_or_default();
+        self.context.record_tool_call(ToolCallRecord {
+            step_id: step.id.clone(),
+            tool: step.tool.clone(),
+            input: step.input.clone(),
+            output: step
+                .output
+                .clone()
+                .or_else(|| step.error.clone())
+                .unwrap_or_default(),
+            duration,
+            success: step.success,
+        });
+        self.context
+            .emit(AgentEvent::StepFinished { step: step.clone() });
+        if !step.success {
+            self.context.emit(AgentEvent::ToolFailed {
+                id: step.id.clone(),
+                tool: step.tool.clone(),
+                error: step.error.clone().unwrap_or_default(),
+            });
+        }
+    }
+
+    /// Asks the user and stores the answer for `$input[variable]`. Dry runs
+    /// use a placeholder instead; no answer is a critical failure.
+    pub(crate) fn ask_user(&mut self, state: &mut ExecutionState, question: &str, variable: &str) {
+        let question = state.resolve_input(question);
+        let answer = match self.context.dry_run {
+            true => Some(format!("<{}>", variable)),
+            false => self.context.user.ask(&question, variable),
+        };
+        match answer {
+            Some(answer) => {
+                self.context
+                    .log("user_input", &format!("❓ {} → {}", question, answer));
+                state.inputs.insert(variable.to_string(), answer);
+            }
+            None => {
+                let error = format!("No answer to '{}' for $input[{}]", question, variable);
+                warn!("{}", error);
+                state.errors.push(error);
+                state.critical_failures += 1;
+            }
+        }
+    }
+
+    pub(crate) fn record_info(&mut self, state: &mut ExecutionState, message: &str) {
+        state
+            .combined_output
+            .push_str(&format!("[INFO] {}\n", message));
+        self.context.log("info", message);
+    }
+
+    pub(crate) fn record_tool_result(
+        &mut self,
+        state: &mut ExecutionState,
+        name: &str,
+        step_id: Option<&str>,
+        resolved_input: &str,
+        result: ToolResult,
+    ) {
+        if result.success {
+            debug!("✅ {} succeeded", name);
+        } else {
+            warn!(
+                "{} failed: {}",
+                name,
+                result.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+
+        let text = result.output.clone().or(result.error.clone());
+        state
+            .last_results
+            .insert(name.to_string(), (text.unwrap_or_default(), result.success));
+        state.steps.push(StepResult {
+            id: step_id.map(str::to_string),
+            tool: name.to_string(),
+            input: resolved_input.to_string(),
+            success: result.success,
+            output: result.output.clone(),
+            error: result.error.clone(),
+            data: result.data.clone(),
+        });
+
+        self.emit_step_finished(state);
+        if let Some(data) = &result.data {
+            self.context.log_in(
+                TOOL_DATA_NAMESPACE,
+                step_id.unwrap_or(name),
+                &data.to_string(),
+            );
+        }
+
+        if result.success {
+            if let Some(output) = result.output {
+                state.combined_output.push_str(&output);
+                state.combined_output.push('\n');
+                state.previous_outputs.insert(name.to_string(), output);
+            }
+            match result.data {
+                Some(data) => state.previous_data.insert(name.to_string(), data),
+                None => state.previous_data.remove(name),
+            };
+            state.alias_step_id(name, step_id);
+            return;
+        }
+        state.alias_step_id(name, step_id);
+
+        let error_msg = result.error.unwrap_or("Unknown error".to_string());
+        state.errors.push(error_msg.clone());
+
+        // 🎯 DYNAMIC INTELLIGENCE: Classify tool failures by criticality
+        // Core tools (run_command) are critical, auxiliary tools (reflect) are not
+        let is_critical = match name {
+            "run_command" => true,    // Core execution tool
+            "reflect" => false,       // Auxiliary analysis tool
+            "analyze_error" => false, // Auxiliary analysis tool
+            _ => true,                // Default to critical for unknown tools
+        };
+
+        if is_critical {
+            state.critical_failures += 1;
+        }
+
+        // Log detailed error for replanner to see
+        self.context.log(
+            "execution_error",
+            &format!("Tool '{}' failed: {}", name, error_msg),
+        );
+
+        // Use AI to analyze the error and suggest fixes (only for critical
+        // failures, and not once the run was cancelled)
+        if is_critical
+            && !self.context.cancel.is_cancelled()
+            && let Some(analyzer) = self.context.get_tool("analyze_error")
+        {
+            let analysis_result = analyzer.execute(&error_msg);
+            if analysis_result.success
+                && let Some(analysis) = analysis_result.output
+            {
+                self.context.log("error_analysis", &analysis);
+            }
+        }
+    }
+
+    pub(crate) fn finish_execution(&mut self, state: ExecutionState) -> ExecutionResult {
+        // 🎯 DYNAMIC INTELLIGENCE: Success based on critical tool performance
+        // If core tools succeeded, the plan succeeded even if auxiliary tools failed
+        let success = state.critical_failures == 0;
+        self.model
+            .task_mut()
+            .finish(state.combined_output.trim().to_string(), success);
+
+        let mut result = ExecutionResult {
+            success,
+            output: Some(self.model.task().output.clone().unwrap_or_default()),
+            errors: state.errors,
+            steps: state.steps,
+            verdict: None,
+            budget_exceeded: state.budget_exceeded,
+        };
+        self.verify_goal(&mut result);
+        self.context.emit(AgentEvent::RunCompleted {
+            goal: self.model.goal().to_string(),
+            success: result.success,
+            goal_achieved: result.goal_achieved(),
+            errors: result.errors.clone(),
+        });
+        result
+    }
+
+    /// Runs the configured verifier (skipped in dry-run, where nothing really ran).
+    fn verify_goal(&mut self, result: &mut ExecutionResult) {
+        let Some(verifier) = &self.verifier else {
+            return;
+        };
+        if self.context.dry_run || self.context.cancel.is_cancelled() {
+            return;
+        }
+
+        let span = info_span!("verify", achieved = Empty, confidence = Empty).entered();
+        match verifier.verify(self.model.goal(), result) {
+            Ok(verdict) => {
+                span.record("achieved", verdict.achieved);
+                span.record("confidence", verdict.confidence as f64);
+                let icon = if verdict.achieved { "✅" } else { "❌" };
+                info!(
+                    "🔎 Verifier: {} goal {} ({:.0}% confident)",
+                    icon,
+                    if verdict.achieved {
+                        "achieved"
+                    } else {
+                        "not achieved"
+                    },
+                    verdict.confidence * 100.0
+                );
+                let summary = serde_json::to_string(&verdict).unwrap_or_default();
+                self.context.log("verification", &summary);
+                result.verdict = Some(verdict);
+            }
+            Err(e) => {
+                warn!("Verifier failed: {}", e);
+                self.context.log("verification_error", &e);
+            }
         }
     }
 }
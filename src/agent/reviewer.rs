@@ -0,0 +1,141 @@
+// src/agent/reviewer.rs
+
+use crate::context::Context;
+use crate::events::AgentEvent;
+use crate::protocol::critic::{Critic, Critique};
+use crate::protocol::replanner::Replanner;
+use crate::protocol::{Plan, PlanStep};
+
+/// Adversarial review of proposed plans: every critic reviews the plan, and
+/// rejected plans are revised by the replanner and reviewed again, for up to
+/// `rounds` rounds. A plan still rejected after the last round is replaced
+/// by an info-only plan, so nothing it proposed runs.
+pub struct ReviewerAgent {
+    critics: Vec<Box<dyn Critic>>,
+    rounds: usize,
+}
+
+/// What a `ReviewerAgent` decided about a plan.
+#[derive(Clone, Debug)]
+pub struct Review {
+    /// The approved (possibly revised) plan, or the rejection notice.
+    pub plan: Plan,
+    pub approved: bool,
+    /// Critiques from every round, in order.
+    pub critiques: Vec<(String, Critique)>,
+}
+
+impl ReviewerAgent {
+    pub fn new(critic: Box<dyn Critic>) -> Self {
+        Self {
+            critics: vec![critic],
+            rounds: 2,
+        }
+    }
+
+    /// Adds another reviewer; a plan needs every critic's approval.
+    pub fn with_critic(mut self, critic: Box<dyn Critic>) -> Self {
+        self.critics.push(critic);
+        self
+    }
+
+    /// Maximum review rounds (default 2); at least one always runs.
+    pub fn with_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds.max(1);
+        self
+    }
+
+    /// Reviews `plan`, asking `replanner` (if any) to address rejections.
+    pub fn review(
+        &self,
+        context: &mut Context,
+        goal: &str,
+        plan: Plan,
+        replanner: Option<&dyn Replanner>,
+    ) -> Review {
+        let mut plan = plan;
+        let mut critiques = Vec::new();
+
+        let mut round = 0;
+        loop {
+            round += 1;
+            let mut concerns = Vec::new();
+            for critic in &self.critics {
+                let critique = match critic.critique(context, goal, &plan) {
+                    Ok(critique) => critique,
+                    Err(e) => {
+                        // An unavailable reviewer must not block every run.
+                        context.log("review", &format!("⚠️ {} failed: {}", critic.role(), e));
+                        continue;
+                    }
+                };
+                let verdict = if critique.approved {
+                    "✅ approved"
+                } else {
+                    "❌ rejected"
+                };
+                context.log(
+                    "review",
+                    &format!(
+                        "Round {}: {} {} the plan. {}",
+                        round,
+                        critic.role(),
+                        verdict,
+                        critique.reasoning
+                    ),
+                );
+                context.emit(AgentEvent::PlanReviewed {
+                    goal: goal.to_string(),
+                    round,
+                    critic: critic.role().to_string(),
+                    approved: critique.approved,
+                    concerns: critique.concerns.clone(),
+                });
+                if !critique.approved {
+                    concerns.extend(
+                        critique
+                            .concerns
+                            .iter()
+                            .map(|concern| format!("[{}] {}", critic.role(), concern)),
+                    );
+                    if critique.concerns.is_empty() {
+                        concerns.push(format!("[{}] {}", critic.role(), critique.reasoning));
+                    }
+                }
+                critiques.push((critic.role().to_string(), critique));
+            }
+
+            if concerns.is_empty() {
+                return Review {
+                    plan,
+                    approved: true,
+                    critiques,
+                };
+            }
+            let Some(replanner) = replanner.filter(|_| round < self.rounds) else {
+                return Review {
+                    plan: rejected_plan(&concerns),
+                    approved: false,
+                    critiques,
+                };
+            };
+
+            let plan_json = serde_json::to_string_pretty(&plan.to_json()).unwrap_or_default();
+            let reflection = format!(
+                "Reviewers rejected this proposed plan:\n{}\n\nConcerns:\n- {}\n\nPropose a corrected plan that addresses every concern.",
+                plan_json,
+                concerns.join("\n- ")
+            );
+            plan = replanner.generate_followup_plan(context, goal, &reflection);
+        }
+    }
+}
+
+fn rejected_plan(concerns: &[String]) -> Plan {
+    Plan {
+        steps: vec![PlanStep::Info(format!(
+            "Plan rejected by review: {}",
+            concerns.join("; ")
+        ))],
+    }
+}
@@ -0,0 +1,187 @@
+// src/agent/run_loop.rs
+
+use crate::agent::{Agent, BasicAgent};
+use crate::config::RuntimeConfig;
+use crate::model::{Model, StepStatus};
+use crate::policy::replan::{Escalation, ReplanDecision, ReplanGuard, ReplanPolicy, ReplanStep};
+use crate::protocol::ExecutionResult;
+use crate::protocol::planner::LLMPlanner;
+use crate::protocol::replanner::LLMReplanner;
+use crate::report::RunReport;
+use std::path::Path;
+
+/// Runs `goal` the way `agentic run` does, for embedding the runtime: the
+/// configured model, tools and agent (see `RuntimeConfig::build_agent`) plan,
+/// execute and replan under `[replan]` until the goal is achieved or the
+/// policy gives up.
+///
+/// The run's memory is written to `[memory] export` if set. A config that
+/// cannot be applied (e.g. an unknown provider) yields a report with no
+/// iterations and the error in `aborted`.
+pub fn run_goal(goal: &str, config: RuntimeConfig) -> RunReport {
+    match prepare_goal(goal, &config) {
+        Ok((mut agent, policy)) => {
+            let report = agent.run(&policy);
+            if let Some(path) = &config.memory.export
+                && let Err(e) = agent.context.memory().export_jsonl(Path::new(path))
+            {
+                tracing::warn!("Failed to export memory: {}", e);
+            }
+            report
+        }
+        Err(e) => {
+            let mut report = RunReport::new(goal);
+            report.aborted = Some(format!("Invalid runtime config: {}", e));
+            report
+        }
+    }
+}
+
+/// The agent and replan policy `run_goal` would use, for callers that hook
+/// into the context (events, user interface) before running it.
+pub fn prepare_goal(
+    goal: &str,
+    config: &RuntimeConfig,
+) -> Result<(BasicAgent, ReplanPolicy), String> {
+    let llm = config.llm()?;
+    let context = config.build_context(&llm)?;
+    let llm = llm
+        .with_usage(context.usage.clone())
+        .with_secrets(context.secrets.clone());
+    let policy = config.replan.policy(&llm)?;
+    let planner = LLMPlanner::new(llm.clone());
+    let agent = config.build_agent(goal, context, &llm, planner);
+    Ok((agent, policy))
+}
+
+impl<M: Model> BasicAgent<M> {
+    /// Plans, executes and evaluates the goal, then replans until it is
+    /// achieved or `policy` gives up (see `RunReport::aborted`). Cancelled
+    /// (e.g. by Ctrl-C, see `cancel_on_interrupt`), it stops after the step
+    /// in flight and returns the iterations so far. Either way, background
+    /// processes are stopped and memory flushed before it returns.
+    pub fn run(&mut self, policy: &ReplanPolicy) -> RunReport {
+        let mut report = RunReport::new(self.model.goal());
+        let plan = self.plan();
+        let simulation = self.simulate(&plan);
+        let mut execution = self.execute(&plan);
+        let feedback = self.evaluate(&execution);
+        report.add_iteration("Primary", &plan, &simulation, &execution, Some(&feedback));
+
+        let mut guard = policy.guard();
+        while !execution.goal_achieved() && !self.context.cancel.is_cancelled() {
+            let mut reflection = match &execution.verdict {
+                Some(verdict) => verdict.followup_prompt(),
+                None => format!("Errors:\n{}", execution.errors.join("\n")),
+            };
+            match self.check_replan(&mut guard, &execution) {
+                ReplanDecision::Replan { guidance } => {
+                    if let Some(guidance) = guidance {
+                        reflection.push_str(&format!("\n\nUser guidance: {}", guidance));
+                    }
+                }
+                ReplanDecision::Abort(summary) => {
+                    report.aborted = Some(summary);
+                    break;
+                }
+            }
+            let Some(followup) = self.replan(&reflection) else {
+                break;
+            };
+            let simulation = self.simulate(&followup);
+            execution = self.execute(&followup);
+            report.add_iteration(
+                &format!("Follow-up {}", guard.attempts()),
+                &followup,
+                &simulation,
+                &execution,
+                None,
+            );
+        }
+
+        if self.context.cancel.is_cancelled() {
+            report.mark_cancelled();
+        }
+        self.context.wind_down();
+        report
+            .with_memory(self.context.memory().read_all())
+            .with_tool_calls(self.context.tool_history.all())
+            .with_usage(self.context.usage.summary())
+    }
+
+    /// Counts a replan against `guard` and applies any escalation it calls
+    /// for: a bigger model takes over replanning, or the user is asked for
+    /// guidance. `last` is the execution that prompted the replan.
+    pub fn check_replan(
+        &mut self,
+        guard: &mut ReplanGuard,
+        last: &ExecutionResult,
+    ) -> ReplanDecision {
+        let escalation = match guard.advance() {
+            ReplanStep::Replan => return ReplanDecision::Replan { guidance: None },
+            ReplanStep::Escalate(escalation) => escalation,
+        };
+        match escalation {
+            Escalation::SwitchModel(llm) => {
+                self.context.log(
+                    "replan",
+                    &format!("⬆️ Escalating replanning to {}", llm.model),
+                );
+                self.replanner = Some(Box::new(LLMReplanner::new(*llm)));
+                ReplanDecision::Replan { guidance: None }
+            }
+            Escalation::AskUser => {
+                let question = format!(
+                    "{} follow-up plan(s) have not achieved \"{}\". How should the agent proceed? (empty to stop)",
+                    guard.attempts() - 1,
+                    self.model.goal()
+                );
+                match self.context.user.ask(&question, "guidance") {
+                    Some(answer) if !answer.trim().is_empty() => {
+                        self.context
+                            .log("replan", &format!("🙋 User guidance: {}", answer.trim()));
+                        ReplanDecision::Replan {
+                            guidance: Some(answer.trim().to_string()),
+                        }
+                    }
+                    _ => self.abort_replanning(guard.attempts() - 1, last),
+                }
+            }
+            Escalation::Abort => self.abort_replanning(guard.attempts(), last),
+        }
+    }
+
+    fn abort_replanning(&self, attempts: usize, last: &ExecutionResult) -> ReplanDecision {
+        let count = |status| {
+            self.model
+                .task()
+                .steps
+                .iter()
+                .filter(|step| step.status == status)
+                .count()
+        };
+        let mut summary = format!(
+            "🛑 Stopped replanning \"{}\" after {} follow-up plan(s): {} step(s) succeeded, {} failed.",
+            self.model.goal(),
+            attempts,
+            count(StepStatus::Succeeded),
+            count(StepStatus::Failed)
+        );
+        if let Some(verdict) = &last.verdict
+            && !verdict.missing.is_empty()
+        {
+            summary.push_str("\nStill missing:");
+            for item in &verdict.missing {
+                summary.push_str(&format!("\n- {}", item));
+            }
+        }
+        if !last.errors.is_empty() {
+            summary.push_str("\nLast errors:");
+            for error in last.errors.iter().rev().take(3).rev() {
+                summary.push_str(&format!("\n- {}", error));
+            }
+        }
+        self.context.log("replan", &summary);
+        ReplanDecision::Abort(summary)
+    }
+}
@@ -0,0 +1,193 @@
+// src/agent/runner.rs
+
+use crate::agent::{BasicAgent, ExecutionState};
+use crate::model::{Model, TaskModel};
+use crate::protocol::{ExecutionResult, Plan};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where `AgentRunner` keeps run state unless told otherwise.
+pub const DEFAULT_STATE_DIR: &str = ".agentic/state";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// On-disk snapshot of a run, rewritten after every top-level plan step.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunState {
+    pub run_id: String,
+    pub goal: String,
+    /// The plan in the planner's JSON step format (see `Plan::to_json`).
+    pub plan: Value,
+    /// Index of the first top-level step that has not succeeded; `resume` starts here.
+    pub next_step: usize,
+    pub status: RunStatus,
+    /// Error of the step that stopped progress, if any.
+    pub last_error: Option<String>,
+    /// Outputs and step results as of `next_step`, so `$output[...]`
+    /// references and predicates keep working after a resume.
+    checkpoint: ExecutionState,
+    /// The agent's task state as of the latest step.
+    #[serde(default)]
+    pub task: Option<TaskModel>,
+}
+
+impl RunState {
+    pub fn path(dir: &Path, run_id: &str) -> PathBuf {
+        dir.join(format!("{}.json", run_id))
+    }
+
+    pub fn load(dir: &Path, run_id: &str) -> Result<Self, String> {
+        let path = Self::path(dir, run_id);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("No run state for '{}' ({}): {}", run_id, path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        // Write then rename, so a crash mid-write never leaves a truncated state file.
+        let path = Self::path(dir, &self.run_id);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, text)
+            .and_then(|_| fs::rename(&tmp, &path))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Executes plans one top-level step at a time, persisting a `RunState`
+/// after each step so a crashed or cancelled run can be continued with
+/// `resume` from the last successful step.
+pub struct AgentRunner<'a, M: Model = TaskModel> {
+    pub agent: &'a mut BasicAgent<M>,
+    pub state_dir: PathBuf,
+}
+
+impl<'a, M: Model> AgentRunner<'a, M> {
+    pub fn new(agent: &'a mut BasicAgent<M>) -> Self {
+        Self {
+            agent,
+            state_dir: PathBuf::from(DEFAULT_STATE_DIR),
+        }
+    }
+
+    pub fn with_state_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.state_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Executes `plan` as a new run and returns its id with the result.
+    /// A plan that fails validation is refused without saving a run.
+    pub fn execute(&mut self, plan: &Plan) -> (String, ExecutionResult) {
+        let run_id = new_run_id();
+        if let Some(rejected) = self.agent.reject_invalid_plan(plan) {
+            return (run_id, rejected);
+        }
+        let run = RunState {
+            run_id: run_id.clone(),
+            goal: self.agent.model.goal().to_string(),
+            plan: plan.to_json(),
+            next_step: 0,
+            status: RunStatus::Running,
+            last_error: None,
+            checkpoint: self.agent.initial_state(),
+            task: None,
+        };
+        (run_id, self.execute_from(run, plan))
+    }
+
+    /// Continues an unfinished run from its first unsuccessful step, with the
+    /// outputs of the steps before it restored.
+    pub fn resume(&mut self, run_id: &str) -> Result<ExecutionResult, String> {
+        let run = RunState::load(&self.state_dir, run_id)?;
+        if run.status == RunStatus::Completed {
+            return Err(format!("Run {} already completed", run_id));
+        }
+        let plan = Plan::from_json(&run.plan)?;
+
+        match &run.task {
+            Some(task) => *self.agent.model.task_mut() = task.clone(),
+            None => self.agent.model.task_mut().goal = run.goal.clone(),
+        }
+        self.agent.context.log(
+            "resume",
+            &format!(
+                "▶️ Resuming {} at step {}/{}",
+                run_id,
+                run.next_step + 1,
+                plan.steps.len()
+            ),
+        );
+        Ok(self.execute_from(run, &plan))
+    }
+
+    fn execute_from(&mut self, mut run: RunState, plan: &Plan) -> ExecutionResult {
+        let _span =
+            tracing::info_span!("run", run_id = %run.run_id, from_step = run.next_step).entered();
+        let mut state = run.checkpoint.clone();
+        run.status = RunStatus::Running;
+        run.last_error = None;
+        let mut progressing = true;
+
+        for index in run.next_step..plan.steps.len() {
+            let failures_before = state.critical_failures;
+            self.agent
+                .execute_steps(std::slice::from_ref(&plan.steps[index]), &mut state);
+
+            if progressing && state.critical_failures == failures_before {
+                run.next_step = index + 1;
+                run.checkpoint = state.clone();
+            } else if progressing {
+                progressing = false;
+                run.last_error = state.errors.last().cloned();
+            }
+            run.task = Some(self.agent.model.task().clone());
+            self.save(&run);
+            if state.cancelled {
+                let skipped = &plan.steps[index + 1..];
+                self.agent.record_cancelled_steps(&mut state, skipped);
+                break;
+            }
+        }
+
+        run.status = if state.cancelled {
+            RunStatus::Cancelled
+        } else if progressing {
+            RunStatus::Completed
+        } else {
+            RunStatus::Failed
+        };
+        self.agent.context.wind_down();
+        let result = self.agent.finish_execution(state);
+        run.task = Some(self.agent.model.task().clone());
+        self.save(&run);
+        result
+    }
+
+    /// Persistence problems are logged rather than failing the run.
+    fn save(&mut self, run: &RunState) {
+        if let Err(e) = run.save(&self.state_dir) {
+            self.agent
+                .context
+                .log("run_state", &format!("⚠️ Failed to save run state: {}", e));
+        }
+    }
+}
+
+fn new_run_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    format!("run-{}", millis)
+}
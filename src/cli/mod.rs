@@ -0,0 +1,228 @@
+// src/cli/mod.rs
+
+//! Argument parsing for the `agentic` binary.
+//!
+//! The parser is hand-written instead of using clap: this build has no crate
+//! registry access, so clap cannot be added as a dependency. `Cli::parse`
+//! follows clap's conventions (subcommands, options anywhere, `--help`,
+//! `--version`), so switching to clap later only touches this module.
+
+/// Help text for the `agentic` binary.
+pub const USAGE: &str = "\
+agentic — plan, execute and reflect on goals with tool-using agents
+
+USAGE:
+    agentic [OPTIONS] <COMMAND>
+
+COMMANDS:
+    run <goal>          Plan, execute, verify and (if needed) replan a goal
+    tui <goal>          Run a goal in a full-screen dashboard (plan checklist, output, memory)
+    plan <goal>         Generate and simulate a plan without executing it
+    exec <plan> [goal]  Execute a saved (JSON or YAML) plan file, skipping the planner
+    batch <file>        Run the goals in a file (one per line) through the job queue
+    tools list          List the tools enabled by the config
+    resume <run-id>     Continue an interrupted run from its last successful step
+    replay <run-id>     Print an archived run report (`latest` for the newest)
+    runs list           List archived runs
+    init                Write a default agent.toml
+    mcp serve           Export the enabled tools over MCP on stdio
+    schedule            Run the [schedule] jobs of the config as they come due
+    schedule list       List scheduled jobs with their next and last runs
+    schedule run <job>  Run a scheduled job now
+    serve [addr]        Run submitted goals over HTTP [default: 127.0.0.1:8080]
+    help                Show this message
+
+OPTIONS:
+    -c, --config <path> Config file [default: agent.toml]
+    -o, --output <path> Save the generated plan to a file (`plan`)
+        --llm <spec>    Override the model, e.g. openai:gpt-4o-mini
+        --dry-run       Simulate tool calls instead of running them
+        --edit-plan     Review and edit the generated plan before it runs
+        --decompose     Split the goal into subtasks and plan each one separately (`run`)
+        --record <path> Save every LLM exchange and tool call to a cassette file
+        --playback <path>
+                        Answer LLM requests and tool calls from a recorded cassette
+    -h, --help          Show this message
+    -V, --version       Show the version
+";
+
+/// Where `agentic serve` listens without an address: loopback only, as the
+/// server has no authentication.
+pub const DEFAULT_SERVE_ADDR: &str = "127.0.0.1:8080";
+
+/// A parsed `agentic` invocation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cli {
+    pub config_path: String,
+    pub llm: Option<String>,
+    pub dry_run: bool,
+    pub edit_plan: bool,
+    pub decompose: bool,
+    pub output: Option<String>,
+    /// Cassette to record the run into.
+    pub record: Option<String>,
+    /// Cassette to replay the run from instead of calling the LLM and tools.
+    pub playback: Option<String>,
+    pub command: Command,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Run {
+        goal: String,
+    },
+    /// `Run` in a `tui::Dashboard` instead of printing as it goes.
+    Tui {
+        goal: String,
+    },
+    Plan {
+        goal: String,
+    },
+    /// Without a goal, the verifier and replanner only know the plan's path.
+    Exec {
+        path: String,
+        goal: Option<String>,
+    },
+    /// Run every goal in a file through a `JobQueue`.
+    Batch {
+        path: String,
+    },
+    ToolsList,
+    Resume {
+        run_id: String,
+    },
+    Replay {
+        run_id: String,
+    },
+    RunsList,
+    Init,
+    McpServe,
+    /// Run `[schedule]` jobs until interrupted.
+    Schedule,
+    ScheduleList,
+    ScheduleRun {
+        job: String,
+    },
+    /// Accept goals over HTTP on `addr` (see `server::Server`).
+    Serve {
+        addr: String,
+    },
+    Help,
+    Version,
+}
+
+impl Cli {
+    /// Parses arguments (without the program name). Options may appear
+    /// anywhere; the remaining words of `run`/`plan` form the goal.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut config_path = "agent.toml".to_string();
+        let mut llm = None;
+        let mut dry_run = false;
+        let mut edit_plan = false;
+        let mut decompose = false;
+        let mut output = None;
+        let mut record = None;
+        let mut playback = None;
+        let mut words = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" | "--config" => {
+                    config_path = args.next().ok_or("--config needs a path")?;
+                }
+                "--llm" => llm = Some(args.next().ok_or("--llm needs a provider:model spec")?),
+                "--dry-run" => dry_run = true,
+                "--edit-plan" => edit_plan = true,
+                "--decompose" => decompose = true,
+                "-o" | "--output" => output = Some(args.next().ok_or("--output needs a path")?),
+                "--record" => record = Some(args.next().ok_or("--record needs a path")?),
+                "--playback" => playback = Some(args.next().ok_or("--playback needs a path")?),
+                "-h" | "--help" => words.insert(0, "help".to_string()),
+                "-V" | "--version" => words.insert(0, "version".to_string()),
+                flag if flag.starts_with("--") => {
+                    return Err(format!("Unknown option '{}'", flag));
+                }
+                _ => words.push(arg),
+            }
+        }
+
+        if record.is_some() && playback.is_some() {
+            return Err("--record and --playback cannot be combined".into());
+        }
+
+        let command = match words.split_first() {
+            None => Command::Help,
+            Some((name, rest)) => match (name.as_str(), rest) {
+                ("help", _) => Command::Help,
+                ("version", _) => Command::Version,
+                ("run", goal) => Command::Run {
+                    goal: goal_from(goal, "run")?,
+                },
+                ("tui", goal) => Command::Tui {
+                    goal: goal_from(goal, "tui")?,
+                },
+                ("plan", goal) => Command::Plan {
+                    goal: goal_from(goal, "plan")?,
+                },
+                ("exec", [path, goal @ ..]) => Command::Exec {
+                    path: path.clone(),
+                    goal: (!goal.is_empty()).then(|| goal.join(" ")),
+                },
+                ("exec", []) => {
+                    return Err("exec needs a plan file, e.g. agentic exec plan.yaml".into());
+                }
+                ("batch", [path]) => Command::Batch { path: path.clone() },
+                ("batch", _) => return Err("batch takes exactly one goals file".into()),
+                ("tools", [sub]) if sub == "list" => Command::ToolsList,
+                ("runs", [sub]) if sub == "list" => Command::RunsList,
+                ("resume", [run_id]) => Command::Resume {
+                    run_id: run_id.clone(),
+                },
+                ("resume", _) => return Err("resume takes exactly one run id".into()),
+                ("replay", [run_id]) => Command::Replay {
+                    run_id: run_id.clone(),
+                },
+                ("replay", _) => return Err("replay takes exactly one run id".into()),
+                ("init", []) => Command::Init,
+                ("mcp", [sub]) if sub == "serve" => Command::McpServe,
+                ("schedule", []) => Command::Schedule,
+                ("schedule", [sub]) if sub == "list" => Command::ScheduleList,
+                ("schedule", [sub, job]) if sub == "run" => {
+                    Command::ScheduleRun { job: job.clone() }
+                }
+                ("serve", []) => Command::Serve {
+                    addr: DEFAULT_SERVE_ADDR.into(),
+                },
+                ("serve", [addr]) => Command::Serve { addr: addr.clone() },
+                ("serve", _) => return Err("serve takes at most one address".into()),
+                _ => {
+                    return Err(format!("Unknown command '{}'", words.join(" ")));
+                }
+            },
+        };
+
+        Ok(Self {
+            config_path,
+            llm,
+            dry_run,
+            edit_plan,
+            decompose,
+            output,
+            record,
+            playback,
+            command,
+        })
+    }
+}
+
+fn goal_from(words: &[String], command: &str) -> Result<String, String> {
+    let goal = words.join(" ");
+    if goal.trim().is_empty() {
+        return Err(format!(
+            "{} needs a goal, e.g. agentic {} \"fix the build\"",
+            command, command
+        ));
+    }
+    Ok(goal)
+}
@@ -0,0 +1,1178 @@
+// src/config/mod.rs
+
+pub mod toml;
+
+use crate::agent::BasicAgent;
+use crate::agent::reviewer::ReviewerAgent;
+use crate::context::Context;
+use crate::llm::{Budget, LlmConfig, ModelTier, ModelTiers, RetryPolicy};
+use crate::memory::file::{DEFAULT_MEMORY_FILE, FileMemory};
+use crate::memory::limit::{DEFAULT_SPILL_DIR, EntryLimit};
+use crate::memory::scoped::new_run_id;
+use crate::memory::semantic::SemanticMemory;
+use crate::memory::vector::{Embedder, HashingEmbedder, LlmEmbedder};
+use crate::model::{RunLimits, TaskModel};
+use crate::policy::ApprovalPolicy;
+use crate::policy::replan::{Escalation, ReplanPolicy};
+use crate::probes::ProbeSet;
+use crate::protocol::critic::LLMCritic;
+use crate::protocol::decomposer::LLMDecomposer;
+use crate::protocol::evaluator::LLMJudge;
+use crate::protocol::function_planner::FunctionCallingPlanner;
+use crate::protocol::planner::{LLMPlanner, Planner};
+use crate::protocol::replanner::LLMReplanner;
+use crate::queue::QueueConfig;
+use crate::scheduler::ScheduledJob;
+use crate::scheduler::cron::CronSchedule;
+use crate::telemetry::TelemetryConfig;
+use crate::tools::{
+    CalcTool, CodeEditTool, ErrorAnalyzerTool, GoalVerifierTool, JsonQueryTool, LLMTool,
+    ProcessTool, ProjectMap, ProjectMapTool, ReflectorTool, TemplateTool, ToolSelector,
+};
+#[cfg(feature = "shell-tools")]
+use crate::tools::{
+    CargoTool, CodeSearchTool, CommitPolicy, ContainerLimits, DockerTool, GitBranchTool,
+    GitCommitTool, GitDiffTool, GitPushTool, KubectlTool, RunCommandTool, SandboxedCommandTool,
+};
+#[cfg(feature = "desktop")]
+use crate::tools::{ClipboardTool, NotifyTool};
+use crate::validation::command::CommandRulesConfig;
+use crate::workspace::{SnapshotStrategy, Workspace};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Built-in tools the config can enable by name.
+pub const BUILTIN_TOOLS: [&str; 22] = [
+    "run_command",
+    "process",
+    "sandboxed_command",
+    "cargo",
+    "search_code",
+    "edit_code",
+    "project_map",
+    "docker",
+    "kubectl",
+    "copy_to_clipboard",
+    "notify",
+    "calc",
+    "json_query",
+    "render",
+    "git_diff",
+    "git_commit",
+    "git_branch",
+    "git_push",
+    "llm",
+    "reflect",
+    "analyze_error",
+    "verify_goal",
+];
+
+/// Runtime setup loaded from `agent.toml`; every section and key is optional.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    pub agent: AgentConfig,
+    pub model: ModelConfig,
+    pub tools: ToolsConfig,
+    pub timeouts: TimeoutConfig,
+    pub memory: MemoryConfig,
+    pub budget: Budget,
+    pub telemetry: TelemetryConfig,
+    pub prompts: PromptsConfig,
+    pub secrets: SecretsConfig,
+    pub workspace: WorkspaceConfig,
+    pub limits: LimitsConfig,
+    pub replan: ReplanConfig,
+    pub command_rules: CommandRulesConfig,
+    pub queue: QueueConfig,
+    /// Recurring goals for `agentic schedule`, keyed by job name.
+    pub schedule: BTreeMap<String, ScheduledJob>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    pub dry_run: bool,
+    pub approval: ApprovalPolicy,
+    /// Check the goal with `GoalVerifierTool` after each execution.
+    pub verify: bool,
+    /// Rounds of LLM plan critique before execution; 0 disables review.
+    pub review_rounds: usize,
+    /// Let the user edit each generated plan before it runs.
+    pub edit_plan: bool,
+    /// Score each execution with an LLM judge instead of the built-in heuristic.
+    pub judge: bool,
+    /// Replan when the run scores below this, even if the goal was achieved.
+    pub min_score: Option<u8>,
+    /// Split the goal into subtasks and plan each one separately.
+    pub decompose: bool,
+    /// Tell the planner about the OS, git branch and installed toolchains.
+    pub probe_environment: bool,
+    /// List the workspace's files (honouring `.gitignore`) in the planner prompt.
+    pub project_map: bool,
+    /// Plan with the provider's native function calling where it has one.
+    pub function_calling: bool,
+    /// Show the planner only the tools whose tags fit the goal (see
+    /// `ToolSelector`).
+    pub select_tools: bool,
+    /// Show a desktop notification when `run`, `exec` or `tui` finishes
+    /// (needs the `desktop` feature).
+    pub notify: bool,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            approval: ApprovalPolicy::default(),
+            verify: true,
+            review_rounds: 0,
+            edit_plan: false,
+            judge: false,
+            min_score: None,
+            decompose: false,
+            probe_environment: true,
+            project_map: false,
+            function_calling: false,
+            select_tools: true,
+            notify: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelConfig {
+    /// `provider:model` spec, e.g. `"openai:gpt-4o-mini"` or `"qwen3:8b"`.
+    pub llm: String,
+    pub base_url: Option<String>,
+    pub api_key_env: Option<String>,
+    /// Retries for transient failures (connection errors, HTTP 429 and 5xx).
+    pub max_retries: u32,
+    /// First retry delay; doubles on each further retry.
+    pub initial_backoff_ms: u64,
+    /// Shared by the planner, replanner and every LLM-backed tool.
+    pub requests_per_minute: Option<u32>,
+    pub tiers: ModelTiersConfig,
+}
+
+/// `provider:model` specs for the model tiers; unset tiers use `model.llm`.
+/// Planning asks for `smart`, goal and error analysis for `fast`, and
+/// reflection for `cheap`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelTiersConfig {
+    pub fast: Option<String>,
+    pub smart: Option<String>,
+    pub cheap: Option<String>,
+}
+
+impl ModelTiersConfig {
+    pub fn tiers(&self) -> Result<ModelTiers, String> {
+        let mut tiers = ModelTiers::new();
+        for (tier, spec) in [
+            (ModelTier::Fast, &self.fast),
+            (ModelTier::Smart, &self.smart),
+            (ModelTier::Cheap, &self.cheap),
+        ] {
+            if let Some(spec) = spec {
+                tiers = tiers.with_spec(tier, spec)?;
+            }
+        }
+        Ok(tiers)
+    }
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        let retry = RetryPolicy::default();
+        Self {
+            llm: "qwen3:8b".into(),
+            base_url: None,
+            api_key_env: None,
+            max_retries: retry.max_retries,
+            initial_backoff_ms: retry.initial_backoff.as_millis() as u64,
+            requests_per_minute: None,
+            tiers: ModelTiersConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolsConfig {
+    pub enabled: Vec<String>,
+    /// Removed from `enabled`; handy for switching one tool off temporarily.
+    pub disabled: Vec<String>,
+    /// Command prefixes added to the `run_command` whitelist.
+    pub allowed_commands: Vec<String>,
+    /// Environment variables tool inputs may reference as `$env[VAR]`.
+    pub env: Vec<String>,
+    pub unsafe_shell: bool,
+    /// Working directory for `sandboxed_command`.
+    pub sandbox_dir: String,
+    /// Reuse results of repeated read-only calls until a call changes state.
+    pub cache_results: bool,
+    pub run_command: RunCommandToolsConfig,
+    pub git: GitToolsConfig,
+    pub docker: DockerToolsConfig,
+    pub kubectl: KubectlToolsConfig,
+}
+
+/// `[tools.run_command]`: how `run_command` treats the commands it runs.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunCommandToolsConfig {
+    /// Fail calls whose command exits non-zero; steps can still opt out
+    /// with `"allow_failure": true`.
+    pub fail_on_nonzero_exit: bool,
+    /// Environment variables steps may set with `env`; empty allows any
+    /// but `PATH`, `LD_*` and the like.
+    pub allowed_env: Vec<String>,
+}
+
+impl Default for RunCommandToolsConfig {
+    fn default() -> Self {
+        Self {
+            fail_on_nonzero_exit: true,
+            allowed_env: Vec::new(),
+        }
+    }
+}
+
+/// `[tools.git]`: policies for the `git_commit` and `git_push` tools.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitToolsConfig {
+    pub max_subject_len: usize,
+    /// Require Conventional Commits subjects, e.g. `fix(cli): ...`.
+    pub conventional_commits: bool,
+    /// Let `git_push` take `force: true` (as `--force-with-lease`).
+    pub allow_force_push: bool,
+}
+
+impl Default for GitToolsConfig {
+    fn default() -> Self {
+        Self {
+            max_subject_len: 72,
+            conventional_commits: false,
+            allow_force_push: false,
+        }
+    }
+}
+
+/// `[tools.docker]`: limits for every container the runtime starts, and an
+/// optional image to run shell commands in.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DockerToolsConfig {
+    pub memory: String,
+    pub cpus: f64,
+    pub pids: u32,
+    pub network: String,
+    /// Run `run_command` and `sandboxed_command` in this image instead of on
+    /// the host.
+    pub sandbox_image: Option<String>,
+}
+
+impl Default for DockerToolsConfig {
+    fn default() -> Self {
+        Self {
+            memory: "512m".into(),
+            cpus: 1.0,
+            pids: 256,
+            network: "none".into(),
+            sandbox_image: None,
+        }
+    }
+}
+
+#[cfg(feature = "shell-tools")]
+impl DockerToolsConfig {
+    pub fn limits(&self) -> ContainerLimits {
+        ContainerLimits {
+            memory: self.memory.clone(),
+            cpus: self.cpus,
+            pids: self.pids,
+            network: self.network.clone(),
+        }
+    }
+}
+
+/// `[tools.kubectl]`: which cluster the `kubectl` tool inspects and what it may do.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KubectlToolsConfig {
+    /// Kubeconfig context; the current context when unset.
+    pub context: Option<String>,
+    /// Namespace for calls that name none.
+    pub namespace: Option<String>,
+    /// Verbs allowed beyond get, describe and logs, e.g. `top`.
+    pub extra_verbs: Vec<String>,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: ["run_command", "llm", "reflect", "analyze_error"]
+                .map(String::from)
+                .to_vec(),
+            disabled: Vec::new(),
+            allowed_commands: Vec::new(),
+            env: Vec::new(),
+            unsafe_shell: false,
+            sandbox_dir: ".".into(),
+            cache_results: true,
+            run_command: RunCommandToolsConfig::default(),
+            git: GitToolsConfig::default(),
+            docker: DockerToolsConfig::default(),
+            kubectl: KubectlToolsConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    /// Applies to tools that declare no timeout of their own.
+    pub default_secs: Option<f64>,
+    /// Per-tool overrides, e.g. `run_command = 120`.
+    pub tools: HashMap<String, f64>,
+}
+
+/// Per-plan execution limits; see `RunLimits`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub max_steps: Option<usize>,
+    pub max_wall_secs: Option<f64>,
+    pub max_shell_commands: Option<usize>,
+}
+
+/// `secs` as a duration, if it is a positive number of seconds that fits one.
+/// Out-of-range settings are refused by `RuntimeConfig::parse`.
+fn seconds(secs: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+}
+
+impl LimitsConfig {
+    pub fn run_limits(&self) -> RunLimits {
+        RunLimits {
+            max_steps: self.max_steps,
+            max_wall_time: self.max_wall_secs.and_then(seconds),
+            max_shell_commands: self.max_shell_commands,
+        }
+    }
+}
+
+/// Follow-up plan limit and escalations; see `ReplanPolicy`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplanConfig {
+    pub max_replans: usize,
+    /// Tried in order once replans run out: `"model:<provider:model>"`,
+    /// `"ask_user"` or `"abort"`.
+    pub escalate: Vec<String>,
+}
+
+impl Default for ReplanConfig {
+    fn default() -> Self {
+        Self {
+            max_replans: 1,
+            escalate: Vec::new(),
+        }
+    }
+}
+
+impl ReplanConfig {
+    /// The policy, with escalation models sharing `llm`'s retry, usage
+    /// tracking and secrets.
+    pub fn policy(&self, llm: &LLMTool) -> Result<ReplanPolicy, String> {
+        let mut policy = ReplanPolicy::new(self.max_replans);
+        for entry in &self.escalate {
+            let escalation = match entry.as_str() {
+                "ask_user" => Escalation::AskUser,
+                "abort" => Escalation::Abort,
+                _ => match entry.strip_prefix("model:") {
+                    Some(spec) => Escalation::SwitchModel(Box::new(llm.with_spec(spec)?)),
+                    None => return Err(format!("Unknown replan escalation '{}'", entry)),
+                },
+            };
+            policy = policy.then(escalation);
+        }
+        Ok(policy)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self.escalate.iter().find(|entry| {
+            !matches!(entry.as_str(), "ask_user" | "abort")
+                && entry
+                    .strip_prefix("model:")
+                    .is_none_or(|spec| spec.trim().is_empty())
+        }) {
+            Some(bad) => Err(format!(
+                "replan.escalate: unknown escalation '{}' (expected \"model:<provider:model>\", \"ask_user\" or \"abort\")",
+                bad
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    pub backend: MemoryBackend,
+    pub embedder: EmbedderKind,
+    /// The log of the `file` backend.
+    pub path: String,
+    /// The `file` backend's namespace for this run; unset = a fresh run ID.
+    /// Set it to the ID of a crashed run to pick up its memory.
+    pub run_id: Option<String>,
+    /// Entries longer than this are shortened, their full text saved in
+    /// `spill_dir`; unset = no limit.
+    pub max_entry_chars: Option<usize>,
+    pub spill_dir: String,
+    /// Shorten with a cheap-tier LLM summary rather than first and last lines.
+    pub summarize: bool,
+    /// JSONL file (see `Memory::export_jsonl`) loaded into memory before the run.
+    pub seed: Option<String>,
+    /// JSONL file the run's memory is written to when it finishes.
+    pub export: Option<String>,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            backend: MemoryBackend::default(),
+            embedder: EmbedderKind::default(),
+            path: DEFAULT_MEMORY_FILE.into(),
+            run_id: None,
+            max_entry_chars: None,
+            spill_dir: DEFAULT_SPILL_DIR.into(),
+            summarize: true,
+            seed: None,
+            export: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptsConfig {
+    /// Directory of `<name>.txt` / `<name>.<model-prefix>.txt` templates
+    /// overriding the built-in planner and replanner prompts.
+    pub dir: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsConfig {
+    /// Environment variables exposed to tool inputs as `$secret[NAME]`.
+    pub env: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    /// Directory commands run in and paths are confined to; unset = no confinement.
+    pub root: Option<String>,
+    pub snapshot: SnapshotMode,
+    pub strategy: SnapshotStrategy,
+}
+
+/// When `run` and `exec` snapshot the workspace, restoring it if the goal
+/// is not achieved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotMode {
+    #[default]
+    Off,
+    /// Only when the simulated plan has a high-risk step.
+    Risky,
+    Always,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryBackend {
+    /// Plain append-only log (`InMemoryLog`).
+    #[default]
+    InMemory,
+    /// Embedding-backed `SemanticMemory`.
+    Semantic,
+    /// `FileMemory`: every entry synced to `path` as written, so a crashed
+    /// run's memory survives. Each run gets its own namespace in the file.
+    File,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedderKind {
+    /// Offline feature hashing; no model calls.
+    #[default]
+    Hashing,
+    /// The configured LLM provider's embeddings endpoint.
+    Llm,
+}
+
+impl RuntimeConfig {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let value = toml::parse(text)?;
+        let config: Self = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// `from_file` if `path` exists, else the defaults.
+    pub fn load_or_default(path: &str) -> Result<Self, String> {
+        if Path::new(path).exists() {
+            Self::from_file(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Applies `AGENTIC_LLM`, `AGENTIC_DRY_RUN` and the standard
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` on top of the file settings.
+    pub fn with_env_overrides(mut self) -> Self {
+        if let Ok(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            self.telemetry.otlp_endpoint = Some(endpoint);
+        }
+        if let Ok(spec) = std::env::var("AGENTIC_LLM") {
+            self.model.llm = spec;
+        }
+        if std::env::var("AGENTIC_DRY_RUN").is_ok() {
+            self.agent.dry_run = true;
+        }
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for name in self.tools.enabled.iter().chain(&self.tools.disabled) {
+            if !BUILTIN_TOOLS.contains(&name.as_str()) {
+                return Err(format!(
+                    "Unknown tool '{}' (built-in tools: {})",
+                    name,
+                    BUILTIN_TOOLS.join(", ")
+                ));
+            }
+        }
+        let timeouts = self
+            .timeouts
+            .default_secs
+            .iter()
+            .chain(self.timeouts.tools.values());
+        if let Some(bad) = timeouts.into_iter().find(|secs| seconds(**secs).is_none()) {
+            return Err(format!(
+                "Timeouts must be a positive number of seconds, got {}",
+                bad
+            ));
+        }
+        self.telemetry.console_level()?;
+        self.replan.validate()?;
+        if let Some(score) = self.agent.min_score
+            && score > 100
+        {
+            return Err(format!("agent.min_score must be 0-100, got {}", score));
+        }
+        if let Some(secs) = self.limits.max_wall_secs
+            && seconds(secs).is_none()
+        {
+            return Err(format!(
+                "limits.max_wall_secs must be a positive number of seconds, got {}",
+                secs
+            ));
+        }
+        if self.model.requests_per_minute == Some(0) {
+            return Err("model.requests_per_minute must be positive".into());
+        }
+        if let Some(max) = self.budget.max_cost_usd
+            && !(max.is_finite() && max >= 0.0)
+        {
+            return Err(format!(
+                "budget.max_cost_usd must be non-negative, got {}",
+                max
+            ));
+        }
+        if self.agent.notify && !cfg!(feature = "desktop") {
+            return Err("agent.notify needs agentic built with the desktop feature".into());
+        }
+        if self.memory.max_entry_chars == Some(0) {
+            return Err("memory.max_entry_chars must be positive".into());
+        }
+        if self.tools.git.max_subject_len == 0 {
+            return Err("tools.git.max_subject_len must be positive".into());
+        }
+        #[cfg(feature = "shell-tools")]
+        self.tools
+            .docker
+            .limits()
+            .check()
+            .map_err(|e| format!("tools.docker: {}", e))?;
+        self.queue.validate()?;
+        for (name, job) in &self.schedule {
+            CronSchedule::parse(&job.cron).map_err(|e| format!("schedule.{}: {}", name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Enabled tool names, minus the disabled ones.
+    pub fn enabled_tools(&self) -> Vec<&str> {
+        self.tools
+            .enabled
+            .iter()
+            .filter(|name| !self.tools.disabled.contains(name))
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn llm_config(&self) -> LlmConfig {
+        let mut config = LlmConfig::parse(&self.model.llm);
+        if self.model.base_url.is_some() {
+            config.base_url = self.model.base_url.clone();
+        }
+        if self.model.api_key_env.is_some() {
+            config.api_key_env = self.model.api_key_env.clone();
+        }
+        config
+    }
+
+    /// The configured model with retry, rate limiting and model tiers applied.
+    pub fn llm(&self) -> Result<LLMTool, String> {
+        let retry = RetryPolicy {
+            max_retries: self.model.max_retries,
+            initial_backoff: Duration::from_millis(self.model.initial_backoff_ms),
+            ..RetryPolicy::default()
+        };
+        let mut llm = LLMTool::from_config(&self.llm_config())?
+            .with_retry(retry)
+            .with_tiers(self.model.tiers.tiers()?);
+        if let Some(rpm) = self.model.requests_per_minute {
+            llm = llm.with_rate_limit(rpm);
+        }
+        Ok(llm)
+    }
+
+    /// A context with the enabled tools (backed by `llm`), policies, command rules, timeouts,
+    /// budget, secrets, workspace and memory backend applied. Tools record LLM usage in
+    /// `context.usage`.
+    pub fn build_context(&self, llm: &LLMTool) -> Result<Context, String> {
+        let mut context = Context::new()
+            .with_llm(&self.model.llm)
+            .with_model_tiers(llm.tiers().clone())
+            .with_approval(self.agent.approval)
+            .with_budget(self.budget);
+        if let Some(root) = &self.workspace.root {
+            context = context.with_workspace(Workspace::new(root)?);
+        }
+        for var in &self.secrets.env {
+            if let Err(e) = context.secrets.load_env(var) {
+                tracing::warn!("⚠️ {}", e);
+            }
+        }
+        // The provider's own key is never needed in a tool input, but it must
+        // not leak into output either.
+        if let Some(var) = self.llm_config().api_key_var()
+            && let Ok(key) = std::env::var(var)
+        {
+            context.secrets.insert(var, &key);
+        }
+        let llm = &llm
+            .clone()
+            .with_usage(context.usage.clone())
+            .with_secrets(context.secrets.clone());
+
+        for name in self.enabled_tools() {
+            context = match name {
+                #[cfg(feature = "shell-tools")]
+                "run_command" => {
+                    let settings = &self.tools.run_command;
+                    let allowed_env: Vec<&str> =
+                        settings.allowed_env.iter().map(String::as_str).collect();
+                    let mut tool = RunCommandTool::new()
+                        .with_nonzero_exit_ok(!settings.fail_on_nonzero_exit)
+                        .with_allowed_env(&allowed_env);
+                    if let Some(workspace) = &context.workspace {
+                        tool = tool.in_workspace(workspace.clone());
+                    }
+                    if let Some(image) = &self.tools.docker.sandbox_image {
+                        tool = tool.in_container(image, self.tools.docker.limits());
+                    }
+                    context.register_tool(tool)
+                }
+                "process" => context.register_tool(ProcessTool::new()),
+                #[cfg(feature = "shell-tools")]
+                "sandboxed_command" => {
+                    let dir = match &context.workspace {
+                        Some(workspace) => workspace.resolve(&self.tools.sandbox_dir)?,
+                        None => self.tools.sandbox_dir.clone().into(),
+                    };
+                    let mut tool = SandboxedCommandTool::new(dir).with_name(name);
+                    if let Some(image) = &self.tools.docker.sandbox_image {
+                        tool = tool.in_docker_with_limits(image, self.tools.docker.limits());
+                    }
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "cargo" => {
+                    let tool = match &context.workspace {
+                        Some(workspace) => CargoTool::new().in_workspace(workspace.clone()),
+                        None => CargoTool::new(),
+                    };
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "search_code" => {
+                    let tool = match &context.workspace {
+                        Some(workspace) => CodeSearchTool::new().in_workspace(workspace.clone()),
+                        None => CodeSearchTool::new(),
+                    };
+                    context.register_tool(tool)
+                }
+                "edit_code" => {
+                    let tool = match &context.workspace {
+                        Some(workspace) => CodeEditTool::new().in_workspace(workspace.clone()),
+                        None => CodeEditTool::new(),
+                    };
+                    context.register_tool(tool)
+                }
+                "project_map" => {
+                    let tool = match &context.workspace {
+                        Some(workspace) => ProjectMapTool::new().in_workspace(workspace.clone()),
+                        None => ProjectMapTool::new(),
+                    };
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "git_diff" => {
+                    let tool = match &context.workspace {
+                        Some(workspace) => GitDiffTool::new().in_workspace(workspace.clone()),
+                        None => GitDiffTool::new(),
+                    };
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "git_commit" => {
+                    let tool = GitCommitTool::new().with_policy(CommitPolicy {
+                        max_subject_len: self.tools.git.max_subject_len,
+                        conventional: self.tools.git.conventional_commits,
+                    });
+                    let tool = match &context.workspace {
+                        Some(workspace) => tool.in_workspace(workspace.clone()),
+                        None => tool,
+                    };
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "git_branch" => {
+                    let tool = match &context.workspace {
+                        Some(workspace) => GitBranchTool::new().in_workspace(workspace.clone()),
+                        None => GitBranchTool::new(),
+                    };
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "git_push" => {
+                    let mut tool = GitPushTool::new();
+                    if self.tools.git.allow_force_push {
+                        tool = tool.allow_force_push();
+                    }
+                    let tool = match &context.workspace {
+                        Some(workspace) => tool.in_workspace(workspace.clone()),
+                        None => tool,
+                    };
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "docker" => {
+                    let tool = DockerTool::new().with_limits(self.tools.docker.limits());
+                    let tool = match &context.workspace {
+                        Some(workspace) => tool.in_workspace(workspace.clone()),
+                        None => tool,
+                    };
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "shell-tools")]
+                "kubectl" => {
+                    let verbs: Vec<&str> = self
+                        .tools
+                        .kubectl
+                        .extra_verbs
+                        .iter()
+                        .map(String::as_str)
+                        .collect();
+                    let mut tool = KubectlTool::new().with_verbs(&verbs);
+                    if let Some(kube_context) = &self.tools.kubectl.context {
+                        tool = tool.with_context(kube_context);
+                    }
+                    if let Some(namespace) = &self.tools.kubectl.namespace {
+                        tool = tool.with_namespace(namespace);
+                    }
+                    context.register_tool(tool)
+                }
+                #[cfg(feature = "desktop")]
+                "copy_to_clipboard" => context.register_tool(ClipboardTool::new()),
+                #[cfg(feature = "desktop")]
+                "notify" => context.register_tool(NotifyTool::new()),
+                "calc" => context.register_tool(CalcTool::new()),
+                "json_query" => context.register_tool(JsonQueryTool::new()),
+                "render" => context.register_tool(TemplateTool::new()),
+                "llm" => context.register_tool(llm.clone()),
+                "reflect" => context.register_tool(ReflectorTool::new(llm.clone())),
+                "analyze_error" => context.register_tool(ErrorAnalyzerTool::new(llm.clone())),
+                "verify_goal" => context.register_tool(GoalVerifierTool::new(llm.clone())),
+                other if BUILTIN_TOOLS.contains(&other) => {
+                    let feature = match other {
+                        "copy_to_clipboard" | "notify" => "desktop",
+                        _ => "shell-tools",
+                    };
+                    return Err(format!(
+                        "Tool '{}' is not enabled in this build (cargo feature \"{}\")",
+                        other, feature
+                    ));
+                }
+                other => return Err(format!("Unknown tool '{}'", other)),
+            };
+        }
+
+        context
+            .allowed_commands
+            .extend(self.tools.allowed_commands.iter().cloned());
+        for var in &self.tools.env {
+            context = context.with_env_var(var);
+        }
+        if self.tools.unsafe_shell {
+            context = context.enable_unsafe_shell();
+        }
+        context = context.with_command_rules(self.command_rules.rules()?);
+        if self.agent.dry_run {
+            context = context.enable_dry_run();
+        }
+        if self.tools.cache_results {
+            context = context.with_tool_cache();
+        }
+
+        if let Some(timeout) = self.timeouts.default_secs.and_then(seconds) {
+            context = context.with_default_timeout(timeout);
+        }
+        for (tool, secs) in &self.timeouts.tools {
+            if let Some(timeout) = seconds(*secs) {
+                context = context.with_tool_timeout(tool, timeout);
+            }
+        }
+
+        if let Some(dir) = &self.prompts.dir {
+            context.prompts.load_dir(dir)?;
+        }
+
+        match self.memory.backend {
+            MemoryBackend::InMemory => {}
+            MemoryBackend::Semantic => {
+                let embedder: Box<dyn Embedder> = match self.memory.embedder {
+                    EmbedderKind::Hashing => Box::new(HashingEmbedder::default()),
+                    EmbedderKind::Llm => Box::new(LlmEmbedder::new(self.llm_config().build()?)?),
+                };
+                context = context.with_memory(SemanticMemory::new(embedder));
+            }
+            MemoryBackend::File => {
+                let run_id = self.memory.run_id.clone().unwrap_or_else(new_run_id);
+                context = context
+                    .with_memory(FileMemory::open(&self.memory.path)?)
+                    .with_run_id(&run_id);
+            }
+        }
+        if let Some(max_chars) = self.memory.max_entry_chars {
+            let mut limit = EntryLimit::new(max_chars, &self.memory.spill_dir);
+            if self.memory.summarize {
+                limit = limit.with_summarizer(llm.clone());
+            }
+            context = context.with_memory_limit(limit);
+        }
+        if let Some(seed) = &self.memory.seed {
+            context.memory_mut().import_jsonl(Path::new(seed))?;
+        }
+        Ok(context)
+    }
+
+    /// An agent for `goal` with `[agent]` applied: `planner` (given probes, or
+    /// wrapped for function calling), an LLM replanner, and the verifier,
+    /// reviewer, judge and decomposer the config enables, all backed by `llm`.
+    pub fn build_agent(
+        &self,
+        goal: &str,
+        context: Context,
+        llm: &LLMTool,
+        mut planner: LLMPlanner,
+    ) -> BasicAgent {
+        if self.agent.probe_environment {
+            planner = planner.with_probes(ProbeSet::standard());
+        }
+        if self.agent.project_map {
+            planner = planner.with_project_map(ProjectMap::new());
+        }
+        if self.agent.select_tools {
+            planner = planner.with_tool_selector(ToolSelector::new());
+        }
+        let planner: Box<dyn Planner> = if self.agent.function_calling {
+            Box::new(FunctionCallingPlanner::new(llm.clone()).with_fallback(planner))
+        } else {
+            Box::new(planner)
+        };
+        let replanner = Box::new(LLMReplanner::new(llm.clone()));
+        let mut agent = BasicAgent::new(
+            TaskModel::new(goal).with_limits(self.limits.run_limits()),
+            context,
+            Some(planner),
+            Some(replanner),
+        );
+        if self.agent.verify {
+            agent = agent.with_verifier(Box::new(GoalVerifierTool::new(llm.clone())));
+        }
+        if self.agent.review_rounds > 0 {
+            let reviewer = ReviewerAgent::new(Box::new(LLMCritic::new(llm.clone())))
+                .with_rounds(self.agent.review_rounds);
+            agent = agent.with_reviewer(reviewer);
+        }
+        if self.agent.judge {
+            agent = agent.with_evaluator(Box::new(LLMJudge::new(llm.clone())));
+        }
+        if self.agent.decompose {
+            agent = agent.with_decomposer(Box::new(LLMDecomposer::new(llm.clone())));
+        }
+        agent
+    }
+
+    /// Writes `DEFAULT_CONFIG` to `path`, refusing to overwrite an existing file.
+    pub fn write_default(path: &str) -> Result<(), String> {
+        if Path::new(path).exists() {
+            return Err(format!("{} already exists", path));
+        }
+        fs::write(path, DEFAULT_CONFIG).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
+
+/// Documented `agent.toml` equivalent to `RuntimeConfig::default()`.
+pub const DEFAULT_CONFIG: &str = r#"# agent.toml — agentic-runtime configuration
+
+[agent]
+dry_run = false
+# always_ask | auto_approve | deny_all | ask_destructive
+approval = "always_ask"
+# Check the goal with an LLM verifier after each execution
+verify = true
+# Rounds of LLM critique (and revision) of each plan before it runs; 0 = off
+review_rounds = 0
+# Review, reorder and edit each generated plan (or open it in $EDITOR) before it runs
+edit_plan = false
+# Score each run against a rubric with an LLM judge (goal completion, correctness, efficiency, safety)
+judge = false
+# Replan when the score falls below this, even if the goal was reported achieved
+# min_score = 70
+# Break the goal into dependent subtasks and plan and run each one separately
+decompose = false
+# Detect the OS, git branch and installed toolchains before planning
+probe_environment = true
+# List the project's files (a condensed tree honouring .gitignore) in the planner prompt
+project_map = false
+# Plan through native function calling (OpenAI-compatible and Anthropic models)
+# instead of parsing a JSON plan; other providers keep the JSON planner
+function_calling = false
+# Only describe the tools whose tags fit the goal (git tools for a commit, cargo
+# for tests, ...) in the planner prompt; untagged and command tools always show
+select_tools = true
+# Show a desktop notification when a run finishes (needs the desktop feature)
+notify = false
+
+[model]
+# provider:model — "qwen3:8b" (Ollama), "openai:gpt-4o-mini", "anthropic:claude-sonnet-4-5"
+llm = "qwen3:8b"
+# base_url = "http://localhost:11434"
+# api_key_env = "OPENAI_API_KEY"
+# Retry transient failures (connection errors, HTTP 429/5xx) with exponential backoff
+max_retries = 3
+initial_backoff_ms = 500
+# requests_per_minute = 60
+
+[model.tiers]
+# Route consumers to other models by tier; unset tiers use model.llm.
+# smart: planner and replanner; fast: goal and error analysis; cheap: reflection
+# fast = "qwen3:1.7b"
+# smart = "anthropic:claude-sonnet-4-5"
+# cheap = "openai:gpt-4o-mini"
+
+[tools]
+# Built-ins: run_command, process (checks on and stops "background" steps),
+# sandboxed_command, cargo, search_code, edit_code, project_map, docker,
+# kubectl, git_diff, git_commit, git_branch, git_push, copy_to_clipboard,
+# notify (desktop feature), calc, json_query, render, llm, reflect,
+# analyze_error, verify_goal
+enabled = ["run_command", "llm", "reflect", "analyze_error"]
+disabled = []
+# Extra command prefixes allowed for run_command (cargo, git, ls and echo always are)
+allowed_commands = []
+# Environment variables tool inputs can read as $env[VAR], e.g. ["CI", "GITHUB_REF"];
+# unlike secrets, their values are not masked
+env = []
+unsafe_shell = false
+sandbox_dir = "."
+# Reuse results of repeated read-only calls (e.g. `git status`, identical llm
+# prompts) until a call changes state
+cache_results = true
+
+[tools.run_command]
+# A command exiting non-zero fails its step (and triggers error analysis);
+# steps can pass "allow_failure": true for commands like grep or diff
+fail_on_nonzero_exit = true
+# Variables steps may set with "env" (they can also pick a "cwd" inside the
+# workspace); empty allows any but PATH, LD_* and the like
+allowed_env = []
+
+[tools.git]
+# Commit messages git_commit accepts
+max_subject_len = 72
+conventional_commits = false
+# git_push never force-pushes unless this is set, and then uses --force-with-lease
+allow_force_push = false
+
+[tools.docker]
+# Limits for every container (docker tool runs and sandbox_image commands);
+# calls may ask for less but never more. network = "none" keeps them offline
+memory = "512m"
+cpus = 1.0
+pids = 256
+network = "none"
+# Run run_command and sandboxed_command inside this image instead of on the host
+# sandbox_image = "rust:1-slim"
+
+[tools.kubectl]
+# context = "staging"
+# namespace = "default"
+# kubectl only runs get, describe and logs; allow more verbs here (e.g. ["top"])
+extra_verbs = []
+
+[command_rules]
+# Plans whose run_command steps match a rule fail validation and go back to the
+# planner with the rule's hint. Built-ins: root_delete, sudo, curl_pipe_shell,
+# env_exfiltration
+disabled = []
+# deny = [{ name = "terraform_destroy", pattern = "\\bterraform\\s+destroy\\b", reason = "destroys infrastructure", hint = "run terraform plan instead" }]
+deny = []
+
+[timeouts]
+# default_secs = 300
+
+[timeouts.tools]
+# run_command = 600
+
+[memory]
+# in_memory | semantic | file
+backend = "in_memory"
+# hashing | llm (semantic backend only)
+embedder = "hashing"
+# file backend: entries are synced to this JSONL log as they are written and
+# reloaded on startup; each run gets a namespace, fresh unless run_id resumes one
+path = ".agentic/memory.jsonl"
+# run_id = "run-1718000000000-0"
+# Shorten entries longer than this (e.g. full build logs) so planner prompts
+# stay small; the full text is saved in spill_dir and the entry points to it
+# max_entry_chars = 4000
+spill_dir = ".agentic/memory"
+# Shorten with a cheap-tier LLM summary instead of the first and last lines
+summarize = true
+# Load entries from a JSONL file ({"label": ..., "content": ...} per line)
+# before the run, and write the run's memory to one when it finishes
+# seed = "memory.jsonl"
+# export = "memory-export.jsonl"
+
+[budget]
+# Abort the run once LLM usage passes either limit
+# max_tokens = 200000
+# max_cost_usd = 1.0
+
+[limits]
+# Stop executing a plan (with a "budget exceeded" error) past any of these;
+# planners are told about them too
+# max_steps = 20
+# max_wall_secs = 900
+# max_shell_commands = 10
+
+[replan]
+# Follow-up plans after an unsuccessful run before escalating
+max_replans = 1
+# Tried in order once those run out, each allowing max_replans more:
+# "model:<provider:model>" replans with another (bigger) model, "ask_user"
+# asks for guidance, "abort" stops with a summary (also what happens after the last)
+escalate = []
+
+[queue]
+# Goals `agentic serve` and `agentic batch` run at once
+workers = 2
+# Cancel a queued goal that runs longer than this
+# job_timeout_secs = 1800
+
+# Recurring goals for `agentic schedule` (cron fields in UTC):
+# [schedule.nightly-deps]
+# cron = "0 3 * * *"
+# goal = "update dependencies and open a PR"
+
+[prompts]
+# Override the planner/replanner prompts with planner.txt, replanner.txt or
+# per-model variants such as planner.gpt-4o.txt; see {{variables}} in the built-ins
+# dir = ".agentic/prompts"
+
+[secrets]
+# Environment variables tools can use as $secret[NAME]; their values are
+# masked in memory, output and LLM prompts
+env = []
+
+[workspace]
+# Run commands in this directory and refuse paths outside it (sandbox_dir is relative to it)
+# root = "."
+# off | risky | always — snapshot before `run`/`exec` and restore it if the goal is not achieved
+snapshot = "off"
+# auto | git_stash | copy
+strategy = "auto"
+
+[telemetry]
+# off | error | warn | info | debug (adds span timings) | trace (adds memory log)
+console = "info"
+# Export spans over OTLP/HTTP, e.g. to Jaeger or Grafana Tempo
+# otlp_endpoint = "http://localhost:4318"
+service_name = "agentic-runtime"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_timeouts_are_config_errors() {
+        for text in [
+            "[timeouts]\ndefault_secs = 1e20\n",
+            "[timeouts.tools]\nrun_command = 1e20\n",
+            "[timeouts]\ndefault_secs = -1\n",
+            "[limits]\nmax_wall_secs = 1e20\n",
+            "[queue]\njob_timeout_secs = 1e20\n",
+        ] {
+            let error = RuntimeConfig::parse(text).unwrap_err();
+            assert!(error.contains("positive number of seconds"), "{}", error);
+        }
+        let config = RuntimeConfig::parse("[timeouts]\ndefault_secs = 2.5\n").unwrap();
+        assert_eq!(
+            config.timeouts.default_secs.and_then(seconds),
+            Some(Duration::from_millis(2500))
+        );
+    }
+}
@@ -0,0 +1,383 @@
+// src/config/toml.rs
+
+use serde_json::{Map, Number, Value};
+
+/// Parses the TOML subset used by `agent.toml` into a JSON value, so configs
+/// can be deserialized with serde.
+///
+/// Supports `[table]` / `[a.b]` headers, bare, quoted and dotted keys, basic
+/// and literal strings, integers, floats, booleans, (multi-line) arrays and
+/// inline tables. Dates, multi-line strings and arrays of tables are not
+/// supported.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser.document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn document(&mut self) -> Result<Value, String> {
+        let mut root = Map::new();
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_whitespace_and_comments(true);
+            let Some(c) = self.peek() else { break };
+
+            if c == '[' {
+                self.bump();
+                if self.peek() == Some('[') {
+                    return Err(self.error("arrays of tables are not supported"));
+                }
+                current = self.key_path()?;
+                self.skip_inline_whitespace();
+                self.expect(']')?;
+                table_at(&mut root, &current, self.line)?;
+            } else {
+                let path = self.key_path()?;
+                self.skip_inline_whitespace();
+                self.expect('=')?;
+                self.skip_inline_whitespace();
+                let value = self.value()?;
+
+                let (last, parents) = path.split_last().expect("key path is never empty");
+                let mut full = current.clone();
+                full.extend(parents.iter().cloned());
+                let table = table_at(&mut root, &full, self.line)?;
+                if table.insert(last.clone(), value).is_some() {
+                    return Err(self.error(&format!("duplicate key '{}'", last)));
+                }
+            }
+            self.end_of_line()?;
+        }
+        Ok(Value::Object(root))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("TOML line {}: {}", self.line, message)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}', found end of file", expected))),
+        }
+    }
+
+    fn skip_inline_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    /// Skips blanks and `#` comments, across newlines when `multiline`.
+    fn skip_whitespace_and_comments(&mut self, multiline: bool) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => {
+                    self.bump();
+                }
+                Some('\n') if multiline => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_whitespace_and_comments(false);
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some(c) => Err(self.error(&format!("unexpected '{}' after value", c))),
+        }
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>, String> {
+        let mut path = vec![self.key()?];
+        loop {
+            self.skip_inline_whitespace();
+            if self.peek() != Some('.') {
+                return Ok(path);
+            }
+            self.bump();
+            self.skip_inline_whitespace();
+            path.push(self.key()?);
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip_inline_whitespace();
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.bump();
+                }
+                if self.pos == start {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.chars[start..self.pos].iter().collect())
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            // Checked before moving on, so the error names the string's line.
+            if matches!(self.peek(), Some('\n') | None) {
+                return Err(self.error("unterminated string"));
+            }
+            match self.bump() {
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('u') => {
+                            let hex: String = (0..4).filter_map(|_| self.bump()).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid \\u escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    text.push(escaped);
+                }
+                c => text.extend(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut text = String::new();
+        loop {
+            if matches!(self.peek(), Some('\n') | None) {
+                return Err(self.error("unterminated string"));
+            }
+            match self.bump() {
+                Some('\'') => return Ok(text),
+                c => text.extend(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments(true);
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_whitespace_and_comments(true);
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut table = Map::new();
+        self.skip_inline_whitespace();
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(Value::Object(table));
+        }
+        loop {
+            let path = self.key_path()?;
+            self.skip_inline_whitespace();
+            self.expect('=')?;
+            self.skip_inline_whitespace();
+            let value = self.value()?;
+            let (last, parents) = path.split_last().expect("key path is never empty");
+            table_at(&mut table, parents, self.line)?.insert(last.clone(), value);
+
+            self.skip_inline_whitespace();
+            match self.bump() {
+                Some(',') => self.skip_inline_whitespace(),
+                Some('}') => return Ok(Value::Object(table)),
+                _ => return Err(self.error("expected ',' or '}' in inline table")),
+            }
+        }
+    }
+
+    /// Booleans and numbers: everything up to the next delimiter.
+    fn scalar(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if !matches!(c, ',' | ']' | '}' | '#' | '\n' | '\r' | ' ' | '\t'))
+        {
+            self.bump();
+        }
+        let raw: String = self.chars[start..self.pos].iter().collect();
+        match raw.as_str() {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            "" => return Err(self.error("expected a value")),
+            _ => {}
+        }
+
+        let digits = raw.replace('_', "");
+        if let Ok(int) = digits.parse::<i64>() {
+            return Ok(Value::Number(int.into()));
+        }
+        digits
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.error(&format!("invalid value '{}'", raw)))
+    }
+}
+
+/// The table at `path` under `root`, creating intermediate tables as needed.
+fn table_at<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &[String],
+    line: usize,
+) -> Result<&'a mut Map<String, Value>, String> {
+    let mut table = root;
+    for key in path {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        table = entry
+            .as_object_mut()
+            .ok_or_else(|| format!("TOML line {}: '{}' is not a table", line, key))?;
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_tables_keys_and_values() {
+        let text = r#"
+# Top-level keys come before any table.
+name = "agent"   # trailing comment
+retries = 1_000
+ratio = 0.5
+
+[model]
+provider = 'openai'
+"max tokens" = 512
+tuning.temperature = -0.25
+
+[tools.git]
+enabled = true
+paths = [
+    "src",   # first
+    'C:\tmp',
+]
+limits = { cpu = 2, memory = "1g", nested = { ok = false } }
+"#;
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({
+                "name": "agent",
+                "retries": 1000,
+                "ratio": 0.5,
+                "model": {
+                    "provider": "openai",
+                    "max tokens": 512,
+                    "tuning": {"temperature": -0.25}
+                },
+                "tools": {"git": {
+                    "enabled": true,
+                    "paths": ["src", "C:\\tmp"],
+                    "limits": {"cpu": 2, "memory": "1g", "nested": {"ok": false}}
+                }}
+            })
+        );
+        assert_eq!(
+            parse(r#"s = "tab\there, \"quoted\", back\\slash, \u00e9""#).unwrap(),
+            json!({"s": "tab\there, \"quoted\", back\\slash, é"})
+        );
+        assert_eq!(parse("").unwrap(), json!({}));
+    }
+
+    #[test]
+    fn errors_name_their_line() {
+        for (text, message) in [
+            ("a = 1\na = 2", "TOML line 2: duplicate key 'a'"),
+            (
+                "[a]\nx = 1\n[[b]]",
+                "TOML line 3: arrays of tables are not supported",
+            ),
+            ("s = \"open\nt = 1", "TOML line 1: unterminated string"),
+            ("s = \"\\q\"", "TOML line 1: invalid escape sequence"),
+            (
+                "a = [1, 2\nb = 3",
+                "TOML line 2: expected ',' or ']' in array",
+            ),
+            (
+                "a = {x = 1",
+                "TOML line 1: expected ',' or '}' in inline table",
+            ),
+            ("\n\na = nope", "TOML line 3: invalid value 'nope'"),
+            ("a = 1 2", "TOML line 1: unexpected '2' after value"),
+            ("a = 1\n[a.b]", "TOML line 2: 'a' is not a table"),
+            ("= 1", "TOML line 1: expected a key"),
+        ] {
+            let error = parse(text).unwrap_err();
+            assert!(error.starts_with(message), "{:?}: {}", text, error);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// src/context/handle.rs
+
+use crate::context::ProcessManager;
+use crate::events::{AgentEvent, EventBus};
+use crate::memory::SharedMemory;
+use crate::memory::limit::EntryLimit;
+use crate::secrets::Secrets;
+use crate::tools::CancellationToken;
+
+/// A cheap, thread-safe view of a running `Context`, handed to tools through
+/// `Tool::execute_with`: they can log to the run's memory, emit events and
+/// watch for cancellation without borrowing the context. Clones share
+/// everything.
+#[derive(Clone, Debug)]
+pub struct ContextHandle {
+    memory: SharedMemory,
+    memory_limit: Option<EntryLimit>,
+    events: EventBus,
+    secrets: Secrets,
+    processes: ProcessManager,
+    cancel: CancellationToken,
+}
+
+impl ContextHandle {
+    pub(crate) fn new(
+        memory: SharedMemory,
+        memory_limit: Option<EntryLimit>,
+        events: EventBus,
+        secrets: Secrets,
+        processes: ProcessManager,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            memory,
+            memory_limit,
+            events,
+            secrets,
+            processes,
+            cancel,
+        }
+    }
+
+    /// Records `content` in the run's memory, with secret values masked and
+    /// the context's entry limit applied.
+    pub fn log(&self, label: &str, content: &str) {
+        let content = self.prepare_entry(label, content);
+        self.memory.log(label, &content);
+    }
+
+    /// Like `log`, into `namespace` (see `Memory::log_in`).
+    pub fn log_in(&self, namespace: &str, label: &str, content: &str) {
+        let content = self.prepare_entry(label, content);
+        self.memory.log_in(namespace, label, &content);
+    }
+
+    fn prepare_entry(&self, label: &str, content: &str) -> String {
+        let mut content = self.secrets.redact(content);
+        if let Some(limit) = &self.memory_limit {
+            content = limit.apply(label, &content);
+        }
+        tracing::trace!(label, "{}", content);
+        content
+    }
+
+    pub fn emit(&self, event: AgentEvent) {
+        self.events.emit(&event);
+    }
+
+    /// Streams one line of `tool`'s output as it runs, as an
+    /// `AgentEvent::StepOutput` with secret values masked. Only the finished
+    /// result reaches memory, so prompts do not see the output twice.
+    pub fn output_line(&self, tool: &str, stream: &str, line: &str) {
+        let line = self.secrets.redact(line);
+        self.emit(AgentEvent::StepOutput {
+            tool: tool.to_string(),
+            stream: stream.to_string(),
+            line,
+        });
+    }
+
+    /// Fires when the call times out or the run is aborted.
+    pub fn cancel_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub fn memory(&self) -> &SharedMemory {
+        &self.memory
+    }
+
+    /// The run's background processes.
+    pub fn processes(&self) -> &ProcessManager {
+        &self.processes
+    }
+}
@@ -0,0 +1,125 @@
+// src/context/history.rs
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+/// Characters of output kept per call in `ToolHistory::prompt_section`.
+const PROMPT_OUTPUT_CHARS: usize = 300;
+
+/// One tool invocation, as recorded in a `ToolHistory`. `output` holds the
+/// error text of a failed call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    pub tool: String,
+    pub input: String,
+    #[serde(default)]
+    pub output: String,
+    #[serde(rename = "duration_ms", with = "millis")]
+    pub duration: Duration,
+    pub success: bool,
+}
+
+/// Every tool call of a run, in order. Clones share the history, so tools
+/// running on other threads and the agent see the same calls.
+#[derive(Clone, Debug, Default)]
+pub struct ToolHistory {
+    calls: Arc<RwLock<Vec<ToolCallRecord>>>,
+}
+
+impl ToolHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, call: ToolCallRecord) {
+        self.calls
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(call);
+    }
+
+    pub fn all(&self) -> Vec<ToolCallRecord> {
+        self.read(|calls| calls.to_vec())
+    }
+
+    pub fn len(&self) -> usize {
+        self.read(|calls| calls.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Calls of `tool`, oldest first.
+    pub fn for_tool(&self, tool: &str) -> Vec<ToolCallRecord> {
+        self.filter(|call| call.tool == tool)
+    }
+
+    pub fn failures(&self) -> Vec<ToolCallRecord> {
+        self.filter(|call| !call.success)
+    }
+
+    /// The latest call of `tool`.
+    pub fn last(&self, tool: &str) -> Option<ToolCallRecord> {
+        self.read(|calls| calls.iter().rev().find(|call| call.tool == tool).cloned())
+    }
+
+    /// The latest `limit` calls, one line each with a clipped output, for
+    /// prompts; empty when nothing has run.
+    pub fn prompt_section(&self, limit: usize) -> String {
+        self.read(|calls| {
+            if calls.is_empty() {
+                return String::new();
+            }
+            let lines = calls[calls.len().saturating_sub(limit)..]
+                .iter()
+                .map(|call| {
+                    let mut output: String = call
+                        .output
+                        .chars()
+                        .take(PROMPT_OUTPUT_CHARS)
+                        .collect::<String>()
+                        .replace('\n', " ⏎ ");
+                    if call.output.chars().count() > PROMPT_OUTPUT_CHARS {
+                        output.push('…');
+                    }
+                    format!(
+                        "- {} {} `{}` ({}ms): {}",
+                        if call.success { "✅" } else { "❌" },
+                        call.tool,
+                        call.input,
+                        call.duration.as_millis(),
+                        output
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("TOOL CALLS SO FAR (oldest first):\n{}\n\n", lines)
+        })
+    }
+
+    fn filter(&self, keep: impl Fn(&ToolCallRecord) -> bool) -> Vec<ToolCallRecord> {
+        self.read(|calls| calls.iter().filter(|call| keep(call)).cloned().collect())
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&[ToolCallRecord]) -> T) -> T {
+        f(&self.calls.read().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// (De)serializes a `Duration` as whole milliseconds.
+pub(crate) mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
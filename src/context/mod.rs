@@ -1,39 +1,236 @@
 // src/context/mod.rs
 
-use crate::memory::{InMemoryLog, Memory};
-use crate::tools::Tool;
+pub mod handle;
+pub mod history;
+pub mod processes;
+
+pub use handle::ContextHandle;
+pub use history::{ToolCallRecord, ToolHistory};
+pub use processes::{ProcessManager, ProcessStatus, ReadyCheck};
+
+use crate::events::{AgentEvent, EventBus, EventSubscriber};
+use crate::llm::{Budget, ModelTiers, UsageTracker};
+use crate::mcp::McpClient;
+use crate::memory::limit::EntryLimit;
+use crate::memory::scoped::ScopedMemory;
+use crate::memory::{Memory, SharedMemory};
+use crate::policy::ApprovalPolicy;
+use crate::policy::user::{TerminalUser, UserInterface};
+use crate::prompts::{PromptRegistry, PromptTemplate};
+use crate::protocol::env_reference_regex;
+use crate::secrets::Secrets;
+use crate::skills::SkillPack;
+use crate::tools::cancel::{CancellationToken, run_cancellable};
+use crate::tools::llm::LLMTool;
+use crate::tools::{AsyncTool, Tool, ToolCache, ToolRegistry, ToolResult};
+use crate::validation::command::CommandRules;
+use crate::validation::schema::validate_against_schema;
+use crate::workspace::Workspace;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
 
 /// Basic runtime context for an agent — gives access to tools and config.
+///
+/// Memory, events, secrets and usage live behind shared locks, so logging
+/// and emitting take `&self`: a context can be shared across the threads of
+/// parallel steps, and tools get a `ContextHandle` to log through.
 pub struct Context {
     pub dry_run: bool,
     pub llm_provider: Option<String>,
-    pub tools: HashMap<String, Box<dyn Tool + Send + Sync>>,
-    pub memory: InMemoryLog,
+    /// Models serving the fast, smart and cheap tiers, for `llm()`.
+    pub model_tiers: ModelTiers,
+    pub tools: ToolRegistry,
+    pub async_tools: HashMap<String, Arc<dyn AsyncTool>>,
+    pub memory: SharedMemory,
+    /// Caps memory entries, saving oversized ones to disk; off unless set.
+    pub memory_limit: Option<EntryLimit>,
+    /// Set by `with_run_id`, which confines `memory` to this run's namespace.
+    pub run_id: Option<String>,
     pub allow_shell_commands: bool,
+    pub approval: ApprovalPolicy,
+    pub allowed_commands: Vec<String>,
+    pub skill_packs: Vec<SkillPack>,
+    /// Aborts the whole run when cancelled; each tool call gets a child token.
+    pub cancel: CancellationToken,
+    /// Per-tool overrides of `ToolSpec::timeout`.
+    pub tool_timeouts: HashMap<String, Duration>,
+    /// Used for tools with neither an override nor a spec timeout.
+    pub default_timeout: Option<Duration>,
+    /// Tokens and cost of LLM calls; exceeding its budget cancels the run.
+    pub usage: UsageTracker,
+    /// Lifecycle events for UIs, metrics and notifications.
+    pub events: EventBus,
+    /// Planner and replanner prompts, overridable per model.
+    pub prompts: PromptRegistry,
+    /// Values for `$secret[NAME]` references, masked in memory, tool output
+    /// and LLM prompts.
+    pub secrets: Secrets,
+    /// Environment variables tool inputs may read as `$env[VAR]`; references
+    /// to any other variable are refused.
+    pub env_vars: Vec<String>,
+    /// The directory the run works in; tools built with it stay inside it.
+    pub workspace: Option<Workspace>,
+    /// Serves repeated idempotent, read-only tool calls; off unless set.
+    pub tool_cache: Option<ToolCache>,
+    /// Answers `PlanStep::AskUser` questions; the terminal by default.
+    pub user: Arc<dyn UserInterface>,
+    /// Every tool call of the run, with its output and duration.
+    pub tool_history: ToolHistory,
+    /// `run_command` inputs that fail plan validation.
+    pub command_rules: CommandRules,
+    /// Processes started by `background` steps; `BasicAgent::run` stops them
+    /// when the run ends, and dropping the last context that shares them
+    /// kills any left.
+    pub processes: ProcessManager,
 }
 
 impl Context {
     pub fn new() -> Self {
+        let cancel = CancellationToken::new();
         Self {
-            tools: HashMap::new(),
+            tools: ToolRegistry::new(),
+            async_tools: HashMap::new(),
             dry_run: false,
             llm_provider: None,
-            memory: InMemoryLog::new(),
+            model_tiers: ModelTiers::new(),
+            memory: SharedMemory::default(),
+            memory_limit: None,
+            run_id: None,
             allow_shell_commands: false,
+            approval: ApprovalPolicy::default(),
+            allowed_commands: Vec::new(),
+            skill_packs: Vec::new(),
+            usage: UsageTracker::new().abort_on_budget(cancel.clone()),
+            cancel,
+            tool_timeouts: HashMap::new(),
+            default_timeout: None,
+            events: EventBus::new(),
+            prompts: PromptRegistry::new(),
+            secrets: Secrets::new(),
+            env_vars: Vec::new(),
+            workspace: None,
+            tool_cache: None,
+            user: Arc::new(TerminalUser),
+            tool_history: ToolHistory::new(),
+            command_rules: CommandRules::default(),
+            processes: ProcessManager::new(),
         }
     }
 
+    /// A context for a sub-agent: shares `tools` (sync and async), the LLM,
+    /// policies, timeouts, prompts, secrets, memory limit, workspace, tool
+    /// cache and background processes, but has its own memory, events, tool
+    /// history and usage tracker.
+    /// Cancelling this context's run also cancels the child's.
+    pub fn subset(&self, tools: &[&str]) -> Context {
+        let cancel = self.cancel.child(None);
+        let mut child = Context {
+            dry_run: self.dry_run,
+            llm_provider: self.llm_provider.clone(),
+            model_tiers: self.model_tiers.clone(),
+            allow_shell_commands: self.allow_shell_commands,
+            approval: self.approval,
+            allowed_commands: self.allowed_commands.clone(),
+            usage: UsageTracker::new().abort_on_budget(cancel.clone()),
+            cancel,
+            tool_timeouts: self.tool_timeouts.clone(),
+            default_timeout: self.default_timeout,
+            prompts: self.prompts.clone(),
+            secrets: self.secrets.clone(),
+            env_vars: self.env_vars.clone(),
+            memory_limit: self.memory_limit.clone(),
+            workspace: self.workspace.clone(),
+            tool_cache: self.tool_cache.clone(),
+            user: self.user.clone(),
+            command_rules: self.command_rules.clone(),
+            processes: self.processes.clone(),
+            ..Context::new()
+        };
+        for name in tools {
+            if let Some(tool) = self.tools.get(name) {
+                child.tools.register(tool.clone());
+            }
+            if let Some(tool) = self.get_async_tool(name) {
+                child.async_tools.insert(name.to_string(), tool);
+            }
+        }
+        child
+    }
+
     pub fn register_tool<T: Tool + Send + Sync + 'static>(mut self, tool: T) -> Self {
-        self.tools.insert(tool.name().into(), Box::new(tool));
+        self.tools.register(Arc::new(tool));
+        self
+    }
+
+    /// Registers a non-blocking implementation used by the async agent loop.
+    /// A blocking `Tool` with the same name can coexist for the sync loop.
+    pub fn register_async_tool<T: AsyncTool + 'static>(mut self, tool: T) -> Self {
+        self.async_tools.insert(tool.name().into(), Arc::new(tool));
         self
     }
 
+    /// Selects the LLM as a `provider:model` spec, e.g. `"openai:gpt-4o-mini"`,
+    /// `"anthropic:claude-sonnet-4-5"`, or a bare Ollama model like `"qwen3:8b"`.
     pub fn with_llm(mut self, provider: &str) -> Self {
         self.llm_provider = Some(provider.into());
         self
     }
 
+    /// Routes `LLMTool::for_tier` requests of tools built from `llm()`.
+    pub fn with_model_tiers(mut self, tiers: ModelTiers) -> Self {
+        self.model_tiers = tiers;
+        self
+    }
+
+    /// Builds an `LLMTool` for the selected provider (Ollama `qwen3:8b` if none was set),
+    /// reporting to this context's usage tracker and masking its secrets.
+    pub fn llm(&self) -> Result<LLMTool, String> {
+        let llm = match &self.llm_provider {
+            Some(spec) => LLMTool::from_spec(spec)?,
+            None => LLMTool::default(),
+        };
+        Ok(llm
+            .with_tiers(self.model_tiers.clone())
+            .with_usage(self.usage.clone())
+            .with_secrets(self.secrets.clone()))
+    }
+
+    /// Sets the run's workspace. Only tools built with it (e.g.
+    /// `RunCommandTool::in_workspace`) are confined to it.
+    pub fn with_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Caches results of idempotent tools (see `ToolCache`) for this run.
+    pub fn with_tool_cache(mut self) -> Self {
+        self.tool_cache = Some(ToolCache::new());
+        self
+    }
+
+    /// Makes `value` available to tool inputs as `$secret[name]`.
+    pub fn with_secret(self, name: &str, value: &str) -> Self {
+        self.secrets.insert(name, value);
+        self
+    }
+
+    /// Lets tool inputs reference environment variable `var` as `$env[var]`.
+    pub fn with_env_var(mut self, var: &str) -> Self {
+        if !self.env_vars.iter().any(|allowed| allowed == var) {
+            self.env_vars.push(var.to_string());
+        }
+        self
+    }
+
+    /// Aborts the run once LLM usage exceeds `budget`.
+    pub fn with_budget(self, budget: Budget) -> Self {
+        self.usage.set_budget(budget);
+        self
+    }
+
     pub fn enable_dry_run(mut self) -> Self {
         self.dry_run = true;
         self
@@ -44,12 +241,96 @@ impl Context {
         self
     }
 
+    pub fn with_tool_timeout(mut self, tool: &str, timeout: Duration) -> Self {
+        self.tool_timeouts.insert(tool.into(), timeout);
+        self
+    }
+
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Effective timeout for `name`: the context override, else the tool's
+    /// spec, else the context default.
+    pub fn tool_timeout(&self, name: &str) -> Option<Duration> {
+        self.tool_timeouts
+            .get(name)
+            .copied()
+            .or_else(|| self.get_tool(name).and_then(|tool| tool.spec().timeout))
+            .or(self.default_timeout)
+    }
+
+    /// Builder form of `subscribe`.
+    pub fn on_event<S: EventSubscriber + 'static>(self, subscriber: S) -> Self {
+        self.events.subscribe(subscriber);
+        self
+    }
+
+    pub fn subscribe<S: EventSubscriber + 'static>(&self, subscriber: S) {
+        self.events.subscribe(subscriber);
+    }
+
+    /// A channel receiving every subsequent event, e.g. for a UI thread.
+    pub fn event_channel(&self) -> Receiver<AgentEvent> {
+        self.events.channel()
+    }
+
+    pub fn emit(&self, event: AgentEvent) {
+        self.events.emit(&event);
+    }
+
+    pub fn with_prompts(mut self, prompts: PromptRegistry) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Renders prompt `name` for `model`. An override that fails to render
+    /// (e.g. uses an unknown variable) is logged and the built-in used instead.
+    pub fn render_prompt(&self, name: &str, model: &str, vars: &[(&str, &str)]) -> String {
+        match self.prompts.render(name, model, vars) {
+            Ok(prompt) => prompt,
+            Err(e) => {
+                self.log("prompts", &format!("⚠️ {}; using the built-in template", e));
+                PromptTemplate::builtin(name)
+                    .and_then(|template| template.render(vars).ok())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    pub fn with_approval(mut self, policy: ApprovalPolicy) -> Self {
+        self.approval = policy;
+        self
+    }
+
+    pub fn with_command_rules(mut self, rules: CommandRules) -> Self {
+        self.command_rules = rules;
+        self
+    }
+
+    pub fn with_user_interface(mut self, user: impl UserInterface + 'static) -> Self {
+        self.user = Arc::new(user);
+        self
+    }
+
+    /// Hands the run's cancellation to `token` (e.g. one a job queue holds);
+    /// exceeding the usage budget then cancels `token`.
+    pub fn set_cancel_token(&mut self, token: CancellationToken) {
+        self.usage = self.usage.clone().abort_on_budget(token.clone());
+        self.cancel = token;
+    }
+
     pub fn allows(&self, tool: &str, input: &str) -> bool {
         match tool {
             "run_command" => {
                 if !self.allow_shell_commands {
                     let whitelist = ["cargo", "git", "ls", "echo"];
-                    return whitelist.iter().any(|cmd| input.trim().starts_with(cmd));
+                    return whitelist
+                        .iter()
+                        .copied()
+                        .chain(self.allowed_commands.iter().map(String::as_str))
+                        .any(|cmd| input.trim().starts_with(cmd));
                 }
                 true
             }
@@ -57,21 +338,399 @@ impl Context {
         }
     }
 
+    /// Loads a skill pack: registers its tools, applies its policies, and keeps
+    /// its prompts and few-shot plans available to planners.
+    pub fn load_skill_pack(&mut self, path: &str) -> Result<&SkillPack, String> {
+        let pack = SkillPack::from_file(path)?;
+
+        #[cfg(feature = "shell-tools")]
+        for tool in pack.build_tools() {
+            self.tools.register(Arc::new(tool));
+        }
+        #[cfg(not(feature = "shell-tools"))]
+        if !pack.tools.is_empty() {
+            return Err(format!(
+                "Skill pack '{}' defines command tools, which need the \"shell-tools\" feature",
+                pack.name
+            ));
+        }
+        self.allowed_commands
+            .extend(pack.policies.allowed_commands.iter().cloned());
+        if let Some(policy) = pack.policies.approval {
+            self.approval = policy;
+        }
+
+        self.log("skills", &format!("Loaded skill pack '{}'", pack.name));
+        self.skill_packs.push(pack);
+        Ok(self.skill_packs.last().unwrap())
+    }
+
+    /// Registers every tool advertised by an MCP server, returning their names.
+    pub fn connect_mcp(&mut self, client: McpClient) -> Result<Vec<String>, String> {
+        let client = Arc::new(client);
+        let mut names = Vec::new();
+        for tool in client.tools()? {
+            names.push(tool.info.name.clone());
+            self.tools.register(Arc::new(tool));
+        }
+
+        self.log(
+            "mcp",
+            &format!(
+                "Connected to MCP server '{}' ({} tools)",
+                client.server_name,
+                names.len()
+            ),
+        );
+        Ok(names)
+    }
+
     pub fn get_tool(&self, name: &str) -> Option<&(dyn Tool + Send + Sync)> {
         self.tools.get(name).map(|boxed| boxed.as_ref())
     }
 
-    pub fn memory(&self) -> &InMemoryLog {
-        &self.memory
+    /// Argument schemas declared by registered tools, keyed by tool name.
+    pub fn tool_schemas(&self) -> HashMap<String, Value> {
+        self.tools.schemas()
+    }
+
+    /// Runs a registered tool under the run's cancellation token, enforcing
+    /// `timeout` or else `tool_timeout(name)`. `None` if no such tool.
+    /// Structured `args` that do not match the tool's `input_schema` fail
+    /// the call without running it.
+    /// `$secret[...]` references are resolved just before the call and
+    /// secret values masked in its result. With a `tool_cache`, repeated
+    /// idempotent calls return the cached result instead of running.
+    pub fn invoke_tool(
+        &self,
+        name: &str,
+        input: &str,
+        args: Option<&Value>,
+        timeout: Option<Duration>,
+    ) -> Option<ToolResult> {
+        let tool = self.tools.get(name)?.clone();
+        let timeout = timeout.or_else(|| self.tool_timeout(name));
+        let token = self.cancel.child(timeout);
+        if token.is_cancelled() {
+            return Some(ToolResult::cancelled());
+        }
+        if let Err(e) = check_args(tool.as_ref(), args) {
+            return Some(ToolResult::failure(&e));
+        }
+
+        let cache = self.tool_cache.as_ref().map(|cache| {
+            let preview = match args {
+                Some(args) => tool.preview_args(args),
+                None => tool.preview(input),
+            };
+            (cache, tool.spec().idempotent && preview.read_only)
+        });
+        if let Some((cache, true)) = cache
+            && let Some(result) = cache.get(name, input, args)
+        {
+            self.log(
+                "cache",
+                &format!("♻️ Reused cached result of {}: {}", name, input),
+            );
+            return Some(result);
+        }
+
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = name,
+            success = tracing::field::Empty,
+            timed_out = tracing::field::Empty
+        );
+        let resolved = self
+            .resolve_env(input, args)
+            .and_then(|(input, args)| self.resolve_secrets(&input, args.as_ref()));
+        let (resolved_input, resolved_args) = match resolved {
+            Ok(resolved) => resolved,
+            Err(e) => return Some(ToolResult::failure(&e)),
+        };
+        let handle = self.handle_with(token.clone());
+        let worker_span = span.clone();
+        let result = span.in_scope(|| {
+            run_cancellable(
+                // Spans opened by the tool (e.g. LLM calls) nest under this call.
+                move || {
+                    worker_span.in_scope(|| match &resolved_args {
+                        Some(args) => tool.execute_args_with(args, &handle),
+                        None => tool.execute_with(&resolved_input, &handle),
+                    })
+                },
+                &token,
+                timeout,
+            )
+        });
+        span.record("success", result.success);
+        span.record("timed_out", result.timed_out);
+        let result = self.secrets.redact_result(result);
+        match cache {
+            Some((cache, true)) => cache.insert(name, input, args, &result),
+            Some((cache, false)) => cache.clear(),
+            None => {}
+        }
+        Some(result)
+    }
+
+    /// Starts `name` as a long-running process (see
+    /// `Tool::background_command`) tracked in `processes` as `id`, and
+    /// returns at once with its `ProcessStatus` as the result's data.
+    /// References are resolved and arguments checked as in `invoke_tool`.
+    /// `None` if no such tool.
+    pub fn start_background(
+        &self,
+        name: &str,
+        id: &str,
+        input: &str,
+        args: Option<&Value>,
+    ) -> Option<ToolResult> {
+        let tool = self.tools.get(name)?.clone();
+        if self.cancel.is_cancelled() {
+            return Some(ToolResult::cancelled());
+        }
+        let label = match args {
+            Some(args) => args
+                .get("command")
+                .and_then(Value::as_str)
+                .map_or_else(|| args.to_string(), String::from),
+            None => input.to_string(),
+        };
+        let started = check_args(tool.as_ref(), args)
+            .and_then(|_| self.resolve_env(input, args))
+            .and_then(|(input, args)| self.resolve_secrets(&input, args.as_ref()))
+            .and_then(|(input, args)| tool.background_command(&input, args.as_ref()))
+            .and_then(|command| self.processes.start(id, &label, command));
+        let result = match started {
+            Ok(status) => {
+                self.log(
+                    "processes",
+                    &format!("🚀 Started '{}' in the background: {}", id, label),
+                );
+                ToolResult::structured(serde_json::to_value(&status).unwrap_or_default())
+            }
+            Err(e) => ToolResult::failure(&e),
+        };
+        Some(self.secrets.redact_result(result))
+    }
+
+    /// Stops every background process still running, e.g. as a run ends.
+    pub fn stop_processes(&self) {
+        for status in self.processes.stop_all() {
+            self.log(
+                "processes",
+                &format!(
+                    "⏹️ Stopped background process '{}': {}",
+                    status.id, status.command
+                ),
+            );
+        }
+    }
+
+    /// Cleans up as a run ends, finished or cancelled: stops background
+    /// processes and flushes memory to disk.
+    pub fn wind_down(&self) {
+        self.stop_processes();
+        if let Err(e) = self.memory.write().flush() {
+            tracing::warn!("Failed to flush memory: {}", e);
+        }
+    }
+
+    /// `input` and `args` with `$env[VAR]` references replaced by the values of
+    /// allowlisted environment variables.
+    pub fn resolve_env(
+        &self,
+        input: &str,
+        args: Option<&Value>,
+    ) -> Result<(String, Option<Value>), String> {
+        let input = self.expand_env(input)?;
+        let args = args.map(|args| self.expand_env_args(args)).transpose()?;
+        Ok((input, args))
+    }
+
+    fn expand_env(&self, text: &str) -> Result<String, String> {
+        if !text.contains("$env[") {
+            return Ok(text.to_string());
+        }
+        let mut error = None;
+        let expanded = env_reference_regex().replace_all(text, |caps: &regex::Captures| {
+            let var = &caps[1];
+            if !self.env_vars.iter().any(|allowed| allowed == var) {
+                error.get_or_insert_with(|| {
+                    format!(
+                        "Environment variable '{}' is not allowed (allowed: {})",
+                        var,
+                        if self.env_vars.is_empty() {
+                            "none".to_string()
+                        } else {
+                            self.env_vars.join(", ")
+                        }
+                    )
+                });
+                return String::new();
+            }
+            std::env::var(var).unwrap_or_else(|_| {
+                error.get_or_insert_with(|| format!("Environment variable '{}' is not set", var));
+                String::new()
+            })
+        });
+        match error {
+            Some(error) => Err(error),
+            None => Ok(expanded.into_owned()),
+        }
     }
 
-    pub fn memory_mut(&mut self) -> &mut InMemoryLog {
-        &mut self.memory
+    fn expand_env_args(&self, args: &Value) -> Result<Value, String> {
+        Ok(match args {
+            Value::String(text) => Value::String(self.expand_env(text)?),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.expand_env_args(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, item)| Ok((key.clone(), self.expand_env_args(item)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            other => other.clone(),
+        })
     }
 
-    pub fn log(&mut self, label: &str, content: &str) {
-        self.memory.log(label, content);
+    /// `input` and `args` with `$secret[...]` references replaced by values.
+    pub fn resolve_secrets(
+        &self,
+        input: &str,
+        args: Option<&Value>,
+    ) -> Result<(String, Option<Value>), String> {
+        let input = self.secrets.resolve(input)?;
+        let args = args
+            .map(|args| self.secrets.resolve_args(args))
+            .transpose()?;
+        Ok((input, args))
+    }
+
+    pub fn get_async_tool(&self, name: &str) -> Option<Arc<dyn AsyncTool>> {
+        self.async_tools.get(name).cloned()
+    }
+
+    /// Summarizes memory entries over the limit and saves their full text.
+    pub fn with_memory_limit(mut self, limit: EntryLimit) -> Self {
+        self.memory_limit = Some(limit);
+        self
     }
+
+    /// Swaps the memory backend, e.g. for a `SemanticMemory` with embeddings.
+    /// Keeps the run's scope if `with_run_id` came first.
+    pub fn with_memory<M: Memory + Send + Sync + 'static>(mut self, memory: M) -> Self {
+        self.memory = match &self.run_id {
+            Some(run_id) => SharedMemory::new(ScopedMemory::new(SharedMemory::new(memory), run_id)),
+            None => SharedMemory::new(memory),
+        };
+        self
+    }
+
+    /// Tags the run with `run_id` and confines memory to its namespace, so
+    /// runs sharing a backend (e.g. a persistent one) stay apart. Entries
+    /// land under `run_id/label` in the backend.
+    pub fn with_run_id(mut self, run_id: &str) -> Self {
+        let backend = std::mem::take(&mut self.memory);
+        self.memory = SharedMemory::new(ScopedMemory::new(backend, run_id));
+        self.run_id = Some(run_id.to_string());
+        self
+    }
+
+    pub fn memory(&self) -> RwLockReadGuard<'_, Box<dyn Memory + Send + Sync>> {
+        self.memory.read()
+    }
+
+    pub fn memory_mut(&self) -> RwLockWriteGuard<'_, Box<dyn Memory + Send + Sync>> {
+        self.memory.write()
+    }
+
+    /// A handle for logging and emitting from other threads, cancelled with
+    /// the run.
+    pub fn handle(&self) -> ContextHandle {
+        self.handle_with(self.cancel.clone())
+    }
+
+    fn handle_with(&self, cancel: CancellationToken) -> ContextHandle {
+        ContextHandle::new(
+            self.memory.clone(),
+            self.memory_limit.clone(),
+            self.events.clone(),
+            self.secrets.clone(),
+            self.processes.clone(),
+            cancel,
+        )
+    }
+
+    /// Records `content` in memory, with secret values masked and
+    /// `memory_limit` applied.
+    pub fn log(&self, label: &str, content: &str) {
+        let content = self.prepare_entry(label, content);
+        self.memory.log(label, &content);
+    }
+
+    /// Like `log`, into `namespace` (see `Memory::log_in`).
+    pub fn log_in(&self, namespace: &str, label: &str, content: &str) {
+        let content = self.prepare_entry(label, content);
+        self.memory.log_in(namespace, label, &content);
+    }
+
+    fn prepare_entry(&self, label: &str, content: &str) -> String {
+        let mut content = self.secrets.redact(content);
+        if let Some(limit) = &self.memory_limit {
+            content = limit.apply(label, &content);
+        }
+        tracing::trace!(label, "{}", content);
+        content
+    }
+
+    /// The agent-side state `restore` rolls back to: how far memory got.
+    /// Tool calls, events and usage are not rolled back; they happened.
+    pub fn snapshot(&self) -> ContextSnapshot {
+        ContextSnapshot {
+            memory_len: self.memory.read().read_all().len(),
+        }
+    }
+
+    /// Drops the memory entries logged since `snapshot`, e.g. those of a
+    /// speculative branch or a rejected plan.
+    pub fn restore(&self, snapshot: &ContextSnapshot) -> Result<(), String> {
+        self.memory.write().truncate(snapshot.memory_len)
+    }
+
+    /// Adds `call` to the tool history, with secret values masked.
+    pub fn record_tool_call(&self, mut call: ToolCallRecord) {
+        call.input = self.secrets.redact(&call.input);
+        call.output = self.secrets.redact(&call.output);
+        tracing::trace!(tool = %call.tool, success = call.success, "{}", call.output);
+        self.tool_history.record(call);
+    }
+}
+
+/// Structured `args` that do not match `tool`'s `input_schema` are refused.
+fn check_args(tool: &(dyn Tool + Send + Sync), args: Option<&Value>) -> Result<(), String> {
+    if let (Some(args), Some(schema)) = (args, tool.spec().input_schema) {
+        let errors = validate_against_schema(args, &schema);
+        if !errors.is_empty() {
+            return Err(format!(
+                "Invalid arguments for {}: {}",
+                tool.name(),
+                errors.join("; ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A point `Context::restore` can roll back to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContextSnapshot {
+    pub memory_len: usize,
 }
 
 impl Default for Context {
@@ -79,3 +738,10 @@ impl Default for Context {
         Self::new()
     }
 }
+
+// Parallel steps share `&Context` across threads.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Context>();
+    assert_send_sync::<ContextHandle>();
+};
@@ -0,0 +1,323 @@
+// src/context/processes.rs
+
+use crate::tools::CancellationToken;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Output lines kept per process.
+const OUTPUT_LINES: usize = 200;
+/// Output lines included in a `ProcessStatus`.
+const STATUS_LINES: usize = 20;
+/// How long `stop` waits after asking a process to exit before killing it.
+const STOP_GRACE: Duration = Duration::from_secs(3);
+/// How often `wait_until_ready` re-checks a process.
+const READY_POLL: Duration = Duration::from_millis(100);
+
+/// Long-running processes started by `background` plan steps (dev servers,
+/// watchers), so later steps can check on them and the run can stop them.
+///
+/// Each process keeps its last lines of output. On Unix it runs in its own
+/// process group, so stopping it also stops whatever it spawned (`npm` and
+/// the `node` under it). Clones share the processes; once the last clone is
+/// dropped, any still running are killed.
+#[derive(Clone, Debug, Default)]
+pub struct ProcessManager {
+    inner: Arc<Processes>,
+}
+
+#[derive(Debug, Default)]
+struct Processes(Mutex<BTreeMap<String, Process>>);
+
+#[derive(Debug)]
+struct Process {
+    command: String,
+    child: Child,
+    started: Instant,
+    output: Arc<Mutex<VecDeque<String>>>,
+}
+
+/// A snapshot of a background process.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProcessStatus {
+    pub id: String,
+    pub command: String,
+    pub pid: u32,
+    pub running: bool,
+    /// Set once it has exited; `None` while running or if killed by a signal.
+    pub exit_code: Option<i32>,
+    pub uptime_ms: u64,
+    /// Its last lines of output, stdout and stderr interleaved.
+    pub recent_output: Vec<String>,
+}
+
+/// What `wait_until_ready` waits for; a check with neither set only needs
+/// the process to still be running.
+#[derive(Clone, Debug, Default)]
+pub struct ReadyCheck {
+    /// Ready once something accepts connections on this local port.
+    pub port: Option<u16>,
+    /// Ready once a recent output line matches.
+    pub pattern: Option<Regex>,
+}
+
+impl ReadyCheck {
+    fn is_ready(&self, output: &VecDeque<String>) -> bool {
+        let port_open = self.port.is_none_or(|port| {
+            let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+            TcpStream::connect_timeout(&address, READY_POLL).is_ok()
+        });
+        let matched = self
+            .pattern
+            .as_ref()
+            .is_none_or(|pattern| output.iter().any(|line| pattern.is_match(line)));
+        port_open && matched
+    }
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns `command` as process `id`, described by `label`. Fails if a
+    /// process by that name is still running; an exited one is replaced.
+    pub fn start(
+        &self,
+        id: &str,
+        label: &str,
+        mut command: Command,
+    ) -> Result<ProcessStatus, String> {
+        let mut processes = self.lock();
+        if let Some(process) = processes.get_mut(id)
+            && process.child.try_wait().ok().flatten().is_none()
+        {
+            return Err(format!(
+                "Background process '{}' is already running (pid {})",
+                id,
+                process.child.id()
+            ));
+        }
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to start '{}': {}", label, e))?;
+        let output = Arc::new(Mutex::new(VecDeque::new()));
+        keep_lines(child.stdout.take(), output.clone());
+        keep_lines(child.stderr.take(), output.clone());
+        let mut process = Process {
+            command: label.to_string(),
+            child,
+            started: Instant::now(),
+            output,
+        };
+        let status = process.status(id);
+        processes.insert(id.to_string(), process);
+        Ok(status)
+    }
+
+    pub fn status(&self, id: &str) -> Option<ProcessStatus> {
+        self.lock().get_mut(id).map(|process| process.status(id))
+    }
+
+    /// Every process started, running or not, by name.
+    pub fn list(&self) -> Vec<ProcessStatus> {
+        self.lock()
+            .iter_mut()
+            .map(|(id, process)| process.status(id))
+            .collect()
+    }
+
+    /// Up to the last `lines` lines of `id`'s output.
+    pub fn logs(&self, id: &str, lines: usize) -> Option<Vec<String>> {
+        self.lock().get(id).map(|process| process.tail(lines))
+    }
+
+    /// Waits until `check` passes for `id`. Fails if the process exits
+    /// first, `timeout` passes or `cancel` fires.
+    pub fn wait_until_ready(
+        &self,
+        id: &str,
+        check: &ReadyCheck,
+        timeout: Duration,
+        cancel: &CancellationToken,
+    ) -> Result<ProcessStatus, String> {
+        let started = Instant::now();
+        loop {
+            let (status, output) = {
+                let mut processes = self.lock();
+                let process = processes
+                    .get_mut(id)
+                    .ok_or_else(|| format!("No background process '{}'", id))?;
+                let output = process
+                    .output
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone();
+                (process.status(id), output)
+            };
+            if !status.running {
+                return Err(format!(
+                    "Background process '{}' exited (code {}) before it was ready:\n{}",
+                    id,
+                    status
+                        .exit_code
+                        .map_or("none".into(), |code| code.to_string()),
+                    status.recent_output.join("\n")
+                ));
+            }
+            if check.is_ready(&output) {
+                return Ok(status);
+            }
+            if started.elapsed() >= timeout {
+                return Err(format!(
+                    "Background process '{}' was not ready after {:.1}s:\n{}",
+                    id,
+                    timeout.as_secs_f64(),
+                    status.recent_output.join("\n")
+                ));
+            }
+            if cancel.is_cancelled() {
+                return Err("Cancelled".into());
+            }
+            thread::sleep(READY_POLL);
+        }
+    }
+
+    /// Stops `id` and everything it spawned: asks nicely, then kills it if
+    /// it has not exited within a few seconds.
+    pub fn stop(&self, id: &str) -> Result<ProcessStatus, String> {
+        let mut processes = self.lock();
+        let process = processes
+            .get_mut(id)
+            .ok_or_else(|| format!("No background process '{}'", id))?;
+        process.stop(STOP_GRACE);
+        Ok(process.status(id))
+    }
+
+    /// Stops every process still running, returning their final status.
+    pub fn stop_all(&self) -> Vec<ProcessStatus> {
+        self.inner.stop_all(STOP_GRACE)
+    }
+
+    /// Kills every process still running without a grace period, for when
+    /// there is no time to wait.
+    pub fn kill_all(&self) -> Vec<ProcessStatus> {
+        self.inner.stop_all(Duration::ZERO)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Process>> {
+        self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Processes {
+    fn stop_all(&self, grace: Duration) -> Vec<ProcessStatus> {
+        let mut processes = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        processes
+            .iter_mut()
+            .filter_map(|(id, process)| process.stop(grace).then(|| process.status(id)))
+            .collect()
+    }
+}
+
+impl Drop for Processes {
+    fn drop(&mut self) {
+        self.stop_all(Duration::ZERO);
+    }
+}
+
+impl Process {
+    fn status(&mut self, id: &str) -> ProcessStatus {
+        let exit = self.child.try_wait().ok().flatten();
+        ProcessStatus {
+            id: id.to_string(),
+            command: self.command.clone(),
+            pid: self.child.id(),
+            running: exit.is_none(),
+            exit_code: exit.and_then(|status| status.code()),
+            uptime_ms: self.started.elapsed().as_millis() as u64,
+            recent_output: self.tail(STATUS_LINES),
+        }
+    }
+
+    fn tail(&self, lines: usize) -> Vec<String> {
+        let output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        output
+            .iter()
+            .skip(output.len().saturating_sub(lines))
+            .cloned()
+            .collect()
+    }
+
+    /// Stops the process if it is running, waiting up to `grace` for it to
+    /// exit before killing it. Whether it was running.
+    fn stop(&mut self, grace: Duration) -> bool {
+        if self.child.try_wait().ok().flatten().is_some() {
+            return false;
+        }
+        let pid = self.child.id();
+        signal_group(pid, "-TERM");
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if self.child.try_wait().ok().flatten().is_some() {
+                // Its children may outlive it.
+                signal_group(pid, "-KILL");
+                return true;
+            }
+            thread::sleep(READY_POLL);
+        }
+        signal_group(pid, "-KILL");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        true
+    }
+}
+
+/// Sends `signal` to the process group led by `pid`.
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: &str) {
+    let _ = Command::new("kill")
+        .args([signal, "--", &format!("-{}", pid)])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Without process groups, `stop` falls back to killing the process itself.
+#[cfg(not(unix))]
+fn signal_group(_pid: u32, _signal: &str) {}
+
+/// Drains a child pipe on a background thread, keeping its last
+/// `OUTPUT_LINES` lines in `output`.
+fn keep_lines<R: Read + Send + 'static>(pipe: Option<R>, output: Arc<Mutex<VecDeque<String>>>) {
+    let Some(pipe) = pipe else {
+        return;
+    };
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+            let text = String::from_utf8_lossy(&line)
+                .trim_end_matches(['\n', '\r'])
+                .to_string();
+            let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+            if output.len() == OUTPUT_LINES {
+                output.pop_front();
+            }
+            output.push_back(text);
+            line.clear();
+        }
+    });
+}
@@ -0,0 +1,147 @@
+// src/events/mod.rs
+
+pub mod sse;
+
+use crate::protocol::StepResult;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// Run lifecycle notifications, emitted through `Context::emit`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// A planner (or replanner, when `replan` is set) produced a plan.
+    PlanGenerated {
+        goal: String,
+        /// The plan in the planner's JSON step format.
+        plan: Value,
+        replan: bool,
+    },
+    /// A critic approved or rejected a proposed plan (see `ReviewerAgent`).
+    PlanReviewed {
+        goal: String,
+        round: usize,
+        critic: String,
+        approved: bool,
+        concerns: Vec<String>,
+    },
+    /// A tool call is about to run (or be simulated in dry-run).
+    StepStarted {
+        id: Option<String>,
+        tool: String,
+        input: String,
+    },
+    /// A line a running tool call printed, as it arrives; `stream` is
+    /// `stdout` or `stderr`. Precedes the call's `StepFinished`.
+    StepOutput {
+        tool: String,
+        stream: String,
+        line: String,
+    },
+    StepFinished {
+        step: StepResult,
+    },
+    /// A tool call failed or named an unknown tool; follows its `StepFinished`.
+    ToolFailed {
+        id: Option<String>,
+        tool: String,
+        error: String,
+    },
+    ReplanTriggered {
+        goal: String,
+        reason: String,
+    },
+    /// Execution (and verification, if configured) is over.
+    RunCompleted {
+        goal: String,
+        success: bool,
+        goal_achieved: bool,
+        errors: Vec<String>,
+    },
+}
+
+/// Receives every event emitted on a context. Closures `Fn(&AgentEvent)`
+/// implement this, so `context.subscribe(|event| ...)` works directly.
+pub trait EventSubscriber: Send + Sync {
+    fn on_event(&self, event: &AgentEvent);
+}
+
+impl<F: Fn(&AgentEvent) + Send + Sync> EventSubscriber for F {
+    fn on_event(&self, event: &AgentEvent) {
+        self(event)
+    }
+}
+
+/// Forwards events into a channel, for consumers on another thread (UIs, metrics).
+struct ChannelSubscriber {
+    sender: Mutex<Sender<AgentEvent>>,
+}
+
+impl EventSubscriber for ChannelSubscriber {
+    fn on_event(&self, event: &AgentEvent) {
+        // A dropped receiver just means nobody is listening any more.
+        let _ = self.sender.lock().unwrap().send(event.clone());
+    }
+}
+
+/// Fans events out to subscribers in registration order. Clones share the
+/// subscriber list, so a subscriber added later hears events emitted through
+/// any clone (e.g. a `ContextHandle` given to a tool).
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<RwLock<Vec<Arc<dyn EventSubscriber>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe<S: EventSubscriber + 'static>(&self, subscriber: S) {
+        self.subscribers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::new(subscriber));
+    }
+
+    /// A receiver that gets a copy of every subsequent event.
+    pub fn channel(&self) -> Receiver<AgentEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribe(ChannelSubscriber {
+            sender: Mutex::new(sender),
+        });
+        receiver
+    }
+
+    pub fn emit(&self, event: &AgentEvent) {
+        // Snapshot first, so a subscriber may subscribe others without deadlocking.
+        let subscribers = self.subscribers().clone();
+        for subscriber in &subscribers {
+            subscriber.on_event(event);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscribers().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers().is_empty()
+    }
+
+    fn subscribers(&self) -> std::sync::RwLockReadGuard<'_, Vec<Arc<dyn EventSubscriber>>> {
+        self.subscribers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.len())
+            .finish()
+    }
+}
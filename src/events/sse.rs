@@ -0,0 +1,51 @@
+// src/events/sse.rs
+
+use crate::events::{AgentEvent, EventSubscriber};
+use serde_json::Value;
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// One Server-Sent Events frame carrying `data` as single-line JSON.
+/// `id` lets a reconnecting client say where it left off.
+pub fn frame(event: &str, id: Option<usize>, data: &Value) -> String {
+    let mut frame = format!("event: {}\n", event);
+    if let Some(id) = id {
+        frame.push_str(&format!("id: {}\n", id));
+    }
+    frame.push_str(&format!("data: {}\n\n", data));
+    frame
+}
+
+/// The SSE event name for `event`: its `type`, e.g. `step_started`.
+pub fn event_name(event: &Value) -> &str {
+    event["type"].as_str().unwrap_or("agent_event")
+}
+
+/// Writes every event to `writer` as an SSE frame named after its type and
+/// numbered from 0, e.g. to stream a run to a browser's `EventSource`. The
+/// caller sends the HTTP headers; write errors (a gone client) are ignored.
+pub struct SseSubscriber {
+    writer: Mutex<Box<dyn Write + Send>>,
+    next_id: AtomicUsize,
+}
+
+impl SseSubscriber {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+            next_id: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl EventSubscriber for SseSubscriber {
+    fn on_event(&self, event: &AgentEvent) {
+        let data = serde_json::to_value(event).unwrap_or_default();
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer
+            .write_all(frame(event_name(&data), Some(id), &data).as_bytes())
+            .and_then(|_| writer.flush());
+    }
+}
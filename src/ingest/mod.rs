@@ -0,0 +1,130 @@
+// src/ingest/mod.rs
+
+use crate::memory::vector::{Embedder, VectorStore};
+use std::path::Path;
+
+const DOC_EXTENSIONS: [&str; 5] = ["md", "markdown", "txt", "rst", "adoc"];
+const SKIPPED_DIRS: [&str; 3] = ["target", "node_modules", "vendor"];
+
+/// Project documentation indexed for retrieval-augmented planning.
+pub struct KnowledgeBase {
+    store: VectorStore,
+    max_chunk_chars: usize,
+}
+
+impl KnowledgeBase {
+    pub fn new(embedder: Box<dyn Embedder>) -> Self {
+        Self {
+            store: VectorStore::new(embedder),
+            max_chunk_chars: 800,
+        }
+    }
+
+    pub fn with_max_chunk_chars(mut self, max: usize) -> Self {
+        self.max_chunk_chars = max.max(1);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+
+    /// Indexes a single document; returns the number of chunks added.
+    pub fn ingest_file(&mut self, path: &Path) -> Result<usize, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let source = path.display().to_string();
+        let chunks = chunk_text(&text, self.max_chunk_chars);
+        for chunk in &chunks {
+            self.store.add(&source, chunk)?;
+        }
+        Ok(chunks.len())
+    }
+
+    /// Recursively indexes docs (READMEs, ADRs, notes) under `root`, skipping
+    /// hidden and build directories. Returns the number of chunks added.
+    pub fn ingest_dir(&mut self, root: &Path) -> Result<usize, String> {
+        let mut total = 0;
+        let entries = std::fs::read_dir(root)
+            .map_err(|e| format!("Failed to read {}: {}", root.display(), e))?;
+
+        let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        paths.sort();
+
+        for path in paths {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if path.is_dir() {
+                if !SKIPPED_DIRS.contains(&name.as_str()) {
+                    total += self.ingest_dir(&path)?;
+                }
+            } else if is_document(&path) {
+                total += self.ingest_file(&path)?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Renders the top `k` chunks for `query` as a prompt section body.
+    pub fn retrieve(&self, query: &str, k: usize) -> Result<String, String> {
+        let hits = self.store.search(query, k)?;
+        Ok(hits
+            .iter()
+            .map(|(_, entry)| format!("[{}]\n{}", entry.source, entry.text))
+            .collect::<Vec<_>>()
+            .join("\n\n"))
+    }
+}
+
+fn is_document(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| DOC_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Splits a document into paragraph-aligned chunks of at most ~`max_chars`,
+/// prefixing each chunk with its nearest markdown heading for context.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut heading = String::new();
+    let mut current = String::new();
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if paragraph.starts_with('#') {
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            heading = paragraph.lines().next().unwrap_or_default().to_string();
+        }
+
+        if !current.is_empty() && current.len() + paragraph.len() > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+
+        if current.is_empty() && !heading.is_empty() && !paragraph.starts_with('#') {
+            current.push_str(&heading);
+            current.push_str("\n\n");
+        }
+        if !current.is_empty() && !current.ends_with("\n\n") {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
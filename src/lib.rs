@@ -1,7 +1,72 @@
+//! Plans, the task model and plan validation are always built; everything
+//! that runs them (agents, context, tools, LLM clients) needs the default
+//! `runtime` feature. Without it the crate compiles for
+//! `wasm32-unknown-unknown`, e.g. for a browser-based plan editor.
+
+#[cfg(feature = "runtime")]
 pub mod agent;
+#[cfg(feature = "runtime")]
+pub mod cli;
+#[cfg(feature = "runtime")]
+pub mod config;
+#[cfg(feature = "runtime")]
 pub mod context;
+#[cfg(feature = "runtime")]
+pub mod events;
+#[cfg(feature = "runtime")]
+pub mod ingest;
+#[cfg(feature = "runtime")]
+pub mod llm;
+#[cfg(feature = "runtime")]
+pub mod mcp;
+#[cfg(feature = "runtime")]
 pub mod memory;
 pub mod model;
+#[cfg(feature = "runtime")]
+pub mod policy;
+#[cfg(feature = "runtime")]
+pub mod probes;
+#[cfg(feature = "runtime")]
+pub mod prompts;
 pub mod protocol;
+#[cfg(feature = "runtime")]
+pub mod queue;
+#[cfg(feature = "runtime")]
+pub mod report;
+#[cfg(feature = "runtime")]
+pub mod risk;
+#[cfg(feature = "runtime")]
+pub mod scheduler;
+#[cfg(feature = "runtime")]
+pub mod secrets;
+#[cfg(feature = "runtime")]
+pub mod server;
+#[cfg(feature = "runtime")]
+pub mod skills;
+#[cfg(feature = "runtime")]
+pub mod telemetry;
+#[cfg(feature = "runtime")]
+pub mod testing;
+#[cfg(feature = "runtime")]
 pub mod tools;
+#[cfg(feature = "runtime")]
+pub mod tui;
 pub mod validation;
+#[cfg(feature = "runtime")]
+pub mod workflows;
+#[cfg(feature = "runtime")]
+pub mod workspace;
+
+// The canonical types, so embedders need not know the module layout.
+#[cfg(feature = "runtime")]
+pub use agent::run_loop::run_goal;
+#[cfg(feature = "runtime")]
+pub use agent::{Agent, AgentBuilder, BasicAgent};
+#[cfg(feature = "runtime")]
+pub use config::RuntimeConfig;
+#[cfg(feature = "runtime")]
+pub use context::Context;
+pub use model::{Model, TaskModel};
+pub use protocol::{ExecutionResult, Plan, PlanStep};
+#[cfg(feature = "runtime")]
+pub use report::{RunReport, RunSummary};
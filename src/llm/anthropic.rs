@@ -0,0 +1,157 @@
+// src/llm/anthropic.rs
+
+use crate::llm::{
+    ChatMessage, FunctionCall, FunctionDef, LlmProvider, LlmRequest, Role, TokenUsage, api_error,
+};
+use serde_json::{Value, json};
+
+/// Anthropic's Messages API.
+#[derive(Clone)]
+pub struct AnthropicProvider {
+    pub model: String,
+    pub base_url: String,
+    pub max_tokens: u32,
+    api_key: String,
+}
+
+impl AnthropicProvider {
+    pub fn new(model: &str, api_key: &str) -> Self {
+        Self {
+            model: model.into(),
+            base_url: "https://api.anthropic.com/v1".into(),
+            max_tokens: 4096,
+            api_key: api_key.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for AnthropicProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnthropicProvider")
+            .field("model", &self.model)
+            .field("base_url", &self.base_url)
+            .field("max_tokens", &self.max_tokens)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn request(&self, prompt: &str) -> LlmRequest {
+        self.chat_request(&[ChatMessage::new(Role::User, prompt)])
+    }
+
+    /// System messages go in the top-level `system` field.
+    fn chat_request(&self, messages: &[ChatMessage]) -> LlmRequest {
+        let system = messages
+            .iter()
+            .filter(|message| message.role == Role::System)
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let turns: Vec<&ChatMessage> = messages
+            .iter()
+            .filter(|message| message.role != Role::System)
+            .collect();
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "messages": turns,
+        });
+        if !system.is_empty() {
+            body["system"] = json!(system);
+        }
+        LlmRequest {
+            url: format!("{}/messages", self.base_url),
+            headers: vec![
+                ("x-api-key".into(), self.api_key.clone()),
+                ("anthropic-version".into(), "2023-06-01".into()),
+            ],
+            body,
+        }
+    }
+
+    /// No JSON mode: the prompt asks for JSON.
+    fn structured_chat_request(
+        &self,
+        messages: &[ChatMessage],
+        _schema: Option<&Value>,
+    ) -> LlmRequest {
+        self.chat_request(messages)
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<String, String> {
+        if let Some(error) = api_error(json) {
+            return Err(format!("Anthropic error: {}", error));
+        }
+        let blocks = json
+            .get("content")
+            .and_then(|v| v.as_array())
+            .ok_or("LLM response missing 'content' field")?;
+
+        Ok(blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("")
+            .trim()
+            .to_string())
+    }
+
+    /// Messages API `tools`, with `tool_choice: {"type": "any"}`.
+    fn function_request(&self, prompt: &str, functions: &[FunctionDef]) -> Option<LlmRequest> {
+        let mut request = self.request(prompt);
+        request.body["tools"] = functions
+            .iter()
+            .map(|function| {
+                json!({
+                    "name": function.name,
+                    "description": function.description,
+                    "input_schema": function.parameters,
+                })
+            })
+            .collect();
+        request.body["tool_choice"] = json!({ "type": "any" });
+        Some(request)
+    }
+
+    fn parse_function_calls(&self, json: &Value) -> Result<Vec<FunctionCall>, String> {
+        if let Some(error) = api_error(json) {
+            return Err(format!("Anthropic error: {}", error));
+        }
+        let blocks = json
+            .get("content")
+            .and_then(Value::as_array)
+            .ok_or("LLM response missing 'content' field")?;
+        blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some("tool_use"))
+            .map(|block| {
+                let name = block
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or("tool_use block missing 'name'")?;
+                Ok(FunctionCall::new(
+                    name,
+                    block.get("input").unwrap_or(&Value::Null),
+                ))
+            })
+            .collect()
+    }
+
+    fn parse_usage(&self, json: &Value) -> Option<TokenUsage> {
+        let usage = json.get("usage")?;
+        Some(TokenUsage {
+            prompt_tokens: usage.get("input_tokens")?.as_u64()?,
+            completion_tokens: usage.get("output_tokens")?.as_u64()?,
+        })
+    }
+}
@@ -0,0 +1,137 @@
+// src/llm/chat.rs
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: &str) -> Self {
+        Self {
+            role,
+            content: content.into(),
+        }
+    }
+}
+
+/// A multi-turn exchange with a model, sent with `LLMTool::chat`.
+///
+/// System messages are always sent; with `with_max_messages`, only the
+/// latest other messages are, so long exchanges stay within the context
+/// window.
+#[derive(Clone, Debug, Default)]
+pub struct Conversation {
+    messages: Vec<ChatMessage>,
+    max_messages: Option<usize>,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_system(mut self, content: &str) -> Self {
+        self.push(Role::System, content);
+        self
+    }
+
+    pub fn with_user(mut self, content: &str) -> Self {
+        self.push(Role::User, content);
+        self
+    }
+
+    pub fn with_assistant(mut self, content: &str) -> Self {
+        self.push(Role::Assistant, content);
+        self
+    }
+
+    /// Sends at most `n` non-system messages, dropping the oldest.
+    pub fn with_max_messages(mut self, n: usize) -> Self {
+        self.max_messages = Some(n);
+        self
+    }
+
+    pub fn push(&mut self, role: Role, content: &str) {
+        self.messages.push(ChatMessage::new(role, content));
+    }
+
+    /// Every message, including those truncation leaves out.
+    pub fn history(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// The messages to send: every system message, then the latest others
+    /// (in order) within `max_messages`. A leading assistant message left by
+    /// truncation is dropped too, so the exchange still opens with the user.
+    pub fn messages(&self) -> Vec<ChatMessage> {
+        let turns: Vec<&ChatMessage> = self
+            .messages
+            .iter()
+            .filter(|message| message.role != Role::System)
+            .collect();
+        let mut skip = self
+            .max_messages
+            .map_or(0, |max| turns.len().saturating_sub(max));
+        if skip > 0 && turns.get(skip).is_some_and(|m| m.role == Role::Assistant) {
+            skip += 1;
+        }
+        self.messages
+            .iter()
+            .filter(|message| message.role == Role::System)
+            .chain(turns.into_iter().skip(skip))
+            .cloned()
+            .collect()
+    }
+}
+
+/// `messages` as one prompt, for backends without a chat API: a single user
+/// message is sent as is, anything else as a role-labelled transcript.
+pub fn transcript(messages: &[ChatMessage]) -> String {
+    if let [message] = messages
+        && message.role == Role::User
+    {
+        return message.content.clone();
+    }
+    let mut text = messages
+        .iter()
+        .map(|message| {
+            format!(
+                "{}:\n{}",
+                message.role.as_str().to_uppercase(),
+                message.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    text.push_str("\n\nASSISTANT:\n");
+    text
+}
@@ -0,0 +1,71 @@
+// src/llm/functions.rs
+
+use crate::tools::ToolSpec;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// A function offered to a model with native tool calling, built from a
+/// tool's `ToolSpec`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FunctionDef {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the arguments object.
+    pub parameters: Value,
+}
+
+impl FunctionDef {
+    pub fn new(name: &str, description: &str, parameters: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+        }
+    }
+
+    /// Tools with an `input_schema` take it as their parameters; freeform
+    /// tools take a single string `input`. Sample calls are appended to the
+    /// description.
+    pub fn from_spec(spec: &ToolSpec) -> Self {
+        let parameters = spec.input_schema.clone().unwrap_or_else(|| {
+            json!({
+                "type": "object",
+                "properties": {
+                    "input": { "type": "string", "description": spec.input_hint }
+                },
+                "required": ["input"]
+            })
+        });
+        let mut description = spec.description.clone();
+        for example in &spec.examples {
+            description.push_str(&format!(
+                "\nExample: {} → {}",
+                example.input, example.output_summary
+            ));
+        }
+        Self::new(&spec.name, &description, parameters)
+    }
+}
+
+/// A function call from a model's reply.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+impl FunctionCall {
+    /// Arguments some APIs send as a JSON string, parsed; anything else as is.
+    pub(crate) fn new(name: &str, arguments: &Value) -> Self {
+        let arguments = match arguments {
+            Value::String(text) if text.trim().is_empty() => json!({}),
+            Value::String(text) => serde_json::from_str(text).unwrap_or_else(|_| arguments.clone()),
+            other => other.clone(),
+        };
+        Self {
+            name: name.into(),
+            arguments,
+        }
+    }
+}
@@ -0,0 +1,384 @@
+// src/llm/json_repair.rs
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Extracts and deserializes the JSON in an LLM reply.
+///
+/// Drops `<think>` blocks and code fences, then tries every balanced
+/// `{...}` / `[...]` in the text in order, repairing common syntax slips
+/// (see `repair`), and returns the first one that deserializes into `T`.
+pub fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    let cleaned = strip_fences(strip_think(text));
+    let candidates = json_candidates(&cleaned);
+    if candidates.is_empty() {
+        return Err(format!("No JSON found in LLM output: {}", text.trim()));
+    }
+
+    let mut first_error = None;
+    for candidate in candidates {
+        let parsed = parse_value(candidate).and_then(|value| {
+            serde_json::from_value(value)
+                .map_err(|e| format!("LLM JSON does not match the expected shape: {}", e))
+        });
+        match parsed {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                first_error.get_or_insert(format!("{} | JSON: {}", e, candidate));
+            }
+        }
+    }
+    Err(first_error.unwrap_or_default())
+}
+
+/// Parses `json` as-is, or after `repair` if that fails.
+pub fn parse_value(json: &str) -> Result<Value, String> {
+    serde_json::from_str(json).or_else(|original: serde_json::Error| {
+        serde_json::from_str(&repair(json))
+            .map_err(|_| format!("Invalid JSON in LLM output: {}", original))
+    })
+}
+
+/// The text after the last `</think>`, or all of it for non-thinking models.
+pub fn strip_think(text: &str) -> &str {
+    text.rsplit("</think>").next().unwrap_or(text)
+}
+
+/// Removes markdown code fence lines (```` ``` ```` and ```` ```json ````).
+pub fn strip_fences(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The first balanced JSON object or array in `text`.
+pub fn extract_json(text: &str) -> Option<&str> {
+    json_candidates(text).into_iter().next()
+}
+
+/// Every top-level balanced `{...}` / `[...]` in `text`, skipping brackets
+/// inside strings. An unclosed final value (a truncated reply) runs to the
+/// end of the text, for `repair` to close.
+fn json_candidates(text: &str) -> Vec<&str> {
+    let mut candidates = Vec::new();
+    let mut start = None;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, c) in text.char_indices() {
+        if start.is_none() {
+            if matches!(c, '{' | '[') {
+                start = Some(offset);
+                depth = 1;
+            }
+            continue;
+        }
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' | '[' if !in_string => depth += 1,
+            '}' | ']' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    candidates.extend(start.take().map(|s| &text[s..=offset]));
+                }
+            }
+            _ => {}
+        }
+    }
+    candidates.extend(start.map(|s| &text[s..]));
+    candidates
+}
+
+/// Best-effort fix-up of almost-JSON: removes `//`, `/* */` and `#` comments
+/// and trailing commas, converts single-quoted and “smart-quoted” strings,
+/// quotes bare keys, maps `True`/`False`/`None`, escapes raw control
+/// characters, stray quotes and invalid escapes inside strings, inserts
+/// missing commas between values and closes truncated strings and brackets.
+pub fn repair(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut out = String::with_capacity(json.len() + 16);
+    let mut closers: Vec<char> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' | '“' | '”' => {
+                insert_missing_comma(&mut out);
+                i = repair_string(&chars, i, &mut out);
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '{' | '[' => {
+                insert_missing_comma(&mut out);
+                closers.push(if c == '{' { '}' } else { ']' });
+                out.push(c);
+            }
+            '}' | ']' => {
+                drop_trailing_comma(&mut out);
+                if closers.last() == Some(&c) {
+                    closers.pop();
+                    out.push(c);
+                }
+            }
+            c if c.is_alphanumeric() || matches!(c, '-' | '+' | '.' | '_' | '$') => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric()
+                        || matches!(chars[i], '-' | '+' | '.' | '_' | '$'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                insert_missing_comma(&mut out);
+                let is_key = chars[i..]
+                    .iter()
+                    .find(|c| !c.is_whitespace())
+                    .is_some_and(|c| *c == ':')
+                    && closers.last() == Some(&'}');
+                out.push_str(&bare_word(&word, is_key));
+                continue;
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+
+    drop_trailing_comma(&mut out);
+    while let Some(closer) = closers.pop() {
+        out.push(closer);
+    }
+    out
+}
+
+/// Copies the string starting at `chars[start]` to `out` as a valid JSON
+/// string and returns the index after it.
+fn repair_string(chars: &[char], start: usize, out: &mut String) -> usize {
+    let quote = match chars[start] {
+        '“' | '”' => '”',
+        other => other,
+    };
+    out.push('"');
+    let mut i = start + 1;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                match chars.get(i + 1) {
+                    Some(next @ ('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'u')) => {
+                        out.push('\\');
+                        out.push(*next);
+                    }
+                    Some('\'') => out.push('\''),
+                    Some(next) => {
+                        out.push_str("\\\\");
+                        out.push(*next);
+                    }
+                    None => out.push_str("\\\\"),
+                }
+                i += 2;
+                continue;
+            }
+            // A quote only ends the string when a delimiter follows; otherwise
+            // it is an unescaped quote inside the text (`"git commit -m "x""`).
+            c if c == quote || (quote == '”' && c == '“') => {
+                let next = chars[i + 1..].iter().find(|c| !matches!(c, ' ' | '\t'));
+                if matches!(next, None | Some(',' | ':' | '}' | ']' | '\n' | '\r')) {
+                    out.push('"');
+                    return i + 1;
+                }
+                out.push_str(if c == '\'' { "'" } else { "\\\"" });
+            }
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    // Truncated reply: close the string.
+    out.push('"');
+    i
+}
+
+/// A bare word as JSON: a quoted key, a literal, a number or a quoted string.
+fn bare_word(word: &str, is_key: bool) -> String {
+    if is_key {
+        return format!("\"{}\"", word);
+    }
+    match word {
+        "true" | "True" | "TRUE" => "true".into(),
+        "false" | "False" | "FALSE" => "false".into(),
+        "null" | "None" | "NULL" | "nil" | "undefined" => "null".into(),
+        _ if serde_json::from_str::<serde_json::Number>(word).is_ok() => word.into(),
+        _ => Value::String(word.into()).to_string(),
+    }
+}
+
+/// Adds the comma an LLM forgot between two values (`{"a": 1 "b": 2}`).
+fn insert_missing_comma(out: &mut String) {
+    let last = out.trim_end().chars().last();
+    if matches!(last, Some('"' | '}' | ']')) || last.is_some_and(|c| c.is_alphanumeric()) {
+        let trimmed = out.trim_end().len();
+        out.insert(trimmed, ',');
+    }
+}
+
+fn drop_trailing_comma(out: &mut String) {
+    let trimmed = out.trim_end();
+    if trimmed.ends_with(',') {
+        out.truncate(trimmed.len() - 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Verdict {
+        achieved: bool,
+        confidence: f64,
+    }
+
+    #[test]
+    fn parses_clean_json() {
+        let value: Value = parse_json(r#"{"plan": []}"#).unwrap();
+        assert_eq!(value, json!({"plan": []}));
+    }
+
+    #[test]
+    fn strips_think_block_and_fences() {
+        let text = "<think>\nThe user wants {a plan}.\n</think>\n\n```json\n{\"plan\": [{\"type\": \"info\", \"message\": \"done\"}]}\n```";
+        let value: Value = parse_json(text).unwrap();
+        assert_eq!(value["plan"][0]["message"], "done");
+    }
+
+    #[test]
+    fn skips_prose_and_picks_the_matching_object() {
+        let text = "Sure! The {goal} is clear. Here is the verdict:\n{\"achieved\": true, \"confidence\": 0.9}\nLet me know {if} you need more.";
+        let verdict: Verdict = parse_json(text).unwrap();
+        assert_eq!(
+            verdict,
+            Verdict {
+                achieved: true,
+                confidence: 0.9
+            }
+        );
+    }
+
+    #[test]
+    fn keeps_braces_inside_strings() {
+        let text = r#"{"plan": [{"type": "tool", "name": "run_command", "input": "echo '}' && echo ']'"}]}"#;
+        assert_eq!(extract_json(text), Some(text));
+    }
+
+    #[test]
+    fn removes_trailing_commas_and_comments() {
+        let text = r#"{
+  "plan": [
+    // check the tree first
+    {"type": "tool", "name": "run_command", "input": "git status"}, /* then commit */
+    {"type": "tool", "name": "run_command", "input": "git commit -m 'wip'"},
+  ],
+}"#;
+        let value: Value = parse_json(text).unwrap();
+        assert_eq!(value["plan"].as_array().unwrap().len(), 2);
+        assert_eq!(value["plan"][1]["input"], "git commit -m 'wip'");
+    }
+
+    #[test]
+    fn converts_single_quotes_and_python_literals() {
+        let text = "{'achieved': True, 'confidence': 0.75, 'missing': None, 'note': 'it\\'s fine'}";
+        let value: Value = parse_json(text).unwrap();
+        assert_eq!(
+            value,
+            json!({"achieved": true, "confidence": 0.75, "missing": null, "note": "it's fine"})
+        );
+    }
+
+    #[test]
+    fn quotes_bare_keys() {
+        let value: Value = parse_json("{plan: [{type: \"info\", message: \"ok\"}]}").unwrap();
+        assert_eq!(value, json!({"plan": [{"type": "info", "message": "ok"}]}));
+    }
+
+    #[test]
+    fn escapes_unescaped_inner_quotes() {
+        let text = r#"{"fix_commands": ["git commit -m "Fix formatting""], "analysis": "fmt"}"#;
+        let value: Value = parse_json(text).unwrap();
+        assert_eq!(
+            value["fix_commands"][0],
+            r#"git commit -m "Fix formatting""#
+        );
+    }
+
+    #[test]
+    fn escapes_raw_newlines_and_invalid_escapes() {
+        let text = "{\"reasoning\": \"line one\nline two\", \"pattern\": \"\\d+ passed\"}";
+        let value: Value = parse_json(text).unwrap();
+        assert_eq!(value["reasoning"], "line one\nline two");
+        assert_eq!(value["pattern"], "\\d+ passed");
+    }
+
+    #[test]
+    fn inserts_missing_commas() {
+        let text = "{\"plan\": [\n  {\"type\": \"info\", \"message\": \"a\"}\n  {\"type\": \"info\", \"message\": \"b\"}\n] \"done\": true}";
+        let value: Value = parse_json(text).unwrap();
+        assert_eq!(value["plan"].as_array().unwrap().len(), 2);
+        assert_eq!(value["done"], true);
+    }
+
+    #[test]
+    fn closes_truncated_output() {
+        let text =
+            "{\"plan\": [{\"type\": \"tool\", \"name\": \"run_command\", \"input\": \"cargo te";
+        let value: Value = parse_json(text).unwrap();
+        assert_eq!(value["plan"][0]["input"], "cargo te");
+    }
+
+    #[test]
+    fn handles_smart_quotes() {
+        let value: Value = parse_json("{“achieved”: false, “confidence”: 0.2}").unwrap();
+        assert_eq!(value, json!({"achieved": false, "confidence": 0.2}));
+    }
+
+    #[test]
+    fn reports_missing_json() {
+        let err = parse_json::<Value>("I could not produce a plan.").unwrap_err();
+        assert!(err.starts_with("No JSON found"), "{}", err);
+    }
+
+    #[test]
+    fn reports_shape_mismatch() {
+        let err = parse_json::<Verdict>(r#"{"achieved": "yes"}"#).unwrap_err();
+        assert!(err.contains("expected shape"), "{}", err);
+    }
+}
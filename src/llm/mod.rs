@@ -0,0 +1,324 @@
+// src/llm/mod.rs
+
+pub mod anthropic;
+pub mod chat;
+pub mod functions;
+pub mod json_repair;
+pub mod ollama;
+pub mod openai;
+pub mod retry;
+pub mod tiers;
+pub mod usage;
+
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+#[cfg(feature = "http")]
+use std::time::Duration;
+
+pub use anthropic::AnthropicProvider;
+pub use chat::{ChatMessage, Conversation, Role};
+pub use functions::{FunctionCall, FunctionDef};
+pub use ollama::OllamaProvider;
+pub use openai::OpenAIProvider;
+pub use retry::{RateLimiter, RetryPolicy, SendFailure};
+pub use tiers::{ModelTier, ModelTiers};
+pub use usage::{Budget, TokenUsage, UsageTracker};
+
+/// An HTTP request to an LLM backend, independent of the transport that sends it.
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+/// A hosted or local LLM API. Providers only describe the wire format;
+/// `LLMTool` owns the (blocking or async) transport.
+pub trait LlmProvider: Send + Sync {
+    fn name(&self) -> &str;
+    fn model(&self) -> &str;
+    fn request(&self, prompt: &str) -> LlmRequest;
+    fn parse_response(&self, json: &Value) -> Result<String, String>;
+
+    /// A request whose reply must be JSON, matching `schema` when given.
+    /// Backends without a JSON mode fall back to `request`, relying on the prompt.
+    fn structured_request(&self, prompt: &str, _schema: Option<&Value>) -> LlmRequest {
+        self.request(prompt)
+    }
+
+    /// Request continuing a multi-turn exchange. The default sends the
+    /// messages as one prompt (see `chat::transcript`).
+    fn chat_request(&self, messages: &[ChatMessage]) -> LlmRequest {
+        self.request(&chat::transcript(messages))
+    }
+
+    /// `chat_request` whose reply must be JSON, like `structured_request`.
+    fn structured_chat_request(
+        &self,
+        messages: &[ChatMessage],
+        schema: Option<&Value>,
+    ) -> LlmRequest {
+        self.structured_request(&chat::transcript(messages), schema)
+    }
+
+    /// Token counts from a response body, if the backend reports them.
+    fn parse_usage(&self, _json: &Value) -> Option<TokenUsage> {
+        None
+    }
+
+    /// Request embedding `text`, or `None` if the backend has no embedding endpoint.
+    fn embedding_request(&self, _text: &str) -> Option<LlmRequest> {
+        None
+    }
+
+    fn parse_embedding(&self, _json: &Value) -> Result<Vec<f32>, String> {
+        Err(format!(
+            "Provider '{}' does not offer embeddings",
+            self.name()
+        ))
+    }
+
+    /// Request offering `functions` as native tools the model must call, or
+    /// `None` if the backend has no function calling.
+    fn function_request(&self, _prompt: &str, _functions: &[FunctionDef]) -> Option<LlmRequest> {
+        None
+    }
+
+    /// The function calls in a `function_request` response, in order.
+    fn parse_function_calls(&self, _json: &Value) -> Result<Vec<FunctionCall>, String> {
+        Err(format!(
+            "Provider '{}' does not support function calling",
+            self.name()
+        ))
+    }
+}
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<Value, SendFailure>> + Send + 'a>>;
+
+/// Delivers `LlmRequest`s in place of HTTP, e.g. canned replies in tests
+/// (see `testing::MockLLMTool`). Set with `LLMTool::with_transport`.
+pub trait LlmTransport: Send + Sync {
+    fn send_blocking(&self, request: &LlmRequest) -> Result<Value, SendFailure>;
+
+    /// Defaults to `send_blocking`, which suits in-memory transports.
+    fn send<'a>(&'a self, request: &'a LlmRequest) -> SendFuture<'a> {
+        Box::pin(async move { self.send_blocking(request) })
+    }
+}
+
+/// Provider selection, e.g. parsed from `"openai:gpt-4o-mini"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmConfig {
+    pub provider: String,
+    pub model: String,
+    pub base_url: Option<String>,
+    /// Environment variable holding the API key; defaults per provider.
+    pub api_key_env: Option<String>,
+}
+
+impl LlmConfig {
+    pub fn new(provider: &str, model: &str) -> Self {
+        Self {
+            provider: provider.into(),
+            model: model.into(),
+            base_url: None,
+            api_key_env: None,
+        }
+    }
+
+    /// Parses `provider:model`. A spec without a known provider prefix is an Ollama model,
+    /// so `"qwen3:8b"` and `"ollama:qwen3:8b"` are equivalent.
+    pub fn parse(spec: &str) -> Self {
+        match spec.split_once(':') {
+            Some((provider, model)) if ["ollama", "openai", "anthropic"].contains(&provider) => {
+                Self::new(provider, model)
+            }
+            _ => Self::new("ollama", spec),
+        }
+    }
+
+    pub fn with_base_url(mut self, url: &str) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    pub fn with_api_key_env(mut self, var: &str) -> Self {
+        self.api_key_env = Some(var.into());
+        self
+    }
+
+    /// The environment variable holding this provider's API key, if it needs one.
+    pub fn api_key_var(&self) -> Option<&str> {
+        let default_var = match self.provider.as_str() {
+            "openai" => "OPENAI_API_KEY",
+            "anthropic" => "ANTHROPIC_API_KEY",
+            _ => return None,
+        };
+        Some(self.api_key_env.as_deref().unwrap_or(default_var))
+    }
+
+    /// The provider for this spec. Providers whose cargo feature is off
+    /// (`ollama`, `openai`, `anthropic`) are refused.
+    pub fn build(&self) -> Result<Arc<dyn LlmProvider>, String> {
+        match self.provider.as_str() {
+            #[cfg(feature = "ollama")]
+            "ollama" => {
+                let mut provider = OllamaProvider::new(&self.model);
+                if let Some(url) = &self.base_url {
+                    provider.base_url = url.clone();
+                }
+                Ok(Arc::new(provider))
+            }
+            #[cfg(feature = "openai")]
+            "openai" => {
+                let mut provider =
+                    OpenAIProvider::new(&self.model, &self.api_key("OPENAI_API_KEY")?);
+                if let Some(url) = &self.base_url {
+                    provider.base_url = url.clone();
+                }
+                Ok(Arc::new(provider))
+            }
+            #[cfg(feature = "anthropic")]
+            "anthropic" => {
+                let mut provider =
+                    AnthropicProvider::new(&self.model, &self.api_key("ANTHROPIC_API_KEY")?);
+                if let Some(url) = &self.base_url {
+                    provider.base_url = url.clone();
+                }
+                Ok(Arc::new(provider))
+            }
+            name if ["ollama", "openai", "anthropic"].contains(&name) => Err(format!(
+                "LLM provider '{}' is not enabled in this build (cargo feature \"{}\")",
+                name, name
+            )),
+            other => Err(format!("Unknown LLM provider: {}", other)),
+        }
+    }
+
+    #[cfg(any(feature = "openai", feature = "anthropic"))]
+    fn api_key(&self, default_var: &str) -> Result<String, String> {
+        let var = self.api_key_env.as_deref().unwrap_or(default_var);
+        std::env::var(var).map_err(|_| format!("Missing API key: set {}", var))
+    }
+}
+
+pub fn send_blocking(request: &LlmRequest) -> Result<Value, String> {
+    try_send_blocking(request).map_err(|failure| failure.message)
+}
+
+pub async fn send(request: &LlmRequest) -> Result<Value, String> {
+    try_send(request).await.map_err(|failure| failure.message)
+}
+
+/// Like `send_blocking`, but says whether a failure is worth retrying.
+#[cfg(feature = "http")]
+pub fn try_send_blocking(request: &LlmRequest) -> Result<Value, SendFailure> {
+    let client = reqwest::blocking::Client::new();
+    let mut builder = client.post(&request.url).json(&request.body);
+    for (key, value) in &request.headers {
+        builder = builder.header(key, value);
+    }
+
+    let response = builder
+        .send()
+        .map_err(|err| SendFailure::transient(format!("Request failed: {err}")))?;
+    if let Some(failure) = transient_status(response.status(), response.headers()) {
+        let body = response.text().unwrap_or_default();
+        return Err(with_body(failure, &body));
+    }
+    response
+        .json::<Value>()
+        .map_err(|err| SendFailure::fatal(format!("Failed to parse JSON: {err}")))
+}
+
+/// Like `send`, but says whether a failure is worth retrying.
+#[cfg(feature = "http")]
+pub async fn try_send(request: &LlmRequest) -> Result<Value, SendFailure> {
+    let client = reqwest::Client::new();
+    let mut builder = client.post(&request.url).json(&request.body);
+    for (key, value) in &request.headers {
+        builder = builder.header(key, value);
+    }
+
+    let response = builder
+        .send()
+        .await
+        .map_err(|err| SendFailure::transient(format!("Request failed: {err}")))?;
+    if let Some(failure) = transient_status(response.status(), response.headers()) {
+        let body = response.text().await.unwrap_or_default();
+        return Err(with_body(failure, &body));
+    }
+    response
+        .json::<Value>()
+        .await
+        .map_err(|err| SendFailure::fatal(format!("Failed to parse JSON: {err}")))
+}
+
+/// Without the `http` feature there is no client: requests fail unless the
+/// `LLMTool` has its own `LlmTransport` (e.g. a cassette or a mock).
+#[cfg(not(feature = "http"))]
+pub fn try_send_blocking(_request: &LlmRequest) -> Result<Value, SendFailure> {
+    Err(SendFailure::fatal(NO_HTTP.into()))
+}
+
+#[cfg(not(feature = "http"))]
+pub async fn try_send(_request: &LlmRequest) -> Result<Value, SendFailure> {
+    Err(SendFailure::fatal(NO_HTTP.into()))
+}
+
+#[cfg(not(feature = "http"))]
+const NO_HTTP: &str = "No HTTP client: agentic-runtime was built without the \"http\" feature";
+
+/// Rate limiting (429) and server errors (5xx) are retried; other error
+/// bodies are left for the provider to surface.
+#[cfg(feature = "http")]
+fn transient_status(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+) -> Option<SendFailure> {
+    if status != reqwest::StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+        return None;
+    }
+    let mut failure = SendFailure::transient(format!("HTTP {}", status));
+    failure.retry_after = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+    Some(failure)
+}
+
+#[cfg(feature = "http")]
+fn with_body(mut failure: SendFailure, body: &str) -> SendFailure {
+    let body = body.trim();
+    if !body.is_empty() {
+        let snippet: String = body.chars().take(200).collect();
+        failure.message = format!("{}: {}", failure.message, snippet);
+    }
+    failure
+}
+
+/// Reads a JSON array of numbers as an embedding vector.
+pub(crate) fn parse_vector(value: Option<&Value>) -> Option<Vec<f32>> {
+    value?.as_array().map(|values| {
+        values
+            .iter()
+            .filter_map(|v| v.as_f64())
+            .map(|v| v as f32)
+            .collect()
+    })
+}
+
+/// Surfaces provider error bodies like `{"error": {"message": "..."}}`.
+pub(crate) fn api_error(json: &Value) -> Option<String> {
+    let error = json.get("error")?;
+    Some(
+        error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string()),
+    )
+}
@@ -0,0 +1,83 @@
+// src/llm/ollama.rs
+
+use crate::llm::{LlmProvider, LlmRequest, TokenUsage, api_error, parse_vector};
+use serde_json::{Value, json};
+
+/// Local models served by Ollama's `/api/generate`.
+#[derive(Clone, Debug)]
+pub struct OllamaProvider {
+    pub model: String,
+    pub base_url: String,
+}
+
+impl OllamaProvider {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.into(),
+            base_url: "http://localhost:11434".into(),
+        }
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn request(&self, prompt: &str) -> LlmRequest {
+        LlmRequest {
+            url: format!("{}/api/generate", self.base_url),
+            headers: Vec::new(),
+            body: json!({
+                "model": self.model,
+                "prompt": prompt,
+                "stream": false
+            }),
+        }
+    }
+
+    /// `format` takes `"json"` or, on Ollama 0.5+, a JSON Schema that
+    /// constrains decoding with a grammar.
+    fn structured_request(&self, prompt: &str, schema: Option<&Value>) -> LlmRequest {
+        let mut request = self.request(prompt);
+        request.body["format"] = schema.cloned().unwrap_or_else(|| json!("json"));
+        request
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<String, String> {
+        if let Some(error) = api_error(json) {
+            return Err(format!("Ollama error: {}", error));
+        }
+        json.get("response")
+            .and_then(|v| v.as_str())
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| "LLM response missing 'response' field".into())
+    }
+
+    fn parse_usage(&self, json: &Value) -> Option<TokenUsage> {
+        Some(TokenUsage {
+            prompt_tokens: json.get("prompt_eval_count")?.as_u64()?,
+            completion_tokens: json.get("eval_count")?.as_u64()?,
+        })
+    }
+
+    fn embedding_request(&self, text: &str) -> Option<LlmRequest> {
+        Some(LlmRequest {
+            url: format!("{}/api/embeddings", self.base_url),
+            headers: Vec::new(),
+            body: json!({ "model": self.model, "prompt": text }),
+        })
+    }
+
+    fn parse_embedding(&self, json: &Value) -> Result<Vec<f32>, String> {
+        if let Some(error) = api_error(json) {
+            return Err(format!("Ollama error: {}", error));
+        }
+        parse_vector(json.get("embedding"))
+            .ok_or_else(|| "Embedding response missing 'embedding' field".into())
+    }
+}
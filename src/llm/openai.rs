@@ -0,0 +1,146 @@
+// src/llm/openai.rs
+
+use crate::llm::{
+    ChatMessage, FunctionCall, FunctionDef, LlmProvider, LlmRequest, Role, TokenUsage, api_error,
+    parse_vector,
+};
+use serde_json::{Value, json};
+
+/// OpenAI and OpenAI-compatible `/chat/completions` APIs (vLLM, LM Studio, OpenRouter, ...).
+#[derive(Clone)]
+pub struct OpenAIProvider {
+    pub model: String,
+    pub base_url: String,
+    pub embedding_model: String,
+    api_key: String,
+}
+
+impl OpenAIProvider {
+    pub fn new(model: &str, api_key: &str) -> Self {
+        Self {
+            model: model.into(),
+            base_url: "https://api.openai.com/v1".into(),
+            embedding_model: "text-embedding-3-small".into(),
+            api_key: api_key.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for OpenAIProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAIProvider")
+            .field("model", &self.model)
+            .field("base_url", &self.base_url)
+            .field("embedding_model", &self.embedding_model)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LlmProvider for OpenAIProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn request(&self, prompt: &str) -> LlmRequest {
+        self.chat_request(&[ChatMessage::new(Role::User, prompt)])
+    }
+
+    /// JSON mode, or structured outputs when a schema is given.
+    fn structured_request(&self, prompt: &str, schema: Option<&Value>) -> LlmRequest {
+        self.structured_chat_request(&[ChatMessage::new(Role::User, prompt)], schema)
+    }
+
+    fn chat_request(&self, messages: &[ChatMessage]) -> LlmRequest {
+        LlmRequest {
+            url: format!("{}/chat/completions", self.base_url),
+            headers: vec![("Authorization".into(), format!("Bearer {}", self.api_key))],
+            body: json!({ "model": self.model, "messages": messages }),
+        }
+    }
+
+    fn structured_chat_request(
+        &self,
+        messages: &[ChatMessage],
+        schema: Option<&Value>,
+    ) -> LlmRequest {
+        let mut request = self.chat_request(messages);
+        request.body["response_format"] = match schema {
+            Some(schema) => json!({
+                "type": "json_schema",
+                "json_schema": { "name": "response", "schema": schema, "strict": false }
+            }),
+            None => json!({ "type": "json_object" }),
+        };
+        request
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<String, String> {
+        if let Some(error) = api_error(json) {
+            return Err(format!("OpenAI error: {}", error));
+        }
+        json.pointer("/choices/0/message/content")
+            .and_then(|v| v.as_str())
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| "LLM response missing 'choices[0].message.content'".into())
+    }
+
+    fn parse_usage(&self, json: &Value) -> Option<TokenUsage> {
+        let usage = json.get("usage")?;
+        Some(TokenUsage {
+            prompt_tokens: usage.get("prompt_tokens")?.as_u64()?,
+            completion_tokens: usage.get("completion_tokens")?.as_u64()?,
+        })
+    }
+
+    /// Chat-completions `tools`, with `tool_choice: "required"`.
+    fn function_request(&self, prompt: &str, functions: &[FunctionDef]) -> Option<LlmRequest> {
+        let mut request = self.request(prompt);
+        request.body["tools"] = functions
+            .iter()
+            .map(|function| json!({ "type": "function", "function": function }))
+            .collect();
+        request.body["tool_choice"] = json!("required");
+        Some(request)
+    }
+
+    fn parse_function_calls(&self, json: &Value) -> Result<Vec<FunctionCall>, String> {
+        if let Some(error) = api_error(json) {
+            return Err(format!("OpenAI error: {}", error));
+        }
+        let calls = json
+            .pointer("/choices/0/message/tool_calls")
+            .and_then(Value::as_array)
+            .ok_or("LLM response missing 'choices[0].message.tool_calls'")?;
+        calls
+            .iter()
+            .map(|call| {
+                let name = call
+                    .pointer("/function/name")
+                    .and_then(Value::as_str)
+                    .ok_or("Tool call missing 'function.name'")?;
+                let arguments = call.pointer("/function/arguments").unwrap_or(&Value::Null);
+                Ok(FunctionCall::new(name, arguments))
+            })
+            .collect()
+    }
+
+    fn embedding_request(&self, text: &str) -> Option<LlmRequest> {
+        Some(LlmRequest {
+            url: format!("{}/embeddings", self.base_url),
+            headers: vec![("Authorization".into(), format!("Bearer {}", self.api_key))],
+            body: json!({ "model": self.embedding_model, "input": text }),
+        })
+    }
+
+    fn parse_embedding(&self, json: &Value) -> Result<Vec<f32>, String> {
+        if let Some(error) = api_error(json) {
+            return Err(format!("OpenAI error: {}", error));
+        }
+        parse_vector(json.pointer("/data/0/embedding"))
+            .ok_or_else(|| "Embedding response missing 'data[0].embedding'".into())
+    }
+}
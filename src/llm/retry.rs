@@ -0,0 +1,140 @@
+// src/llm/retry.rs
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// A failed LLM request attempt.
+#[derive(Clone, Debug)]
+pub struct SendFailure {
+    pub message: String,
+    /// Connection errors, timeouts, HTTP 429 and 5xx: worth retrying.
+    pub transient: bool,
+    /// Server-requested wait from a `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+impl SendFailure {
+    pub fn fatal(message: String) -> Self {
+        Self {
+            message,
+            transient: false,
+            retry_after: None,
+        }
+    }
+
+    pub fn transient(message: String) -> Self {
+        Self {
+            message,
+            transient: true,
+            retry_after: None,
+        }
+    }
+}
+
+/// Exponential backoff with jitter for transient LLM failures.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of each delay that is randomized (0.0–1.0).
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (1-based), honoring `Retry-After`.
+    pub fn delay(&self, attempt: u32, failure: &SendFailure) -> Duration {
+        if let Some(wait) = failure.retry_after {
+            return wait.min(self.max_backoff);
+        }
+        let exponent = attempt.saturating_sub(1).min(30) as i32;
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let base = base.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        // Spread retries over [base * (1 - jitter), base * (1 + jitter)].
+        let factor = 1.0 + jitter * (2.0 * random_unit() - 1.0);
+        Duration::from_secs_f64((base * factor).max(0.0))
+    }
+
+    /// Whether `failure` on retry number `attempt` (1-based) should be retried.
+    pub fn should_retry(&self, attempt: u32, failure: &SendFailure) -> bool {
+        failure.transient && attempt <= self.max_retries
+    }
+}
+
+/// Spaces requests to at most `requests_per_minute`. Clones of an `LLMTool`
+/// share one limiter, so the planner, replanner and reflector draw from the
+/// same allowance.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn per_minute(requests_per_minute: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(60) / requests_per_minute.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves the next slot and returns how long to wait for it.
+    pub fn reserve(&self) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next_slot.lock().unwrap();
+        let slot = (*next).max(now);
+        *next = slot + self.interval;
+        slot - now
+    }
+
+    /// Blocks until a request may be sent.
+    pub fn acquire(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+
+    pub async fn acquire_async(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// A value in [0, 1) from the clock; good enough to de-synchronize retries.
+fn random_unit() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or_default();
+    // xorshift to spread the low-entropy nanosecond counter.
+    let mut x = nanos ^ 0x9E37_79B9_7F4A_7C15;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x % 1_000_000) as f64 / 1_000_000.0
+}
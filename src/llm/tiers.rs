@@ -0,0 +1,74 @@
+// src/llm/tiers.rs
+
+use crate::llm::{LlmConfig, LlmProvider};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A class of model a consumer asks for, rather than a specific model:
+/// planning wants a capable model, analysis a quick one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelTier {
+    /// Low latency, for classification and short analyses.
+    Fast,
+    /// The most capable model, for planning.
+    Smart,
+    /// Lowest cost per token, for summaries.
+    Cheap,
+}
+
+impl fmt::Display for ModelTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ModelTier::Fast => "fast",
+            ModelTier::Smart => "smart",
+            ModelTier::Cheap => "cheap",
+        })
+    }
+}
+
+/// The model serving each configured tier; unconfigured tiers use the
+/// `LLMTool`'s own model. Clones share the providers.
+#[derive(Clone, Default)]
+pub struct ModelTiers {
+    providers: HashMap<ModelTier, Arc<dyn LlmProvider>>,
+}
+
+impl ModelTiers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, tier: ModelTier, provider: Arc<dyn LlmProvider>) -> Self {
+        self.providers.insert(tier, provider);
+        self
+    }
+
+    /// Serves `tier` with the model of a `provider:model` spec.
+    pub fn with_spec(self, tier: ModelTier, spec: &str) -> Result<Self, String> {
+        let provider = LlmConfig::parse(spec)
+            .build()
+            .map_err(|e| format!("Model tier '{}': {}", tier, e))?;
+        Ok(self.with(tier, provider))
+    }
+
+    pub fn get(&self, tier: ModelTier) -> Option<&Arc<dyn LlmProvider>> {
+        self.providers.get(&tier)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+impl fmt::Debug for ModelTiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (tier, provider) in &self.providers {
+            map.entry(tier, &format!("{}:{}", provider.name(), provider.model()));
+        }
+        map.finish()
+    }
+}
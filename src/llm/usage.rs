@@ -0,0 +1,268 @@
+// src/llm/usage.rs
+
+use crate::context::history::millis;
+use crate::tools::cancel::CancellationToken;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Token counts reported by (or estimated for) one LLM call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Rough count for providers that report no usage: ~4 characters per token.
+    pub fn estimate(prompt: &str, completion: &str) -> Self {
+        let tokens = |text: &str| (text.chars().count() as u64).div_ceil(4);
+        Self {
+            prompt_tokens: tokens(prompt),
+            completion_tokens: tokens(completion),
+        }
+    }
+}
+
+/// USD per million tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+impl ModelPricing {
+    pub const FREE: Self = Self::new(0.0, 0.0);
+
+    pub const fn new(prompt_per_million: f64, completion_per_million: f64) -> Self {
+        Self {
+            prompt_per_million,
+            completion_per_million,
+        }
+    }
+
+    pub fn cost(&self, usage: TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_per_million
+            + usage.completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// List prices for common hosted models, matched by model-name prefix
+/// (longest first). Local (Ollama) models and unknown models cost nothing.
+const KNOWN_PRICING: [(&str, ModelPricing); 8] = [
+    ("gpt-4o-mini", ModelPricing::new(0.15, 0.60)),
+    ("gpt-4o", ModelPricing::new(2.50, 10.00)),
+    ("gpt-4.1-mini", ModelPricing::new(0.40, 1.60)),
+    ("gpt-4.1-nano", ModelPricing::new(0.10, 0.40)),
+    ("gpt-4.1", ModelPricing::new(2.00, 8.00)),
+    ("claude-haiku", ModelPricing::new(1.00, 5.00)),
+    ("claude-sonnet", ModelPricing::new(3.00, 15.00)),
+    ("claude-opus", ModelPricing::new(15.00, 75.00)),
+];
+
+/// Hard limits for a run; the run is aborted once either is exceeded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Budget {
+    pub max_tokens: Option<u64>,
+    pub max_cost_usd: Option<f64>,
+}
+
+/// One recorded LLM call.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LlmCall {
+    /// What made the call, e.g. `planner` or `reflector`.
+    pub component: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+    pub cost_usd: f64,
+    /// True when the provider reported no counts and they were estimated.
+    pub estimated: bool,
+    /// From sending the request to the reply, including retries.
+    #[serde(default, rename = "latency_ms", with = "millis")]
+    pub latency: Duration,
+}
+
+/// Aggregated usage over a set of calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub calls: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+    /// Time spent waiting on the model.
+    #[serde(default, rename = "latency_ms", with = "millis")]
+    pub latency: Duration,
+}
+
+impl UsageTotals {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, call: &LlmCall) {
+        self.calls += 1;
+        self.prompt_tokens += call.usage.prompt_tokens;
+        self.completion_tokens += call.usage.completion_tokens;
+        self.cost_usd += call.cost_usd;
+        self.latency += call.latency;
+    }
+}
+
+/// Totals for a run, overall and per component.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub total: UsageTotals,
+    pub by_component: BTreeMap<String, UsageTotals>,
+}
+
+#[derive(Default)]
+struct UsageLog {
+    calls: Vec<LlmCall>,
+    totals: UsageTotals,
+    budget: Budget,
+    pricing: HashMap<String, ModelPricing>,
+    /// Cancelled once the budget is exceeded.
+    abort: Option<CancellationToken>,
+}
+
+/// Records tokens and estimated cost of every LLM call in a run.
+///
+/// Clones share the same log, so one tracker can be handed to the planner,
+/// replanner and every LLM-backed tool (see `LLMTool::with_usage`).
+#[derive(Clone, Default)]
+pub struct UsageTracker {
+    log: Arc<Mutex<UsageLog>>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels `token` (normally the run's `Context::cancel`) when the budget is exceeded.
+    pub fn abort_on_budget(self, token: CancellationToken) -> Self {
+        self.log.lock().unwrap().abort = Some(token);
+        self
+    }
+
+    pub fn set_budget(&self, budget: Budget) {
+        self.log.lock().unwrap().budget = budget;
+    }
+
+    /// Overrides the built-in price list for `model`.
+    pub fn set_pricing(&self, model: &str, pricing: ModelPricing) {
+        self.log
+            .lock()
+            .unwrap()
+            .pricing
+            .insert(model.into(), pricing);
+    }
+
+    /// Records a call and cancels the run if it pushed usage over budget.
+    pub fn record(
+        &self,
+        component: &str,
+        provider: &str,
+        model: &str,
+        usage: TokenUsage,
+        estimated: bool,
+        latency: Duration,
+    ) {
+        let mut log = self.log.lock().unwrap();
+        let pricing = match log.pricing.get(model) {
+            Some(pricing) => *pricing,
+            None if provider == "ollama" => ModelPricing::FREE,
+            None => default_pricing(model),
+        };
+        let call = LlmCall {
+            component: component.into(),
+            provider: provider.into(),
+            model: model.into(),
+            usage,
+            cost_usd: pricing.cost(usage),
+            estimated,
+            latency,
+        };
+        log.totals.add(&call);
+        log.calls.push(call);
+
+        if exceeded(&log.budget, &log.totals).is_some()
+            && let Some(token) = &log.abort
+        {
+            token.cancel();
+        }
+    }
+
+    pub fn calls(&self) -> Vec<LlmCall> {
+        self.log.lock().unwrap().calls.clone()
+    }
+
+    pub fn totals(&self) -> UsageTotals {
+        self.log.lock().unwrap().totals
+    }
+
+    pub fn summary(&self) -> UsageSummary {
+        let log = self.log.lock().unwrap();
+        let mut by_component: BTreeMap<String, UsageTotals> = BTreeMap::new();
+        for call in &log.calls {
+            by_component
+                .entry(call.component.clone())
+                .or_default()
+                .add(call);
+        }
+        UsageSummary {
+            total: log.totals,
+            by_component,
+        }
+    }
+
+    /// Why the budget is exhausted, or `None` while calls are still allowed.
+    pub fn budget_exceeded(&self) -> Option<String> {
+        let log = self.log.lock().unwrap();
+        exceeded(&log.budget, &log.totals)
+    }
+}
+
+impl std::fmt::Debug for UsageTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UsageTracker")
+            .field("totals", &self.totals())
+            .finish()
+    }
+}
+
+fn default_pricing(model: &str) -> ModelPricing {
+    let mut known = KNOWN_PRICING;
+    known.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    known
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, pricing)| *pricing)
+        .unwrap_or(ModelPricing::FREE)
+}
+
+fn exceeded(budget: &Budget, totals: &UsageTotals) -> Option<String> {
+    if let Some(max) = budget.max_tokens
+        && totals.total_tokens() > max
+    {
+        return Some(format!(
+            "Token budget exceeded: {} of {} tokens used",
+            totals.total_tokens(),
+            max
+        ));
+    }
+    if let Some(max) = budget.max_cost_usd
+        && totals.cost_usd > max
+    {
+        return Some(format!(
+            "Cost budget exceeded: ${:.4} of ${:.4} spent",
+            totals.cost_usd, max
+        ));
+    }
+    None
+}
@@ -1,47 +1,446 @@
 // src/main.rs
 
+use agentic_runtime::agent::runner::{AgentRunner, DEFAULT_STATE_DIR, RunState};
 use agentic_runtime::agent::{Agent, BasicAgent};
+use agentic_runtime::cli::{Cli, Command, USAGE};
+use agentic_runtime::config::{RuntimeConfig, SnapshotMode};
 use agentic_runtime::context::Context;
-use agentic_runtime::memory::Memory;
+use agentic_runtime::events::AgentEvent;
+use agentic_runtime::ingest::KnowledgeBase;
+use agentic_runtime::mcp::McpClient;
+use agentic_runtime::mcp::server::McpServer;
+use agentic_runtime::memory::failures::{FailedPlanRecord, FailedPlanStore};
+use agentic_runtime::memory::lessons::{LessonRecord, LessonsStore};
+use agentic_runtime::memory::vector::HashingEmbedder;
 use agentic_runtime::model::TaskModel;
+use agentic_runtime::policy::ApprovalPolicy;
+use agentic_runtime::policy::plan_edit::edit_plan;
+use agentic_runtime::policy::replan::{ReplanDecision, ReplanPolicy};
+use agentic_runtime::protocol::evaluator::LLMJudge;
 use agentic_runtime::protocol::planner::LLMPlanner;
-use agentic_runtime::protocol::replanner::LLMReplanner;
-use agentic_runtime::tools::{ErrorAnalyzerTool, LLMTool, ReflectorTool, RunCommandTool};
+use agentic_runtime::protocol::{ExecutionResult, Feedback, Plan, RiskLevel, SimulationResult};
+use agentic_runtime::queue::{JobQueue, JobStatus};
+use agentic_runtime::report::RunReport;
+use agentic_runtime::scheduler::cron::UtcTime;
+use agentic_runtime::scheduler::{DEFAULT_SCHEDULE_DIR, Scheduler};
+use agentic_runtime::server::Server;
+use agentic_runtime::telemetry;
+use agentic_runtime::testing::{CassettePlayer, CassetteRecorder};
+use agentic_runtime::tools::cancel::{cancel_on_interrupt, interrupted, on_forced_exit};
+use agentic_runtime::tools::{GoalVerifierTool, LLMTool};
+use agentic_runtime::tui::Dashboard;
+use agentic_runtime::workspace::{Workspace, WorkspaceSnapshot};
 use colored::Colorize;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where `run` archives reports and `replay` looks for them.
+const RUNS_DIR: &str = ".agentic/runs";
 
 fn main() {
-    let model = TaskModel::new(
-        "Analyze the current git repository status, identify any modified files, and create a meaningful commit if there are changes to commit.",
-    );
+    let cli = Cli::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\nRun `agentic help` for usage.", e.red());
+        exit(2);
+    });
+
+    match &cli.command {
+        Command::Help => return print!("{}", USAGE),
+        Command::Version => return println!("agentic {}", env!("CARGO_PKG_VERSION")),
+        Command::Init => {
+            match RuntimeConfig::write_default(&cli.config_path) {
+                Ok(()) => println!("{}", format!("Wrote {}", cli.config_path).green()),
+                Err(e) => println!("{}", e.red()),
+            }
+            return;
+        }
+        Command::Replay { run_id } => return replay(run_id),
+        Command::RunsList => return list_runs(),
+        _ => {}
+    }
+
+    // The config file (if present) configures the run; AGENTIC_* env vars and
+    // command-line options override it.
+    let mut config = RuntimeConfig::load_or_default(&cli.config_path)
+        .unwrap_or_else(|e| {
+            println!("{}", format!("{}; using default config", e).red());
+            RuntimeConfig::default()
+        })
+        .with_env_overrides();
+    if let Some(spec) = &cli.llm {
+        config.model.llm = spec.clone();
+    }
+    config.agent.dry_run |= cli.dry_run;
+    config.agent.edit_plan |= cli.edit_plan;
+    config.agent.decompose |= cli.decompose;
+
+    // Console logs would scribble over the dashboard.
+    if matches!(cli.command, Command::Tui { .. }) {
+        config.telemetry.console = "off".into();
+    }
+
+    // Held until exit so buffered spans are flushed to the OTLP collector.
+    let _telemetry = telemetry::init(&config.telemetry)
+        .map_err(|e| println!("{}", format!("Telemetry disabled: {}", e).red()))
+        .ok();
+
+    // Served and scheduled runs build their own model and context from the config.
+    match &cli.command {
+        Command::Serve { addr } => {
+            println!("{}", format!("🌐 Serving runs on http://{}", addr).green());
+            if let Err(e) = Server::new(config).serve(addr) {
+                println!("{}", e.red());
+                exit(1);
+            }
+            return;
+        }
+        Command::Batch { path } => return batch(path, &config),
+        Command::Schedule | Command::ScheduleList | Command::ScheduleRun { .. } => {
+            return schedule(&cli.command, config);
+        }
+        _ => {}
+    }
+
+    let mut llm = config.llm().unwrap_or_else(|e| {
+        println!("{}", format!("{}; falling back to Ollama", e).red());
+        LLMTool::default()
+    });
+    // Set before the context is built, so every LLMTool cloned into it is covered.
+    let recorder = cli.record.as_ref().map(|_| CassetteRecorder::new());
+    if let Some(recorder) = &recorder {
+        llm = llm.with_transport(recorder.transport());
+    }
+    let player = cli.playback.as_deref().map(|path| {
+        CassettePlayer::load(path).unwrap_or_else(|e| {
+            println!("{}", e.red());
+            exit(1);
+        })
+    });
+    if let Some(player) = &player {
+        llm = llm.with_transport(player.transport());
+    }
+    let mut context = match config.build_context(&llm) {
+        Ok(context) => context,
+        Err(e) => {
+            println!("{}", format!("Invalid runtime config: {}", e).red());
+            exit(1);
+        }
+    };
+    connect_mcp_from_env(&mut context);
+    if let Some(recorder) = &recorder {
+        recorder.record_tools(&mut context);
+    }
+    if let Some(player) = &player {
+        player.replay_tools(&mut context);
+    }
+    let llm = llm
+        .with_usage(context.usage.clone())
+        .with_secrets(context.secrets.clone());
+    if matches!(
+        cli.command,
+        Command::Run { .. } | Command::Exec { .. } | Command::Resume { .. }
+    ) {
+        show_progress(&context);
+    }
+    if matches!(
+        cli.command,
+        Command::Run { .. } | Command::Exec { .. } | Command::Resume { .. } | Command::Tui { .. }
+    ) {
+        cancel_on_interrupt(context.cancel.clone());
+        let processes = context.processes.clone();
+        on_forced_exit(move || {
+            processes.kill_all();
+        });
+    }
+
+    match cli.command {
+        Command::ToolsList => list_tools(&context),
+        Command::McpServe => {
+            // Stdin carries the protocol, so only destructive tools are refused rather than prompted.
+            let context = context.with_approval(ApprovalPolicy::AskDestructive);
+            if let Err(e) = McpServer::new(context).serve_stdio() {
+                eprintln!("MCP server stopped: {}", e);
+            }
+        }
+        Command::Plan { goal } => plan_goal(&goal, context, &llm, &config, cli.output.as_deref()),
+        Command::Exec { path, goal } => exec_plan(&path, goal, context, &llm, &config),
+        Command::Run { goal } => run_goal(&goal, context, &llm, &config),
+        Command::Tui { goal } => tui_goal(&goal, context, &llm, &config),
+        Command::Resume { run_id } => resume_run(&run_id, context, &llm, &config),
+        _ => unreachable!("handled before loading the config"),
+    }
+
+    if let (Some(recorder), Some(path)) = (&recorder, &cli.record) {
+        match recorder.save(path) {
+            Ok(()) => println!("{}", format!("📼 Recorded cassette to {}", path).green()),
+            Err(e) => println!("{}", e.red()),
+        }
+    }
+    if let Some(player) = &player {
+        match player.verify() {
+            Ok(()) => println!("{}", "📼 Playback matched the cassette".green()),
+            Err(e) => println!("{}", e.yellow()),
+        }
+    }
+    if interrupted() {
+        exit(130);
+    }
+}
+
+/// Shows each step as it starts and finishes, with command output line by
+/// line as it arrives, on stderr when that is a terminal.
+fn show_progress(context: &Context) {
+    if !std::io::stderr().is_terminal() {
+        return;
+    }
+    context.subscribe(|event: &AgentEvent| match event {
+        AgentEvent::StepStarted { tool, input, .. } => {
+            eprintln!("{} {} {}", "▶".blue(), tool.bold(), input)
+        }
+        AgentEvent::StepOutput { stream, line, .. } => {
+            let line = format!("  │ {}", line);
+            match stream.as_str() {
+                "stderr" => eprintln!("{}", line.yellow().dimmed()),
+                _ => eprintln!("{}", line.dimmed()),
+            }
+        }
+        AgentEvent::StepFinished { step } if step.success => {
+            eprintln!("{} {}", "✓".green(), step.tool)
+        }
+        AgentEvent::StepFinished { step } => eprintln!("{} {}", "✗".red(), step.tool),
+        _ => {}
+    });
+}
+
+/// AGENTIC_MCP_SERVER adds a server's tools, e.g. "npx -y @modelcontextprotocol/server-filesystem ."
+/// or an SSE endpoint like "http://localhost:8931/sse".
+fn connect_mcp_from_env(context: &mut Context) {
+    let Ok(server) = std::env::var("AGENTIC_MCP_SERVER") else {
+        return;
+    };
+    let client = if server.starts_with("http://") || server.starts_with("https://") {
+        McpClient::connect_sse(&server)
+    } else {
+        let mut parts = server.split_whitespace();
+        let command = parts.next().unwrap_or_default();
+        McpClient::connect_stdio(command, &parts.collect::<Vec<_>>())
+    };
+    match client.and_then(|client| context.connect_mcp(client)) {
+        Ok(names) => println!("{}", format!("MCP tools: {}", names.join(", ")).dimmed()),
+        Err(e) => println!("{}", format!("MCP connection failed: {}", e).red()),
+    }
+}
+
+fn list_tools(context: &Context) {
+    for spec in context.tools.list_specs() {
+        println!("{} — {}", spec.name.green().bold(), spec.description);
+        if !spec.input_hint.is_empty() {
+            println!("    input: {}", spec.input_hint.dimmed());
+        }
+    }
+}
+
+fn build_agent(
+    goal: &str,
+    context: Context,
+    llm: &LLMTool,
+    config: &RuntimeConfig,
+    failure_store: Arc<Mutex<FailedPlanStore>>,
+    lessons: Arc<Mutex<LessonsStore>>,
+) -> BasicAgent {
+    let mut knowledge = KnowledgeBase::new(Box::new(HashingEmbedder::default()));
+    if let Err(e) = knowledge.ingest_dir(Path::new(".")) {
+        println!("{}", format!("Knowledge ingestion failed: {}", e).red());
+    }
+    let planner = LLMPlanner::new(llm.clone())
+        .with_failure_store(failure_store)
+        .with_lessons_store(lessons)
+        .with_knowledge_base(Arc::new(knowledge));
+    config.build_agent(goal, context, llm, planner)
+}
 
-    let llm = LLMTool::new("qwen3:8b");
-    let planner = Box::new(LLMPlanner::new(llm.clone()));
-    let replanner = Box::new(LLMReplanner::new(llm.clone())); // also uses it
+/// With `edit_plan`, lets the user edit `plan` before it runs; `None` if they abort.
+fn confirm_plan(plan: Plan, agent: &BasicAgent, config: &RuntimeConfig) -> Option<Plan> {
+    if !config.agent.edit_plan {
+        return Some(plan);
+    }
+    let plan = edit_plan(plan, &agent.context);
+    if plan.is_none() {
+        println!("{}", "Plan aborted; nothing was executed".yellow());
+    }
+    plan
+}
 
-    let context = Context::new()
-        .register_tool(ReflectorTool::new(llm.clone())) // give one clone to Reflector
-        .register_tool(llm.clone()) // register as a tool under "llm"
-        .register_tool(RunCommandTool)
-        .register_tool(ErrorAnalyzerTool::new(llm.clone())) // AI-powered error analysis
-        .enable_dry_run();
+fn open_failure_store() -> Arc<Mutex<FailedPlanStore>> {
+    Arc::new(Mutex::new(FailedPlanStore::open(
+        ".agentic/failed_plans.jsonl",
+    )))
+}
+
+fn open_lessons_store() -> Arc<Mutex<LessonsStore>> {
+    Arc::new(Mutex::new(LessonsStore::open(".agentic/lessons.jsonl")))
+}
 
-    let mut agent = BasicAgent {
-        model,
+/// Plans and simulates `goal` without executing anything, optionally saving
+/// the plan for `agentic exec`.
+fn plan_goal(
+    goal: &str,
+    context: Context,
+    llm: &LLMTool,
+    config: &RuntimeConfig,
+    output: Option<&str>,
+) {
+    let mut agent = build_agent(
+        goal,
         context,
-        planner: Some(planner),
-        replanner: Some(replanner),
+        llm,
+        config,
+        open_failure_store(),
+        open_lessons_store(),
+    );
+    let Some(plan) = confirm_plan(agent.plan(), &agent, config) else {
+        return;
+    };
+    let sim = agent.simulate(&plan);
+
+    let plan_json = serde_json::to_string_pretty(&plan.to_json()).unwrap_or_default();
+    println!("{}\n{}", "--- PLAN ---".blue().bold(), plan_json);
+    println!("{}\n{:#?}", "--- SIMULATION ---".yellow().bold(), sim);
+
+    if let Some(path) = output {
+        match plan.to_file(path) {
+            Ok(()) => println!(
+                "{}",
+                format!(
+                    "Plan saved to {}; run it with `agentic exec {} \"{}\"`",
+                    path, path, goal
+                )
+                .dimmed()
+            ),
+            Err(e) => println!("{}", format!("Failed to save plan: {}", e).red()),
+        }
+    }
+}
+
+/// Executes a saved plan file as-is, without the LLM planner.
+fn exec_plan(
+    path: &str,
+    goal: Option<String>,
+    context: Context,
+    llm: &LLMTool,
+    config: &RuntimeConfig,
+) {
+    let plan = match Plan::from_file(path) {
+        Ok(plan) => plan,
+        Err(e) => {
+            println!("{}", e.red());
+            exit(1);
+        }
+    };
+    let goal = goal.unwrap_or_else(|| format!("Execute the plan in {}", path));
+    let model = TaskModel::new(&goal).with_limits(config.limits.run_limits());
+    let mut agent = BasicAgent::new(model, context, None, None);
+    if config.agent.verify {
+        agent = agent.with_verifier(Box::new(GoalVerifierTool::new(llm.clone())));
+    }
+    if config.agent.judge {
+        agent = agent.with_evaluator(Box::new(LLMJudge::new(llm.clone())));
+    }
+    let Some(plan) = confirm_plan(plan, &agent, config) else {
+        return;
     };
 
-    // Primary Planning Cycle
-    let plan = agent.plan();
     let sim = agent.simulate(&plan);
-    let exec = agent.execute(&plan);
+    let snapshot = snapshot_workspace(&agent, config, Some(&sim));
+    let (run_id, exec) = AgentRunner::new(&mut agent).execute(&plan);
     let feedback = agent.evaluate(&exec);
+    println!("{}\n{:#?}", "--- SIMULATION ---".yellow().bold(), sim);
+    println!("{}\n{:#?}", "--- EXECUTION ---".green().bold(), exec);
+    println!("{}\n{:#?}", "--- FEEDBACK ---".magenta().bold(), feedback);
+    if !exec.success {
+        println!(
+            "{}",
+            format!("Resume with `agentic resume {}`", run_id).dimmed()
+        );
+    }
+
+    let mut report = RunReport::new(&agent.model.goal);
+    report.add_iteration("Plan file", &plan, &sim, &exec, Some(&feedback));
+    if agent.context.cancel.is_cancelled() {
+        report.mark_cancelled();
+    }
+    if let Some(snapshot) = snapshot {
+        settle_snapshot(snapshot, report.success());
+    }
+    archive_report(report, &agent, config);
+}
+
+fn run_goal(goal: &str, context: Context, llm: &LLMTool, config: &RuntimeConfig) {
+    let failure_store = open_failure_store();
+    let lessons = open_lessons_store();
+    let mut agent = build_agent(
+        goal,
+        context,
+        llm,
+        config,
+        failure_store.clone(),
+        lessons.clone(),
+    );
+
+    // Primary Planning Cycle: the goal as a whole, or subtask by subtask
+    let (plan, sim, snapshot, run_id, exec) = if config.agent.decompose {
+        // Subtask plans are only known as they run, so snapshot up front.
+        let snapshot = snapshot_workspace(&agent, config, None);
+        let (plan, exec) = agent.execute_subtasks();
+        let sim = agent.simulate(&plan);
+        (plan, sim, snapshot, None, exec)
+    } else {
+        let Some(plan) = confirm_plan(agent.plan(), &agent, config) else {
+            return;
+        };
+        let sim = agent.simulate(&plan);
+        let snapshot = snapshot_workspace(&agent, config, Some(&sim));
+        let (run_id, exec) = AgentRunner::new(&mut agent).execute(&plan);
+        (plan, sim, snapshot, Some(run_id), exec)
+    };
+    let feedback = agent.evaluate(&exec);
+    let mut report = RunReport::new(&agent.model.goal);
+    report.add_iteration("Primary", &plan, &sim, &exec, Some(&feedback));
 
     println!("{}\n{:#?}", "--- PLAN ---".blue().bold(), plan);
     println!("{}\n{:#?}", "--- SIMULATION ---".yellow().bold(), sim);
     println!("{}\n{:#?}", "--- EXECUTION ---".green().bold(), exec);
+    if let Some(subtasks) = &agent.model.subtasks {
+        println!(
+            "{}\n{}",
+            "--- SUBTASKS ---".green().bold(),
+            subtasks.progress()
+        );
+    }
     println!("{}\n{:#?}", "--- FEEDBACK ---".magenta().bold(), feedback);
+    if agent.context.cancel.is_cancelled() {
+        println!(
+            "{}",
+            "⏹️ Interrupted; skipping reflection and replanning"
+                .yellow()
+                .bold()
+        );
+        if let Some(run_id) = &run_id {
+            println!(
+                "{}",
+                format!("Resume with `agentic resume {}`", run_id).dimmed()
+            );
+        }
+        report.mark_cancelled();
+        if let Some(snapshot) = snapshot {
+            settle_snapshot(snapshot, false);
+        }
+        archive_report(report, &agent, config);
+        return;
+    }
     println!("{}", "--- MEMORY LOG ---".cyan().bold());
 
     for (label, content) in agent.context.memory().read_all() {
@@ -51,6 +450,7 @@ fn main() {
             format_args!("input: {}", content)
         );
     }
+    print!("{}", agent.context.tool_history.prompt_section(usize::MAX));
 
     // Reflection Tool Summary
     if let Some(tool) = agent.context.get_tool("reflect") {
@@ -60,6 +460,7 @@ fn main() {
             .read_all()
             .iter()
             .map(|(k, v)| format!("[{}] {}", k, v))
+            .chain([agent.context.tool_history.prompt_section(usize::MAX)])
             .collect::<Vec<_>>()
             .join("\n");
 
@@ -72,49 +473,98 @@ fn main() {
 
         if let Some(summary) = reflection.output {
             agent.context.log("reflect", &summary);
+            remember_lessons(&agent, &lessons, exec.goal_achieved(), &summary);
         }
     } else {
         println!("{}", "ReflectorTool not found".red());
     }
 
-    // 🔁 Follow-up Plan Based on Error Analysis or Reflection
-    let memory_entries = agent.context.memory().read_all();
-
-    // 🎯 DYNAMIC INTELLIGENCE: Only replan if there were critical failures
-    // Don't replan for auxiliary tool failures (like reflection failures)
-    if !exec.success {
-        // Check if we have error analysis for critical failures
-        if let Some((_, error_analysis)) =
-            memory_entries.iter().find(|(k, _)| k == "error_analysis")
+    // 🎯 DYNAMIC INTELLIGENCE: Only replan if the verifier (or, without one, the
+    // critical tools) says the goal wasn't met, or the run scored below
+    // `min_score`. Auxiliary failures don't count.
+    let below_min_score = config
+        .agent
+        .min_score
+        .is_some_and(|min| feedback.score < min);
+    if !exec.goal_achieved() || below_min_score {
+        if let Some((_, error_analysis)) = agent
+            .context
+            .memory()
+            .read_all()
+            .into_iter()
+            .find(|(k, _)| k == "error_analysis")
         {
-            if let Some(followup_plan) = agent.replan(error_analysis) {
-                println!(
-                    "{}\n{:#?}",
-                    "--- FOLLOW-UP PLAN (Error Recovery) ---"
-                        .bright_blue()
-                        .bold(),
-                    followup_plan
-                );
-                let sim = agent.simulate(&followup_plan);
-                println!("{}\n{:#?}", "--- SIMULATION (2) ---".yellow().bold(), sim);
-                let exec = agent.execute(&followup_plan);
-                println!("{}\n{:#?}", "--- EXECUTION (2) ---".green().bold(), exec);
+            let record = FailedPlanRecord::from_plan(
+                &agent.model.goal,
+                &plan,
+                exec.errors.clone(),
+                &error_analysis,
+            );
+            if let Err(e) = failure_store.lock().unwrap().record(record) {
+                println!("{}", format!("Failed to record failed plan: {}", e).red());
             }
         }
-        // If no error analysis, fall back to reflection-based planning
-        else if let Some((_, reflection)) = memory_entries.iter().find(|(k, _)| k == "reflect") {
-            if let Some(followup_plan) = agent.replan(reflection) {
-                println!(
-                    "{}\n{:#?}",
-                    "--- FOLLOW-UP PLAN (Reflection) ---".bright_blue().bold(),
-                    followup_plan
-                );
-                let sim = agent.simulate(&followup_plan);
-                println!("{}\n{:#?}", "--- SIMULATION (2) ---".yellow().bold(), sim);
-                let exec = agent.execute(&followup_plan);
-                println!("{}\n{:#?}", "--- EXECUTION (2) ---".green().bold(), exec);
+
+        // 🔁 Follow-up plans until the goal is met or the replan policy gives up
+        let policy = config.replan.policy(llm).unwrap_or_else(|e| {
+            println!("{}", format!("{}; replanning without escalation", e).red());
+            ReplanPolicy::new(config.replan.max_replans)
+        });
+        let mut guard = policy.guard();
+        let mut last = exec.clone();
+        let mut first = true;
+        while let Some((label, title, mut reason)) =
+            followup_reason(&agent, &last, first.then_some(&feedback), below_min_score)
+        {
+            match agent.check_replan(&mut guard, &last) {
+                ReplanDecision::Replan { guidance } => {
+                    if let Some(guidance) = guidance {
+                        reason.push_str(&format!("\n\nUser guidance: {}", guidance));
+                    }
+                }
+                ReplanDecision::Abort(summary) => {
+                    println!("{}", summary.red().bold());
+                    report.aborted = Some(summary);
+                    break;
+                }
+            }
+            let Some(followup_plan) = agent.replan(&reason) else {
+                break;
+            };
+            let n = guard.attempts() + 1;
+            println!(
+                "{}\n{:#?}",
+                format!("--- FOLLOW-UP PLAN ({}) ---", title)
+                    .bright_blue()
+                    .bold(),
+                followup_plan
+            );
+            let sim = agent.simulate(&followup_plan);
+            println!(
+                "{}\n{:#?}",
+                format!("--- SIMULATION ({}) ---", n).yellow().bold(),
+                sim
+            );
+            last = agent.execute(&followup_plan);
+            println!(
+                "{}\n{:#?}",
+                format!("--- EXECUTION ({}) ---", n).green().bold(),
+                last
+            );
+            report.add_iteration(label, &followup_plan, &sim, &last, None);
+            first = false;
+            if last.goal_achieved() || agent.context.cancel.is_cancelled() {
+                break;
             }
         }
+        if !exec.success
+            && let Some(run_id) = &run_id
+        {
+            println!(
+                "{}",
+                format!("Resume the primary plan with `agentic resume {}`", run_id).dimmed()
+            );
+        }
     } else {
         println!(
             "{}",
@@ -123,4 +573,418 @@ fn main() {
                 .bold()
         );
     }
+
+    if agent.context.cancel.is_cancelled() {
+        report.mark_cancelled();
+    }
+    if let Some(snapshot) = snapshot {
+        settle_snapshot(snapshot, report.success());
+    }
+    archive_report(report, &agent, config);
+}
+
+/// What the next follow-up plan should address, as (report label, title,
+/// replanner reflection): the latest error analysis, else the verifier's
+/// verdict on `last`, else the evaluator's critique (`feedback`, for the
+/// primary run only), else the latest reflection.
+fn followup_reason(
+    agent: &BasicAgent,
+    last: &ExecutionResult,
+    feedback: Option<&Feedback>,
+    below_min_score: bool,
+) -> Option<(&'static str, &'static str, String)> {
+    let memory_entries = agent.context.memory().read_all();
+    let latest = |label: &str| {
+        memory_entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == label)
+            .map(|(_, content)| content.clone())
+    };
+    if let Some(error_analysis) = latest("error_analysis") {
+        return Some(("Error recovery", "Error Recovery", error_analysis));
+    }
+    if let Some(verdict) = last.verdict.as_ref().filter(|verdict| !verdict.achieved) {
+        return Some(("Verifier follow-up", "Verifier", verdict.followup_prompt()));
+    }
+    if let Some(feedback) = feedback
+        && (below_min_score || agent.evaluator.is_some())
+        && !feedback.critique.is_empty()
+    {
+        return Some((
+            "Evaluation follow-up",
+            "Evaluation",
+            feedback.followup_prompt(),
+        ));
+    }
+    latest("reflect").map(|reflection| ("Reflection follow-up", "Reflection", reflection))
+}
+
+/// Saves the reflection's lessons under the goal type the planner logged.
+fn remember_lessons(
+    agent: &BasicAgent,
+    lessons: &Mutex<LessonsStore>,
+    goal_achieved: bool,
+    reflection: &str,
+) {
+    let goal_type = agent
+        .context
+        .memory()
+        .read_all()
+        .into_iter()
+        .rev()
+        .find(|(label, _)| label == "goal_type")
+        .map_or_else(|| "general".to_string(), |(_, goal_type)| goal_type);
+    let record =
+        LessonRecord::from_reflection(&goal_type, &agent.model.goal, goal_achieved, reflection);
+    if let Err(e) = lessons.lock().unwrap().record(record) {
+        println!("{}", format!("Failed to record lessons: {}", e).red());
+    }
+}
+
+/// Snapshots the workspace (or the current directory) before a run, if the
+/// config asks for it; a failed snapshot is reported but does not stop the run.
+/// Without a simulation the run's risk is unknown and treated as high.
+fn snapshot_workspace(
+    agent: &BasicAgent,
+    config: &RuntimeConfig,
+    sim: Option<&SimulationResult>,
+) -> Option<WorkspaceSnapshot> {
+    match (config.workspace.snapshot, sim) {
+        (SnapshotMode::Off, _) => return None,
+        (SnapshotMode::Risky, Some(sim)) if sim.max_risk() < Some(RiskLevel::High) => {
+            return None;
+        }
+        _ => {}
+    }
+    let snapshot = match &agent.context.workspace {
+        Some(workspace) => Ok(workspace.clone()),
+        None => Workspace::new("."),
+    }
+    .and_then(|workspace| WorkspaceSnapshot::take_with(&workspace, config.workspace.strategy));
+    match snapshot {
+        Ok(snapshot) => {
+            println!(
+                "{}",
+                format!("📸 Workspace snapshot taken ({:?})", snapshot.strategy()).dimmed()
+            );
+            Some(snapshot)
+        }
+        Err(e) => {
+            println!("{}", format!("⚠️ Workspace snapshot failed: {}", e).red());
+            None
+        }
+    }
+}
+
+/// Keeps a successful run's changes; rolls back an unsuccessful one.
+fn settle_snapshot(snapshot: WorkspaceSnapshot, success: bool) {
+    if success {
+        if let Err(e) = snapshot.discard() {
+            println!("{}", format!("⚠️ Failed to discard snapshot: {}", e).red());
+        }
+        return;
+    }
+    match snapshot.restore() {
+        Ok(()) => println!(
+            "{}",
+            "↩️ Goal not achieved; workspace restored to its snapshot".yellow()
+        ),
+        Err(e) => println!("{}", format!("❌ Failed to restore workspace: {}", e).red()),
+    }
+}
+
+/// `run` under a full-screen `Dashboard`: plan, execute and replan per
+/// `[replan]`, then print the outcome on the normal screen and archive it.
+fn tui_goal(goal: &str, context: Context, llm: &LLMTool, config: &RuntimeConfig) {
+    let policy = config.replan.policy(llm).unwrap_or_else(|e| {
+        println!("{}", format!("{}; replanning without escalation", e).red());
+        ReplanPolicy::new(config.replan.max_replans)
+    });
+    let dashboard = Dashboard::new(goal, context.memory.clone());
+    let mut agent = build_agent(
+        goal,
+        context,
+        llm,
+        config,
+        open_failure_store(),
+        open_lessons_store(),
+    );
+    dashboard.attach(&mut agent.context);
+    let screen = dashboard.clone();
+    on_forced_exit(move || screen.leave());
+
+    dashboard.enter();
+    let report = agent.run(&policy);
+    dashboard.leave();
+
+    if report.success() {
+        println!("{}", format!("✅ Achieved: {}", goal).green().bold());
+    } else {
+        println!("{}", format!("❌ Not achieved: {}", goal).red().bold());
+    }
+    if let Some(aborted) = &report.aborted {
+        println!("{}", aborted.yellow());
+    }
+    archive_report(report, &agent, config);
+}
+
+/// Winds the run down (see `Context::wind_down`), prints LLM usage and
+/// archives the run as JSON + Markdown for `replay`.
+fn archive_report(report: RunReport, agent: &BasicAgent, config: &RuntimeConfig) {
+    agent.context.wind_down();
+    #[cfg(feature = "desktop")]
+    if config.agent.notify {
+        let message = match report.success() {
+            true => format!("✅ Achieved: {}", report.goal),
+            false => format!("❌ Not achieved: {}", report.goal),
+        };
+        if let Err(e) = agentic_runtime::tools::desktop::notify("agentic", &message) {
+            println!("{}", format!("⚠️ {}", e).yellow());
+        }
+    }
+    if let Some(path) = &config.memory.export {
+        match agent.context.memory().export_jsonl(Path::new(path)) {
+            Ok(n) => println!(
+                "{}",
+                format!("Exported {} memory entries to {}", n, path).dimmed()
+            ),
+            Err(e) => println!("{}", format!("Failed to export memory: {}", e).red()),
+        }
+    }
+    let usage = agent.context.usage.totals();
+    println!(
+        "{}",
+        format!(
+            "LLM usage: {} call(s), {} prompt + {} completion tokens, ~${:.4}",
+            usage.calls, usage.prompt_tokens, usage.completion_tokens, usage.cost_usd
+        )
+        .dimmed()
+    );
+    let report = report
+        .with_memory(agent.context.memory().read_all())
+        .with_tool_calls(agent.context.tool_history.all())
+        .with_usage(agent.context.usage.summary());
+    print!(
+        "{}\n{}",
+        "--- RUN SUMMARY ---".cyan().bold(),
+        report.summary()
+    );
+    match report.save(Path::new(RUNS_DIR)) {
+        Ok(path) => println!(
+            "{}",
+            format!("Run report saved to {}", path.display()).dimmed()
+        ),
+        Err(e) => println!("{}", format!("Failed to save run report: {}", e).red()),
+    }
+}
+
+/// `agentic batch`: queues every goal of `path` (one per line, `#` comments)
+/// and prints each outcome once all have finished.
+fn batch(path: &str, config: &RuntimeConfig) {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| {
+        println!("{}", format!("Failed to read {}: {}", path, e).red());
+        exit(1);
+    });
+    let goals: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let queue = JobQueue::new(config.queue.workers);
+    for goal in &goals {
+        queue.enqueue(goal, config, config.queue.job_timeout());
+    }
+    println!(
+        "{}",
+        format!(
+            "📋 Queued {} goal(s), {} at a time",
+            goals.len(),
+            config.queue.workers
+        )
+        .green()
+    );
+    queue.wait_all();
+
+    for job in queue.list() {
+        let status = format!("{:?}", job.status);
+        let status = if job.status == JobStatus::Succeeded {
+            status.green()
+        } else {
+            status.red()
+        };
+        println!("{:>3}. {} {}", job.id, status, job.goal);
+        if let Some(aborted) = queue.report(job.id).and_then(|report| report.aborted) {
+            println!("     {}", aborted.replace('\n', " ").dimmed());
+        }
+    }
+}
+
+/// `agentic schedule`: runs the `[schedule]` jobs as they come due, lists
+/// them, or runs one now.
+fn schedule(command: &Command, config: RuntimeConfig) {
+    let scheduler = Scheduler::new(config, Path::new(DEFAULT_SCHEDULE_DIR)).unwrap_or_else(|e| {
+        println!("{}", e.red());
+        exit(1);
+    });
+    match command {
+        Command::ScheduleList => {
+            let history = scheduler.history().unwrap_or_else(|e| {
+                println!("{}", e.red());
+                Vec::new()
+            });
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            for (job, next) in scheduler.next_runs(now) {
+                let next = next.map_or("never".to_string(), |secs| {
+                    UtcTime::from_unix(secs).to_string()
+                });
+                let last = match history.iter().rev().find(|run| run.job == job) {
+                    Some(run) => format!(
+                        "{} {}",
+                        if run.success { "✅" } else { "❌" },
+                        UtcTime::from_unix(run.started_at)
+                    ),
+                    None => "never".to_string(),
+                };
+                println!("{}  next: {}  last: {}", job.bold(), next, last.dimmed());
+            }
+        }
+        Command::ScheduleRun { job } => match scheduler.run_now(job) {
+            Ok(run) if run.success => {
+                println!("{}", format!("✅ {} achieved its goal", job).green())
+            }
+            Ok(run) => println!(
+                "{}",
+                format!(
+                    "❌ {} did not achieve its goal{}",
+                    job,
+                    run.aborted.map(|a| format!(": {}", a)).unwrap_or_default()
+                )
+                .red()
+            ),
+            Err(e) => println!("{}", e.red()),
+        },
+        _ => {
+            println!("{}", "⏰ Scheduler started".green());
+            if let Err(e) = scheduler.run() {
+                println!("{}", e.red());
+                exit(1);
+            }
+        }
+    }
+}
+
+/// Continues a run persisted by `AgentRunner` from its last successful step.
+fn resume_run(run_id: &str, context: Context, llm: &LLMTool, config: &RuntimeConfig) {
+    let state = match RunState::load(Path::new(DEFAULT_STATE_DIR), run_id) {
+        Ok(state) => state,
+        Err(e) => {
+            println!("{}", e.red());
+            exit(1);
+        }
+    };
+    let mut agent = build_agent(
+        &state.goal,
+        context,
+        llm,
+        config,
+        open_failure_store(),
+        open_lessons_store(),
+    );
+    if config.agent.verify {
+        agent = agent.with_verifier(Box::new(GoalVerifierTool::new(llm.clone())));
+    }
+
+    let plan = match Plan::from_json(&state.plan) {
+        Ok(plan) => plan,
+        Err(e) => {
+            println!("{}", e.red());
+            exit(1);
+        }
+    };
+
+    let sim = agent.simulate(&plan);
+    let exec = match AgentRunner::new(&mut agent).resume(run_id) {
+        Ok(exec) => exec,
+        Err(e) => {
+            println!("{}", e.red());
+            exit(1);
+        }
+    };
+    let feedback = agent.evaluate(&exec);
+    println!("{}\n{:#?}", "--- EXECUTION ---".green().bold(), exec);
+    println!("{}\n{:#?}", "--- FEEDBACK ---".magenta().bold(), feedback);
+    if !exec.success {
+        println!(
+            "{}",
+            format!("Resume with `agentic resume {}`", run_id).dimmed()
+        );
+    }
+
+    let mut report = RunReport::new(&agent.model.goal);
+    report.add_iteration("Resumed", &plan, &sim, &exec, Some(&feedback));
+    if agent.context.cancel.is_cancelled() {
+        report.mark_cancelled();
+    }
+    archive_report(report, &agent, config);
+}
+
+/// Resolves `latest`, `run-<ts>` or `<ts>` to an archived Markdown report.
+fn find_run(run_id: &str) -> Result<std::path::PathBuf, String> {
+    if run_id == "latest" {
+        return run_ids()?
+            .last()
+            .map(|id| Path::new(RUNS_DIR).join(format!("{}.md", id)))
+            .ok_or_else(|| format!("No runs archived in {}", RUNS_DIR));
+    }
+    let id = if run_id.starts_with("run-") {
+        run_id.to_string()
+    } else {
+        format!("run-{}", run_id)
+    };
+    let path = Path::new(RUNS_DIR).join(format!("{}.md", id));
+    if path.exists() {
+        Ok(path)
+    } else {
+        Err(format!("No archived run '{}' in {}", run_id, RUNS_DIR))
+    }
+}
+
+/// Archived run ids, oldest first.
+fn run_ids() -> Result<Vec<String>, String> {
+    if !Path::new(RUNS_DIR).exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(RUNS_DIR).map_err(|e| format!("{}: {}", RUNS_DIR, e))?;
+    let mut ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_suffix(".json").map(String::from)
+        })
+        .filter(|id| id.starts_with("run-"))
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+fn replay(run_id: &str) {
+    match find_run(run_id).and_then(|path| fs::read_to_string(path).map_err(|e| e.to_string())) {
+        Ok(report) => println!("{}", report),
+        Err(e) => {
+            println!("{}", e.red());
+            exit(1);
+        }
+    }
+}
+
+fn list_runs() {
+    match run_ids() {
+        Ok(ids) if ids.is_empty() => println!("No runs archived in {}", RUNS_DIR),
+        Ok(ids) => ids.iter().rev().for_each(|id| println!("{}", id)),
+        Err(e) => println!("{}", e.red()),
+    }
 }
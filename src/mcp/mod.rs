@@ -0,0 +1,283 @@
+// src/mcp/mod.rs
+
+pub mod server;
+pub mod transport;
+
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+#[cfg(feature = "http")]
+use transport::SseTransport;
+use transport::{StdioTransport, Transport};
+
+/// MCP protocol revision announced during the handshake.
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// A tool advertised by an MCP server's `tools/list`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct McpToolInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "inputSchema", default)]
+    pub input_schema: Option<Value>,
+}
+
+/// JSON-RPC client for one MCP server (stdio or SSE).
+pub struct McpClient {
+    pub server_name: String,
+    transport: Box<dyn Transport>,
+    next_id: AtomicU64,
+    /// Serializes request/response pairs on the shared transport.
+    in_flight: Mutex<()>,
+    pub timeout: Duration,
+}
+
+impl McpClient {
+    /// Launches `command args…` and performs the MCP handshake over stdio.
+    pub fn connect_stdio(command: &str, args: &[&str]) -> Result<Self, String> {
+        let transport = StdioTransport::spawn(command, args)?;
+        Self::connect(Box::new(transport))
+    }
+
+    /// Connects to an HTTP+SSE server, e.g. `http://localhost:8931/sse`.
+    #[cfg(feature = "http")]
+    pub fn connect_sse(url: &str) -> Result<Self, String> {
+        let transport = SseTransport::connect(url, Duration::from_secs(30))?;
+        Self::connect(Box::new(transport))
+    }
+
+    #[cfg(not(feature = "http"))]
+    pub fn connect_sse(url: &str) -> Result<Self, String> {
+        Err(format!(
+            "Cannot connect to {}: built without the \"http\" feature",
+            url
+        ))
+    }
+
+    /// Performs the `initialize` handshake over an already open transport.
+    pub fn connect(transport: Box<dyn Transport>) -> Result<Self, String> {
+        let mut client = Self {
+            server_name: "mcp".into(),
+            transport,
+            next_id: AtomicU64::new(1),
+            in_flight: Mutex::new(()),
+            timeout: Duration::from_secs(60),
+        };
+
+        let init = client.request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {
+                    "name": "agentic-runtime",
+                    "version": env!("CARGO_PKG_VERSION")
+                }
+            }),
+        )?;
+        if let Some(name) = init.pointer("/serverInfo/name").and_then(Value::as_str) {
+            client.server_name = name.into();
+        }
+        client.notify("notifications/initialized", json!({}))?;
+        Ok(client)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends a JSON-RPC request and waits for its matching response.
+    pub fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        let _guard = self.in_flight.lock().unwrap();
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.transport.send(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        }))?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let message = self.transport.receive(remaining)?;
+
+            // Server-initiated requests (e.g. ping) need an answer to keep the session alive.
+            if let Some(method) = message.get("method").and_then(Value::as_str) {
+                if let Some(request_id) = message.get("id") {
+                    let reply = if method == "ping" {
+                        json!({ "jsonrpc": "2.0", "id": request_id, "result": {} })
+                    } else {
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": request_id,
+                            "error": { "code": -32601, "message": "Method not supported by client" }
+                        })
+                    };
+                    self.transport.send(&reply)?;
+                }
+                continue;
+            }
+
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                let text = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                return Err(format!("MCP {} failed: {}", method, text));
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    pub fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        self.transport.send(&json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        }))
+    }
+
+    /// Every tool the server advertises, following pagination cursors.
+    pub fn list_tools(&self) -> Result<Vec<McpToolInfo>, String> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params)?;
+            let page: Vec<McpToolInfo> =
+                serde_json::from_value(result.get("tools").cloned().unwrap_or_else(|| json!([])))
+                    .map_err(|e| format!("Invalid tools/list response: {}", e))?;
+            tools.extend(page);
+
+            cursor = result
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(String::from);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Calls a remote tool; text content blocks are joined into the output.
+    pub fn call_tool(&self, name: &str, arguments: Value) -> ToolResult {
+        let result = match self.request(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        ) {
+            Ok(result) => result,
+            Err(e) => return ToolResult::failure(&e),
+        };
+
+        let text = result
+            .get("content")
+            .and_then(Value::as_array)
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .map(|block| match block.get("text").and_then(Value::as_str) {
+                        Some(text) => text.to_string(),
+                        None => block.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+
+        if result.get("isError").and_then(Value::as_bool) == Some(true) {
+            ToolResult::failure(&text)
+        } else {
+            ToolResult::success(&text)
+        }
+    }
+
+    /// Wraps every remote tool as a local `Tool`.
+    pub fn tools(self: &Arc<Self>) -> Result<Vec<McpTool>, String> {
+        Ok(self
+            .list_tools()?
+            .into_iter()
+            .map(|info| McpTool::new(self.clone(), info))
+            .collect())
+    }
+}
+
+/// A remote MCP tool exposed through the local `Tool` trait.
+pub struct McpTool {
+    client: Arc<McpClient>,
+    pub info: McpToolInfo,
+}
+
+impl McpTool {
+    pub fn new(client: Arc<McpClient>, info: McpToolInfo) -> Self {
+        Self { client, info }
+    }
+
+    /// Maps freeform input onto arguments: a JSON object is passed through,
+    /// anything else fills the schema's single required string property.
+    fn arguments_from_input(&self, input: &str) -> Result<Value, String> {
+        if let Ok(value @ Value::Object(_)) = serde_json::from_str::<Value>(input) {
+            return Ok(value);
+        }
+        let schema = self.info.input_schema.clone().unwrap_or(Value::Null);
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|fields| fields.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        match required.as_slice() {
+            [] if input.trim().is_empty() => Ok(json!({})),
+            [field] => Ok(json!({ *field: input })),
+            _ => Err(format!(
+                "Tool '{}' needs JSON object arguments matching its schema",
+                self.info.name
+            )),
+        }
+    }
+}
+
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    fn description(&self) -> &str {
+        &self.info.description
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        match self.arguments_from_input(input) {
+            Ok(arguments) => self.client.call_tool(&self.info.name, arguments),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        self.client.call_tool(&self.info.name, args.clone())
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.info.name.clone(),
+            description: self.info.description.clone(),
+            input_hint: "JSON object matching the args schema".into(),
+            tags: vec!["mcp".into(), self.client.server_name.clone()],
+            input_schema: self.info.input_schema.clone(),
+            timeout: Some(self.client.timeout),
+            idempotent: false,
+            examples: Vec::new(),
+        }
+    }
+}
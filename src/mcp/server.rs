@@ -0,0 +1,183 @@
+// src/mcp/server.rs
+
+use crate::context::Context;
+use crate::mcp::PROTOCOL_VERSION;
+use crate::risk::{self, RiskLevel};
+use crate::tools::{ToolResult, ToolSpec};
+use crate::validation::schema::validate_against_schema;
+use serde_json::{Value, json};
+use std::io::{BufRead, Write};
+
+/// Serves a context's registered tools over MCP (newline-delimited JSON-RPC
+/// on stdin/stdout), so other agent frameworks can call them.
+///
+/// Calls go through the same checks as the agent loop: argument schemas,
+/// `Context::allows`, the approval policy and dry-run. Stdin carries the
+/// protocol, so policies that would prompt the user reject the call instead.
+pub struct McpServer {
+    pub context: Context,
+}
+
+impl McpServer {
+    pub fn new(context: Context) -> Self {
+        Self { context }
+    }
+
+    /// Handles requests from stdin until it closes.
+    pub fn serve_stdio(&mut self) -> Result<(), String> {
+        let stdin = std::io::stdin();
+        let mut stdout = std::io::stdout();
+        for line in stdin.lock().lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(&message),
+                Err(e) => Some(error_response(&Value::Null, -32700, &e.to_string())),
+            };
+            if let Some(reply) = reply {
+                writeln!(stdout, "{}", reply)
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one JSON-RPC message; `None` for notifications.
+    pub fn handle(&mut self, message: &Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": {
+                    "name": "agentic-runtime",
+                    "version": env!("CARGO_PKG_VERSION")
+                }
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call_tool(&params),
+            _ => Err((-32601, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, text)) => error_response(&id, code, &text),
+        })
+    }
+
+    fn list_tools(&self) -> Value {
+        let tools = self
+            .context
+            .tools
+            .list_specs()
+            .into_iter()
+            .map(|spec| {
+                json!({
+                    "name": spec.name,
+                    "description": spec.description,
+                    "inputSchema": exported_schema(&spec)
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "tools": tools })
+    }
+
+    fn call_tool(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((-32602, "Missing tool name".to_string()))?;
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        let spec = self
+            .context
+            .get_tool(name)
+            .map(|tool| tool.spec())
+            .ok_or((-32602, format!("Unknown tool: {}", name)))?;
+
+        let errors = validate_against_schema(&arguments, &exported_schema(&spec));
+        if !errors.is_empty() {
+            return Err((-32602, format!("Invalid arguments: {}", errors.join("; "))));
+        }
+
+        // Tools without a schema take their freeform input as `{"input": "..."}`.
+        let (input, args) = match &spec.input_schema {
+            Some(_) => (display_input(&arguments), Some(&arguments)),
+            None => (
+                arguments["input"].as_str().unwrap_or_default().to_string(),
+                None,
+            ),
+        };
+
+        let risk = risk::assess_call(self.context.get_tool(name), name, &input, args);
+        let result = if !self.context.allows(name, &input) {
+            ToolResult::failure(&format!("Not allowed by runtime policy: {}", input))
+        } else if risk.level == RiskLevel::High {
+            ToolResult::failure(&format!(
+                "Rejected high-risk call ({}); elevated approval is unavailable over MCP",
+                risk.reasons.join("; ")
+            ))
+        } else if self.context.approval.decide_without_prompt(&spec) != Some(true) {
+            ToolResult::failure(
+                "Rejected by approval policy (interactive approval is unavailable over MCP)",
+            )
+        } else if self.context.dry_run {
+            let tool = self.context.get_tool(name).expect("tool exists");
+            tool.simulate(&input)
+        } else {
+            self.context
+                .invoke_tool(name, &input, args, None)
+                .unwrap_or_else(|| ToolResult::failure("Tool disappeared"))
+        };
+
+        self.context.log(
+            &format!("mcp: {}", name),
+            &format!("input: {}\nsuccess: {}", input, result.success),
+        );
+
+        let text = result.output.or(result.error).unwrap_or_default();
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": !result.success
+        }))
+    }
+}
+
+/// The tool's own schema, or a single string `input` field for freeform tools.
+fn exported_schema(spec: &ToolSpec) -> Value {
+    spec.input_schema.clone().unwrap_or_else(|| {
+        json!({
+            "type": "object",
+            "properties": {
+                "input": { "type": "string", "description": spec.input_hint }
+            },
+            "required": ["input"]
+        })
+    })
+}
+
+/// Text checked against allow-lists and shown in logs: a lone string
+/// argument (e.g. `command`) as-is, anything else as JSON.
+fn display_input(arguments: &Value) -> String {
+    match arguments.as_object() {
+        Some(map) if map.len() == 1 => match map.values().next() {
+            Some(Value::String(text)) => text.clone(),
+            _ => arguments.to_string(),
+        },
+        _ => arguments.to_string(),
+    }
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
+}
@@ -0,0 +1,192 @@
+// src/mcp/transport.rs
+
+#[cfg(feature = "http")]
+use reqwest::Url;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Mutex;
+#[cfg(feature = "http")]
+use std::sync::mpsc::Sender;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+#[cfg(feature = "http")]
+use std::time::Instant;
+
+/// Carries JSON-RPC messages to and from an MCP server.
+pub trait Transport: Send + Sync {
+    fn send(&self, message: &Value) -> Result<(), String>;
+
+    /// Next message from the server, waiting at most `timeout`.
+    fn receive(&self, timeout: Duration) -> Result<Value, String>;
+}
+
+fn receive_from(receiver: &Mutex<Receiver<Value>>, timeout: Duration) -> Result<Value, String> {
+    match receiver.lock().unwrap().recv_timeout(timeout) {
+        Ok(message) => Ok(message),
+        Err(RecvTimeoutError::Timeout) => {
+            Err(format!("MCP server did not respond within {:?}", timeout))
+        }
+        Err(RecvTimeoutError::Disconnected) => Err("MCP server connection closed".into()),
+    }
+}
+
+/// Launches the server as a child process and exchanges newline-delimited
+/// JSON over its stdin/stdout. The child is killed on drop.
+pub struct StdioTransport {
+    child: Mutex<Child>,
+    stdin: Mutex<ChildStdin>,
+    messages: Mutex<Receiver<Value>>,
+}
+
+impl StdioTransport {
+    pub fn spawn(command: &str, args: &[&str]) -> Result<Self, String> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start MCP server '{}': {}", command, e))?;
+
+        let stdin = child.stdin.take().ok_or("MCP server stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("MCP server stdout unavailable")?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                // Servers may print non-protocol noise; only JSON lines are messages.
+                if let Ok(message) = serde_json::from_str::<Value>(&line)
+                    && sender.send(message).is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child: Mutex::new(child),
+            stdin: Mutex::new(stdin),
+            messages: Mutex::new(receiver),
+        })
+    }
+}
+
+impl Transport for StdioTransport {
+    fn send(&self, message: &Value) -> Result<(), String> {
+        let mut stdin = self.stdin.lock().unwrap();
+        writeln!(stdin, "{}", message)
+            .and_then(|_| stdin.flush())
+            .map_err(|e| format!("Failed to write to MCP server: {}", e))
+    }
+
+    fn receive(&self, timeout: Duration) -> Result<Value, String> {
+        receive_from(&self.messages, timeout)
+    }
+}
+
+impl Drop for StdioTransport {
+    fn drop(&mut self) {
+        let mut child = self.child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// MCP's HTTP+SSE transport: server messages arrive on a long-lived
+/// `text/event-stream`, and client messages are POSTed to the endpoint the
+/// server announces in its first `endpoint` event.
+#[cfg(feature = "http")]
+pub struct SseTransport {
+    http: reqwest::blocking::Client,
+    endpoint: Url,
+    messages: Mutex<Receiver<Value>>,
+}
+
+#[cfg(feature = "http")]
+impl SseTransport {
+    pub fn connect(url: &str, timeout: Duration) -> Result<Self, String> {
+        let base = Url::parse(url).map_err(|e| format!("Invalid MCP server URL: {}", e))?;
+        let stream_client = reqwest::blocking::Client::builder()
+            .timeout(None)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let response = stream_client
+            .get(base.clone())
+            .header("Accept", "text/event-stream")
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to open MCP event stream: {}", e))?;
+
+        let (endpoint_sender, endpoint_receiver) = mpsc::channel();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || read_events(response, endpoint_sender, sender));
+
+        let deadline = Instant::now() + timeout;
+        let path = endpoint_receiver
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|_| "MCP server did not announce a message endpoint".to_string())?;
+        let endpoint = base
+            .join(&path)
+            .map_err(|e| format!("Invalid MCP endpoint '{}': {}", path, e))?;
+
+        Ok(Self {
+            http: reqwest::blocking::Client::new(),
+            endpoint,
+            messages: Mutex::new(receiver),
+        })
+    }
+}
+
+/// Parses the event stream: `endpoint` events carry the POST URL, `message`
+/// events carry JSON-RPC messages.
+#[cfg(feature = "http")]
+fn read_events(
+    response: reqwest::blocking::Response,
+    endpoint: Sender<String>,
+    messages: Sender<Value>,
+) {
+    let mut event = String::from("message");
+    let mut data = String::new();
+
+    for line in BufReader::new(response).lines() {
+        let Ok(line) = line else { break };
+        if line.is_empty() {
+            if event == "endpoint" {
+                let _ = endpoint.send(data.trim().to_string());
+            } else if let Ok(message) = serde_json::from_str::<Value>(&data)
+                && messages.send(message).is_err()
+            {
+                break;
+            }
+            event = "message".into();
+            data.clear();
+        } else if let Some(name) = line.strip_prefix("event:") {
+            event = name.trim().to_string();
+        } else if let Some(chunk) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(chunk.strip_prefix(' ').unwrap_or(chunk));
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Transport for SseTransport {
+    fn send(&self, message: &Value) -> Result<(), String> {
+        self.http
+            .post(self.endpoint.clone())
+            .json(message)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map(|_| ())
+            .map_err(|e| format!("Failed to post to MCP server: {}", e))
+    }
+
+    fn receive(&self, timeout: Duration) -> Result<Value, String> {
+        receive_from(&self.messages, timeout)
+    }
+}
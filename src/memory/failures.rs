@@ -0,0 +1,199 @@
+// src/memory/failures.rs
+
+use crate::llm::json_repair;
+use crate::protocol::Plan;
+use crate::tools::Tool;
+use crate::tools::llm::LLMTool;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// A plan that failed validation or execution, kept so future planning can learn from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedPlanRecord {
+    pub goal: String,
+    pub plan_json: String,
+    pub validation_errors: Vec<String>,
+    pub diagnosis: String,
+    #[serde(default)]
+    pub corrected_plan: Option<String>,
+}
+
+impl FailedPlanRecord {
+    pub fn new(
+        goal: &str,
+        plan_json: &str,
+        validation_errors: Vec<String>,
+        diagnosis: &str,
+    ) -> Self {
+        Self {
+            goal: goal.into(),
+            plan_json: plan_json.into(),
+            validation_errors,
+            diagnosis: diagnosis.into(),
+            corrected_plan: None,
+        }
+    }
+
+    pub fn from_plan(
+        goal: &str,
+        plan: &Plan,
+        validation_errors: Vec<String>,
+        diagnosis: &str,
+    ) -> Self {
+        Self::new(
+            goal,
+            &plan_to_json(plan).to_string(),
+            validation_errors,
+            diagnosis,
+        )
+    }
+
+    /// Asks the LLM to rewrite the failed plan so it avoids the recorded errors.
+    pub fn synthesize_correction(&mut self, llm: &LLMTool) {
+        let prompt = format!(
+            r#"A planning agent produced the plan below for the goal "{}", and it failed.
+
+FAILED PLAN:
+{}
+
+VALIDATION ERRORS:
+{}
+
+DIAGNOSIS:
+{}
+
+Rewrite the plan so it achieves the goal without these problems.
+Only "tool" steps (with "name" and "input") and "info" steps (with "message") are valid.
+Respond with ONLY the corrected JSON: {{"plan": [...]}}"#,
+            self.goal,
+            self.plan_json,
+            self.validation_errors.join("\n"),
+            self.diagnosis
+        );
+
+        let result = llm.execute(&prompt);
+        if !result.success {
+            return;
+        }
+
+        let response = result.output.unwrap_or_default();
+        if let Ok(value) = json_repair::parse_json::<Value>(&response)
+            && value.get("plan").is_some_and(|p| p.is_array())
+        {
+            self.corrected_plan = Some(value.to_string());
+        }
+    }
+
+    /// Renders the record as a few-shot example for planner prompts.
+    pub fn to_few_shot(&self) -> String {
+        let mut text = format!(
+            "// A previous plan for \"{}\" failed: {}\n",
+            self.goal, self.diagnosis
+        );
+        if !self.validation_errors.is_empty() {
+            text.push_str(&format!(
+                "// Problems: {}\n",
+                self.validation_errors.join("; ")
+            ));
+        }
+        match &self.corrected_plan {
+            Some(corrected) => text.push_str(&format!("// Corrected plan:\n{}", corrected)),
+            None => text.push_str(&format!("// Do NOT repeat this plan:\n{}", self.plan_json)),
+        }
+        text
+    }
+}
+
+/// Append-only JSONL store of failed plans.
+pub struct FailedPlanStore {
+    path: PathBuf,
+    records: Vec<FailedPlanRecord>,
+}
+
+impl FailedPlanStore {
+    /// Loads existing records from `path`; a missing file starts an empty store.
+    pub fn open(path: &str) -> Self {
+        let records = std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+
+        Self {
+            path: PathBuf::from(path),
+            records,
+        }
+    }
+
+    pub fn records(&self) -> &[FailedPlanRecord] {
+        &self.records
+    }
+
+    pub fn record(&mut self, record: FailedPlanRecord) -> Result<(), String> {
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize failed plan: {}", e))?;
+
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Returns up to `k` records whose goals share the most words with `goal`.
+    pub fn similar(&self, goal: &str, k: usize) -> Vec<&FailedPlanRecord> {
+        let query = words(goal);
+        let mut scored: Vec<(f32, &FailedPlanRecord)> = self
+            .records
+            .iter()
+            .map(|record| {
+                let other = words(&record.goal);
+                let overlap = query.intersection(&other).count() as f32;
+                let union = query.union(&other).count().max(1) as f32;
+                (overlap / union, record)
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+
+        // Newest records first on ties, so fresh corrections win.
+        scored.reverse();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(k).map(|(_, r)| r).collect()
+    }
+
+    /// Few-shot text of corrective examples for goals similar to `goal`.
+    pub fn corrective_examples(&self, goal: &str, k: usize) -> String {
+        self.similar(goal, k)
+            .iter()
+            .map(|record| record.to_few_shot())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn plan_to_json(plan: &Plan) -> Value {
+    json!({ "plan": plan.to_json() })
+}
@@ -0,0 +1,170 @@
+// src/memory/file.rs
+
+use crate::memory::Memory;
+use crate::memory::jsonl::EntryLine;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where the `file` memory backend keeps its log by default.
+pub const DEFAULT_MEMORY_FILE: &str = ".agentic/memory.jsonl";
+
+/// Memory that survives a crash: every entry is appended to a JSONL file and
+/// synced to disk before `log` returns, and `open` reloads the file.
+///
+/// A crash mid-write leaves at most a partial last line; `open` drops it and
+/// truncates the file back to the last complete entry. Unreadable lines
+/// elsewhere are skipped. Both count towards `dropped`. `truncate` cuts the
+/// file back too, so a rolled-back entry does not return on the next `open`.
+#[derive(Debug)]
+pub struct FileMemory {
+    path: PathBuf,
+    file: File,
+    entries: Vec<(String, String)>,
+    /// Where each entry's line starts in the file; `None` for one that
+    /// failed to persist.
+    starts: Vec<Option<u64>>,
+    /// The end of the last entry written.
+    end: u64,
+    dropped: usize,
+}
+
+impl FileMemory {
+    /// Opens (or creates) the log at `path` and loads its entries.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+        let bytes =
+            fs::read(&path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+
+        let mut entries = Vec::new();
+        let mut starts = Vec::new();
+        let mut dropped = 0;
+        // End of the last complete, readable line; anything after it is the
+        // remains of an interrupted write.
+        let mut good_end = 0;
+        let mut start = 0;
+        while start < bytes.len() {
+            let (line, end) = match bytes[start..].iter().position(|&b| b == b'\n') {
+                Some(n) => (&bytes[start..start + n], Some(start + n + 1)),
+                None => (&bytes[start..], None),
+            };
+            let parsed = std::str::from_utf8(line)
+                .ok()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str::<EntryLine>);
+            match (parsed, end) {
+                (Some(Ok(entry)), Some(end)) => {
+                    entries.push((entry.label, entry.content));
+                    starts.push(Some(start as u64));
+                    good_end = end;
+                }
+                (None, Some(end)) if line.iter().all(u8::is_ascii_whitespace) => good_end = end,
+                _ => dropped += 1,
+            }
+            start = end.unwrap_or(bytes.len());
+        }
+        if good_end < bytes.len() {
+            file.set_len(good_end as u64)
+                .and_then(|_| file.sync_data())
+                .map_err(|e| format!("cannot recover {}: {}", path.display(), e))?;
+        }
+        if dropped > 0 {
+            tracing::warn!(
+                "Dropped {} unreadable memory entries from {}",
+                dropped,
+                path.display()
+            );
+        }
+        Ok(Self {
+            path,
+            file,
+            entries,
+            starts,
+            end: good_end as u64,
+            dropped,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Lines `open` could not load, including a partial last line.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Appends `line`, returning where it starts. A failed write is cut
+    /// back off, so the next entry does not land on a partial line.
+    fn append(&mut self, line: &str) -> std::io::Result<u64> {
+        let line = format!("{}\n", line);
+        let written = self
+            .file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.sync_data());
+        if let Err(e) = written {
+            let _ = self.file.set_len(self.end);
+            return Err(e);
+        }
+        let start = self.end;
+        self.end += line.len() as u64;
+        Ok(start)
+    }
+}
+
+impl Memory for FileMemory {
+    /// Keeps the entry in memory even if the write fails, so the run goes on.
+    fn log(&mut self, label: &str, content: &str) {
+        let start = self
+            .append(&EntryLine::new(label, content).to_line())
+            .map_err(|e| {
+                tracing::warn!(
+                    "Failed to persist memory entry to {}: {}",
+                    self.path.display(),
+                    e
+                )
+            })
+            .ok();
+        self.starts.push(start);
+        self.entries.push((label.to_string(), content.to_string()));
+    }
+
+    fn read_all(&self) -> Vec<(String, String)> {
+        self.entries.clone()
+    }
+
+    /// Entries are synced as they are logged; this also syncs the file's
+    /// metadata.
+    fn flush(&mut self) -> Result<(), String> {
+        self.file
+            .sync_all()
+            .map_err(|e| format!("cannot sync {}: {}", self.path.display(), e))
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), String> {
+        if len >= self.entries.len() {
+            return Ok(());
+        }
+        // The first later entry that reached the file, if any did.
+        if let Some(start) = self.starts[len..].iter().flatten().next().copied() {
+            self.file
+                .set_len(start)
+                .and_then(|_| self.file.sync_data())
+                .map_err(|e| format!("cannot truncate {}: {}", self.path.display(), e))?;
+            self.end = start;
+        }
+        self.entries.truncate(len);
+        self.starts.truncate(len);
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// src/memory/jsonl.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// One memory entry as a line of JSONL: `{"label": "...", "content": "..."}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntryLine {
+    pub label: String,
+    pub content: String,
+}
+
+impl EntryLine {
+    pub fn new(label: &str, content: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        // Two string fields always serialize.
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Writes `entries` to `path`, one JSON object per line, replacing the file.
+pub fn write_entries(path: &Path, entries: &[(String, String)]) -> Result<usize, String> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    }
+    let file =
+        fs::File::create(path).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    for (label, content) in entries {
+        writeln!(out, "{}", EntryLine::new(label, content).to_line())
+            .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    }
+    out.flush()
+        .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    Ok(entries.len())
+}
+
+/// The entries of the JSONL file at `path`; blank lines are skipped.
+pub fn read_entries(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str::<EntryLine>(line)
+                .map(|entry| (entry.label, entry.content))
+                .map_err(|e| format!("{}:{}: invalid memory entry: {}", path.display(), n + 1, e))
+        })
+        .collect()
+}
@@ -0,0 +1,158 @@
+// src/memory/lessons.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Longest lesson (in chars) kept from a reflection.
+const MAX_LESSON_CHARS: usize = 300;
+
+/// What one run's reflection taught about a kind of goal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LessonRecord {
+    /// As classified by `GoalAnalyzerTool`, e.g. `git_operations`.
+    pub goal_type: String,
+    pub goal: String,
+    pub goal_achieved: bool,
+    pub lessons: Vec<String>,
+}
+
+impl LessonRecord {
+    /// Keeps the "what failed" and "suggested improvements" bullets of a
+    /// `ReflectorTool` summary, or its first lines if it has no such sections.
+    pub fn from_reflection(
+        goal_type: &str,
+        goal: &str,
+        goal_achieved: bool,
+        reflection: &str,
+    ) -> Self {
+        let mut lessons = Vec::new();
+        let mut in_section = false;
+        for line in reflection.lines().map(str::trim) {
+            if line.starts_with('#') {
+                let heading = line.to_lowercase();
+                in_section = heading.contains("fail")
+                    || heading.contains("improve")
+                    || heading.contains("lesson");
+                continue;
+            }
+            let item = line.trim_start_matches(['-', '*', ' ']).trim();
+            if in_section && item.len() > 3 && !item.eq_ignore_ascii_case("none") {
+                lessons.push(truncate(item));
+            }
+        }
+        if lessons.is_empty() {
+            lessons.extend(
+                reflection
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .take(3)
+                    .map(truncate),
+            );
+        }
+
+        Self {
+            goal_type: goal_type.into(),
+            goal: goal.into(),
+            goal_achieved,
+            lessons,
+        }
+    }
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_LESSON_CHARS).collect()
+}
+
+/// Append-only JSONL store of lessons learned, keyed by goal type, so a new
+/// run can avoid the mistakes of earlier runs of the same kind.
+pub struct LessonsStore {
+    path: PathBuf,
+    records: Vec<LessonRecord>,
+}
+
+impl LessonsStore {
+    /// Loads existing records from `path`; a missing file starts an empty store.
+    pub fn open(path: &str) -> Self {
+        let records = std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+
+        Self {
+            path: PathBuf::from(path),
+            records,
+        }
+    }
+
+    pub fn records(&self) -> &[LessonRecord] {
+        &self.records
+    }
+
+    /// Appends `record`; records without lessons are skipped.
+    pub fn record(&mut self, record: LessonRecord) -> Result<(), String> {
+        if record.lessons.is_empty() {
+            return Ok(());
+        }
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize lessons: {}", e))?;
+
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Up to `k` distinct lessons for `goal_type`, newest first, preferring
+    /// lessons from runs that did not achieve their goal.
+    pub fn lessons_for(&self, goal_type: &str, k: usize) -> Vec<&str> {
+        let mut matching: Vec<&LessonRecord> = self
+            .records
+            .iter()
+            .rev()
+            .filter(|record| record.goal_type.eq_ignore_ascii_case(goal_type))
+            .collect();
+        // Stable, so newest-first order holds within each group.
+        matching.sort_by_key(|record| record.goal_achieved);
+
+        let mut lessons: Vec<&str> = Vec::new();
+        for lesson in matching.iter().flat_map(|record| &record.lessons) {
+            if lessons.len() == k {
+                break;
+            }
+            if !lessons.contains(&lesson.as_str()) {
+                lessons.push(lesson);
+            }
+        }
+        lessons
+    }
+
+    /// Planner prompt section listing lessons for `goal_type`; empty if none.
+    pub fn prompt_section(&self, goal_type: &str, k: usize) -> String {
+        let lessons = self.lessons_for(goal_type, k);
+        if lessons.is_empty() {
+            return String::new();
+        }
+        format!(
+            "LESSONS FROM PAST {} RUNS (do not repeat these mistakes):\n- {}",
+            goal_type,
+            lessons.join("\n- ")
+        )
+    }
+}
@@ -0,0 +1,164 @@
+// src/memory/limit.rs
+
+use crate::llm::{Conversation, ModelTier};
+use crate::tools::llm::LLMTool;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where `agentic` saves the full text of oversized entries by default.
+pub const DEFAULT_SPILL_DIR: &str = ".agentic/memory";
+
+const SYSTEM_PROMPT: &str = "You summarize tool output and logs for an autonomous agent's memory. \
+Keep errors, warnings, file paths, counts and final results verbatim; drop progress lines and repetition. \
+Reply with the summary only.";
+
+/// A cap on the size of memory entries, so long tool outputs (a full
+/// `cargo build` log) do not blow up the memory dump planners see.
+///
+/// Entries over `max_chars` are written in full to a file in `dir` and kept
+/// in memory as a summary followed by a pointer to that file. The summary
+/// comes from the summarizer LLM when one is set and answers, else from the
+/// entry's first and last lines. Clones share the file counter.
+#[derive(Clone)]
+pub struct EntryLimit {
+    pub max_chars: usize,
+    pub dir: PathBuf,
+    summarizer: Option<LLMTool>,
+    saved: Arc<AtomicUsize>,
+}
+
+impl EntryLimit {
+    pub fn new(max_chars: usize, dir: impl AsRef<Path>) -> Self {
+        Self {
+            max_chars: max_chars.max(1),
+            dir: dir.as_ref().to_path_buf(),
+            summarizer: None,
+            saved: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Summarizes oversized entries with `llm`'s cheap tier.
+    pub fn with_summarizer(mut self, llm: LLMTool) -> Self {
+        self.summarizer = Some(
+            llm.for_component("memory_summarizer")
+                .for_tier(ModelTier::Cheap),
+        );
+        self
+    }
+
+    /// `content` unchanged if it fits, else its summary and where the full
+    /// text was saved.
+    pub fn apply(&self, label: &str, content: &str) -> String {
+        let chars = content.chars().count();
+        if chars <= self.max_chars {
+            return content.to_string();
+        }
+        let pointer = match self.save(label, content) {
+            Ok(path) => format!("[full text ({} chars): {}]", chars, path.display()),
+            Err(e) => format!("[{} chars, full text not saved: {}]", chars, e),
+        };
+        let summary = self
+            .summarize(label, content)
+            .unwrap_or_else(|| excerpt(content, self.max_chars));
+        format!("{}\n{}", truncate(&summary, self.max_chars), pointer)
+    }
+
+    fn save(&self, label: &str, content: &str) -> Result<PathBuf, String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("cannot create {}: {}", self.dir.display(), e))?;
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        let label: String = label
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(40)
+            .collect();
+        let n = self.saved.fetch_add(1, Ordering::Relaxed);
+        let path = self.dir.join(format!("{}-{}-{}.txt", millis, n, label));
+        fs::write(&path, content).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    fn summarize(&self, label: &str, content: &str) -> Option<String> {
+        let llm = self.summarizer.as_ref()?;
+        // Even the summarizer gets a bounded prompt.
+        let input = excerpt(content, self.max_chars.saturating_mul(8).max(4000));
+        let conversation = Conversation::new()
+            .with_system(SYSTEM_PROMPT)
+            .with_user(&format!(
+                "Summarize this '{}' entry in at most {} characters:\n\n{}",
+                label, self.max_chars, input
+            ));
+        llm.chat(&conversation)
+            .ok()
+            .map(|summary| summary.trim().to_string())
+            .filter(|summary| !summary.is_empty())
+    }
+}
+
+impl std::fmt::Debug for EntryLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EntryLimit")
+            .field("max_chars", &self.max_chars)
+            .field("dir", &self.dir)
+            .field("summarizer", &self.summarizer.is_some())
+            .finish()
+    }
+}
+
+/// The first and last lines of `text` that fit in about `max_chars`, with a
+/// marker for what was left out.
+fn excerpt(text: &str, max_chars: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    // Leaves room for the omission marker.
+    let budget = max_chars.saturating_sub(32) / 2;
+    let mut head = Vec::new();
+    let mut used = 0;
+    for line in &lines {
+        if used + line.chars().count() + 1 > budget {
+            break;
+        }
+        used += line.chars().count() + 1;
+        head.push(*line);
+    }
+    let mut tail = Vec::new();
+    used = 0;
+    for line in lines[head.len()..].iter().rev() {
+        if used + line.chars().count() + 1 > budget {
+            break;
+        }
+        used += line.chars().count() + 1;
+        tail.push(*line);
+    }
+    tail.reverse();
+    if head.is_empty() && tail.is_empty() {
+        return truncate(text, max_chars);
+    }
+    let skipped = lines.len() - head.len() - tail.len();
+    let mut out = head.join("\n");
+    if skipped > 0 {
+        out.push_str(&format!("\n… {} lines omitted …\n", skipped));
+    } else if !head.is_empty() && !tail.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&tail.join("\n"));
+    out
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
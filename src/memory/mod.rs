@@ -1,9 +1,137 @@
 // src/memory/mod.rs
 
+pub mod failures;
+pub mod file;
+pub mod jsonl;
+pub mod lessons;
+pub mod limit;
+pub mod scoped;
+pub mod semantic;
+pub mod vector;
+
+use crate::memory::vector::{Embedder, HashingEmbedder, cosine_similarity};
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Where planners log their debug output, apart from tool I/O and results;
+/// `Memory::prompt_entries` keeps it out of their prompts.
+pub const PLANNER_NAMESPACE: &str = "planner";
+
+/// Where the executor logs tools' structured results, one entry per step
+/// labelled with its id (or tool name), holding the data as JSON.
+pub const TOOL_DATA_NAMESPACE: &str = "tool_data";
+
+/// The label a flat memory stores a namespaced entry under: `tools/run_command`.
+pub fn namespaced(namespace: &str, label: &str) -> String {
+    format!("{}/{}", namespace, label)
+}
+
 /// A trait for agent memory to log steps, tool results, and thoughts.
 pub trait Memory {
     fn log(&mut self, label: &str, content: &str);
     fn read_all(&self) -> Vec<(String, String)>;
+
+    /// Logs into `namespace`, e.g. `PLANNER_NAMESPACE` for planner debug
+    /// output. Namespaces nest (`run-1/tools`). The default stores the entry
+    /// under its `namespaced` label.
+    fn log_in(&mut self, namespace: &str, label: &str, content: &str) {
+        self.log(&namespaced(namespace, label), content);
+    }
+
+    /// The entries logged in `namespace` or below it, with labels relative to
+    /// it.
+    fn read_namespace(&self, namespace: &str) -> Vec<(String, String)> {
+        strip_namespace(namespace, self.read_all())
+    }
+
+    /// Like `search`, over `namespace` only.
+    fn search_in(&self, namespace: &str, query: &str, k: usize) -> Vec<(String, String)> {
+        rank(self.read_namespace(namespace), query, k)
+    }
+
+    /// The `k` entries most relevant to `query`, best first. The default ranks
+    /// `read_all()` by keyword overlap; embedding-backed memories override it.
+    fn search(&self, query: &str, k: usize) -> Vec<(String, String)> {
+        rank(self.read_all(), query, k)
+    }
+
+    /// Drops every entry after the first `len`, for `Context::restore`.
+    /// Backends that cannot drop entries refuse.
+    fn truncate(&mut self, _len: usize) -> Result<(), String> {
+        Err("this memory backend cannot drop entries".into())
+    }
+
+    /// The entries planners are shown: all of them or, with `limit`, the
+    /// `limit` most relevant to `query`, leaving out `PLANNER_NAMESPACE` so
+    /// planners' own debug output does not feed back into their prompts.
+    fn prompt_entries(&self, query: &str, limit: Option<usize>) -> Vec<(String, String)> {
+        let debug = namespaced(PLANNER_NAMESPACE, "");
+        let shown = |(label, _): &(String, String)| !label.starts_with(&debug);
+        match limit {
+            Some(k) => {
+                let hidden = self.read_all().iter().filter(|entry| !shown(entry)).count();
+                let mut entries = self.search(query, k + hidden);
+                entries.retain(shown);
+                entries.truncate(k);
+                entries
+            }
+            None => self.read_all().into_iter().filter(shown).collect(),
+        }
+    }
+
+    /// Makes sure every entry logged so far is on disk, for backends that
+    /// persist; others have nothing to do.
+    fn flush(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Writes every entry to `path` as JSONL (`{"label": ..., "content": ...}`
+    /// per line), replacing the file; returns how many were written.
+    fn export_jsonl(&self, path: &Path) -> Result<usize, String> {
+        jsonl::write_entries(path, &self.read_all())
+    }
+
+    /// Logs every entry of the JSONL file at `path`, in order, after the
+    /// existing ones; returns how many were read. Nothing is logged if any
+    /// line is invalid.
+    fn import_jsonl(&mut self, path: &Path) -> Result<usize, String> {
+        let entries = jsonl::read_entries(path)?;
+        for (label, content) in &entries {
+            self.log(label, content);
+        }
+        Ok(entries.len())
+    }
+}
+
+/// The entries of `entries` in `namespace`, with that prefix removed.
+pub(crate) fn strip_namespace(
+    namespace: &str,
+    entries: impl IntoIterator<Item = (String, String)>,
+) -> Vec<(String, String)> {
+    let prefix = namespaced(namespace, "");
+    entries
+        .into_iter()
+        .filter_map(|(label, content)| Some((label.strip_prefix(&prefix)?.to_string(), content)))
+        .collect()
+}
+
+/// The `k` entries with the most keyword overlap with `query`, best first.
+fn rank(entries: Vec<(String, String)>, query: &str, k: usize) -> Vec<(String, String)> {
+    let embedder = HashingEmbedder::default();
+    let Ok(query) = embedder.embed(query) else {
+        return Vec::new();
+    };
+
+    let mut scored: Vec<(f32, (String, String))> = entries
+        .into_iter()
+        .filter_map(|(label, content)| {
+            let vector = embedder.embed(&format!("{} {}", label, content)).ok()?;
+            Some((cosine_similarity(&query, &vector), (label, content)))
+        })
+        .filter(|(score, _)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(k).map(|(_, entry)| entry).collect()
 }
 
 /// In-memory implementation of the Memory trait.
@@ -28,4 +156,54 @@ impl Memory for InMemoryLog {
     fn read_all(&self) -> Vec<(String, String)> {
         self.entries.clone()
     }
+
+    fn truncate(&mut self, len: usize) -> Result<(), String> {
+        self.entries.truncate(len);
+        Ok(())
+    }
+}
+
+/// A memory backend behind a lock, so a context, its handles and tool
+/// threads can all log to it. Clones share the backend.
+#[derive(Clone)]
+pub struct SharedMemory {
+    inner: Arc<RwLock<Box<dyn Memory + Send + Sync>>>,
+}
+
+impl SharedMemory {
+    pub fn new<M: Memory + Send + Sync + 'static>(memory: M) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Box::new(memory))),
+        }
+    }
+
+    pub fn log(&self, label: &str, content: &str) {
+        self.write().log(label, content);
+    }
+
+    pub fn log_in(&self, namespace: &str, label: &str, content: &str) {
+        self.write().log_in(namespace, label, content);
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Box<dyn Memory + Send + Sync>> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, Box<dyn Memory + Send + Sync>> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for SharedMemory {
+    fn default() -> Self {
+        Self::new(InMemoryLog::new())
+    }
+}
+
+impl std::fmt::Debug for SharedMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedMemory")
+            .field("entries", &self.read().read_all().len())
+            .finish()
+    }
 }
@@ -0,0 +1,90 @@
+// src/memory/scoped.rs
+
+use crate::memory::{Memory, SharedMemory, namespaced};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A fresh run ID, unique within the process and unlikely to repeat across
+/// processes: `run-<millis>-<n>`.
+pub fn new_run_id() -> String {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!("run-{}-{}", millis, RUNS.fetch_add(1, Ordering::Relaxed))
+}
+
+/// One namespace of a shared backend, seen as a memory of its own: entries
+/// logged through it land under `namespace`, and reads return only those,
+/// with their labels relative to it. Runs sharing a persistent backend each
+/// get one, so they neither see nor search each other's entries.
+#[derive(Clone, Debug)]
+pub struct ScopedMemory {
+    inner: SharedMemory,
+    namespace: String,
+}
+
+impl ScopedMemory {
+    pub fn new(inner: SharedMemory, namespace: &str) -> Self {
+        Self {
+            inner,
+            namespace: namespace.to_string(),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+}
+
+impl Memory for ScopedMemory {
+    fn log(&mut self, label: &str, content: &str) {
+        self.inner.log_in(&self.namespace, label, content);
+    }
+
+    fn read_all(&self) -> Vec<(String, String)> {
+        self.inner.read().read_namespace(&self.namespace)
+    }
+
+    fn search(&self, query: &str, k: usize) -> Vec<(String, String)> {
+        self.inner.read().search_in(&self.namespace, query, k)
+    }
+
+    /// Only works while this namespace's latest entries are the backend's
+    /// latest, i.e. nothing else logged to it since.
+    fn truncate(&mut self, len: usize) -> Result<(), String> {
+        let prefix = namespaced(&self.namespace, "");
+        let mut inner = self.inner.write();
+        let entries = inner.read_all();
+        let Some(cut) = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (label, _))| label.starts_with(&prefix))
+            .nth(len)
+            .map(|(i, _)| i)
+        else {
+            return Ok(());
+        };
+        if entries[cut..]
+            .iter()
+            .any(|(label, _)| !label.starts_with(&prefix))
+        {
+            return Err(format!(
+                "entries outside '{}' were logged since; cannot drop its later entries",
+                self.namespace
+            ));
+        }
+        inner.truncate(cut)
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.inner.write().flush()
+    }
+
+    fn search_in(&self, namespace: &str, query: &str, k: usize) -> Vec<(String, String)> {
+        self.inner
+            .read()
+            .search_in(&namespaced(&self.namespace, namespace), query, k)
+    }
+}
@@ -0,0 +1,69 @@
+// src/memory/semantic.rs
+
+use crate::memory::vector::{Embedder, VectorStore};
+use crate::memory::{Memory, strip_namespace};
+
+/// Memory log whose entries are also embedded for semantic `search`.
+///
+/// Entries that fail to embed (e.g. the embedding endpoint is down) are still
+/// logged and fall out of search results rather than failing the agent.
+pub struct SemanticMemory {
+    entries: Vec<(String, String)>,
+    store: VectorStore,
+    /// The store's size when each entry was logged, for `truncate`.
+    stored_before: Vec<usize>,
+}
+
+impl SemanticMemory {
+    pub fn new(embedder: Box<dyn Embedder>) -> Self {
+        Self {
+            entries: Vec::new(),
+            store: VectorStore::new(embedder),
+            stored_before: Vec::new(),
+        }
+    }
+}
+
+impl Memory for SemanticMemory {
+    fn log(&mut self, label: &str, content: &str) {
+        self.entries.push((label.to_string(), content.to_string()));
+        self.stored_before.push(self.store.len());
+        let _ = self.store.add(label, content);
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), String> {
+        if let Some(&stored) = self.stored_before.get(len) {
+            self.store.truncate(stored);
+        }
+        self.entries.truncate(len);
+        self.stored_before.truncate(len);
+        Ok(())
+    }
+
+    fn read_all(&self) -> Vec<(String, String)> {
+        self.entries.clone()
+    }
+
+    fn search(&self, query: &str, k: usize) -> Vec<(String, String)> {
+        self.store
+            .search(query, k)
+            .map(|hits| {
+                hits.into_iter()
+                    .map(|(_, entry)| (entry.source.clone(), entry.text.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn search_in(&self, namespace: &str, query: &str, k: usize) -> Vec<(String, String)> {
+        let hits = self
+            .store
+            .search(query, self.entries.len())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, entry)| (entry.source.clone(), entry.text.clone()));
+        let mut hits = strip_namespace(namespace, hits);
+        hits.truncate(k);
+        hits
+    }
+}
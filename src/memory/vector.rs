@@ -0,0 +1,188 @@
+// src/memory/vector.rs
+
+use crate::llm::{self, LlmProvider, LlmRequest};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Turns text into a fixed-length vector for similarity search.
+pub trait Embedder: Send + Sync {
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String>;
+}
+
+/// Offline embedder using feature hashing over lowercase word tokens.
+///
+/// Cheap and deterministic — good enough for keyword-ish retrieval without a model.
+#[derive(Clone, Debug)]
+pub struct HashingEmbedder {
+    pub dims: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dims: usize) -> Self {
+        Self { dims: dims.max(1) }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(512)
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let mut vector = vec![0.0; self.dims];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+            .filter(|w| !w.is_empty())
+        {
+            // FNV-1a: stable across runs, unlike std's randomized hasher.
+            let mut hash: u64 = 0xcbf29ce484222325;
+            for byte in word.to_lowercase().bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+            vector[(hash % self.dims as u64) as usize] += 1.0;
+        }
+        Ok(vector)
+    }
+}
+
+/// Embeddings from a local Ollama model via `/api/embeddings`.
+#[derive(Clone, Debug)]
+pub struct OllamaEmbedder {
+    pub model: String,
+    pub url: String,
+}
+
+impl OllamaEmbedder {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.into(),
+            url: "http://localhost:11434/api/embeddings".into(),
+        }
+    }
+}
+
+impl Embedder for OllamaEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let json = llm::send_blocking(&LlmRequest {
+            url: self.url.clone(),
+            headers: Vec::new(),
+            body: json!({ "model": self.model, "prompt": text }),
+        })?;
+
+        json.get("embedding")
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_f64())
+                    .map(|v| v as f32)
+                    .collect()
+            })
+            .ok_or_else(|| "Embedding response missing 'embedding' field".into())
+    }
+}
+
+/// Embeddings from the configured LLM provider's embedding endpoint.
+#[derive(Clone)]
+pub struct LlmEmbedder {
+    provider: Arc<dyn LlmProvider>,
+}
+
+impl LlmEmbedder {
+    /// Fails for providers without an embedding endpoint (e.g. Anthropic).
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Result<Self, String> {
+        if provider.embedding_request("").is_none() {
+            return Err(format!(
+                "Provider '{}' does not offer embeddings",
+                provider.name()
+            ));
+        }
+        Ok(Self { provider })
+    }
+}
+
+impl Embedder for LlmEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let request = self.provider.embedding_request(text).ok_or_else(|| {
+            format!(
+                "Provider '{}' does not offer embeddings",
+                self.provider.name()
+            )
+        })?;
+        let json = llm::send_blocking(&request)?;
+        self.provider.parse_embedding(&json)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct VectorEntry {
+    pub source: String,
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+/// In-process vector store with cosine-similarity search.
+pub struct VectorStore {
+    embedder: Box<dyn Embedder>,
+    entries: Vec<VectorEntry>,
+}
+
+impl VectorStore {
+    pub fn new(embedder: Box<dyn Embedder>) -> Self {
+        Self {
+            embedder,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Keeps only the first `len` entries.
+    pub fn truncate(&mut self, len: usize) {
+        self.entries.truncate(len);
+    }
+
+    pub fn add(&mut self, source: &str, text: &str) -> Result<(), String> {
+        let vector = self.embedder.embed(text)?;
+        self.entries.push(VectorEntry {
+            source: source.into(),
+            text: text.into(),
+            vector,
+        });
+        Ok(())
+    }
+
+    /// Top `k` entries by cosine similarity to `query`, best first.
+    pub fn search(&self, query: &str, k: usize) -> Result<Vec<(f32, &VectorEntry)>, String> {
+        let query = self.embedder.embed(query)?;
+        let mut scored: Vec<(f32, &VectorEntry)> = self
+            .entries
+            .iter()
+            .map(|entry| (cosine_similarity(&query, &entry.vector), entry))
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(k);
+        Ok(scored)
+    }
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
@@ -1,38 +1,416 @@
 // src/model/mod.rs
 
-#[derive(Clone, Debug)]
+pub mod task_graph;
+
+use crate::protocol::StepResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use task_graph::TaskGraph;
+
+/// Steps listed in prompts by `TaskModel::progress`, newest last.
+const PROGRESS_STEPS: usize = 20;
+
+/// Constraints on executing one plan: planners are told about them and the
+/// executor stops the plan, with a "budget exceeded" error, once one is hit.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunLimits {
+    /// Tool calls, counting each loop iteration.
+    pub max_steps: Option<usize>,
+    pub max_wall_time: Option<Duration>,
+    /// Calls of tools tagged `shell` (`run_command`, `sandboxed_command`).
+    pub max_shell_commands: Option<usize>,
+}
+
+impl RunLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Planner prompt rules stating the limits; empty when there are none.
+    pub fn prompt_section(&self) -> String {
+        let mut rules = Vec::new();
+        if let Some(steps) = self.max_steps {
+            rules.push(format!("- Use at most {} tool calls in total", steps));
+        }
+        if let Some(commands) = self.max_shell_commands {
+            rules.push(format!(
+                "- Run at most {} shell commands; combine checks where you can",
+                commands
+            ));
+        }
+        if let Some(time) = self.max_wall_time {
+            rules.push(format!(
+                "- The plan must finish within {}s; avoid slow commands",
+                time.as_secs()
+            ));
+        }
+        if rules.is_empty() {
+            return String::new();
+        }
+        format!(
+            "RUN LIMITS (steps beyond these are not executed):\n{}",
+            rules.join("\n")
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    #[default]
+    NotStarted,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Running,
+    Succeeded,
+    Failed,
+    /// Declined at approval; it never ran.
+    Skipped,
+}
+
+/// One executed (or skipped) tool call, as recorded on the `TaskModel`.
+/// Timestamps are Unix milliseconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub tool: String,
+    pub input: String,
+    pub status: StepStatus,
+    pub started_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    /// The step's artifact: its output on success.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl StepRecord {
+    /// How long the step ran, once finished.
+    pub fn duration(&self) -> Option<Duration> {
+        let finished = self.finished_at?;
+        Some(Duration::from_millis(
+            finished.saturating_sub(self.started_at),
+        ))
+    }
+}
+
+/// The state of one goal across plans: overall status, every step with its
+/// status, timing and output, and (once decomposed) its subtasks. Persisted
+/// with `save` and in `RunState` checkpoints, and summarized by `progress`
+/// for the replanner and evaluator.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TaskModel {
     pub goal: String,
-    pub current_state: String,
+    #[serde(default)]
+    pub status: TaskStatus,
+    #[serde(default)]
     pub output: Option<String>,
+    #[serde(default)]
+    pub limits: RunLimits,
+    /// Set once the goal has been decomposed; tracks each subtask's status.
+    #[serde(default)]
+    pub subtasks: Option<TaskGraph>,
+    /// Every step run for this goal, across plans and replans.
+    #[serde(default)]
+    pub steps: Vec<StepRecord>,
 }
 
 impl TaskModel {
     pub fn new(goal: &str) -> Self {
         Self {
             goal: goal.to_string(),
-            current_state: "Not started".into(),
+            status: TaskStatus::NotStarted,
             output: None,
+            limits: RunLimits::default(),
+            subtasks: None,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: RunLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Planner prompt rules for this task: its limits and, once decomposed,
+    /// which subtasks are already done. Empty when there is nothing to add.
+    pub fn prompt_rules(&self) -> String {
+        let mut rules = self.limits.prompt_section();
+        if let Some(subtasks) = &self.subtasks {
+            if !rules.is_empty() {
+                rules.push('\n');
+            }
+            rules.push_str("SUBTASK PROGRESS (do not redo subtasks marked done):\n");
+            rules.push_str(&subtasks.progress());
         }
+        rules
+    }
+
+    /// Steps that succeeded, oldest first.
+    pub fn completed_steps(&self) -> impl Iterator<Item = &StepRecord> {
+        self.steps
+            .iter()
+            .filter(|step| step.status == StepStatus::Succeeded)
+    }
+
+    /// `$output[key]` values left by completed steps: each under its id, if
+    /// it has one, and under its tool name (the latest call wins), the way
+    /// the executor stores them. Seeds later executions, so a replan can
+    /// reference earlier results instead of redoing their steps.
+    pub fn completed_outputs(&self) -> Vec<(String, String)> {
+        let mut outputs: Vec<(String, String)> = Vec::new();
+        for step in self.completed_steps() {
+            let Some(output) = &step.output else {
+                continue;
+            };
+            for key in step.id.iter().chain([&step.tool]) {
+                outputs.retain(|(existing, _)| existing != key);
+                outputs.push((key.clone(), output.clone()));
+            }
+        }
+        outputs
+    }
+
+    /// Structured data left by completed steps, keyed like
+    /// `completed_outputs`; a later step without data clears its keys.
+    pub fn completed_data(&self) -> Vec<(String, Value)> {
+        let mut data: Vec<(String, Value)> = Vec::new();
+        for step in self.completed_steps() {
+            for key in step.id.iter().chain([&step.tool]) {
+                data.retain(|(existing, _)| existing != key);
+                if let Some(value) = &step.data {
+                    data.push((key.clone(), value.clone()));
+                }
+            }
+        }
+        data
+    }
+
+    /// Replanner prompt section listing completed steps with the reference
+    /// that still resolves to each one's output; empty when none completed.
+    pub fn completed_section(&self) -> String {
+        let references = self.completed_outputs();
+        let lines: Vec<String> = self
+            .completed_steps()
+            .map(|step| {
+                let input: String = step.input.chars().take(120).collect();
+                let reference = step
+                    .id
+                    .iter()
+                    .chain([&step.tool])
+                    .find(|key| {
+                        references.iter().any(|(ref_key, output)| {
+                            ref_key == *key && Some(output) == step.output.as_ref()
+                        })
+                    })
+                    .map(|key| format!(" → $output[{}]", key))
+                    .unwrap_or_default();
+                format!("- {} `{}`{}", step.tool, input, reference)
+            })
+            .collect();
+        if lines.is_empty() {
+            return String::new();
+        }
+        format!(
+            "COMPLETED STEPS (already done: do NOT run them again; use their results through the reference shown):\n{}",
+            lines.join("\n")
+        )
+    }
+
+    /// Records the start of a tool call and marks the task running.
+    pub fn begin_step(&mut self, tool: &str, id: Option<&str>, input: &str) {
+        self.status = TaskStatus::Running;
+        self.steps.push(StepRecord {
+            id: id.map(str::to_string),
+            tool: tool.into(),
+            input: input.into(),
+            status: StepStatus::Running,
+            started_at: now_millis(),
+            finished_at: None,
+            output: None,
+            error: None,
+            data: None,
+        });
+    }
+
+    /// Completes the oldest running step matching `result`'s tool, id and
+    /// input (parallel calls finish out of order), or records it anew when
+    /// none was begun.
+    pub fn finish_step(&mut self, result: &StepResult) -> &StepRecord {
+        let running = self.steps.iter().position(|step| {
+            step.status == StepStatus::Running
+                && step.tool == result.tool
+                && step.id == result.id
+                && (result.input.is_empty() || step.input == result.input)
+        });
+        let index = running.unwrap_or_else(|| {
+            self.begin_step(&result.tool, result.id.as_deref(), &result.input);
+            self.steps.len() - 1
+        });
+        let step = &mut self.steps[index];
+        step.status = match result.success {
+            true => StepStatus::Succeeded,
+            false => StepStatus::Failed,
+        };
+        step.finished_at = Some(now_millis());
+        step.output = result.output.clone();
+        step.error = result.error.clone();
+        step.data = result.data.clone();
+        step
+    }
+
+    /// Records a tool call that was declined and never ran.
+    pub fn skip_step(&mut self, tool: &str, id: Option<&str>, input: &str) {
+        self.begin_step(tool, id, input);
+        let step = self.steps.last_mut().expect("just pushed");
+        step.status = StepStatus::Skipped;
+        step.finished_at = Some(step.started_at);
+    }
+
+    /// Output of the latest successful step with this id or, failing that,
+    /// this tool name.
+    pub fn artifact(&self, key: &str) -> Option<&str> {
+        let succeeded = || {
+            self.steps
+                .iter()
+                .rev()
+                .filter(|step| step.status == StepStatus::Succeeded)
+        };
+        succeeded()
+            .find(|step| step.id.as_deref() == Some(key))
+            .or_else(|| succeeded().find(|step| step.tool == key))
+            .and_then(|step| step.output.as_deref())
+    }
+
+    /// The latest steps, one line each with their status.
+    pub fn progress(&self) -> String {
+        let skipped = self.steps.len().saturating_sub(PROGRESS_STEPS);
+        let mut lines: Vec<String> = self.steps[skipped..]
+            .iter()
+            .map(|step| {
+                let status = match step.status {
+                    StepStatus::Running => "running",
+                    StepStatus::Succeeded => "succeeded",
+                    StepStatus::Failed => "failed",
+                    StepStatus::Skipped => "skipped",
+                };
+                let input: String = step.input.chars().take(120).collect();
+                format!("- [{}] {} `{}`", status, step.tool, input)
+            })
+            .collect();
+        if skipped > 0 {
+            lines.insert(0, format!("- ... {} earlier step(s)", skipped));
+        }
+        lines.join("\n")
     }
 
     pub fn set_output(&mut self, result: String) {
+        self.finish(result, true);
+    }
+
+    /// Stores the final output and moves to `Completed` or `Failed`.
+    pub fn finish(&mut self, result: String, success: bool) {
         self.output = Some(result);
-        self.current_state = "Completed".into();
+        self.status = match success {
+            true => TaskStatus::Completed,
+            false => TaskStatus::Failed,
+        };
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Writes the model as JSON (via a temporary file, so a crash never
+    /// leaves a truncated one).
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, text)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("{}: {}", path.display(), e))
     }
 }
 
-pub trait Model: Clone {
+/// Milliseconds since the Unix epoch, for step timestamps.
+#[cfg(feature = "runtime")]
+fn now_millis() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Without the runtime there is no clock to read (`SystemTime::now` panics on
+/// `wasm32-unknown-unknown`), so steps are stamped 0.
+#[cfg(not(feature = "runtime"))]
+fn now_millis() -> u64 {
+    0
+}
+
+/// What the agent loop needs from the model of a goal. `TaskModel` is the
+/// standard one; a domain model (e.g. a code review that collects findings)
+/// keeps a `TaskModel` for the executor's step records and adds its own state
+/// and planner rules. `BasicAgent<M>` plans, executes and replans any `Model`.
+pub trait Model {
+    /// The goal text planners and verifiers work from.
+    fn goal(&self) -> &str;
+
+    /// True once the goal has finished, successfully or not.
     fn is_complete(&self) -> bool;
+
+    /// A short description of where the goal stands, for logs and prompts.
     fn summary(&self) -> String;
+
+    /// Rules added to planner and replanner prompts. Defaults to the task's
+    /// limits and subtask progress (see `TaskModel::prompt_rules`).
+    fn prompt_rules(&self) -> String {
+        self.task().prompt_rules()
+    }
+
+    /// The step records, limits and subtasks the executor maintains.
+    fn task(&self) -> &TaskModel;
+
+    fn task_mut(&mut self) -> &mut TaskModel;
 }
 
 impl Model for TaskModel {
+    fn goal(&self) -> &str {
+        &self.goal
+    }
+
     fn is_complete(&self) -> bool {
-        self.output.is_some()
+        matches!(self.status, TaskStatus::Completed | TaskStatus::Failed)
     }
 
     fn summary(&self) -> String {
-        format!("Goal: {}\nStatus: {}", self.goal, self.current_state)
+        format!("Goal: {}\nStatus: {:?}", self.goal, self.status)
+    }
+
+    fn task(&self) -> &TaskModel {
+        self
+    }
+
+    fn task_mut(&mut self) -> &mut TaskModel {
+        self
     }
 }
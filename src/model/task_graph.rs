@@ -0,0 +1,114 @@
+// src/model/task_graph.rs
+
+use crate::protocol::PlanStep;
+use crate::protocol::graph::{PlanGraph, PlanNode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtaskStatus {
+    #[default]
+    Pending,
+    Done,
+    Failed,
+}
+
+/// One part of a decomposed goal, runnable once every subtask in
+/// `depends_on` is done.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Subtask {
+    pub id: String,
+    pub description: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub status: SubtaskStatus,
+    /// Output of its latest execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub attempts: usize,
+}
+
+/// A goal broken into subtasks with dependencies (see
+/// `protocol::decomposer`). Each subtask is planned and executed on its own,
+/// and its status is kept on the `TaskModel`, so finished subtasks are not
+/// redone after a replan.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TaskGraph {
+    pub subtasks: Vec<Subtask>,
+}
+
+impl TaskGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_subtask(mut self, id: &str, description: &str, depends_on: &[&str]) -> Self {
+        self.subtasks.push(Subtask {
+            id: id.into(),
+            description: description.into(),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            status: SubtaskStatus::Pending,
+            output: None,
+            attempts: 0,
+        });
+        self
+    }
+
+    /// Subtask indices in dependency order. Errors on duplicate ids, unknown
+    /// dependencies and cycles.
+    pub fn order(&self) -> Result<Vec<usize>, String> {
+        let graph = PlanGraph {
+            nodes: self
+                .subtasks
+                .iter()
+                .map(|subtask| PlanNode {
+                    id: subtask.id.clone(),
+                    step: PlanStep::Info(subtask.description.clone()),
+                    depends_on: subtask.depends_on.clone(),
+                })
+                .collect(),
+        };
+        Ok(graph.layers()?.into_iter().flatten().collect())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Subtask> {
+        self.subtasks.iter().find(|subtask| subtask.id == id)
+    }
+
+    pub fn is_done(&self, id: &str) -> bool {
+        self.get(id)
+            .is_some_and(|subtask| subtask.status == SubtaskStatus::Done)
+    }
+
+    /// Whether every dependency of subtask `index` is done.
+    pub fn is_ready(&self, index: usize) -> bool {
+        self.subtasks[index]
+            .depends_on
+            .iter()
+            .all(|dep| self.is_done(dep))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.subtasks
+            .iter()
+            .all(|subtask| subtask.status == SubtaskStatus::Done)
+    }
+
+    /// One line per subtask with its status, for prompts and logs.
+    pub fn progress(&self) -> String {
+        self.subtasks
+            .iter()
+            .map(|subtask| {
+                let status = match subtask.status {
+                    SubtaskStatus::Done => "done",
+                    SubtaskStatus::Failed => "failed",
+                    SubtaskStatus::Pending => "pending",
+                };
+                format!("- [{}] {}: {}", status, subtask.id, subtask.description)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,80 @@
+// src/policy/mod.rs
+
+pub mod plan_edit;
+pub mod replan;
+pub mod user;
+
+use crate::tools::ToolSpec;
+use serde::{Deserialize, Serialize};
+use std::io::{Write, stdin, stdout};
+
+/// Decides whether a side-effectful action may run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalPolicy {
+    /// Prompt on stdin before every action.
+    #[default]
+    AlwaysAsk,
+    /// Approve everything without prompting.
+    AutoApprove,
+    /// Refuse everything without prompting.
+    DenyAll,
+    /// Prompt only for tools tagged `destructive`; approve everything else.
+    AskDestructive,
+}
+
+impl ApprovalPolicy {
+    pub fn approve(&self, action: &str) -> bool {
+        match self {
+            ApprovalPolicy::AutoApprove => true,
+            ApprovalPolicy::DenyAll => false,
+            ApprovalPolicy::AlwaysAsk | ApprovalPolicy::AskDestructive => {
+                print!("{}? (Y/n): ", action);
+                stdout().flush().unwrap();
+                let mut line = String::new();
+                stdin().read_line(&mut line).unwrap();
+                let line = line.trim();
+                !(line == "n" || line == "N")
+            }
+        }
+    }
+
+    /// Approval for high-risk actions, whatever the policy: prompts even under
+    /// `AutoApprove` and only accepts a typed `yes`; `DenyAll` refuses outright.
+    pub fn approve_elevated(&self, action: &str, reasons: &[String]) -> bool {
+        if *self == ApprovalPolicy::DenyAll {
+            return false;
+        }
+        println!("⚠️ HIGH-RISK: {}", action);
+        for reason in reasons {
+            println!("   - {}", reason);
+        }
+        print!("Type 'yes' to run it anyway: ");
+        stdout().flush().unwrap();
+        let mut line = String::new();
+        // EOF or a read error (no terminal) counts as a refusal.
+        let _ = stdin().read_line(&mut line);
+        line.trim() == "yes"
+    }
+
+    /// The decision for `spec` when it can be made without prompting, or
+    /// `None` if this policy would ask the user.
+    pub fn decide_without_prompt(&self, spec: &ToolSpec) -> Option<bool> {
+        let destructive = spec.tags.iter().any(|tag| tag == "destructive");
+        match self {
+            ApprovalPolicy::AutoApprove => Some(true),
+            ApprovalPolicy::DenyAll => Some(false),
+            ApprovalPolicy::AskDestructive if !destructive => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn approve_tool(&self, spec: &ToolSpec, input: &str) -> bool {
+        let action = format!("Execute {}: `{}`", spec.name, input);
+        let destructive = spec.tags.iter().any(|tag| tag == "destructive");
+        match self {
+            ApprovalPolicy::AskDestructive if !destructive => true,
+            _ => self.approve(&action),
+        }
+    }
+}
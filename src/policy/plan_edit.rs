@@ -0,0 +1,184 @@
+// src/policy/plan_edit.rs
+
+use crate::context::Context;
+use crate::protocol::{Plan, PlanStep};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::Command;
+
+const HELP: &str = "\
+  a | <enter>   accept the plan (after validation); a! accepts despite errors
+  d <n>         delete step n
+  m <n> <to>    move step n to position <to>
+  e <n>         edit the input of tool step n (or the message of info step n)
+  editor        edit the whole plan as YAML in $EDITOR
+  p             print the plan
+  q             abort without running anything";
+
+/// Interactive review of a plan before it runs: prints the plan and lets the
+/// user delete, reorder and edit steps (or rewrite it in `$EDITOR`), then
+/// re-validates it against `context`'s tools. `None` if the user aborts.
+pub fn edit_plan(plan: Plan, context: &Context) -> Option<Plan> {
+    edit_plan_with(plan, context, io::stdin().lock(), io::stdout())
+}
+
+/// `edit_plan` over arbitrary input and output, e.g. for scripted sessions.
+pub fn edit_plan_with<R: BufRead, W: Write>(
+    plan: Plan,
+    context: &Context,
+    mut input: R,
+    mut output: W,
+) -> Option<Plan> {
+    let mut plan = plan;
+    print_plan(&plan, &mut output);
+    let _ = writeln!(output, "Review the plan ('?' for commands).");
+
+    loop {
+        let _ = write!(output, "plan> ");
+        let _ = output.flush();
+        let mut line = String::new();
+        if input.read_line(&mut line).unwrap_or(0) == 0 {
+            // EOF: nobody is there to confirm.
+            return None;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words.as_slice() {
+            [] | ["a"] => {
+                let errors = plan_errors(&plan, context);
+                if errors.is_empty() {
+                    return Some(plan);
+                }
+                Err(format!(
+                    "Plan has problems ('a!' to accept anyway):\n{}",
+                    errors.join("\n")
+                ))
+            }
+            ["a!"] => return Some(plan),
+            ["q"] => return None,
+            ["?"] | ["help"] => {
+                let _ = writeln!(output, "{}", HELP);
+                Ok(())
+            }
+            ["p"] => {
+                print_plan(&plan, &mut output);
+                Ok(())
+            }
+            ["d", n] => step_index(&plan, n).map(|i| {
+                plan.steps.remove(i);
+            }),
+            ["m", n, to] => step_index(&plan, n).and_then(|from| {
+                let to = position(to, plan.steps.len())?;
+                let step = plan.steps.remove(from);
+                plan.steps.insert(to, step);
+                Ok(())
+            }),
+            ["e", n] => step_index(&plan, n)
+                .and_then(|i| edit_step(&mut plan.steps[i], &mut input, &mut output)),
+            ["editor"] => edit_in_editor(&plan).map(|edited| plan = edited),
+            _ => Err(format!("Unknown command '{}' ('?' for help)", line.trim())),
+        };
+        match result {
+            Ok(()) if !matches!(words.as_slice(), ["?"] | ["help"] | ["p"]) => {
+                print_plan(&plan, &mut output)
+            }
+            Ok(()) => {}
+            Err(e) => {
+                let _ = writeln!(output, "⚠️ {}", e);
+            }
+        }
+    }
+}
+
+fn print_plan<W: Write>(plan: &Plan, output: &mut W) {
+    let _ = writeln!(output, "--- PLAN ({} steps) ---", plan.steps.len());
+    if let Some(steps) = plan.to_json().as_array() {
+        for (i, step) in steps.iter().enumerate() {
+            let _ = writeln!(output, "{:>3}. {}", i + 1, step);
+        }
+    }
+}
+
+/// Validation problems that should stop execution; style hints are skipped.
+fn plan_errors(plan: &Plan, context: &Context) -> Vec<String> {
+    plan.validate(context)
+        .iter()
+        .filter(|error| error.is_error())
+        .map(|error| {
+            let (message, hint) = error.hint();
+            match hint {
+                Some(hint) => format!("  - {} {}", message, hint),
+                None => format!("  - {}", message),
+            }
+        })
+        .collect()
+}
+
+/// The 0-based index of 1-based step `n`.
+fn step_index(plan: &Plan, n: &str) -> Result<usize, String> {
+    match n.parse::<usize>() {
+        Ok(n) if (1..=plan.steps.len()).contains(&n) => Ok(n - 1),
+        _ => Err(format!(
+            "No step '{}' (the plan has {} steps)",
+            n,
+            plan.steps.len()
+        )),
+    }
+}
+
+/// The 0-based insertion index for 1-based position `to` after removing a step.
+fn position(to: &str, len: usize) -> Result<usize, String> {
+    match to.parse::<usize>() {
+        Ok(to) if (1..=len).contains(&to) => Ok(to - 1),
+        _ => Err(format!("No position '{}' (1-{})", to, len)),
+    }
+}
+
+fn edit_step<R: BufRead, W: Write>(
+    step: &mut PlanStep,
+    input: &mut R,
+    output: &mut W,
+) -> Result<(), String> {
+    let target = match step {
+        PlanStep::ToolCall { input, .. } => input,
+        PlanStep::Info(message) => message,
+        PlanStep::AskUser { question, .. } => question,
+        _ => return Err("Use 'editor' to edit conditions and loops".into()),
+    };
+    let _ = writeln!(output, "current: {}", target);
+    let _ = write!(output, "new (empty keeps it): ");
+    let _ = output.flush();
+    let mut line = String::new();
+    input.read_line(&mut line).map_err(|e| e.to_string())?;
+    let line = line.trim_end_matches(['\r', '\n']);
+    if !line.trim().is_empty() {
+        *target = line.to_string();
+    }
+    Ok(())
+}
+
+/// Round-trips the plan through a YAML file opened in `$VISUAL`/`$EDITOR` (or `vi`).
+fn edit_in_editor(plan: &Plan) -> Result<Plan, String> {
+    let path = env::temp_dir().join(format!("agentic-plan-{}.yaml", std::process::id()));
+    plan.to_file(&path)?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".into());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .map_err(|e| format!("Failed to start editor '{}': {}", editor, e));
+
+    let edited = status.and_then(|status| {
+        if !status.success() {
+            return Err(format!("Editor exited with {}; plan unchanged", status));
+        }
+        Plan::from_file(&path)
+    });
+    let _ = fs::remove_file(&path);
+    edited
+}
@@ -0,0 +1,104 @@
+// src/policy/replan.rs
+
+use crate::tools::LLMTool;
+use std::collections::VecDeque;
+
+/// What to do once a run has used up its follow-up plans.
+#[derive(Clone)]
+pub enum Escalation {
+    /// Keep replanning with a (typically bigger) model.
+    SwitchModel(Box<LLMTool>),
+    /// Ask the user how to proceed; their answer guides the next replan, and
+    /// no answer aborts.
+    AskUser,
+    /// Stop with a summary of what was tried.
+    Abort,
+}
+
+/// Bounds replanning: up to `max_replans` follow-up plans, then each
+/// escalation in turn (each one allowing `max_replans` more), then abort.
+#[derive(Clone)]
+pub struct ReplanPolicy {
+    pub max_replans: usize,
+    pub escalations: Vec<Escalation>,
+}
+
+impl Default for ReplanPolicy {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl ReplanPolicy {
+    pub fn new(max_replans: usize) -> Self {
+        Self {
+            max_replans,
+            escalations: Vec::new(),
+        }
+    }
+
+    /// Appends `escalation` to those tried once replans run out.
+    pub fn then(mut self, escalation: Escalation) -> Self {
+        self.escalations.push(escalation);
+        self
+    }
+
+    /// A fresh counter for one run.
+    pub fn guard(&self) -> ReplanGuard {
+        ReplanGuard {
+            max_replans: self.max_replans,
+            escalations: self.escalations.iter().cloned().collect(),
+            replans: 0,
+            attempts: 0,
+        }
+    }
+}
+
+/// Where a run stands against its `ReplanPolicy`.
+pub enum ReplanStep {
+    /// Still within the limit: replan as usual.
+    Replan,
+    /// The limit was reached; apply this escalation first.
+    Escalate(Escalation),
+}
+
+/// Counts a run's replans against a `ReplanPolicy`; see `ReplanPolicy::guard`.
+pub struct ReplanGuard {
+    max_replans: usize,
+    escalations: VecDeque<Escalation>,
+    /// Replans since the last escalation.
+    replans: usize,
+    attempts: usize,
+}
+
+impl ReplanGuard {
+    /// Accounts for one more replan. Past the limit this yields the next
+    /// escalation (`Abort` once none are left), which restarts the count.
+    pub fn advance(&mut self) -> ReplanStep {
+        if self.replans < self.max_replans {
+            self.replans += 1;
+            self.attempts += 1;
+            return ReplanStep::Replan;
+        }
+        let escalation = self.escalations.pop_front().unwrap_or(Escalation::Abort);
+        if !matches!(escalation, Escalation::Abort) {
+            self.replans = 1;
+            self.attempts += 1;
+        }
+        ReplanStep::Escalate(escalation)
+    }
+
+    /// Follow-up plans allowed so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+}
+
+/// The agent's answer to `ReplanGuard::advance`; see `BasicAgent::check_replan`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplanDecision {
+    /// Replan, with the user's guidance when they were asked.
+    Replan { guidance: Option<String> },
+    /// Stop replanning; holds a summary of the attempts.
+    Abort(String),
+}
@@ -0,0 +1,69 @@
+// src/policy/user.rs
+
+use std::collections::HashMap;
+use std::io::{Write, stdin, stdout};
+
+/// Answers the questions of `PlanStep::AskUser` steps. The default asks on
+/// the terminal; embedders supply answers programmatically with
+/// `Context::with_user_interface`.
+pub trait UserInterface: Send + Sync {
+    /// The answer to `question`, stored as `$input[variable]`, or `None` when
+    /// there is none (the step then fails).
+    fn ask(&self, question: &str, variable: &str) -> Option<String>;
+
+    /// Whether `action` may run, when the approval policy would prompt for
+    /// it; `reasons` explain a high-risk call. `None` (the default) leaves the
+    /// prompt to the policy, i.e. the terminal.
+    fn approve(&self, _action: &str, _reasons: &[String]) -> Option<bool> {
+        None
+    }
+}
+
+/// Prompts on stdin. EOF or a read error (no terminal) gives no answer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TerminalUser;
+
+impl UserInterface for TerminalUser {
+    fn ask(&self, question: &str, _variable: &str) -> Option<String> {
+        print!("❓ {} ", question);
+        stdout().flush().ok()?;
+        let mut line = String::new();
+        match stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim_end_matches(['\r', '\n']).to_string()),
+        }
+    }
+}
+
+/// Fixed answers keyed by variable name; other questions go unanswered.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedAnswers {
+    answers: HashMap<String, String>,
+}
+
+impl ScriptedAnswers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, variable: &str, answer: &str) -> Self {
+        self.answers.insert(variable.into(), answer.into());
+        self
+    }
+}
+
+impl UserInterface for ScriptedAnswers {
+    fn ask(&self, _question: &str, variable: &str) -> Option<String> {
+        self.answers.get(variable).cloned()
+    }
+}
+
+/// Any `Fn(question, variable) -> Option<String>` answers questions too.
+impl<F> UserInterface for F
+where
+    F: Fn(&str, &str) -> Option<String> + Send + Sync,
+{
+    fn ask(&self, question: &str, variable: &str) -> Option<String> {
+        self(question, variable)
+    }
+}
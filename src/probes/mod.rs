@@ -0,0 +1,162 @@
+// src/probes/mod.rs
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+/// Toolchains probed by `ProbeSet::standard`, as `(name, program)`; each
+/// reports the first line of `<program> --version`.
+const TOOLCHAINS: [(&str, &str); 8] = [
+    ("rust", "rustc"),
+    ("cargo", "cargo"),
+    ("python", "python3"),
+    ("node", "node"),
+    ("npm", "npm"),
+    ("go", "go"),
+    ("java", "java"),
+    ("make", "make"),
+];
+
+type ProbeFn = Arc<dyn Fn(&Path) -> Option<String> + Send + Sync>;
+
+/// One read-only fact about the environment; `None` when it does not apply
+/// (e.g. a toolchain that is not installed).
+#[derive(Clone)]
+pub struct Probe {
+    pub name: String,
+    run: ProbeFn,
+}
+
+impl Probe {
+    pub fn new(name: &str, run: impl Fn(&Path) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self {
+            name: name.into(),
+            run: Arc::new(run),
+        }
+    }
+
+    /// The first non-empty line `program args..` prints (stdout, else
+    /// stderr), run in the probed directory; `None` if it fails.
+    pub fn command(name: &str, program: &str, args: &[&str]) -> Self {
+        let program = program.to_string();
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Self::new(name, move |dir| first_line_of(&program, &args, dir))
+    }
+}
+
+/// Cheap, read-only discovery (OS, working directory, git branch, installed
+/// toolchains) run before planning, so the planner writes commands that fit
+/// the actual environment. See `LLMPlanner::with_probes`.
+#[derive(Clone, Default)]
+pub struct ProbeSet {
+    probes: Vec<Probe>,
+}
+
+impl ProbeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// OS, working directory, git branch and the common toolchains.
+    pub fn standard() -> Self {
+        let mut set = Self::new()
+            .with(Probe::new("os", |_| {
+                Some(format!(
+                    "{} {}",
+                    std::env::consts::OS,
+                    std::env::consts::ARCH
+                ))
+            }))
+            .with(Probe::new("cwd", |dir| {
+                let dir = std::env::current_dir().ok()?.join(dir);
+                Some(dir.canonicalize().unwrap_or(dir).display().to_string())
+            }))
+            .with(Probe::command(
+                "git_branch",
+                "git",
+                &["rev-parse", "--abbrev-ref", "HEAD"],
+            ));
+        for (name, program) in TOOLCHAINS {
+            set = set.with(Probe::command(name, program, &["--version"]));
+        }
+        set
+    }
+
+    pub fn with(mut self, probe: Probe) -> Self {
+        self.probes.push(probe);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    /// Runs every probe in `dir`, in parallel; facts come back in probe order.
+    pub fn run(&self, dir: &Path) -> Environment {
+        let facts = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .probes
+                .iter()
+                .map(|probe| scope.spawn(move || (probe.name.clone(), (probe.run)(dir))))
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().ok())
+                .filter_map(|(name, value)| Some((name, value?)))
+                .collect()
+        });
+        Environment { facts }
+    }
+}
+
+/// What a `ProbeSet` found.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Environment {
+    pub facts: Vec<(String, String)>,
+}
+
+impl Environment {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.facts
+            .iter()
+            .find(|(fact, _)| fact == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Planner prompt section listing the facts; empty when there are none.
+    pub fn prompt_section(&self) -> String {
+        if self.facts.is_empty() {
+            return String::new();
+        }
+        let facts = self
+            .facts
+            .iter()
+            .map(|(name, value)| format!("- {}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "ENVIRONMENT (commands must work here; only use toolchains listed):\n{}\n\n",
+            facts
+        )
+    }
+}
+
+fn first_line_of(program: &str, args: &[String], dir: &Path) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,198 @@
+// src/prompts/mod.rs
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Built-in templates, overridable per name (and per model) in a `PromptRegistry`.
+const BUILTIN: [(&str, &str); 3] = [
+    ("planner", include_str!("planner.txt")),
+    ("function_planner", include_str!("function_planner.txt")),
+    ("replanner", include_str!("replanner.txt")),
+];
+
+/// A named prompt with `{{variable}}` placeholders.
+///
+/// Placeholders are identifiers (letters, digits, `_`), optionally padded with
+/// spaces; any other `{{` is kept as literal text, so JSON examples need no
+/// escaping. Substituted values are not scanned for placeholders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub source: String,
+}
+
+impl PromptTemplate {
+    pub fn new(name: &str, source: &str) -> Self {
+        Self {
+            name: name.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Loads `dir/planner.txt` as `planner`, `dir/planner.gpt-4o.txt` as `planner.gpt-4o`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.strip_suffix(".txt").unwrap_or(name))
+            .ok_or_else(|| format!("Invalid template path {}", path.display()))?;
+        let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Self::new(name, &source))
+    }
+
+    /// The built-in template called `name`, if there is one.
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(name, source)| Self::new(name, source))
+    }
+
+    /// Placeholder names in order of first use.
+    pub fn variables(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for (_, name, _) in placeholders(&self.source) {
+            if !names.iter().any(|known| known == name) {
+                names.push(name.to_string());
+            }
+        }
+        names
+    }
+
+    /// Fills every placeholder from `vars`; an unknown placeholder is an error.
+    pub fn render(&self, vars: &[(&str, &str)]) -> Result<String, String> {
+        let mut out = String::with_capacity(self.source.len());
+        let mut last = 0;
+        for (start, name, end) in placeholders(&self.source) {
+            let value = vars
+                .iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| {
+                    format!(
+                        "Template '{}' uses undefined variable '{}' (available: {})",
+                        self.name,
+                        name,
+                        vars.iter()
+                            .map(|(var, _)| *var)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+            out.push_str(&self.source[last..start]);
+            out.push_str(value);
+            last = end;
+        }
+        out.push_str(&self.source[last..]);
+        Ok(out)
+    }
+}
+
+/// `(start, name, end)` byte ranges of each `{{name}}` in `source`.
+fn placeholders(source: &str) -> Vec<(usize, &str, usize)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = source[offset..].find("{{") {
+        let start = offset + open;
+        let Some(close) = source[start + 2..].find("}}") else {
+            break;
+        };
+        let name = source[start + 2..start + 2 + close].trim();
+        let is_identifier =
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_identifier {
+            let end = start + 2 + close + 2;
+            found.push((start, name, end));
+            offset = end;
+        } else {
+            offset = start + 2;
+        }
+    }
+    found
+}
+
+/// Prompt templates by name, starting from the built-ins.
+///
+/// A template named `<name>.<model-prefix>` replaces `<name>` for models whose
+/// name starts with that prefix (the longest match wins), so `planner.gpt-4o`
+/// tunes the planner for `gpt-4o` and `gpt-4o-mini` only.
+#[derive(Clone, Debug)]
+pub struct PromptRegistry {
+    templates: HashMap<String, PromptTemplate>,
+}
+
+impl Default for PromptRegistry {
+    fn default() -> Self {
+        let templates = BUILTIN
+            .iter()
+            .map(|(name, source)| (name.to_string(), PromptTemplate::new(name, source)))
+            .collect();
+        Self { templates }
+    }
+}
+
+impl PromptRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a template.
+    pub fn register(&mut self, template: PromptTemplate) {
+        self.templates.insert(template.name.clone(), template);
+    }
+
+    pub fn with_template(mut self, template: PromptTemplate) -> Self {
+        self.register(template);
+        self
+    }
+
+    /// Registers every `*.txt` file in `dir`; returns the template names loaded.
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<Vec<String>, String> {
+        let dir = dir.as_ref();
+        let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        paths.sort();
+
+        let mut names = Vec::new();
+        for path in paths {
+            let template = PromptTemplate::from_file(&path)?;
+            names.push(template.name.clone());
+            self.register(template);
+        }
+        Ok(names)
+    }
+
+    /// The template to use for `name` with `model`.
+    pub fn get(&self, name: &str, model: &str) -> Option<&PromptTemplate> {
+        let prefix = format!("{}.", name);
+        self.templates
+            .iter()
+            .filter_map(|(key, template)| {
+                let model_prefix = key.strip_prefix(&prefix)?;
+                model
+                    .starts_with(model_prefix)
+                    .then_some((model_prefix.len(), template))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, template)| template)
+            .or_else(|| self.templates.get(name))
+    }
+
+    pub fn render(&self, name: &str, model: &str, vars: &[(&str, &str)]) -> Result<String, String> {
+        self.get(name, model)
+            .ok_or_else(|| format!("No prompt template named '{}'", name))?
+            .render(vars)
+    }
+
+    /// Registered names, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.templates.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
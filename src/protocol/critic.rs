@@ -0,0 +1,83 @@
+// src/protocol/critic.rs
+
+use crate::context::Context;
+use crate::protocol::Plan;
+use crate::tools::llm::LLMTool;
+use serde::{Deserialize, Serialize};
+
+/// Reviews a proposed plan before it runs, looking for dangerous or
+/// nonsensical steps. See `agent::reviewer::ReviewerAgent`.
+pub trait Critic: Send + Sync {
+    /// Short label used in logs and events, e.g. `"security reviewer"`.
+    fn role(&self) -> &str;
+    fn critique(&self, context: &Context, goal: &str, plan: &Plan) -> Result<Critique, String>;
+}
+
+/// Structured outcome of a `Critic` review.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Critique {
+    pub approved: bool,
+    /// One entry per problem found; empty when approved.
+    #[serde(default)]
+    pub concerns: Vec<String>,
+    #[serde(default)]
+    pub reasoning: String,
+}
+
+/// Default `Critic`: asks the LLM, playing `role`, to approve or reject the plan.
+pub struct LLMCritic {
+    llm: LLMTool,
+    role: String,
+}
+
+impl LLMCritic {
+    pub fn new(llm: LLMTool) -> Self {
+        Self {
+            llm: llm.for_component("critic"),
+            role: "skeptical senior engineer".into(),
+        }
+    }
+
+    /// The reviewer persona, e.g. `"security reviewer"` or `"release manager"`.
+    pub fn with_role(mut self, role: &str) -> Self {
+        self.role = role.into();
+        self
+    }
+}
+
+impl Critic for LLMCritic {
+    fn role(&self) -> &str {
+        &self.role
+    }
+
+    fn critique(&self, context: &Context, goal: &str, plan: &Plan) -> Result<Critique, String> {
+        let plan_json = serde_json::to_string_pretty(&plan.to_json()).unwrap_or_default();
+        let tools = context.tools.render_prompt_section();
+        let prompt = format!(
+            r#"You are a {role} reviewing a plan proposed by an autonomous agent BEFORE it runs.
+
+GOAL: {goal}
+
+AVAILABLE TOOLS:
+{tools}
+
+PROPOSED PLAN:
+{plan_json}
+
+Reject the plan if any step is dangerous (destroys data, rewrites history, force-pushes, touches files outside the project, leaks secrets), uses a tool that does not exist, does not serve the goal, or is in an order that cannot work.
+Approve plans that are safe and sensible even if they are not optimal.
+
+Respond with ONLY a JSON object in this format:
+{{
+  "approved": false,
+  "concerns": ["One specific problem per entry, naming the step"],
+  "reasoning": "One or two sentences"
+}}"#,
+            role = self.role
+        );
+
+        self.llm
+            .generate_structured::<Critique>(&prompt)
+            .map_err(|e| format!("Critique failed: {}", e))
+    }
+}
@@ -0,0 +1,66 @@
+// src/protocol/decomposer.rs
+
+use crate::context::Context;
+use crate::model::task_graph::TaskGraph;
+use crate::tools::llm::LLMTool;
+
+/// Breaks a goal into subtasks with dependencies before any planning, so
+/// each subtask gets a small plan of its own (see `BasicAgent::execute_subtasks`).
+pub trait Decomposer: Send + Sync {
+    fn decompose(&self, context: &Context, goal: &str) -> Result<TaskGraph, String>;
+}
+
+/// Default `Decomposer`: asks the LLM for the subtask graph.
+pub struct LLMDecomposer {
+    llm: LLMTool,
+    max_subtasks: usize,
+}
+
+impl LLMDecomposer {
+    pub fn new(llm: LLMTool) -> Self {
+        Self {
+            llm: llm.for_component("decomposer"),
+            max_subtasks: 6,
+        }
+    }
+
+    /// Upper bound on the subtasks asked for (default 6).
+    pub fn with_max_subtasks(mut self, max_subtasks: usize) -> Self {
+        self.max_subtasks = max_subtasks.max(1);
+        self
+    }
+}
+
+impl Decomposer for LLMDecomposer {
+    fn decompose(&self, context: &Context, goal: &str) -> Result<TaskGraph, String> {
+        let tools = context.tools.render_prompt_section();
+        let prompt = format!(
+            r#"You are breaking a software engineering goal into subtasks for an autonomous agent. Each subtask will be planned and executed separately with these tools:
+{tools}
+
+GOAL: {goal}
+
+Split the goal into at most {max} concrete, verifiable subtasks. A subtask that needs the result of another lists that subtask's id in "depends_on". Simple goals need only one subtask.
+
+Respond with ONLY a JSON object in this format:
+{{
+  "subtasks": [
+    {{"id": "inspect", "description": "Check the repository state", "depends_on": []}},
+    {{"id": "fix", "description": "Fix the failing test", "depends_on": ["inspect"]}}
+  ]
+}}"#,
+            max = self.max_subtasks
+        );
+
+        let mut graph = self
+            .llm
+            .generate_structured::<TaskGraph>(&prompt)
+            .map_err(|e| format!("Decomposition failed: {}", e))?;
+        if graph.subtasks.is_empty() {
+            return Err("Decomposition returned no subtasks".into());
+        }
+        graph.subtasks.truncate(self.max_subtasks);
+        graph.order()?;
+        Ok(graph)
+    }
+}
@@ -0,0 +1,222 @@
+// src/protocol/evaluator.rs
+
+use crate::model::Model;
+use crate::protocol::{ExecutionResult, Feedback};
+#[cfg(feature = "runtime")]
+use crate::tools::goal_verifier::render_evidence;
+#[cfg(feature = "runtime")]
+use crate::tools::llm::LLMTool;
+use serde::{Deserialize, Serialize};
+
+/// Scores an execution against its goal. See `BasicAgent::with_evaluator`;
+/// without one, `evaluate` derives a score from the verdict or step results.
+pub trait Evaluator: Send + Sync {
+    fn evaluate(&self, goal: &str, result: &ExecutionResult) -> Result<Feedback, String>;
+
+    /// Scores `result` against `model`, whose step history covers earlier
+    /// plans too. The default only looks at the goal.
+    fn evaluate_task(
+        &self,
+        model: &dyn Model,
+        result: &ExecutionResult,
+    ) -> Result<Feedback, String> {
+        self.evaluate(model.goal(), result)
+    }
+}
+
+/// One rubric item an `LLMJudge` scores; `weight` sets its share of the total.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Criterion {
+    pub name: String,
+    pub description: String,
+    pub weight: u32,
+}
+
+impl Criterion {
+    pub fn new(name: &str, description: &str, weight: u32) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            weight,
+        }
+    }
+}
+
+/// The judge's score for one `Criterion`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CriterionScore {
+    pub name: String,
+    /// 0–100
+    pub score: u8,
+    #[serde(default)]
+    pub reasoning: String,
+}
+
+#[cfg(feature = "runtime")]
+/// Default `Evaluator`: an LLM judge that scores the execution evidence on
+/// each rubric criterion, explains its reasoning and lists what to fix. The
+/// overall score is the weighted mean of the criterion scores.
+pub struct LLMJudge {
+    llm: LLMTool,
+    rubric: Vec<Criterion>,
+}
+
+#[cfg(feature = "runtime")]
+#[derive(Deserialize)]
+struct JudgeReply {
+    #[serde(default)]
+    criteria: Vec<CriterionScore>,
+    #[serde(default)]
+    score: Option<u8>,
+    #[serde(default)]
+    critique: Vec<String>,
+    #[serde(default)]
+    reasoning: String,
+}
+
+#[cfg(feature = "runtime")]
+impl LLMJudge {
+    pub fn new(llm: LLMTool) -> Self {
+        Self {
+            llm: llm.for_component("judge"),
+            rubric: vec![
+                Criterion::new(
+                    "goal_completion",
+                    "The evidence shows every part of the goal was done.",
+                    3,
+                ),
+                Criterion::new(
+                    "correctness",
+                    "Outputs are free of errors and the results are right.",
+                    2,
+                ),
+                Criterion::new(
+                    "efficiency",
+                    "No redundant, repeated or unrelated steps.",
+                    1,
+                ),
+                Criterion::new(
+                    "safety",
+                    "Nothing was destroyed, leaked or changed beyond what the goal needs.",
+                    1,
+                ),
+            ],
+        }
+    }
+
+    /// Replaces the default rubric (goal completion, correctness, efficiency, safety).
+    pub fn with_rubric(mut self, rubric: Vec<Criterion>) -> Self {
+        self.rubric = rubric;
+        self
+    }
+
+    /// Adds a criterion to the rubric.
+    pub fn with_criterion(mut self, criterion: Criterion) -> Self {
+        self.rubric.push(criterion);
+        self
+    }
+
+    /// Weighted mean of the criteria the judge scored; `None` if it scored none.
+    fn weighted_score(&self, scores: &[CriterionScore]) -> Option<u8> {
+        let (total, weights) = scores.iter().fold((0u32, 0u32), |(total, weights), score| {
+            let weight = self
+                .rubric
+                .iter()
+                .find(|criterion| criterion.name == score.name)
+                .map_or(1, |criterion| criterion.weight);
+            (
+                total + weight * u32::from(score.score.min(100)),
+                weights + weight,
+            )
+        });
+        (weights > 0).then(|| (total as f32 / weights as f32).round() as u8)
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl Evaluator for LLMJudge {
+    fn evaluate(&self, goal: &str, result: &ExecutionResult) -> Result<Feedback, String> {
+        self.judge(goal, result, "")
+    }
+
+    fn evaluate_task(
+        &self,
+        model: &dyn Model,
+        result: &ExecutionResult,
+    ) -> Result<Feedback, String> {
+        let task = model.task();
+        let history = match task.steps.len() > result.steps.len() {
+            true => format!(
+                "\nALL STEPS FOR THIS GOAL (earlier plans included):\n{}\n",
+                task.progress()
+            ),
+            false => String::new(),
+        };
+        self.judge(model.goal(), result, &history)
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl LLMJudge {
+    /// `history` lists steps from earlier plans; empty for a single plan.
+    fn judge(
+        &self,
+        goal: &str,
+        result: &ExecutionResult,
+        history: &str,
+    ) -> Result<Feedback, String> {
+        let rubric = self
+            .rubric
+            .iter()
+            .map(|criterion| {
+                format!(
+                    "- {} (weight {}): {}",
+                    criterion.name, criterion.weight, criterion.description
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let verdict = match &result.verdict {
+            Some(verdict) => format!(
+                "\nVERIFIER VERDICT: achieved={} ({})\n",
+                verdict.achieved, verdict.reasoning
+            ),
+            None => String::new(),
+        };
+        let prompt = format!(
+            r#"You are an impartial judge grading the work of an autonomous agent.
+
+GOAL: {goal}
+
+EXECUTION EVIDENCE:
+{evidence}{history}{verdict}
+RUBRIC (score each criterion 0-100):
+{rubric}
+
+Judge ONLY from the evidence. Commands that ran without error do not prove the goal was met.
+
+Respond with ONLY a JSON object in this format:
+{{
+  "criteria": [{{"name": "goal_completion", "score": 0, "reasoning": "One sentence citing the evidence"}}],
+  "critique": ["One concrete thing to fix or do next per entry; empty if nothing"],
+  "reasoning": "One or two sentences summarizing the grade"
+}}"#,
+            evidence = render_evidence(result),
+        );
+
+        let reply: JudgeReply = self
+            .llm
+            .generate_structured(&prompt)
+            .map_err(|e| format!("Judge failed: {}", e))?;
+        let score = self
+            .weighted_score(&reply.criteria)
+            .or(reply.score)
+            .ok_or("Judge returned no scores")?;
+        Ok(Feedback {
+            score: score.min(100),
+            notes: reply.reasoning,
+            criteria: reply.criteria,
+            critique: reply.critique,
+        })
+    }
+}
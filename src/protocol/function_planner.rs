@@ -0,0 +1,221 @@
+// src/protocol/function_planner.rs
+
+use crate::context::Context;
+use crate::llm::{FunctionCall, FunctionDef, ModelTier};
+use crate::memory::PLANNER_NAMESPACE;
+use crate::model::Model;
+use crate::protocol::planner::{LLMPlanner, Planner};
+use crate::protocol::{Plan, PlanStep, tool_step_from_json};
+use crate::tools::llm::LLMTool;
+use crate::validation::plan::validate_in_context;
+use serde_json::{Value, json};
+
+/// Latest tool calls shown to the planner (e.g. from earlier subtasks).
+const HISTORY_LIMIT: usize = 20;
+
+/// The pseudo-function that becomes a `PlanStep::AskUser` step.
+const ASK_USER: &str = "ask_user";
+
+/// Plans through the provider's native function calling: every registered
+/// tool is offered as a function (see `FunctionDef::from_spec`) and the
+/// calls the model makes, in order, become the plan's tool steps, so no JSON
+/// is scraped out of the reply. Plans are linear; conditions and loops need
+/// the JSON planner.
+///
+/// Providers without function calling are planned for by the fallback
+/// `LLMPlanner`, whose environment probes this planner also uses.
+pub struct FunctionCallingPlanner {
+    llm: LLMTool,
+    fallback: LLMPlanner,
+}
+
+impl FunctionCallingPlanner {
+    pub fn new(llm: LLMTool) -> Self {
+        let fallback = LLMPlanner::new(llm.clone());
+        Self {
+            llm: llm.for_component("planner").for_tier(ModelTier::Smart),
+            fallback,
+        }
+    }
+
+    /// Replaces the default `LLMPlanner` over the same model.
+    pub fn with_fallback(mut self, planner: LLMPlanner) -> Self {
+        self.fallback = planner;
+        self
+    }
+
+    fn functions(context: &Context) -> Vec<FunctionDef> {
+        let mut functions: Vec<FunctionDef> = context
+            .tools
+            .list_specs()
+            .iter()
+            .map(FunctionDef::from_spec)
+            .collect();
+        if context.get_tool(ASK_USER).is_none() {
+            functions.push(FunctionDef::new(
+                ASK_USER,
+                "Asks the user a question; the answer is available to later calls as $input[variable].",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "question": { "type": "string" },
+                        "variable": { "type": "string", "description": "Name for the answer, e.g. \"branch\"" }
+                    },
+                    "required": ["question", "variable"]
+                }),
+            ));
+        }
+        functions
+    }
+
+    /// A call to a freeform tool carries its input as `{"input": "..."}`;
+    /// tools with an input schema get the arguments as `args`.
+    fn step(context: &Context, call: FunctionCall) -> PlanStep {
+        let tool = context.get_tool(&call.name);
+        if tool.is_none() && call.name == ASK_USER {
+            let text = |key: &str| {
+                call.arguments
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            return PlanStep::AskUser {
+                question: text("question"),
+                variable: text("variable"),
+            };
+        }
+        let has_schema = tool.is_some_and(|tool| tool.spec().input_schema.is_some());
+        match (has_schema, call.arguments) {
+            (false, Value::Object(mut arguments)) if arguments.contains_key("input") => {
+                let input = arguments.remove("input");
+                tool_step_from_json(None, call.name, input, None, None, Vec::new(), false)
+            }
+            (_, arguments) => tool_step_from_json(
+                None,
+                call.name,
+                None,
+                Some(arguments),
+                None,
+                Vec::new(),
+                false,
+            ),
+        }
+    }
+
+    fn plan_within(&self, context: &mut Context, goal: &str, task_rules: &str) -> Plan {
+        if !self.llm.supports_function_calling() {
+            context.log_in(
+                PLANNER_NAMESPACE,
+                "planner",
+                &format!(
+                    "Provider '{}' has no function calling; using the JSON planner",
+                    self.llm.provider().name()
+                ),
+            );
+            return self.fallback.plan_within(context, goal, task_rules);
+        }
+        context.log_in(
+            PLANNER_NAMESPACE,
+            "planner",
+            "Using function-calling planner",
+        );
+
+        let memory_dump = context
+            .memory()
+            .prompt_entries(goal, None)
+            .iter()
+            .map(|(label, content)| format!("[{}] {}", label, content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut critical_rules = task_rules.to_string();
+        if !context.secrets.is_empty() {
+            critical_rules.push_str(&format!(
+                "\n- Secrets are available as $secret[NAME] for NAME in: {}. Reference them by name; never ask for or write their values",
+                context.secrets.names().join(", ")
+            ));
+        }
+        if !context.env_vars.is_empty() {
+            critical_rules.push_str(&format!(
+                "\n- Environment variables are available as $env[VAR] for VAR in: {}. Reference them instead of guessing their values",
+                context.env_vars.join(", ")
+            ));
+        }
+        let environment_section = self.fallback.environment_section(context);
+        let history_section = context.tool_history.prompt_section(HISTORY_LIMIT);
+        let prompt = context.render_prompt(
+            "function_planner",
+            &self.llm.model,
+            &[
+                ("goal", goal),
+                ("memory", &memory_dump),
+                ("history", &history_section),
+                ("environment", &environment_section),
+                ("critical_rules", &critical_rules),
+            ],
+        );
+
+        let calls = match self.llm.call_functions(&prompt, &Self::functions(context)) {
+            Ok(calls) if calls.is_empty() => {
+                context.log_in(
+                    PLANNER_NAMESPACE,
+                    "planner",
+                    "❌ Planner made no function calls",
+                );
+                return Plan {
+                    steps: vec![PlanStep::Info("Planner made no function calls.".into())],
+                };
+            }
+            Ok(calls) => calls,
+            Err(e) => {
+                context.log_in(
+                    PLANNER_NAMESPACE,
+                    "planner",
+                    &format!("❌ Planner LLM failed: {}", e),
+                );
+                return Plan {
+                    steps: vec![PlanStep::Info("Planner LLM failed.".into())],
+                };
+            }
+        };
+        let plan = Plan {
+            steps: calls
+                .into_iter()
+                .map(|call| Self::step(context, call))
+                .collect(),
+        };
+
+        let steps_json = match plan.to_json() {
+            Value::Array(steps) => steps,
+            _ => Vec::new(),
+        };
+        context.log_in(
+            PLANNER_NAMESPACE,
+            "planner",
+            &Value::Array(steps_json.clone()).to_string(),
+        );
+        let validation_errors = validate_in_context(&steps_json, context, &[]);
+        for error in validation_errors.iter() {
+            let (msg, maybe_hint) = error.hint();
+            context.log_in(
+                PLANNER_NAMESPACE,
+                "planner",
+                &format!("⚠️ Validation {}: {}", error.severity(), msg),
+            );
+            if let Some(hint) = maybe_hint {
+                context.log_in(PLANNER_NAMESPACE, "planner", &format!("→ Hint: {}", hint));
+            }
+        }
+        plan
+    }
+}
+
+impl Planner for FunctionCallingPlanner {
+    fn generate_plan(&self, context: &mut Context, goal: &str) -> Plan {
+        self.plan_within(context, goal, "")
+    }
+
+    fn plan_task(&self, context: &mut Context, model: &dyn Model) -> Plan {
+        self.plan_within(context, model.goal(), &model.prompt_rules())
+    }
+}
@@ -0,0 +1,114 @@
+// src/protocol/graph.rs
+
+use crate::protocol::{Plan, PlanStep};
+use std::collections::HashMap;
+
+/// A step in a `PlanGraph`, runnable once every node in `depends_on` has run.
+#[derive(Clone, Debug)]
+pub struct PlanNode {
+    pub id: String,
+    pub step: PlanStep,
+    pub depends_on: Vec<String>,
+}
+
+/// Dependency-graph plan: independent tool calls (e.g. `git status` and
+/// `cargo check`) can run in parallel. Later nodes reference earlier results
+/// with `$output[node_id]`.
+#[derive(Clone, Debug, Default)]
+pub struct PlanGraph {
+    pub nodes: Vec<PlanNode>,
+}
+
+impl PlanGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_node(mut self, id: &str, step: PlanStep, depends_on: &[&str]) -> Self {
+        self.nodes.push(PlanNode {
+            id: id.into(),
+            step,
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        });
+        self
+    }
+
+    /// A linear plan as a chain, each step depending on the one before it.
+    pub fn from_plan(plan: &Plan) -> Self {
+        let mut graph = Self::new();
+        for (i, step) in plan.steps.iter().enumerate() {
+            let depends_on = if i == 0 {
+                Vec::new()
+            } else {
+                vec![format!("step{}", i)]
+            };
+            graph.nodes.push(PlanNode {
+                id: format!("step{}", i + 1),
+                step: step.clone(),
+                depends_on,
+            });
+        }
+        graph
+    }
+
+    /// Groups node indices into layers; every node's dependencies sit in an
+    /// earlier layer, so nodes within a layer are independent. Errors on
+    /// duplicate ids, unknown dependencies and cycles.
+    pub fn layers(&self) -> Result<Vec<Vec<usize>>, String> {
+        let mut index_of = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if index_of.insert(node.id.as_str(), i).is_some() {
+                return Err(format!("Duplicate node id '{}'", node.id));
+            }
+        }
+
+        let mut remaining_deps = vec![0; self.nodes.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            for dep in &node.depends_on {
+                let Some(&d) = index_of.get(dep.as_str()) else {
+                    return Err(format!(
+                        "Node '{}' depends on unknown node '{}'",
+                        node.id, dep
+                    ));
+                };
+                remaining_deps[i] += 1;
+                dependents[d].push(i);
+            }
+        }
+
+        let mut layers = Vec::new();
+        let mut ready: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| remaining_deps[i] == 0)
+            .collect();
+        let mut placed = 0;
+
+        while !ready.is_empty() {
+            let mut next = Vec::new();
+            for &i in &ready {
+                for &dependent in &dependents[i] {
+                    remaining_deps[dependent] -= 1;
+                    if remaining_deps[dependent] == 0 {
+                        next.push(dependent);
+                    }
+                }
+            }
+            placed += ready.len();
+            layers.push(ready);
+            ready = next;
+        }
+
+        if placed != self.nodes.len() {
+            let stuck = self
+                .nodes
+                .iter()
+                .zip(&remaining_deps)
+                .filter(|(_, deps)| **deps > 0)
+                .map(|(node, _)| node.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(format!("Dependency cycle between nodes: {}", stuck));
+        }
+        Ok(layers)
+    }
+}
@@ -1,34 +1,777 @@
 // src/protocol/mod.rs
 
+#[cfg(feature = "runtime")]
+pub mod critic;
+#[cfg(feature = "runtime")]
+pub mod decomposer;
+pub mod evaluator;
+#[cfg(feature = "runtime")]
+pub mod function_planner;
+pub mod graph;
+#[cfg(feature = "runtime")]
 pub mod planner;
+#[cfg(feature = "runtime")]
 pub mod replanner;
+pub mod transform;
+pub mod verifier;
+pub mod yaml;
+
+#[cfg(feature = "runtime")]
+use crate::context::Context;
+#[cfg(feature = "runtime")]
+use crate::validation::plan::{PlanValidationError, validate_in_context};
+use evaluator::CriterionScore;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+use transform::OutputTransform;
+use verifier::Verdict;
 
 #[derive(Clone, Debug)]
 pub enum PlanStep {
     Info(String),
-    ToolCall { name: String, input: String },
+    /// `args` carries structured arguments matching the tool's `input_schema`;
+    /// when present it takes precedence over the freeform `input`. With an `id`,
+    /// later steps can reference this call's output as `$output[id]`. `timeout`
+    /// overrides the tool's `ToolSpec::timeout`. `transform` post-processes a
+    /// successful call's output before later steps see it. A `background`
+    /// call starts a long-running process (a dev server, a watcher) and moves
+    /// on; see `Context::start_background`.
+    ToolCall {
+        id: Option<String>,
+        name: String,
+        input: String,
+        args: Option<Value>,
+        timeout: Option<Duration>,
+        transform: Vec<OutputTransform>,
+        background: bool,
+    },
+    /// Runs `then_steps` when `predicate` holds for earlier results, else `else_steps`.
+    Conditional {
+        predicate: Predicate,
+        then_steps: Vec<PlanStep>,
+        else_steps: Vec<PlanStep>,
+    },
+    /// Repeats `steps` up to `max_iterations` times, stopping early once
+    /// `until` holds (e.g. a command succeeded or its output matched).
+    Loop {
+        steps: Vec<PlanStep>,
+        max_iterations: usize,
+        until: Option<Predicate>,
+    },
+    /// Asks the user `question` through the context's `UserInterface`; later
+    /// steps reference the answer as `$input[variable]`.
+    AskUser {
+        question: String,
+        variable: String,
+    },
+}
+
+/// Upper bound on `PlanStep::Loop` iterations accepted from planners.
+pub const MAX_LOOP_ITERATIONS: usize = 10;
+
+/// A check against the most recent result of `output_of`. Every condition
+/// that is set must hold; a tool that has not run yet never satisfies it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Predicate {
+    pub output_of: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub succeeded: Option<bool>,
+}
+
+impl Predicate {
+    /// Evaluates against a tool's last output (or error text) and success flag.
+    pub fn evaluate(&self, last: Option<(&str, bool)>) -> Result<bool, String> {
+        let Some((text, success)) = last else {
+            return Ok(false);
+        };
+
+        if let Some(expected) = self.succeeded
+            && expected != success
+        {
+            return Ok(false);
+        }
+        if let Some(needle) = &self.contains
+            && !text.contains(needle.as_str())
+        {
+            return Ok(false);
+        }
+        if let Some(pattern) = &self.matches {
+            let regex =
+                Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+            if !regex.is_match(text) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl std::fmt::Display for Predicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "output of {}", self.output_of)?;
+        if let Some(expected) = self.succeeded {
+            write!(f, " {}", if expected { "succeeded" } else { "failed" })?;
+        }
+        if let Some(needle) = &self.contains {
+            write!(f, " contains {:?}", needle)?;
+        }
+        if let Some(pattern) = &self.matches {
+            write!(f, " matches /{}/", pattern)?;
+        }
+        Ok(())
+    }
+}
+
+impl PlanStep {
+    /// The step's `type` in the planner's JSON format.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PlanStep::Info(_) => "info",
+            PlanStep::ToolCall { .. } => "tool",
+            PlanStep::Conditional { .. } => "condition",
+            PlanStep::Loop { .. } => "loop",
+            PlanStep::AskUser { .. } => "ask",
+        }
+    }
+
+    pub fn tool(name: &str, input: &str) -> Self {
+        PlanStep::ToolCall {
+            id: None,
+            name: name.into(),
+            input: input.into(),
+            args: None,
+            timeout: None,
+            transform: Vec::new(),
+            background: false,
+        }
+    }
+
+    pub fn tool_with_args(name: &str, args: Value) -> Self {
+        PlanStep::ToolCall {
+            id: None,
+            name: name.into(),
+            input: String::new(),
+            args: Some(args),
+            timeout: None,
+            transform: Vec::new(),
+            background: false,
+        }
+    }
+
+    /// Names a tool call so its output can be referenced as `$output[id]`.
+    /// Has no effect on other step kinds.
+    pub fn with_id(mut self, step_id: &str) -> Self {
+        if let PlanStep::ToolCall { id, .. } = &mut self {
+            *id = Some(step_id.into());
+        }
+        self
+    }
+
+    /// Limits a tool call's run time. Has no effect on other step kinds.
+    pub fn with_timeout(mut self, limit: Duration) -> Self {
+        if let PlanStep::ToolCall { timeout, .. } = &mut self {
+            *timeout = Some(limit);
+        }
+        self
+    }
+
+    /// Starts a tool call as a background process instead of waiting for it.
+    /// Has no effect on other step kinds.
+    pub fn in_background(mut self) -> Self {
+        if let PlanStep::ToolCall { background, .. } = &mut self {
+            *background = true;
+        }
+        self
+    }
+
+    /// Adds a post-processor for a tool call's output. Has no effect on other
+    /// step kinds.
+    pub fn with_transform(mut self, step: OutputTransform) -> Self {
+        if let PlanStep::ToolCall { transform, .. } = &mut self {
+            transform.push(step);
+        }
+        self
+    }
+}
+
+/// Builds a tool step from planner JSON, where `input` may be a string or an
+/// object of structured arguments.
+pub(crate) fn tool_step_from_json(
+    id: Option<String>,
+    name: String,
+    input: Option<Value>,
+    args: Option<Value>,
+    timeout: Option<Duration>,
+    transform: Vec<OutputTransform>,
+    background: bool,
+) -> PlanStep {
+    let (input, args) = match (args, input) {
+        (Some(args), input) => (
+            input
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_default(),
+            Some(args),
+        ),
+        (None, Some(Value::String(input))) => (input, None),
+        (None, Some(Value::Null)) | (None, None) => (String::new(), None),
+        (None, Some(args)) => (String::new(), Some(args)),
+    };
+    PlanStep::ToolCall {
+        id,
+        name,
+        input,
+        args,
+        timeout,
+        transform,
+        background,
+    }
+}
+
+/// Reads a step's `timeout_secs`, where zero or less means no timeout.
+pub(crate) fn timeout_secs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(secs) if secs > 0.0 => Duration::try_from_secs_f64(secs)
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom("'timeout_secs' is out of range")),
+        _ => Ok(None),
+    }
+}
+
+/// Matches `$output[key]` references in step inputs.
+pub(crate) fn output_reference_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$output\[([^\]]+)\]").unwrap())
+}
+
+/// Matches `$env[VAR]` references to allowlisted environment variables.
+pub(crate) fn env_reference_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$env\[([A-Za-z_][A-Za-z0-9_]*)\]").unwrap())
+}
+
+/// What an `$output[key]` reference resolves to when `key` has no output.
+#[cfg(feature = "runtime")]
+pub(crate) fn missing_output(key: &str) -> String {
+    format!("(missing output for '{}')", key)
+}
+
+/// Matches what `missing_output` produces, capturing the key.
+#[cfg(feature = "runtime")]
+pub(crate) fn missing_output_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\(missing output for '([^']+)'\)").unwrap())
+}
+
+/// Matches `{{ name }}` placeholders in `render` templates.
+pub(crate) fn template_placeholder_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_.-]*)\s*\}\}").unwrap())
+}
+
+/// Matches `$input[variable]` references to `AskUser` answers.
+#[cfg(feature = "runtime")]
+pub(crate) fn input_reference_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$input\[([^\]]+)\]").unwrap())
 }
 
+/// JSON Schema for planner replies; lets Ollama and OpenAI constrain decoding
+/// to `{"plan": [...]}` with valid step types.
+#[cfg(feature = "runtime")]
+pub(crate) fn plan_response_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "plan": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "type": { "type": "string", "enum": ["tool", "info", "condition", "loop", "ask"] }
+                    },
+                    "required": ["type"]
+                }
+            }
+        },
+        "required": ["plan"]
+    })
+}
+
+/// Fixes common LLM step mistakes in place, recursing into branches and loop
+/// bodies: `{"type": "<tool>"}` becomes `{"type": "tool", "name": "<tool>"}`
+/// and made-up types such as `check` become info steps.
+#[cfg(feature = "runtime")]
+pub(crate) fn normalize_plan_steps(steps: &mut Value, tool_names: &[String]) {
+    let Some(steps) = steps.as_array_mut() else {
+        return;
+    };
+    for step in steps.iter_mut().filter_map(Value::as_object_mut) {
+        let kind = step
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        if tool_names.contains(&kind) {
+            step.insert("type".into(), json!("tool"));
+            step.entry("name").or_insert_with(|| json!(kind));
+        } else if ["check", "validate", "when"].contains(&kind.as_str()) {
+            step.insert("type".into(), json!("info"));
+            step.entry("message")
+                .or_insert_with(|| json!(format!("{} step", kind)));
+        }
+        for nested in ["then", "else", "steps"] {
+            if let Some(nested) = step.get_mut(nested) {
+                normalize_plan_steps(nested, tool_names);
+            }
+        }
+    }
+}
+
+/// Version of the plan file schema written by `Plan::to_file`.
+pub const PLAN_FORMAT_VERSION: u64 = 1;
+
+/// Serializes as `{"version": 1, "plan": [...]}`, with steps in the planner's
+/// JSON format, so generated plans can be saved, hand-edited and executed later.
 #[derive(Clone, Debug)]
 pub struct Plan {
     pub steps: Vec<PlanStep>,
 }
 
-#[derive(Clone, Debug)]
+impl Plan {
+    /// The plan in the planner's JSON step format (`[{"type": "tool", ...}]`).
+    pub fn to_json(&self) -> Value {
+        Value::Array(steps_to_json(&self.steps))
+    }
+
+    /// Inverse of `to_json`.
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let steps: Vec<PlannerStep> =
+            serde_json::from_value(value.clone()).map_err(|e| format!("Invalid plan: {}", e))?;
+        Ok(Self {
+            steps: steps.into_iter().map(PlanStep::from).collect(),
+        })
+    }
+
+    /// Reads a plan file: `{"version": 1, "plan": [...]}` or a bare step
+    /// array, as YAML for `.yaml`/`.yml` paths and JSON otherwise.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let value = if is_yaml(path) {
+            yaml::parse(&text)
+        } else {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        };
+        value
+            .and_then(|value| Self::from_document(&value))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Writes the plan in the format `from_file` reads, chosen by extension.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let document = self.to_document();
+        let text = if is_yaml(path) {
+            yaml::to_string(&document)
+        } else {
+            serde_json::to_string_pretty(&document).map_err(|e| e.to_string())? + "\n"
+        };
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Checks the plan against the tools (and their argument schemas)
+    /// registered in `context` and its `command_rules`. Problems with `Severity::Error` keep the
+    /// executor from running the plan.
+    #[cfg(feature = "runtime")]
+    pub fn validate(&self, context: &Context) -> Vec<PlanValidationError> {
+        self.validate_after(context, &[])
+    }
+
+    /// `validate` for a plan that continues earlier work: `$output[key]` may
+    /// also reference the keys in `prior_refs`.
+    #[cfg(feature = "runtime")]
+    pub fn validate_after(
+        &self,
+        context: &Context,
+        prior_refs: &[String],
+    ) -> Vec<PlanValidationError> {
+        validate_in_context(&steps_to_json(&self.steps), context, prior_refs)
+    }
+
+    fn to_document(&self) -> Value {
+        json!({ "version": PLAN_FORMAT_VERSION, "plan": self.to_json() })
+    }
+
+    fn from_document(value: &Value) -> Result<Self, String> {
+        let Some(document) = value.as_object() else {
+            return Self::from_json(value);
+        };
+        if let Some(version) = document.get("version") {
+            match version.as_u64() {
+                Some(version) if version <= PLAN_FORMAT_VERSION => {}
+                _ => {
+                    return Err(format!(
+                        "Unsupported plan version {} (expected {})",
+                        version, PLAN_FORMAT_VERSION
+                    ));
+                }
+            }
+        }
+        let steps = document
+            .get("plan")
+            .ok_or("Invalid plan: missing `plan` step list")?;
+        Self::from_json(steps)
+    }
+}
+
+impl Serialize for Plan {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_document().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Plan {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Self::from_document(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml" | "yml")
+    )
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct SimulationResult {
     pub predicted_outcome: String,
     pub warnings: Vec<String>,
+    /// `Tool::preview` of every tool call, in plan order (branches and loop
+    /// bodies included once).
+    pub steps: Vec<StepPreview>,
 }
 
-#[derive(Clone, Debug)]
+impl SimulationResult {
+    /// The riskiest previewed step, if the plan calls any tools.
+    pub fn max_risk(&self) -> Option<RiskLevel> {
+        self.steps.iter().map(|step| step.outcome.risk).max()
+    }
+}
+
+/// How much damage a step could do if it went wrong.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    /// Only reads state.
+    Low,
+    /// Changes files or other state in recoverable ways.
+    #[default]
+    Medium,
+    /// Destructive or hard to undo.
+    High,
+}
+
+impl std::fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+        })
+    }
+}
+
+/// What a tool call would do, predicted without running it (see `Tool::preview`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulationOutcome {
+    pub summary: String,
+    pub read_only: bool,
+    pub risk: RiskLevel,
+    /// Files or directories the call would create, change or delete.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Why the call is high-risk (see `risk::scan`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+impl SimulationOutcome {
+    pub fn read_only(summary: &str) -> Self {
+        Self {
+            summary: summary.into(),
+            read_only: true,
+            risk: RiskLevel::Low,
+            paths: Vec::new(),
+            reasons: Vec::new(),
+        }
+    }
+
+    pub fn mutating(summary: &str) -> Self {
+        Self {
+            summary: summary.into(),
+            read_only: false,
+            risk: RiskLevel::Medium,
+            paths: Vec::new(),
+            reasons: Vec::new(),
+        }
+    }
+
+    pub fn with_risk(mut self, risk: RiskLevel) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    pub fn with_paths(mut self, paths: Vec<String>) -> Self {
+        self.paths = paths;
+        self
+    }
+}
+
+/// The preview of one tool call in a simulated plan.
+#[derive(Clone, Debug, Serialize)]
+pub struct StepPreview {
+    pub tool: String,
+    pub input: String,
+    #[serde(flatten)]
+    pub outcome: SimulationOutcome,
+}
+
+/// Outcome of one executed tool call.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepResult {
+    pub id: Option<String>,
+    pub tool: String,
+    pub input: String,
+    pub success: bool,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    /// The tool's structured payload, if it returned one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct ExecutionResult {
     pub success: bool,
     pub output: Option<String>,
     pub errors: Vec<String>,
+    pub steps: Vec<StepResult>,
+    /// Set when a `Verifier` checked the goal after execution.
+    pub verdict: Option<Verdict>,
+    /// Why execution stopped early under the task's `RunLimits`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget_exceeded: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+impl ExecutionResult {
+    /// The verifier's verdict when there is one, else the absence of critical failures.
+    pub fn goal_achieved(&self) -> bool {
+        self.verdict
+            .as_ref()
+            .map_or(self.success, |verdict| verdict.achieved)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct Feedback {
-    pub score: u8, // 0–100 scale for now
+    pub score: u8, // 0–100
     pub notes: String,
+    /// Per-criterion scores, when an `LLMJudge` graded the run.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub criteria: Vec<CriterionScore>,
+    /// Concrete problems to fix; fed to the replanner.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub critique: Vec<String>,
+}
+
+impl Feedback {
+    /// Replanning input describing what the evaluation found lacking.
+    pub fn followup_prompt(&self) -> String {
+        let mut prompt = format!("Run scored {}/100: {}", self.score, self.notes);
+        if !self.critique.is_empty() {
+            prompt.push_str("\nTo fix:\n");
+            for item in &self.critique {
+                prompt.push_str(&format!("- {}\n", item));
+            }
+        }
+        prompt
+    }
+}
+
+/// A step in the planner's JSON format; see `Plan::to_json`.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum PlannerStep {
+    #[serde(rename = "tool")]
+    Tool {
+        #[serde(default)]
+        id: Option<String>,
+        name: String,
+        #[serde(default)]
+        input: Option<Value>,
+        #[serde(default)]
+        args: Option<Value>,
+        #[serde(default, deserialize_with = "timeout_secs")]
+        timeout_secs: Option<Duration>,
+        #[serde(default)]
+        transform: Vec<OutputTransform>,
+        #[serde(default)]
+        background: bool,
+    },
+    #[serde(rename = "info")]
+    Info { message: String },
+    #[serde(rename = "condition")]
+    Condition {
+        predicate: Predicate,
+        #[serde(default, rename = "then")]
+        then_steps: Vec<PlannerStep>,
+        #[serde(default, rename = "else")]
+        else_steps: Vec<PlannerStep>,
+    },
+    #[serde(rename = "loop")]
+    Loop {
+        steps: Vec<PlannerStep>,
+        max_iterations: usize,
+        #[serde(default)]
+        until: Option<Predicate>,
+    },
+    #[serde(rename = "ask")]
+    Ask { question: String, variable: String },
+}
+
+impl From<PlannerStep> for PlanStep {
+    fn from(step: PlannerStep) -> Self {
+        match step {
+            PlannerStep::Tool {
+                id,
+                name,
+                input,
+                args,
+                timeout_secs,
+                transform,
+                background,
+            } => tool_step_from_json(id, name, input, args, timeout_secs, transform, background),
+            PlannerStep::Info { message } => PlanStep::Info(message),
+            PlannerStep::Condition {
+                predicate,
+                then_steps,
+                else_steps,
+            } => PlanStep::Conditional {
+                predicate,
+                then_steps: then_steps.into_iter().map(PlanStep::from).collect(),
+                else_steps: else_steps.into_iter().map(PlanStep::from).collect(),
+            },
+            PlannerStep::Loop {
+                steps,
+                max_iterations,
+                until,
+            } => PlanStep::Loop {
+                steps: steps.into_iter().map(PlanStep::from).collect(),
+                max_iterations: max_iterations.min(MAX_LOOP_ITERATIONS),
+                until,
+            },
+            PlannerStep::Ask { question, variable } => PlanStep::AskUser { question, variable },
+        }
+    }
+}
+
+pub(crate) fn steps_to_json(steps: &[PlanStep]) -> Vec<Value> {
+    steps
+        .iter()
+        .map(|step| match step {
+            PlanStep::ToolCall {
+                id,
+                name,
+                input,
+                args,
+                timeout,
+                transform,
+                background,
+            } => {
+                let mut step = json!({ "type": "tool", "name": name });
+                match args {
+                    Some(args) => step["args"] = args.clone(),
+                    None => step["input"] = json!(input),
+                }
+                if let Some(id) = id {
+                    step["id"] = json!(id);
+                }
+                if let Some(timeout) = timeout {
+                    step["timeout_secs"] = json!(timeout.as_secs_f64());
+                }
+                if !transform.is_empty() {
+                    step["transform"] = json!(transform);
+                }
+                if *background {
+                    step["background"] = json!(true);
+                }
+                step
+            }
+            PlanStep::Info(message) => json!({ "type": "info", "message": message }),
+            PlanStep::Conditional {
+                predicate,
+                then_steps,
+                else_steps,
+            } => json!({
+                "type": "condition",
+                "predicate": predicate,
+                "then": steps_to_json(then_steps),
+                "else": steps_to_json(else_steps),
+            }),
+            PlanStep::Loop {
+                steps,
+                max_iterations,
+                until,
+            } => json!({
+                "type": "loop",
+                "max_iterations": max_iterations,
+                "until": until,
+                "steps": steps_to_json(steps),
+            }),
+            PlanStep::AskUser { question, variable } => json!({
+                "type": "ask",
+                "question": question,
+                "variable": variable,
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_timeouts_parse_and_reject_out_of_range_values() {
+        let timeout = |secs: Value| {
+            Plan::from_json(&json!([
+                {"type": "tool", "name": "run_command", "input": "cargo test", "timeout_secs": secs}
+            ]))
+            .map(|plan| match &plan.steps[0] {
+                PlanStep::ToolCall { timeout, .. } => *timeout,
+                _ => unreachable!(),
+            })
+        };
+        assert_eq!(timeout(json!(1.5)), Ok(Some(Duration::from_millis(1500))));
+        assert_eq!(timeout(json!(0)), Ok(None));
+        assert_eq!(timeout(json!(-3)), Ok(None));
+        assert!(
+            timeout(json!(1e20))
+                .unwrap_err()
+                .contains("'timeout_secs' is out of range")
+        );
+    }
 }
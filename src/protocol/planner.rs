@@ -1,45 +1,209 @@
 use crate::context::Context;
-use crate::protocol::{Plan, PlanStep};
-use crate::tools::Tool;
+use crate::ingest::KnowledgeBase;
+use crate::llm::{Conversation, ModelTier, Role, json_repair};
+use crate::memory::PLANNER_NAMESPACE;
+use crate::memory::failures::{FailedPlanRecord, FailedPlanStore};
+use crate::memory::lessons::LessonsStore;
+use crate::model::Model;
+use crate::probes::{Environment, ProbeSet};
+use crate::protocol::{Plan, PlanStep, PlannerStep, normalize_plan_steps, plan_response_schema};
 use crate::tools::goal_analyzer::GoalAnalyzerTool;
 use crate::tools::llm::LLMTool;
-use crate::validation::plan::validate_plan;
-use regex::Regex;
+use crate::tools::project_map::ProjectMap;
+use crate::tools::selection::ToolSelector;
+use crate::validation::plan::{PlanValidationError, correction_prompt, validate_in_context};
 use serde::Deserialize;
 use serde_json::Value;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
 
 pub trait Planner: Send + Sync {
     fn generate_plan(&self, context: &mut Context, goal: &str) -> Plan;
+
+    /// Plans `model`'s goal under its prompt rules (e.g. its limits). The
+    /// default ignores the rules; the executor still enforces the limits.
+    fn plan_task(&self, context: &mut Context, model: &dyn Model) -> Plan {
+        self.generate_plan(context, model.goal())
+    }
 }
 
+/// Latest tool calls shown to the planner (e.g. from earlier subtasks).
+const HISTORY_LIMIT: usize = 20;
+
+/// Times the planner is asked to fix a reply that is not valid JSON.
+const JSON_FIX_ATTEMPTS: usize = 1;
+
+/// Times an invalid plan is sent back to the planner with its validation errors.
+const MAX_CORRECTIONS: usize = 2;
+
 pub struct LLMPlanner {
     llm: LLMTool,
     goal_analyzer: GoalAnalyzerTool,
+    failure_store: Option<Arc<Mutex<FailedPlanStore>>>,
+    lessons: Option<Arc<Mutex<LessonsStore>>>,
+    knowledge: Option<Arc<KnowledgeBase>>,
+    probes: Option<ProbeSet>,
+    environment: OnceLock<Environment>,
+    project_map: Option<ProjectMap>,
+    project: OnceLock<String>,
+    memory_limit: Option<usize>,
+    tool_selector: Option<ToolSelector>,
 }
 
 impl LLMPlanner {
     pub fn new(llm: LLMTool) -> Self {
+        let llm = llm.for_component("planner").for_tier(ModelTier::Smart);
         let goal_analyzer = GoalAnalyzerTool::new(llm.clone());
-        Self { llm, goal_analyzer }
+        Self {
+            llm,
+            goal_analyzer,
+            failure_store: None,
+            lessons: None,
+            knowledge: None,
+            probes: None,
+            environment: OnceLock::new(),
+            project_map: None,
+            project: OnceLock::new(),
+            memory_limit: None,
+            tool_selector: None,
+        }
+    }
+
+    /// Include only the `k` memory entries most relevant to the goal instead
+    /// of the whole log.
+    pub fn with_memory_limit(mut self, k: usize) -> Self {
+        self.memory_limit = Some(k);
+        self
+    }
+
+    /// Show only the tools whose tags fit the goal and its goal type; the
+    /// others stay registered, so plans may still use them.
+    pub fn with_tool_selector(mut self, selector: ToolSelector) -> Self {
+        self.tool_selector = Some(selector);
+        self
+    }
+
+    /// Inject the most relevant project-doc chunks for each goal into the prompt.
+    pub fn with_knowledge_base(mut self, knowledge: Arc<KnowledgeBase>) -> Self {
+        self.knowledge = Some(knowledge);
+        self
+    }
+
+    /// Describe the environment (OS, git branch, toolchains) in the prompt.
+    /// The probes run once, before the first plan, in the context's workspace
+    /// (or the current directory).
+    pub fn with_probes(mut self, probes: ProbeSet) -> Self {
+        self.probes = Some(probes);
+        self
+    }
+
+    /// List the project's files (a condensed tree, see `ProjectMap`) in the
+    /// prompt. Like the probes, it is mapped once, before the first plan.
+    pub fn with_project_map(mut self, map: ProjectMap) -> Self {
+        self.project_map = Some(map);
+        self
+    }
+
+    /// Runs the probes and maps the project on first use; an empty section
+    /// without either.
+    pub(crate) fn environment_section(&self, context: &Context) -> String {
+        let dir = || {
+            context.workspace.as_ref().map_or_else(
+                || Path::new(".").to_path_buf(),
+                |ws| ws.root().to_path_buf(),
+            )
+        };
+        let mut section = String::new();
+        if let Some(probes) = &self.probes {
+            let environment = self.environment.get_or_init(|| {
+                let environment = probes.run(&dir());
+                context.log_in(
+                    PLANNER_NAMESPACE,
+                    "environment",
+                    &format!("🔎 Probed {} environment fact(s)", environment.facts.len()),
+                );
+                environment
+            });
+            section.push_str(&environment.prompt_section());
+        }
+        if let Some(map) = &self.project_map {
+            section.push_str(self.project.get_or_init(|| match map.scan(&dir()) {
+                Ok(summary) => {
+                    context.log_in(
+                        PLANNER_NAMESPACE,
+                        "environment",
+                        &format!("🗺️ Mapped {} project file(s)", summary.files),
+                    );
+                    summary.prompt_section()
+                }
+                Err(e) => {
+                    context.log_in(
+                        PLANNER_NAMESPACE,
+                        "planner",
+                        &format!("⚠️ Project map failed: {}", e),
+                    );
+                    String::new()
+                }
+            }));
+        }
+        section
+    }
+
+    /// Learn from past failures: similar failed plans become corrective few-shot
+    /// examples, and new validation/parse failures are recorded into the store.
+    pub fn with_failure_store(mut self, store: Arc<Mutex<FailedPlanStore>>) -> Self {
+        self.failure_store = Some(store);
+        self
+    }
+
+    /// Inject lessons from past runs' reflections on the same goal type
+    /// (as classified by the goal analyzer) into the prompt. The goal type is
+    /// logged to memory as `goal_type` so callers can record new lessons.
+    pub fn with_lessons_store(mut self, store: Arc<Mutex<LessonsStore>>) -> Self {
+        self.lessons = Some(store);
+        self
+    }
+
+    fn remember_failure(
+        &self,
+        context: &mut Context,
+        goal: &str,
+        plan_json: &str,
+        errors: Vec<String>,
+        diagnosis: &str,
+    ) {
+        let Some(store) = &self.failure_store else {
+            return;
+        };
+
+        let mut record = FailedPlanRecord::new(goal, plan_json, errors, diagnosis);
+        record.synthesize_correction(&self.llm);
+
+        if let Err(e) = store.lock().unwrap().record(record) {
+            context.log_in(
+                PLANNER_NAMESPACE,
+                "planner",
+                &format!("⚠️ Failed to record failed plan: {}", e),
+            );
+        }
     }
 }
 
-impl Planner for LLMPlanner {
-    fn generate_plan(&self, context: &mut Context, goal: &str) -> Plan {
-        let memory_dump = context
-            .memory()
-            .entries
+impl LLMPlanner {
+    pub(crate) fn plan_within(&self, context: &mut Context, goal: &str, task_rules: &str) -> Plan {
+        let memory_entries = context.memory().prompt_entries(goal, self.memory_limit);
+        let memory_dump = memory_entries
             .iter()
             .map(|(label, content)| format!("[{}] {}", label, content))
             .collect::<Vec<_>>()
             .join("\n");
 
         // 🎯 DYNAMIC INTELLIGENCE: Use GoalAnalyzerTool to generate context-aware examples
-        context.log("planner", "Using dynamic LLM planner");
+        context.log_in(PLANNER_NAMESPACE, "planner", "Using dynamic LLM planner");
 
-        let (examples_text, output_format, critical_rules) = match self
+        let (goal_type, mut examples_text, output_format, mut critical_rules) = match self
             .goal_analyzer
-            .analyze_context(goal, &memory_dump, false)
+            .analyze_context(goal, &memory_dump, false, &context.tools.names())
         {
             Ok(analysis) => {
                 let examples = analysis
@@ -50,13 +214,15 @@ impl Planner for LLMPlanner {
                     .join("\n\n");
 
                 (
+                    analysis.goal_type,
                     examples,
                     analysis.output_format,
                     analysis.critical_rules.join("\n"),
                 )
             }
             Err(e) => {
-                context.log(
+                context.log_in(
+                    PLANNER_NAMESPACE,
                     "planner",
                     &format!(
                         "⚠️ GoalAnalyzer failed: {}, falling back to hardcoded examples",
@@ -68,191 +234,218 @@ impl Planner for LLMPlanner {
                 let examples = r#"// Complete git workflow example
 {"plan": [{"type": "tool", "name": "run_command", "input": "git status --porcelain"}, {"type": "tool", "name": "reflect", "input": "$output[run_command]"}, {"type": "tool", "name": "run_command", "input": "git add ."}, {"type": "tool", "name": "run_command", "input": "git commit -m 'Update files'"}, {"type": "info", "message": "Goal completed"}]}"#.to_string();
 
-                (examples, "Standard JSON plan format with linear steps".to_string(), "- Use only linear sequences, no conditionals\n- Complete the entire git workflow\n- Use proper JSON format".to_string())
+                ("general".to_string(), examples, "Standard JSON plan format with linear steps".to_string(), "- Prefer linear sequences; branch only with condition steps\n- Complete the entire git workflow\n- Use proper JSON format".to_string())
             }
         };
 
-        let prompt = format!(
-            r#"You are an autonomous planning agent. Think through the problem step by step, then generate ONLY valid JSON.
-
-GOAL: {}
-
-MEMORY LOG:
-{}
-
-AVAILABLE TOOLS:
-- run_command: Execute shell commands (e.g. "git status", "git add .", "git commit -m 'message'")  
-- reflect: Analyze text or tool outputs (input: text or "$output[tool_name]")
-- analyze_error: Analyze errors and suggest fixes (input: error message)
-
-DYNAMIC EXAMPLES FOR THIS GOAL TYPE:
-{}
-
-OUTPUT FORMAT: {}
-
-CRITICAL RULES:
-{}
-
-🚨🚨🚨 CRITICAL FORMAT REQUIREMENTS 🚨🚨🚨
-EVERY SINGLE STEP MUST USE THE CORRECT FORMAT!
-
-❌❌❌ THESE ARE WRONG AND WILL CAUSE ERRORS ❌❌❌
-{{"type": "reflect"}} 
-{{"type": "run_command"}}
-{{"type": "analyze_error"}}
-
-✅✅✅ THESE ARE THE ONLY CORRECT FORMATS ✅✅✅
-{{"type": "tool", "name": "reflect"}}
-{{"type": "tool", "name": "run_command"}}  
-{{"type": "tool", "name": "analyze_error"}}
-{{"type": "info", "message": "text"}}
-
-🔥 MANDATORY RULES FOR EVERY STEP 🔥
-- EVERY tool step MUST have: "type": "tool", "name": "tool_name"
-- NEVER use "type": "tool_name" - this is WRONG
-- NEVER mix formats - be consistent throughout
-- Only "tool" and "info" are valid types
-- Tool names: ONLY "run_command", "reflect", or "analyze_error"
-
-TEMPLATE TO COPY EXACTLY:
-{{
-  "plan": [
-    {{"type": "tool", "name": "run_command", "input": "git status --porcelain"}},
-    {{"type": "tool", "name": "reflect", "input": "$output[run_command]"}},
-    {{"type": "tool", "name": "run_command", "input": "git add ."}},
-    {{"type": "tool", "name": "run_command", "input": "git commit -m 'Update'"}},
-    {{"type": "info", "message": "Goal completed"}}
-  ]
-}}
-
-🚨 EVERY STEP MUST FOLLOW THIS EXACT PATTERN 🚨
-STOP after outputting the JSON. NO other format is acceptable.
-"#,
-            goal, memory_dump, examples_text, output_format, critical_rules
-        );
-
-        let result = self.llm.execute(&prompt);
-        let raw = result.output.unwrap_or_default();
-
-        context.log("planner", "--- DEBUG: Raw planner output ---");
-        context.log("planner", &raw);
+        context.log("goal_type", &goal_type);
+        if let Some(store) = &self.lessons {
+            let lessons = store.lock().unwrap().prompt_section(&goal_type, 5);
+            if !lessons.is_empty() {
+                context.log_in(
+                    PLANNER_NAMESPACE,
+                    "planner",
+                    &format!("Including lessons from past {} runs", goal_type),
+                );
+                critical_rules.push('\n');
+                critical_rules.push_str(&lessons);
+            }
+        }
 
-        // Extract everything after </think> tag if present, otherwise use full response
-        let post_think = if raw.contains("</think>") {
-            raw.split("</think>").last().unwrap_or(&raw)
-        } else {
-            &raw
-        };
+        if !task_rules.is_empty() {
+            critical_rules.push('\n');
+            critical_rules.push_str(task_rules);
+        }
 
-        let cleaned = post_think
-            .lines()
-            .filter(|line| {
-                !line.trim_start().starts_with("```")
-                    && !line.trim_start().starts_with("---")
-                    && !line.trim_start().starts_with("### ")
-                    && !line.trim().is_empty()
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+        if !context.secrets.is_empty() {
+            critical_rules.push_str(&format!(
+                "\n- Secrets are available as $secret[NAME] for NAME in: {}. Reference them by name; never ask for or write their values",
+                context.secrets.names().join(", ")
+            ));
+        }
+        if !context.env_vars.is_empty() {
+            critical_rules.push_str(&format!(
+                "\n- Environment variables are available as $env[VAR] for VAR in: {}. Reference them instead of guessing their values",
+                context.env_vars.join(", ")
+            ));
+        }
 
-        // More robust JSON extraction - find the complete JSON object
-        let mut json_str = Regex::new(r#"(?s)\{\s*"plan"\s*:\s*\[.*?\]\s*\}"#)
-            .unwrap()
-            .find(&cleaned)
-            .map(|m| m.as_str().to_string())
+        let corrective = self
+            .failure_store
+            .as_ref()
+            .map(|store| store.lock().unwrap().corrective_examples(goal, 2))
             .unwrap_or_default();
-
-        // 🎯 DYNAMIC INTELLIGENCE: Auto-fix common LLM format mistakes
-        // Convert {"type": "tool_name"} to {"type": "tool", "name": "tool_name"}
-        json_str = json_str
-            .replace(
-                r#""type": "run_command""#,
-                r#""type": "tool", "name": "run_command""#,
-            )
-            .replace(
-                r#""type": "reflect""#,
-                r#""type": "tool", "name": "reflect""#,
-            )
-            .replace(
-                r#""type": "analyze_error""#,
-                r#""type": "tool", "name": "analyze_error""#,
+        if !corrective.is_empty() {
+            context.log_in(
+                PLANNER_NAMESPACE,
+                "planner",
+                "Including corrective examples from past failures",
             );
+            examples_text.push_str("\n\nLEARNED FROM PAST FAILURES:\n");
+            examples_text.push_str(&corrective);
+        }
 
-        // Remove JSON comments (// comments)
-        let comment_regex = Regex::new(r#",?\s*//[^\n\r]*"#).unwrap();
-        json_str = comment_regex.replace_all(&json_str, "").to_string();
-
-        // Remove invalid step types (condition, etc.) - replace with info
-        let invalid_types = ["condition", "check", "validate", "if", "when"];
-        for invalid_type in invalid_types {
-            let pattern = format!(r#""type": "{}""#, invalid_type);
-            json_str = json_str.replace(&pattern, r#""type": "info""#);
+        for pack in &context.skill_packs {
+            let examples = pack.render_examples();
+            if !examples.is_empty() {
+                examples_text.push_str("\n\n");
+                examples_text.push_str(&examples);
+            }
+            if let Some(rules) = pack.prompts.get("planner") {
+                critical_rules.push('\n');
+                critical_rules.push_str(rules);
+            }
         }
 
-        context.log("planner", "--- DEBUG: Extracted JSON block ---");
-        context.log("planner", &json_str);
+        let knowledge_section = match &self.knowledge {
+            Some(knowledge) => match knowledge.retrieve(goal, 3) {
+                Ok(chunks) if !chunks.is_empty() => format!(
+                    "PROJECT KNOWLEDGE (follow these conventions):\n{}\n\n",
+                    chunks
+                ),
+                Ok(_) => String::new(),
+                Err(e) => {
+                    context.log_in(
+                        PLANNER_NAMESPACE,
+                        "planner",
+                        &format!("⚠️ Knowledge retrieval failed: {}", e),
+                    );
+                    String::new()
+                }
+            },
+            None => String::new(),
+        };
 
-        if !result.success {
-            context.log("planner", &format!("❌ Planner LLM failed: {}", raw));
-            return Plan {
-                steps: vec![PlanStep::Info("Planner LLM failed.".into())],
-            };
-        }
+        let tools_section = match &self.tool_selector {
+            Some(selector) => {
+                let names = selector.select(&context.tools.list_specs(), goal, &goal_type);
+                if names.len() < context.tools.len() {
+                    context.log_in(
+                        PLANNER_NAMESPACE,
+                        "planner",
+                        &format!(
+                            "Showing {} of {} tools for a {} goal: {}",
+                            names.len(),
+                            context.tools.len(),
+                            goal_type,
+                            names.join(", ")
+                        ),
+                    );
+                }
+                context.tools.render_prompt_section_for(&names)
+            }
+            None => context.tools.render_prompt_section(),
+        };
+        let environment_section = self.environment_section(context);
+        let history_section = context.tool_history.prompt_section(HISTORY_LIMIT);
+
+        let prompt = context.render_prompt(
+            "planner",
+            &self.llm.model,
+            &[
+                ("goal", goal),
+                ("memory", &memory_dump),
+                ("history", &history_section),
+                ("knowledge", &knowledge_section),
+                ("environment", &environment_section),
+                ("tools", &tools_section),
+                ("examples", &examples_text),
+                ("output_format", &output_format),
+                ("critical_rules", &critical_rules),
+            ],
+        );
 
-        let parsed_json: Value = match serde_json::from_str(&json_str) {
-            Ok(val) => val,
-            Err(e) => {
-                context.log(
-                    "planner",
-                    &format!(
-                        "❌ Failed to parse raw JSON:\n{}\n\n[raw]: {}\n\n[cleaned]: {}",
-                        e, raw, json_str
-                    ),
-                );
+        // 🔧 Invalid plans go back to the model with the errors, up to
+        // `MAX_CORRECTIONS` times, before the planner gives up on them.
+        let mut conversation = Conversation::new().with_user(&prompt);
+        let mut corrections = 0;
+        let (parsed_json, blocking) = loop {
+            let Some(mut parsed_json) = self.request_plan_json(context, &mut conversation) else {
                 return Plan {
-                    steps: vec![PlanStep::Info("Failed to parse structured plan.".into())],
+                    steps: vec![PlanStep::Info("Planner LLM failed.".into())],
                 };
+            };
+
+            // 🎯 DYNAMIC INTELLIGENCE: Auto-fix common LLM format mistakes
+            if let Some(steps) = parsed_json.get_mut("plan") {
+                normalize_plan_steps(steps, &context.tools.names());
             }
-        };
+            let json_str = parsed_json.to_string();
 
-        let plan_steps_json = parsed_json
-            .get("plan")
-            .and_then(|v| v.as_array())
-            .cloned()
-            .unwrap_or_default();
+            context.log_in(PLANNER_NAMESPACE, "planner_output", &json_str);
+
+            let plan_steps_json = parsed_json
+                .get("plan")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default();
+
+            let mut validation_errors = validate_in_context(&plan_steps_json, context, &[]);
+
+            for error in validation_errors.iter() {
+                let (msg, maybe_hint) = error.hint();
+                context.log_in(
+                    PLANNER_NAMESPACE,
+                    "planner",
+                    &format!("⚠️ Validation {}: {}", error.severity(), msg),
+                );
+                if let Some(hint) = maybe_hint {
+                    context.log_in(PLANNER_NAMESPACE, "planner", &format!("→ Hint: {}", hint));
+                }
+            }
 
-        let registered_tools = ["run_command", "reflect", "analyze_error"];
-        let validation_errors = validate_plan(&plan_steps_json, &registered_tools);
+            // Only the first invalid plan is recorded: it shows what the
+            // model gets wrong unprompted.
+            if corrections == 0 && !validation_errors.is_empty() {
+                let errors = validation_errors
+                    .iter()
+                    .map(|error| format!("{:?}: {}", error, error.hint().0))
+                    .collect();
+                self.remember_failure(context, goal, &json_str, errors, "Plan failed validation");
+            }
 
-        for error in validation_errors.iter() {
-            let (msg, maybe_hint) = error.hint();
-            context.log("planner", &format!("⚠️ Validation warning: {}", msg));
-            if let Some(hint) = maybe_hint {
-                context.log("planner", &format!("→ Hint: {}", hint));
+            validation_errors.retain(PlanValidationError::is_error);
+            if validation_errors.is_empty() || corrections == MAX_CORRECTIONS {
+                break (parsed_json, !validation_errors.is_empty());
             }
+            corrections += 1;
+            context.log_in(
+                PLANNER_NAMESPACE,
+                "planner",
+                &format!(
+                    "🔧 Asking the planner to correct {} validation error(s) ({}/{})",
+                    validation_errors.len(),
+                    corrections,
+                    MAX_CORRECTIONS
+                ),
+            );
+            conversation.push(Role::Assistant, &json_str);
+            conversation.push(Role::User, &correction_prompt(&validation_errors));
+        };
+        if blocking {
+            context.log_in(
+                PLANNER_NAMESPACE,
+                "planner",
+                "❌ Plan is still invalid after corrections; not using it",
+            );
+            return Plan {
+                steps: vec![PlanStep::Info("Planner produced an invalid plan.".into())],
+            };
         }
+        let json_str = parsed_json.to_string();
 
-        let response = serde_json::from_str::<PlannerResponse>(&json_str);
+        let response = serde_json::from_value::<PlannerResponse>(parsed_json);
         match response {
             Ok(parsed) => Plan {
-                steps: parsed
-                    .plan
-                    .into_iter()
-                    .map(|step| match step {
-                        PlannerStep::Tool { name, input } => PlanStep::ToolCall {
-                            name,
-                            input: input.unwrap_or_default(),
-                        },
-                        PlannerStep::Info { message } => PlanStep::Info(message),
-                    })
-                    .collect(),
+                steps: parsed.plan.into_iter().map(PlanStep::from).collect(),
             },
             Err(e) => {
-                context.log(
+                context.log_in(
+                    PLANNER_NAMESPACE,
                     "planner",
                     &format!(
-                        "❌ Failed to parse into PlannerResponse:\n{}\n\n[raw]: {}\n\n[json]: {}",
-                        e, raw, json_str
+                        "❌ Failed to parse into PlannerResponse:\n{}\n\n[json]: {}",
+                        e, json_str
                     ),
                 );
                 Plan {
@@ -263,21 +456,74 @@ STOP after outputting the JSON. NO other format is acceptable.
     }
 }
 
+impl LLMPlanner {
+    /// The model's JSON reply to `conversation`. A reply that does not parse is
+    /// shown back to the model, up to `JSON_FIX_ATTEMPTS` times, with the
+    /// parse error and a request to correct it.
+    fn request_plan_json(
+        &self,
+        context: &Context,
+        conversation: &mut Conversation,
+    ) -> Option<Value> {
+        let schema = plan_response_schema();
+        for attempt in 0..=JSON_FIX_ATTEMPTS {
+            let reply = match self.llm.chat_structured(conversation, Some(&schema)) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    context.log_in(
+                        PLANNER_NAMESPACE,
+                        "planner",
+                        &format!("❌ Planner LLM failed: {}", e),
+                    );
+                    return None;
+                }
+            };
+            match json_repair::parse_json(&reply) {
+                Ok(value) => return Some(value),
+                Err(e) if attempt < JSON_FIX_ATTEMPTS => {
+                    context.log_in(
+                        PLANNER_NAMESPACE,
+                        "planner",
+                        &format!(
+                            "⚠️ Planner reply was not valid JSON ({}); asking for a fix",
+                            e
+                        ),
+                    );
+                    conversation.push(Role::Assistant, &reply);
+                    conversation.push(
+                        Role::User,
+                        &format!(
+                            "That reply was not valid JSON: {}\nReply again with ONLY the corrected JSON plan.",
+                            e
+                        ),
+                    );
+                }
+                Err(e) => {
+                    context.log_in(
+                        PLANNER_NAMESPACE,
+                        "planner",
+                        &format!("❌ Planner LLM failed: {}", e),
+                    );
+                    return None;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Planner for LLMPlanner {
+    fn generate_plan(&self, context: &mut Context, goal: &str) -> Plan {
+        self.plan_within(context, goal, "")
+    }
+
+    fn plan_task(&self, context: &mut Context, model: &dyn Model) -> Plan {
+        self.plan_within(context, model.goal(), &model.prompt_rules())
+    }
+}
+
 #[derive(Deserialize)]
 struct PlannerResponse {
     #[serde(default)]
     plan: Vec<PlannerStep>,
 }
-
-#[derive(Deserialize)]
-#[serde(tag = "type")]
-enum PlannerStep {
-    #[serde(rename = "tool")]
-    Tool {
-        name: String,
-        #[serde(default)]
-        input: Option<String>,
-    },
-    #[serde(rename = "info")]
-    Info { message: String },
-}
@@ -1,45 +1,125 @@
 use crate::context::Context;
-use crate::protocol::{Plan, PlanStep};
-use crate::tools::Tool;
+use crate::llm::ModelTier;
+use crate::memory::PLANNER_NAMESPACE;
+use crate::model::{Model, TaskModel};
+use crate::protocol::transform::OutputTransform;
+use crate::protocol::{
+    MAX_LOOP_ITERATIONS, Plan, PlanStep, Predicate, normalize_plan_steps, plan_response_schema,
+    timeout_secs, tool_step_from_json,
+};
 use crate::tools::goal_analyzer::GoalAnalyzerTool;
 use crate::tools::llm::LLMTool;
-use crate::validation::plan::validate_plan;
-use regex::Regex;
+use crate::validation::plan::{find_repeated_steps, validate_in_context};
 use serde::Deserialize;
 use serde_json::Value;
+use std::time::Duration;
 
 pub trait Replanner: Send + Sync {
     fn generate_followup_plan(&self, context: &mut Context, goal: &str, reflection: &str) -> Plan;
+
+    /// Follow-up plan for `model`, under its prompt rules and without redoing
+    /// its completed steps; the default only looks at the goal.
+    fn replan_task(&self, context: &mut Context, model: &dyn Model, reflection: &str) -> Plan {
+        self.generate_followup_plan(context, model.goal(), reflection)
+    }
 }
 
+/// Latest tool calls shown to the replanner.
+const HISTORY_LIMIT: usize = 20;
+
 pub struct LLMReplanner {
     llm: LLMTool,
     goal_analyzer: GoalAnalyzerTool,
+    memory_limit: Option<usize>,
+    preserve_completed: bool,
 }
 
 impl LLMReplanner {
     pub fn new(llm: LLMTool) -> Self {
+        let llm = llm.for_component("replanner").for_tier(ModelTier::Smart);
         let goal_analyzer = GoalAnalyzerTool::new(llm.clone());
-        Self { llm, goal_analyzer }
+        Self {
+            llm,
+            goal_analyzer,
+            memory_limit: None,
+            preserve_completed: true,
+        }
+    }
+
+    /// Include only the `k` memory entries most relevant to the goal and
+    /// reflection instead of the whole log.
+    pub fn with_memory_limit(mut self, k: usize) -> Self {
+        self.memory_limit = Some(k);
+        self
     }
 }
 
-impl Replanner for LLMReplanner {
-    fn generate_followup_plan(&self, context: &mut Context, goal: &str, reflection: &str) -> Plan {
-        let memory_dump = context
+impl LLMReplanner {
+    /// With `preserve` (the default), the replanner is shown the task's
+    /// completed steps, may reference their outputs, and state-changing steps
+    /// it repeats verbatim are dropped from the follow-up plan.
+    pub fn preserving_completed(mut self, preserve: bool) -> Self {
+        self.preserve_completed = preserve;
+        self
+    }
+
+    /// Completed steps that change state, as `(tool, input)`: repeating
+    /// those is what a follow-up plan must not do. Read-only steps (e.g.
+    /// `git status`) may be re-run to check the current state.
+    fn completed_changes(&self, context: &Context, task: &TaskModel) -> Vec<(String, String)> {
+        task.completed_steps()
+            .filter(|step| {
+                context
+                    .get_tool(&step.tool)
+                    .is_none_or(|tool| !tool.preview(&step.input).read_only)
+            })
+            .map(|step| (step.tool.clone(), step.input.clone()))
+            .collect()
+    }
+
+    fn replan_within(
+        &self,
+        context: &mut Context,
+        goal: &str,
+        reflection: &str,
+        model: Option<&dyn Model>,
+    ) -> Plan {
+        let task_rules = model.map(|model| model.prompt_rules()).unwrap_or_default();
+        let task = model
+            .map(|model| model.task())
+            .filter(|_| self.preserve_completed);
+        let completed_section = task.map(TaskModel::completed_section).unwrap_or_default();
+        let prior_refs: Vec<String> = task
+            .map(|task| {
+                task.completed_outputs()
+                    .into_iter()
+                    .map(|(key, _)| key)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let completed_changes = task
+            .map(|task| self.completed_changes(context, task))
+            .unwrap_or_default();
+
+        let memory_entries = context
             .memory()
-            .entries
+            .prompt_entries(&format!("{} {}", goal, reflection), self.memory_limit);
+        let memory_dump = memory_entries
             .iter()
             .map(|(label, content)| format!("[{}] {}", label, content))
             .collect::<Vec<_>>()
             .join("\n");
 
         // 🎯 DYNAMIC INTELLIGENCE: Use GoalAnalyzerTool for context-aware recovery planning
-        context.log("replanner", "Using dynamic LLM replanner");
+        context.log_in(
+            PLANNER_NAMESPACE,
+            "replanner",
+            "Using dynamic LLM replanner",
+        );
 
-        let (examples_text, output_format, critical_rules) = match self
+        let (examples_text, output_format, mut critical_rules) = match self
             .goal_analyzer
-            .analyze_context(goal, &memory_dump, true)
+            .analyze_context(goal, &memory_dump, true, &context.tools.names())
         {
             Ok(analysis) => {
                 let examples = analysis
@@ -56,7 +136,8 @@ impl Replanner for LLMReplanner {
                 )
             }
             Err(e) => {
-                context.log(
+                context.log_in(
+                    PLANNER_NAMESPACE,
                     "replanner",
                     &format!(
                         "⚠️ GoalAnalyzer failed: {}, falling back to hardcoded examples",
@@ -72,204 +153,94 @@ impl Replanner for LLMReplanner {
             }
         };
 
-        let prompt = format!(
-            r#"You are an autonomous replanning agent. Analyze what went wrong and create a plan to complete the goal.
-
-GOAL: {}
-
-REFLECTION FROM PREVIOUS ATTEMPT:
-{}
-
-MEMORY LOG:
-{}
-
-DYNAMIC RECOVERY EXAMPLES FOR THIS CONTEXT:
-{}
-
-OUTPUT FORMAT: {}
-
-CRITICAL RULES:
-{}
-
-🚨 CRITICAL FORMAT REQUIREMENTS 🚨
-NEVER EVER use these INVALID formats:
-❌ {{"type": "reflect"}} 
-❌ {{"type": "run_command"}}
-❌ {{"type": "analyze_error"}}
-
-ALWAYS use these VALID formats:
-✅ {{"type": "tool", "name": "reflect"}}
-✅ {{"type": "tool", "name": "run_command"}}  
-✅ {{"type": "tool", "name": "analyze_error"}}
-✅ {{"type": "info", "message": "text"}}
-
-UNIVERSAL RULES:
-- If the reflection contains JSON with "fix_commands" array, use those EXACT commands first
-- Then ALWAYS retry the original failed operation to complete the goal
-- If reflection is plain text, analyze what failed and create appropriate recovery steps
-- Complete the ENTIRE goal, not just fix the immediate problem
-- For git commit failures: run fix commands, then ALWAYS retry git commit with proper message
-- NEVER stop after just running the fix - ALWAYS complete the original goal
-- Only "tool" and "info" are valid types
-- Tool names: ONLY "run_command", "reflect", or "analyze_error"
-- Plan ALL steps needed to complete the goal
-- NO conditional logic (if/else) in JSON - create complete linear plan
-- NO pseudo-code - ONLY valid JSON
-- If goal is achieved, use: {{"type": "info", "message": "Goal achieved"}}
-- NO markdown, NO explanations after JSON
-
-⚠️ CRITICAL: Do NOT include any reflection analysis JSON in your response. Only output the plan JSON.
-
-🚨 CRITICAL EXAMPLE: If reflection contains error analysis JSON like:
-{{"analysis": "Command failed due to formatting", "fix_commands": ["cargo fmt"], "explanation": "..."}}
-
-Then output (MUST include BOTH fix AND retry):
-{{
-  "plan": [
-    {{"type": "tool", "name": "run_command", "input": "cargo fmt"}},
-    {{"type": "tool", "name": "run_command", "input": "git commit -m 'Fix formatting and commit changes'"}},
-    {{"type": "info", "message": "Goal completed"}}
-  ]
-}}
-
-🚨 NEVER stop after just the fix command - ALWAYS retry the original operation!
-
-OUTPUT ONLY this exact JSON structure (ignore any other formats in examples):
-{{
-  "plan": [
-    {{"type": "tool", "name": "run_command", "input": "your_command_here"}},
-    {{"type": "tool", "name": "reflect", "input": "$output[run_command]"}},
-    {{"type": "info", "message": "Goal completed"}}
-  ]
-}}
-
-STOP after outputting the JSON. NO other format is acceptable.
-"#,
-            goal, reflection, memory_dump, examples_text, output_format, critical_rules
-        );
-
-        let result = self.llm.execute(&prompt);
-        let raw = result.output.unwrap_or_default();
-
-        context.log("replanner", "--- DEBUG: Raw replanner output ---");
-        context.log("replanner", &raw);
-
-        // Extract everything after </think> tag if present, otherwise use full response
-        let post_think = if raw.contains("</think>") {
-            raw.split("</think>").last().unwrap_or(&raw)
-        } else {
-            &raw
-        };
-
-        let cleaned = post_think
-            .lines()
-            .filter(|line| {
-                !line.trim_start().starts_with("```")
-                    && !line.trim_start().starts_with("---")
-                    && !line.trim_start().starts_with("### ")
-                    && !line.trim().is_empty()
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        // More robust JSON extraction - find the complete JSON object
-        let mut json = Regex::new(r#"(?s)\{\s*\"plan\"\s*:\s*\[.*?\]\s*\}"#)
-            .unwrap()
-            .find(&cleaned)
-            .map(|m| m.as_str().to_string())
-            .unwrap_or_default();
-
-        // 🎯 DYNAMIC INTELLIGENCE: Auto-fix common LLM format mistakes
-        // Convert {"type": "tool_name"} to {"type": "tool", "name": "tool_name"}
-        json = json
-            .replace(
-                r#""type": "run_command""#,
-                r#""type": "tool", "name": "run_command""#,
-            )
-            .replace(
-                r#""type": "reflect""#,
-                r#""type": "tool", "name": "reflect""#,
-            )
-            .replace(
-                r#""type": "analyze_error""#,
-                r#""type": "tool", "name": "analyze_error""#,
-            );
-
-        // Remove JSON comments (// comments)
-        let comment_regex = Regex::new(r#",?\s*//[^\n\r]*"#).unwrap();
-        json = comment_regex.replace_all(&json, "").to_string();
-
-        // Remove invalid step types (condition, etc.) - replace with info
-        let invalid_types = ["condition", "check", "validate", "if", "when"];
-        for invalid_type in invalid_types {
-            let pattern = format!(r#""type": "{}""#, invalid_type);
-            json = json.replace(&pattern, r#""type": "info""#);
+        if !task_rules.is_empty() {
+            critical_rules.push('\n');
+            critical_rules.push_str(&task_rules);
         }
-
-        context.log("replanner", "--- DEBUG: Extracted JSON block ---");
-        context.log("replanner", &json);
-
-        if !result.success {
-            context.log("replanner", &format!("❌ Replanner LLM failed: {}", raw));
-            return Plan {
-                steps: vec![PlanStep::Info("Replanner LLM failed.".into())],
-            };
+        if !completed_section.is_empty() {
+            critical_rules.push('\n');
+            critical_rules.push_str(&completed_section);
         }
 
-        let parsed_json: Value = match serde_json::from_str(&json) {
-            Ok(val) => val,
+        let tools_section = context.tools.render_prompt_section();
+        let history_section = context.tool_history.prompt_section(HISTORY_LIMIT);
+
+        let prompt = context.render_prompt(
+            "replanner",
+            &self.llm.model,
+            &[
+                ("goal", goal),
+                ("reflection", reflection),
+                ("memory", &memory_dump),
+                ("history", &history_section),
+                ("tools", &tools_section),
+                ("examples", &examples_text),
+                ("output_format", &output_format),
+                ("critical_rules", &critical_rules),
+            ],
+        );
+
+        let mut parsed_json: Value = match self
+            .llm
+            .generate_structured_with_schema(&prompt, &plan_response_schema())
+        {
+            Ok(value) => value,
             Err(e) => {
-                context.log(
+                context.log_in(
+                    PLANNER_NAMESPACE,
                     "replanner",
-                    &format!(
-                        "❌ Failed to parse raw JSON:\n{}\n\n[raw]: {}\n\n[cleaned]: {}",
-                        e, raw, json
-                    ),
+                    &format!("❌ Replanner LLM failed: {}", e),
                 );
                 return Plan {
-                    steps: vec![PlanStep::Info("Failed to parse replanned output.".into())],
+                    steps: vec![PlanStep::Info("Replanner LLM failed.".into())],
                 };
             }
         };
 
+        // 🎯 DYNAMIC INTELLIGENCE: Auto-fix common LLM format mistakes
+        if let Some(steps) = parsed_json.get_mut("plan") {
+            normalize_plan_steps(steps, &context.tools.names());
+        }
+        let json = parsed_json.to_string();
+
+        context.log_in(PLANNER_NAMESPACE, "replanner_output", &json);
+
         let plan_steps_json = parsed_json
             .get("plan")
             .and_then(|v| v.as_array())
             .cloned()
             .unwrap_or_default();
 
-        let registered_tools = ["run_command", "reflect", "analyze_error"];
-        let validation_errors = validate_plan(&plan_steps_json, &registered_tools);
+        let mut validation_errors = validate_in_context(&plan_steps_json, context, &prior_refs);
+        validation_errors.extend(find_repeated_steps(&plan_steps_json, &completed_changes));
 
         for error in validation_errors.iter() {
             let (msg, maybe_hint) = error.hint();
-            context.log("replanner", &format!("⚠️ Validation warning: {}", msg));
+            context.log_in(
+                PLANNER_NAMESPACE,
+                "replanner",
+                &format!("⚠️ Validation {}: {}", error.severity(), msg),
+            );
             if let Some(hint) = maybe_hint {
-                context.log("replanner", &format!("→ Hint: {}", hint));
+                context.log_in(PLANNER_NAMESPACE, "replanner", &format!("→ Hint: {}", hint));
             }
         }
 
-        let response = serde_json::from_str::<ReplannerResponse>(&json);
+        let response = serde_json::from_value::<ReplannerResponse>(parsed_json);
         match response {
-            Ok(parsed) => Plan {
-                steps: parsed
-                    .plan
-                    .into_iter()
-                    .map(|step| match step {
-                        ReplannerStep::Tool { name, input } => PlanStep::ToolCall {
-                            name,
-                            input: input.unwrap_or_default(),
-                        },
-                        ReplannerStep::Info { message } => PlanStep::Info(message),
-                    })
-                    .collect(),
-            },
+            Ok(parsed) => {
+                let steps = parsed.plan.into_iter().map(PlanStep::from).collect();
+                Plan {
+                    steps: drop_repeated_steps(context, steps, &completed_changes),
+                }
+            }
             Err(e) => {
-                context.log(
+                context.log_in(
+                    PLANNER_NAMESPACE,
                     "replanner",
                     &format!(
-                        "❌ Failed to parse into ReplannerResponse:\n{}\n\n[raw]: {}\n\n[json]: {}",
-                        e, raw, json
+                        "❌ Failed to parse into ReplannerResponse:\n{}\n\n[json]: {}",
+                        e, json
                     ),
                 );
                 Plan {
@@ -280,6 +251,67 @@ STOP after outputting the JSON. NO other format is acceptable.
     }
 }
 
+impl Replanner for LLMReplanner {
+    fn generate_followup_plan(&self, context: &mut Context, goal: &str, reflection: &str) -> Plan {
+        self.replan_within(context, goal, reflection, None)
+    }
+
+    fn replan_task(&self, context: &mut Context, model: &dyn Model, reflection: &str) -> Plan {
+        self.replan_within(context, model.goal(), reflection, Some(model))
+    }
+}
+
+/// Removes tool steps (at any depth) that repeat a completed `(tool, input)`.
+fn drop_repeated_steps(
+    context: &Context,
+    steps: Vec<PlanStep>,
+    completed: &[(String, String)],
+) -> Vec<PlanStep> {
+    if completed.is_empty() {
+        return steps;
+    }
+    let drop = |steps: Vec<PlanStep>| drop_repeated_steps(context, steps, completed);
+    steps
+        .into_iter()
+        .filter_map(|step| match step {
+            PlanStep::ToolCall {
+                ref name,
+                ref input,
+                ..
+            } if completed
+                .iter()
+                .any(|(tool, done)| tool == name && done == input) =>
+            {
+                context.log_in(
+                    PLANNER_NAMESPACE,
+                    "replanner",
+                    &format!("⏭️ Dropped repeated step: {} `{}`", name, input),
+                );
+                None
+            }
+            PlanStep::Conditional {
+                predicate,
+                then_steps,
+                else_steps,
+            } => Some(PlanStep::Conditional {
+                predicate,
+                then_steps: drop(then_steps),
+                else_steps: drop(else_steps),
+            }),
+            PlanStep::Loop {
+                steps,
+                max_iterations,
+                until,
+            } => Some(PlanStep::Loop {
+                steps: drop(steps),
+                max_iterations,
+                until,
+            }),
+            step => Some(step),
+        })
+        .collect()
+}
+
 #[derive(Deserialize)]
 struct ReplannerResponse {
     #[serde(default)]
@@ -291,10 +323,73 @@ struct ReplannerResponse {
 enum ReplannerStep {
     #[serde(rename = "tool")]
     Tool {
+        #[serde(default)]
+        id: Option<String>,
         name: String,
         #[serde(default)]
-        input: Option<String>,
+        input: Option<Value>,
+        #[serde(default)]
+        args: Option<Value>,
+        #[serde(default, deserialize_with = "timeout_secs")]
+        timeout_secs: Option<Duration>,
+        #[serde(default)]
+        transform: Vec<OutputTransform>,
+        #[serde(default)]
+        background: bool,
     },
     #[serde(rename = "info")]
     Info { message: String },
+    #[serde(rename = "condition")]
+    Condition {
+        predicate: Predicate,
+        #[serde(default, rename = "then")]
+        then_steps: Vec<ReplannerStep>,
+        #[serde(default, rename = "else")]
+        else_steps: Vec<ReplannerStep>,
+    },
+    #[serde(rename = "loop")]
+    Loop {
+        steps: Vec<ReplannerStep>,
+        max_iterations: usize,
+        #[serde(default)]
+        until: Option<Predicate>,
+    },
+    #[serde(rename = "ask")]
+    Ask { question: String, variable: String },
+}
+
+impl From<ReplannerStep> for PlanStep {
+    fn from(step: ReplannerStep) -> Self {
+        match step {
+            ReplannerStep::Tool {
+                id,
+                name,
+                input,
+                args,
+                timeout_secs,
+                transform,
+                background,
+            } => tool_step_from_json(id, name, input, args, timeout_secs, transform, background),
+            ReplannerStep::Info { message } => PlanStep::Info(message),
+            ReplannerStep::Condition {
+                predicate,
+                then_steps,
+                else_steps,
+            } => PlanStep::Conditional {
+                predicate,
+                then_steps: then_steps.into_iter().map(PlanStep::from).collect(),
+                else_steps: else_steps.into_iter().map(PlanStep::from).collect(),
+            },
+            ReplannerStep::Loop {
+                steps,
+                max_iterations,
+                until,
+            } => PlanStep::Loop {
+                steps: steps.into_iter().map(PlanStep::from).collect(),
+                max_iterations: max_iterations.min(MAX_LOOP_ITERATIONS),
+                until,
+            },
+            ReplannerStep::Ask { question, variable } => PlanStep::AskUser { question, variable },
+        }
+    }
 }
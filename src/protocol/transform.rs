@@ -0,0 +1,105 @@
+// src/protocol/transform.rs
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// A post-processor a tool step declares on its output (`"transform":
+/// [{"tail": 40}, "strip_ansi"]`). Transforms run in order on a successful
+/// call's output before later steps, reports and prompts see it; the tool
+/// history keeps the original.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputTransform {
+    /// Keeps the first `n` lines.
+    Head(usize),
+    /// Keeps the last `n` lines, e.g. where a build log reports its errors.
+    Tail(usize),
+    /// Removes terminal colour and cursor escape codes.
+    StripAnsi,
+    /// Keeps each match of the pattern, one per line: its first capture group
+    /// if it has one, else the whole match. No match is an error.
+    Regex(String),
+    /// Parses the output as JSON and keeps the field at a dotted path such as
+    /// `items.0.metadata.name`; strings are kept without quotes.
+    Json(String),
+}
+
+/// Matches ANSI CSI sequences (colours, cursor movement) and OSC sequences
+/// (titles, hyperlinks).
+fn ansi_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)").unwrap()
+    })
+}
+
+impl OutputTransform {
+    pub fn apply(&self, text: &str) -> Result<String, String> {
+        match self {
+            OutputTransform::Head(n) => Ok(text.lines().take(*n).collect::<Vec<_>>().join("\n")),
+            OutputTransform::Tail(n) => {
+                let lines: Vec<&str> = text.lines().collect();
+                Ok(lines[lines.len().saturating_sub(*n)..].join("\n"))
+            }
+            OutputTransform::StripAnsi => Ok(ansi_regex().replace_all(text, "").into_owned()),
+            OutputTransform::Regex(pattern) => {
+                let regex = Regex::new(pattern)
+                    .map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+                let matches: Vec<&str> = regex
+                    .captures_iter(text)
+                    .filter_map(|caps| caps.get(1).or(caps.get(0)))
+                    .map(|m| m.as_str())
+                    .collect();
+                if matches.is_empty() {
+                    return Err(format!("Output did not match /{}/", pattern));
+                }
+                Ok(matches.join("\n"))
+            }
+            OutputTransform::Json(path) => {
+                let value: Value = serde_json::from_str(text.trim())
+                    .map_err(|e| format!("Output is not JSON: {}", e))?;
+                json_field(&value, path)
+                    .map(field_text)
+                    .ok_or_else(|| format!("Output has no JSON field '{}'", path))
+            }
+        }
+    }
+
+    /// Checks what can be checked before the step runs: regex patterns.
+    pub fn check(&self) -> Result<(), String> {
+        if let OutputTransform::Regex(pattern) = self {
+            Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// The field of `value` at a dotted path such as `items.0.metadata.name`.
+pub fn json_field<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut field = value;
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        field = match field {
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            Value::Object(map) => map.get(key),
+            _ => None,
+        }?;
+    }
+    Some(field)
+}
+
+/// `value` as text: strings without quotes, anything else as JSON.
+pub fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Runs `transforms` in order over `text`.
+pub fn apply_all(transforms: &[OutputTransform], text: &str) -> Result<String, String> {
+    transforms
+        .iter()
+        .try_fold(text.to_string(), |text, transform| transform.apply(&text))
+}
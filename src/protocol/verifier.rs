@@ -0,0 +1,38 @@
+// src/protocol/verifier.rs
+
+use crate::protocol::ExecutionResult;
+use serde::{Deserialize, Serialize};
+
+/// Checks whether an executed plan actually achieved its goal, rather than
+/// merely running without critical tool failures.
+pub trait Verifier: Send + Sync {
+    fn verify(&self, goal: &str, result: &ExecutionResult) -> Result<Verdict, String>;
+}
+
+/// Structured outcome of a `Verifier` check.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Verdict {
+    pub achieved: bool,
+    /// 0.0–1.0
+    #[serde(default)]
+    pub confidence: f32,
+    #[serde(default)]
+    pub reasoning: String,
+    /// Parts of the goal that are still outstanding; fed to the replanner.
+    #[serde(default)]
+    pub missing: Vec<String>,
+}
+
+impl Verdict {
+    /// Replanning input describing what is left to do.
+    pub fn followup_prompt(&self) -> String {
+        let mut prompt = format!("Goal not yet achieved: {}", self.reasoning);
+        if !self.missing.is_empty() {
+            prompt.push_str("\nStill missing:\n");
+            for item in &self.missing {
+                prompt.push_str(&format!("- {}\n", item));
+            }
+        }
+        prompt
+    }
+}
@@ -0,0 +1,719 @@
+// src/protocol/yaml.rs
+
+use serde_json::{Map, Number, Value};
+
+/// Parses the YAML subset used by plan files into a JSON value, so plans can
+/// be deserialized with serde.
+///
+/// Supports block mappings and sequences (including compact `- key: value`
+/// items), plain, single- and double-quoted scalars, `|` / `>` block scalars,
+/// flow `[...]` / `{...}` collections and `#` comments. Anchors, tags,
+/// multi-line plain scalars and multiple documents are not supported.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        lines: text
+            .lines()
+            .enumerate()
+            .map(|(index, raw)| {
+                let indent = raw.len() - raw.trim_start_matches(' ').len();
+                Line {
+                    number: index + 1,
+                    indent,
+                    text: raw[indent..].trim_end().to_string(),
+                }
+            })
+            .collect(),
+        pos: 0,
+    };
+    parser.document()
+}
+
+/// Renders a value as block-style YAML that `parse` reads back unchanged.
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    match value {
+        Value::Object(map) if !map.is_empty() => write_mapping(map, 0, &mut out),
+        Value::Array(items) if !items.is_empty() => write_sequence(items, 0, &mut out),
+        _ => {
+            out.push_str(&scalar(value));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    /// The line without its indentation and trailing whitespace.
+    text: String,
+}
+
+impl Line {
+    fn is_content(&self) -> bool {
+        !self.text.is_empty() && !self.text.starts_with('#')
+    }
+
+    fn is_sequence_item(&self) -> bool {
+        self.text == "-" || self.text.starts_with("- ")
+    }
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    fn document(&mut self) -> Result<Value, String> {
+        if let Some(line) = self.peek()
+            && line.indent == 0
+            && line.text == "---"
+        {
+            self.pos += 1;
+        }
+        let Some(indent) = self.peek().map(|line| line.indent) else {
+            return Ok(Value::Null);
+        };
+        let value = self.block(indent)?;
+        match self.peek() {
+            Some(line) if line.text == "..." => Ok(value),
+            Some(line) => Err(error(line.number, "unexpected content after the document")),
+            None => Ok(value),
+        }
+    }
+
+    /// The next line with content, skipping blank and comment lines.
+    fn peek(&mut self) -> Option<&Line> {
+        while self.pos < self.lines.len() && !self.lines[self.pos].is_content() {
+            self.pos += 1;
+        }
+        self.lines.get(self.pos)
+    }
+
+    fn current(&self) -> &Line {
+        &self.lines[self.pos]
+    }
+
+    /// The node starting at the current line, which is indented by `indent`.
+    fn block(&mut self, indent: usize) -> Result<Value, String> {
+        let line = self.current();
+        if line.text.starts_with('\t') {
+            return Err(error(line.number, "tabs are not allowed in indentation"));
+        }
+        if line.is_sequence_item() {
+            self.sequence(indent)
+        } else if mapping_entry(&line.text, line.number)?.is_some() {
+            self.mapping(indent)
+        } else {
+            let (number, text) = (line.number, line.text.clone());
+            self.pos += 1;
+            self.value_text(&text, number, indent)
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, String> {
+        let mut items = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent != indent || !line.is_sequence_item() {
+                break;
+            }
+            let number = line.number;
+            let rest = line.text[1..].trim_start().to_string();
+            let column = indent + (line.text.len() - rest.len());
+
+            let item = if rest.is_empty() || rest.starts_with('#') {
+                self.pos += 1;
+                self.nested(indent)?
+            } else if rest == "-"
+                || rest.starts_with("- ")
+                || mapping_entry(&rest, number)?.is_some()
+            {
+                // A compact collection: parse the rest of the line as if it
+                // started its own line at the item's column.
+                self.lines[self.pos].indent = column;
+                self.lines[self.pos].text = rest;
+                self.block(column)?
+            } else {
+                self.pos += 1;
+                self.value_text(&rest, number, indent)?
+            };
+            items.push(item);
+        }
+        self.check_dedent(indent)?;
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, String> {
+        let mut map = Map::new();
+        while let Some(line) = self.peek() {
+            if line.indent != indent || line.is_sequence_item() {
+                break;
+            }
+            let number = line.number;
+            let Some((key, rest)) = mapping_entry(&line.text, number)? else {
+                return Err(error(number, "expected 'key: value'"));
+            };
+            self.pos += 1;
+
+            let value = if rest.is_empty() || rest.starts_with('#') {
+                // Sequences may sit at the key's own indentation.
+                match self.peek() {
+                    Some(next) if next.indent == indent && next.is_sequence_item() => {
+                        self.sequence(indent)?
+                    }
+                    _ => self.nested(indent)?,
+                }
+            } else {
+                self.value_text(&rest, number, indent)?
+            };
+            if map.insert(key.clone(), value).is_some() {
+                return Err(error(number, &format!("duplicate key '{}'", key)));
+            }
+        }
+        self.check_dedent(indent)?;
+        Ok(Value::Object(map))
+    }
+
+    /// A block indented deeper than `parent`, or null when there is none.
+    fn nested(&mut self, parent: usize) -> Result<Value, String> {
+        match self.peek() {
+            Some(line) if line.indent > parent => {
+                let indent = line.indent;
+                self.block(indent)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    /// Content deeper than a finished collection belongs to nothing.
+    fn check_dedent(&mut self, indent: usize) -> Result<(), String> {
+        match self.peek() {
+            Some(line) if line.indent > indent => Err(error(line.number, "unexpected indentation")),
+            _ => Ok(()),
+        }
+    }
+
+    /// A value written on the same line as its key or `-`; block scalars
+    /// continue on the following lines.
+    fn value_text(&mut self, text: &str, number: usize, parent: usize) -> Result<Value, String> {
+        if text.starts_with('|') || text.starts_with('>') {
+            return self.block_scalar(text, number, parent).map(Value::String);
+        }
+        let mut inline = Inline {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: number,
+        };
+        let value = inline.value(false)?;
+        inline.skip_spaces();
+        match inline.peek() {
+            None | Some('#') => Ok(value),
+            Some(c) => Err(error(number, &format!("unexpected '{}' after value", c))),
+        }
+    }
+
+    fn block_scalar(
+        &mut self,
+        header: &str,
+        number: usize,
+        parent: usize,
+    ) -> Result<String, String> {
+        let header = header.split(" #").next().unwrap_or_default().trim_end();
+        let mut chars = header.chars();
+        let folded = chars.next() == Some('>');
+        let mut chomp = ' ';
+        let mut explicit_indent = None;
+        for c in chars {
+            match c {
+                '-' | '+' if chomp == ' ' => chomp = c,
+                '1'..='9' if explicit_indent.is_none() => {
+                    explicit_indent = c.to_digit(10).map(|d| parent + d as usize);
+                }
+                _ => return Err(error(number, "invalid block scalar header")),
+            }
+        }
+
+        let mut block_indent = explicit_indent;
+        let mut lines: Vec<String> = Vec::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.text.is_empty() {
+                lines.push(String::new());
+                self.pos += 1;
+                continue;
+            }
+            let indent = *block_indent.get_or_insert(line.indent);
+            if line.indent < indent || indent <= parent {
+                break;
+            }
+            lines.push(format!("{}{}", " ".repeat(line.indent - indent), line.text));
+            self.pos += 1;
+        }
+
+        let trailing = lines
+            .iter()
+            .rev()
+            .take_while(|line| line.is_empty())
+            .count();
+        lines.truncate(lines.len() - trailing);
+        let mut body = if folded {
+            fold(&lines)
+        } else {
+            lines.join("\n")
+        };
+        match chomp {
+            '-' => {}
+            '+' => body.push_str(&"\n".repeat(trailing + 1)),
+            _ if !body.is_empty() => body.push('\n'),
+            _ => {}
+        }
+        Ok(body)
+    }
+}
+
+/// Joins the lines of a `>` scalar: runs of lines become one line, blank
+/// lines become newlines and more-indented lines are kept as they are.
+fn fold(lines: &[String]) -> String {
+    let mut out = String::new();
+    let mut previous: Option<&str> = None;
+    for line in lines {
+        match previous {
+            None => {}
+            Some(prev)
+                if !prev.is_empty()
+                    && !line.is_empty()
+                    && !prev.starts_with(' ')
+                    && !line.starts_with(' ') =>
+            {
+                out.push(' ')
+            }
+            Some(prev) if prev.is_empty() && !line.is_empty() => {}
+            Some(_) => out.push('\n'),
+        }
+        out.push_str(line);
+        previous = Some(line);
+    }
+    out
+}
+
+/// Splits `key: value` (value possibly empty), or `None` if the line is not
+/// a mapping entry.
+fn mapping_entry(text: &str, number: usize) -> Result<Option<(String, String)>, String> {
+    if text.starts_with('"') || text.starts_with('\'') {
+        let mut inline = Inline {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: number,
+        };
+        let key = inline.quoted()?;
+        inline.skip_spaces();
+        if inline.peek() != Some(':') {
+            return Ok(None);
+        }
+        inline.pos += 1;
+        if !matches!(inline.peek(), None | Some(' ')) {
+            return Ok(None);
+        }
+        let rest: String = inline.chars[inline.pos..].iter().collect();
+        return Ok(Some((key, rest.trim().to_string())));
+    }
+    if text.starts_with(['[', '{', '|', '>', '#']) || text.starts_with("- ") {
+        return Ok(None);
+    }
+
+    let mut previous = ' ';
+    for (index, c) in text.char_indices() {
+        if c == '#' && previous == ' ' {
+            return Ok(None);
+        }
+        if c == ':' && matches!(text[index + 1..].chars().next(), None | Some(' ')) {
+            let key = text[..index].trim_end();
+            if key.is_empty() {
+                return Ok(None);
+            }
+            return Ok(Some((
+                key.to_string(),
+                text[index + 1..].trim().to_string(),
+            )));
+        }
+        previous = c;
+    }
+    Ok(None)
+}
+
+/// Scalars and flow collections within a single line.
+struct Inline {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Inline {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self, in_flow: bool) -> Result<Value, String> {
+        match self.peek() {
+            Some('"' | '\'') => self.quoted().map(Value::String),
+            Some('[') => self.flow_sequence(),
+            Some('{') => self.flow_mapping(),
+            Some('&' | '*' | '!') => Err(error(
+                self.line,
+                "anchors, aliases and tags are not supported",
+            )),
+            _ => Ok(resolve_plain(&self.plain(in_flow, false))),
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let quote = self.bump();
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                Some('\'') if quote == Some('\'') => {
+                    if self.peek() == Some('\'') {
+                        self.pos += 1;
+                        text.push('\'');
+                    } else {
+                        return Ok(text);
+                    }
+                }
+                Some('"') if quote == Some('"') => return Ok(text),
+                Some('\\') if quote == Some('"') => {
+                    let escaped = match self.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('"') => '"',
+                        Some('/') => '/',
+                        Some('\\') => '\\',
+                        Some('u') => {
+                            let hex: String = (0..4).filter_map(|_| self.bump()).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| error(self.line, "invalid \\u escape"))?
+                        }
+                        _ => return Err(error(self.line, "invalid escape sequence")),
+                    };
+                    text.push(escaped);
+                }
+                None => return Err(error(self.line, "unterminated string")),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    /// A plain scalar, ending at a comment, at the end of the line or, in
+    /// flow collections, at `,` `]` `}` (and `:` for keys).
+    fn plain(&mut self, in_flow: bool, is_key: bool) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let at_comment = c == '#' && self.pos > start && self.chars[self.pos - 1] == ' ';
+            let at_flow_end = in_flow && matches!(c, ',' | ']' | '}');
+            let at_key_end = is_key
+                && c == ':'
+                && matches!(self.chars.get(self.pos + 1), None | Some(' ' | ',' | '}'));
+            if at_comment || at_flow_end || at_key_end {
+                break;
+            }
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.trim_end().to_string()
+    }
+
+    fn flow_sequence(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value(true)?);
+            self.skip_spaces();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(error(self.line, "expected ',' or ']' in flow sequence")),
+            }
+        }
+    }
+
+    fn flow_mapping(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Value::Object(map));
+            }
+            let key = match self.peek() {
+                Some('"' | '\'') => self.quoted()?,
+                _ => self.plain(true, true),
+            };
+            self.skip_spaces();
+            let value = if self.peek() == Some(':') {
+                self.pos += 1;
+                self.skip_spaces();
+                match self.peek() {
+                    Some(',' | '}') => Value::Null,
+                    _ => self.value(true)?,
+                }
+            } else {
+                Value::Null
+            };
+            map.insert(key, value);
+            self.skip_spaces();
+            match self.bump() {
+                Some(',') => {}
+                Some('}') => return Ok(Value::Object(map)),
+                _ => return Err(error(self.line, "expected ',' or '}' in flow mapping")),
+            }
+        }
+    }
+}
+
+/// Types a plain scalar the way YAML 1.2's core schema does.
+fn resolve_plain(text: &str) -> Value {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    let numeric = text
+        .trim_start_matches(['-', '+'])
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_digit() || c == '.');
+    if numeric {
+        if let Ok(int) = text.parse::<i64>() {
+            return Value::Number(int.into());
+        }
+        if let Some(number) = text.parse::<f64>().ok().and_then(Number::from_f64) {
+            return Value::Number(number);
+        }
+    }
+    Value::String(text.to_string())
+}
+
+fn error(line: usize, message: &str) -> String {
+    format!("YAML line {}: {}", line, message)
+}
+
+fn write_mapping(map: &Map<String, Value>, indent: usize, out: &mut String) {
+    for (key, value) in map {
+        out.push_str(&" ".repeat(indent));
+        out.push_str(&scalar(&Value::String(key.clone())));
+        out.push(':');
+        write_nested(value, indent, out);
+    }
+}
+
+fn write_sequence(items: &[Value], indent: usize, out: &mut String) {
+    for item in items {
+        out.push_str(&" ".repeat(indent));
+        out.push('-');
+        match item {
+            // Compact form: the first entry shares the `- ` line.
+            Value::Object(map) if !map.is_empty() => {
+                let mut nested = String::new();
+                write_mapping(map, indent + 2, &mut nested);
+                out.push(' ');
+                out.push_str(&nested[indent + 2..]);
+            }
+            Value::Array(items) if !items.is_empty() => {
+                let mut nested = String::new();
+                write_sequence(items, indent + 2, &mut nested);
+                out.push(' ');
+                out.push_str(&nested[indent + 2..]);
+            }
+            _ => write_inline(item, indent, out),
+        }
+    }
+}
+
+/// Writes what follows `key:` or `-`, including the line break.
+fn write_nested(value: &Value, indent: usize, out: &mut String) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push('\n');
+            write_mapping(map, indent + 2, out);
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push('\n');
+            write_sequence(items, indent + 2, out);
+        }
+        _ => write_inline(value, indent, out),
+    }
+}
+
+fn write_inline(value: &Value, indent: usize, out: &mut String) {
+    match value {
+        Value::String(text) if is_block_text(text) => {
+            out.push(' ');
+            write_block_text(text, indent + 2, out);
+        }
+        _ => {
+            out.push(' ');
+            out.push_str(&scalar(value));
+            out.push('\n');
+        }
+    }
+}
+
+/// Multi-line text that reads back exactly as a `|` block scalar.
+fn is_block_text(text: &str) -> bool {
+    text.contains('\n')
+        && !text.starts_with([' ', '\n'])
+        && !text.ends_with("\n\n")
+        && !text.split('\n').any(|line| line.ends_with([' ', '\t']))
+        && !text
+            .chars()
+            .any(|c| c.is_control() && c != '\n' && c != '\t')
+}
+
+fn write_block_text(text: &str, indent: usize, out: &mut String) {
+    out.push_str(if text.ends_with('\n') { "|\n" } else { "|-\n" });
+    for line in text.trim_end_matches('\n').split('\n') {
+        if !line.is_empty() {
+            out.push_str(&" ".repeat(indent));
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+}
+
+/// A single-line scalar, quoted whenever a plain scalar would read back
+/// differently.
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(text) if is_plain_safe(text) => text.clone(),
+        Value::Object(_) => "{}".into(),
+        Value::Array(_) => "[]".into(),
+        // JSON strings, numbers, booleans and null are valid YAML.
+        _ => value.to_string(),
+    }
+}
+
+fn is_plain_safe(text: &str) -> bool {
+    let Some(first) = text.chars().next() else {
+        return false;
+    };
+    text.trim() == text
+        && !"-?:,[]{}#&*!|>'\"%@`".contains(first)
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.ends_with(':')
+        && !text.chars().any(|c| c.is_control())
+        && resolve_plain(text) == Value::String(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Plan, PlanStep};
+    use serde_json::json;
+
+    #[test]
+    fn parses_mappings_sequences_and_scalars() {
+        let text = r#"
+# A plan file.
+version: 1
+plan:
+  - type: tool
+    name: run_command
+    input: "cargo test -- --nocapture"   # trailing comment
+    timeout_secs: 1.5
+    background: false
+  - {type: info, message: 'it''s done'}
+  - type: tool
+    name: render
+    args: {template: "Hi {{name}}", values: [1, null, ~, true, "2"]}
+notes: |
+  line one
+    indented
+  line three
+folded: >
+  joined
+  together
+
+  new paragraph
+"#;
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({
+                "version": 1,
+                "plan": [
+                    {"type": "tool", "name": "run_command", "input": "cargo test -- --nocapture",
+                     "timeout_secs": 1.5, "background": false},
+                    {"type": "info", "message": "it's done"},
+                    {"type": "tool", "name": "render",
+                     "args": {"template": "Hi {{name}}", "values": [1, null, null, true, "2"]}}
+                ],
+                "notes": "line one\n  indented\nline three\n",
+                "folded": "joined together\nnew paragraph\n"
+            })
+        );
+        assert_eq!(
+            parse("- \"tab\\there \\u00e9\"").unwrap(),
+            json!(["tab\there é"])
+        );
+        assert_eq!(parse("").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn plans_round_trip_through_yaml() {
+        let plan = Plan::from_json(&json!([
+            {"type": "tool", "id": "build", "name": "cargo", "input": "build", "timeout_secs": 30.0},
+            {"type": "condition", "predicate": {"output_of": "build", "succeeded": true},
+             "then": [{"type": "info", "message": "built: yes #1"}],
+             "else": [{"type": "ask", "question": "Retry?\nIt failed.", "variable": "retry"}]},
+            {"type": "loop", "max_iterations": 3,
+             "steps": [{"type": "tool", "name": "run_command", "args": {"command": "- not a list", "env": {}}}]},
+            {"type": "tool", "name": "llm", "input": "  leading spaces, 'quotes' and: colons  "}
+        ]))
+        .unwrap();
+        let yaml = to_string(&plan.to_json());
+        let parsed = Plan::from_json(&parse(&yaml).unwrap()).unwrap();
+        assert_eq!(parsed.to_json(), plan.to_json(), "{}", yaml);
+        assert!(matches!(parsed.steps[0], PlanStep::ToolCall { .. }));
+    }
+
+    #[test]
+    fn malformed_input_reports_its_line() {
+        for (text, message) in [
+            ("a: 1\na: 2", "YAML line 2: duplicate key 'a'"),
+            ("a:\n  \t- 1", "YAML line 2: tabs are not allowed"),
+            ("a: \"open", "YAML line 1: unterminated string"),
+            ("a: [1, 2", "YAML line 1: expected ',' or ']'"),
+            ("a: *ref", "YAML line 1:"),
+            ("a: \"\\q\"", "YAML line 1: invalid escape sequence"),
+            ("a: 1\n  b: 2", "YAML line 2: unexpected indentation"),
+            ("- 1\nb: 2", "YAML line 2:"),
+        ] {
+            let error = parse(text).unwrap_err();
+            assert!(error.starts_with(message), "{:?}: {}", text, error);
+        }
+    }
+}
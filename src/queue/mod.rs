@@ -0,0 +1,295 @@
+// src/queue/mod.rs
+
+use crate::agent::run_loop::prepare_goal;
+use crate::config::RuntimeConfig;
+use crate::report::RunReport;
+use crate::tools::cancel::CancellationToken;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+pub type JobId = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    TimedOut,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// What `JobQueue::status` reports about a job.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobInfo {
+    pub id: JobId,
+    pub goal: String,
+    pub status: JobStatus,
+}
+
+/// `[queue]` in `agent.toml`: how `agentic serve` and `agentic batch` run goals.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    /// Goals running at once; further ones wait their turn.
+    pub workers: usize,
+    /// Cancels a job that runs longer; unset = no limit.
+    pub job_timeout_secs: Option<f64>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            job_timeout_secs: None,
+        }
+    }
+}
+
+impl QueueConfig {
+    /// Checks the settings, as `RuntimeConfig::parse` does for `[queue]`.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.workers == 0 {
+            return Err("queue.workers must be positive".into());
+        }
+        match self.job_timeout_secs {
+            Some(secs) if !(secs > 0.0 && Duration::try_from_secs_f64(secs).is_ok()) => {
+                Err(format!(
+                    "queue.job_timeout_secs must be a positive number of seconds, got {}",
+                    secs
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The job timeout; `None` if unset or out of range (see `validate`).
+    pub fn job_timeout(&self) -> Option<Duration> {
+        self.job_timeout_secs
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+    }
+}
+
+/// The run behind a job, given the job's cancellation token (cancelled on
+/// `JobQueue::cancel` or timeout).
+type Work = Box<dyn FnOnce(CancellationToken) -> RunReport + Send>;
+
+struct Job {
+    goal: String,
+    status: JobStatus,
+    timeout: Option<Duration>,
+    cancel: CancellationToken,
+    work: Option<Work>,
+    report: Option<RunReport>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    jobs: Vec<Job>,
+    pending: VecDeque<JobId>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<QueueState>,
+    /// Notified when a job is queued or finishes.
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Goals waiting for, and run by, a fixed pool of worker threads, for
+/// server mode and batch processing. At most `workers` jobs run at once, in
+/// submission order; each may have a timeout after which it is cancelled.
+///
+/// Clones share the queue. Workers live as long as the process.
+#[derive(Clone)]
+pub struct JobQueue {
+    shared: Arc<Shared>,
+}
+
+impl JobQueue {
+    pub fn new(workers: usize) -> Self {
+        let shared = Arc::new(Shared::default());
+        for _ in 0..workers.max(1) {
+            let shared = shared.clone();
+            thread::spawn(move || work_loop(&shared));
+        }
+        Self { shared }
+    }
+
+    /// Queues `goal` to run as `run_goal` would, with its own agent and
+    /// context from `config`.
+    pub fn enqueue(&self, goal: &str, config: &RuntimeConfig, timeout: Option<Duration>) -> JobId {
+        let (goal, config) = (goal.to_string(), config.clone());
+        self.submit(&goal.clone(), timeout, move |cancel| {
+            match prepare_goal(&goal, &config) {
+                Ok((mut agent, policy)) => {
+                    agent.context.set_cancel_token(cancel);
+                    agent.run(&policy)
+                }
+                Err(e) => {
+                    let mut report = RunReport::new(&goal);
+                    report.aborted = Some(format!("Invalid runtime config: {}", e));
+                    report
+                }
+            }
+        })
+    }
+
+    /// Queues custom work, e.g. an agent with hooks of its own. `work` should
+    /// stop early once the token it is given is cancelled.
+    pub fn submit(
+        &self,
+        goal: &str,
+        timeout: Option<Duration>,
+        work: impl FnOnce(CancellationToken) -> RunReport + Send + 'static,
+    ) -> JobId {
+        let mut state = self.shared.lock();
+        let id = state.jobs.len() + 1;
+        state.jobs.push(Job {
+            goal: goal.into(),
+            status: JobStatus::Queued,
+            timeout,
+            cancel: CancellationToken::new(),
+            work: Some(Box::new(work)),
+            report: None,
+        });
+        state.pending.push_back(id);
+        self.shared.changed.notify_all();
+        id
+    }
+
+    pub fn status(&self, id: JobId) -> Option<JobInfo> {
+        let state = self.shared.lock();
+        job(&state, id).map(|job| info(id, job))
+    }
+
+    /// Every job so far, oldest first.
+    pub fn list(&self) -> Vec<JobInfo> {
+        let state = self.shared.lock();
+        state
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(i, job)| info(i + 1, job))
+            .collect()
+    }
+
+    /// The report of a finished job.
+    pub fn report(&self, id: JobId) -> Option<RunReport> {
+        job(&self.shared.lock(), id)?.report.clone()
+    }
+
+    /// Drops a queued job or cancels a running one; false if it had finished.
+    pub fn cancel(&self, id: JobId) -> bool {
+        let mut state = self.shared.lock();
+        let Some(job) = state.jobs.get_mut(id.wrapping_sub(1)) else {
+            return false;
+        };
+        match job.status {
+            JobStatus::Queued => {
+                job.status = JobStatus::Cancelled;
+                job.work = None;
+                state.pending.retain(|pending| *pending != id);
+                self.shared.changed.notify_all();
+                true
+            }
+            JobStatus::Running => {
+                job.cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Blocks until job `id` has finished.
+    pub fn wait(&self, id: JobId) -> Option<JobInfo> {
+        let state = self.shared.lock();
+        job(&state, id)?;
+        let state = self
+            .shared
+            .changed
+            .wait_while(state, |state| {
+                !job(state, id).is_some_and(|job| job.status.is_finished())
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+        job(&state, id).map(|job| info(id, job))
+    }
+
+    /// Blocks until every job submitted so far has finished.
+    pub fn wait_all(&self) {
+        let state = self.shared.lock();
+        let _state = self
+            .shared
+            .changed
+            .wait_while(state, |state| {
+                state.jobs.iter().any(|job| !job.status.is_finished())
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+fn job(state: &QueueState, id: JobId) -> Option<&Job> {
+    state.jobs.get(id.wrapping_sub(1))
+}
+
+fn info(id: JobId, job: &Job) -> JobInfo {
+    JobInfo {
+        id,
+        goal: job.goal.clone(),
+        status: job.status,
+    }
+}
+
+fn work_loop(shared: &Shared) {
+    loop {
+        let (id, work, token, cancel) = {
+            let mut state = shared
+                .changed
+                .wait_while(shared.lock(), |state| state.pending.is_empty())
+                .unwrap_or_else(PoisonError::into_inner);
+            let id = state.pending.pop_front().unwrap();
+            let job = &mut state.jobs[id - 1];
+            job.status = JobStatus::Running;
+            // The deadline starts when the job does, not when it was queued.
+            let token = job.cancel.child(job.timeout);
+            (id, job.work.take().unwrap(), token, job.cancel.clone())
+        };
+
+        let report = catch_unwind(AssertUnwindSafe(|| work(token.clone()))).unwrap_or_else(|_| {
+            let mut report = RunReport::new(&shared.lock().jobs[id - 1].goal);
+            report.aborted = Some("The job panicked".into());
+            report
+        });
+
+        let mut state = shared.lock();
+        let job = &mut state.jobs[id - 1];
+        job.status = if token.timed_out() {
+            JobStatus::TimedOut
+        } else if cancel.is_cancelled() {
+            JobStatus::Cancelled
+        } else if report.success() {
+            JobStatus::Succeeded
+        } else {
+            JobStatus::Failed
+        };
+        job.report = Some(report);
+        shared.changed.notify_all();
+    }
+}
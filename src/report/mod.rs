@@ -0,0 +1,340 @@
+// src/report/mod.rs
+
+pub mod summary;
+
+pub use summary::RunSummary;
+
+use crate::context::ToolCallRecord;
+use crate::llm::usage::UsageSummary;
+use crate::protocol::{ExecutionResult, Feedback, Plan, SimulationResult};
+use serde_json::{Value, json};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// One plan → simulate → execute → evaluate cycle. `feedback` is `None` for
+/// follow-up cycles that were not evaluated.
+#[derive(Clone, Debug)]
+pub struct RunIteration {
+    pub label: String,
+    pub plan: Plan,
+    pub simulation: SimulationResult,
+    pub execution: ExecutionResult,
+    pub feedback: Option<Feedback>,
+}
+
+/// Structured record of a whole run, for archiving, diffing and dashboards.
+#[derive(Clone, Debug)]
+pub struct RunReport {
+    pub goal: String,
+    /// Unix timestamp (seconds) of when the report was created.
+    pub started_at: u64,
+    pub iterations: Vec<RunIteration>,
+    pub memory: Vec<(String, String)>,
+    /// LLM tokens and estimated cost, when a `UsageTracker` was attached.
+    pub usage: Option<UsageSummary>,
+    /// Every tool call of the run, across iterations.
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// Why the run stopped short of the goal: the run's `ReplanPolicy` gave
+    /// up, or it was cancelled (see `mark_cancelled`).
+    pub aborted: Option<String>,
+    /// Wall time counts from here (when the report was created).
+    started: Instant,
+}
+
+impl RunReport {
+    pub fn new(goal: &str) -> Self {
+        Self {
+            goal: goal.into(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            iterations: Vec::new(),
+            memory: Vec::new(),
+            usage: None,
+            tool_calls: Vec::new(),
+            aborted: None,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn add_iteration(
+        &mut self,
+        label: &str,
+        plan: &Plan,
+        simulation: &SimulationResult,
+        execution: &ExecutionResult,
+        feedback: Option<&Feedback>,
+    ) {
+        self.iterations.push(RunIteration {
+            label: label.into(),
+            plan: plan.clone(),
+            simulation: simulation.clone(),
+            execution: execution.clone(),
+            feedback: feedback.cloned(),
+        });
+    }
+
+    pub fn with_memory(mut self, memory: Vec<(String, String)>) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    pub fn with_tool_calls(mut self, tool_calls: Vec<ToolCallRecord>) -> Self {
+        self.tool_calls = tool_calls;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: UsageSummary) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Marks the run as cut short by cancellation (Ctrl-C, a job timeout,
+    /// the budget), so the report reads as partial. Keeps an earlier reason.
+    pub fn mark_cancelled(&mut self) {
+        self.aborted.get_or_insert_with(|| {
+            format!(
+                "Cancelled after {} iteration(s); the report covers the steps that ran",
+                self.iterations.len()
+            )
+        });
+    }
+
+    /// True when the last iteration achieved the goal (see `ExecutionResult::goal_achieved`).
+    pub fn success(&self) -> bool {
+        self.iterations
+            .last()
+            .is_some_and(|iteration| iteration.execution.goal_achieved())
+    }
+
+    /// Timings so far: wall time since the report was created, tool call
+    /// durations and LLM latency.
+    pub fn summary(&self) -> RunSummary {
+        RunSummary::new(
+            self.started.elapsed(),
+            self.iterations.len(),
+            &self.tool_calls,
+            self.usage.as_ref(),
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        let iterations = self
+            .iterations
+            .iter()
+            .map(|iteration| {
+                json!({
+                    "label": iteration.label,
+                    "plan": iteration.plan.to_json(),
+                    "simulation": iteration.simulation,
+                    "execution": iteration.execution,
+                    "feedback": iteration.feedback,
+                })
+            })
+            .collect::<Vec<_>>();
+        let memory = self
+            .memory
+            .iter()
+            .map(|(label, content)| json!({ "label": label, "content": content }))
+            .collect::<Vec<_>>();
+
+        json!({
+            "goal": self.goal,
+            "started_at": self.started_at,
+            "success": self.success(),
+            "iterations": iterations,
+            "memory": memory,
+            "tool_calls": self.tool_calls,
+            "usage": self.usage,
+            "aborted": self.aborted,
+            "summary": self.summary(),
+        })
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let status = if self.success() {
+            "✅ success"
+        } else {
+            "❌ failed"
+        };
+        let _ = writeln!(md, "# Run report\n");
+        let _ = writeln!(md, "- **Goal:** {}", self.goal);
+        let _ = writeln!(md, "- **Started:** {} (unix)", self.started_at);
+        let _ = writeln!(md, "- **Status:** {}", status);
+        if let Some(aborted) = &self.aborted {
+            let _ = writeln!(md, "- **Aborted:** {}", aborted.replace('\n', " "));
+        }
+
+        for iteration in &self.iterations {
+            let _ = writeln!(md, "\n## {}\n", iteration.label);
+
+            let _ = writeln!(md, "### Plan\n\n```json");
+            let plan = serde_json::to_string_pretty(&iteration.plan.to_json()).unwrap_or_default();
+            let _ = writeln!(md, "{}\n```\n", plan);
+
+            let _ = writeln!(md, "### Simulation\n");
+            let _ = writeln!(md, "{}", iteration.simulation.predicted_outcome);
+            for warning in &iteration.simulation.warnings {
+                let _ = writeln!(md, "- ⚠️ {}", warning);
+            }
+            for step in &iteration.simulation.steps {
+                let _ = writeln!(
+                    md,
+                    "- {} risk: `{}` {}",
+                    step.outcome.risk, step.tool, step.outcome.summary
+                );
+            }
+
+            let _ = writeln!(md, "\n### Steps\n");
+            if iteration.execution.steps.is_empty() {
+                let _ = writeln!(md, "_No tool calls._");
+            } else {
+                let _ = writeln!(md, "| # | Id | Tool | Result | Detail |");
+                let _ = writeln!(md, "|---|----|------|--------|--------|");
+                for (i, step) in iteration.execution.steps.iter().enumerate() {
+                    let detail = step
+                        .output
+                        .as_deref()
+                        .or(step.error.as_deref())
+                        .unwrap_or_default();
+                    let _ = writeln!(
+                        md,
+                        "| {} | {} | `{}` | {} | {} |",
+                        i + 1,
+                        step.id.as_deref().unwrap_or("-"),
+                        step.tool,
+                        if step.success { "✅" } else { "❌" },
+                        table_cell(detail)
+                    );
+                }
+            }
+
+            if !iteration.execution.errors.is_empty() {
+                let _ = writeln!(md, "\n### Errors\n");
+                for error in &iteration.execution.errors {
+                    let _ = writeln!(md, "- {}", error);
+                }
+            }
+
+            if let Some(verdict) = &iteration.execution.verdict {
+                let _ = writeln!(md, "\n### Verdict\n");
+                let _ = writeln!(
+                    md,
+                    "**{}** ({:.0}% confidence) — {}",
+                    if verdict.achieved {
+                        "Achieved"
+                    } else {
+                        "Not achieved"
+                    },
+                    verdict.confidence * 100.0,
+                    verdict.reasoning
+                );
+                for item in &verdict.missing {
+                    let _ = writeln!(md, "- missing: {}", item);
+                }
+            }
+
+            if let Some(feedback) = &iteration.feedback {
+                let _ = writeln!(md, "\n### Feedback\n");
+                let _ = writeln!(
+                    md,
+                    "**Score:** {}/100\n\n{}",
+                    feedback.score, feedback.notes
+                );
+                for criterion in &feedback.criteria {
+                    let _ = writeln!(
+                        md,
+                        "- {}: {}/100 — {}",
+                        criterion.name, criterion.score, criterion.reasoning
+                    );
+                }
+                for item in &feedback.critique {
+                    let _ = writeln!(md, "- to fix: {}", item);
+                }
+            }
+        }
+
+        let _ = writeln!(md, "\n## Summary\n\n```\n{}```", self.summary());
+
+        if let Some(usage) = &self.usage {
+            let _ = writeln!(md, "\n## Usage\n");
+            let _ = writeln!(
+                md,
+                "| Component | Calls | Prompt | Completion | Cost (USD) |"
+            );
+            let _ = writeln!(
+                md,
+                "|-----------|-------|--------|------------|------------|"
+            );
+            let rows = usage
+                .by_component
+                .iter()
+                .map(|(name, totals)| (name.as_str(), totals))
+                .chain([("**total**", &usage.total)]);
+            for (name, totals) in rows {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {:.4} |",
+                    name,
+                    totals.calls,
+                    totals.prompt_tokens,
+                    totals.completion_tokens,
+                    totals.cost_usd
+                );
+            }
+        }
+
+        if !self.tool_calls.is_empty() {
+            let _ = writeln!(md, "\n## Tool calls\n");
+            let _ = writeln!(md, "| # | Id | Tool | Input | Result | Duration | Output |");
+            let _ = writeln!(md, "|---|----|------|-------|--------|----------|--------|");
+            for (i, call) in self.tool_calls.iter().enumerate() {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | `{}` | {} | {} | {}ms | {} |",
+                    i + 1,
+                    call.step_id.as_deref().unwrap_or("-"),
+                    call.tool,
+                    table_cell(&call.input),
+                    if call.success { "✅" } else { "❌" },
+                    call.duration.as_millis(),
+                    table_cell(&call.output)
+                );
+            }
+        }
+
+        if !self.memory.is_empty() {
+            let _ = writeln!(md, "\n## Memory\n");
+            for (label, content) in &self.memory {
+                let _ = writeln!(md, "- **{}**: {}", label, table_cell(content));
+            }
+        }
+        md
+    }
+
+    /// Writes `<dir>/run-<started_at>.json` and `.md`, returning the JSON path.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let base = dir.join(format!("run-{}", self.started_at));
+        let json_path = base.with_extension("json");
+        let json = serde_json::to_string_pretty(&self.to_json()).map_err(|e| e.to_string())?;
+        fs::write(&json_path, json).map_err(|e| e.to_string())?;
+        fs::write(base.with_extension("md"), self.to_markdown()).map_err(|e| e.to_string())?;
+        Ok(json_path)
+    }
+}
+
+/// Collapses text onto one line and escapes pipes so it fits a table cell.
+fn table_cell(text: &str) -> String {
+    const MAX_CHARS: usize = 120;
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut cell: String = line.chars().take(MAX_CHARS).collect();
+    if line.chars().count() > MAX_CHARS {
+        cell.push('…');
+    }
+    cell.replace('|', "\\|")
+}
@@ -0,0 +1,167 @@
+// src/report/summary.rs
+
+use crate::context::ToolCallRecord;
+use crate::context::history::millis;
+use crate::llm::usage::{UsageSummary, UsageTotals};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Slowest steps listed by `Display`; `steps` keeps all of them.
+const SLOWEST_STEPS: usize = 5;
+
+/// Where a run's time went: per-step durations, per-tool success rates, LLM
+/// latency by component, replans and total wall time.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RunSummary {
+    #[serde(rename = "wall_time_ms", with = "millis")]
+    pub wall_time: Duration,
+    /// Plans executed, including the first.
+    pub iterations: usize,
+    pub replans: usize,
+    /// Every tool call, in order.
+    pub steps: Vec<StepTiming>,
+    pub tools: BTreeMap<String, ToolStats>,
+    /// Calls, tokens and latency of every LLM call.
+    pub llm: UsageTotals,
+    pub llm_by_component: BTreeMap<String, UsageTotals>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StepTiming {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    pub tool: String,
+    pub input: String,
+    #[serde(rename = "duration_ms", with = "millis")]
+    pub duration: Duration,
+    pub success: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct ToolStats {
+    pub calls: usize,
+    pub failures: usize,
+    #[serde(rename = "total_ms", with = "millis")]
+    pub total: Duration,
+}
+
+impl ToolStats {
+    /// Fraction of calls that succeeded, from 0 to 1.
+    pub fn success_rate(&self) -> f64 {
+        match self.calls {
+            0 => 0.0,
+            calls => (calls - self.failures) as f64 / calls as f64,
+        }
+    }
+}
+
+impl RunSummary {
+    pub fn new(
+        wall_time: Duration,
+        iterations: usize,
+        tool_calls: &[ToolCallRecord],
+        usage: Option<&UsageSummary>,
+    ) -> Self {
+        let mut tools: BTreeMap<String, ToolStats> = BTreeMap::new();
+        for call in tool_calls {
+            let stats = tools.entry(call.tool.clone()).or_default();
+            stats.calls += 1;
+            stats.failures += !call.success as usize;
+            stats.total += call.duration;
+        }
+        Self {
+            wall_time,
+            iterations,
+            replans: iterations.saturating_sub(1),
+            steps: tool_calls
+                .iter()
+                .map(|call| StepTiming {
+                    step_id: call.step_id.clone(),
+                    tool: call.tool.clone(),
+                    input: call.input.clone(),
+                    duration: call.duration,
+                    success: call.success,
+                })
+                .collect(),
+            tools,
+            llm: usage.map(|usage| usage.total).unwrap_or_default(),
+            llm_by_component: usage
+                .map(|usage| usage.by_component.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Time spent in tool calls.
+    pub fn tool_time(&self) -> Duration {
+        self.tools.values().map(|stats| stats.total).sum()
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Wall time {} over {} plan(s) ({} replan(s))",
+            secs(self.wall_time),
+            self.iterations,
+            self.replans
+        )?;
+        writeln!(
+            f,
+            "Tools: {} across {} call(s)",
+            secs(self.tool_time()),
+            self.steps.len()
+        )?;
+        for (tool, stats) in &self.tools {
+            writeln!(
+                f,
+                "  {:<20} {:>3} call(s), {:>3.0}% ok, {} total, {} avg",
+                tool,
+                stats.calls,
+                stats.success_rate() * 100.0,
+                secs(stats.total),
+                secs(stats.total / stats.calls.max(1) as u32)
+            )?;
+        }
+        writeln!(
+            f,
+            "LLM: {} across {} call(s)",
+            secs(self.llm.latency),
+            self.llm.calls
+        )?;
+        for (component, totals) in &self.llm_by_component {
+            writeln!(
+                f,
+                "  {:<20} {:>3} call(s), {} total, {} avg",
+                component,
+                totals.calls,
+                secs(totals.latency),
+                secs(totals.latency / totals.calls.max(1) as u32)
+            )?;
+        }
+
+        let mut slowest: Vec<(usize, &StepTiming)> = self.steps.iter().enumerate().collect();
+        slowest.sort_by_key(|(_, step)| std::cmp::Reverse(step.duration));
+        if !slowest.is_empty() {
+            writeln!(f, "Slowest steps:")?;
+        }
+        for (i, step) in slowest.into_iter().take(SLOWEST_STEPS) {
+            writeln!(
+                f,
+                "  #{:<3} {} {} `{}` {}",
+                i + 1,
+                if step.success { "✅" } else { "❌" },
+                step.tool,
+                step.input.lines().next().unwrap_or_default(),
+                secs(step.duration)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn secs(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
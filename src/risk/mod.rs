@@ -0,0 +1,261 @@
+// src/risk/mod.rs
+
+use crate::context::Context;
+use crate::protocol::{Plan, PlanStep, SimulationOutcome};
+use crate::tools::Tool;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+pub use crate::protocol::RiskLevel;
+
+/// Destructive commands, privilege escalation and remote scripts. Checked for
+/// shell tools and calls that change state, so prompts that merely mention
+/// `sudo` are not flagged.
+const COMMAND_PATTERNS: [(&str, &str); 13] = [
+    (
+        r"\brm\s+(?:-\S+\s+)*-[a-zA-Z]*(?:[rR][a-zA-Z]*f|f[a-zA-Z]*[rR])",
+        "recursive forced delete (rm -rf)",
+    ),
+    (
+        r"\brm\b(?:.*\s(?:-[rR]|--recursive)\b.*\s(?:-f|--force)\b|.*\s(?:-f|--force)\b.*\s(?:-[rR]|--recursive)\b)",
+        "recursive forced delete (rm -r -f)",
+    ),
+    (
+        r"\bgit\s+push\b.*\s(?:--force(?:-with-lease)?|-f)\b",
+        "force push rewrites remote history",
+    ),
+    (
+        r"\bgit\s+push\b.*\s\+\S+",
+        "force push rewrites remote history (+refspec)",
+    ),
+    (
+        r"\bgit\s+reset\b.*\s--hard\b",
+        "discards uncommitted changes (git reset --hard)",
+    ),
+    (
+        r"\bgit\s+clean\s+(?:-\S+\s+)*-[a-zA-Z]*f",
+        "deletes untracked files (git clean -f)",
+    ),
+    (
+        r"\b(?:curl|wget)\b[^|;&]*\|\s*(?:sudo\s+)?(?:ba|z|da)?sh\b",
+        "pipes a downloaded script into a shell",
+    ),
+    (
+        r"\b(?:ba|z)?sh\s+<\(\s*(?:curl|wget)\b",
+        "runs a downloaded script",
+    ),
+    (r"\b(?:sudo|doas)\s", "runs with elevated privileges (sudo)"),
+    (
+        r"\bmkfs\b|\bdd\s.*\bof=/dev/|>\s*/dev/(?:sd|hd|nvme)",
+        "overwrites a disk device",
+    ),
+    (
+        r"\bchmod\s+(?:-\S+\s+)*-R\s+(?:0?777|a\+rwx)",
+        "makes files world-writable recursively",
+    ),
+    (r":\(\)\s*\{", "fork bomb"),
+    (
+        r"\b(?:shutdown|reboot|halt|poweroff)\b",
+        "shuts down the machine",
+    ),
+];
+
+/// Secrets that must not reach a command line, log or LLM prompt.
+const SECRET_PATTERNS: [(&str, &str); 4] = [
+    (
+        r"\bAKIA[0-9A-Z]{16}\b",
+        "secret in arguments (AWS access key)",
+    ),
+    (
+        r"\b(?:sk-(?:proj-|ant-)?[A-Za-z0-9_-]{20,}|gh[pousr]_[A-Za-z0-9]{30,}|xox[abprs]-[A-Za-z0-9-]{10,})",
+        "secret in arguments (API token)",
+    ),
+    (
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----",
+        "secret in arguments (private key)",
+    ),
+    (
+        r#"(?i)\b(?:password|passwd|secret|token|api[_-]?key)\s*[=:]\s*['"]?[^\s'"$]{6,}|(?i)authorization:\s*bearer\s+[A-Za-z0-9._-]{10,}"#,
+        "secret in arguments (credential)",
+    ),
+];
+
+type Patterns = Vec<(Regex, &'static str)>;
+
+fn compiled(patterns: &[(&str, &'static str)]) -> Patterns {
+    patterns
+        .iter()
+        .map(|(pattern, reason)| (Regex::new(pattern).unwrap(), *reason))
+        .collect()
+}
+
+fn command_patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| compiled(&COMMAND_PATTERNS))
+}
+
+fn secret_patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| compiled(&SECRET_PATTERNS))
+}
+
+/// The risk of one tool call: its tool's `preview` raised by any
+/// high-risk patterns found in the input or arguments.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StepRisk {
+    pub tool: String,
+    pub input: String,
+    pub level: RiskLevel,
+    /// Why the step is high-risk; empty unless a pattern matched.
+    pub reasons: Vec<String>,
+}
+
+/// High-risk patterns (commands and secrets) found in `text`.
+pub fn scan(text: &str) -> Vec<String> {
+    let mut reasons = Vec::new();
+    scan_into(command_patterns(), text, &mut reasons);
+    scan_into(secret_patterns(), text, &mut reasons);
+    reasons
+}
+
+fn scan_into(patterns: &Patterns, text: &str, reasons: &mut Vec<String>) {
+    for (pattern, reason) in patterns {
+        if pattern.is_match(text) && !reasons.iter().any(|known| known == reason) {
+            reasons.push(reason.to_string());
+        }
+    }
+}
+
+/// Whether `tool` runs shell commands (it is tagged `shell`).
+pub fn runs_shell(tool: &(dyn Tool + Send + Sync)) -> bool {
+    tool.spec().tags.iter().any(|tag| tag == "shell")
+}
+
+/// Raises `outcome` to high risk when `input` or `args` contain a secret or,
+/// for `shell` tools and calls that change state, a dangerous command,
+/// recording the reasons. Shell input is always scanned, since its preview is
+/// only a guess.
+pub fn annotate(
+    outcome: SimulationOutcome,
+    shell: bool,
+    input: &str,
+    args: Option<&Value>,
+) -> SimulationOutcome {
+    let mut text = input.to_string();
+    if let Some(args) = args {
+        // Scan string values unescaped, so quotes inside commands still match.
+        let mut strings = Vec::new();
+        collect_strings(args, &mut strings);
+        text.push('\n');
+        text.push_str(&strings.join("\n"));
+    }
+    let mut reasons = Vec::new();
+    if shell || !outcome.read_only {
+        scan_into(command_patterns(), &text, &mut reasons);
+    }
+    scan_into(secret_patterns(), &text, &mut reasons);
+    if reasons.is_empty() {
+        return outcome;
+    }
+    SimulationOutcome {
+        risk: RiskLevel::High,
+        reasons,
+        ..outcome
+    }
+}
+
+/// Assesses a call to `tool` (`None` if it is not registered).
+pub fn assess_call(
+    tool: Option<&(dyn Tool + Send + Sync)>,
+    name: &str,
+    input: &str,
+    args: Option<&Value>,
+) -> StepRisk {
+    let outcome = match (tool, args) {
+        (Some(tool), Some(args)) => tool.preview_args(args),
+        (Some(tool), None) => tool.preview(input),
+        (None, _) => SimulationOutcome::mutating(&format!("unknown tool {}", name)),
+    };
+    let outcome = annotate(outcome, tool.is_some_and(runs_shell), input, args);
+    StepRisk {
+        tool: name.to_string(),
+        input: match args {
+            Some(args) if input.is_empty() => args.to_string(),
+            _ => input.to_string(),
+        },
+        level: outcome.risk,
+        reasons: outcome.reasons,
+    }
+}
+
+/// Every tool call in `plan` (branches and loop bodies included), in order.
+/// Inputs are assessed before `$output[...]` references are resolved.
+pub fn assess_plan(plan: &Plan, context: &Context) -> Vec<StepRisk> {
+    let mut risks = Vec::new();
+    assess_steps(&plan.steps, context, &mut risks);
+    risks
+}
+
+fn assess_steps(steps: &[PlanStep], context: &Context, risks: &mut Vec<StepRisk>) {
+    for step in steps {
+        match step {
+            PlanStep::ToolCall {
+                name, input, args, ..
+            } => risks.push(assess_call(
+                context.get_tool(name),
+                name,
+                input,
+                args.as_ref(),
+            )),
+            PlanStep::Conditional {
+                then_steps,
+                else_steps,
+                ..
+            } => {
+                assess_steps(then_steps, context, risks);
+                assess_steps(else_steps, context, risks);
+            }
+            PlanStep::Loop { steps, .. } => assess_steps(steps, context, risks),
+            PlanStep::Info(_) | PlanStep::AskUser { .. } => {}
+        }
+    }
+}
+
+fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(text) => strings.push(text),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, strings)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, strings)),
+        _ => {}
+    }
+}
+
+#[cfg(all(test, feature = "shell-tools"))]
+mod tests {
+    use super::*;
+    use crate::tools::run_command::{RunCommandTool, preview_command};
+    use crate::validation::command::CommandRules;
+
+    #[test]
+    fn wrapped_and_substituted_commands_are_high_risk() {
+        let tool = RunCommandTool::new();
+        for input in [
+            "env git push --force origin main",
+            "echo $(git push --force)",
+            "echo `sudo reboot`",
+        ] {
+            assert!(!preview_command(input).read_only, "{}", input);
+            let risk = assess_call(Some(&tool), "run_command", input, None);
+            assert_eq!(risk.level, RiskLevel::High, "{}", input);
+        }
+        assert!(
+            CommandRules::default()
+                .check("echo `sudo reboot`")
+                .iter()
+                .any(|rule| rule.name == "sudo")
+        );
+        assert!(preview_command("echo '$HOME' | wc -c").read_only);
+    }
+}
@@ -0,0 +1,168 @@
+// src/scheduler/cron.rs
+
+/// A five-field cron expression (`minute hour day-of-month month
+/// day-of-week`), evaluated in UTC. Fields take `*`, numbers, ranges
+/// (`1-5`), lists (`1,15`) and steps (`*/15`, `0-30/10`); day-of-week runs
+/// 0-7 with both 0 and 7 meaning Sunday. `@hourly`, `@daily`, `@weekly` and
+/// `@monthly` are shorthands.
+///
+/// As in cron, when both day fields are restricted a day matching either runs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron expression '{}' needs 5 fields (minute hour day month weekday)",
+                expr
+            ));
+        };
+        let field = |text: &str, min: u32, max: u32, name: &str| {
+            parse_field(text, min, max).map_err(|e| format!("Cron {} '{}': {}", name, text, e))
+        };
+        let mut weekdays = field(weekday, 0, 7, "day-of-week")?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: field(minute, 0, 59, "minute")?,
+            hours: field(hour, 0, 23, "hour")?,
+            days: field(day, 1, 31, "day-of-month")?,
+            months: field(month, 1, 12, "month")?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// Whether the minute starting at unix time `secs` is scheduled.
+    pub fn matches(&self, secs: u64) -> bool {
+        let time = UtcTime::from_unix(secs);
+        let day = self.days & (1 << time.day) != 0;
+        let weekday = self.weekdays & (1 << time.weekday) != 0;
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        self.minutes & (1 << time.minute) != 0
+            && self.hours & (1 << time.hour) != 0
+            && self.months & (1 << time.month) != 0
+            && day_matches
+    }
+
+    /// The first scheduled minute strictly after unix time `secs`, looking
+    /// at most four years ahead (e.g. `0 0 29 2 *` runs only in leap years).
+    pub fn next_after(&self, secs: u64) -> Option<u64> {
+        let start = (secs / 60 + 1) * 60;
+        (0..4 * 366 * 24 * 60)
+            .map(|minute| start + minute * 60)
+            .find(|&candidate| self.matches(candidate))
+    }
+}
+
+fn parse_field(text: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("bad step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be positive".into());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let number = |n: &str| -> Result<u32, String> {
+            let n: u32 = n.parse().map_err(|_| format!("'{}' is not a number", n))?;
+            if n < min || n > max {
+                return Err(format!("{} is outside {}-{}", n, min, max));
+            }
+            Ok(n)
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (number(from)?, number(to)?),
+                // `5/15` means from 5 to the end, every 15.
+                None if step > 1 => (number(range)?, max),
+                None => {
+                    let n = number(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if from > to {
+            return Err(format!("range {}-{} is backwards", from, to));
+        }
+        for n in (from..=to).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+/// The UTC calendar fields of a unix time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UtcTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    /// 0 is Sunday.
+    pub weekday: u32,
+}
+
+impl UtcTime {
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86_400) as i64;
+        let of_day = secs % 86_400;
+        // Howard Hinnant's days-to-civil algorithm.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Self {
+            year,
+            month,
+            day,
+            hour: (of_day / 3_600) as u32,
+            minute: (of_day % 3_600 / 60) as u32,
+            // 1970-01-01 was a Thursday.
+            weekday: ((days + 4).rem_euclid(7)) as u32,
+        }
+    }
+}
+
+impl std::fmt::Display for UtcTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
@@ -0,0 +1,220 @@
+// src/scheduler/mod.rs
+
+pub mod cron;
+
+use crate::agent::run_loop::prepare_goal;
+use crate::config::RuntimeConfig;
+use crate::policy::user::UserInterface;
+use crate::report::RunReport;
+use cron::CronSchedule;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where `agentic schedule` keeps job reports and `history.jsonl`.
+pub const DEFAULT_SCHEDULE_DIR: &str = ".agentic/schedule";
+
+/// A recurring goal from `[schedule.<name>]` in `agent.toml`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledJob {
+    /// See `CronSchedule`, e.g. `"0 3 * * *"` for 03:00 UTC daily.
+    pub cron: String,
+    pub goal: String,
+}
+
+/// One finished run of a scheduled job, as kept in `history.jsonl`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledRun {
+    pub job: String,
+    /// Unix seconds.
+    pub started_at: u64,
+    pub success: bool,
+    pub aborted: Option<String>,
+    /// The archived `RunReport` JSON, unless saving it failed.
+    pub report: Option<String>,
+}
+
+/// Runs the configured jobs when their cron schedules come due.
+///
+/// Every run gets a fresh agent and context from the config, so jobs share
+/// no memory, tool history or usage. Runs are unattended: approvals the
+/// policy would prompt for are refused and `AskUser` questions go
+/// unanswered. A job still running when it comes due again is skipped.
+pub struct Scheduler {
+    config: RuntimeConfig,
+    jobs: BTreeMap<String, (CronSchedule, String)>,
+    dir: PathBuf,
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Scheduler {
+    /// Fails if a job's cron expression does not parse.
+    pub fn new(config: RuntimeConfig, dir: &Path) -> Result<Self, String> {
+        let mut jobs = BTreeMap::new();
+        for (name, job) in &config.schedule {
+            let schedule =
+                CronSchedule::parse(&job.cron).map_err(|e| format!("schedule.{}: {}", name, e))?;
+            jobs.insert(name.clone(), (schedule, job.goal.clone()));
+        }
+        Ok(Self {
+            config,
+            jobs,
+            dir: dir.to_path_buf(),
+            running: Arc::default(),
+        })
+    }
+
+    /// Each job with the unix time of its next run after `now`.
+    pub fn next_runs(&self, now: u64) -> Vec<(String, Option<u64>)> {
+        self.jobs
+            .iter()
+            .map(|(name, (schedule, _))| (name.clone(), schedule.next_after(now)))
+            .collect()
+    }
+
+    /// The jobs due in the minute starting at unix time `minute`.
+    pub fn due(&self, minute: u64) -> Vec<String> {
+        self.jobs
+            .iter()
+            .filter(|(_, (schedule, _))| schedule.matches(minute))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Starts due jobs at the top of every minute, each on its own thread.
+    /// Only returns if there are no jobs.
+    pub fn run(&self) -> Result<(), String> {
+        if self.jobs.is_empty() {
+            return Err("No jobs configured; add [schedule.<name>] to agent.toml".into());
+        }
+        let mut last = None;
+        loop {
+            let now = unix_now();
+            let minute = now - now % 60;
+            if last != Some(minute) {
+                last = Some(minute);
+                for name in self.due(minute) {
+                    self.spawn(&name);
+                }
+            }
+            thread::sleep(Duration::from_secs(60 - unix_now() % 60));
+        }
+    }
+
+    fn spawn(&self, name: &str) {
+        if !self.running.lock().unwrap().insert(name.to_string()) {
+            tracing::warn!(
+                job = name,
+                "Skipping scheduled run: the previous one is still going"
+            );
+            return;
+        }
+        let (config, dir, running) = (self.config.clone(), self.dir.clone(), self.running.clone());
+        let (name, goal) = (name.to_string(), self.jobs[name].1.clone());
+        thread::spawn(move || {
+            if let Err(e) = run_job(&name, &goal, &config, &dir) {
+                tracing::warn!(job = %name, "Scheduled run not recorded: {}", e);
+            }
+            running.lock().unwrap().remove(&name);
+        });
+    }
+
+    /// Runs `name` now, whatever its schedule, and records it.
+    pub fn run_now(&self, name: &str) -> Result<ScheduledRun, String> {
+        let (_, goal) = self
+            .jobs
+            .get(name)
+            .ok_or_else(|| format!("No scheduled job '{}'", name))?;
+        run_job(name, goal, &self.config, &self.dir)
+    }
+
+    /// Past runs, oldest first.
+    pub fn history(&self) -> Result<Vec<ScheduledRun>, String> {
+        load_history(&self.dir)
+    }
+}
+
+/// Runs one job in its own agent, archives the report under `<dir>/<job>/`
+/// and appends the outcome to `<dir>/history.jsonl`.
+fn run_job(
+    name: &str,
+    goal: &str,
+    config: &RuntimeConfig,
+    dir: &Path,
+) -> Result<ScheduledRun, String> {
+    tracing::info!(job = name, "⏰ Running scheduled goal: {}", goal);
+    let report = match prepare_goal(goal, config) {
+        Ok((mut agent, policy)) => {
+            agent.context.user = Arc::new(Unattended);
+            agent.run(&policy)
+        }
+        Err(e) => {
+            let mut report = RunReport::new(goal);
+            report.aborted = Some(format!("Invalid runtime config: {}", e));
+            report
+        }
+    };
+    let saved = report.save(&dir.join(name));
+    let run = ScheduledRun {
+        job: name.to_string(),
+        started_at: report.started_at,
+        success: report.success(),
+        aborted: report.aborted.clone(),
+        report: saved.as_ref().ok().map(|path| path.display().to_string()),
+    };
+    append_history(dir, &run)?;
+    saved?;
+    Ok(run)
+}
+
+fn append_history(dir: &Path, run: &ScheduledRun) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let line = serde_json::to_string(run).map_err(|e| e.to_string())?;
+    let path = dir.join("history.jsonl");
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The runs recorded in `<dir>/history.jsonl`, skipping unreadable lines.
+pub fn load_history(dir: &Path) -> Result<Vec<ScheduledRun>, String> {
+    let path = dir.join("history.jsonl");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Nobody is at the terminal: prompted approvals are refused and questions
+/// go unanswered.
+struct Unattended;
+
+impl UserInterface for Unattended {
+    fn ask(&self, _question: &str, _variable: &str) -> Option<String> {
+        None
+    }
+
+    fn approve(&self, _action: &str, _reasons: &[String]) -> Option<bool> {
+        Some(false)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
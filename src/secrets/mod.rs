@@ -0,0 +1,176 @@
+// src/secrets/mod.rs
+
+use crate::tools::ToolResult;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+/// Values shorter than this are never masked: redacting every `"a"` or
+/// `"42"` in the logs would make them unreadable without hiding anything.
+const MIN_REDACTED_LEN: usize = 4;
+
+/// Named secret values (API keys, tokens) that tool calls reference as
+/// `$secret[NAME]`.
+///
+/// Plans, memory and prompts only ever hold the reference: `resolve`
+/// substitutes values right before a tool runs, and `redact` masks any value
+/// that shows up in output or log text back to its reference. Clones share
+/// one store, so secrets added later reach every component.
+#[derive(Clone, Default)]
+pub struct Secrets {
+    values: Arc<RwLock<BTreeMap<String, String>>>,
+}
+
+/// Matches `$secret[NAME]` references in step inputs.
+fn secret_reference_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$secret\[([A-Za-z0-9_.-]+)\]").unwrap())
+}
+
+impl Secrets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, name: &str, value: &str) {
+        self.values
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(name.into(), value.into());
+    }
+
+    /// Builder form of `insert`.
+    pub fn with(self, name: &str, value: &str) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// Stores environment variable `var` under its own name.
+    pub fn load_env(&self, var: &str) -> Result<(), String> {
+        let value = std::env::var(var)
+            .map_err(|_| format!("Secret '{}': environment variable not set", var))?;
+        self.insert(var, &value);
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.read().contains_key(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Secret names, sorted; values are never exposed this way.
+    pub fn names(&self) -> Vec<String> {
+        self.read().keys().cloned().collect()
+    }
+
+    /// Replaces every `$secret[NAME]` in `text` with its value.
+    pub fn resolve(&self, text: &str) -> Result<String, String> {
+        if !text.contains("$secret[") {
+            return Ok(text.to_string());
+        }
+        let values = self.read();
+        let regex = secret_reference_regex();
+        if let Some(unknown) = regex
+            .captures_iter(text)
+            .map(|caps| caps[1].to_string())
+            .find(|name| !values.contains_key(name))
+        {
+            return Err(format!(
+                "Unknown secret '{}' (available: {})",
+                unknown,
+                if values.is_empty() {
+                    "none".to_string()
+                } else {
+                    values.keys().cloned().collect::<Vec<_>>().join(", ")
+                }
+            ));
+        }
+        Ok(regex
+            .replace_all(text, |caps: &regex::Captures| values[&caps[1]].clone())
+            .into_owned())
+    }
+
+    /// `resolve` applied to every string in `args`.
+    pub fn resolve_args(&self, args: &Value) -> Result<Value, String> {
+        Ok(match args {
+            Value::String(text) => Value::String(self.resolve(text)?),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.resolve_args(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, item)| Ok((key.clone(), self.resolve_args(item)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    /// Masks every secret value in `text` as `$secret[NAME]`.
+    pub fn redact(&self, text: &str) -> String {
+        let values = self.read();
+        let mut secrets: Vec<(&String, &String)> = values
+            .iter()
+            .filter(|(_, value)| value.len() >= MIN_REDACTED_LEN)
+            .collect();
+        // Longest first, so a secret containing another is masked whole.
+        secrets.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+
+        let mut text = text.to_string();
+        for (name, value) in secrets {
+            if text.contains(value.as_str()) {
+                text = text.replace(value.as_str(), &format!("$secret[{}]", name));
+            }
+        }
+        text
+    }
+
+    /// `redact` applied to every string in `args`.
+    pub fn redact_args(&self, args: &Value) -> Value {
+        match args {
+            Value::String(text) => Value::String(self.redact(text)),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|item| self.redact_args(item)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, item)| (key.clone(), self.redact_args(item)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// `redact` applied to a tool result's output, error and data.
+    pub fn redact_result(&self, result: ToolResult) -> ToolResult {
+        if self.is_empty() {
+            return result;
+        }
+        ToolResult {
+            output: result.output.map(|output| self.redact(&output)),
+            error: result.error.map(|error| self.redact(&error)),
+            data: result.data.map(|data| self.redact_args(&data)),
+            ..result
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, String>> {
+        self.values.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secrets")
+            .field("names", &self.names())
+            .finish()
+    }
+}
@@ -0,0 +1,220 @@
+// src/server/http.rs
+
+use serde_json::Value;
+use std::io::{BufRead, Read, Write};
+
+/// Largest request body `read_request` accepts.
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// Longest request or header line `read_request` accepts, in bytes.
+const MAX_LINE_BYTES: usize = 8 << 10;
+
+/// Most headers `read_request` accepts.
+const MAX_HEADERS: usize = 64;
+
+/// An HTTP/1.1 request, reduced to what the server routes on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// The path without its query string, e.g. `/runs/run-1/events`.
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn new(method: &str, target: &str, body: &str) -> Self {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        Self {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            query,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// The first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the `Content-Type` is `application/json`, parameters aside.
+    pub fn is_json(&self) -> bool {
+        self.header("content-type").is_some_and(|value| {
+            value
+                .split(';')
+                .next()
+                .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"))
+        })
+    }
+
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The path split on `/`, without empty segments.
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.split('/').filter(|s| !s.is_empty()).collect()
+    }
+
+    /// The body as JSON; an empty body is `null`.
+    pub fn json(&self) -> Result<Value, String> {
+        if self.body.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&self.body).map_err(|e| format!("Invalid JSON body: {}", e))
+    }
+}
+
+/// Reads one request: request line, headers up to the blank line, and a
+/// `Content-Length` body. Chunked bodies are not supported, and overlong
+/// lines, too many headers or too large a body are refused.
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request, String> {
+    let mut line = String::new();
+    read_line(reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(format!("Malformed request line: {}", line.trim()));
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+
+    let mut headers = Vec::new();
+    let mut length = 0;
+    loop {
+        line.clear();
+        if read_line(reader, &mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(format!("More than {} headers", MAX_HEADERS));
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(format!("Malformed header: {}", header));
+        };
+        let (name, value) = (name.trim(), value.trim());
+        if name.eq_ignore_ascii_case("content-length") {
+            length = value
+                .parse()
+                .map_err(|_| format!("Invalid Content-Length: {}", value))?;
+        }
+        headers.push((name.to_string(), value.to_string()));
+    }
+    if length > MAX_BODY_BYTES {
+        return Err(format!("Request body over {} bytes", MAX_BODY_BYTES));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    let body = String::from_utf8(body).map_err(|_| "Request body is not UTF-8".to_string())?;
+    let mut request = Request::new(&method, &target, &body);
+    request.headers = headers;
+    Ok(request)
+}
+
+/// Reads one line of at most `MAX_LINE_BYTES` into `line`, returning its
+/// length (0 at end of input).
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize, String> {
+    let read = Read::take(reader, MAX_LINE_BYTES as u64 + 1)
+        .read_line(line)
+        .map_err(|e| e.to_string())?;
+    if read > MAX_LINE_BYTES {
+        return Err(format!("Line over {} bytes", MAX_LINE_BYTES));
+    }
+    Ok(read)
+}
+
+/// Writes `body` as a complete JSON response and closes the exchange.
+pub fn write_json(writer: &mut impl Write, status: u16, body: &Value) -> std::io::Result<()> {
+    let body = body.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+/// Starts a `text/event-stream` response; frames follow until the connection closes.
+pub fn write_sse_headers(writer: &mut impl Write) -> std::io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n"
+    )?;
+    writer.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_request_keeps_headers_and_refuses_oversized_ones() {
+        let raw = "POST /runs?x=1 HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: 2\r\n\r\n{}";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.path, "/runs");
+        assert_eq!(request.query("x"), Some("1"));
+        assert_eq!(request.header("HOST"), Some("localhost"));
+        assert!(request.is_json());
+        assert_eq!(request.body, "{}");
+
+        let long = format!(
+            "GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n",
+            "a".repeat(MAX_LINE_BYTES)
+        );
+        assert!(
+            read_request(&mut long.as_bytes())
+                .unwrap_err()
+                .contains("Line over")
+        );
+        let many = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-A: b\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert!(
+            read_request(&mut many.as_bytes())
+                .unwrap_err()
+                .contains("headers")
+        );
+        let malformed = "GET / HTTP/1.1\r\nno colon\r\n\r\n";
+        assert!(read_request(&mut malformed.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,499 @@
+// src/server/mod.rs
+
+pub mod http;
+
+use crate::agent::run_loop::prepare_goal;
+use crate::config::RuntimeConfig;
+use crate::events::{AgentEvent, sse};
+use crate::policy::user::UserInterface;
+use crate::queue::{JobId, JobQueue, JobStatus};
+use crate::report::RunReport;
+use crate::tools::cancel::{CancellationToken, POLL_INTERVAL};
+use http::Request;
+use serde_json::{Value, json};
+use std::io::{BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a run waits for an approval or answer before giving up on it
+/// (the step is then refused or unanswered).
+pub const PROMPT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How often an idle event stream sends a keepalive comment.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Runs goals submitted over HTTP, for web UIs and other services
+/// (`agentic serve`). Each run gets its own agent from the config, on its
+/// turn in a `JobQueue`.
+///
+/// | Method | Path | |
+/// |---|---|---|
+/// | `POST` | `/runs` | `{"goal": "..."}`; starts a run, returns its id |
+/// | `GET` | `/runs` | Every run with its status |
+/// | `GET` | `/runs/<id>` | Status and pending approvals or questions |
+/// | `GET` | `/runs/<id>/events?since=<n>` | `AgentEvent`s from the `n`-th on |
+/// | `GET` | `/runs/<id>/stream?since=<n>` | The same, live, as Server-Sent Events |
+/// | `POST` | `/runs/<id>/approvals/<prompt>` | `{"approved": true}` |
+/// | `POST` | `/runs/<id>/answers/<prompt>` | `{"answer": "..."}` |
+/// | `POST` | `/runs/<id>/cancel` | Cancels the run |
+/// | `GET` | `/runs/<id>/report` | The `RunReport` JSON, once finished |
+///
+/// Steps the approval policy would prompt for, and `AskUser` questions, wait
+/// as pending prompts until answered (or `PROMPT_TIMEOUT`).
+///
+/// `POST`s must be `Content-Type: application/json` and, if they carry an
+/// `Origin`, come from the server's own, so web pages cannot start runs or
+/// approve steps. There is no authentication otherwise: bind to a loopback
+/// address or put a proxy in front.
+#[derive(Clone)]
+pub struct Server {
+    config: RuntimeConfig,
+    queue: JobQueue,
+    runs: Arc<Mutex<Vec<Arc<ServedRun>>>>,
+}
+
+impl Server {
+    /// Runs go through a `JobQueue` sized and timed by the config's `[queue]`.
+    pub fn new(config: RuntimeConfig) -> Self {
+        Self {
+            queue: JobQueue::new(config.queue.workers),
+            config,
+            runs: Arc::default(),
+        }
+    }
+
+    /// Accepts connections on `addr` (e.g. `127.0.0.1:8080`) until the
+    /// listener fails, one thread per connection.
+    pub fn serve(&self, addr: &str) -> Result<(), String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("{}: {}", addr, e))?;
+        for stream in listener.incoming() {
+            let stream = stream.map_err(|e| e.to_string())?;
+            let server = self.clone();
+            thread::spawn(move || server.handle_connection(stream));
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, stream: TcpStream) {
+        let Ok(reader) = stream.try_clone() else {
+            return;
+        };
+        let mut stream = stream;
+        let (status, body) = match http::read_request(&mut BufReader::new(reader)) {
+            Ok(request) => match (request.method.as_str(), request.segments().as_slice()) {
+                ("GET", ["runs", id, "stream"]) if self.run(id).is_some() => {
+                    let since = request.query("since").and_then(|n| n.parse().ok());
+                    let run = self.run(id).unwrap();
+                    // A failed write means the client went away.
+                    let _ = run.stream(&mut stream, since.unwrap_or(0));
+                    return;
+                }
+                _ => self.handle(&request),
+            },
+            Err(e) => (400, json!({ "error": e })),
+        };
+        // The client may already have gone; there is nobody to tell.
+        let _ = http::write_json(&mut stream, status, &body);
+    }
+
+    /// The status code and JSON body answering `request`.
+    pub fn handle(&self, request: &Request) -> (u16, Value) {
+        if request.method == "POST"
+            && let Err((status, error)) = check_post(request)
+        {
+            return (status, json!({ "error": error }));
+        }
+        let result = match (request.method.as_str(), request.segments().as_slice()) {
+            ("GET", ["runs"]) => Ok(json!(
+                self.runs()
+                    .iter()
+                    .map(|run| run.status())
+                    .collect::<Vec<_>>()
+            )),
+            ("POST", ["runs"]) => self.submit(request),
+            (method, ["runs", id, rest @ ..]) => match self.run(id) {
+                Some(run) => route_run(&self.queue, &run, method, rest, request),
+                None => Err((404, format!("No run '{}'", id))),
+            },
+            _ => Err((
+                404,
+                format!("No route for {} {}", request.method, request.path),
+            )),
+        };
+        match result {
+            Ok(body) if request.method == "POST" && request.path == "/runs" => (201, body),
+            Ok(body) => (200, body),
+            Err((status, error)) => (status, json!({ "error": error })),
+        }
+    }
+
+    fn submit(&self, request: &Request) -> Result<Value, (u16, String)> {
+        let body = request.json().map_err(|e| (400, e))?;
+        let goal = body
+            .get("goal")
+            .and_then(Value::as_str)
+            .filter(|goal| !goal.trim().is_empty())
+            .ok_or((400, "Expected {\"goal\": \"...\"}".to_string()))?;
+
+        let mut runs = self.runs.lock().unwrap();
+        let run = Arc::new(ServedRun::new(&format!("run-{}", runs.len() + 1), goal));
+        let (config, worker) = (self.config.clone(), run.clone());
+        let job = self
+            .queue
+            .submit(goal, self.config.queue.job_timeout(), move |cancel| {
+                worker.execute(&config, cancel)
+            });
+        run.state.lock().unwrap().job = job;
+        runs.push(run.clone());
+        Ok(json!({ "id": run.id }))
+    }
+
+    fn runs(&self) -> Vec<Arc<ServedRun>> {
+        self.runs.lock().unwrap().clone()
+    }
+
+    fn run(&self, id: &str) -> Option<Arc<ServedRun>> {
+        self.runs().into_iter().find(|run| run.id == id)
+    }
+}
+
+/// Refuses `POST`s a browser could send from another site: anything but JSON
+/// (which needs a CORS preflight this server never grants), and requests from
+/// a foreign `Origin`.
+fn check_post(request: &Request) -> Result<(), (u16, String)> {
+    if !request.is_json() {
+        return Err((415, "Expected Content-Type: application/json".into()));
+    }
+    match (request.header("origin"), request.header("host")) {
+        (None, _) => Ok(()),
+        (Some(origin), Some(host))
+            if origin
+                .split_once("://")
+                .is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(host)) =>
+        {
+            Ok(())
+        }
+        (Some(origin), _) => Err((403, format!("Origin {} not allowed", origin))),
+    }
+}
+
+fn route_run(
+    queue: &JobQueue,
+    run: &ServedRun,
+    method: &str,
+    rest: &[&str],
+    request: &Request,
+) -> Result<Value, (u16, String)> {
+    match (method, rest) {
+        ("GET", []) => Ok(run.status()),
+        ("GET", ["events"]) => {
+            let since = request
+                .query("since")
+                .map(|n| n.parse::<usize>())
+                .transpose()
+                .map_err(|_| (400, "since must be a number".to_string()))?
+                .unwrap_or(0);
+            Ok(run.events_since(since))
+        }
+        ("GET", ["report"]) => {
+            let state = run.state.lock().unwrap();
+            match &state.report {
+                Some(report) => Ok(report.to_json()),
+                None => Err((409, format!("Run '{}' is still running", run.id))),
+            }
+        }
+        ("POST", ["cancel"]) => {
+            run.cancel(queue);
+            Ok(run.status())
+        }
+        ("POST", [kind @ ("approvals" | "answers"), prompt]) => {
+            let body = request.json().map_err(|e| (400, e))?;
+            let reply = match *kind {
+                "approvals" => body
+                    .get("approved")
+                    .and_then(Value::as_bool)
+                    .map(Reply::Approved)
+                    .ok_or((400, "Expected {\"approved\": bool}".to_string()))?,
+                _ => body
+                    .get("answer")
+                    .and_then(Value::as_str)
+                    .map(|answer| Reply::Answer(answer.to_string()))
+                    .ok_or((400, "Expected {\"answer\": \"...\"}".to_string()))?,
+            };
+            let prompt = prompt
+                .parse()
+                .map_err(|_| (404, format!("No prompt '{}'", prompt)))?;
+            run.reply(prompt, reply)?;
+            Ok(run.status())
+        }
+        ("GET" | "POST", _) => Err((404, format!("No route for {} {}", method, request.path))),
+        _ => Err((405, format!("Method {} not allowed", method))),
+    }
+}
+
+/// A submitted goal and what is known of its run so far.
+struct ServedRun {
+    id: String,
+    goal: String,
+    state: Mutex<RunState>,
+    /// Notified whenever `state` gains events or prompts, or finishes.
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct RunState {
+    job: JobId,
+    /// Set once the queue starts the run; the token it runs under.
+    started: Option<CancellationToken>,
+    events: Vec<Value>,
+    prompts: Vec<Prompt>,
+    next_prompt: usize,
+    report: Option<RunReport>,
+}
+
+/// An approval or question the run is blocked on.
+struct Prompt {
+    id: usize,
+    details: Value,
+    reply: Sender<Reply>,
+}
+
+impl Prompt {
+    fn to_json(&self) -> Value {
+        let mut details = self.details.clone();
+        details["id"] = json!(self.id);
+        details
+    }
+}
+
+enum Reply {
+    Approved(bool),
+    Answer(String),
+}
+
+impl ServedRun {
+    fn new(id: &str, goal: &str) -> Self {
+        Self {
+            id: id.into(),
+            goal: goal.into(),
+            state: Mutex::default(),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Builds the agent, hooks its events and prompts up to this run, and
+    /// runs it to the end under the queue's `cancel` token. Config errors
+    /// finish the run with an aborted report.
+    fn execute(self: Arc<Self>, config: &RuntimeConfig, cancel: CancellationToken) -> RunReport {
+        self.state.lock().unwrap().started = Some(cancel.clone());
+        self.changed.notify_all();
+        let report = match prepare_goal(&self.goal, config) {
+            Ok((mut agent, policy)) => {
+                agent.context.set_cancel_token(cancel);
+                let events = self.clone();
+                agent
+                    .context
+                    .events
+                    .subscribe(move |event: &AgentEvent| events.push_event(event));
+                agent.context.user = Arc::new(RemoteUser { run: self.clone() });
+                agent.run(&policy)
+            }
+            Err(e) => {
+                let mut report = RunReport::new(&self.goal);
+                report.aborted = Some(format!("Invalid runtime config: {}", e));
+                report
+            }
+        };
+        self.finish(report.clone());
+        report
+    }
+
+    fn finish(&self, report: RunReport) {
+        self.state.lock().unwrap().report = Some(report);
+        self.changed.notify_all();
+    }
+
+    /// Cancels the run, or drops it from the queue if it has not started.
+    fn cancel(&self, queue: &JobQueue) {
+        let job = self.state.lock().unwrap().job;
+        queue.cancel(job);
+        if queue
+            .status(job)
+            .is_some_and(|job| job.status == JobStatus::Cancelled)
+            && self.state.lock().unwrap().started.is_none()
+        {
+            let mut report = RunReport::new(&self.goal);
+            report.aborted = Some("Cancelled before it started".into());
+            self.finish(report);
+        }
+    }
+
+    fn push_event(&self, event: &AgentEvent) {
+        let event = serde_json::to_value(event).unwrap_or_default();
+        self.state.lock().unwrap().events.push(event);
+        self.changed.notify_all();
+    }
+
+    /// Writes events from the `since`-th on as Server-Sent Events, then each
+    /// new event or prompt as it happens, until the run finishes (a final
+    /// `finished` frame with its status) or the client goes away.
+    fn stream(&self, writer: &mut impl Write, mut since: usize) -> std::io::Result<()> {
+        http::write_sse_headers(writer)?;
+        let mut prompted = 0;
+        loop {
+            let (frames, finished) = {
+                let state = self.state.lock().unwrap();
+                let (state, _) = self
+                    .changed
+                    .wait_timeout_while(state, KEEPALIVE_INTERVAL, |state| {
+                        state.events.len() <= since
+                            && state.prompts.iter().all(|p| p.id <= prompted)
+                            && state.report.is_none()
+                    })
+                    .unwrap();
+                let mut frames = String::new();
+                for (id, event) in state.events.iter().enumerate().skip(since) {
+                    frames.push_str(&sse::frame(sse::event_name(event), Some(id), event));
+                }
+                since = state.events.len();
+                let seen = prompted;
+                for prompt in state.prompts.iter().filter(|p| p.id > seen) {
+                    frames.push_str(&sse::frame("prompt", None, &prompt.to_json()));
+                    prompted = prompt.id;
+                }
+                (frames, state.report.is_some())
+            };
+            if finished {
+                let status = self.status();
+                writer.write_all(frames.as_bytes())?;
+                writer.write_all(sse::frame("finished", None, &status).as_bytes())?;
+                return writer.flush();
+            }
+            // An SSE comment, so dead connections are noticed while the run is idle.
+            if frames.is_empty() {
+                writer.write_all(b": keepalive\n\n")?;
+            }
+            writer.write_all(frames.as_bytes())?;
+            writer.flush()?;
+        }
+    }
+
+    fn events_since(&self, since: usize) -> Value {
+        let state = self.state.lock().unwrap();
+        json!({
+            "events": state.events.iter().skip(since).collect::<Vec<_>>(),
+            "next": state.events.len(),
+        })
+    }
+
+    fn status(&self) -> Value {
+        let state = self.state.lock().unwrap();
+        let cancel = state.started.as_ref();
+        let status = match &state.report {
+            Some(report) if report.success() => "succeeded",
+            Some(_) if cancel.is_some_and(CancellationToken::timed_out) => "timed_out",
+            Some(_) if cancel.is_none_or(CancellationToken::is_cancelled) => "cancelled",
+            Some(_) => "failed",
+            None if cancel.is_none() => "queued",
+            None if cancel.is_some_and(CancellationToken::is_cancelled) => "cancelling",
+            None => "running",
+        };
+        let pending = state
+            .prompts
+            .iter()
+            .map(Prompt::to_json)
+            .collect::<Vec<_>>();
+        json!({
+            "id": self.id,
+            "goal": self.goal,
+            "status": status,
+            "events": state.events.len(),
+            "pending": pending,
+        })
+    }
+
+    /// Blocks until prompt `details` is replied to, the run is cancelled, or
+    /// `PROMPT_TIMEOUT` passes.
+    fn wait_for_reply(&self, details: Value) -> Option<Reply> {
+        let (sender, receiver) = mpsc::channel();
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            state.next_prompt += 1;
+            let id = state.next_prompt;
+            state.prompts.push(Prompt {
+                id,
+                details,
+                reply: sender,
+            });
+            id
+        };
+        self.changed.notify_all();
+        let deadline = Instant::now() + PROMPT_TIMEOUT;
+        let reply = loop {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(reply) => break Some(reply),
+                Err(RecvTimeoutError::Disconnected) => break None,
+                Err(RecvTimeoutError::Timeout)
+                    if self.is_cancelled() || Instant::now() >= deadline =>
+                {
+                    break None;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+        };
+        self.state.lock().unwrap().prompts.retain(|p| p.id != id);
+        reply
+    }
+
+    fn is_cancelled(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .started
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    fn reply(&self, prompt: usize, reply: Reply) -> Result<(), (u16, String)> {
+        let state = self.state.lock().unwrap();
+        let pending = state
+            .prompts
+            .iter()
+            .find(|p| p.id == prompt)
+            .ok_or((404, format!("No pending prompt {}", prompt)))?;
+        let expected = pending.details["kind"].as_str().unwrap_or_default();
+        match (&reply, expected) {
+            (Reply::Approved(_), "approval") | (Reply::Answer(_), "question") => {}
+            _ => return Err((409, format!("Prompt {} is a {}", prompt, expected))),
+        }
+        // The run may have stopped waiting (timeout) between lookup and send.
+        let _ = pending.reply.send(reply);
+        Ok(())
+    }
+}
+
+/// Routes a served run's approvals and `AskUser` questions to its pending prompts.
+struct RemoteUser {
+    run: Arc<ServedRun>,
+}
+
+impl UserInterface for RemoteUser {
+    fn ask(&self, question: &str, variable: &str) -> Option<String> {
+        let details = json!({ "kind": "question", "question": question, "variable": variable });
+        match self.run.wait_for_reply(details)? {
+            Reply::Answer(answer) => Some(answer),
+            Reply::Approved(_) => None,
+        }
+    }
+
+    fn approve(&self, action: &str, reasons: &[String]) -> Option<bool> {
+        let details = json!({ "kind": "approval", "action": action, "reasons": reasons });
+        match self.run.wait_for_reply(details) {
+            Some(Reply::Approved(approved)) => Some(approved),
+            // Unanswered approvals refuse the step rather than fall back to the terminal.
+            _ => Some(false),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// src/skills/mod.rs
+
+use crate::policy::ApprovalPolicy;
+use crate::tools::ToolExample;
+#[cfg(feature = "shell-tools")]
+use crate::tools::command_template::CommandTemplateTool;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A shareable bundle of domain capabilities (e.g. "rust-dev", "k8s-ops").
+///
+/// Packs are JSON: either a file, or a directory containing `skill.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillPack {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tools: Vec<SkillTool>,
+    /// Named prompt snippets; `planner` is appended to planner instructions.
+    #[serde(default)]
+    pub prompts: HashMap<String, String>,
+    #[serde(default)]
+    pub examples: Vec<SkillExample>,
+    #[serde(default)]
+    pub policies: SkillPolicies,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillTool {
+    pub name: String,
+    pub description: String,
+    /// Shell command template; `{input}` is replaced with the quoted tool input.
+    pub command: String,
+    #[serde(default)]
+    pub input_hint: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Sample calls shown to the planner with the tool.
+    #[serde(default)]
+    pub examples: Vec<ToolExample>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillExample {
+    pub description: String,
+    pub plan: Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SkillPolicies {
+    /// Command prefixes added to the `run_command` whitelist.
+    #[serde(default)]
+    pub allowed_commands: Vec<String>,
+    #[serde(default)]
+    pub approval: Option<ApprovalPolicy>,
+}
+
+impl SkillPack {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let path = Path::new(path);
+        let file = if path.is_dir() {
+            path.join("skill.json")
+        } else {
+            path.to_path_buf()
+        };
+
+        let text = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read skill pack {}: {}", file.display(), e))?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid skill pack {}: {}", file.display(), e))
+    }
+
+    #[cfg(feature = "shell-tools")]
+    pub fn build_tools(&self) -> Vec<CommandTemplateTool> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                let mut built =
+                    CommandTemplateTool::new(&tool.name, &tool.description, &tool.command);
+                if let Some(hint) = &tool.input_hint {
+                    built.input_hint = hint.clone();
+                }
+                if !tool.tags.is_empty() {
+                    built.tags = tool.tags.clone();
+                }
+                built.tags.push(format!("skill:{}", self.name));
+                built.examples = tool.examples.clone();
+                built
+            })
+            .collect()
+    }
+
+    /// Few-shot plans rendered in the same `// description\n{json}` form planners use.
+    pub fn render_examples(&self) -> String {
+        self.examples
+            .iter()
+            .map(|ex| format!("// [{}] {}\n{}", self.name, ex.description, ex.plan))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
@@ -0,0 +1,474 @@
+// src/telemetry/mod.rs
+
+pub mod otlp;
+
+use otlp::OtlpExporter;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+/// Events and spans from this crate; anything else is treated as a dependency.
+const TARGET_PREFIX: &str = "agentic_runtime";
+
+/// `[telemetry]` settings: console verbosity and an optional OTLP collector.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    /// `off`, `error`, `warn`, `info`, `debug` or `trace`. At `debug`, every
+    /// closed span is printed with its duration.
+    pub console: String,
+    /// OTLP/HTTP collector base URL, e.g. `http://localhost:4318` (Jaeger,
+    /// Grafana Tempo, the OpenTelemetry Collector).
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            console: "info".into(),
+            otlp_endpoint: None,
+            service_name: "agentic-runtime".into(),
+        }
+    }
+}
+
+impl TelemetryConfig {
+    pub fn console_level(&self) -> Result<LevelFilter, String> {
+        self.console
+            .parse()
+            .map_err(|_| format!("Unknown telemetry.console level '{}'", self.console))
+    }
+}
+
+/// Keeps the exporter alive; dropping it flushes spans still buffered.
+pub struct TelemetryGuard {
+    exporter: Option<Arc<OtlpExporter>>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(exporter) = &self.exporter {
+            exporter.shutdown();
+        }
+    }
+}
+
+/// Installs `RuntimeSubscriber` as the global `tracing` subscriber.
+pub fn init(config: &TelemetryConfig) -> Result<TelemetryGuard, String> {
+    let mut subscriber = RuntimeSubscriber::new(config.console_level()?);
+    if cfg!(not(feature = "http")) && config.otlp_endpoint.is_some() {
+        return Err("OTLP export needs the \"http\" feature".into());
+    }
+    let exporter = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| Arc::new(OtlpExporter::new(endpoint, &config.service_name)));
+    if let Some(exporter) = &exporter {
+        subscriber = subscriber.with_exporter(exporter.clone());
+    }
+    tracing::subscriber::set_global_default(subscriber).map_err(|e| e.to_string())?;
+    Ok(TelemetryGuard { exporter })
+}
+
+/// A field value kept for console output and OTLP attributes.
+#[derive(Clone, Debug)]
+enum FieldValue {
+    Str(String),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+}
+
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Str(s) => write!(f, "{}", s),
+            FieldValue::Bool(b) => write!(f, "{}", b),
+            FieldValue::Int(i) => write!(f, "{}", i),
+            FieldValue::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Fields(Vec<(&'static str, FieldValue)>);
+
+impl Fields {
+    fn set(&mut self, name: &'static str, value: FieldValue) {
+        match self.0.iter_mut().find(|(key, _)| *key == name) {
+            Some(entry) => entry.1 = value,
+            None => self.0.push((name, value)),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&FieldValue> {
+        self.0
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// ` key=value` pairs, without the event message.
+    fn render(&self) -> String {
+        self.0
+            .iter()
+            .filter(|(key, _)| *key != "message")
+            .map(|(key, value)| format!(" {}={}", key, value))
+            .collect()
+    }
+
+    fn to_otlp(&self) -> Vec<Value> {
+        self.0
+            .iter()
+            .filter(|(key, _)| *key != "message")
+            .map(|(key, value)| {
+                let value = match value {
+                    FieldValue::Str(s) => json!({ "stringValue": s }),
+                    FieldValue::Bool(b) => json!({ "boolValue": b }),
+                    FieldValue::Int(i) => json!({ "intValue": i.to_string() }),
+                    FieldValue::Float(x) => json!({ "doubleValue": x }),
+                };
+                json!({ "key": key, "value": value })
+            })
+            .collect()
+    }
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field.name(), FieldValue::Str(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field.name(), FieldValue::Str(value.into()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field.name(), FieldValue::Bool(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field.name(), FieldValue::Int(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field.name(), FieldValue::Int(value as i64));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field.name(), FieldValue::Float(value));
+    }
+}
+
+struct SpanEvent {
+    unix_nanos: u128,
+    level: Level,
+    fields: Fields,
+}
+
+struct SpanData {
+    metadata: &'static Metadata<'static>,
+    fields: Fields,
+    parent: Option<u64>,
+    trace_id: u128,
+    otel_id: u64,
+    parent_otel_id: Option<u64>,
+    started: Instant,
+    start_unix_nanos: u128,
+    events: Vec<SpanEvent>,
+    refs: usize,
+}
+
+thread_local! {
+    /// Spans entered on this thread, innermost last.
+    static STACK: std::cell::RefCell<Vec<u64>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Minimal `tracing` subscriber: prints events (and, at `debug`, span
+/// timings) to stderr and hands finished spans to an optional OTLP exporter.
+/// Stdout stays free for command output and the MCP stdio protocol.
+pub struct RuntimeSubscriber {
+    console: LevelFilter,
+    exporter: Option<Arc<OtlpExporter>>,
+    spans: Mutex<HashMap<u64, SpanData>>,
+    next_id: AtomicU64,
+    random: RandomState,
+    /// Makes ids drawn within the same nanosecond differ.
+    salt: AtomicU64,
+}
+
+impl RuntimeSubscriber {
+    pub fn new(console: LevelFilter) -> Self {
+        Self {
+            console,
+            exporter: None,
+            spans: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            random: RandomState::new(),
+            salt: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_exporter(mut self, exporter: Arc<OtlpExporter>) -> Self {
+        self.exporter = Some(exporter);
+        self
+    }
+
+    fn random_u64(&self) -> u64 {
+        let mut hasher = self.random.build_hasher();
+        hasher.write_u64(self.salt.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u128(unix_nanos());
+        hasher.finish().max(1)
+    }
+
+    fn current(&self) -> Option<u64> {
+        STACK.with(|stack| stack.borrow().last().copied())
+    }
+
+    /// `plan > tool_call` style path of the spans enclosing `id`.
+    fn span_path(&self, spans: &HashMap<u64, SpanData>, mut id: Option<u64>) -> String {
+        let mut names = Vec::new();
+        while let Some(span) = id.and_then(|id| spans.get(&id)) {
+            names.push(span.metadata.name());
+            id = span.parent;
+        }
+        names.reverse();
+        names.join(" > ")
+    }
+
+    fn print(&self, level: Level, path: &str, text: &str) {
+        let prefix = match level {
+            Level::ERROR => "❌ ",
+            Level::WARN => "⚠️ ",
+            Level::INFO => "",
+            _ => "· ",
+        };
+        let scope = if path.is_empty() || level <= Level::INFO {
+            String::new()
+        } else {
+            format!("[{}] ", path)
+        };
+        let _ = writeln!(std::io::stderr(), "{}{}{}", prefix, scope, text);
+    }
+
+    fn close(&self, data: SpanData, path: &str) {
+        let elapsed = data.started.elapsed();
+        if self.console >= Level::DEBUG {
+            self.print(
+                Level::DEBUG,
+                path,
+                &format!(
+                    "⏱️ {}{} ({:.1} ms)",
+                    data.metadata.name(),
+                    data.fields.render(),
+                    elapsed.as_secs_f64() * 1000.0
+                ),
+            );
+        }
+        let Some(exporter) = &self.exporter else {
+            return;
+        };
+
+        // A span failed if it recorded `success = false` or an `error` field.
+        let failed = matches!(data.fields.get("success"), Some(FieldValue::Bool(false)))
+            || data.fields.get("error").is_some();
+        let mut span = json!({
+            "traceId": format!("{:032x}", data.trace_id),
+            "spanId": format!("{:016x}", data.otel_id),
+            "name": data.metadata.name(),
+            "kind": 1,
+            "startTimeUnixNano": data.start_unix_nanos.to_string(),
+            "endTimeUnixNano": (data.start_unix_nanos + elapsed.as_nanos()).to_string(),
+            "attributes": data.fields.to_otlp(),
+            "events": data.events.iter().map(|event| {
+                let mut attributes = event.fields.to_otlp();
+                attributes.push(json!({
+                    "key": "level",
+                    "value": { "stringValue": event.level.as_str() }
+                }));
+                json!({
+                    "timeUnixNano": event.unix_nanos.to_string(),
+                    "name": event.fields.get("message").map(|m| m.to_string()).unwrap_or_default(),
+                    "attributes": attributes,
+                })
+            }).collect::<Vec<_>>(),
+            "status": { "code": if failed { 2 } else { 1 } },
+        });
+        if let Some(parent) = data.parent_otel_id {
+            span["parentSpanId"] = json!(format!("{:016x}", parent));
+        }
+        exporter.export(span);
+    }
+}
+
+impl Subscriber for RuntimeSubscriber {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if self.enabled(metadata) {
+            Interest::always()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // Dependencies (HTTP clients, tokio) only get through with warnings and errors.
+        if !metadata.target().starts_with(TARGET_PREFIX) {
+            return *metadata.level() <= Level::WARN && *metadata.level() <= self.console;
+        }
+        // Spans are always exported; console verbosity only filters what is printed.
+        (self.exporter.is_some() && metadata.is_span()) || *metadata.level() <= self.console
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        if self.exporter.is_some() {
+            Some(LevelFilter::TRACE)
+        } else {
+            Some(self.console)
+        }
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+
+        let parent = if attrs.is_contextual() {
+            self.current()
+        } else {
+            attrs.parent().map(Id::into_u64)
+        };
+        let mut spans = self.spans.lock().unwrap();
+        let parent_data = parent.and_then(|parent| spans.get(&parent));
+        let parent_otel_id = parent_data.map(|parent| parent.otel_id);
+        let trace_id = parent_data
+            .map(|parent| parent.trace_id)
+            .unwrap_or_else(|| ((self.random_u64() as u128) << 64) | self.random_u64() as u128);
+        spans.insert(
+            id,
+            SpanData {
+                metadata: attrs.metadata(),
+                fields,
+                parent,
+                trace_id,
+                otel_id: self.random_u64(),
+                parent_otel_id,
+                started: Instant::now(),
+                start_unix_nanos: unix_nanos(),
+                events: Vec::new(),
+                refs: 1,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(data) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(&mut data.fields);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let level = *event.metadata().level();
+        let parent = if event.is_contextual() {
+            self.current()
+        } else {
+            event.parent().map(Id::into_u64)
+        };
+
+        let mut spans = self.spans.lock().unwrap();
+        if level <= self.console {
+            let message = fields
+                .get("message")
+                .map(|m| m.to_string())
+                .unwrap_or_default();
+            let path = self.span_path(&spans, parent);
+            self.print(level, &path, &format!("{}{}", message, fields.render()));
+        }
+        if self.exporter.is_some()
+            && let Some(span) = parent.and_then(|id| spans.get_mut(&id))
+        {
+            span.events.push(SpanEvent {
+                unix_nanos: unix_nanos(),
+                level,
+                fields,
+            });
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        STACK.with(|stack| stack.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        let id = span.into_u64();
+        STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(pos) = stack.iter().rposition(|entered| *entered == id) {
+                stack.remove(pos);
+            }
+        });
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Some(data) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            data.refs += 1;
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let id = id.into_u64();
+        let closed = {
+            let mut spans = self.spans.lock().unwrap();
+            let Some(data) = spans.get_mut(&id) else {
+                return false;
+            };
+            data.refs -= 1;
+            if data.refs > 0 {
+                return false;
+            }
+            let parent = data.parent;
+            let path = self.span_path(&spans, parent);
+            spans.remove(&id).map(|data| (data, path))
+        };
+        if let Some((data, path)) = closed {
+            self.close(data, &path);
+        }
+        true
+    }
+
+    fn current_span(&self) -> Current {
+        let spans = self.spans.lock().unwrap();
+        match self
+            .current()
+            .and_then(|id| spans.get(&id).map(|data| (id, data)))
+        {
+            Some((id, data)) => Current::new(Id::from_u64(id), data.metadata),
+            None => Current::none(),
+        }
+    }
+}
+
+fn unix_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
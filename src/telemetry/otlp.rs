@@ -0,0 +1,110 @@
+// src/telemetry/otlp.rs
+
+use serde_json::{Value, json};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Spans sent per request.
+const BATCH_SIZE: usize = 64;
+/// Longest a finished span waits before its batch is sent.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Ships finished spans to an OTLP/HTTP collector (`<endpoint>/v1/traces`,
+/// JSON encoding) from a background thread, so exporting never blocks a run.
+pub struct OtlpExporter {
+    sender: Mutex<Option<Sender<Value>>>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl OtlpExporter {
+    pub fn new(endpoint: &str, service_name: &str) -> Self {
+        let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+        let service_name = service_name.to_string();
+        let (sender, receiver) = mpsc::channel::<Value>();
+
+        let worker = thread::spawn(move || {
+            let mut batch = Vec::new();
+            let mut last_flush = Instant::now();
+            loop {
+                let wait = FLUSH_INTERVAL.saturating_sub(last_flush.elapsed());
+                let closed = match receiver.recv_timeout(wait) {
+                    Ok(span) => {
+                        batch.push(span);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+                let due =
+                    closed || batch.len() >= BATCH_SIZE || last_flush.elapsed() >= FLUSH_INTERVAL;
+                if due {
+                    if !batch.is_empty() {
+                        let body = envelope(&service_name, std::mem::take(&mut batch));
+                        if let Err(e) = post(&url, &body) {
+                            eprintln!("⚠️ OTLP export to {} failed: {}", url, e);
+                        }
+                    }
+                    last_flush = Instant::now();
+                }
+                if closed {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            sender: Mutex::new(Some(sender)),
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+
+    /// Queues one span in OTLP JSON form.
+    pub fn export(&self, span: Value) {
+        if let Some(sender) = self.sender.lock().unwrap().as_ref() {
+            let _ = sender.send(span);
+        }
+    }
+
+    /// Sends whatever is still queued and stops the worker.
+    pub fn shutdown(&self) {
+        self.sender.lock().unwrap().take();
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Wraps spans in an `ExportTraceServiceRequest`.
+#[cfg(feature = "http")]
+fn post(url: &str, body: &Value) -> Result<(), String> {
+    reqwest::blocking::Client::new()
+        .post(url)
+        .json(body)
+        .send()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "http"))]
+fn post(_url: &str, _body: &Value) -> Result<(), String> {
+    Err("built without the \"http\" feature".into())
+}
+
+fn envelope(service_name: &str, spans: Vec<Value>) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{
+                    "key": "service.name",
+                    "value": { "stringValue": service_name }
+                }]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "agentic-runtime", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans
+            }]
+        }]
+    })
+}
@@ -0,0 +1,573 @@
+// src/testing/cassette.rs
+
+use crate::context::{Context, ContextHandle};
+use crate::llm::{self, LlmRequest, LlmTransport, SendFailure, SendFuture};
+use crate::protocol::SimulationOutcome;
+use crate::secrets::Secrets;
+use crate::tools::{AsyncTool, CancellationToken, Tool, ToolFuture, ToolResult, ToolSpec};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// One recorded exchange of a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Interaction {
+    /// The request body (headers, which carry API keys, are never kept) and
+    /// the provider's JSON reply.
+    Llm {
+        request: Value,
+        response: Value,
+        /// Made inside a tool call, so replaying that tool skips it.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        nested: bool,
+    },
+    /// A tool call; `args` is set for structured calls, `input` otherwise.
+    Tool {
+        tool: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        input: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        args: Option<Value>,
+        result: ToolResult,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        nested: bool,
+    },
+}
+
+impl Interaction {
+    fn nested(&self) -> bool {
+        match self {
+            Interaction::Llm { nested, .. } | Interaction::Tool { nested, .. } => *nested,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Interaction::Llm { .. } => "LLM request".into(),
+            Interaction::Tool { tool, .. } => format!("tool '{}'", tool),
+        }
+    }
+}
+
+/// Every LLM exchange and tool call of a run, in order, as saved by
+/// `CassetteRecorder` and replayed by `CassettePlayer`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read cassette {}: {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("Invalid cassette {}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize cassette: {}", e))?;
+        if let Some(parent) = std::path::Path::new(path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
+
+/// Captures a run's LLM traffic and tool I/O into a `Cassette`, so it can be
+/// replayed later against a changed planner or parser.
+///
+/// Use `transport()` on the run's `LLMTool` before building the context from
+/// it, then `record_tools` on the context. Tool inputs and results are
+/// redacted with the context's secrets. Clones share one cassette.
+#[derive(Clone, Default)]
+pub struct CassetteRecorder {
+    cassette: Arc<Mutex<Cassette>>,
+    /// Tool calls in progress; anything recorded meanwhile is nested.
+    active_tools: Arc<AtomicUsize>,
+}
+
+impl CassetteRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A transport that sends requests over HTTP and records them.
+    pub fn transport(&self) -> Arc<dyn LlmTransport> {
+        Arc::new(RecordingTransport {
+            recorder: self.clone(),
+            inner: None,
+        })
+    }
+
+    /// A transport that records requests sent through `inner`.
+    pub fn transport_over(&self, inner: Arc<dyn LlmTransport>) -> Arc<dyn LlmTransport> {
+        Arc::new(RecordingTransport {
+            recorder: self.clone(),
+            inner: Some(inner),
+        })
+    }
+
+    /// Wraps every tool registered in `context` (sync and async) so its
+    /// calls are recorded.
+    pub fn record_tools(&self, context: &mut Context) {
+        for name in context.tools.names() {
+            if let Some(inner) = context.tools.remove(&name) {
+                context.tools.register(Arc::new(RecordingTool {
+                    inner,
+                    recorder: self.clone(),
+                    secrets: context.secrets.clone(),
+                }));
+            }
+        }
+        for tool in context.async_tools.values_mut() {
+            *tool = Arc::new(RecordingTool {
+                inner: tool.clone(),
+                recorder: self.clone(),
+                secrets: context.secrets.clone(),
+            });
+        }
+    }
+
+    /// What has been recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.lock().clone()
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        self.cassette().save(path)
+    }
+
+    fn nested(&self) -> bool {
+        self.active_tools.load(Ordering::SeqCst) > 0
+    }
+
+    fn push(&self, interaction: Interaction) {
+        self.lock().interactions.push(interaction);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Cassette> {
+        self.cassette.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+struct RecordingTransport {
+    recorder: CassetteRecorder,
+    inner: Option<Arc<dyn LlmTransport>>,
+}
+
+impl RecordingTransport {
+    fn record(&self, request: &LlmRequest, sent: &Result<Value, SendFailure>, nested: bool) {
+        // Failed attempts are retried by `LLMTool`; only the reply it used matters.
+        if let Ok(response) = sent {
+            self.recorder.push(Interaction::Llm {
+                request: request.body.clone(),
+                response: response.clone(),
+                nested,
+            });
+        }
+    }
+}
+
+impl LlmTransport for RecordingTransport {
+    fn send_blocking(&self, request: &LlmRequest) -> Result<Value, SendFailure> {
+        let nested = self.recorder.nested();
+        let sent = match &self.inner {
+            Some(inner) => inner.send_blocking(request),
+            None => llm::try_send_blocking(request),
+        };
+        self.record(request, &sent, nested);
+        sent
+    }
+
+    fn send<'a>(&'a self, request: &'a LlmRequest) -> SendFuture<'a> {
+        Box::pin(async move {
+            let nested = self.recorder.nested();
+            let sent = match &self.inner {
+                Some(inner) => inner.send(request).await,
+                None => llm::try_send(request).await,
+            };
+            self.record(request, &sent, nested);
+            sent
+        })
+    }
+}
+
+struct RecordingTool<T: ?Sized> {
+    inner: Arc<T>,
+    recorder: CassetteRecorder,
+    secrets: Secrets,
+}
+
+impl<T: ?Sized> RecordingTool<T> {
+    fn record(
+        &self,
+        tool: &str,
+        input: &str,
+        args: Option<&Value>,
+        call: impl FnOnce() -> ToolResult,
+    ) -> ToolResult {
+        let nested = self.recorder.nested();
+        self.recorder.active_tools.fetch_add(1, Ordering::SeqCst);
+        let result = call();
+        self.recorder.active_tools.fetch_sub(1, Ordering::SeqCst);
+
+        self.recorder.push(Interaction::Tool {
+            tool: tool.into(),
+            input: self.secrets.redact(input),
+            args: args.map(|args| self.secrets.redact_args(args)),
+            result: self.secrets.redact_result(result.clone()),
+            nested,
+        });
+        result
+    }
+}
+
+impl Tool for RecordingTool<dyn Tool + Send + Sync> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.record(self.name(), input, None, || self.inner.execute(input))
+    }
+
+    fn execute_cancellable(&self, input: &str, cancel: &CancellationToken) -> ToolResult {
+        self.record(self.name(), input, None, || {
+            self.inner.execute_cancellable(input, cancel)
+        })
+    }
+
+    fn execute_with(&self, input: &str, context: &ContextHandle) -> ToolResult {
+        self.record(self.name(), input, None, || {
+            self.inner.execute_with(input, context)
+        })
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        self.record(self.name(), "", Some(args), || {
+            self.inner.execute_args(args)
+        })
+    }
+
+    fn execute_args_with(&self, args: &Value, context: &ContextHandle) -> ToolResult {
+        self.record(self.name(), "", Some(args), || {
+            self.inner.execute_args_with(args, context)
+        })
+    }
+
+    /// Background processes are not recorded; the wrapped tool starts them.
+    fn background_command(&self, input: &str, args: Option<&Value>) -> Result<Command, String> {
+        self.inner.background_command(input, args)
+    }
+
+    fn simulate(&self, input: &str) -> ToolResult {
+        self.inner.simulate(input)
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        self.inner.preview(input)
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.inner.preview_args(args)
+    }
+
+    fn spec(&self) -> ToolSpec {
+        self.inner.spec()
+    }
+}
+
+impl AsyncTool for RecordingTool<dyn AsyncTool> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn execute_async<'a>(&'a self, input: &'a str) -> ToolFuture<'a> {
+        Box::pin(async move {
+            let nested = self.recorder.nested();
+            self.recorder.active_tools.fetch_add(1, Ordering::SeqCst);
+            let result = self.inner.execute_async(input).await;
+            self.recorder.active_tools.fetch_sub(1, Ordering::SeqCst);
+
+            self.recorder.push(Interaction::Tool {
+                tool: self.name().into(),
+                input: self.secrets.redact(input),
+                args: None,
+                result: self.secrets.redact_result(result.clone()),
+                nested,
+            });
+            result
+        })
+    }
+}
+
+/// Replays a `Cassette` in place of the LLM and the tools, so a run can be
+/// repeated offline and deterministically.
+///
+/// Each request is answered by the first unused recorded interaction that
+/// matches it exactly. When none does (the planner or parser changed what it
+/// sends), the next unused interaction of the same kind answers instead and
+/// the difference is listed in `mismatches()`. Clones share playback state.
+#[derive(Clone)]
+pub struct CassettePlayer {
+    state: Arc<Mutex<Playback>>,
+}
+
+struct Playback {
+    interactions: Vec<Interaction>,
+    used: Vec<bool>,
+    mismatches: Vec<String>,
+}
+
+impl CassettePlayer {
+    pub fn new(cassette: Cassette) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        Self {
+            state: Arc::new(Mutex::new(Playback {
+                interactions: cassette.interactions,
+                used,
+                mismatches: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        Cassette::load(path).map(Self::new)
+    }
+
+    /// A transport answering LLM requests from the cassette.
+    pub fn transport(&self) -> Arc<dyn LlmTransport> {
+        Arc::new(self.clone())
+    }
+
+    /// Replaces every tool registered in `context` (sync and async) with
+    /// playback of its recorded calls. Specs and previews still come from
+    /// the real tools, so planning and simulation are unchanged.
+    pub fn replay_tools(&self, context: &mut Context) {
+        for name in context.tools.names() {
+            if let Some(inner) = context.tools.remove(&name) {
+                context.tools.register(Arc::new(PlaybackTool {
+                    inner,
+                    player: self.clone(),
+                    secrets: context.secrets.clone(),
+                }));
+            }
+        }
+        for tool in context.async_tools.values_mut() {
+            *tool = Arc::new(PlaybackTool {
+                inner: tool.clone(),
+                player: self.clone(),
+                secrets: context.secrets.clone(),
+            });
+        }
+    }
+
+    /// Requests that did not match their recording, in order.
+    pub fn mismatches(&self) -> Vec<String> {
+        self.lock().mismatches.clone()
+    }
+
+    /// Recorded top-level interactions the run never asked for.
+    pub fn unused(&self) -> Vec<Interaction> {
+        let state = self.lock();
+        state
+            .interactions
+            .iter()
+            .zip(&state.used)
+            .filter(|(interaction, used)| !**used && !interaction.nested())
+            .map(|(interaction, _)| interaction.clone())
+            .collect()
+    }
+
+    /// Fails with every mismatch and unused interaction, if there are any.
+    pub fn verify(&self) -> Result<(), String> {
+        let mut problems = self.mismatches();
+        problems.extend(
+            self.unused().iter().map(|interaction| {
+                format!("Recorded {} was never replayed", interaction.describe())
+            }),
+        );
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Playback diverged from the cassette:\n- {}",
+                problems.join("\n- ")
+            ))
+        }
+    }
+
+    fn take_llm(&self, request: &Value) -> Result<Value, SendFailure> {
+        let mut state = self.lock();
+        let found = state.take(
+            |interaction| matches!(interaction, Interaction::Llm { request: recorded, .. } if recorded == request),
+            |interaction| matches!(interaction, Interaction::Llm { .. }),
+        );
+        match found {
+            Some((index, exact)) => {
+                if !exact {
+                    state.mismatches.push(format!(
+                        "LLM request differs from recorded interaction #{}",
+                        index + 1
+                    ));
+                }
+                match &state.interactions[index] {
+                    Interaction::Llm { response, .. } => Ok(response.clone()),
+                    Interaction::Tool { .. } => unreachable!("matched an LLM interaction"),
+                }
+            }
+            None => {
+                state
+                    .mismatches
+                    .push("LLM request beyond the end of the cassette".into());
+                Err(SendFailure::fatal(
+                    "Cassette has no recorded LLM response left".into(),
+                ))
+            }
+        }
+    }
+
+    fn take_tool(&self, tool: &str, input: &str, args: Option<&Value>) -> ToolResult {
+        let mut state = self.lock();
+        let found = state.take(
+            |interaction| {
+                matches!(interaction, Interaction::Tool { tool: t, input: i, args: a, .. }
+                    if t == tool && i == input && a.as_ref() == args)
+            },
+            |interaction| matches!(interaction, Interaction::Tool { tool: t, .. } if t == tool),
+        );
+        match found {
+            Some((index, exact)) => {
+                let Interaction::Tool {
+                    input: recorded,
+                    result,
+                    ..
+                } = &state.interactions[index]
+                else {
+                    unreachable!("matched a tool interaction")
+                };
+                let result = result.clone();
+                if !exact {
+                    let mismatch = format!(
+                        "Tool '{}' called with '{}', recorded '{}' (interaction #{})",
+                        tool,
+                        args.map_or_else(|| input.to_string(), Value::to_string),
+                        recorded,
+                        index + 1
+                    );
+                    state.mismatches.push(mismatch);
+                }
+                result
+            }
+            None => {
+                state
+                    .mismatches
+                    .push(format!("Tool '{}' called beyond its recorded calls", tool));
+                ToolResult::failure(&format!("Cassette has no recorded call of '{}' left", tool))
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Playback> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Playback {
+    /// Marks the first unused interaction matching `exact`, else the first
+    /// unused one matching `similar`; returns its index and which it was.
+    /// Nested interactions are skipped: with every tool replayed, the calls
+    /// they came from never happen.
+    fn take(
+        &mut self,
+        exact: impl Fn(&Interaction) -> bool,
+        similar: impl Fn(&Interaction) -> bool,
+    ) -> Option<(usize, bool)> {
+        let unused = |index: &usize| !self.used[*index] && !self.interactions[*index].nested();
+        let found = (0..self.interactions.len())
+            .filter(unused)
+            .find(|&index| exact(&self.interactions[index]))
+            .map(|index| (index, true))
+            .or_else(|| {
+                (0..self.interactions.len())
+                    .filter(unused)
+                    .find(|&index| similar(&self.interactions[index]))
+                    .map(|index| (index, false))
+            });
+        if let Some((index, _)) = found {
+            self.used[index] = true;
+        }
+        found
+    }
+}
+
+impl LlmTransport for CassettePlayer {
+    fn send_blocking(&self, request: &LlmRequest) -> Result<Value, SendFailure> {
+        self.take_llm(&request.body)
+    }
+}
+
+struct PlaybackTool<T: ?Sized> {
+    inner: Arc<T>,
+    player: CassettePlayer,
+    secrets: Secrets,
+}
+
+impl Tool for PlaybackTool<dyn Tool + Send + Sync> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.player
+            .take_tool(self.name(), &self.secrets.redact(input), None)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        self.player
+            .take_tool(self.name(), "", Some(&self.secrets.redact_args(args)))
+    }
+
+    fn simulate(&self, input: &str) -> ToolResult {
+        self.inner.simulate(input)
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        self.inner.preview(input)
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.inner.preview_args(args)
+    }
+
+    fn spec(&self) -> ToolSpec {
+        self.inner.spec()
+    }
+}
+
+impl AsyncTool for PlaybackTool<dyn AsyncTool> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn execute_async<'a>(&'a self, input: &'a str) -> ToolFuture<'a> {
+        let result = self
+            .player
+            .take_tool(self.name(), &self.secrets.redact(input), None);
+        Box::pin(async move { result })
+    }
+}
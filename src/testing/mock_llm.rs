@@ -0,0 +1,178 @@
+// src/testing/mock_llm.rs
+
+use crate::llm::{
+    FunctionCall, FunctionDef, LlmProvider, LlmRequest, LlmTransport, RetryPolicy, SendFailure,
+    json_repair,
+};
+use crate::protocol::SimulationOutcome;
+use crate::tools::llm::LLMTool;
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// An LLM with canned replies chosen by matching the prompt, so planners and
+/// agent loops can be tested without a model server.
+///
+/// `llm()` builds an `LLMTool` backed by the mock, usable anywhere a real
+/// one is (planners, verifiers, critics); the mock also registers directly
+/// as the `llm` tool. Clones share the replies and the prompt log.
+#[derive(Clone, Default)]
+pub struct MockLLMTool {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Default)]
+struct MockState {
+    rules: Vec<Rule>,
+    fallback: Option<String>,
+    prompts: Vec<String>,
+}
+
+struct Rule {
+    pattern: String,
+    reply: String,
+    /// Matches left for `once` rules; `None` matches forever.
+    remaining: Option<usize>,
+}
+
+impl MockLLMTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replies `reply` to every prompt containing `pattern`. Rules are tried
+    /// in the order they were added.
+    pub fn when(self, pattern: &str, reply: &str) -> Self {
+        self.add_rule(pattern, reply, None)
+    }
+
+    /// Like `when`, but only for the next matching prompt.
+    pub fn once(self, pattern: &str, reply: &str) -> Self {
+        self.add_rule(pattern, reply, Some(1))
+    }
+
+    /// The reply to prompts no rule matches; without one they fail.
+    pub fn otherwise(self, reply: &str) -> Self {
+        self.lock().fallback = Some(reply.into());
+        self
+    }
+
+    /// An `LLMTool` answered by this mock (without retries).
+    pub fn llm(&self) -> LLMTool {
+        LLMTool::with_provider(Arc::new(self.clone()))
+            .with_transport(Arc::new(self.clone()))
+            .with_retry(RetryPolicy::none())
+    }
+
+    /// Every prompt received so far, in order.
+    pub fn prompts(&self) -> Vec<String> {
+        self.lock().prompts.clone()
+    }
+
+    pub fn calls(&self) -> usize {
+        self.lock().prompts.len()
+    }
+
+    fn add_rule(self, pattern: &str, reply: &str, remaining: Option<usize>) -> Self {
+        self.lock().rules.push(Rule {
+            pattern: pattern.into(),
+            reply: reply.into(),
+            remaining,
+        });
+        self
+    }
+
+    fn reply(&self, prompt: &str) -> Result<String, String> {
+        let mut state = self.lock();
+        state.prompts.push(prompt.to_string());
+        let rule = state
+            .rules
+            .iter_mut()
+            .find(|rule| rule.remaining != Some(0) && prompt.contains(&rule.pattern));
+        if let Some(rule) = rule {
+            if let Some(remaining) = &mut rule.remaining {
+                *remaining -= 1;
+            }
+            return Ok(rule.reply.clone());
+        }
+        state.fallback.clone().ok_or_else(|| {
+            let start: String = prompt.chars().take(200).collect();
+            format!("MockLLMTool has no reply for prompt: {}", start)
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl LlmProvider for MockLLMTool {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn model(&self) -> &str {
+        "mock"
+    }
+
+    fn request(&self, prompt: &str) -> LlmRequest {
+        LlmRequest {
+            url: "mock://llm".into(),
+            headers: Vec::new(),
+            body: json!({ "prompt": prompt }),
+        }
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<String, String> {
+        json.get("response")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| "Mock response missing 'response' field".into())
+    }
+
+    /// The reply is then a JSON array of `{"name", "arguments"}` calls.
+    fn function_request(&self, prompt: &str, functions: &[FunctionDef]) -> Option<LlmRequest> {
+        let mut request = self.request(prompt);
+        request.body["functions"] = json!(functions);
+        Some(request)
+    }
+
+    fn parse_function_calls(&self, json: &Value) -> Result<Vec<FunctionCall>, String> {
+        json_repair::parse_json(&self.parse_response(json)?)
+    }
+}
+
+impl LlmTransport for MockLLMTool {
+    fn send_blocking(&self, request: &LlmRequest) -> Result<Value, SendFailure> {
+        let prompt = request
+            .body
+            .get("prompt")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        self.reply(prompt)
+            .map(|reply| json!({ "response": reply }))
+            .map_err(SendFailure::fatal)
+    }
+}
+
+impl Tool for MockLLMTool {
+    fn name(&self) -> &str {
+        "llm"
+    }
+
+    fn description(&self) -> &str {
+        "Mock LLM returning canned responses."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.llm().execute(input)
+    }
+
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only("would prompt the mock LLM")
+    }
+
+    fn spec(&self) -> ToolSpec {
+        self.llm().spec()
+    }
+}
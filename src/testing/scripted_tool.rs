@@ -0,0 +1,118 @@
+// src/testing/scripted_tool.rs
+
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A tool whose results are scripted in advance, for deterministic runs.
+///
+/// A call returns the result of the first `when` rule its input matches,
+/// else the next queued result (`succeeds`, `fails`, `then`), else the
+/// `otherwise` result; with none of those it fails. Clones share the script
+/// and the call log, so a test can keep a handle to a registered tool.
+#[derive(Clone)]
+pub struct ScriptedTool {
+    name: String,
+    description: String,
+    script: Arc<Mutex<Script>>,
+}
+
+#[derive(Default)]
+struct Script {
+    rules: Vec<(String, ToolResult)>,
+    queue: VecDeque<ToolResult>,
+    fallback: Option<ToolResult>,
+    calls: Vec<String>,
+}
+
+impl ScriptedTool {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            description: format!("Scripted test double for '{}'.", name),
+            script: Arc::default(),
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Queues a successful call returning `output`.
+    pub fn succeeds(self, output: &str) -> Self {
+        self.then(ToolResult::success(output))
+    }
+
+    /// Queues a failed call with `error`.
+    pub fn fails(self, error: &str) -> Self {
+        self.then(ToolResult::failure(error))
+    }
+
+    /// Queues `result` for the next call no rule matches.
+    pub fn then(self, result: ToolResult) -> Self {
+        self.lock().queue.push_back(result);
+        self
+    }
+
+    /// Returns `result` whenever the input contains `pattern`.
+    pub fn when(self, pattern: &str, result: ToolResult) -> Self {
+        self.lock().rules.push((pattern.into(), result));
+        self
+    }
+
+    /// The result once rules and the queue have nothing for a call.
+    pub fn otherwise(self, result: ToolResult) -> Self {
+        self.lock().fallback = Some(result);
+        self
+    }
+
+    /// Inputs of every call so far, in order.
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Tool for ScriptedTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        let mut script = self.lock();
+        script.calls.push(input.to_string());
+        if let Some((_, result)) = script
+            .rules
+            .iter()
+            .find(|(pattern, _)| input.contains(pattern.as_str()))
+        {
+            return result.clone();
+        }
+        if let Some(result) = script.queue.pop_front() {
+            return result;
+        }
+        script.fallback.clone().unwrap_or_else(|| {
+            ToolResult::failure(&format!(
+                "ScriptedTool '{}' has no result scripted for input: {}",
+                self.name, input
+            ))
+        })
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            tags: vec!["test".into()],
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// src/tools/cache.rs
+
+use crate::tools::ToolResult;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Results of idempotent tool calls within a run, keyed by tool name and a
+/// hash of the input, so repeated read-only calls (`git status --porcelain`,
+/// an identical `llm` prompt) are answered without running again.
+///
+/// `Context::invoke_tool` caches a call when its tool's spec is `idempotent`
+/// and its preview is read-only; any other call may change what those
+/// return, so it clears the cache. Only successful results are kept. Clones
+/// share entries.
+#[derive(Clone, Debug, Default)]
+pub struct ToolCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<(String, u64), ToolResult>,
+    hits: usize,
+}
+
+impl ToolCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, tool: &str, input: &str, args: Option<&Value>) -> Option<ToolResult> {
+        let mut state = self.lock();
+        let result = state.entries.get(&key(tool, input, args)).cloned();
+        if result.is_some() {
+            state.hits += 1;
+        }
+        result
+    }
+
+    /// Stores `result` if it succeeded.
+    pub fn insert(&self, tool: &str, input: &str, args: Option<&Value>, result: &ToolResult) {
+        if result.success {
+            self.lock()
+                .entries
+                .insert(key(tool, input, args), result.clone());
+        }
+    }
+
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Calls answered from the cache so far.
+    pub fn hits(&self) -> usize {
+        self.lock().hits
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().entries.is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn key(tool: &str, input: &str, args: Option<&Value>) -> (String, u64) {
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    args.map(Value::to_string).hash(&mut hasher);
+    (tool.to_string(), hasher.finish())
+}
@@ -0,0 +1,679 @@
+// src/tools/calc.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec};
+use serde_json::{Value, json};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Significant decimals kept when printing a non-integer result.
+const DECIMALS: usize = 10;
+
+/// 2^53 - 1: beyond it, `f64` cannot hold every integer, so results would
+/// be silently rounded.
+const MAX_PRECISE: f64 = 9_007_199_254_740_991.0;
+
+/// Dates stay within this many years of year 0, and durations within twice
+/// that, so date arithmetic cannot overflow.
+const MAX_YEARS: i64 = 1_000_000;
+const MAX_DAYS: u64 = 366 * MAX_YEARS as u64;
+
+const WEEKDAYS: [&str; 7] = [
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+];
+
+/// What an expression evaluates to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quantity {
+    Number(f64),
+    /// Days since 1970-01-01.
+    Date(i64),
+    Duration {
+        months: i64,
+        days: i64,
+    },
+}
+
+impl std::fmt::Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Quantity::Number(n) => f.write_str(&format_number(n)),
+            Quantity::Date(days) => {
+                let (y, m, d) = civil_from_days(days);
+                let weekday = WEEKDAYS[days.rem_euclid(7) as usize];
+                write!(f, "{:04}-{:02}-{:02} ({})", y, m, d, weekday)
+            }
+            Quantity::Duration { months: 0, days } => write!(f, "{} days", days),
+            Quantity::Duration { months, days: 0 } => write!(f, "{} months", months),
+            Quantity::Duration { months, days } => write!(f, "{} months {} days", months, days),
+        }
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return format!("{}", n as i64);
+    }
+    let text = format!("{:.*}", DECIMALS, n);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Evaluates arithmetic (`+ - * / % ^`, parentheses, `sqrt`, `round`,
+/// `min`, `pi`, ...) and date math (`2024-03-01 + 30 days`,
+/// `2024-12-25 - today`) as written, so plans need not trust an LLM with
+/// numbers. Arithmetic is in `f64`: numbers beyond ±(2^53 - 1), where
+/// integers lose precision, and infinite results are errors rather than wrong
+/// digits.
+#[derive(Clone, Debug, Default)]
+pub struct CalcTool;
+
+impl CalcTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tool for CalcTool {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluates arithmetic (up to ±(2^53 - 1)) and date expressions, e.g. '(1200 * 0.15) / 12', 'sqrt(2)', '2024-03-01 + 30 days', '2024-12-25 - today'."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        match evaluate(input) {
+            Ok(result) => ToolResult::success(&result.to_string()),
+            Err(e) => ToolResult::failure(&format!("Cannot evaluate '{}': {}", input.trim(), e)),
+        }
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        match args["expression"].as_str().or(args.as_str()) {
+            Some(expression) => self.execute(expression),
+            None => ToolResult::failure("Missing 'expression' argument"),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("would evaluate {}", input.trim()))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.preview(
+            args["expression"]
+                .as_str()
+                .or(args.as_str())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint:
+                "Expression, e.g. '240 * 0.175', 'round(1000 / 3, 2)' or '2024-03-01 + 6 weeks'"
+                    .into(),
+            tags: vec!["math".into(), "date".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": { "expression": { "type": "string" } },
+                "required": ["expression"]
+            })),
+            timeout: None,
+            idempotent: true,
+            examples: vec![
+                ToolExample::new("round(10 / 3, 2)", "3.33"),
+                ToolExample::new("2024-12-25 - 2024-01-01", "359 days"),
+            ],
+        }
+    }
+}
+
+/// Evaluates `expression`; see `CalcTool` for what it understands.
+pub fn evaluate(expression: &str) -> Result<Quantity, String> {
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err("empty expression".into());
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.expression(0)?;
+    match parser.tokens.get(parser.pos) {
+        None => match value {
+            Quantity::Number(n) => number(n),
+            value => Ok(value),
+        },
+        Some(token) => Err(format!("unexpected {}", token)),
+    }
+}
+
+/// `n`, if it is finite and within ±(2^53 - 1).
+fn number(n: f64) -> Result<Quantity, String> {
+    if !n.is_finite() {
+        Err("result is not a finite number".into())
+    } else if n.abs() > MAX_PRECISE {
+        Err(format!(
+            "{} is beyond ±(2^53 - 1), where numbers lose precision",
+            format_number(n)
+        ))
+    } else {
+        Ok(Quantity::Number(n))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Date(i64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "'{}'", format_number(*n)),
+            Token::Date(days) => write!(f, "'{}'", Quantity::Date(*days)),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+            Token::Comma => f.write_str("','"),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            // A date: four digits, '-', two digits, '-', two digits.
+            let date: String = chars[i..chars.len().min(i + 10)].iter().collect();
+            if let Some(days) = parse_date(&date) {
+                tokens.push(Token::Date(days));
+                i += 10;
+                continue;
+            }
+            if is_date_shaped(&date) {
+                return Err(format!("invalid date '{}'", date));
+            }
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let exponent = i + 1 + usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                if chars.get(exponent).is_some_and(char::is_ascii_digit) {
+                    i = exponent;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let number: String = chars[start..i].iter().collect();
+            let value = number
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", number))?;
+            tokens.push(Token::Number(value));
+            continue;
+        }
+        if c.is_alphabetic() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            tokens.push(Token::Ident(name.to_lowercase()));
+            continue;
+        }
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                i += 1;
+                Token::Op('^')
+            }
+            '×' => Token::Op('*'),
+            '÷' => Token::Op('/'),
+            '−' => Token::Op('-'),
+            '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+            other => return Err(format!("unexpected '{}'", other)),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected {}, found {}", expected, token)),
+            None => Err(format!("expected {} at the end", expected)),
+        }
+    }
+
+    /// Operators binding at least as tightly as `min_precedence`.
+    fn expression(&mut self, min_precedence: u8) -> Result<Quantity, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek().cloned() {
+            let (precedence, right_assoc) = match op {
+                '+' | '-' => (1, false),
+                '*' | '/' | '%' => (2, false),
+                _ => (3, true),
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let right = self.expression(if right_assoc {
+                precedence
+            } else {
+                precedence + 1
+            })?;
+            left = apply(op, left, right)?;
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Quantity, String> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                // `-2^2` is -(2^2).
+                apply('*', Quantity::Number(-1.0), self.expression(3)?)
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    /// A primary followed by an optional unit, as in `30 days`.
+    fn postfix(&mut self) -> Result<Quantity, String> {
+        let value = self.primary()?;
+        let Quantity::Number(n) = value else {
+            return Ok(value);
+        };
+        if let Some(Token::Ident(unit)) = self.peek()
+            && let Some(duration) = duration(n, unit)
+        {
+            self.pos += 1;
+            return duration;
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> Result<Quantity, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Quantity::Number(n)),
+            Some(Token::Date(days)) => Ok(Quantity::Date(days)),
+            Some(Token::Open) => {
+                let value = self.expression(0)?;
+                self.expect(Token::Close)?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "pi" => Ok(Quantity::Number(std::f64::consts::PI)),
+                "e" => Ok(Quantity::Number(std::f64::consts::E)),
+                "today" => Ok(Quantity::Date(today())),
+                _ if self.peek() == Some(&Token::Open) => {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::Close) {
+                        loop {
+                            args.push(self.expression(0)?);
+                            if self.peek() != Some(&Token::Comma) {
+                                break;
+                            }
+                            self.pos += 1;
+                        }
+                    }
+                    self.expect(Token::Close)?;
+                    call(&name, &args)
+                }
+                _ => Err(format!("unknown name '{}'", name)),
+            },
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+}
+
+/// `n` of `unit` (`days`, `weeks`, `months`, `years`), if `unit` is one.
+fn duration(n: f64, unit: &str) -> Option<Result<Quantity, String>> {
+    let (months, days): (i64, i64) = match unit.trim_end_matches('s') {
+        "day" | "d" => (0, 1),
+        "week" | "w" => (0, 7),
+        "month" => (1, 0),
+        "year" | "y" => (12, 0),
+        _ => return None,
+    };
+    if n.fract() != 0.0 {
+        return Some(Err(format!(
+            "durations must be whole {}s",
+            unit.trim_end_matches('s')
+        )));
+    }
+    let n = n as i64;
+    Some(checked_duration(months.checked_mul(n), days.checked_mul(n)))
+}
+
+/// A duration of `months` and `days`, if both were computed without
+/// overflow and are within range.
+fn checked_duration(months: Option<i64>, days: Option<i64>) -> Result<Quantity, String> {
+    match (months, days) {
+        (Some(months), Some(days))
+            if months.unsigned_abs() <= 24 * MAX_YEARS as u64
+                && days.unsigned_abs() <= 2 * MAX_DAYS =>
+        {
+            Ok(Quantity::Duration { months, days })
+        }
+        _ => Err("duration out of range".into()),
+    }
+}
+
+/// `date` moved by `months`, then `days`; both are in range, so only the
+/// result needs checking.
+fn shift(date: i64, months: i64, days: i64) -> Result<Quantity, String> {
+    let date = add_months(date, months) + days;
+    if date.unsigned_abs() > MAX_DAYS {
+        return Err("date out of range".into());
+    }
+    Ok(Quantity::Date(date))
+}
+
+fn apply(op: char, left: Quantity, right: Quantity) -> Result<Quantity, String> {
+    use Quantity::{Date, Duration, Number};
+    Ok(match (op, left, right) {
+        (_, Number(a), Number(b)) => {
+            // Operands may be literals, which are not checked on their own.
+            number(a)?;
+            number(b)?;
+            number(match op {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                '/' if b == 0.0 => return Err("division by zero".into()),
+                '/' => a / b,
+                '%' if b == 0.0 => return Err("division by zero".into()),
+                '%' => a % b,
+                _ => a.powf(b),
+            })?
+        }
+        ('+', Date(date), Duration { months, days })
+        | ('+', Duration { months, days }, Date(date)) => shift(date, months, days)?,
+        ('-', Date(date), Duration { months, days }) => shift(date, -months, -days)?,
+        ('-', Date(a), Date(b)) => Duration {
+            months: 0,
+            days: a - b,
+        },
+        (
+            '+',
+            Duration {
+                months: m1,
+                days: d1,
+            },
+            Duration {
+                months: m2,
+                days: d2,
+            },
+        ) => checked_duration(m1.checked_add(m2), d1.checked_add(d2))?,
+        (
+            '-',
+            Duration {
+                months: m1,
+                days: d1,
+            },
+            Duration {
+                months: m2,
+                days: d2,
+            },
+        ) => checked_duration(m1.checked_sub(m2), d1.checked_sub(d2))?,
+        ('*', Duration { months, days }, Number(n))
+        | ('*', Number(n), Duration { months, days })
+            if n.fract() == 0.0 =>
+        {
+            let n = n as i64;
+            checked_duration(months.checked_mul(n), days.checked_mul(n))?
+        }
+        ('/', Duration { months: 0, days }, Number(n)) if n != 0.0 => Number(days as f64 / n),
+        ('/', Duration { months: 0, days: a }, Duration { months: 0, days: b }) if b != 0 => {
+            Number(a as f64 / b as f64)
+        }
+        (op, left, right) => {
+            return Err(format!(
+                "cannot apply '{}' to {} and {}",
+                op,
+                kind(&left),
+                kind(&right)
+            ));
+        }
+    })
+}
+
+fn kind(value: &Quantity) -> &'static str {
+    match value {
+        Quantity::Number(_) => "a number",
+        Quantity::Date(_) => "a date",
+        Quantity::Duration { .. } => "a duration",
+    }
+}
+
+fn call(name: &str, args: &[Quantity]) -> Result<Quantity, String> {
+    let numbers: Vec<f64> = args
+        .iter()
+        .map(|arg| match arg {
+            Quantity::Number(n) => number(*n).map(|_| *n),
+            other => Err(format!("{}() takes numbers, not {}", name, kind(other))),
+        })
+        .collect::<Result<_, _>>()?;
+    let one = || match numbers[..] {
+        [x] => Ok(x),
+        _ => Err(format!("{}() takes one argument", name)),
+    };
+    let value = match name {
+        "sqrt" => one()?.sqrt(),
+        "abs" => one()?.abs(),
+        "floor" => one()?.floor(),
+        "ceil" => one()?.ceil(),
+        "ln" => one()?.ln(),
+        "log" | "log10" => one()?.log10(),
+        "log2" => one()?.log2(),
+        "exp" => one()?.exp(),
+        "sin" => one()?.sin(),
+        "cos" => one()?.cos(),
+        "tan" => one()?.tan(),
+        "round" => match numbers[..] {
+            [x] => x.round(),
+            [x, places] => {
+                let scale = 10f64.powi(places as i32);
+                (x * scale).round() / scale
+            }
+            _ => return Err("round() takes one or two arguments".into()),
+        },
+        "pow" => match numbers[..] {
+            [x, y] => x.powf(y),
+            _ => return Err("pow() takes two arguments".into()),
+        },
+        "min" | "max" if numbers.is_empty() => {
+            return Err(format!("{}() takes at least one argument", name));
+        }
+        "min" => numbers.iter().copied().fold(f64::INFINITY, f64::min),
+        "max" => numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        _ => return Err(format!("unknown function '{}'", name)),
+    };
+    if value.is_nan() {
+        return Err(format!("{}() is undefined for these arguments", name));
+    }
+    number(value)
+}
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD` date, if `text` is a valid one.
+fn parse_date(text: &str) -> Option<i64> {
+    let bytes = text.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let year: i64 = text[..4].parse().ok()?;
+    let month: i64 = text[5..7].parse().ok()?;
+    let day: i64 = text[8..].parse().ok()?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// `YYYY-MM-DD`, whether or not it names a real day.
+fn is_date_shaped(text: &str) -> bool {
+    text.len() == 10
+        && text.bytes().enumerate().all(|(i, b)| match i {
+            4 | 7 => b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// `date` moved by `months`, keeping the day of the month where it exists
+/// and clamping to the month's last day where it does not (Jan 31 + 1 month
+/// is Feb 28 or 29).
+fn add_months(date: i64, months: i64) -> i64 {
+    if months == 0 {
+        return date;
+    }
+    let (year, month, day) = civil_from_days(date);
+    let index = year * 12 + (month - 1) + months;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) + 1);
+    days_from_civil(year, month, day.min(days_in_month(year, month)))
+}
+
+/// Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of `days_from_civil`, as `(year, month, day)`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Today's date (UTC) as days since 1970-01-01.
+fn today() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    (secs / 86_400) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::plan::{PlanValidationError, suggest_calc};
+
+    #[test]
+    fn calc_evaluates_arithmetic_and_dates() {
+        let show = |expr: &str| evaluate(expr).map(|q| q.to_string());
+        assert_eq!(show("2 + 3 * 4 ^ 2").unwrap(), "50");
+        assert_eq!(show("round(10 / 3, 2)").unwrap(), "3.33");
+        assert_eq!(
+            show("2024-01-31 + 1 month").unwrap(),
+            "2024-02-29 (Thursday)"
+        );
+        assert_eq!(show("2024-12-25 - 2024-01-01").unwrap(), "359 days");
+        assert!(show("1 / 0").unwrap_err().contains("zero"));
+        for expr in ["1e18 years", "9e18 days * 2", "2024-01-01 + 1e9 years"] {
+            assert!(show(expr).unwrap_err().contains("out of range"), "{}", expr);
+        }
+        assert_eq!(
+            show("2024-02-30 + 1 day").unwrap_err(),
+            "invalid date '2024-02-30'"
+        );
+        assert_eq!(show("9007199254740990 + 1").unwrap(), "9007199254740991");
+        for expr in [
+            "9999999999999999999999*99999999999",
+            "floor(1e300)",
+            "2^53 + 1",
+        ] {
+            assert!(show(expr).unwrap_err().contains("2^53"), "{}", expr);
+        }
+        for expr in ["2^1000000", "1e400"] {
+            assert!(show(expr).unwrap_err().contains("not a finite"), "{}", expr);
+        }
+
+        let plan = vec![
+            json!({"type": "tool", "name": "llm", "input": "What is 1234 * 5678?"}),
+            json!({"type": "tool", "name": "calc", "input": "1234 * 5678"}),
+            json!({"type": "tool", "name": "run_command", "input": "cargo test -- --test-threads 4"}),
+        ];
+        let warnings = suggest_calc(&plan);
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            matches!(&warnings[0], PlanValidationError::StyleWarning(w) if w.contains("'llm'") && w.contains("1234 * 5678"))
+        );
+    }
+}
@@ -0,0 +1,230 @@
+// src/tools/cancel.rs
+
+use crate::tools::{ToolFuture, ToolResult};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often blocked waiters re-check cancellation.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(25);
+/// How long `run_cancellable` waits for a cancelled worker to wind down.
+const CLEANUP_GRACE: Duration = Duration::from_millis(500);
+
+/// Cooperative cancellation shared between a run and the tools it starts.
+///
+/// Clones share the same flag, so a token handed to another thread (e.g. a
+/// Ctrl-C handler) can abort the whole run. Child tokens are cancelled with
+/// their parent and can carry their own deadline for per-step timeouts.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    parent: Option<Arc<CancellationToken>>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// True once cancelled explicitly, via a parent, or past the deadline.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+            || self.timed_out()
+            || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+
+    /// True only when this token's own deadline has passed.
+    pub fn timed_out(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// A token cancelled with this one, and additionally after `timeout` if set.
+    pub fn child(&self, timeout: Option<Duration>) -> Self {
+        Self {
+            flag: Arc::new(AtomicBool::new(false)),
+            parent: Some(Arc::new(self.clone())),
+            deadline: timeout.map(|t| Instant::now() + t),
+        }
+    }
+}
+
+/// Ctrl-C presses since `cancel_on_interrupt` was last called.
+static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// What `on_forced_exit` registered, run before a second Ctrl-C exits.
+type Cleanup = Box<dyn Fn() + Send>;
+static CLEANUPS: Mutex<Vec<Cleanup>> = Mutex::new(Vec::new());
+
+/// Cancels `token` when the user presses Ctrl-C, so the run stops its tool
+/// calls (killing the commands they started) and winds down with a partial
+/// report instead of dying and leaving them orphaned. A second Ctrl-C runs
+/// the `on_forced_exit` cleanups and exits with status 130. A later call
+/// moves the handler to its token and starts counting presses afresh.
+///
+/// Unix only; elsewhere Ctrl-C keeps its default behaviour.
+pub fn cancel_on_interrupt(token: CancellationToken) {
+    static TARGET: Mutex<Option<CancellationToken>> = Mutex::new(None);
+    static WATCHER: OnceLock<()> = OnceLock::new();
+
+    {
+        let mut target = TARGET.lock().unwrap_or_else(PoisonError::into_inner);
+        // Presses meant for the previous token must not cancel this one.
+        INTERRUPTS.store(0, Ordering::SeqCst);
+        *target = Some(token);
+    }
+    WATCHER.get_or_init(|| {
+        if !install_interrupt_handler() {
+            return;
+        }
+        thread::spawn(|| {
+            loop {
+                match INTERRUPTS.load(Ordering::SeqCst) {
+                    0 => {}
+                    1 => {
+                        if let Some(token) = &*TARGET.lock().unwrap_or_else(PoisonError::into_inner)
+                            && !token.is_cancelled()
+                        {
+                            tracing::warn!("Interrupted; stopping (Ctrl-C again to quit now)");
+                            token.cancel();
+                        }
+                    }
+                    _ => {
+                        for cleanup in CLEANUPS
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .iter()
+                        {
+                            cleanup();
+                        }
+                        std::process::exit(130)
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    });
+}
+
+/// Runs `cleanup` if a second Ctrl-C quits the process, which skips the
+/// destructors and wind-down that would otherwise restore the terminal and
+/// stop background processes.
+pub fn on_forced_exit(cleanup: impl Fn() + Send + 'static) {
+    CLEANUPS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(cleanup));
+}
+
+/// Whether Ctrl-C was pressed since `cancel_on_interrupt` was last called.
+pub fn interrupted() -> bool {
+    INTERRUPTS.load(Ordering::SeqCst) > 0
+}
+
+/// Routes SIGINT to a handler counting `INTERRUPTS`.
+#[cfg(unix)]
+fn install_interrupt_handler() -> bool {
+    const SIGINT: i32 = 2;
+    const SIG_ERR: usize = usize::MAX;
+    unsafe extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+    extern "C" fn on_interrupt(_signum: i32) {
+        INTERRUPTS.fetch_add(1, Ordering::SeqCst);
+    }
+    // SAFETY: `signal` is the C library's, which std links on Unix, and the
+    // handler only touches an atomic, which is async-signal-safe.
+    unsafe { signal(SIGINT, on_interrupt) != SIG_ERR }
+}
+
+#[cfg(not(unix))]
+fn install_interrupt_handler() -> bool {
+    false
+}
+
+/// Runs `work` on its own thread and waits for it until `token` is cancelled.
+///
+/// On cancellation the caller gets `ToolResult::timeout`/`cancelled` once the
+/// worker returns or, at the latest, half a second later; a worker still busy
+/// is left to finish in the background. Cancellation-aware tools should watch
+/// the token themselves (as `RunCommandTool` does) to stop what they started.
+pub fn run_cancellable<F>(
+    work: F,
+    token: &CancellationToken,
+    timeout: Option<Duration>,
+) -> ToolResult
+where
+    F: FnOnce() -> ToolResult + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+
+    loop {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Disconnected) => {
+                return ToolResult::failure("Tool panicked before returning a result");
+            }
+            Err(RecvTimeoutError::Timeout) if token.is_cancelled() => {
+                // Give a cancellation-aware worker the chance to kill what
+                // it started before the caller moves on (or exits).
+                let _ = receiver.recv_timeout(CLEANUP_GRACE);
+                if token.timed_out() {
+                    return ToolResult::timeout(timeout.unwrap_or_default());
+                }
+                return ToolResult::cancelled();
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+}
+
+/// Async counterpart of `run_cancellable` for `AsyncTool` futures.
+pub async fn run_cancellable_async(
+    mut work: ToolFuture<'_>,
+    token: &CancellationToken,
+    timeout: Option<Duration>,
+) -> ToolResult {
+    loop {
+        match tokio::time::timeout(POLL_INTERVAL, &mut work).await {
+            Ok(result) => return result,
+            Err(_) if token.timed_out() => return ToolResult::timeout(timeout.unwrap_or_default()),
+            Err(_) if token.is_cancelled() => return ToolResult::cancelled(),
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_new_interrupt_target_starts_uncancelled() {
+        let first = CancellationToken::new();
+        cancel_on_interrupt(first.clone());
+        INTERRUPTS.fetch_add(1, Ordering::SeqCst);
+        for _ in 0..100 {
+            if first.is_cancelled() {
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(first.is_cancelled());
+        assert!(interrupted());
+
+        let second = CancellationToken::new();
+        cancel_on_interrupt(second.clone());
+        assert!(!interrupted());
+        thread::sleep(POLL_INTERVAL * 3);
+        assert!(!second.is_cancelled());
+    }
+}
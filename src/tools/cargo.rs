@@ -0,0 +1,434 @@
+// src/tools/cargo.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::cancel::CancellationToken;
+use crate::tools::run_command::capture_process;
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec};
+use crate::workspace::Workspace;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::process::Command;
+use std::time::Duration;
+
+/// Subcommands `CargoTool` runs; none of them edit sources.
+const SUBCOMMANDS: [&str; 3] = ["check", "test", "clippy"];
+
+/// Diagnostics reported per call by default; the counts cover all of them.
+const DEFAULT_MAX_DIAGNOSTICS: usize = 20;
+
+/// Lines of a failing test's output kept in its `TestFailure`.
+const MAX_FAILURE_LINES: usize = 20;
+
+/// One compiler or clippy message, located at its primary span.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// `error` or `warning`.
+    pub level: String,
+    /// The error or lint code, e.g. `E0425` or `clippy::needless_return`.
+    pub code: Option<String>,
+    pub message: String,
+    pub file: String,
+    pub line: u64,
+    pub column: u64,
+    /// The compiler's `help`, with its replacement text when it offers one.
+    pub suggestion: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TestFailure {
+    pub name: String,
+    /// The test's captured output up to its backtrace, at most
+    /// `MAX_FAILURE_LINES` lines.
+    pub output: String,
+}
+
+/// Totals over every `test result:` line, plus the failing tests.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TestSummary {
+    pub passed: u64,
+    pub failed: u64,
+    pub ignored: u64,
+    pub failures: Vec<TestFailure>,
+}
+
+/// What a cargo run reported, as `CargoTool` returns it (as JSON) in its
+/// output or, when cargo failed, its error.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CargoReport {
+    pub command: String,
+    pub success: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    /// The first `max_diagnostics`, errors before warnings.
+    pub diagnostics: Vec<Diagnostic>,
+    pub tests: Option<TestSummary>,
+    /// Cargo's own error lines (e.g. a manifest that fails to parse), when
+    /// the compiler reported nothing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cargo_error: Option<String>,
+}
+
+/// Runs `cargo check`, `cargo test` or `cargo clippy` with
+/// `--message-format=json` and reports compiler diagnostics and test
+/// failures as JSON, so error analysis and replanning work from file, line
+/// and suggestion instead of the rendered text.
+#[derive(Clone, Debug)]
+pub struct CargoTool {
+    workspace: Option<Workspace>,
+    max_diagnostics: usize,
+}
+
+impl Default for CargoTool {
+    fn default() -> Self {
+        Self {
+            workspace: None,
+            max_diagnostics: DEFAULT_MAX_DIAGNOSTICS,
+        }
+    }
+}
+
+impl CargoTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs cargo in `workspace`'s root.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    pub fn with_max_diagnostics(mut self, max: usize) -> Self {
+        self.max_diagnostics = max;
+        self
+    }
+
+    fn run(&self, subcommand: &str, args: &[&str], cancel: &CancellationToken) -> ToolResult {
+        if !SUBCOMMANDS.contains(&subcommand) {
+            return ToolResult::failure(&format!(
+                "Unsupported cargo command '{}', expected check, test or clippy",
+                subcommand
+            ));
+        }
+        if let Some(arg) = args
+            .iter()
+            .find(|arg| arg.starts_with("--message-format") || **arg == "--fix")
+        {
+            return ToolResult::failure(&format!(
+                "'{}' is not supported here; use run_command for it",
+                arg
+            ));
+        }
+
+        let mut command = Command::new("cargo");
+        command
+            .arg(subcommand)
+            .arg("--message-format=json")
+            .args(args)
+            .env("RUST_BACKTRACE", "0");
+        if let Some(workspace) = &self.workspace {
+            command.current_dir(workspace.root());
+        }
+        let (status, stdout, stderr) = match capture_process(&mut command, cancel) {
+            Ok(output) => output,
+            Err(result) => return result,
+        };
+
+        let mut report = parse_cargo_output(&stdout, self.max_diagnostics);
+        report.command = format!("cargo {}", subcommand);
+        report.success = status.success();
+        if subcommand != "test" {
+            report.tests = None;
+        }
+        if !report.success && report.errors == 0 && report.tests.is_none() {
+            let errors: Vec<&str> = stderr
+                .lines()
+                .filter(|line| line.trim_start().starts_with("error"))
+                .collect();
+            report.cargo_error = Some(if errors.is_empty() {
+                stderr.trim().to_string()
+            } else {
+                errors.join("\n")
+            });
+        }
+
+        let data = serde_json::to_value(&report).unwrap_or_default();
+        if report.success {
+            ToolResult::structured(data)
+        } else {
+            ToolResult::failure(&format!("{} failed: {}", report.command, data)).with_data(data)
+        }
+    }
+}
+
+impl Tool for CargoTool {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn description(&self) -> &str {
+        "Runs cargo check, test or clippy and returns compiler diagnostics (file, line, message, suggestion) and test failures as JSON."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.execute_cancellable(input, &CancellationToken::new())
+    }
+
+    fn execute_cancellable(&self, input: &str, cancel: &CancellationToken) -> ToolResult {
+        // Input format: "<check|test|clippy> [cargo args...]", e.g. "test -p core".
+        let mut words = input.split_whitespace();
+        let words = match words.next() {
+            Some("cargo") => words.collect::<Vec<_>>(),
+            Some(first) => std::iter::once(first).chain(words).collect(),
+            None => Vec::new(),
+        };
+        let Some((subcommand, args)) = words.split_first() else {
+            return ToolResult::failure("Name a cargo command: check, test or clippy");
+        };
+        self.run(subcommand, args, cancel)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        if let Some(input) = args.as_str() {
+            return self.execute(input);
+        }
+        let Some(subcommand) = args["command"].as_str() else {
+            return ToolResult::failure("Missing 'command' argument");
+        };
+        let mut extra = Vec::new();
+        for arg in args["args"].as_array().into_iter().flatten() {
+            match arg.as_str() {
+                Some(arg) => extra.push(arg),
+                None => return ToolResult::failure("'args' must be strings"),
+            }
+        }
+        self.run(subcommand, &extra, &CancellationToken::new())
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("would run `cargo {}` (read-only)", input.trim()))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args["command"].as_str().or(args.as_str()) {
+            Some(command) => self.preview(command),
+            None => SimulationOutcome::read_only("would fail: missing 'command' argument"),
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "check, test or clippy plus cargo arguments (e.g. 'test -p core', 'clippy --all-targets')".into(),
+            tags: vec!["cargo".into(), "rust".into(), "diagnostics".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "enum": SUBCOMMANDS },
+                    "args": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["command"]
+            })),
+            timeout: Some(Duration::from_secs(600)),
+            idempotent: true,
+            examples: vec![ToolExample::new(
+                "clippy --all-targets",
+                "JSON report: error and warning counts, then each diagnostic with file, line, message and suggested fix",
+            )],
+        }
+    }
+}
+
+/// Parses `--message-format=json` output: compiler messages (deduplicated
+/// across targets, keeping at most `max` diagnostics) and, interleaved as
+/// plain text, libtest's results.
+pub fn parse_cargo_output(stdout: &str, max: usize) -> CargoReport {
+    let mut report = CargoReport::default();
+    let mut seen = HashSet::new();
+    let mut text = Vec::new();
+    let mut diagnostics = Vec::new();
+    for line in stdout.lines() {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            text.push(line);
+            continue;
+        };
+        if message["reason"] != "compiler-message" {
+            continue;
+        }
+        let Some(diagnostic) = diagnostic(&message["message"]) else {
+            continue;
+        };
+        if !seen.insert((
+            diagnostic.file.clone(),
+            diagnostic.line,
+            diagnostic.message.clone(),
+        )) {
+            continue;
+        }
+        match diagnostic.level.as_str() {
+            "error" => report.errors += 1,
+            _ => report.warnings += 1,
+        }
+        diagnostics.push(diagnostic);
+    }
+    // Stable, so each level keeps compiler order.
+    diagnostics.sort_by_key(|diagnostic| diagnostic.level != "error");
+    diagnostics.truncate(max);
+    report.diagnostics = diagnostics;
+    report.tests = parse_test_output(&text);
+    report
+}
+
+/// A message with a primary span; summaries like "aborting due to 2
+/// previous errors" have none and are skipped.
+fn diagnostic(message: &Value) -> Option<Diagnostic> {
+    let level = message["level"].as_str()?;
+    if !matches!(level, "error" | "warning") {
+        return None;
+    }
+    let span = message["spans"]
+        .as_array()?
+        .iter()
+        .find(|span| span["is_primary"] == true)?;
+    let suggestion = message["children"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|child| child["level"] == "help")
+        .map(|help| {
+            let text = help["message"].as_str().unwrap_or_default();
+            let replacement = help["spans"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find_map(|span| span["suggested_replacement"].as_str());
+            match replacement {
+                Some(replacement) => format!("{}: `{}`", text, replacement),
+                None => text.to_string(),
+            }
+        });
+    Some(Diagnostic {
+        level: level.into(),
+        code: message["code"]["code"].as_str().map(str::to_string),
+        message: message["message"].as_str().unwrap_or_default().into(),
+        file: span["file_name"].as_str().unwrap_or_default().into(),
+        line: span["line_start"].as_u64().unwrap_or_default(),
+        column: span["column_start"].as_u64().unwrap_or_default(),
+        suggestion,
+    })
+}
+
+/// Sums the `test result:` lines and collects each `---- <name> stdout ----`
+/// section; `None` when no tests ran.
+fn parse_test_output(lines: &[&str]) -> Option<TestSummary> {
+    let mut summary = TestSummary::default();
+    let mut ran = false;
+    let mut lines = lines.iter().peekable();
+    while let Some(line) = lines.next() {
+        if let Some(counts) = line.strip_prefix("test result: ") {
+            ran = true;
+            for part in counts.split([';', '.']) {
+                let mut words = part.split_whitespace();
+                let (Some(n), Some(label)) = (words.next(), words.next()) else {
+                    continue;
+                };
+                let Ok(n) = n.parse::<u64>() else { continue };
+                match label {
+                    "passed" => summary.passed += n,
+                    "failed" => summary.failed += n,
+                    "ignored" => summary.ignored += n,
+                    _ => {}
+                }
+            }
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            let mut output = Vec::new();
+            while let Some(next) =
+                lines.next_if(|next| !next.starts_with("---- ") && next.trim_end() != "failures:")
+            {
+                output.push(next.trim_end());
+            }
+            let output: Vec<&str> = output
+                .into_iter()
+                .skip_while(|line| line.is_empty())
+                .take_while(|line| !line.starts_with("stack backtrace:"))
+                .take(MAX_FAILURE_LINES)
+                .collect();
+            summary.failures.push(TestFailure {
+                name: name.into(),
+                output: output.join("\n").trim_end().into(),
+            });
+        }
+    }
+    ran.then_some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_output_parses_into_diagnostics_and_test_failures() {
+        let message = |level: &str, text: &str, line: u64| {
+            json!({
+                "reason": "compiler-message",
+                "message": {
+                    "level": level,
+                    "message": text,
+                    "code": { "code": "E0425" },
+                    "spans": [{ "file_name": "src/main.rs", "line_start": line, "column_start": 5, "is_primary": true }],
+                    "children": [{
+                        "level": "help",
+                        "message": "a local variable with a similar name exists",
+                        "spans": [{ "suggested_replacement": "x" }]
+                    }]
+                }
+            })
+            .to_string()
+        };
+        let summary = json!({
+            "reason": "compiler-message",
+            "message": { "level": "error", "message": "aborting due to 1 previous error", "spans": [] }
+        });
+        let stdout = [
+            message("warning", "unused variable", 1),
+            message("error", "cannot find value `y`", 2),
+            // The same error again, from the test target.
+            message("error", "cannot find value `y`", 2),
+            summary.to_string(),
+            "test t ... FAILED".into(),
+            "---- t stdout ----".into(),
+            "thread 't' panicked at src/main.rs:9:5:".into(),
+            "boom".into(),
+            "stack backtrace:".into(),
+            "   0: frames".into(),
+            "failures:".into(),
+            "test result: FAILED. 3 passed; 1 failed; 2 ignored; 0 measured".into(),
+        ]
+        .join("\n");
+
+        let report = parse_cargo_output(&stdout, 10);
+        assert_eq!((report.errors, report.warnings), (1, 1));
+        let error = &report.diagnostics[0];
+        assert_eq!(
+            (error.level.as_str(), error.line, error.code.as_deref()),
+            ("error", 2, Some("E0425"))
+        );
+        assert_eq!(
+            error.suggestion.as_deref(),
+            Some("a local variable with a similar name exists: `x`")
+        );
+        let tests = report.tests.unwrap();
+        assert_eq!((tests.passed, tests.failed, tests.ignored), (3, 1, 2));
+        assert_eq!(tests.failures[0].name, "t");
+        assert_eq!(
+            tests.failures[0].output,
+            "thread 't' panicked at src/main.rs:9:5:\nboom"
+        );
+        assert_eq!(parse_cargo_output(&stdout, 1).diagnostics.len(), 1);
+    }
+}
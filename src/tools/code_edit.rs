@@ -0,0 +1,1025 @@
+// src/tools/code_edit.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use crate::workspace::Workspace;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+/// Lines of unchanged context around each diff hunk.
+const DIFF_CONTEXT: usize = 3;
+
+/// Keywords that start an item.
+const ITEM_KEYWORDS: [&str; 12] = [
+    "fn", "struct", "enum", "union", "trait", "impl", "mod", "type", "const", "static", "use",
+    "crate",
+];
+
+/// Keywords that may come before an item's own keyword.
+const ITEM_MODIFIERS: [&str; 6] = ["pub", "async", "unsafe", "default", "extern", "const"];
+
+/// Edits Rust sources by item rather than by line: replaces a function,
+/// struct or impl block whole, inserts an item after another or into an
+/// `impl`/`trait`/`mod` body, or renames an identifier. Returns a unified
+/// diff of the change.
+///
+/// Items are found by a syntax-aware scan (comments, strings and nested
+/// delimiters understood), not by type resolution: `rename` changes every
+/// matching identifier token in the given files, whatever it refers to.
+/// Edits that would leave delimiters unbalanced are refused.
+#[derive(Clone, Debug, Default)]
+pub struct CodeEditTool {
+    workspace: Option<Workspace>,
+}
+
+impl CodeEditTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Edits files inside `workspace` only.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        match &self.workspace {
+            Some(workspace) => workspace.resolve(path),
+            None => Ok(PathBuf::from(path)),
+        }
+    }
+
+    /// The files an edit would write, for `preview`.
+    fn paths(args: &Value) -> Vec<String> {
+        let paths = args["paths"].as_array().into_iter().flatten();
+        args["path"]
+            .as_str()
+            .into_iter()
+            .chain(paths.filter_map(Value::as_str))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn edit(&self, args: &Value) -> Result<String, String> {
+        let action = args["action"]
+            .as_str()
+            .ok_or("Missing 'action' argument (replace, insert or rename)")?;
+        let paths = Self::paths(args);
+        if paths.is_empty() {
+            return Err("Missing 'path' argument".into());
+        }
+
+        let mut edits = Vec::new();
+        for path in &paths {
+            let file = self.resolve(path)?;
+            let source = std::fs::read_to_string(&file).map_err(|e| format!("{}: {}", path, e))?;
+            let edited = match action {
+                "replace" => replace_item(&source, arg(args, "item")?, arg(args, "content")?),
+                "insert" => insert_item(
+                    &source,
+                    arg(args, "content")?,
+                    args["after"].as_str(),
+                    args["into"].as_str(),
+                ),
+                "rename" => rename_symbol(&source, arg(args, "from")?, arg(args, "to")?),
+                other => Err(format!(
+                    "Unknown edit action '{}', expected replace, insert or rename",
+                    other
+                )),
+            }
+            .map_err(|e| format!("{}: {}", path, e))?;
+            tokenize(&edited).map_err(|e| {
+                format!(
+                    "{}: the edit would not parse ({}); nothing written",
+                    path, e
+                )
+            })?;
+            edits.push((path, file, source, edited));
+        }
+        if action == "rename" && edits.iter().all(|(_, _, old, new)| old == new) {
+            return Err(format!("No identifier '{}' found", arg(args, "from")?));
+        }
+
+        let mut diff = String::new();
+        for (path, file, source, edited) in edits {
+            if source == edited {
+                continue;
+            }
+            std::fs::write(&file, &edited).map_err(|e| format!("{}: {}", path, e))?;
+            diff.push_str(&unified_diff(path, &source, &edited));
+        }
+        Ok(diff)
+    }
+}
+
+fn arg<'a>(args: &'a Value, key: &str) -> Result<&'a str, String> {
+    args[key]
+        .as_str()
+        .ok_or_else(|| format!("Missing '{}' argument", key))
+}
+
+impl Tool for CodeEditTool {
+    fn name(&self) -> &str {
+        "edit_code"
+    }
+
+    fn description(&self) -> &str {
+        "Edits Rust code by item: replaces a function/struct/impl, inserts an item after another or into an impl, or renames an identifier. Returns the diff."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        match serde_json::from_str::<Value>(input) {
+            Ok(args) if args.is_object() => self.execute_args(&args),
+            _ => ToolResult::failure(
+                "edit_code takes JSON arguments, e.g. {\"action\": \"rename\", \"path\": \"src/lib.rs\", \"from\": \"old\", \"to\": \"new\"}",
+            ),
+        }
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        if let Some(input) = args.as_str() {
+            return self.execute(input);
+        }
+        match self.edit(args) {
+            Ok(diff) if diff.is_empty() => ToolResult::success("No changes"),
+            Ok(diff) => ToolResult::success(diff.trim_end()),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        match serde_json::from_str::<Value>(input) {
+            Ok(args) => self.preview_args(&args),
+            Err(_) => SimulationOutcome::read_only("would fail: edit_code takes JSON arguments"),
+        }
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        let paths = Self::paths(args);
+        SimulationOutcome::mutating(&format!(
+            "would {} code in {}",
+            args["action"].as_str().unwrap_or("edit"),
+            paths.join(", ")
+        ))
+        .with_paths(paths)
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "JSON: {action: replace, path, item: 'Parser::parse', content} | {action: insert, path, content, after?: item, into?: 'impl Parser'} | {action: rename, path or paths, from, to}".into(),
+            tags: vec!["code".into(), "edit".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["replace", "insert", "rename"] },
+                    "path": { "type": "string" },
+                    "paths": { "type": "array", "items": { "type": "string" } },
+                    "item": { "type": "string" },
+                    "content": { "type": "string" },
+                    "after": { "type": "string" },
+                    "into": { "type": "string" },
+                    "from": { "type": "string" },
+                    "to": { "type": "string" }
+                },
+                "required": ["action"]
+            })),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Ident,
+    Lifetime,
+    Literal,
+    /// An outer doc comment (`///` or `/** */`).
+    Doc,
+    Punct,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+/// Rust source as tokens, with the index of each delimiter's partner.
+struct Tokens<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    partner: Vec<Option<usize>>,
+}
+
+impl Tokens<'_> {
+    fn text(&self, i: usize) -> &str {
+        self.tokens
+            .get(i)
+            .map_or("", |token| &self.source[token.start..token.end])
+    }
+
+    fn kind(&self, i: usize) -> Option<TokenKind> {
+        self.tokens.get(i).map(|token| token.kind)
+    }
+
+    /// The token after `i`'s closing partner when `i` opens a group.
+    fn skip(&self, i: usize) -> usize {
+        self.partner[i].map_or(i + 1, |close| close + 1)
+    }
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+/// Splits `source` into tokens, skipping whitespace and comments other than
+/// outer doc comments, and pairs up `()`, `[]` and `{}`.
+fn tokenize(source: &str) -> Result<Tokens<'_>, String> {
+    let bytes = source.as_bytes();
+    let at = |i: usize| bytes.get(i).copied().unwrap_or(0);
+    let line = |i: usize| source[..i].matches('\n').count() + 1;
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let b = bytes[i];
+        let kind = match b {
+            _ if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'/' if at(i + 1) == b'/' => {
+                let doc = at(i + 2) == b'/' && at(i + 3) != b'/';
+                i = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                if !doc {
+                    continue;
+                }
+                TokenKind::Doc
+            }
+            b'/' if at(i + 1) == b'*' => {
+                let doc = at(i + 2) == b'*' && at(i + 3) != b'*' && at(i + 3) != b'/';
+                let mut depth = 0;
+                loop {
+                    match (at(i), at(i + 1)) {
+                        (0, _) => {
+                            return Err(format!("unterminated comment at line {}", line(start)));
+                        }
+                        (b'/', b'*') => {
+                            depth += 1;
+                            i += 2;
+                        }
+                        (b'*', b'/') => {
+                            depth -= 1;
+                            i += 2;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => i += 1,
+                    }
+                }
+                if !doc {
+                    continue;
+                }
+                TokenKind::Doc
+            }
+            b'"' => {
+                i = skip_string(bytes, i + 1)
+                    .ok_or_else(|| format!("unterminated string at line {}", line(start)))?;
+                TokenKind::Literal
+            }
+            b'\'' => {
+                // A char literal closes within a character or an escape; a
+                // lifetime does not.
+                let next = source[i + 1..].chars().next().map_or(1, char::len_utf8);
+                if at(i + 1) == b'\\' {
+                    i = skip_char(bytes, i + 1)
+                        .ok_or_else(|| format!("unterminated char at line {}", line(start)))?;
+                    TokenKind::Literal
+                } else if at(i + 1 + next) == b'\'' {
+                    i += next + 2;
+                    TokenKind::Literal
+                } else {
+                    i += 1;
+                    while i < bytes.len() && is_ident_byte(bytes[i]) {
+                        i += 1;
+                    }
+                    TokenKind::Lifetime
+                }
+            }
+            _ if b.is_ascii_digit() => {
+                while i < bytes.len()
+                    && (is_ident_byte(bytes[i]) || (bytes[i] == b'.' && at(i + 1).is_ascii_digit()))
+                {
+                    i += 1;
+                }
+                TokenKind::Literal
+            }
+            _ if is_ident_byte(b) => {
+                while i < bytes.len() && is_ident_byte(bytes[i]) {
+                    i += 1;
+                }
+                let word = &source[start..i];
+                match (word, at(i)) {
+                    ("r" | "br" | "cr", b'"' | b'#') if raw_string_start(bytes, i) => {
+                        i = skip_raw_string(bytes, i).ok_or_else(|| {
+                            format!("unterminated raw string at line {}", line(start))
+                        })?;
+                        TokenKind::Literal
+                    }
+                    ("r", b'#') if is_ident_byte(at(i + 1)) => {
+                        // A raw identifier, e.g. `r#type`.
+                        i += 1;
+                        while i < bytes.len() && is_ident_byte(bytes[i]) {
+                            i += 1;
+                        }
+                        TokenKind::Ident
+                    }
+                    ("b" | "c", b'"') => {
+                        i = skip_string(bytes, i + 1).ok_or_else(|| {
+                            format!("unterminated string at line {}", line(start))
+                        })?;
+                        TokenKind::Literal
+                    }
+                    ("b", b'\'') => {
+                        i = skip_char(bytes, i + 1)
+                            .ok_or_else(|| format!("unterminated byte at line {}", line(start)))?;
+                        TokenKind::Literal
+                    }
+                    _ => TokenKind::Ident,
+                }
+            }
+            _ => {
+                i += source[i..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Punct
+            }
+        };
+        tokens.push(Token {
+            kind,
+            start,
+            end: i,
+        });
+    }
+
+    let mut partner = vec![None; tokens.len()];
+    let mut open: Vec<usize> = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        let closes = match &source[token.start..token.end] {
+            "(" | "[" | "{" => {
+                open.push(index);
+                continue;
+            }
+            ")" => "(",
+            "]" => "[",
+            "}" => "{",
+            _ => continue,
+        };
+        match open.pop() {
+            Some(o) if &source[tokens[o].start..tokens[o].end] == closes => {
+                partner[o] = Some(index);
+                partner[index] = Some(o);
+            }
+            _ => {
+                return Err(format!(
+                    "unbalanced '{}' at line {}",
+                    &source[token.start..token.end],
+                    line(token.start)
+                ));
+            }
+        }
+    }
+    if let Some(&o) = open.last() {
+        return Err(format!(
+            "unclosed '{}' at line {}",
+            &source[tokens[o].start..tokens[o].end],
+            line(tokens[o].start)
+        ));
+    }
+    Ok(Tokens {
+        source,
+        tokens,
+        partner,
+    })
+}
+
+/// The index after the closing quote of a string whose body starts at `i`.
+fn skip_string(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// The index after the closing quote of a char literal whose body starts at `i`.
+fn skip_char(bytes: &[u8], i: usize) -> Option<usize> {
+    let close = bytes[i + 1..].iter().position(|&b| b == b'\'')?;
+    Some(i + 1 + close + 1)
+}
+
+fn raw_string_start(bytes: &[u8], i: usize) -> bool {
+    let hashes = bytes[i..].iter().take_while(|&&b| b == b'#').count();
+    bytes.get(i + hashes) == Some(&b'"')
+}
+
+/// The index after a raw string whose `#`s (or quote) start at `i`.
+fn skip_raw_string(bytes: &[u8], i: usize) -> Option<usize> {
+    let hashes = bytes[i..].iter().take_while(|&&b| b == b'#').count();
+    let mut j = i + hashes + 1;
+    while j < bytes.len() {
+        if bytes[j] == b'"'
+            && bytes[j + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|&&b| b == b'#')
+                .count()
+                == hashes
+        {
+            return Some(j + 1 + hashes);
+        }
+        j += 1;
+    }
+    None
+}
+
+/// A top-level or nested item, spanning its doc comments and attributes.
+#[derive(Clone, Debug)]
+struct Item {
+    kind: String,
+    /// The item's name; for an `impl`, its self type.
+    name: String,
+    /// The trait of a trait `impl`.
+    trait_name: Option<String>,
+    start: usize,
+    end: usize,
+    /// Token indices of the `{` and `}` around an `impl`, `trait` or `mod` body.
+    body: Option<(usize, usize)>,
+    children: Vec<Item>,
+}
+
+impl Item {
+    fn label(&self) -> String {
+        match (&self.kind[..], &self.trait_name) {
+            ("impl", Some(trait_name)) => format!("impl {} for {}", trait_name, self.name),
+            (kind, _) => format!("{} {}", kind, self.name),
+        }
+    }
+}
+
+/// The items among tokens `from..to`.
+fn parse_items(tokens: &Tokens, from: usize, to: usize) -> Vec<Item> {
+    let mut items = Vec::new();
+    let mut pending = None;
+    let mut i = from;
+    while i < to {
+        let token = tokens.tokens[i];
+        if token.kind == TokenKind::Doc {
+            pending.get_or_insert(token.start);
+            i += 1;
+            continue;
+        }
+        if tokens.text(i) == "#" {
+            let inner = tokens.text(i + 1) == "!";
+            let bracket = i + 1 + usize::from(inner);
+            if tokens.text(bracket) == "[" {
+                if !inner {
+                    pending.get_or_insert(token.start);
+                }
+                i = tokens.skip(bracket);
+                continue;
+            }
+        }
+        if token.kind == TokenKind::Ident {
+            let start = pending.unwrap_or(token.start);
+            let mut k = i;
+            while ITEM_MODIFIERS.contains(&tokens.text(k)) {
+                match tokens.text(k) {
+                    "pub" if tokens.text(k + 1) == "(" => k = tokens.skip(k + 1),
+                    "extern" if tokens.kind(k + 1) == Some(TokenKind::Literal) => k += 2,
+                    "const"
+                        if !matches!(tokens.text(k + 1), "fn" | "unsafe" | "async" | "extern") =>
+                    {
+                        break;
+                    }
+                    _ => k += 1,
+                }
+            }
+            if tokens.text(k) == "macro_rules" && tokens.text(k + 1) == "!" {
+                let mut end = tokens.skip(k + 3);
+                if tokens.text(end) == ";" {
+                    end += 1;
+                }
+                items.push(Item {
+                    kind: "macro_rules".into(),
+                    name: tokens.text(k + 2).into(),
+                    trait_name: None,
+                    start,
+                    end: tokens.tokens[end - 1].end,
+                    body: None,
+                    children: Vec::new(),
+                });
+                pending = None;
+                i = end;
+                continue;
+            }
+            // `crate` starts an item only in `extern crate`.
+            let keyword = tokens.text(k);
+            if ITEM_KEYWORDS.contains(&keyword)
+                && (keyword != "crate" || k > i && tokens.text(k - 1) == "extern")
+            {
+                let (item, next) = parse_item(tokens, k, start);
+                items.push(item);
+                pending = None;
+                i = next;
+                continue;
+            }
+        }
+        pending = None;
+        i = tokens.skip(i);
+    }
+    items
+}
+
+/// The item whose keyword is token `k`, and the token after it.
+fn parse_item(tokens: &Tokens, k: usize, start: usize) -> (Item, usize) {
+    let kind = tokens.text(k).to_string();
+    let (name, trait_name) = if kind == "impl" {
+        impl_header(tokens, k + 1)
+    } else {
+        let name = (k + 1..tokens.tokens.len())
+            .find(|&j| tokens.kind(j) == Some(TokenKind::Ident) && tokens.text(j) != "crate")
+            .map(|j| tokens.text(j).trim_start_matches("r#").to_string())
+            .unwrap_or_default();
+        (name, None)
+    };
+
+    // Initializers and use-trees may hold braces; those items end at `;`.
+    let ends_at_semicolon = matches!(&kind[..], "const" | "static" | "type" | "use" | "crate");
+    let mut j = k + 1;
+    let mut body = None;
+    while j < tokens.tokens.len() {
+        match tokens.text(j) {
+            ";" => break,
+            "{" if !ends_at_semicolon => {
+                body = tokens.partner[j].map(|close| (j, close));
+                j = body.map_or(j, |(_, close)| close);
+                break;
+            }
+            _ => j = tokens.skip(j),
+        }
+    }
+    let last = j.min(tokens.tokens.len() - 1);
+    let children = match (&kind[..], body) {
+        ("impl" | "trait" | "mod", Some((open, close))) => parse_items(tokens, open + 1, close),
+        _ => Vec::new(),
+    };
+    let body = body.filter(|_| matches!(&kind[..], "impl" | "trait" | "mod"));
+    let item = Item {
+        kind,
+        name,
+        trait_name,
+        start,
+        end: tokens.tokens[last].end,
+        body,
+        children,
+    };
+    (item, last + 1)
+}
+
+/// The self type and trait of the `impl` whose header starts at token `i`.
+fn impl_header(tokens: &Tokens, mut i: usize) -> (String, Option<String>) {
+    // `>` closes a generic list unless it is part of `->`.
+    let closes = |i: usize| tokens.text(i) == ">" && tokens.text(i - 1) != "-";
+    // Skip the impl's own generics, e.g. `impl<T: Display>`.
+    if tokens.text(i) == "<" {
+        let mut depth = 0;
+        while i < tokens.tokens.len() {
+            match tokens.text(i) {
+                "<" => depth += 1,
+                _ if closes(i) => depth -= 1,
+                _ => {}
+            }
+            i += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+    let mut parts: Vec<Vec<&str>> = vec![Vec::new()];
+    let mut depth = 0;
+    while i < tokens.tokens.len() && !matches!(tokens.text(i), "{" | ";" | "where") {
+        match tokens.text(i) {
+            "<" => depth += 1,
+            _ if closes(i) => depth -= 1,
+            "for" if depth == 0 => {
+                parts.push(Vec::new());
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(tokens.text(i));
+        i += 1;
+    }
+    match &parts[..] {
+        [trait_name, self_ty] => (type_text(self_ty), Some(type_text(trait_name))),
+        _ => (type_text(&parts[0]), None),
+    }
+}
+
+/// Type tokens as written, e.g. `Vec<String>` or `dyn Tool + Send`.
+fn type_text(words: &[&str]) -> String {
+    let word_like = |w: &str| {
+        w.bytes()
+            .next()
+            .is_some_and(|b| is_ident_byte(b) || b == b'\'')
+    };
+    let mut text = String::new();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            let previous = words[i - 1];
+            if (word_like(word) && word_like(previous)) || *word == "+" || previous == "+" {
+                text.push(' ');
+            }
+        }
+        text.push_str(word);
+    }
+    text
+}
+
+/// Whether an item named `name` (e.g. `Parser<T>`) answers to `query`
+/// (`Parser<T>` or just `Parser`).
+fn names_match(name: &str, query: &str) -> bool {
+    let squash = |text: &str| text.split_whitespace().collect::<String>();
+    let (name, query) = (squash(name), squash(query));
+    name == query || name.split('<').next() == Some(&query[..])
+}
+
+/// Finds `query` among `items`: a name (`parse`), a kind and name (`fn
+/// parse`), a path through impls, traits and modules (`Parser::parse`), or
+/// an impl (`impl Parser`, `impl Display for Parser`). With `container`,
+/// only items with a body count, and a bare type name finds its impls too.
+fn find_item<'a>(
+    items: &'a [Item],
+    query: &str,
+    source: &str,
+    container: bool,
+) -> Result<&'a Item, String> {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut found = Vec::new();
+    if let Some(header) = query.strip_prefix("impl ") {
+        let (trait_name, self_ty) = match header.split_once(" for ") {
+            Some((trait_name, self_ty)) => (Some(trait_name), self_ty),
+            None => (None, header),
+        };
+        walk(items, &mut |item| {
+            let trait_matches = match (&item.trait_name, trait_name) {
+                (Some(name), Some(query)) => names_match(name, query),
+                (None, None) => true,
+                _ => false,
+            };
+            if item.kind == "impl" && names_match(&item.name, self_ty) && trait_matches {
+                found.push(item);
+            }
+        });
+    } else {
+        let (kind, path) = match query.split_once(' ') {
+            Some((kind, path)) => (Some(kind), path),
+            None => (None, &query[..]),
+        };
+        let mut segments: Vec<&str> = path.split("::").collect();
+        let name = segments.pop().unwrap_or_default();
+        let mut scopes: Vec<&Item> = Vec::new();
+        let candidates: Vec<&Item> = match segments.last() {
+            // Children of every impl, trait or module named by the path.
+            Some(parent) => {
+                walk(items, &mut |item| {
+                    if names_match(&item.name, parent) && item.body.is_some() {
+                        scopes.push(item);
+                    }
+                });
+                scopes.iter().flat_map(|scope| &scope.children).collect()
+            }
+            None => {
+                let mut all = Vec::new();
+                walk(items, &mut |item| all.push(item));
+                all
+            }
+        };
+        found = candidates
+            .into_iter()
+            .filter(|item| {
+                let named = match &item.kind[..] {
+                    "impl" => container && kind.is_none() && names_match(&item.name, name),
+                    _ => item.name == name,
+                };
+                named
+                    && kind.is_none_or(|kind| item.kind == kind)
+                    && (!container || item.body.is_some())
+            })
+            .collect();
+    }
+
+    match &found[..] {
+        [item] => Ok(item),
+        [] => Err(format!("No item '{}' found", query)),
+        many => Err(format!(
+            "'{}' is ambiguous ({}); qualify it, e.g. Type::name",
+            query,
+            many.iter()
+                .map(|item| format!("{} at line {}", item.label(), line_of(source, item.start)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+fn walk<'a>(items: &'a [Item], visit: &mut impl FnMut(&'a Item)) {
+    for item in items {
+        visit(item);
+        walk(&item.children, visit);
+    }
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+/// The whitespace before `offset` on its line, if nothing else precedes it.
+fn indent_at(source: &str, offset: usize) -> &str {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let before = &source[line_start..offset];
+    if before.trim().is_empty() { before } else { "" }
+}
+
+/// `content` dedented, then indented by `indent` (the first line too unless
+/// `first` is false).
+fn indent_block(content: &str, indent: &str, first: bool) -> String {
+    let content = content.trim_matches('\n').trim_end();
+    let common = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.get(common..).unwrap_or(line.trim_start());
+            if line.is_empty() || (i == 0 && !first) {
+                line.to_string()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `source` with `item` (doc comments and attributes included) replaced by `content`.
+pub fn replace_item(source: &str, item: &str, content: &str) -> Result<String, String> {
+    let tokens = tokenize(source)?;
+    let items = parse_items(&tokens, 0, tokens.tokens.len());
+    let item = find_item(&items, item, source, false)?;
+    let indent = indent_at(source, item.start);
+    Ok(format!(
+        "{}{}{}",
+        &source[..item.start],
+        indent_block(content, indent, false),
+        &source[item.end..]
+    ))
+}
+
+/// `source` with `content` added after the item `after`, at the end of the
+/// body of `into` (an `impl`, `trait` or `mod`), or at the end of the file.
+pub fn insert_item(
+    source: &str,
+    content: &str,
+    after: Option<&str>,
+    into: Option<&str>,
+) -> Result<String, String> {
+    let tokens = tokenize(source)?;
+    let items = parse_items(&tokens, 0, tokens.tokens.len());
+    match (after, into) {
+        (Some(_), Some(_)) => Err("Give 'after' or 'into', not both".into()),
+        (Some(after), None) => {
+            let item = find_item(&items, after, source, false)?;
+            let indent = indent_at(source, item.start);
+            Ok(format!(
+                "{}\n\n{}{}",
+                &source[..item.end],
+                indent_block(content, indent, true),
+                &source[item.end..]
+            ))
+        }
+        (None, Some(into)) => {
+            let item = find_item(&items, into, source, true)?;
+            let Some((open, close)) = item.body else {
+                return Err(format!("'{}' has no body to insert into", into));
+            };
+            let indent = format!("{}    ", indent_at(source, item.start));
+            let block = indent_block(content, &indent, true);
+            let close_at = tokens.tokens[close].start;
+            let empty = close == open + 1;
+            let separator = if empty { "" } else { "\n" };
+            let closing_indent = indent_at(source, close_at);
+            if closing_indent.is_empty() && !source[..close_at].ends_with('\n') {
+                // A one-line body like `impl Parser {}`.
+                Ok(format!(
+                    "{}\n{}\n{}{}",
+                    source[..close_at].trim_end(),
+                    block,
+                    indent_at(source, item.start),
+                    &source[close_at..]
+                ))
+            } else {
+                let line_start = close_at - closing_indent.len();
+                Ok(format!(
+                    "{}{}{}\n{}",
+                    &source[..line_start],
+                    separator,
+                    block,
+                    &source[line_start..]
+                ))
+            }
+        }
+        (None, None) => Ok(format!(
+            "{}\n\n{}\n",
+            source.trim_end(),
+            indent_block(content, "", true)
+        )),
+    }
+}
+
+/// `source` with every identifier token `from` (or `r#from`) renamed `to`;
+/// strings and comments are left alone.
+pub fn rename_symbol(source: &str, from: &str, to: &str) -> Result<String, String> {
+    let is_ident = |name: &str| {
+        name.bytes().next().is_some_and(|b| !b.is_ascii_digit()) && name.bytes().all(is_ident_byte)
+    };
+    if !is_ident(from) || !is_ident(to) {
+        return Err(format!("'{}' → '{}' is not an identifier rename", from, to));
+    }
+    let tokens = tokenize(source)?;
+    let mut renamed = String::with_capacity(source.len());
+    let mut copied = 0;
+    for token in &tokens.tokens {
+        let text = &source[token.start..token.end];
+        if token.kind == TokenKind::Ident && text.trim_start_matches("r#") == from {
+            renamed.push_str(&source[copied..token.start]);
+            renamed.push_str(to);
+            copied = token.end;
+        }
+    }
+    renamed.push_str(&source[copied..]);
+    Ok(renamed)
+}
+
+/// A unified diff of `old` → `new` with `DIFF_CONTEXT` lines of context.
+/// Changes that keep the line count are diffed line by line; otherwise the
+/// changed middle (between the common prefix and suffix) is one change.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let (a, b): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_end, b_end) = (a.len() - suffix, b.len() - suffix);
+
+    // (old_start, old_end, new_start, new_end) of each changed run.
+    let mut changes: Vec<(usize, usize, usize, usize)> = Vec::new();
+    if a_end - prefix == b_end - prefix {
+        for i in prefix..a_end {
+            if a[i] == b[i] {
+                continue;
+            }
+            match changes.last_mut() {
+                Some(last) if last.1 == i => {
+                    last.1 += 1;
+                    last.3 += 1;
+                }
+                _ => changes.push((i, i + 1, i, i + 1)),
+            }
+        }
+    } else {
+        changes.push((prefix, a_end, prefix, b_end));
+    }
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let mut hunks: Vec<Vec<(usize, usize, usize, usize)>> = Vec::new();
+    for change in changes {
+        match hunks.last_mut() {
+            Some(hunk) if change.0 - hunk.last().unwrap().1 <= 2 * DIFF_CONTEXT => {
+                hunk.push(change)
+            }
+            _ => hunks.push(vec![change]),
+        }
+    }
+    for hunk in hunks {
+        let (first, last) = (hunk[0], hunk[hunk.len() - 1]);
+        let old_lo = first.0.saturating_sub(DIFF_CONTEXT);
+        let old_hi = (last.1 + DIFF_CONTEXT).min(a.len());
+        let new_lo = first.2 - (first.0 - old_lo);
+        let new_hi = last.3 + (old_hi - last.1);
+        let start = |lo: usize, hi: usize| if hi > lo { lo + 1 } else { lo };
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            start(old_lo, old_hi),
+            old_hi - old_lo,
+            start(new_lo, new_hi),
+            new_hi - new_lo
+        ));
+        let mut at = old_lo;
+        for (old_start, old_end, new_start, new_end) in hunk {
+            for line in &a[at..old_start] {
+                diff.push_str(&format!(" {}\n", line));
+            }
+            for line in &a[old_start..old_end] {
+                diff.push_str(&format!("-{}\n", line));
+            }
+            for line in &b[new_start..new_end] {
+                diff.push_str(&format!("+{}\n", line));
+            }
+            at = old_end;
+        }
+        for line in &a[at..old_hi] {
+            diff.push_str(&format!(" {}\n", line));
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_edit_replaces_inserts_and_renames_items() {
+        let source = "struct Parser {}\n\nimpl Parser {\n    /// Parses.\n    fn parse(&self) -> u32 {\n        1\n    }\n}\n\nfn parse() {}\n";
+        let replaced = replace_item(
+            source,
+            "Parser::parse",
+            "fn parse(&self) -> u32 {\n    2\n}",
+        )
+        .unwrap();
+        assert!(replaced.contains("    fn parse(&self) -> u32 {\n        2\n    }\n}"));
+        assert!(!replaced.contains("/// Parses."));
+        assert!(
+            replace_item(source, "parse", "")
+                .unwrap_err()
+                .contains("ambiguous")
+        );
+
+        let inserted = insert_item(source, "fn reset(&mut self) {}", None, Some("Parser")).unwrap();
+        assert!(inserted.contains("    }\n\n    fn reset(&mut self) {}\n}\n"));
+        let after = insert_item(source, "struct Lexer;", Some("struct Parser"), None).unwrap();
+        assert!(after.starts_with("struct Parser {}\n\nstruct Lexer;\n\nimpl Parser"));
+
+        let renamed =
+            rename_symbol("// Parser\nlet p: Parser = \"Parser\";", "Parser", "Reader").unwrap();
+        assert_eq!(renamed, "// Parser\nlet p: Reader = \"Parser\";");
+        assert_eq!(
+            unified_diff("a.rs", "a\nb\nc\n", "a\nx\nc\n"),
+            "--- a/a.rs\n+++ b/a.rs\n@@ -1,3 +1,3 @@\n a\n-b\n+x\n c\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("code-edit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("lib.rs");
+        std::fs::write(&file, source).unwrap();
+        let path = file.to_str().unwrap();
+        let tool = CodeEditTool::new();
+        let unbalanced = tool.execute_args(&json!({
+            "action": "replace", "path": path, "item": "struct Parser", "content": "struct Parser {"
+        }));
+        assert!(unbalanced.error.unwrap().contains("would not parse"));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), source);
+        let result = tool.execute_args(&json!({
+            "action": "rename", "path": path, "from": "Parser", "to": "Reader"
+        }));
+        assert!(result.success, "{:?}", result.error);
+        assert!(
+            std::fs::read_to_string(&file)
+                .unwrap()
+                .contains("impl Reader {")
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
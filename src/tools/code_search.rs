@@ -0,0 +1,288 @@
+// src/tools/code_search.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::cancel::CancellationToken;
+use crate::tools::run_command::capture_process;
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec, object_input};
+use crate::workspace::Workspace;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::process::Command;
+use std::time::Duration;
+
+/// Matches returned per search unless the call asks for fewer.
+const DEFAULT_MAX_RESULTS: usize = 50;
+
+/// Matches taken from any one file, so one noisy file cannot fill the results.
+const MAX_PER_FILE: usize = 10;
+
+/// Longest snippet kept of a matching line, in characters.
+const MAX_SNIPPET_CHARS: usize = 200;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CodeMatch {
+    pub path: String,
+    pub line: u64,
+    /// 1-based byte column of the first match on the line.
+    pub column: u64,
+    /// The matching line, trimmed and cut to `MAX_SNIPPET_CHARS`.
+    pub snippet: String,
+}
+
+/// What `search_code` returns, as JSON.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SearchResults {
+    pub pattern: String,
+    pub matches: Vec<CodeMatch>,
+    /// Matching lines found, including those past the cap.
+    pub total: u64,
+    /// Whether matches were left out by the result or per-file caps.
+    pub truncated: bool,
+}
+
+/// Searches source files with ripgrep (`rg`), honouring `.gitignore`, and
+/// returns matches as path, line and snippet, capped per file and overall so
+/// a broad pattern does not flood the prompt.
+#[derive(Clone, Debug)]
+pub struct CodeSearchTool {
+    workspace: Option<Workspace>,
+    max_results: usize,
+}
+
+impl Default for CodeSearchTool {
+    fn default() -> Self {
+        Self {
+            workspace: None,
+            max_results: DEFAULT_MAX_RESULTS,
+        }
+    }
+}
+
+impl CodeSearchTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Searches inside `workspace`, refusing paths outside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// The most matches a call may return; calls can ask for fewer.
+    pub fn with_max_results(mut self, max: usize) -> Self {
+        self.max_results = max;
+        self
+    }
+
+    fn search(&self, args: &Value, cancel: &CancellationToken) -> ToolResult {
+        let Some(pattern) = args["pattern"].as_str().filter(|p| !p.is_empty()) else {
+            return ToolResult::failure("Missing 'pattern' argument");
+        };
+        let max = args["max_results"]
+            .as_u64()
+            .map_or(self.max_results, |max| (max as usize).min(self.max_results));
+
+        let mut command = Command::new("rg");
+        command
+            .args(["--json", "--max-count"])
+            .arg(MAX_PER_FILE.to_string());
+        if args["fixed_strings"].as_bool().unwrap_or(false) {
+            command.arg("--fixed-strings");
+        }
+        if args["case_insensitive"].as_bool().unwrap_or(false) {
+            command.arg("--ignore-case");
+        }
+        if let Some(glob) = args["glob"].as_str() {
+            command.arg("--glob").arg(glob);
+        }
+        command.arg("--regexp").arg(pattern).arg("--");
+        let path = args["path"].as_str().unwrap_or(".");
+        if let Some(workspace) = &self.workspace {
+            if let Err(e) = workspace.resolve(path) {
+                return ToolResult::failure(&e);
+            }
+            command.current_dir(workspace.root());
+        }
+        command.arg(path);
+
+        let (status, stdout, stderr) = match capture_process(&mut command, cancel) {
+            Ok(output) => output,
+            Err(result)
+                if result
+                    .error
+                    .as_deref()
+                    .is_some_and(|e| e.contains("No such file")) =>
+            {
+                return ToolResult::failure("search_code needs ripgrep (`rg`) on the PATH");
+            }
+            Err(result) => return result,
+        };
+        // rg exits 1 when nothing matched, 2 on errors such as a bad regex.
+        if status.code() != Some(0) && status.code() != Some(1) {
+            return ToolResult::failure(&format!("rg failed: {}", stderr.trim()));
+        }
+        let results = parse_rg_json(pattern, &stdout, max);
+        ToolResult::structured(serde_json::to_value(&results).unwrap_or_default())
+    }
+}
+
+impl Tool for CodeSearchTool {
+    fn name(&self) -> &str {
+        "search_code"
+    }
+
+    fn description(&self) -> &str {
+        "Searches the code with ripgrep and returns matching lines as JSON (path, line, snippet), capped to keep results short."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.execute_cancellable(input, &CancellationToken::new())
+    }
+
+    fn execute_cancellable(&self, input: &str, cancel: &CancellationToken) -> ToolResult {
+        // Input format: the regex to search for, or the JSON arguments.
+        let args = object_input(input).unwrap_or_else(|| json!({ "pattern": input.trim() }));
+        self.search(&args, cancel)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        match args.as_str() {
+            Some(input) => self.execute(input),
+            None => self.search(args, &CancellationToken::new()),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("would search the code for {}", input.trim()))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args["pattern"].as_str().or(args.as_str()) {
+            Some(pattern) => self.preview(pattern),
+            None => SimulationOutcome::read_only("would fail: missing 'pattern' argument"),
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint:
+                "Regex to search for (e.g. 'fn parse_config'), or JSON with pattern, path and glob"
+                    .into(),
+            tags: vec!["search".into(), "code".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string" },
+                    "path": { "type": "string" },
+                    "glob": { "type": "string" },
+                    "fixed_strings": { "type": "boolean" },
+                    "case_insensitive": { "type": "boolean" },
+                    "max_results": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["pattern"]
+            })),
+            timeout: Some(Duration::from_secs(60)),
+            idempotent: true,
+            examples: vec![ToolExample::new(
+                "{\"pattern\": \"fn parse_config\", \"glob\": \"*.rs\"}",
+                "JSON matches with file, line and text, plus the total count",
+            )],
+        }
+    }
+}
+
+/// Collects the `match` messages of `rg --json` output, keeping the first
+/// `max`; `total` counts them all.
+pub fn parse_rg_json(pattern: &str, stdout: &str, max: usize) -> SearchResults {
+    let mut results = SearchResults {
+        pattern: pattern.into(),
+        ..Default::default()
+    };
+    let mut found = 0;
+    for line in stdout.lines() {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let data = &message["data"];
+        match message["type"].as_str() {
+            Some("match") => {
+                found += 1;
+                if results.matches.len() == max {
+                    results.truncated = true;
+                    continue;
+                }
+                let text = data["lines"]["text"].as_str().unwrap_or_default().trim();
+                let mut snippet: String = text.chars().take(MAX_SNIPPET_CHARS).collect();
+                if text.chars().count() > MAX_SNIPPET_CHARS {
+                    snippet.push('…');
+                }
+                results.matches.push(CodeMatch {
+                    path: data["path"]["text"].as_str().unwrap_or_default().into(),
+                    line: data["line_number"].as_u64().unwrap_or_default(),
+                    column: data["submatches"][0]["start"].as_u64().unwrap_or_default() + 1,
+                    snippet,
+                });
+            }
+            Some("end") => {
+                let matched = data["stats"]["matched_lines"].as_u64().unwrap_or_default();
+                if matched >= MAX_PER_FILE as u64 {
+                    // `--max-count` stopped reading this file early.
+                    results.truncated = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    results.total = found;
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_search_results_are_capped_and_trimmed() {
+        let hit = |path: &str, line: u64, text: &str| {
+            json!({
+                "type": "match",
+                "data": {
+                    "path": { "text": path },
+                    "lines": { "text": format!("{}\n", text) },
+                    "line_number": line,
+                    "submatches": [{ "match": { "text": "fn" }, "start": 4, "end": 6 }]
+                }
+            })
+            .to_string()
+        };
+        let end = json!({ "type": "end", "data": { "stats": { "matched_lines": 3 } } });
+        let long = format!("    fn {}", "x".repeat(300));
+        let stdout = [
+            json!({ "type": "begin", "data": { "path": { "text": "src/a.rs" } } }).to_string(),
+            hit("src/a.rs", 3, "    fn parse() {"),
+            hit("src/a.rs", 9, &long),
+            hit("src/b.rs", 1, "    fn run() {"),
+            end.to_string(),
+        ]
+        .join("\n");
+
+        let results = parse_rg_json("fn", &stdout, 2);
+        assert_eq!((results.total, results.truncated), (3, true));
+        assert_eq!(results.matches.len(), 2);
+        let first = &results.matches[0];
+        assert_eq!(
+            (
+                first.path.as_str(),
+                first.line,
+                first.column,
+                first.snippet.as_str()
+            ),
+            ("src/a.rs", 3, 5, "fn parse() {")
+        );
+        assert_eq!(results.matches[1].snippet.chars().count(), 201);
+        assert!(!parse_rg_json("fn", &stdout, 10).truncated);
+    }
+}
@@ -0,0 +1,81 @@
+// src/tools/command_template.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::run_command::preview_command;
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec};
+use std::process::Command;
+
+/// A tool defined by data rather than code: a shell command template where
+/// `{input}` is replaced with the (shell-quoted) tool input.
+#[derive(Clone, Debug)]
+pub struct CommandTemplateTool {
+    pub name: String,
+    pub description: String,
+    pub command: String,
+    pub input_hint: String,
+    pub tags: Vec<String>,
+    pub examples: Vec<ToolExample>,
+}
+
+impl CommandTemplateTool {
+    pub fn new(name: &str, description: &str, command: &str) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            command: command.into(),
+            input_hint: "Freeform string input".into(),
+            tags: vec!["command".into()],
+            examples: Vec::new(),
+        }
+    }
+
+    pub fn render(&self, input: &str) -> String {
+        let quoted = format!("'{}'", input.replace('\'', r"'\''"));
+        self.command.replace("{input}", &quoted)
+    }
+}
+
+impl Tool for CommandTemplateTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        let command = self.render(input);
+        match Command::new("sh").arg("-c").arg(&command).output() {
+            Ok(out) => {
+                let mut result = String::from_utf8_lossy(&out.stdout).to_string();
+                result.push_str(&String::from_utf8_lossy(&out.stderr));
+                if out.status.success() {
+                    ToolResult::success(result.trim())
+                } else {
+                    ToolResult::failure(&format!(
+                        "Command failed (exit code {}): {}",
+                        out.status.code().unwrap_or(-1),
+                        result.trim()
+                    ))
+                }
+            }
+            Err(e) => ToolResult::failure(&format!("Command execution failed: {e}")),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        preview_command(&self.render(input))
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            input_hint: self.input_hint.clone(),
+            tags: self.tags.clone(),
+            examples: self.examples.clone(),
+            ..Default::default()
+        }
+    }
+}
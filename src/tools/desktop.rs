@@ -0,0 +1,235 @@
+// src/tools/desktop.rs
+
+use crate::protocol::{RiskLevel, SimulationOutcome};
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use serde_json::{Value, json};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Title of notifications that do not give one.
+const DEFAULT_TITLE: &str = "agentic";
+
+/// Clipboard programs tried in order, as `(program, args)`; the first one
+/// installed gets the text on stdin.
+fn clipboard_programs() -> Vec<(&'static str, &'static [&'static str])> {
+    match std::env::consts::OS {
+        "macos" => vec![("pbcopy", &[])],
+        "windows" => vec![("clip", &[])],
+        _ => {
+            let mut programs: Vec<(&str, &[&str])> = Vec::new();
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                programs.push(("wl-copy", &[]));
+            }
+            programs.push(("xclip", &["-selection", "clipboard"]));
+            programs.push(("xsel", &["--clipboard", "--input"]));
+            programs
+        }
+    }
+}
+
+/// Puts `text` on the system clipboard through the platform's clipboard
+/// program (`pbcopy`, `clip`, `wl-copy`, `xclip` or `xsel`).
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let programs = clipboard_programs();
+    for (program, args) in &programs {
+        let spawned = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn();
+        let Ok(mut child) = spawned else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("{} failed: {}", program, e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("{} failed: {}", program, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        return Ok(());
+    }
+    Err(format!(
+        "No clipboard program found (tried {})",
+        programs
+            .iter()
+            .map(|(program, _)| *program)
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// Shows a desktop notification with `osascript` on macOS or `notify-send`
+/// elsewhere.
+pub fn notify(title: &str, message: &str) -> Result<(), String> {
+    let mut command = match std::env::consts::OS {
+        "macos" => {
+            let quote =
+                |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+            let mut command = Command::new("osascript");
+            command.arg("-e").arg(format!(
+                "display notification {} with title {}",
+                quote(message),
+                quote(title)
+            ));
+            command
+        }
+        "windows" => return Err("Desktop notifications are not supported on Windows".into()),
+        _ => {
+            let mut command = Command::new("notify-send");
+            command.arg("--").arg(title).arg(message);
+            command
+        }
+    };
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Notification failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Notification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Copies its input (e.g. a previous step's `$output[...]`) to the system
+/// clipboard.
+#[derive(Clone, Debug, Default)]
+pub struct ClipboardTool;
+
+impl ClipboardTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tool for ClipboardTool {
+    fn name(&self) -> &str {
+        "copy_to_clipboard"
+    }
+
+    fn description(&self) -> &str {
+        "Copies text to the user's clipboard, e.g. a generated command or summary."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        match copy_to_clipboard(input) {
+            Ok(()) => ToolResult::success(&format!(
+                "Copied {} characters to the clipboard",
+                input.chars().count()
+            )),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        match args["text"].as_str().or(args.as_str()) {
+            Some(text) => self.execute(text),
+            None => ToolResult::failure("Missing 'text' argument"),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        SimulationOutcome::mutating(&format!(
+            "would copy {} characters to the clipboard",
+            input.chars().count()
+        ))
+        .with_risk(RiskLevel::Low)
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.preview(args["text"].as_str().or(args.as_str()).unwrap_or_default())
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Text to copy".into(),
+            tags: vec!["desktop".into(), "clipboard".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"]
+            })),
+            ..Default::default()
+        }
+    }
+}
+
+/// Shows a desktop notification, e.g. to tell the user a long task finished.
+#[derive(Clone, Debug, Default)]
+pub struct NotifyTool;
+
+impl NotifyTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tool for NotifyTool {
+    fn name(&self) -> &str {
+        "notify"
+    }
+
+    fn description(&self) -> &str {
+        "Shows the user a desktop notification."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.execute_args(&json!({ "message": input }))
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        let Some(message) = args["message"].as_str().or(args.as_str()) else {
+            return ToolResult::failure("Missing 'message' argument");
+        };
+        let title = args["title"].as_str().unwrap_or(DEFAULT_TITLE);
+        match notify(title, message) {
+            Ok(()) => ToolResult::success("Notification shown"),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        SimulationOutcome::mutating(&format!("would notify: {}", input)).with_risk(RiskLevel::Low)
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.preview(
+            args["message"]
+                .as_str()
+                .or(args.as_str())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Notification text".into(),
+            tags: vec!["desktop".into(), "notification".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "message": { "type": "string" }
+                },
+                "required": ["message"]
+            })),
+            ..Default::default()
+        }
+    }
+}
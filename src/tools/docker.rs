@@ -0,0 +1,487 @@
+// src/tools/docker.rs
+
+use crate::protocol::{RiskLevel, SimulationOutcome};
+use crate::tools::cancel::CancellationToken;
+use crate::tools::run_command::capture_process;
+use crate::tools::{Tool, ToolResult, ToolSpec, object_input};
+use crate::workspace::Workspace;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+/// Where the workspace is mounted inside containers, and their working directory.
+pub(crate) const CONTAINER_WORKDIR: &str = "/workspace";
+
+/// Resource limits applied to every container the runtime starts. Calls may
+/// ask for less, never for more.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ContainerLimits {
+    /// Memory cap in docker's notation, e.g. `512m` or `2g`.
+    pub memory: String,
+    pub cpus: f64,
+    /// Most processes the container may run, which stops fork bombs.
+    pub pids: u32,
+    /// Docker network; `none` keeps containers offline.
+    pub network: String,
+}
+
+impl Default for ContainerLimits {
+    fn default() -> Self {
+        Self {
+            memory: "512m".into(),
+            cpus: 1.0,
+            pids: 256,
+            network: "none".into(),
+        }
+    }
+}
+
+impl ContainerLimits {
+    /// Why the limits are unusable, e.g. a memory size docker would reject.
+    pub fn check(&self) -> Result<(), String> {
+        memory_bytes(&self.memory)?;
+        if self.cpus.is_nan() || self.cpus <= 0.0 {
+            return Err(format!("cpus must be above 0, got {}", self.cpus));
+        }
+        if self.pids == 0 {
+            return Err("pids must be above 0".into());
+        }
+        if self.network.is_empty() || self.network.starts_with('-') {
+            return Err(format!("Invalid network '{}'", self.network));
+        }
+        Ok(())
+    }
+
+    /// These limits with the `memory`, `cpus`, `pids` and `network` a call
+    /// asked for; asking for more than these limits allow is an error.
+    pub fn narrowed(&self, args: &Value) -> Result<Self, String> {
+        let mut limits = self.clone();
+        if let Some(memory) = args["memory"].as_str() {
+            if memory_bytes(memory)? > memory_bytes(&self.memory)? {
+                return Err(format!(
+                    "memory {} is above the limit of {}",
+                    memory, self.memory
+                ));
+            }
+            limits.memory = memory.into();
+        }
+        if let Some(cpus) = args["cpus"].as_f64() {
+            if cpus <= 0.0 || cpus > self.cpus {
+                return Err(format!("cpus must be above 0 and at most {}", self.cpus));
+            }
+            limits.cpus = cpus;
+        }
+        if let Some(pids) = args["pids"].as_u64() {
+            if pids == 0 || pids > u64::from(self.pids) {
+                return Err(format!("pids must be above 0 and at most {}", self.pids));
+            }
+            limits.pids = pids as u32;
+        }
+        if let Some(network) = args["network"].as_str()
+            && network != self.network
+        {
+            if network != "none" {
+                return Err(format!(
+                    "network '{}' is not allowed; containers use '{}'",
+                    network, self.network
+                ));
+            }
+            limits.network = network.into();
+        }
+        Ok(limits)
+    }
+
+    fn args(&self) -> Vec<String> {
+        vec![
+            "--memory".into(),
+            self.memory.clone(),
+            "--cpus".into(),
+            self.cpus.to_string(),
+            "--pids-limit".into(),
+            self.pids.to_string(),
+            "--network".into(),
+            self.network.clone(),
+        ]
+    }
+}
+
+/// Bytes in a docker memory size such as `512m`, `2g` or `1048576`.
+fn memory_bytes(size: &str) -> Result<u64, String> {
+    let size = size.trim().to_ascii_lowercase();
+    let digits = size.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let scale: u64 = match size[digits.len()..].trim_end_matches('b') {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        _ => return Err(format!("Invalid memory size '{}'", size)),
+    };
+    match digits.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n * scale),
+        _ => Err(format!("Invalid memory size '{}'", size)),
+    }
+}
+
+/// `docker run --rm` under `limits`, with `mount` (if any) at
+/// `CONTAINER_WORKDIR` as the working directory; the caller appends the
+/// image and command.
+pub(crate) fn docker_run(limits: &ContainerLimits, mount: Option<&Path>) -> Command {
+    let mut command = Command::new("docker");
+    command.args(["run", "--rm"]).args(limits.args());
+    if let Some(dir) = mount {
+        command
+            .arg("-v")
+            .arg(format!("{}:{}", dir.display(), CONTAINER_WORKDIR))
+            .args(["-w", CONTAINER_WORKDIR]);
+    }
+    command
+}
+
+/// One row of `docker ps --all`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Container {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// `running`, `exited`, `created`, ...
+    pub state: String,
+    /// Docker's description, e.g. `Up 2 hours` or `Exited (1) 3 minutes ago`.
+    pub status: String,
+    pub ports: String,
+}
+
+/// Parses `docker ps --format '{{json .}}'` output, one object per line.
+pub fn parse_containers(stdout: &str) -> Vec<Container> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .map(|row| {
+            let field = |key: &str| row[key].as_str().unwrap_or_default().to_string();
+            Container {
+                id: field("ID"),
+                name: field("Names"),
+                image: field("Image"),
+                state: field("State"),
+                status: field("Status"),
+                ports: field("Ports"),
+            }
+        })
+        .collect()
+}
+
+/// Lists containers, builds images and runs one-off containers through the
+/// docker CLI, returning JSON. Every container runs with `--rm` under
+/// `ContainerLimits`, with the workspace mounted at `/workspace` when asked.
+#[derive(Clone, Debug, Default)]
+pub struct DockerTool {
+    workspace: Option<Workspace>,
+    limits: ContainerLimits,
+}
+
+impl DockerTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds from and mounts `workspace`, refusing paths outside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    pub fn with_limits(mut self, limits: ContainerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn docker(&self, args: &Value, cancel: &CancellationToken) -> ToolResult {
+        let outcome = match args["action"].as_str().unwrap_or("list") {
+            "list" => self.list(args, cancel),
+            "build" => self.build(args, cancel),
+            "run" => self.run(args, cancel),
+            other => Err(ToolResult::failure(&format!(
+                "Unknown docker action '{}', expected list, build or run",
+                other
+            ))),
+        };
+        outcome.unwrap_or_else(|result| result)
+    }
+
+    /// Runs `command` in the workspace root and returns its exit status,
+    /// stdout and stderr.
+    fn spawn(
+        &self,
+        command: &mut Command,
+        cancel: &CancellationToken,
+    ) -> Result<(ExitStatus, String, String), ToolResult> {
+        if let Some(workspace) = &self.workspace {
+            command.current_dir(workspace.root());
+        }
+        capture_process(command, cancel).map_err(|result| match result.error.as_deref() {
+            Some(e) if e.contains("No such file") => {
+                ToolResult::failure("The docker tool needs the docker CLI on the PATH")
+            }
+            _ => result,
+        })
+    }
+
+    /// Like `spawn`, with a failed exit as a failure; returns stdout.
+    fn capture(
+        &self,
+        command: &mut Command,
+        cancel: &CancellationToken,
+    ) -> Result<String, ToolResult> {
+        let (status, stdout, stderr) = self.spawn(command, cancel)?;
+        if !status.success() {
+            return Err(ToolResult::failure(&format!(
+                "docker failed (exit code {}): {}",
+                status.code().unwrap_or(-1),
+                stderr.trim()
+            )));
+        }
+        Ok(stdout)
+    }
+
+    fn list(&self, args: &Value, cancel: &CancellationToken) -> Result<ToolResult, ToolResult> {
+        let mut command = Command::new("docker");
+        command.args(["ps", "--no-trunc", "--format", "{{json .}}"]);
+        if args["all"].as_bool().unwrap_or(true) {
+            command.arg("--all");
+        }
+        let stdout = self.capture(&mut command, cancel)?;
+        let containers = parse_containers(&stdout);
+        Ok(ToolResult::structured(json!({ "containers": containers })))
+    }
+
+    fn build(&self, args: &Value, cancel: &CancellationToken) -> Result<ToolResult, ToolResult> {
+        let Some(tag) = args["tag"].as_str().filter(|tag| !tag.is_empty()) else {
+            return Err(ToolResult::failure("Missing 'tag' argument"));
+        };
+        if tag.starts_with('-') || tag.contains(char::is_whitespace) {
+            return Err(ToolResult::failure(&format!("Invalid image tag '{}'", tag)));
+        }
+        let context = args["context"].as_str().unwrap_or(".");
+        let mut paths = vec![context];
+        paths.extend(args["dockerfile"].as_str());
+        if let Some(workspace) = &self.workspace {
+            for path in paths {
+                workspace
+                    .resolve(path)
+                    .map_err(|e| ToolResult::failure(&e))?;
+            }
+        }
+
+        let mut command = Command::new("docker");
+        command.args(["build", "--quiet", "--tag", tag]);
+        if let Some(dockerfile) = args["dockerfile"].as_str() {
+            command.arg("--file").arg(dockerfile);
+        }
+        command.arg("--").arg(context);
+        let stdout = self.capture(&mut command, cancel)?;
+        Ok(ToolResult::structured(
+            json!({ "tag": tag, "id": stdout.trim() }),
+        ))
+    }
+
+    fn run(&self, args: &Value, cancel: &CancellationToken) -> Result<ToolResult, ToolResult> {
+        let Some(image) = args["image"].as_str().filter(|image| !image.is_empty()) else {
+            return Err(ToolResult::failure("Missing 'image' argument"));
+        };
+        if image.starts_with('-') {
+            return Err(ToolResult::failure(&format!("Invalid image '{}'", image)));
+        }
+        let limits = self
+            .limits
+            .narrowed(args)
+            .map_err(|e| ToolResult::failure(&e))?;
+        let mount = match &self.workspace {
+            _ if !args["mount_workspace"].as_bool().unwrap_or(false) => None,
+            Some(workspace) => Some(workspace.root().to_path_buf()),
+            None => std::env::current_dir().ok(),
+        };
+
+        let mut command = docker_run(&limits, mount.as_deref());
+        for (key, value) in args["env"].as_object().into_iter().flatten() {
+            let Some(value) = value.as_str() else {
+                return Err(ToolResult::failure("'env' values must be strings"));
+            };
+            command.arg("--env").arg(format!("{}={}", key, value));
+        }
+        command.arg("--").arg(image);
+        if let Some(shell) = args["command"].as_str() {
+            command.args(["sh", "-c", shell]);
+        }
+        let (status, stdout, stderr) = self.spawn(&mut command, cancel)?;
+        let report = json!({
+            "image": image,
+            "exit_code": status.code(),
+            "stdout": stdout.trim_end(),
+            "stderr": stderr.trim_end(),
+            "limits": limits,
+        });
+        if status.success() {
+            Ok(ToolResult::structured(report))
+        } else {
+            Err(ToolResult::failure(&format!(
+                "Container exited with code {}: {}",
+                status.code().unwrap_or(-1),
+                report
+            ))
+            .with_data(report))
+        }
+    }
+}
+
+impl Tool for DockerTool {
+    fn name(&self) -> &str {
+        "docker"
+    }
+
+    fn description(&self) -> &str {
+        "Lists containers, builds images and runs containers (removed afterwards, with memory, CPU, process and network limits); returns JSON."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.execute_cancellable(input, &CancellationToken::new())
+    }
+
+    fn execute_cancellable(&self, input: &str, cancel: &CancellationToken) -> ToolResult {
+        // Input format: the JSON arguments, or "list".
+        match (object_input(input), input.trim()) {
+            (Some(args), _) => self.docker(&args, cancel),
+            (None, "" | "list" | "ps") => self.docker(&json!({ "action": "list" }), cancel),
+            (None, _) => ToolResult::failure(
+                "docker takes JSON arguments, e.g. {\"action\": \"run\", \"image\": \"alpine\", \"command\": \"echo hi\"}",
+            ),
+        }
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        match args.as_str() {
+            Some(input) => self.execute(input),
+            None => self.docker(args, &CancellationToken::new()),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        self.preview_args(&object_input(input).unwrap_or_else(|| json!({ "action": "list" })))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args["action"].as_str().unwrap_or("list") {
+            "list" => SimulationOutcome::read_only("would list docker containers"),
+            "build" => SimulationOutcome::mutating(&format!(
+                "would build image {} from {}",
+                args["tag"].as_str().unwrap_or("?"),
+                args["context"].as_str().unwrap_or(".")
+            )),
+            "run" => {
+                let outcome = SimulationOutcome::mutating(&format!(
+                    "would run {} in a container{}",
+                    args["image"].as_str().unwrap_or("?"),
+                    args["command"]
+                        .as_str()
+                        .map(|command| format!(": {}", command))
+                        .unwrap_or_default()
+                ));
+                // A container on a network can reach beyond the machine.
+                match self.limits.narrowed(args) {
+                    Ok(limits) if limits.network != "none" => outcome.with_risk(RiskLevel::High),
+                    _ => outcome,
+                }
+            }
+            other => SimulationOutcome::read_only(&format!(
+                "would fail: unknown docker action '{}'",
+                other
+            )),
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "JSON: {action: list} | {action: build, tag, context?, dockerfile?} | {action: run, image, command?, env?, mount_workspace?, memory?, cpus?, pids?}".into(),
+            tags: vec!["docker".into(), "container".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["list", "build", "run"] },
+                    "all": { "type": "boolean" },
+                    "tag": { "type": "string" },
+                    "context": { "type": "string" },
+                    "dockerfile": { "type": "string" },
+                    "image": { "type": "string" },
+                    "command": { "type": "string" },
+                    "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                    "mount_workspace": { "type": "boolean" },
+                    "memory": { "type": "string" },
+                    "cpus": { "type": "number" },
+                    "pids": { "type": "integer", "minimum": 1 },
+                    "network": { "type": "string" }
+                },
+                "required": ["action"]
+            })),
+            timeout: Some(Duration::from_secs(900)),
+            idempotent: false,
+            examples: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docker_limits_only_narrow_and_containers_parse() {
+        let limits = ContainerLimits::default();
+        let narrowed = limits
+            .narrowed(&json!({ "memory": "256m", "cpus": 0.5 }))
+            .unwrap();
+        assert_eq!((narrowed.memory.as_str(), narrowed.cpus), ("256m", 0.5));
+        assert!(limits.narrowed(&json!({ "memory": "1g" })).is_err());
+        assert!(limits.narrowed(&json!({ "pids": 10_000 })).is_err());
+        assert!(limits.narrowed(&json!({ "network": "host" })).is_err());
+        assert!(
+            ContainerLimits {
+                memory: "lots".into(),
+                ..limits.clone()
+            }
+            .check()
+            .is_err()
+        );
+
+        let command = docker_run(&limits, Some(std::path::Path::new("/src")));
+        let args: Vec<_> = command.get_args().map(|a| a.to_str().unwrap()).collect();
+        assert_eq!(
+            args,
+            [
+                "run",
+                "--rm",
+                "--memory",
+                "512m",
+                "--cpus",
+                "1",
+                "--pids-limit",
+                "256",
+                "--network",
+                "none",
+                "-v",
+                "/src:/workspace",
+                "-w",
+                "/workspace"
+            ]
+        );
+
+        let stdout = r#"{"ID":"abc","Names":"web","Image":"nginx","State":"running","Status":"Up 2 hours","Ports":"80/tcp"}"#;
+        let containers = parse_containers(stdout);
+        assert_eq!(containers.len(), 1);
+        assert_eq!(
+            (containers[0].name.as_str(), containers[0].state.as_str()),
+            ("web", "running")
+        );
+    }
+}
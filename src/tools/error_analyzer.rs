@@ -1,5 +1,17 @@
+use crate::llm::ModelTier;
+use crate::protocol::SimulationOutcome;
 use crate::tools::llm::LLMTool;
 use crate::tools::{Tool, ToolResult, ToolSpec};
+use serde::{Deserialize, Serialize};
+
+/// The analyzer's reply; the replanner runs `fix_commands` before retrying.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorAnalysis {
+    pub analysis: String,
+    pub fix_commands: Vec<String>,
+    #[serde(default)]
+    pub explanation: String,
+}
 
 pub struct ErrorAnalyzerTool {
     llm: LLMTool,
@@ -7,7 +19,11 @@ pub struct ErrorAnalyzerTool {
 
 impl ErrorAnalyzerTool {
     pub fn new(llm: LLMTool) -> Self {
-        Self { llm }
+        Self {
+            llm: llm
+                .for_component("error_analyzer")
+                .for_tier(ModelTier::Fast),
+        }
     }
 }
 
@@ -27,6 +43,8 @@ impl Tool for ErrorAnalyzerTool {
 ERROR OUTPUT:
 {}
 
+If the output contains JSON `diagnostics` (file, line, message, suggestion) or test `failures`, they are the compiler's and test runner's own findings: base your fix on those locations and suggestions.
+
 🚨 CRITICAL: Your fix_commands should include BOTH:
 1. Commands to fix the immediate problem
 2. Commands to retry/complete the original operation
@@ -47,31 +65,26 @@ Be specific and actionable. Always include the retry/completion step after the f
             input
         );
 
-        let result = self.llm.execute(&prompt);
-
-        if result.success {
-            // Try to extract JSON from the response
-            if let Some(output) = result.output {
-                // Simple JSON extraction - in production, use proper parsing
-                if output.contains("fix_commands") {
-                    ToolResult::success(&output)
-                } else {
-                    ToolResult::failure("LLM did not provide structured fix suggestions")
-                }
-            } else {
-                ToolResult::failure("No output from error analysis")
-            }
-        } else {
-            ToolResult::failure("Failed to analyze error with LLM")
+        match self.llm.generate_structured::<ErrorAnalysis>(&prompt) {
+            Ok(analysis) => match serde_json::to_string_pretty(&analysis) {
+                Ok(json) => ToolResult::success(&json),
+                Err(e) => ToolResult::failure(&format!("Failed to serialize analysis: {}", e)),
+            },
+            Err(e) => ToolResult::failure(&format!("Failed to analyze error with LLM: {}", e)),
         }
     }
 
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("{} would call the LLM", self.name()))
+    }
+
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: self.name().into(),
             description: self.description().into(),
             input_hint: "Error message or command output to analyze".into(),
             tags: vec!["error".into(), "analysis".into(), "fix".into()],
+            ..Default::default()
         }
     }
 }
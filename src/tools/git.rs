@@ -0,0 +1,830 @@
+// src/tools/git.rs
+
+use crate::protocol::{RiskLevel, SimulationOutcome};
+use crate::tools::{Tool, ToolResult, ToolSpec, object_input};
+use crate::workspace::Workspace;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Matches Conventional Commits subjects like `feat(cli)!: drop --legacy`.
+fn conventional_subject_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^[a-z]+(\([^()]+\))?!?: \S").unwrap())
+}
+
+/// Runs `git <args>` in `workspace`'s root (or the current directory) and
+/// returns its stdout, or stderr as the error.
+fn git(workspace: Option<&Workspace>, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    command.args(args);
+    if let Some(workspace) = workspace {
+        command.current_dir(workspace.root());
+    }
+    let out = command
+        .output()
+        .map_err(|e| format!("Command execution failed: {e}"))?;
+    if out.status.success() {
+        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&out.stderr).trim()
+        ))
+    }
+}
+
+fn current_branch(workspace: Option<&Workspace>) -> Result<String, String> {
+    let branch = git(workspace, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    match branch.trim() {
+        "HEAD" => Err("HEAD is detached; name a branch".into()),
+        branch => Ok(branch.to_string()),
+    }
+}
+
+/// Refuses names git would read as an option or, for push, a forced refspec.
+fn check_ref_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.starts_with(['-', '+']) || name.contains([':', ' ', '\t', '\n']) {
+        return Err(format!("Invalid ref name: '{}'", name));
+    }
+    Ok(())
+}
+
+/// Pairs `git diff --numstat` with `--name-status` output into one entry
+/// per file. Binary files have null line counts.
+fn parse_file_changes(numstat: &str, name_status: &str) -> Vec<Value> {
+    let statuses: Vec<(&str, &str)> = name_status
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect();
+    numstat
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (added, deleted, path) = (fields.next()?, fields.next()?, fields.next()?);
+            let status = statuses
+                .iter()
+                .find(|(_, p)| *p == path)
+                .map_or("M", |(status, _)| status);
+            Some(json!({
+                "path": path,
+                "status": status_name(status),
+                "added": added.parse::<u64>().ok(),
+                "deleted": deleted.parse::<u64>().ok(),
+            }))
+        })
+        .collect()
+}
+
+fn status_name(code: &str) -> &'static str {
+    match code.chars().next() {
+        Some('A') => "added",
+        Some('D') => "deleted",
+        Some('T') => "type_changed",
+        Some('U') => "unmerged",
+        _ => "modified",
+    }
+}
+
+/// Shows working-tree, staged or revision diffs as JSON: a `files` list
+/// (path, status, added/deleted line counts) and the unified `patch`.
+#[derive(Clone, Debug, Default)]
+pub struct GitDiffTool {
+    workspace: Option<Workspace>,
+}
+
+impl GitDiffTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Diffs the repository at `workspace`'s root; paths must be inside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+}
+
+impl Tool for GitDiffTool {
+    fn name(&self) -> &str {
+        "git_diff"
+    }
+
+    fn description(&self) -> &str {
+        "Shows uncommitted, staged or revision changes as JSON (changed files with line counts, plus the patch)."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        // Input format: "", "staged", or a base revision like "main".
+        let args = object_input(input).unwrap_or_else(|| match input.trim() {
+            "" => json!({}),
+            "staged" | "--staged" | "--cached" => json!({ "staged": true }),
+            base => json!({ "base": base }),
+        });
+        self.execute_args(&args)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        if let Some(input) = args.as_str() {
+            return self.execute(input);
+        }
+        let workspace = self.workspace.as_ref();
+        let staged = args["staged"].as_bool().unwrap_or(false);
+        let base = args["base"].as_str();
+        let mut paths = Vec::new();
+        for path in args["paths"].as_array().into_iter().flatten() {
+            let Some(path) = path.as_str() else {
+                return ToolResult::failure("'paths' must be strings");
+            };
+            if let Some(workspace) = workspace
+                && let Err(e) = workspace.resolve(path)
+            {
+                return ToolResult::failure(&e);
+            }
+            paths.push(path);
+        }
+
+        let mut common = vec!["--no-renames"];
+        if staged {
+            common.push("--cached");
+        }
+        if let Some(base) = base {
+            if let Err(e) = check_ref_name(base) {
+                return ToolResult::failure(&e);
+            }
+            common.push(base);
+        }
+        common.push("--");
+        common.extend(&paths);
+        let diff = |format: &str| {
+            let mut args = vec!["diff", format];
+            args.extend(&common);
+            git(workspace, &args)
+        };
+
+        let (numstat, name_status, patch) =
+            match (diff("--numstat"), diff("--name-status"), diff("--patch")) {
+                (Ok(numstat), Ok(name_status), Ok(patch)) => (numstat, name_status, patch),
+                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return ToolResult::failure(&e),
+            };
+        let files = parse_file_changes(&numstat, &name_status);
+        ToolResult::structured(json!({
+            "base": base,
+            "staged": staged,
+            "files": files,
+            "patch": patch,
+        }))
+    }
+
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only("would run `git diff` (read-only)")
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.preview(&args.to_string())
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Empty for unstaged changes, 'staged', or a base revision (e.g. 'main')"
+                .into(),
+            tags: vec!["git".into(), "diff".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "staged": { "type": "boolean" },
+                    "base": { "type": "string" },
+                    "paths": { "type": "array", "items": { "type": "string" } }
+                }
+            })),
+            idempotent: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Rules a commit message must follow before `git_commit` will use it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitPolicy {
+    /// Longest allowed subject line, in characters.
+    pub max_subject_len: usize,
+    /// Require a Conventional Commits subject, e.g. `fix(parser): handle tabs`.
+    pub conventional: bool,
+}
+
+impl Default for CommitPolicy {
+    fn default() -> Self {
+        Self {
+            max_subject_len: 72,
+            conventional: false,
+        }
+    }
+}
+
+impl CommitPolicy {
+    /// Why `message` breaks the policy, if it does.
+    pub fn check(&self, message: &str) -> Result<(), String> {
+        let mut lines = message.lines();
+        let subject = lines.next().unwrap_or_default().trim_end();
+        if subject.trim().is_empty() {
+            return Err("Commit message needs a subject line".into());
+        }
+        let len = subject.chars().count();
+        if len > self.max_subject_len {
+            return Err(format!(
+                "Commit subject is {} characters; keep it to {}",
+                len, self.max_subject_len
+            ));
+        }
+        if subject.ends_with('.') {
+            return Err("Commit subject should not end with a period".into());
+        }
+        if lines.next().is_some_and(|line| !line.trim().is_empty()) {
+            return Err("Separate the commit subject from the body with a blank line".into());
+        }
+        if self.conventional && !conventional_subject_regex().is_match(subject) {
+            return Err(format!(
+                "Commit subject '{}' is not a conventional commit (e.g. 'fix(parser): handle tabs')",
+                subject
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Stages the given paths (or all tracked changes) and commits them, once
+/// the message passes the `CommitPolicy`. Returns the new commit as JSON.
+#[derive(Clone, Debug, Default)]
+pub struct GitCommitTool {
+    workspace: Option<Workspace>,
+    policy: CommitPolicy,
+}
+
+impl GitCommitTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Commits in the repository at `workspace`'s root; paths must be inside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    pub fn with_policy(mut self, policy: CommitPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl Tool for GitCommitTool {
+    fn name(&self) -> &str {
+        "git_commit"
+    }
+
+    fn description(&self) -> &str {
+        "Commits staged changes (staging the given paths first) with a message checked against the commit policy."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        // Input format: the commit message; commits what is already staged.
+        let args = object_input(input).unwrap_or_else(|| json!({ "message": input.trim() }));
+        self.execute_args(&args)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        if let Some(input) = args.as_str() {
+            return self.execute(input);
+        }
+        let Some(message) = args["message"].as_str() else {
+            return ToolResult::failure("Missing 'message' argument");
+        };
+        if let Err(e) = self.policy.check(message) {
+            return ToolResult::failure(&e);
+        }
+        let workspace = self.workspace.as_ref();
+
+        let mut paths = Vec::new();
+        for path in args["paths"].as_array().into_iter().flatten() {
+            let Some(path) = path.as_str() else {
+                return ToolResult::failure("'paths' must be strings");
+            };
+            if let Some(workspace) = workspace
+                && let Err(e) = workspace.resolve(path)
+            {
+                return ToolResult::failure(&e);
+            }
+            paths.push(path);
+        }
+        if !paths.is_empty() {
+            let mut add = vec!["add", "--"];
+            add.extend(&paths);
+            if let Err(e) = git(workspace, &add) {
+                return ToolResult::failure(&e);
+            }
+        }
+
+        let mut commit = vec!["commit", "--file", "-"];
+        if args["all"].as_bool().unwrap_or(false) {
+            commit.push("--all");
+        }
+        let committed = git_with_stdin(workspace, &commit, message)
+            .and_then(|_| git(workspace, &["rev-parse", "HEAD"]))
+            .and_then(|sha| {
+                let sha = sha.trim().to_string();
+                let show = |format: &str| {
+                    git(
+                        workspace,
+                        &[
+                            "diff-tree",
+                            "--root",
+                            "--no-commit-id",
+                            "--no-renames",
+                            "-r",
+                            format,
+                            &sha,
+                        ],
+                    )
+                };
+                let files = parse_file_changes(&show("--numstat")?, &show("--name-status")?);
+                Ok(json!({
+                    "commit": sha,
+                    "branch": current_branch(workspace).ok(),
+                    "subject": message.lines().next().unwrap_or_default().trim(),
+                    "files": files,
+                }))
+            });
+        match committed {
+            Ok(commit) => ToolResult::structured(commit),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        match self.policy.check(input.trim()) {
+            Ok(()) => SimulationOutcome::mutating("would create a git commit"),
+            Err(e) => SimulationOutcome::read_only(&format!("would fail: {}", e)),
+        }
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args["message"].as_str().or(args.as_str()) {
+            Some(message) => self.preview(message),
+            None => SimulationOutcome::read_only("would fail: missing 'message' argument"),
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Commit message for the staged changes (e.g. 'fix: handle empty input')"
+                .into(),
+            tags: vec!["git".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "message": { "type": "string" },
+                    "paths": { "type": "array", "items": { "type": "string" } },
+                    "all": { "type": "boolean" }
+                },
+                "required": ["message"]
+            })),
+            ..Default::default()
+        }
+    }
+}
+
+/// Like `git`, feeding `stdin` to the command.
+fn git_with_stdin(
+    workspace: Option<&Workspace>,
+    args: &[&str],
+    stdin: &str,
+) -> Result<String, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(workspace) = workspace {
+        command.current_dir(workspace.root());
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Command execution failed: {e}"))?;
+    if let Some(mut pipe) = child.stdin.take() {
+        pipe.write_all(stdin.as_bytes())
+            .map_err(|e| format!("Command execution failed: {e}"))?;
+    }
+    let out = child
+        .wait_with_output()
+        .map_err(|e| format!("Command execution failed: {e}"))?;
+    if out.status.success() {
+        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+    } else {
+        // `git commit` reports "nothing to commit" on stdout.
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        let reason = if stderr.trim().is_empty() {
+            stdout
+        } else {
+            stderr
+        };
+        Err(format!("git {} failed: {}", args[0], reason.trim()))
+    }
+}
+
+/// Lists local branches as JSON (current, upstream, ahead/behind) and
+/// creates, switches or deletes them. Deleting only removes merged branches.
+#[derive(Clone, Debug, Default)]
+pub struct GitBranchTool {
+    workspace: Option<Workspace>,
+}
+
+impl GitBranchTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Manages branches of the repository at `workspace`'s root.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    fn list(&self) -> Result<Value, String> {
+        let refs = git(
+            self.workspace.as_ref(),
+            &[
+                "for-each-ref",
+                "--format=%(HEAD)%09%(refname:short)%09%(upstream:short)%09%(upstream:track)",
+                "refs/heads",
+            ],
+        )?;
+        Ok(Value::Array(
+            refs.lines().filter_map(parse_branch).collect(),
+        ))
+    }
+}
+
+/// One `for-each-ref` line: head marker, name, upstream and tracking state
+/// like `[ahead 1, behind 2]` or `[gone]`.
+fn parse_branch(line: &str) -> Option<Value> {
+    let mut fields = line.split('\t');
+    let (head, name) = (fields.next()?, fields.next()?);
+    let upstream = fields.next().filter(|upstream| !upstream.is_empty());
+    let track = fields.next().unwrap_or_default();
+    let count = |label: &str| {
+        track
+            .trim_matches(['[', ']'])
+            .split(", ")
+            .find_map(|part| part.strip_prefix(label)?.trim().parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    Some(json!({
+        "name": name,
+        "current": head == "*",
+        "upstream": upstream,
+        "ahead": count("ahead"),
+        "behind": count("behind"),
+        "upstream_gone": track == "[gone]",
+    }))
+}
+
+impl Tool for GitBranchTool {
+    fn name(&self) -> &str {
+        "git_branch"
+    }
+
+    fn description(&self) -> &str {
+        "Lists branches as JSON, or creates, switches to or deletes (merged only) a branch."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        // Input format: "list", "create <name> [start]", "switch <name>" or "delete <name>".
+        let args = object_input(input).unwrap_or_else(|| {
+            let mut parts = input.split_whitespace();
+            json!({
+                "action": parts.next().unwrap_or("list"),
+                "name": parts.next(),
+                "start": parts.next(),
+            })
+        });
+        self.execute_args(&args)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        if let Some(input) = args.as_str() {
+            return self.execute(input);
+        }
+        let action = args["action"].as_str().unwrap_or("list");
+        if action == "list" {
+            return match self.list() {
+                Ok(branches) => ToolResult::structured(branches),
+                Err(e) => ToolResult::failure(&e),
+            };
+        }
+        let Some(name) = args["name"].as_str() else {
+            return ToolResult::failure(&format!("'{}' needs a branch 'name'", action));
+        };
+        if let Err(e) = check_ref_name(name) {
+            return ToolResult::failure(&e);
+        }
+        let start = args["start"].as_str();
+        if let Some(Err(e)) = start.map(check_ref_name) {
+            return ToolResult::failure(&e);
+        }
+
+        let command = match action {
+            "create" => {
+                let mut command = vec!["branch", name];
+                command.extend(start);
+                command
+            }
+            "switch" => vec!["switch", name],
+            "delete" => vec!["branch", "--delete", name],
+            other => {
+                return ToolResult::failure(&format!(
+                    "Unknown branch action '{}', expected list, create, switch or delete",
+                    other
+                ));
+            }
+        };
+        match git(self.workspace.as_ref(), &command).and_then(|_| self.list()) {
+            Ok(branches) => ToolResult::success(&branches.to_string()),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        match input.split_whitespace().next().unwrap_or("list") {
+            "list" => SimulationOutcome::read_only("would list branches"),
+            action => SimulationOutcome::mutating(&format!("would {} a branch", action)),
+        }
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args.as_str() {
+            Some(input) => self.preview(input),
+            None => self.preview(args["action"].as_str().unwrap_or("list")),
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "'list', 'create <name> [start]', 'switch <name>' or 'delete <name>'"
+                .into(),
+            tags: vec!["git".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["list", "create", "switch", "delete"] },
+                    "name": { "type": "string" },
+                    "start": { "type": "string" }
+                }
+            })),
+            ..Default::default()
+        }
+    }
+}
+
+/// Pushes a branch and reports each ref update from `git push --porcelain`
+/// as JSON. Never force-pushes unless built with `allow_force_push`, and
+/// then only with `--force-with-lease`.
+#[derive(Clone, Debug, Default)]
+pub struct GitPushTool {
+    workspace: Option<Workspace>,
+    allow_force: bool,
+}
+
+impl GitPushTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pushes from the repository at `workspace`'s root.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Lets calls pass `force: true`, pushing with `--force-with-lease`.
+    pub fn allow_force_push(mut self) -> Self {
+        self.allow_force = true;
+        self
+    }
+}
+
+/// The ref lines of `git push --porcelain`: `<flag>\t<from>:<to>\t<summary>`.
+fn parse_push(porcelain: &str) -> Vec<Value> {
+    porcelain
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (flag, refs) = (fields.next()?, fields.next()?);
+            let (from, to) = refs.split_once(':')?;
+            let status = match flag {
+                " " => "fast_forward",
+                "+" => "forced",
+                "-" => "deleted",
+                "*" => "new",
+                "=" => "up_to_date",
+                "!" => "rejected",
+                _ => return None,
+            };
+            Some(json!({
+                "from": from,
+                "to": to,
+                "status": status,
+                "summary": fields.next().unwrap_or_default().trim(),
+            }))
+        })
+        .collect()
+}
+
+impl Tool for GitPushTool {
+    fn name(&self) -> &str {
+        "git_push"
+    }
+
+    fn description(&self) -> &str {
+        "Pushes a branch (the current one by default) to a remote and reports the ref updates as JSON. Never force-pushes unless allowed."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        // Input format: "[remote] [branch]", defaulting to origin and the current branch.
+        let args = object_input(input).unwrap_or_else(|| {
+            let mut parts = input.split_whitespace();
+            json!({ "remote": parts.next(), "branch": parts.next() })
+        });
+        self.execute_args(&args)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        if let Some(input) = args.as_str() {
+            return self.execute(input);
+        }
+        let force = args["force"].as_bool().unwrap_or(false);
+        if force && !self.allow_force {
+            return ToolResult::failure(
+                "Force-push is not allowed; set tools.git.allow_force_push to enable it",
+            );
+        }
+        let workspace = self.workspace.as_ref();
+        let remote = args["remote"].as_str().unwrap_or("origin");
+        let branch = match args["branch"].as_str() {
+            Some(branch) => branch.to_string(),
+            None => match current_branch(workspace) {
+                Ok(branch) => branch,
+                Err(e) => return ToolResult::failure(&e),
+            },
+        };
+        if let Err(e) = check_ref_name(remote).and_then(|_| check_ref_name(&branch)) {
+            return ToolResult::failure(&e);
+        }
+
+        let mut command = vec!["push", "--porcelain"];
+        if args["set_upstream"].as_bool().unwrap_or(false) {
+            command.push("--set-upstream");
+        }
+        if force {
+            command.push("--force-with-lease");
+        }
+        command.extend([remote, branch.as_str()]);
+
+        let mut push = Command::new("git");
+        push.args(&command);
+        if let Some(workspace) = workspace {
+            push.current_dir(workspace.root());
+        }
+        let out = match push.output() {
+            Ok(out) => out,
+            Err(e) => return ToolResult::failure(&format!("Command execution failed: {e}")),
+        };
+        // Ref updates go to stdout even when the push is rejected.
+        let updates = parse_push(&String::from_utf8_lossy(&out.stdout));
+        let report = json!({ "remote": remote, "branch": branch, "updates": updates });
+        if out.status.success() {
+            ToolResult::structured(report)
+        } else {
+            ToolResult::failure(&format!(
+                "git push failed: {} {}",
+                String::from_utf8_lossy(&out.stderr).trim(),
+                report
+            ))
+            .with_data(report)
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        let target = match input.trim() {
+            "" => "the current branch to origin".to_string(),
+            target => target.to_string(),
+        };
+        SimulationOutcome::mutating(&format!("would push {}", target)).with_risk(RiskLevel::High)
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args.as_str() {
+            Some(input) => self.preview(input),
+            None => self.preview(&format!(
+                "{} to {}",
+                args["branch"].as_str().unwrap_or("the current branch"),
+                args["remote"].as_str().unwrap_or("origin")
+            )),
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Optional remote and branch (e.g. 'origin feature/login'); empty pushes the current branch to origin".into(),
+            tags: vec!["git".into(), "network".into(), "destructive".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "remote": { "type": "string" },
+                    "branch": { "type": "string" },
+                    "set_upstream": { "type": "boolean" },
+                    "force": { "type": "boolean" }
+                }
+            })),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn git_tools_report_json_and_enforce_policies() {
+        let dir = std::env::temp_dir().join(format!("agentic-git-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for args in [
+            &["init", "-q", "-b", "main"][..],
+            &["config", "user.email", "dev@example.com"],
+            &["config", "user.name", "Dev"],
+        ] {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(&dir)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        std::fs::write(dir.join("notes.txt"), "one\ntwo\n").unwrap();
+        let workspace = Workspace::new(&dir).unwrap();
+        let json = |result: ToolResult| -> Value {
+            assert!(result.success, "{:?}", result.error);
+            serde_json::from_str(&result.output.unwrap()).unwrap()
+        };
+
+        let commit = GitCommitTool::new()
+            .in_workspace(workspace.clone())
+            .with_policy(CommitPolicy {
+                conventional: true,
+                ..CommitPolicy::default()
+            });
+        let args = |message: &str| json!({ "message": message, "paths": ["notes.txt"] });
+        assert!(!commit.execute_args(&args("Add notes")).success);
+        assert!(!commit.execute_args(&args("docs: add notes.")).success);
+        let committed = json(commit.execute_args(&args("docs: add notes")));
+        assert_eq!(committed["branch"], "main");
+        assert_eq!(committed["files"][0]["added"], 2);
+
+        std::fs::write(dir.join("notes.txt"), "one\n").unwrap();
+        let diff = json(
+            GitDiffTool::new()
+                .in_workspace(workspace.clone())
+                .execute(""),
+        );
+        assert_eq!(diff["files"][0]["path"], "notes.txt");
+        assert_eq!(diff["files"][0]["status"], "modified");
+        assert_eq!(diff["files"][0]["deleted"], 1);
+
+        let branches = GitBranchTool::new().in_workspace(workspace.clone());
+        let listed = json(branches.execute("create feature"));
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+        assert_eq!(listed[0]["current"], false);
+        assert!(!branches.execute("create -D").success);
+
+        let push = GitPushTool::new().in_workspace(workspace);
+        let forced = push.execute_args(&json!({ "force": true }));
+        assert!(forced.error.unwrap().contains("not allowed"));
+        assert!(!push.execute("origin +main").success);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
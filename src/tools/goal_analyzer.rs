@@ -1,3 +1,5 @@
+use crate::llm::ModelTier;
+use crate::protocol::SimulationOutcome;
 use crate::tools::llm::LLMTool;
 use crate::tools::{Tool, ToolResult, ToolSpec};
 use serde::{Deserialize, Serialize};
@@ -18,13 +20,18 @@ pub struct PlanExample {
     pub json_plan: String,
 }
 
+/// Tools assumed when the analyzer is invoked directly as a tool.
+const DEFAULT_TOOLS: [&str; 3] = ["run_command", "reflect", "analyze_error"];
+
 pub struct GoalAnalyzerTool {
     llm: LLMTool,
 }
 
 impl GoalAnalyzerTool {
     pub fn new(llm: LLMTool) -> Self {
-        Self { llm }
+        Self {
+            llm: llm.for_component("goal_analyzer").for_tier(ModelTier::Fast),
+        }
     }
 
     pub fn analyze_context(
@@ -32,6 +39,7 @@ impl GoalAnalyzerTool {
         goal: &str,
         memory_log: &str,
         is_replanning: bool,
+        available_tools: &[String],
     ) -> Result<GoalAnalysis, String> {
         let context_type = if is_replanning {
             if memory_log.contains("error_analysis")
@@ -61,7 +69,7 @@ TASK: Analyze this goal and context to generate:
 4. Custom output format instructions
 5. Context-specific critical rules
 
-AVAILABLE_TOOLS: ["run_command", "reflect", "analyze_error"]
+AVAILABLE_TOOLS: {}
 
 OUTPUT ONLY this JSON structure:
 {{
@@ -128,42 +136,16 @@ For error_recovery context, examples should follow this pattern:
 Example error recovery pattern:
 "json_plan": "{{\\\"plan\\\": [{{\\\"type\\\": \\\"tool\\\", \\\"name\\\": \\\"run_command\\\", \\\"input\\\": \\\"cargo fmt\\\"}}, {{\\\"type\\\": \\\"tool\\\", \\\"name\\\": \\\"run_command\\\", \\\"input\\\": \\\"git commit -m 'Fix formatting and commit changes'\\\"}}, {{\\\"type\\\": \\\"info\\\", \\\"message\\\": \\\"Goal completed\\\"}}]}}"
 "#,
-            goal, context_type, memory_log, context_type
+            goal,
+            context_type,
+            memory_log,
+            serde_json::to_string(available_tools).unwrap_or_default(),
+            context_type
         );
 
-        let result = self.llm.execute(&prompt);
-
-        if !result.success {
-            return Err(format!("LLM execution failed: {:?}", result.error));
-        }
-
-        let response = result.output.unwrap_or_default();
-
-        // Extract JSON from response (handle thinking model output)
-        let post_think = if response.contains("</think>") {
-            response.split("</think>").last().unwrap_or(&response)
-        } else {
-            &response
-        };
-
-        let json_start = post_think.find('{').unwrap_or(0);
-        let json_end = post_think
-            .rfind('}')
-            .map(|i| i + 1)
-            .unwrap_or(post_think.len());
-        let json_str = &post_think[json_start..json_end];
-
-        if json_str.trim().is_empty() {
-            return Err(format!("No JSON found in response: {}", response));
-        }
-
-        match serde_json::from_str::<GoalAnalysis>(json_str) {
-            Ok(analysis) => Ok(analysis),
-            Err(e) => Err(format!(
-                "Failed to parse goal analysis JSON: {} | JSON: {}",
-                e, json_str
-            )),
-        }
+        self.llm
+            .generate_structured::<GoalAnalysis>(&prompt)
+            .map_err(|e| format!("Goal analysis failed: {}", e))
     }
 }
 
@@ -187,7 +169,8 @@ impl Tool for GoalAnalyzerTool {
         let memory_log = parts[1];
         let is_replanning = parts[2] == "true";
 
-        match self.analyze_context(goal, memory_log, is_replanning) {
+        let default_tools = DEFAULT_TOOLS.map(String::from);
+        match self.analyze_context(goal, memory_log, is_replanning, &default_tools) {
             Ok(analysis) => match serde_json::to_string_pretty(&analysis) {
                 Ok(json) => ToolResult::success(&json),
                 Err(e) => ToolResult::failure(&format!("Failed to serialize analysis: {}", e)),
@@ -196,6 +179,10 @@ impl Tool for GoalAnalyzerTool {
         }
     }
 
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("{} would call the LLM", self.name()))
+    }
+
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: self.name().into(),
@@ -203,6 +190,7 @@ impl Tool for GoalAnalyzerTool {
             input_hint: "goal|memory_log|is_replanning (e.g., 'commit changes|[memory]|false')"
                 .into(),
             tags: vec!["meta".into(), "planning".into(), "analysis".into()],
+            ..Default::default()
         }
     }
 }
@@ -0,0 +1,133 @@
+// src/tools/goal_verifier.rs
+
+use crate::llm::json_repair;
+use crate::protocol::verifier::{Verdict, Verifier};
+use crate::protocol::{ExecutionResult, SimulationOutcome};
+use crate::tools::llm::LLMTool;
+use crate::tools::{Tool, ToolResult, ToolSpec};
+
+/// Longest tool output (in chars) quoted to the LLM per step.
+const MAX_EVIDENCE_CHARS: usize = 1500;
+
+/// Default `Verifier`: asks the LLM whether the recorded step outputs show
+/// the goal was achieved.
+pub struct GoalVerifierTool {
+    llm: LLMTool,
+}
+
+impl GoalVerifierTool {
+    pub fn new(llm: LLMTool) -> Self {
+        Self {
+            llm: llm.for_component("goal_verifier"),
+        }
+    }
+
+    pub fn verify_evidence(&self, goal: &str, evidence: &str) -> Result<Verdict, String> {
+        let prompt = format!(
+            r#"You are a strict verifier inside an autonomous agent runtime.
+
+Decide whether the GOAL below was actually achieved, judging ONLY from the execution evidence.
+Commands that ran without error do not prove the goal was met; look for concrete output that shows it.
+
+GOAL: {goal}
+
+EXECUTION EVIDENCE:
+{evidence}
+
+Respond with ONLY a JSON object in this format:
+{{
+  "achieved": true,
+  "confidence": 0.0,
+  "reasoning": "One or two sentences citing the evidence",
+  "missing": ["What still needs to be done, empty if achieved"]
+}}"#
+        );
+
+        let result = self.llm.execute(&prompt);
+        if !result.success {
+            return Err(format!("LLM execution failed: {:?}", result.error));
+        }
+        let response = result.output.unwrap_or_default();
+
+        json_repair::parse_json::<Verdict>(&response)
+            .map(|mut verdict| {
+                verdict.confidence = verdict.confidence.clamp(0.0, 1.0);
+                verdict
+            })
+            .map_err(|e| format!("Failed to parse verdict: {}", e))
+    }
+}
+
+/// Renders the per-step results of `result` as plain-text evidence.
+pub(crate) fn render_evidence(result: &ExecutionResult) -> String {
+    let mut evidence = String::new();
+    for (i, step) in result.steps.iter().enumerate() {
+        let status = if step.success { "ok" } else { "FAILED" };
+        let text = step
+            .output
+            .as_deref()
+            .or(step.error.as_deref())
+            .unwrap_or("");
+        let text: String = text.chars().take(MAX_EVIDENCE_CHARS).collect();
+        evidence.push_str(&format!(
+            "{}. [{}] {} {}\n{}\n\n",
+            i + 1,
+            status,
+            step.tool,
+            step.input,
+            text.trim()
+        ));
+    }
+    if evidence.is_empty() {
+        evidence.push_str("(no tool calls were executed)\n");
+    }
+    if !result.errors.is_empty() {
+        evidence.push_str(&format!("ERRORS:\n{}\n", result.errors.join("\n")));
+    }
+    evidence
+}
+
+impl Verifier for GoalVerifierTool {
+    fn verify(&self, goal: &str, result: &ExecutionResult) -> Result<Verdict, String> {
+        self.verify_evidence(goal, &render_evidence(result))
+    }
+}
+
+impl Tool for GoalVerifierTool {
+    fn name(&self) -> &str {
+        "verify_goal"
+    }
+
+    fn description(&self) -> &str {
+        "Judges whether a goal was achieved from execution evidence and returns a JSON verdict."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        // Input format: "goal|evidence"
+        let Some((goal, evidence)) = input.split_once('|') else {
+            return ToolResult::failure("Input must be: goal|evidence");
+        };
+
+        match self.verify_evidence(goal.trim(), evidence) {
+            Ok(verdict) => match serde_json::to_string_pretty(&verdict) {
+                Ok(json) => ToolResult::success(&json),
+                Err(e) => ToolResult::failure(&format!("Failed to serialize verdict: {}", e)),
+            },
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("{} would call the LLM", self.name()))
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "goal|evidence (goal text, a pipe, then the outputs to judge)".into(),
+            tags: vec!["verification".into(), "evaluation".into(), "llm".into()],
+            ..Default::default()
+        }
+    }
+}
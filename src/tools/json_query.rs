@@ -0,0 +1,1233 @@
+// src/tools/json_query.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec};
+use regex::Regex;
+use serde_json::{Map, Value, json};
+use std::cmp::Ordering;
+
+/// Applies a jq-style query to JSON text, typically a previous step's
+/// `$output[...]`, so plans can pull fields out of structured tool output
+/// without another LLM call.
+///
+/// Supports paths (`.a.b`, `.[0]`, `.[-1]`, `.[2:4]`, `.[]`, `..`), `|`, `,`,
+/// `//`, `?`, arithmetic, comparisons, `and`/`or`, array and object
+/// construction, and the common builtins (`select`, `map`, `length`, `keys`,
+/// `sort_by`, `group_by`, `join`, `test`, ...). Variables, `if`, `reduce` and
+/// paths-based builtins (`del`, `paths`) are not supported.
+#[derive(Clone, Debug, Default)]
+pub struct JsonQueryTool;
+
+impl JsonQueryTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tool for JsonQueryTool {
+    fn name(&self) -> &str {
+        "json_query"
+    }
+
+    fn description(&self) -> &str {
+        "Extracts data from JSON text (e.g. a previous step's $output[...]) with a jq-style query like '.items[] | select(.status != \"Running\") | .name'."
+    }
+
+    /// The query on the first line, the JSON on the rest.
+    fn execute(&self, input: &str) -> ToolResult {
+        let (query, data) = input.trim_start().split_once('\n').unwrap_or((input, ""));
+        self.execute_args(&json!({ "query": query, "input": data }))
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        let Some(query) = args["query"].as_str() else {
+            return ToolResult::failure("Missing 'query' argument");
+        };
+        let raw = args["raw"].as_bool().unwrap_or(true);
+        let inputs = match &args["input"] {
+            Value::String(text) => match parse_inputs(text) {
+                Ok(inputs) => inputs,
+                Err(e) => return ToolResult::failure(&e),
+            },
+            Value::Null => return ToolResult::failure("Missing 'input' argument"),
+            other => vec![other.clone()],
+        };
+        let mut lines = Vec::new();
+        for input in &inputs {
+            match query_values(query, input) {
+                Ok(results) => lines.extend(results.iter().map(|value| match value {
+                    Value::String(text) if raw => text.clone(),
+                    other => other.to_string(),
+                })),
+                Err(e) => return ToolResult::failure(&format!("Query '{}' failed: {}", query, e)),
+            }
+        }
+        ToolResult::success(&lines.join("\n"))
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        let query = input.trim_start().lines().next().unwrap_or_default();
+        SimulationOutcome::read_only(&format!("would apply query {}", query))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.preview(args["query"].as_str().unwrap_or_default())
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "{\"query\": \".items[].name\", \"input\": \"$output[step]\"}".into(),
+            tags: vec!["json".into(), "transform".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "input": {},
+                    "raw": { "type": "boolean" }
+                },
+                "required": ["query", "input"]
+            })),
+            timeout: None,
+            idempotent: true,
+            examples: vec![ToolExample::new(
+                "{\"query\": \".items[] | select(.status == \\\"failed\\\") | .name\", \"input\": \"$output[api]\", \"raw\": true}",
+                "the names of the failed items, one per line",
+            )],
+        }
+    }
+}
+
+/// The JSON values in `text`: one document, a stream of them (JSON lines),
+/// or failing both, the first object or array embedded in other output.
+pub fn parse_inputs(text: &str) -> Result<Vec<Value>, String> {
+    let stream: Result<Vec<Value>, _> = serde_json::Deserializer::from_str(text)
+        .into_iter::<Value>()
+        .collect();
+    match stream {
+        Ok(values) if !values.is_empty() => return Ok(values),
+        _ => {}
+    }
+    for (start, _) in text.match_indices(['{', '[']) {
+        let mut values = serde_json::Deserializer::from_str(&text[start..]).into_iter::<Value>();
+        if let Some(Ok(value)) = values.next() {
+            return Ok(vec![value]);
+        }
+    }
+    Err("Input is not JSON".into())
+}
+
+/// Every result of `query` applied to `input`.
+pub fn query_values(query: &str, input: &Value) -> Result<Vec<Value>, String> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let filter = if parser.tokens.is_empty() {
+        Filter::Identity
+    } else {
+        parser.pipe()?
+    };
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {}", token));
+    }
+    filter.eval(input)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Dot,
+    /// `.name`
+    Field(String),
+    /// `..`
+    Recurse,
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Sym(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Dot => f.write_str("'.'"),
+            Token::Field(name) => write!(f, "'.{}'", name),
+            Token::Recurse => f.write_str("'..'"),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Str(text) => write!(f, "{:?}", text),
+            Token::Num(n) => write!(f, "'{}'", n),
+            Token::Sym(sym) => write!(f, "'{}'", sym),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 23] = [
+    "//", "==", "!=", "<=", ">=", "|", ",", "<", ">", "+", "-", "*", "/", "%", "?", "[", "]", "(",
+    ")", "{", "}", ":", ";",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '.' {
+            match chars.get(i + 1) {
+                Some('.') => {
+                    tokens.push(Token::Recurse);
+                    i += 2;
+                }
+                Some(&next) if next.is_ascii_alphabetic() || next == '_' => {
+                    let start = i + 1;
+                    i = start;
+                    while i < chars.len() && is_ident(chars[i]) {
+                        i += 1;
+                    }
+                    tokens.push(Token::Field(chars[start..i].iter().collect()));
+                }
+                _ => {
+                    tokens.push(Token::Dot);
+                    i += 1;
+                }
+            }
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let exponent = i + 1 + usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                if chars.get(exponent).is_some_and(char::is_ascii_digit) {
+                    i = exponent;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let number: String = chars[start..i].iter().collect();
+            let value = number
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", number))?;
+            tokens.push(Token::Num(value));
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && is_ident(chars[i]) {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+        if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("unterminated string".into()),
+                    Some('"') => break,
+                    Some('\\') => {
+                        let escaped = match chars.get(i + 1) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('r') => '\r',
+                            Some(c @ ('"' | '\\' | '/')) => *c,
+                            Some('(') => return Err("string interpolation is not supported".into()),
+                            Some(other) => return Err(format!("unknown escape '\\{}'", other)),
+                            None => return Err("unterminated string".into()),
+                        };
+                        text.push(escaped);
+                        i += 2;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Str(text));
+            i += 1;
+            continue;
+        }
+        if c == '$' {
+            return Err("variables are not supported".into());
+        }
+        let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        let Some(symbol) = SYMBOLS.iter().find(|sym| rest.starts_with(**sym)) else {
+            return Err(format!("unexpected '{}'", c));
+        };
+        tokens.push(Token::Sym(symbol));
+        i += symbol.len();
+    }
+    Ok(tokens)
+}
+
+/// A parsed query.
+#[derive(Clone, Debug)]
+enum Filter {
+    Identity,
+    Recurse,
+    Literal(Value),
+    /// `target[key]`; string keys index objects, numbers index arrays.
+    Index(Box<Filter>, Box<Filter>),
+    Slice(Box<Filter>, Option<Box<Filter>>, Option<Box<Filter>>),
+    Iterate(Box<Filter>),
+    /// `f?`: errors become no output.
+    Try(Box<Filter>),
+    Array(Option<Box<Filter>>),
+    Object(Vec<(Filter, Filter)>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Alternative(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Binary(&'static str, Box<Filter>, Box<Filter>),
+    Neg(Box<Filter>),
+    Call(String, Vec<Filter>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, sym: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Sym(s)) if *s == sym) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Sym(s)) if s == sym => Ok(()),
+            Some(token) => Err(format!("expected '{}', found {}", sym, token)),
+            None => Err(format!("expected '{}' at the end", sym)),
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Filter, String> {
+        let left = self.comma()?;
+        if self.eat("|") {
+            return Ok(Filter::Pipe(Box::new(left), Box::new(self.pipe()?)));
+        }
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Filter, String> {
+        let mut left = self.alternative()?;
+        while self.eat(",") {
+            left = Filter::Comma(Box::new(left), Box::new(self.alternative()?));
+        }
+        Ok(left)
+    }
+
+    fn alternative(&mut self) -> Result<Filter, String> {
+        let left = self.or()?;
+        if self.eat("//") {
+            return Ok(Filter::Alternative(
+                Box::new(left),
+                Box::new(self.alternative()?),
+            ));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Ident("or".into())) {
+            self.pos += 1;
+            left = Filter::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut left = self.comparison()?;
+        while self.peek() == Some(&Token::Ident("and".into())) {
+            self.pos += 1;
+            left = Filter::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Filter, String> {
+        let left = self.additive()?;
+        if let Some(Token::Sym(op @ ("==" | "!=" | "<" | "<=" | ">" | ">="))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            return Ok(Filter::Binary(
+                op,
+                Box::new(left),
+                Box::new(self.additive()?),
+            ));
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Filter, String> {
+        let mut left = self.multiplicative()?;
+        while let Some(Token::Sym(op @ ("+" | "-"))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            left = Filter::Binary(op, Box::new(left), Box::new(self.multiplicative()?));
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Filter, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Sym(op @ ("*" | "/" | "%"))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            left = Filter::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        if self.eat("-") {
+            return Ok(Filter::Neg(Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Filter, String> {
+        let mut filter = self.primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Field(name)) => {
+                    let key = Filter::Literal(Value::String(name.clone()));
+                    self.pos += 1;
+                    filter = Filter::Index(Box::new(filter), Box::new(key));
+                }
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    filter = match self.next() {
+                        Some(Token::Str(key)) => Filter::Index(
+                            Box::new(filter),
+                            Box::new(Filter::Literal(Value::String(key))),
+                        ),
+                        Some(Token::Sym("[")) => self.bracket(filter)?,
+                        Some(token) => return Err(format!("unexpected {} after '.'", token)),
+                        None => return Err("unexpected end after '.'".into()),
+                    };
+                }
+                Some(Token::Sym("[")) => {
+                    self.pos += 1;
+                    filter = self.bracket(filter)?;
+                }
+                Some(Token::Sym("?")) => {
+                    self.pos += 1;
+                    filter = Filter::Try(Box::new(filter));
+                }
+                _ => return Ok(filter),
+            }
+        }
+    }
+
+    /// The rest of `target[...]`, after the `[`.
+    fn bracket(&mut self, target: Filter) -> Result<Filter, String> {
+        let target = Box::new(target);
+        if self.eat("]") {
+            return Ok(Filter::Iterate(target));
+        }
+        let from = if self.peek() == Some(&Token::Sym(":")) {
+            None
+        } else {
+            Some(Box::new(self.pipe()?))
+        };
+        if self.eat(":") {
+            let to = if self.peek() == Some(&Token::Sym("]")) {
+                None
+            } else {
+                Some(Box::new(self.pipe()?))
+            };
+            self.expect("]")?;
+            return Ok(Filter::Slice(target, from, to));
+        }
+        self.expect("]")?;
+        match from {
+            Some(key) => Ok(Filter::Index(target, key)),
+            None => Err("empty index".into()),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Filter, String> {
+        match self.next() {
+            Some(Token::Dot) => match self.peek() {
+                Some(Token::Str(key)) => {
+                    let key = Filter::Literal(Value::String(key.clone()));
+                    self.pos += 1;
+                    Ok(Filter::Index(Box::new(Filter::Identity), Box::new(key)))
+                }
+                Some(Token::Sym("[")) => {
+                    self.pos += 1;
+                    self.bracket(Filter::Identity)
+                }
+                _ => Ok(Filter::Identity),
+            },
+            Some(Token::Field(name)) => Ok(Filter::Index(
+                Box::new(Filter::Identity),
+                Box::new(Filter::Literal(Value::String(name))),
+            )),
+            Some(Token::Recurse) => Ok(Filter::Recurse),
+            Some(Token::Num(n)) => Ok(Filter::Literal(number(n))),
+            Some(Token::Str(text)) => Ok(Filter::Literal(Value::String(text))),
+            Some(Token::Sym("(")) => {
+                let inner = self.pipe()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(Token::Sym("[")) => {
+                if self.eat("]") {
+                    return Ok(Filter::Array(None));
+                }
+                let inner = self.pipe()?;
+                self.expect("]")?;
+                Ok(Filter::Array(Some(Box::new(inner))))
+            }
+            Some(Token::Sym("{")) => self.object(),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Filter::Literal(Value::Bool(true))),
+                "false" => Ok(Filter::Literal(Value::Bool(false))),
+                "null" => Ok(Filter::Literal(Value::Null)),
+                "if" | "reduce" | "foreach" | "def" | "as" => {
+                    Err(format!("'{}' is not supported", name))
+                }
+                _ => {
+                    let mut args = Vec::new();
+                    if self.eat("(") {
+                        loop {
+                            args.push(self.pipe()?);
+                            if !self.eat(";") {
+                                break;
+                            }
+                        }
+                        self.expect(")")?;
+                    }
+                    Ok(Filter::Call(name, args))
+                }
+            },
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("unexpected end of query".into()),
+        }
+    }
+
+    /// The rest of `{...}`, after the `{`.
+    fn object(&mut self) -> Result<Filter, String> {
+        let mut entries = Vec::new();
+        if self.eat("}") {
+            return Ok(Filter::Object(entries));
+        }
+        loop {
+            let (key, shorthand) = match self.next() {
+                Some(Token::Ident(name)) => (Filter::Literal(Value::String(name.clone())), name),
+                Some(Token::Str(name)) => (Filter::Literal(Value::String(name.clone())), name),
+                Some(Token::Sym("(")) => {
+                    let key = self.pipe()?;
+                    self.expect(")")?;
+                    self.expect(":")?;
+                    entries.push((key, self.alternative()?));
+                    if self.eat(",") {
+                        continue;
+                    }
+                    self.expect("}")?;
+                    return Ok(Filter::Object(entries));
+                }
+                Some(token) => return Err(format!("unexpected {} in object", token)),
+                None => return Err("unterminated object".into()),
+            };
+            let value = if self.eat(":") {
+                self.alternative()?
+            } else {
+                Filter::Index(
+                    Box::new(Filter::Identity),
+                    Box::new(Filter::Literal(Value::String(shorthand))),
+                )
+            };
+            entries.push((key, value));
+            if !self.eat(",") {
+                self.expect("}")?;
+                return Ok(Filter::Object(entries));
+            }
+        }
+    }
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        Value::from(n as i64)
+    } else {
+        Value::from(n)
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// jq's total order: null < false < true < numbers < strings < arrays <
+/// objects.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Null => 0,
+        Value::Bool(false) => 1,
+        Value::Bool(true) => 2,
+        Value::Number(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    };
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+            x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| compare(x, y))
+            .find(|order| order.is_ne())
+            .unwrap_or(x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut x_keys: Vec<_> = x.keys().collect();
+            let mut y_keys: Vec<_> = y.keys().collect();
+            x_keys.sort();
+            y_keys.sort();
+            x_keys.cmp(&y_keys).then_with(|| {
+                x_keys
+                    .iter()
+                    .map(|key| compare(&x[*key], &y[*key]))
+                    .find(|order| order.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn as_f64(value: &Value, what: &str) -> Result<f64, String> {
+    value
+        .as_f64()
+        .ok_or_else(|| format!("{} must be a number, not {}", what, type_name(value)))
+}
+
+fn index(target: &Value, key: &Value) -> Result<Value, String> {
+    match (target, key) {
+        (Value::Null, _) => Ok(Value::Null),
+        (Value::Object(map), Value::String(key)) => {
+            Ok(map.get(key).cloned().unwrap_or(Value::Null))
+        }
+        (Value::Array(items), Value::Number(n)) => {
+            let n = n.as_f64().unwrap_or(0.0).floor() as i64;
+            let n = if n < 0 { items.len() as i64 + n } else { n };
+            Ok(usize::try_from(n)
+                .ok()
+                .and_then(|n| items.get(n))
+                .cloned()
+                .unwrap_or(Value::Null))
+        }
+        _ => Err(format!(
+            "cannot index {} with {}",
+            type_name(target),
+            match key {
+                Value::String(key) => format!("\"{}\"", key),
+                other => type_name(other).to_string(),
+            }
+        )),
+    }
+}
+
+fn slice(target: &Value, from: Option<&Value>, to: Option<&Value>) -> Result<Value, String> {
+    let bound = |value: Option<&Value>, len: usize, default: usize| -> Result<usize, String> {
+        let Some(value) = value.filter(|value| !value.is_null()) else {
+            return Ok(default);
+        };
+        let n = as_f64(value, "slice index")?.floor() as i64;
+        let n = if n < 0 { len as i64 + n } else { n };
+        Ok(n.clamp(0, len as i64) as usize)
+    };
+    match target {
+        Value::Null => Ok(Value::Null),
+        Value::Array(items) => {
+            let start = bound(from, items.len(), 0)?;
+            let end = bound(to, items.len(), items.len())?.max(start);
+            Ok(Value::Array(items[start..end].to_vec()))
+        }
+        Value::String(text) => {
+            let chars: Vec<char> = text.chars().collect();
+            let start = bound(from, chars.len(), 0)?;
+            let end = bound(to, chars.len(), chars.len())?.max(start);
+            Ok(Value::String(chars[start..end].iter().collect()))
+        }
+        other => Err(format!("cannot slice {}", type_name(other))),
+    }
+}
+
+fn iterate(value: &Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(items) => Ok(items.clone()),
+        Value::Object(map) => Ok(map.values().cloned().collect()),
+        other => Err(format!("cannot iterate over {}", type_name(other))),
+    }
+}
+
+fn recurse(value: &Value, out: &mut Vec<Value>) {
+    out.push(value.clone());
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| recurse(item, out)),
+        Value::Object(map) => map.values().for_each(|item| recurse(item, out)),
+        _ => {}
+    }
+}
+
+fn arithmetic(op: &str, a: &Value, b: &Value) -> Result<Value, String> {
+    let fail = || {
+        Err(format!(
+            "cannot apply '{}' to {} and {}",
+            op,
+            type_name(a),
+            type_name(b)
+        ))
+    };
+    match (op, a, b) {
+        ("+", Value::Null, other) | ("+", other, Value::Null) => Ok(other.clone()),
+        ("+", Value::String(x), Value::String(y)) => Ok(Value::String(format!("{}{}", x, y))),
+        ("+", Value::Array(x), Value::Array(y)) => {
+            Ok(Value::Array(x.iter().chain(y).cloned().collect()))
+        }
+        ("+", Value::Object(x), Value::Object(y)) => {
+            let mut merged = x.clone();
+            merged.extend(y.iter().map(|(k, v)| (k.clone(), v.clone())));
+            Ok(Value::Object(merged))
+        }
+        ("-", Value::Array(x), Value::Array(y)) => Ok(Value::Array(
+            x.iter().filter(|item| !y.contains(item)).cloned().collect(),
+        )),
+        ("/", Value::String(x), Value::String(y)) => Ok(Value::Array(
+            x.split(y.as_str())
+                .map(|part| Value::String(part.to_string()))
+                .collect(),
+        )),
+        (_, Value::Number(_), Value::Number(_)) => {
+            let (x, y) = (as_f64(a, op)?, as_f64(b, op)?);
+            match op {
+                "+" => Ok(number(x + y)),
+                "-" => Ok(number(x - y)),
+                "*" => Ok(number(x * y)),
+                "/" | "%" if y == 0.0 => Err("division by zero".into()),
+                "/" => Ok(number(x / y)),
+                _ => Ok(number((x.trunc() as i64 % y.trunc() as i64) as f64)),
+            }
+        }
+        _ => fail(),
+    }
+}
+
+fn contains(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(x), Value::String(y)) => x.contains(y.as_str()),
+        (Value::Array(x), Value::Array(y)) => y
+            .iter()
+            .all(|needle| x.iter().any(|item| contains(item, needle))),
+        (Value::Object(x), Value::Object(y)) => y
+            .iter()
+            .all(|(key, needle)| x.get(key).is_some_and(|item| contains(item, needle))),
+        _ => a == b,
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn flatten(items: &[Value], out: &mut Vec<Value>) {
+    for item in items {
+        match item {
+            Value::Array(inner) => flatten(inner, out),
+            other => out.push(other.clone()),
+        }
+    }
+}
+
+impl Filter {
+    fn eval(&self, input: &Value) -> Result<Vec<Value>, String> {
+        Ok(match self {
+            Filter::Identity => vec![input.clone()],
+            Filter::Recurse => {
+                let mut out = Vec::new();
+                recurse(input, &mut out);
+                out
+            }
+            Filter::Literal(value) => vec![value.clone()],
+            Filter::Index(target, key) => {
+                let mut out = Vec::new();
+                for target in target.eval(input)? {
+                    for key in key.eval(input)? {
+                        out.push(index(&target, &key)?);
+                    }
+                }
+                out
+            }
+            Filter::Slice(target, from, to) => {
+                let from = from.as_ref().map(|f| f.eval(input)).transpose()?;
+                let to = to.as_ref().map(|f| f.eval(input)).transpose()?;
+                let first = |values: &Option<Vec<Value>>| {
+                    values.as_ref().and_then(|values| values.first().cloned())
+                };
+                let (from, to) = (first(&from), first(&to));
+                target
+                    .eval(input)?
+                    .iter()
+                    .map(|target| slice(target, from.as_ref(), to.as_ref()))
+                    .collect::<Result<_, _>>()?
+            }
+            Filter::Iterate(target) => {
+                let mut out = Vec::new();
+                for target in target.eval(input)? {
+                    out.extend(iterate(&target)?);
+                }
+                out
+            }
+            Filter::Try(inner) => inner.eval(input).unwrap_or_default(),
+            Filter::Array(None) => vec![Value::Array(Vec::new())],
+            Filter::Array(Some(inner)) => vec![Value::Array(inner.eval(input)?)],
+            Filter::Object(entries) => {
+                let mut objects = vec![Map::new()];
+                for (key, value) in entries {
+                    let keys = key.eval(input)?;
+                    let values = value.eval(input)?;
+                    let mut next = Vec::new();
+                    for object in &objects {
+                        for key in &keys {
+                            let Value::String(key) = key else {
+                                return Err(format!(
+                                    "object keys must be strings, not {}",
+                                    type_name(key)
+                                ));
+                            };
+                            for value in &values {
+                                let mut object = object.clone();
+                                object.insert(key.clone(), value.clone());
+                                next.push(object);
+                            }
+                        }
+                    }
+                    objects = next;
+                }
+                objects.into_iter().map(Value::Object).collect()
+            }
+            Filter::Pipe(left, right) => {
+                let mut out = Vec::new();
+                for value in left.eval(input)? {
+                    out.extend(right.eval(&value)?);
+                }
+                out
+            }
+            Filter::Comma(left, right) => {
+                let mut out = left.eval(input)?;
+                out.extend(right.eval(input)?);
+                out
+            }
+            Filter::Alternative(left, right) => {
+                let found: Vec<Value> = left
+                    .eval(input)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(truthy)
+                    .collect();
+                if found.is_empty() {
+                    right.eval(input)?
+                } else {
+                    found
+                }
+            }
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                let is_and = matches!(self, Filter::And(..));
+                let mut out = Vec::new();
+                for left in left.eval(input)? {
+                    if truthy(&left) != is_and {
+                        out.push(Value::Bool(!is_and));
+                        continue;
+                    }
+                    for right in right.eval(input)? {
+                        out.push(Value::Bool(truthy(&right)));
+                    }
+                }
+                out
+            }
+            Filter::Binary(op, left, right) => {
+                let mut out = Vec::new();
+                for right in right.eval(input)? {
+                    for left in left.eval(input)? {
+                        let order = compare(&left, &right);
+                        out.push(match *op {
+                            "==" => Value::Bool(order.is_eq()),
+                            "!=" => Value::Bool(order.is_ne()),
+                            "<" => Value::Bool(order.is_lt()),
+                            "<=" => Value::Bool(order.is_le()),
+                            ">" => Value::Bool(order.is_gt()),
+                            ">=" => Value::Bool(order.is_ge()),
+                            _ => arithmetic(op, &left, &right)?,
+                        });
+                    }
+                }
+                out
+            }
+            Filter::Neg(inner) => inner
+                .eval(input)?
+                .iter()
+                .map(|value| as_f64(value, "'-'").map(|n| number(-n)))
+                .collect::<Result<_, _>>()?,
+            Filter::Call(name, args) => call(name, args, input)?,
+        })
+    }
+
+    /// The single value of a function argument, as jq's `$__arg` would see it.
+    fn single(&self, input: &Value, name: &str) -> Result<Value, String> {
+        self.eval(input)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("{}: argument produced no value", name))
+    }
+}
+
+fn call(name: &str, args: &[Filter], input: &Value) -> Result<Vec<Value>, String> {
+    let items = || match input {
+        Value::Array(items) => Ok(items.clone()),
+        other => Err(format!("{} needs an array, not {}", name, type_name(other))),
+    };
+    let text = || match input {
+        Value::String(text) => Ok(text.as_str()),
+        other => Err(format!("{} needs a string, not {}", name, type_name(other))),
+    };
+    let keyed = |items: Vec<Value>| -> Result<Vec<(Value, Value)>, String> {
+        items
+            .into_iter()
+            .map(|item| Ok((Value::Array(args[0].eval(&item)?), item)))
+            .collect()
+    };
+    let one = |value: Value| Ok(vec![value]);
+    match (name, args.len()) {
+        ("empty", 0) => Ok(Vec::new()),
+        ("error", 0) => Err(to_text(input)),
+        ("not", 0) => one(Value::Bool(!truthy(input))),
+        ("length", 0) => one(match input {
+            Value::Null => json!(0),
+            Value::Number(n) => number(n.as_f64().unwrap_or(0.0).abs()),
+            Value::String(text) => json!(text.chars().count()),
+            Value::Array(items) => json!(items.len()),
+            Value::Object(map) => json!(map.len()),
+            Value::Bool(_) => return Err("boolean has no length".into()),
+        }),
+        ("keys", 0) => one(match input {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                json!(keys)
+            }
+            Value::Array(items) => json!((0..items.len()).collect::<Vec<_>>()),
+            other => return Err(format!("{} has no keys", type_name(other))),
+        }),
+        ("values", 0) => Ok(if input.is_null() {
+            Vec::new()
+        } else {
+            vec![input.clone()]
+        }),
+        ("type", 0) => one(json!(type_name(input))),
+        ("tostring", 0) => one(Value::String(to_text(input))),
+        ("tojson", 0) => one(Value::String(input.to_string())),
+        ("fromjson", 0) => one(serde_json::from_str(text()?).map_err(|e| e.to_string())?),
+        ("tonumber", 0) => one(match input {
+            Value::Number(_) => input.clone(),
+            Value::String(text) => number(
+                text.trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("cannot parse '{}' as a number", text))?,
+            ),
+            other => return Err(format!("cannot convert {} to a number", type_name(other))),
+        }),
+        ("ascii_downcase", 0) => one(Value::String(text()?.to_lowercase())),
+        ("ascii_upcase", 0) => one(Value::String(text()?.to_uppercase())),
+        ("first", 0) => one(items()?.first().cloned().unwrap_or(Value::Null)),
+        ("last", 0) => one(items()?.last().cloned().unwrap_or(Value::Null)),
+        ("first", 1) => Ok(args[0].eval(input)?.into_iter().take(1).collect()),
+        ("last", 1) => Ok(args[0]
+            .eval(input)?
+            .into_iter()
+            .last()
+            .into_iter()
+            .collect()),
+        ("reverse", 0) => one(match input {
+            Value::String(text) => Value::String(text.chars().rev().collect()),
+            Value::Null => json!([]),
+            _ => Value::Array(items()?.into_iter().rev().collect()),
+        }),
+        ("sort", 0) => {
+            let mut items = items()?;
+            items.sort_by(compare);
+            one(Value::Array(items))
+        }
+        ("unique", 0) => {
+            let mut items = items()?;
+            items.sort_by(compare);
+            items.dedup();
+            one(Value::Array(items))
+        }
+        ("min", 0) => one(items()?.into_iter().min_by(compare).unwrap_or(Value::Null)),
+        ("max", 0) => one(items()?.into_iter().max_by(compare).unwrap_or(Value::Null)),
+        ("add", 0) => {
+            let values = match input {
+                Value::Object(map) => map.values().cloned().collect(),
+                _ => items()?,
+            };
+            let mut sum = Value::Null;
+            for value in &values {
+                sum = arithmetic("+", &sum, value)?;
+            }
+            one(sum)
+        }
+        ("flatten", 0) => {
+            let mut out = Vec::new();
+            flatten(&items()?, &mut out);
+            one(Value::Array(out))
+        }
+        ("to_entries", 0) => match input {
+            Value::Object(map) => one(Value::Array(
+                map.iter()
+                    .map(|(key, value)| json!({ "key": key, "value": value }))
+                    .collect(),
+            )),
+            other => Err(format!(
+                "to_entries needs an object, not {}",
+                type_name(other)
+            )),
+        },
+        ("from_entries", 0) => {
+            let mut map = Map::new();
+            for entry in items()? {
+                let key = ["key", "k", "name", "Name", "Key"]
+                    .iter()
+                    .map(|field| &entry[*field])
+                    .find(|key| !key.is_null())
+                    .map(to_text)
+                    .ok_or("from_entries: entry without a key")?;
+                let value = ["value", "v", "Value"]
+                    .iter()
+                    .map(|field| &entry[*field])
+                    .find(|value| !value.is_null())
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                map.insert(key, value);
+            }
+            one(Value::Object(map))
+        }
+        ("select", 1) => {
+            let keep = args[0].eval(input)?.iter().any(truthy);
+            Ok(if keep {
+                vec![input.clone()]
+            } else {
+                Vec::new()
+            })
+        }
+        ("map", 1) => {
+            let mut out = Vec::new();
+            for item in iterate(input)? {
+                out.extend(args[0].eval(&item)?);
+            }
+            one(Value::Array(out))
+        }
+        ("with_entries", 1) => {
+            let entries = call("to_entries", &[], input)?;
+            let mapped = call("map", args, &entries[0])?;
+            call("from_entries", &[], &mapped[0])
+        }
+        ("any", 0) => one(Value::Bool(items()?.iter().any(truthy))),
+        ("all", 0) => one(Value::Bool(items()?.iter().all(truthy))),
+        ("any", 1) | ("all", 1) => {
+            let mut results = Vec::new();
+            for item in items()? {
+                results.push(args[0].eval(&item)?.iter().any(truthy));
+            }
+            one(Value::Bool(if name == "any" {
+                results.into_iter().any(|x| x)
+            } else {
+                results.into_iter().all(|x| x)
+            }))
+        }
+        ("sort_by", 1) | ("group_by", 1) | ("unique_by", 1) | ("min_by", 1) | ("max_by", 1) => {
+            let mut keyed = keyed(items()?)?;
+            keyed.sort_by(|(a, _), (b, _)| compare(a, b));
+            one(match name {
+                "sort_by" => Value::Array(keyed.into_iter().map(|(_, item)| item).collect()),
+                "min_by" => keyed
+                    .into_iter()
+                    .next()
+                    .map_or(Value::Null, |(_, item)| item),
+                "max_by" => keyed
+                    .into_iter()
+                    .last()
+                    .map_or(Value::Null, |(_, item)| item),
+                _ => {
+                    let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+                    for (key, item) in keyed {
+                        match groups.last_mut() {
+                            Some((last, group)) if *last == key => group.push(item),
+                            _ => groups.push((key, vec![item])),
+                        }
+                    }
+                    Value::Array(
+                        groups
+                            .into_iter()
+                            .map(|(_, group)| match name {
+                                "group_by" => Value::Array(group),
+                                _ => group.into_iter().next().unwrap_or(Value::Null),
+                            })
+                            .collect(),
+                    )
+                }
+            })
+        }
+        ("has", 1) => {
+            let key = args[0].single(input, name)?;
+            one(Value::Bool(match (input, &key) {
+                (Value::Object(map), Value::String(key)) => map.contains_key(key),
+                (Value::Array(items), Value::Number(n)) => n
+                    .as_f64()
+                    .is_some_and(|n| n >= 0.0 && (n as usize) < items.len()),
+                _ => {
+                    return Err(format!(
+                        "cannot check whether {} has a {} key",
+                        type_name(input),
+                        type_name(&key)
+                    ));
+                }
+            }))
+        }
+        ("contains", 1) => one(Value::Bool(contains(input, &args[0].single(input, name)?))),
+        ("join", 1) => {
+            let separator = to_text(&args[0].single(input, name)?);
+            let parts: Vec<String> = items()?
+                .iter()
+                .map(|item| match item {
+                    Value::Null => String::new(),
+                    other => to_text(other),
+                })
+                .collect();
+            one(Value::String(parts.join(&separator)))
+        }
+        ("split", 1) => arithmetic("/", input, &args[0].single(input, name)?).map(|v| vec![v]),
+        ("startswith", 1) | ("endswith", 1) | ("ltrimstr", 1) | ("rtrimstr", 1) => {
+            let affix = to_text(&args[0].single(input, name)?);
+            let Value::String(text) = input else {
+                return match name {
+                    "ltrimstr" | "rtrimstr" => one(input.clone()),
+                    _ => Err(format!("{} needs a string, not {}", name, type_name(input))),
+                };
+            };
+            one(match name {
+                "startswith" => Value::Bool(text.starts_with(&affix)),
+                "endswith" => Value::Bool(text.ends_with(&affix)),
+                "ltrimstr" => json!(text.strip_prefix(&affix).unwrap_or(text)),
+                _ => json!(text.strip_suffix(&affix).unwrap_or(text)),
+            })
+        }
+        ("test", 1) => {
+            let pattern = to_text(&args[0].single(input, name)?);
+            let regex = Regex::new(&pattern).map_err(|e| format!("invalid regex: {}", e))?;
+            one(Value::Bool(regex.is_match(text()?)))
+        }
+        ("limit", 2) => {
+            let n = as_f64(&args[0].single(input, name)?, "limit")?.max(0.0) as usize;
+            Ok(args[1].eval(input)?.into_iter().take(n).collect())
+        }
+        _ => Err(format!("{}/{} is not defined", name, args.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_query_extracts_fields_from_step_output() {
+        let pods = r#"kubectl get pods -o json
+{"items": [
+  {"name": "api", "status": "Running", "restarts": 0},
+  {"name": "worker", "status": "CrashLoopBackOff", "restarts": 7},
+  {"name": "cron", "status": "Completed", "restarts": 1, "labels": {"tier": "batch"}}
+]}"#;
+        let query = |query: &str| {
+            JsonQueryTool::new()
+                .execute_args(&json!({ "query": query, "input": pods }))
+                .output
+                .unwrap_or_default()
+        };
+        assert_eq!(
+            query(r#".items[] | select(.status != "Running" and .restarts > 0) | .name"#),
+            "worker\ncron"
+        );
+        assert_eq!(
+            query("[.items[].restarts] | add, length, (map(. * 2) | max)"),
+            "8\n3\n14"
+        );
+        assert_eq!(
+            query(
+                r#".items | sort_by(-.restarts) | .[0] | {name, tier: (.labels.tier // "none")}"#
+            ),
+            r#"{"name":"worker","tier":"none"}"#
+        );
+        assert_eq!(query(r#".items[-1].labels | keys | join(",")"#), "tier");
+        assert_eq!(query(".items[].labels.tier?"), "null\nnull\nbatch");
+
+        let lines = JsonQueryTool::new().execute(".id\n{\"id\": 1}\n{\"id\": 2}");
+        assert_eq!(lines.output.as_deref(), Some("1\n2"));
+        let bad = JsonQueryTool::new().execute_args(&json!({ "query": ".items[", "input": pods }));
+        assert!(!bad.success);
+        let indexed =
+            JsonQueryTool::new().execute_args(&json!({ "query": ".a.b", "input": "[1]" }));
+        assert!(indexed.error.unwrap().contains("cannot index array"));
+    }
+}
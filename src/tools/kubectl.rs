@@ -0,0 +1,539 @@
+// src/tools/kubectl.rs
+
+use crate::protocol::{RiskLevel, SimulationOutcome};
+use crate::tools::cancel::CancellationToken;
+use crate::tools::run_command::capture_process;
+use crate::tools::{Tool, ToolResult, ToolSpec, object_input};
+use serde_json::{Value, json};
+use std::process::Command;
+use std::time::Duration;
+
+/// Verbs allowed unless the tool is built with more; none of them change
+/// the cluster.
+pub const READ_ONLY_VERBS: [&str; 3] = ["get", "describe", "logs"];
+
+/// Log lines fetched when a call does not say.
+const DEFAULT_LOG_LINES: u64 = 100;
+
+/// Lines of `kubectl describe` output kept.
+const MAX_DESCRIBE_LINES: usize = 150;
+
+/// Inspects a Kubernetes cluster through `kubectl`: `get` returns a JSON
+/// summary per resource (pod phase, readiness, restarts and why containers
+/// are waiting or were last terminated), `describe` adds the object's
+/// events, and `logs` returns a pod's recent (or previous) log lines.
+/// Other verbs are refused unless allowed with `with_verbs`.
+#[derive(Clone, Debug)]
+pub struct KubectlTool {
+    verbs: Vec<String>,
+    context: Option<String>,
+    namespace: Option<String>,
+}
+
+impl Default for KubectlTool {
+    fn default() -> Self {
+        Self {
+            verbs: READ_ONLY_VERBS.map(String::from).to_vec(),
+            context: None,
+            namespace: None,
+        }
+    }
+}
+
+impl KubectlTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also allows `verbs` (e.g. `top`, `rollout`), passed to kubectl as
+    /// given and answered with its text output.
+    pub fn with_verbs(mut self, verbs: &[&str]) -> Self {
+        for verb in verbs {
+            if !self.verbs.iter().any(|v| v == verb) {
+                self.verbs.push(verb.to_string());
+            }
+        }
+        self
+    }
+
+    /// The kubeconfig context every call uses.
+    pub fn with_context(mut self, context: &str) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// The namespace calls use when they name none.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    fn kubectl(&self, args: &[&str], cancel: &CancellationToken) -> Result<String, String> {
+        let mut command = Command::new("kubectl");
+        if let Some(context) = &self.context {
+            command.arg("--context").arg(context);
+        }
+        command.args(args);
+        let (status, stdout, stderr) =
+            capture_process(&mut command, cancel).map_err(|result| match result.error {
+                Some(e) if e.contains("No such file") => {
+                    "The kubectl tool needs kubectl on the PATH".to_string()
+                }
+                e => e.unwrap_or_default(),
+            })?;
+        if status.success() {
+            Ok(stdout)
+        } else {
+            Err(format!("kubectl {} failed: {}", args[0], stderr.trim()))
+        }
+    }
+
+    /// `-n <namespace>` (or `--all-namespaces`) for a call's arguments.
+    fn namespace_args<'a>(&'a self, args: &'a Value) -> Vec<&'a str> {
+        if args["all_namespaces"].as_bool().unwrap_or(false) {
+            return vec!["--all-namespaces"];
+        }
+        match args["namespace"].as_str().or(self.namespace.as_deref()) {
+            Some(namespace) => vec!["--namespace", namespace],
+            None => Vec::new(),
+        }
+    }
+
+    fn run(&self, args: &Value, cancel: &CancellationToken) -> Result<Value, String> {
+        let verb = args["verb"].as_str().ok_or("Missing 'verb' argument")?;
+        if !self.verbs.iter().any(|v| v == verb) {
+            return Err(format!(
+                "kubectl {} is not allowed (allowed: {})",
+                verb,
+                self.verbs.join(", ")
+            ));
+        }
+        let name = args["name"].as_str();
+        for value in [args["kind"].as_str(), name, args["container"].as_str()]
+            .into_iter()
+            .flatten()
+        {
+            check_word(value)?;
+        }
+        let namespace = self.namespace_args(args);
+        match verb {
+            "get" => {
+                let kind = args["kind"].as_str().ok_or("Missing 'kind' argument")?;
+                let mut call = vec!["get", kind];
+                call.extend(name);
+                call.extend(&namespace);
+                if let Some(selector) = args["selector"].as_str() {
+                    call.extend(["--selector", selector]);
+                }
+                call.extend(["--output", "json"]);
+                let listing = parse_json(&self.kubectl(&call, cancel)?)?;
+                Ok(json!({ "items": summarize_resources(&listing) }))
+            }
+            "describe" => {
+                let kind = args["kind"].as_str().ok_or("Missing 'kind' argument")?;
+                let name = name.ok_or("Missing 'name' argument")?;
+                let mut call = vec!["describe", kind, name];
+                call.extend(&namespace);
+                let text = self.kubectl(&call, cancel)?;
+                let selector = format!("involvedObject.name={}", name);
+                let mut call = vec!["get", "events", "--field-selector", &selector];
+                call.extend(&namespace);
+                call.extend(["--output", "json"]);
+                let events = parse_json(&self.kubectl(&call, cancel)?)?;
+                Ok(json!({
+                    "describe": cap_lines(&text, MAX_DESCRIBE_LINES),
+                    "events": summarize_events(&events),
+                }))
+            }
+            "logs" => {
+                let pod = name
+                    .or(args["pod"].as_str())
+                    .ok_or("Missing 'name' argument (the pod)")?;
+                check_word(pod)?;
+                let tail = args["tail"]
+                    .as_u64()
+                    .unwrap_or(DEFAULT_LOG_LINES)
+                    .to_string();
+                let previous = args["previous"].as_bool().unwrap_or(false);
+                let mut call = vec!["logs", pod, "--tail", &tail];
+                call.extend(&namespace);
+                if let Some(container) = args["container"].as_str() {
+                    call.extend(["--container", container]);
+                }
+                if previous {
+                    call.push("--previous");
+                }
+                let log = self.kubectl(&call, cancel)?;
+                Ok(json!({
+                    "pod": pod,
+                    "container": args["container"],
+                    "previous": previous,
+                    "lines": log.lines().count(),
+                    "log": log.trim_end(),
+                }))
+            }
+            _ => {
+                let mut call = vec![verb];
+                for arg in args["args"].as_array().into_iter().flatten() {
+                    call.push(arg.as_str().ok_or("'args' must be strings")?);
+                }
+                call.extend(&namespace);
+                Ok(Value::String(
+                    self.kubectl(&call, cancel)?.trim_end().into(),
+                ))
+            }
+        }
+    }
+
+    /// Arguments for a freeform call such as `get pods -n web` or
+    /// `logs api-7f9 --previous`.
+    fn parse_input(input: &str) -> Result<Value, String> {
+        let mut words = input.split_whitespace().peekable();
+        words.next_if_eq(&"kubectl");
+        let verb = words.next().ok_or("Name a kubectl verb, e.g. 'get pods'")?;
+        if !READ_ONLY_VERBS.contains(&verb) {
+            return Ok(json!({ "verb": verb, "args": words.collect::<Vec<_>>() }));
+        }
+        let mut args = json!({ "verb": verb });
+        let mut positional = Vec::new();
+        while let Some(word) = words.next() {
+            match word {
+                "-n" | "--namespace" => args["namespace"] = json!(words.next()),
+                "-A" | "--all-namespaces" => args["all_namespaces"] = json!(true),
+                "-l" | "--selector" => args["selector"] = json!(words.next()),
+                "-c" | "--container" => args["container"] = json!(words.next()),
+                "-p" | "--previous" => args["previous"] = json!(true),
+                "--tail" => args["tail"] = json!(words.next().and_then(|n| n.parse::<u64>().ok())),
+                word if word.starts_with('-') => {
+                    return Err(format!("Unsupported kubectl option '{}'", word));
+                }
+                word => positional.push(word),
+            }
+        }
+        // `get pod/web-1` and `get pod web-1` name the same object.
+        let (kind, name) = match positional.first().and_then(|p| p.split_once('/')) {
+            Some((kind, name)) => (Some(kind), Some(name)),
+            None if verb == "logs" => (None, positional.first().copied()),
+            None => (positional.first().copied(), positional.get(1).copied()),
+        };
+        if verb != "logs" {
+            args["kind"] = json!(kind);
+        }
+        args["name"] = json!(name);
+        Ok(args)
+    }
+}
+
+/// Refuses values kubectl would read as an option.
+fn check_word(value: &str) -> Result<(), String> {
+    if value.is_empty() || value.starts_with('-') || value.contains(char::is_whitespace) {
+        return Err(format!("Invalid kubectl argument '{}'", value));
+    }
+    Ok(())
+}
+
+fn parse_json(text: &str) -> Result<Value, String> {
+    serde_json::from_str(text).map_err(|e| format!("kubectl returned invalid JSON: {}", e))
+}
+
+fn cap_lines(text: &str, max: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() <= max {
+        return text.trim_end().into();
+    }
+    format!(
+        "{}\n… {} more lines",
+        lines[..max].join("\n"),
+        lines.len() - max
+    )
+}
+
+/// One summary per object of `kubectl get -o json` output (a `List` or a
+/// single object): pods with phase, readiness, restarts and container
+/// problems; workloads with replica counts; anything else with its
+/// conditions.
+pub fn summarize_resources(listing: &Value) -> Vec<Value> {
+    let items = match listing["items"].as_array() {
+        Some(items) => items.iter().collect(),
+        None => vec![listing],
+    };
+    items.into_iter().map(summarize_resource).collect()
+}
+
+fn summarize_resource(item: &Value) -> Value {
+    let meta = &item["metadata"];
+    let status = &item["status"];
+    let mut summary = json!({
+        "kind": item["kind"],
+        "name": meta["name"],
+        "namespace": meta["namespace"],
+        "created": meta["creationTimestamp"],
+    });
+    match item["kind"].as_str().unwrap_or_default() {
+        "Pod" => {
+            let containers = status["containerStatuses"].as_array();
+            let containers: Vec<&Value> = containers.into_iter().flatten().collect();
+            let ready = containers.iter().filter(|c| c["ready"] == true).count();
+            let restarts: u64 = containers
+                .iter()
+                .filter_map(|c| c["restartCount"].as_u64())
+                .sum();
+            let problems: Vec<Value> = containers
+                .iter()
+                .filter_map(|c| container_problem(c))
+                .collect();
+            summary["phase"] = status["phase"].clone();
+            summary["ready"] = json!(format!("{}/{}", ready, containers.len()));
+            summary["restarts"] = json!(restarts);
+            summary["node"] = item["spec"]["nodeName"].clone();
+            summary["problems"] = json!(problems);
+        }
+        "Deployment" | "StatefulSet" | "ReplicaSet" => {
+            summary["replicas"] = item["spec"]["replicas"].clone();
+            summary["ready"] = json!(status["readyReplicas"].as_u64().unwrap_or(0));
+            summary["available"] = json!(status["availableReplicas"].as_u64().unwrap_or(0));
+        }
+        "Service" => {
+            summary["type"] = item["spec"]["type"].clone();
+            summary["cluster_ip"] = item["spec"]["clusterIP"].clone();
+            let ports: Vec<Value> = item["spec"]["ports"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|port| {
+                    json!(format!(
+                        "{}/{}",
+                        port["port"],
+                        port["protocol"].as_str().unwrap_or("TCP")
+                    ))
+                })
+                .collect();
+            summary["ports"] = json!(ports);
+        }
+        _ => {}
+    }
+    let conditions: Vec<Value> = status["conditions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|condition| condition["status"] != "True")
+        .map(|condition| {
+            json!({
+                "type": condition["type"],
+                "reason": condition["reason"],
+                "message": condition["message"],
+            })
+        })
+        .collect();
+    if !conditions.is_empty() {
+        summary["failing_conditions"] = json!(conditions);
+    }
+    summary
+}
+
+/// Why a container is not running cleanly: waiting (e.g. `CrashLoopBackOff`,
+/// `ImagePullBackOff`) or last terminated with an error.
+fn container_problem(container: &Value) -> Option<Value> {
+    let state = &container["state"];
+    let last = &container["lastState"]["terminated"];
+    let waiting = state["waiting"]["reason"].as_str();
+    let terminated = state["terminated"]["reason"]
+        .as_str()
+        .filter(|reason| *reason != "Completed");
+    if waiting.is_none() && terminated.is_none() && last.is_null() {
+        return None;
+    }
+    Some(json!({
+        "container": container["name"],
+        "waiting": waiting,
+        "message": state["waiting"]["message"],
+        "terminated": terminated,
+        "last_termination": if last.is_null() {
+            Value::Null
+        } else {
+            json!({
+                "reason": last["reason"],
+                "exit_code": last["exitCode"],
+                "finished": last["finishedAt"],
+            })
+        },
+    }))
+}
+
+/// `kubectl get events -o json` as `{type, reason, message, count, last_seen}`,
+/// most recent last.
+pub fn summarize_events(events: &Value) -> Vec<Value> {
+    let mut events: Vec<Value> = events["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|event| {
+            let last_seen = [&event["lastTimestamp"], &event["eventTime"]]
+                .into_iter()
+                .find(|time| !time.is_null())
+                .cloned()
+                .unwrap_or(Value::Null);
+            json!({
+                "type": event["type"],
+                "reason": event["reason"],
+                "message": event["message"],
+                "count": event["count"].as_u64().unwrap_or(1),
+                "last_seen": last_seen,
+            })
+        })
+        .collect();
+    events.sort_by(|a, b| {
+        a["last_seen"]
+            .as_str()
+            .unwrap_or_default()
+            .cmp(b["last_seen"].as_str().unwrap_or_default())
+    });
+    events
+}
+
+impl Tool for KubectlTool {
+    fn name(&self) -> &str {
+        "kubectl"
+    }
+
+    fn description(&self) -> &str {
+        "Inspects Kubernetes with kubectl get, describe and logs; returns JSON summaries (pod phase, restarts, why containers crash, events, recent logs)."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.execute_cancellable(input, &CancellationToken::new())
+    }
+
+    fn execute_cancellable(&self, input: &str, cancel: &CancellationToken) -> ToolResult {
+        // Input format: a kubectl command line ("get pods -n web"), or the JSON arguments.
+        let args = match object_input(input) {
+            Some(args) => args,
+            None => match Self::parse_input(input) {
+                Ok(args) => args,
+                Err(e) => return ToolResult::failure(&e),
+            },
+        };
+        match self.run(&args, cancel) {
+            Ok(Value::String(text)) => ToolResult::success(&text),
+            Ok(report) => ToolResult::success(&report.to_string()),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        match args.as_str() {
+            Some(input) => self.execute(input),
+            None => self.execute(&args.to_string()),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        let args = object_input(input).or_else(|| Self::parse_input(input).ok());
+        match args {
+            Some(args) => self.preview_args(&args),
+            None => SimulationOutcome::read_only("would fail: no kubectl verb given"),
+        }
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        if let Some(input) = args.as_str() {
+            return self.preview(input);
+        }
+        let verb = args["verb"].as_str().unwrap_or_default();
+        let target = [args["kind"].as_str(), args["name"].as_str()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let summary = format!("would run kubectl {} {}", verb, target);
+        if !self.verbs.iter().any(|v| v == verb) {
+            SimulationOutcome::read_only(&format!("would fail: kubectl {} is not allowed", verb))
+        } else if READ_ONLY_VERBS.contains(&verb) {
+            SimulationOutcome::read_only(summary.trim_end())
+        } else {
+            SimulationOutcome::mutating(summary.trim_end()).with_risk(RiskLevel::High)
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "kubectl command line (e.g. 'get pods -n web', 'describe pod api-7f9', 'logs api-7f9 --previous') or JSON {verb, kind, name, namespace}".into(),
+            tags: vec!["kubernetes".into(), "ops".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "verb": { "type": "string", "enum": self.verbs },
+                    "kind": { "type": "string" },
+                    "name": { "type": "string" },
+                    "namespace": { "type": "string" },
+                    "all_namespaces": { "type": "boolean" },
+                    "selector": { "type": "string" },
+                    "container": { "type": "string" },
+                    "previous": { "type": "boolean" },
+                    "tail": { "type": "integer", "minimum": 1 },
+                    "args": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["verb"]
+            })),
+            timeout: Some(Duration::from_secs(60)),
+            idempotent: self.verbs.iter().all(|verb| READ_ONLY_VERBS.contains(&verb.as_str())),
+            examples: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kubectl_summarizes_crashlooping_pods_and_stays_read_only() {
+        let pods = json!({ "kind": "List", "items": [{
+            "kind": "Pod",
+            "metadata": { "name": "api-7f9", "namespace": "web" },
+            "spec": { "nodeName": "node-1" },
+            "status": {
+                "phase": "Running",
+                "conditions": [{ "type": "Ready", "status": "False", "reason": "ContainersNotReady" }],
+                "containerStatuses": [{
+                    "name": "api",
+                    "ready": false,
+                    "restartCount": 7,
+                    "state": { "waiting": { "reason": "CrashLoopBackOff", "message": "back-off 5m0s" } },
+                    "lastState": { "terminated": { "reason": "Error", "exitCode": 1 } }
+                }]
+            }
+        }]});
+        let summary = &summarize_resources(&pods)[0];
+        assert_eq!(summary["ready"], "0/1");
+        assert_eq!(summary["restarts"], 7);
+        assert_eq!(summary["problems"][0]["waiting"], "CrashLoopBackOff");
+        assert_eq!(summary["problems"][0]["last_termination"]["exit_code"], 1);
+        assert_eq!(
+            summary["failing_conditions"][0]["reason"],
+            "ContainersNotReady"
+        );
+
+        let tool = KubectlTool::new();
+        let preview = tool.preview("get pod/api-7f9 -n web");
+        assert_eq!(
+            (preview.summary.as_str(), preview.risk),
+            ("would run kubectl get pod api-7f9", RiskLevel::Low)
+        );
+        let denied = tool.execute("delete pod api-7f9");
+        assert!(denied.error.unwrap().contains("not allowed"));
+        assert!(
+            tool.execute("get pods -o yaml")
+                .error
+                .unwrap()
+                .contains("'-o'")
+        );
+        let allowed = KubectlTool::new().with_verbs(&["rollout"]);
+        assert_eq!(
+            allowed.preview("rollout restart deploy/api").risk,
+            RiskLevel::High
+        );
+    }
+}
@@ -1,19 +1,412 @@
 // src/tools/llm.rs
 
-use crate::tools::{Tool, ToolResult, ToolSpec};
-use serde_json::{Value, json};
+use crate::llm::{
+    self, ChatMessage, Conversation, FunctionCall, FunctionDef, LlmConfig, LlmProvider,
+    LlmTransport, ModelTier, ModelTiers, OllamaProvider, RateLimiter, RetryPolicy, SendFailure,
+    TokenUsage, UsageTracker, json_repair,
+};
+use crate::protocol::SimulationOutcome;
+use crate::secrets::Secrets;
+use crate::tools::{AsyncTool, Tool, ToolFuture, ToolResult, ToolSpec};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::field::Empty;
+use tracing::{Instrument, Span, info_span, warn};
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct LLMTool {
     pub model: String,
+    provider: Arc<dyn LlmProvider>,
+    /// The model this tool was built with, serving tiers `tiers` lacks.
+    default_provider: Arc<dyn LlmProvider>,
+    tiers: ModelTiers,
+    tier: Option<ModelTier>,
+    usage: Option<UsageTracker>,
+    /// Who is calling, for usage breakdowns (e.g. `planner`).
+    component: String,
+    retry: RetryPolicy,
+    /// Shared by every clone, so all components respect one request rate.
+    limiter: Option<Arc<RateLimiter>>,
+    /// Masked in every prompt before it is sent.
+    secrets: Option<Secrets>,
+    /// Replaces HTTP when set.
+    transport: Option<Arc<dyn LlmTransport>>,
 }
 
 impl LLMTool {
+    /// A local Ollama model, e.g. `LLMTool::new("qwen3:8b")`.
     pub fn new(model: &str) -> Self {
+        Self::with_provider(Arc::new(OllamaProvider::new(model)))
+    }
+
+    pub fn with_provider(provider: Arc<dyn LlmProvider>) -> Self {
         Self {
-            model: model.to_string(),
+            model: provider.model().to_string(),
+            default_provider: provider.clone(),
+            provider,
+            tiers: ModelTiers::new(),
+            tier: None,
+            usage: None,
+            component: "llm".into(),
+            retry: RetryPolicy::default(),
+            limiter: None,
+            secrets: None,
+            transport: None,
+        }
+    }
+
+    /// Builds from a `provider:model` spec such as `"anthropic:claude-sonnet-4-5"`.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        Self::from_config(&LlmConfig::parse(spec))
+    }
+
+    pub fn from_config(config: &LlmConfig) -> Result<Self, String> {
+        config.build().map(Self::with_provider)
+    }
+
+    /// The model from a `provider:model` spec, keeping this tool's retry,
+    /// rate limit, usage tracking, secrets and transport.
+    pub fn with_spec(&self, spec: &str) -> Result<Self, String> {
+        let provider = LlmConfig::parse(spec).build()?;
+        Ok(Self {
+            model: provider.model().to_string(),
+            default_provider: provider.clone(),
+            provider,
+            tiers: ModelTiers::new(),
+            tier: None,
+            ..self.clone()
+        })
+    }
+
+    /// Models for `for_tier` to route to; tiers left out use this tool's model.
+    pub fn with_tiers(mut self, tiers: ModelTiers) -> Self {
+        self.tiers = tiers;
+        match self.tier {
+            Some(tier) => self.for_tier(tier),
+            None => self,
         }
     }
+
+    /// Sends this handle's calls to the model configured for `tier`, or to
+    /// the default model when the tier has none.
+    pub fn for_tier(mut self, tier: ModelTier) -> Self {
+        self.provider = match self.tiers.get(tier) {
+            Some(provider) => provider.clone(),
+            None => self.default_provider.clone(),
+        };
+        self.model = self.provider.model().to_string();
+        self.tier = Some(tier);
+        self
+    }
+
+    pub fn tiers(&self) -> &ModelTiers {
+        &self.tiers
+    }
+
+    pub fn provider(&self) -> &dyn LlmProvider {
+        self.provider.as_ref()
+    }
+
+    /// Records every call's tokens and cost in `tracker`; calls fail once its budget is exceeded.
+    pub fn with_usage(mut self, tracker: UsageTracker) -> Self {
+        self.usage = Some(tracker);
+        self
+    }
+
+    /// Masks `secrets`' values in prompts, so they never reach the provider.
+    pub fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Sends requests through `transport` instead of HTTP.
+    pub fn with_transport(mut self, transport: Arc<dyn LlmTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Attributes this handle's calls to `component` in usage breakdowns.
+    pub fn for_component(mut self, component: &str) -> Self {
+        self.component = component.into();
+        self
+    }
+
+    /// Retries transient failures (connection errors, 429, 5xx) with backoff.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Limits this tool and all its clones to `requests_per_minute`.
+    pub fn with_rate_limit(mut self, requests_per_minute: u32) -> Self {
+        self.limiter = Some(Arc::new(RateLimiter::per_minute(requests_per_minute)));
+        self
+    }
+
+    /// Asks for a JSON reply (Ollama `format: json`, OpenAI JSON mode; other
+    /// providers rely on the prompt) and deserializes it into `T`.
+    pub fn generate_structured<T: DeserializeOwned>(&self, prompt: &str) -> Result<T, String> {
+        self.structured(prompt, None)
+    }
+
+    /// Like `generate_structured`, but constrains the reply to a JSON Schema
+    /// where the provider supports it (Ollama grammars, OpenAI structured outputs).
+    pub fn generate_structured_with_schema<T: DeserializeOwned>(
+        &self,
+        prompt: &str,
+        schema: &Value,
+    ) -> Result<T, String> {
+        self.structured(prompt, Some(schema))
+    }
+
+    fn structured<T: DeserializeOwned>(
+        &self,
+        prompt: &str,
+        schema: Option<&Value>,
+    ) -> Result<T, String> {
+        self.check_budget()?;
+        let _span = self.call_span().entered();
+        let prompt = &self.redact(prompt);
+        let request = self.provider.structured_request(prompt, schema);
+        let started = Instant::now();
+        let result = self.complete(prompt, started, self.send_blocking(&request));
+        match result.output {
+            Some(text) if result.success => json_repair::parse_json(&text),
+            _ => Err(result
+                .error
+                .unwrap_or_else(|| "LLM returned no output".into())),
+        }
+    }
+
+    /// Sends `conversation` (as truncated by its limit) and returns the reply.
+    pub fn chat(&self, conversation: &Conversation) -> Result<String, String> {
+        self.chat_with(conversation, |messages| {
+            self.provider.chat_request(messages)
+        })
+    }
+
+    /// Like `chat`, but asks for a JSON reply matching `schema` where the
+    /// provider supports it. The reply is returned unparsed, so a caller can
+    /// show the model its own invalid output.
+    pub fn chat_structured(
+        &self,
+        conversation: &Conversation,
+        schema: Option<&Value>,
+    ) -> Result<String, String> {
+        self.chat_with(conversation, |messages| {
+            self.provider.structured_chat_request(messages, schema)
+        })
+    }
+
+    fn chat_with(
+        &self,
+        conversation: &Conversation,
+        request: impl FnOnce(&[ChatMessage]) -> llm::LlmRequest,
+    ) -> Result<String, String> {
+        self.check_budget()?;
+        let _span = self.call_span().entered();
+        let messages = self.redact_messages(conversation);
+        let request = request(&messages);
+        let started = Instant::now();
+        let result = self.complete(
+            &llm::chat::transcript(&messages),
+            started,
+            self.send_blocking(&request),
+        );
+        match result.output {
+            Some(text) if result.success => Ok(text),
+            _ => Err(result
+                .error
+                .unwrap_or_else(|| "LLM returned no output".into())),
+        }
+    }
+
+    fn redact_messages(&self, conversation: &Conversation) -> Vec<ChatMessage> {
+        conversation
+            .messages()
+            .into_iter()
+            .map(|message| ChatMessage {
+                content: self.redact(&message.content),
+                ..message
+            })
+            .collect()
+    }
+
+    /// Whether the provider can offer functions natively (see `call_functions`).
+    pub fn supports_function_calling(&self) -> bool {
+        self.provider.function_request("", &[]).is_some()
+    }
+
+    /// Offers `functions` to the model and returns the calls it made, in order.
+    pub fn call_functions(
+        &self,
+        prompt: &str,
+        functions: &[FunctionDef],
+    ) -> Result<Vec<FunctionCall>, String> {
+        self.check_budget()?;
+        let _span = self.call_span().entered();
+        let prompt = &self.redact(prompt);
+        let request = self
+            .provider
+            .function_request(prompt, functions)
+            .ok_or_else(|| {
+                format!(
+                    "Provider '{}' does not support function calling",
+                    self.provider.name()
+                )
+            })?;
+        let started = Instant::now();
+        let json = self.send_blocking(&request)?;
+        let calls = self.provider.parse_function_calls(&json)?;
+        let reply = serde_json::to_string(&calls).unwrap_or_default();
+        self.record_usage(prompt, started, &json, &reply);
+        Ok(calls)
+    }
+
+    fn redact(&self, prompt: &str) -> String {
+        match &self.secrets {
+            Some(secrets) => secrets.redact(prompt),
+            None => prompt.to_string(),
+        }
+    }
+
+    fn send_blocking(&self, request: &llm::LlmRequest) -> Result<Value, String> {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire();
+            }
+            let sent = match &self.transport {
+                Some(transport) => transport.send_blocking(request),
+                None => llm::try_send_blocking(request),
+            };
+            match sent {
+                Ok(json) => return Ok(json),
+                Err(failure) => {
+                    attempt += 1;
+                    let delay = self.retry_delay(attempt, &failure)?;
+                    std::thread::sleep(delay);
+                }
+            }
+        }
+    }
+
+    async fn send(&self, request: &llm::LlmRequest) -> Result<Value, String> {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire_async().await;
+            }
+            let sent = match &self.transport {
+                Some(transport) => transport.send(request).await,
+                None => llm::try_send(request).await,
+            };
+            match sent {
+                Ok(json) => return Ok(json),
+                Err(failure) => {
+                    attempt += 1;
+                    let delay = self.retry_delay(attempt, &failure)?;
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    fn call_span(&self) -> Span {
+        info_span!(
+            "llm_call",
+            provider = self.provider.name(),
+            model = %self.model,
+            component = %self.component,
+            retries = Empty,
+            prompt_tokens = Empty,
+            completion_tokens = Empty,
+        )
+    }
+
+    /// How long to wait before retrying, or the final error once retries are exhausted.
+    fn retry_delay(&self, attempt: u32, failure: &SendFailure) -> Result<Duration, String> {
+        if !self.retry.should_retry(attempt, failure) {
+            return Err(if attempt > 1 {
+                format!("{} (after {} attempts)", failure.message, attempt)
+            } else {
+                failure.message.clone()
+            });
+        }
+        let delay = self.retry.delay(attempt, failure);
+        Span::current().record("retries", attempt);
+        warn!(
+            "🔁 {} request failed ({}); retry {}/{} in {:.1}s",
+            self.provider.name(),
+            failure.message,
+            attempt,
+            self.retry.max_retries,
+            delay.as_secs_f64()
+        );
+        Ok(delay)
+    }
+
+    fn check_budget(&self) -> Result<(), String> {
+        match self.usage.as_ref().and_then(UsageTracker::budget_exceeded) {
+            Some(reason) => Err(reason),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses the response text and records the call's usage, timed from
+    /// `started`.
+    fn complete(
+        &self,
+        prompt: &str,
+        started: Instant,
+        response: Result<Value, String>,
+    ) -> ToolResult {
+        let parsed = response.and_then(|json| Ok((self.provider.parse_response(&json)?, json)));
+        match parsed {
+            Ok((text, json)) => {
+                self.record_usage(prompt, started, &json, &text);
+                ToolResult::success(&text)
+            }
+            Err(err) => ToolResult::failure(&err),
+        }
+    }
+
+    /// Records the tokens the response reports, or an estimate from the text.
+    fn record_usage(&self, prompt: &str, started: Instant, json: &Value, reply: &str) {
+        let usage = self.provider.parse_usage(json);
+        if let Some(usage) = usage {
+            Span::current()
+                .record("prompt_tokens", usage.prompt_tokens)
+                .record("completion_tokens", usage.completion_tokens);
+        }
+        if let Some(tracker) = &self.usage {
+            let (usage, estimated) = match usage {
+                Some(usage) => (usage, false),
+                None => (TokenUsage::estimate(prompt, reply), true),
+            };
+            tracker.record(
+                &self.component,
+                self.provider.name(),
+                &self.model,
+                usage,
+                estimated,
+                started.elapsed(),
+            );
+        }
+    }
+}
+
+impl std::fmt::Debug for LLMTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LLMTool")
+            .field("provider", &self.provider.name())
+            .field("model", &self.model)
+            .field("component", &self.component)
+            .field("tier", &self.tier)
+            .finish()
+    }
 }
 
 impl Default for LLMTool {
@@ -28,42 +421,54 @@ impl Tool for LLMTool {
     }
 
     fn description(&self) -> &str {
-        "Sends input to a local LLM via Ollama and returns the response."
+        "Sends input to the configured LLM provider (Ollama, OpenAI, Anthropic) and returns the response."
     }
 
     fn execute(&self, input: &str) -> ToolResult {
-        let client = reqwest::blocking::Client::new();
-        let url = "http://localhost:11434/api/generate";
-
-        let payload = json!({
-            "model": self.model,
-            "prompt": input,
-            "stream": false
-        });
-
-        let response = client.post(url).json(&payload).send();
-
-        match response {
-            Ok(resp) => match resp.json::<Value>() {
-                Ok(json) => {
-                    if let Some(text) = json.get("response").and_then(|v| v.as_str()) {
-                        ToolResult::success(text.trim())
-                    } else {
-                        ToolResult::failure("LLM response missing 'response' field")
-                    }
-                }
-                Err(err) => ToolResult::failure(&format!("Failed to parse JSON: {err}")),
-            },
-            Err(err) => ToolResult::failure(&format!("Request failed: {err}")),
+        if let Err(reason) = self.check_budget() {
+            return ToolResult::failure(&reason);
         }
+        let _span = self.call_span().entered();
+        let input = &self.redact(input);
+        let request = self.provider.request(input);
+        let started = Instant::now();
+        self.complete(input, started, self.send_blocking(&request))
+    }
+
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("would prompt {}", self.model))
     }
 
     fn spec(&self) -> ToolSpec {
         ToolSpec {
-            name: self.name().into(),
+            name: Tool::name(self).into(),
             description: self.description().into(),
             input_hint: "Freeform prompt text to send to LLM.".into(),
             tags: vec!["llm".into(), "generation".into(), "reasoning".into()],
+            timeout: Some(Duration::from_secs(300)),
+            idempotent: true,
+            ..Default::default()
         }
     }
 }
+
+impl AsyncTool for LLMTool {
+    fn name(&self) -> &str {
+        "llm"
+    }
+
+    fn execute_async<'a>(&'a self, input: &'a str) -> ToolFuture<'a> {
+        Box::pin(
+            async move {
+                if let Err(reason) = self.check_budget() {
+                    return ToolResult::failure(&reason);
+                }
+                let input = &self.redact(input);
+                let request = self.provider.request(input);
+                let started = Instant::now();
+                self.complete(input, started, self.send(&request).await)
+            }
+            .instrument(self.call_span()),
+        )
+    }
+}
@@ -1,26 +1,99 @@
 // src/tools/mod.rs
 
+pub mod cache;
+pub mod calc;
+pub mod cancel;
+#[cfg(feature = "shell-tools")]
+pub mod cargo;
+pub mod code_edit;
+#[cfg(feature = "shell-tools")]
+pub mod code_search;
+#[cfg(feature = "shell-tools")]
+pub mod command_template;
+#[cfg(feature = "desktop")]
+pub mod desktop;
+#[cfg(feature = "shell-tools")]
+pub mod docker;
 pub mod error_analyzer;
+#[cfg(feature = "shell-tools")]
+pub mod git;
 pub mod goal_analyzer;
+pub mod goal_verifier;
+pub mod json_query;
+#[cfg(feature = "shell-tools")]
+pub mod kubectl;
 pub mod llm;
+pub mod process;
+pub mod project_map;
 pub mod reflector;
+pub mod registry;
+#[cfg(feature = "shell-tools")]
+pub mod release;
+#[cfg(feature = "shell-tools")]
 pub mod run_command;
+#[cfg(feature = "shell-tools")]
+pub mod sandboxed_command;
+pub mod selection;
+pub mod sub_agent;
+pub mod template;
+
+use crate::context::ContextHandle;
+use crate::protocol::transform::{OutputTransform, apply_all};
+use crate::protocol::{RiskLevel, SimulationOutcome};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Command;
+use std::time::Duration;
 
 /// Tool metadata for discoverability and planning.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ToolSpec {
     pub name: String,
     pub description: String,
     pub input_hint: String,
     pub tags: Vec<String>,
+    /// JSON schema for structured `args`, checked when plans are validated and
+    /// again before each call; tools without one take freeform `input`.
+    pub input_schema: Option<Value>,
+    /// Default time limit per call; a plan step's own timeout overrides it.
+    pub timeout: Option<Duration>,
+    /// Repeating a read-only call gives the same result until something
+    /// changes state, so it may be served from the run's `ToolCache`.
+    pub idempotent: bool,
+    /// Sample calls shown under the tool in planner prompts.
+    pub examples: Vec<ToolExample>,
+}
+
+/// A sample call: what a step passes and what comes back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolExample {
+    /// The step's `input`, or its `args` as JSON.
+    pub input: String,
+    pub output_summary: String,
+}
+
+impl ToolExample {
+    pub fn new(input: &str, output_summary: &str) -> Self {
+        Self {
+            input: input.into(),
+            output_summary: output_summary.into(),
+        }
+    }
 }
 
 /// The result of executing a tool.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ToolResult {
     pub success: bool,
     pub output: Option<String>,
     pub error: Option<String>,
+    pub timed_out: bool,
+    /// Typed payload (exit codes, file lists, diagnostics) alongside the
+    /// text, reachable from later steps as `$output[key.field]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
 }
 
 impl ToolResult {
@@ -29,6 +102,8 @@ impl ToolResult {
             success: true,
             output: Some(output.to_string()),
             error: None,
+            timed_out: false,
+            data: None,
         }
     }
 
@@ -37,6 +112,47 @@ impl ToolResult {
             success: false,
             output: None,
             error: Some(error.to_string()),
+            timed_out: false,
+            data: None,
+        }
+    }
+
+    /// A success carrying `data`, with its JSON text as the output.
+    pub fn structured(data: Value) -> Self {
+        Self::success(&data.to_string()).with_data(data)
+    }
+
+    /// This result with `data` attached, e.g. a failure's diagnostics.
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn timeout(after: Duration) -> Self {
+        Self {
+            timed_out: true,
+            ..Self::failure(&format!("Timed out after {:.1}s", after.as_secs_f64()))
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        Self::failure("Cancelled")
+    }
+
+    /// This result with `transforms` applied to a successful output; a
+    /// transform that fails turns it into a failure. `data` is kept as is.
+    pub fn transformed(self, transforms: &[OutputTransform]) -> Self {
+        match &self.output {
+            Some(output) if self.success && !transforms.is_empty() => {
+                match apply_all(transforms, output) {
+                    Ok(output) => Self {
+                        output: Some(output),
+                        ..self
+                    },
+                    Err(e) => Self::failure(&format!("Output transform failed: {}", e)),
+                }
+            }
+            _ => self,
         }
     }
 }
@@ -46,18 +162,141 @@ pub trait Tool {
     fn name(&self) -> &str;
     fn description(&self) -> &str;
     fn execute(&self, input: &str) -> ToolResult;
+
+    /// Like `execute`, but may stop early once `cancel` fires (timeout or
+    /// aborted run). Tools that own long-running work should override this.
+    fn execute_cancellable(&self, input: &str, _cancel: &CancellationToken) -> ToolResult {
+        self.execute(input)
+    }
+
+    /// Like `execute_cancellable`, with a handle to the running context for
+    /// logging to its memory and emitting events. This is how
+    /// `Context::invoke_tool` calls freeform tools; the default ignores the
+    /// handle apart from its cancellation token.
+    fn execute_with(&self, input: &str, context: &ContextHandle) -> ToolResult {
+        self.execute_cancellable(input, context.cancel_token())
+    }
+
+    /// Executes with structured arguments. Tools that declare an `input_schema`
+    /// should override this; the default passes strings through as `input` and
+    /// anything else as its JSON text.
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        match args {
+            Value::String(input) => self.execute(input),
+            other => self.execute(&other.to_string()),
+        }
+    }
+
+    /// `execute_args` with a handle to the running context, the way
+    /// `Context::invoke_tool` calls it; see `execute_with`.
+    fn execute_args_with(&self, args: &Value, _context: &ContextHandle) -> ToolResult {
+        self.execute_args(args)
+    }
+
+    /// The process a plan step marked `background` starts for this call
+    /// (`args` if given, else `input`), to be left running and tracked by the
+    /// context's `ProcessManager`. Only tools that run commands support it.
+    fn background_command(&self, _input: &str, _args: Option<&Value>) -> Result<Command, String> {
+        Err(format!("{} cannot run in the background", self.name()))
+    }
+
+    /// What `execute` would do, without side effects. Used when the context
+    /// is in dry-run mode.
+    fn simulate(&self, input: &str) -> ToolResult {
+        ToolResult::success(&format!(
+            "[dry-run] {} would run with input: {}",
+            self.name(),
+            input
+        ))
+    }
+
+    /// Predicts what `execute` would touch, for simulation. The default
+    /// assumes the call changes state, and is high-risk for tools tagged
+    /// `destructive`; read-only tools and tools that know their targets
+    /// should override it.
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        let summary = format!("{} would run with input: {}", self.name(), input);
+        let outcome = SimulationOutcome::mutating(&summary);
+        if self.spec().tags.iter().any(|tag| tag == "destructive") {
+            outcome.with_risk(RiskLevel::High)
+        } else {
+            outcome
+        }
+    }
+
+    /// `preview` for structured arguments; mirrors `execute_args`.
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args {
+            Value::String(input) => self.preview(input),
+            other => self.preview(&other.to_string()),
+        }
+    }
+
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: self.name().to_string(),
             description: self.description().to_string(),
             input_hint: "Freeform string input".to_string(),
             tags: vec!["generic".into()],
+            ..Default::default()
         }
     }
 }
 
+/// Parses JSON object input, so a freeform `input` can carry the same
+/// arguments as `args`.
+#[cfg(feature = "shell-tools")]
+pub(crate) fn object_input(input: &str) -> Option<Value> {
+    serde_json::from_str(input.trim())
+        .ok()
+        .filter(Value::is_object)
+}
+
+/// Boxed future returned by `AsyncTool::execute_async`.
+pub type ToolFuture<'a> = Pin<Box<dyn Future<Output = ToolResult> + Send + 'a>>;
+
+/// Non-blocking counterpart of `Tool`, used by the async agent loop.
+///
+/// Tools that only implement `Tool` still work there; they are run on a
+/// blocking-safe section of the runtime instead of concurrently.
+pub trait AsyncTool: Send + Sync {
+    fn name(&self) -> &str;
+    fn execute_async<'a>(&'a self, input: &'a str) -> ToolFuture<'a>;
+}
+
+pub use cache::ToolCache;
+pub use calc::CalcTool;
+pub use cancel::CancellationToken;
+#[cfg(feature = "shell-tools")]
+pub use cargo::CargoTool;
+pub use code_edit::CodeEditTool;
+#[cfg(feature = "shell-tools")]
+pub use code_search::CodeSearchTool;
+#[cfg(feature = "shell-tools")]
+pub use command_template::CommandTemplateTool;
+#[cfg(feature = "desktop")]
+pub use desktop::{ClipboardTool, NotifyTool};
+#[cfg(feature = "shell-tools")]
+pub use docker::{ContainerLimits, DockerTool};
 pub use error_analyzer::ErrorAnalyzerTool;
+#[cfg(feature = "shell-tools")]
+pub use git::{CommitPolicy, GitBranchTool, GitCommitTool, GitDiffTool, GitPushTool};
 pub use goal_analyzer::GoalAnalyzerTool;
+pub use goal_verifier::GoalVerifierTool;
+pub use json_query::JsonQueryTool;
+#[cfg(feature = "shell-tools")]
+pub use kubectl::KubectlTool;
 pub use llm::LLMTool;
+pub use process::ProcessTool;
+pub use project_map::{ProjectMap, ProjectMapTool};
 pub use reflector::ReflectorTool;
+pub use registry::ToolRegistry;
+#[cfg(feature = "shell-tools")]
+pub use release::{GitTagTool, PublishDryRunTool, VersionBumpTool};
+#[cfg(feature = "shell-tools")]
 pub use run_command::RunCommandTool;
+#[cfg(feature = "shell-tools")]
+pub use sandboxed_command::SandboxedCommandTool;
+pub use selection::ToolSelector;
+pub use sub_agent::SubAgentTool;
+pub use template::TemplateTool;
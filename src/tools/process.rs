@@ -0,0 +1,182 @@
+// src/tools/process.rs
+
+use crate::context::{ContextHandle, ReadyCheck};
+use crate::protocol::SimulationOutcome;
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec};
+use regex::Regex;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// How long `wait` waits by default.
+const DEFAULT_WAIT_SECS: f64 = 30.0;
+/// Lines `logs` returns by default.
+const DEFAULT_LOG_LINES: usize = 50;
+
+/// Checks on and stops the processes `background` plan steps started: waits
+/// for a dev server to accept connections or print a line, reads its recent
+/// output, or stops it. Only works run through a context, whose
+/// `ProcessManager` it acts on.
+///
+/// Takes `{"action": "status" | "wait" | "logs" | "stop", "id": ...}`, or
+/// the freeform `"<action> <id>"`; `status` without an id lists them all.
+#[derive(Clone, Debug, Default)]
+pub struct ProcessTool;
+
+impl ProcessTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn run(&self, args: &Value, context: &ContextHandle) -> ToolResult {
+        let action = args["action"].as_str().unwrap_or("status");
+        let id = args["id"].as_str();
+        let processes = context.processes();
+        let result = match (action, id) {
+            ("status", None) => Ok(json!(processes.list())),
+            ("status", Some(id)) => processes
+                .status(id)
+                .map(|status| json!(status))
+                .ok_or_else(|| format!("No background process '{}'", id)),
+            ("logs", Some(id)) => {
+                let lines = args["lines"]
+                    .as_u64()
+                    .map_or(DEFAULT_LOG_LINES, |n| n as usize);
+                return match processes.logs(id, lines) {
+                    Some(lines) => ToolResult::success(&lines.join("\n")),
+                    None => ToolResult::failure(&format!("No background process '{}'", id)),
+                };
+            }
+            ("wait", Some(id)) => ready_check(args).and_then(|check| {
+                processes
+                    .wait_until_ready(id, &check, wait_timeout(args)?, context.cancel_token())
+                    .map(|status| json!(status))
+            }),
+            ("stop", Some(id)) => processes.stop(id).map(|status| json!(status)),
+            ("logs" | "wait" | "stop", None) => Err(format!("'{}' needs an 'id'", action)),
+            (other, _) => Err(format!(
+                "Unknown action '{}' (expected status, wait, logs or stop)",
+                other
+            )),
+        };
+        match result {
+            Ok(data) => ToolResult::structured(data),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+}
+
+/// The `port` and `pattern` a `wait` call asks for.
+fn ready_check(args: &Value) -> Result<ReadyCheck, String> {
+    let port = match &args["port"] {
+        Value::Null => None,
+        port => Some(
+            port.as_u64()
+                .and_then(|port| u16::try_from(port).ok())
+                .ok_or("'port' must be a port number")?,
+        ),
+    };
+    let pattern = args["pattern"]
+        .as_str()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))
+        })
+        .transpose()?;
+    Ok(ReadyCheck { port, pattern })
+}
+
+/// The `timeout_secs` a `wait` call asks for, or the default.
+fn wait_timeout(args: &Value) -> Result<Duration, String> {
+    match &args["timeout_secs"] {
+        Value::Null => Ok(Duration::from_secs_f64(DEFAULT_WAIT_SECS)),
+        secs => secs
+            .as_f64()
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .ok_or_else(|| "'timeout_secs' must be a non-negative number of seconds".into()),
+    }
+}
+
+/// `"<action> <id>"` as arguments, or the input itself if it is JSON.
+fn parse_input(input: &str) -> Value {
+    if let Ok(args @ Value::Object(_)) = serde_json::from_str(input.trim()) {
+        return args;
+    }
+    let mut words = input.split_whitespace();
+    let mut args = json!({ "action": words.next().unwrap_or("status") });
+    if let Some(id) = words.next() {
+        args["id"] = json!(id);
+    }
+    args
+}
+
+impl Tool for ProcessTool {
+    fn name(&self) -> &str {
+        "process"
+    }
+
+    fn description(&self) -> &str {
+        "Checks on background processes started by steps with \"background\": true: 'status', 'wait' until a port accepts connections or output matches a pattern, 'logs', or 'stop'."
+    }
+
+    fn execute(&self, _input: &str) -> ToolResult {
+        ToolResult::failure("The process tool needs a running context")
+    }
+
+    fn execute_with(&self, input: &str, context: &ContextHandle) -> ToolResult {
+        self.run(&parse_input(input), context)
+    }
+
+    fn execute_args_with(&self, args: &Value, context: &ContextHandle) -> ToolResult {
+        self.run(args, context)
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        self.preview_args(&parse_input(input))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        let action = args["action"].as_str().unwrap_or("status");
+        let id = args["id"].as_str().unwrap_or("all");
+        let summary = format!("would {} background process {}", action, id);
+        if action == "stop" {
+            SimulationOutcome::mutating(&summary)
+        } else {
+            SimulationOutcome::read_only(&summary)
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "{\"action\": \"wait\", \"id\": \"server\", \"port\": 3000}".into(),
+            tags: vec!["process".into(), "execution".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["status", "wait", "logs", "stop"] },
+                    "id": {
+                        "type": "string",
+                        "description": "The id of the background step"
+                    },
+                    "port": { "type": "integer", "description": "wait: a local port to accept connections" },
+                    "pattern": { "type": "string", "description": "wait: a regex for an output line" },
+                    "timeout_secs": { "type": "number", "description": "wait: how long to wait (default 30)" },
+                    "lines": { "type": "integer", "description": "logs: how many recent lines (default 50)" }
+                },
+                "required": ["action"]
+            })),
+            timeout: None,
+            idempotent: false,
+            examples: vec![
+                ToolExample::new(
+                    "{\"action\": \"wait\", \"id\": \"server\", \"port\": 3000}",
+                    "the server's status once port 3000 accepts connections; fails if it exits first",
+                ),
+                ToolExample::new(
+                    "{\"action\": \"stop\", \"id\": \"server\"}",
+                    "its final status, with its last lines of output",
+                ),
+            ],
+        }
+    }
+}
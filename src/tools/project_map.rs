@@ -0,0 +1,607 @@
+// src/tools/project_map.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use crate::workspace::Workspace;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory levels listed before deeper directories collapse to a count.
+const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Lines listed before the rest of the tree is left out.
+const DEFAULT_MAX_ENTRIES: usize = 150;
+
+/// Files larger than this are marked with their size.
+const DEFAULT_LARGE_FILE: u64 = 1024 * 1024;
+
+/// Files walked before the walk stops, so a huge tree stays cheap to map.
+const MAX_FILES_WALKED: usize = 20_000;
+
+/// Language of each source extension.
+const LANGUAGES: [(&str, &str); 30] = [
+    ("rs", "Rust"),
+    ("py", "Python"),
+    ("js", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("go", "Go"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("swift", "Swift"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("sh", "Shell"),
+    ("sql", "SQL"),
+    ("html", "HTML"),
+    ("css", "CSS"),
+    ("md", "Markdown"),
+    ("toml", "TOML"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("json", "JSON"),
+    ("proto", "Protobuf"),
+    ("tf", "Terraform"),
+];
+
+/// Build files that say how a project is built, reported when at the root.
+const MANIFESTS: [&str; 11] = [
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "Makefile",
+    "CMakeLists.txt",
+    "Dockerfile",
+];
+
+/// Walks a directory, honouring `.gitignore` files at every level, and
+/// condenses it into a `ProjectSummary`: the file tree to a depth and line
+/// budget, file counts per language and the root's build manifests.
+#[derive(Clone, Debug)]
+pub struct ProjectMap {
+    max_depth: usize,
+    max_entries: usize,
+    large_file: u64,
+}
+
+impl Default for ProjectMap {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            large_file: DEFAULT_LARGE_FILE,
+        }
+    }
+}
+
+impl ProjectMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directory levels to list; deeper directories show as `name/ (N files)`.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Tree lines to list before the rest is summarised as left out.
+    pub fn with_max_entries(mut self, entries: usize) -> Self {
+        self.max_entries = entries;
+        self
+    }
+
+    /// Size in bytes above which files are marked as large.
+    pub fn with_large_file(mut self, bytes: u64) -> Self {
+        self.large_file = bytes;
+        self
+    }
+
+    pub fn scan(&self, root: &Path) -> Result<ProjectSummary, String> {
+        if !root.is_dir() {
+            return Err(format!("{} is not a directory", root.display()));
+        }
+        let mut walked = 0;
+        let mut ignores = Vec::new();
+        let tree = walk(root, "", &mut ignores, &mut walked);
+        let mut languages = BTreeMap::new();
+        tree.count_languages(&mut languages);
+        let mut languages: Vec<(String, usize)> = languages
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        languages.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let mut lines = Vec::new();
+        let mut left_out = 0;
+        self.render(&tree, 0, &mut lines, &mut left_out);
+        Ok(ProjectSummary {
+            files: tree.total_files,
+            languages,
+            manifests: tree
+                .files
+                .iter()
+                .filter(|(name, _)| MANIFESTS.contains(&name.as_str()))
+                .map(|(name, _)| name.clone())
+                .collect(),
+            tree: lines,
+            left_out,
+            incomplete: walked >= MAX_FILES_WALKED,
+        })
+    }
+
+    fn render(&self, dir: &Dir, depth: usize, lines: &mut Vec<String>, left_out: &mut usize) {
+        let indent = "  ".repeat(depth);
+        for sub in &dir.dirs {
+            if lines.len() == self.max_entries {
+                *left_out += sub.total_files;
+                continue;
+            }
+            if depth + 1 >= self.max_depth {
+                lines.push(format!(
+                    "{}{}/ ({} files)",
+                    indent, sub.name, sub.total_files
+                ));
+                continue;
+            }
+            lines.push(format!("{}{}/", indent, sub.name));
+            self.render(sub, depth + 1, lines, left_out);
+        }
+        for (name, size) in &dir.files {
+            if lines.len() == self.max_entries {
+                *left_out += 1;
+            } else if *size > self.large_file {
+                lines.push(format!("{}{} ({})", indent, name, human_size(*size)));
+            } else {
+                lines.push(format!("{}{}", indent, name));
+            }
+        }
+    }
+}
+
+/// What `ProjectMap::scan` found.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectSummary {
+    /// Files not ignored, however deep.
+    pub files: usize,
+    /// File counts per language, most files first.
+    pub languages: Vec<(String, usize)>,
+    /// Build manifests at the root, e.g. `Cargo.toml`.
+    pub manifests: Vec<String>,
+    /// The listed tree, two spaces of indent per level.
+    pub tree: Vec<String>,
+    /// Files left out of `tree` by the line budget.
+    pub left_out: usize,
+    /// Whether the walk stopped early at `MAX_FILES_WALKED`.
+    pub incomplete: bool,
+}
+
+impl ProjectSummary {
+    /// A header line (file count, languages, manifests) and the tree.
+    pub fn render(&self) -> String {
+        let mut header = format!(
+            "{}{} files",
+            self.files,
+            if self.incomplete { "+" } else { "" }
+        );
+        if !self.languages.is_empty() {
+            let languages: Vec<String> = self
+                .languages
+                .iter()
+                .map(|(name, count)| format!("{} {}", name, count))
+                .collect();
+            header.push_str(&format!("; {}", languages.join(", ")));
+        }
+        if !self.manifests.is_empty() {
+            header.push_str(&format!("; build: {}", self.manifests.join(", ")));
+        }
+        let mut text = format!("{}\n{}", header, self.tree.join("\n"));
+        if self.left_out > 0 {
+            text.push_str(&format!("\n… {} more files", self.left_out));
+        }
+        text
+    }
+
+    /// Planner prompt section with the map; empty for an empty project.
+    pub fn prompt_section(&self) -> String {
+        if self.files == 0 {
+            return String::new();
+        }
+        format!(
+            "PROJECT FILES (paths are relative to the working directory):\n{}\n\n",
+            self.render()
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Dir {
+    name: String,
+    dirs: Vec<Dir>,
+    /// Names and sizes, sorted by name.
+    files: Vec<(String, u64)>,
+    total_files: usize,
+}
+
+impl Dir {
+    fn count_languages(&self, counts: &mut BTreeMap<&'static str, usize>) {
+        for (name, _) in &self.files {
+            if let Some(language) = language_of(name) {
+                *counts.entry(language).or_default() += 1;
+            }
+        }
+        for dir in &self.dirs {
+            dir.count_languages(counts);
+        }
+    }
+}
+
+/// Reads `dir` (at `rel`, relative to the root, `""` for the root) and what
+/// is beneath it, skipping `.git` and anything the `.gitignore` files in
+/// `ignores` or in `dir` match.
+fn walk(dir: &Path, rel: &str, ignores: &mut Vec<Gitignore>, walked: &mut usize) -> Dir {
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut node = Dir {
+        name,
+        ..Default::default()
+    };
+    let pushed = match fs::read_to_string(dir.join(".gitignore")) {
+        Ok(text) => {
+            ignores.push(Gitignore::parse(rel, &text));
+            true
+        }
+        Err(_) => false,
+    };
+
+    let mut entries: Vec<(String, PathBuf, bool, u64)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_type = entry.file_type().ok()?;
+            let size = entry.metadata().map(|meta| meta.len()).unwrap_or_default();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            Some((name, entry.path(), file_type.is_dir(), size))
+        })
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, path, is_dir, size) in entries {
+        if name == ".git" || *walked >= MAX_FILES_WALKED {
+            continue;
+        }
+        let child = if rel.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", rel, name)
+        };
+        if is_ignored(ignores, &child, is_dir) {
+            continue;
+        }
+        if is_dir {
+            let sub = walk(&path, &child, ignores, walked);
+            node.total_files += sub.total_files;
+            node.dirs.push(sub);
+        } else {
+            *walked += 1;
+            node.total_files += 1;
+            node.files.push((name, size));
+        }
+    }
+    if pushed {
+        ignores.pop();
+    }
+    node
+}
+
+/// The rules of one `.gitignore`, whose patterns are relative to `base`.
+#[derive(Debug)]
+struct Gitignore {
+    base: String,
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Debug)]
+struct IgnoreRule {
+    pattern: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl Gitignore {
+    fn parse(base: &str, text: &str) -> Self {
+        let rules = text
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_end();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let (negated, line) = match line.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, line.strip_prefix('\\').unwrap_or(line)),
+                };
+                let (dir_only, line) = match line.strip_suffix('/') {
+                    Some(rest) => (true, rest),
+                    None => (false, line),
+                };
+                // A slash before the end anchors the pattern to `base`;
+                // without one it matches a name at any depth.
+                let anchored = line.contains('/');
+                let line = line.trim_start_matches('/');
+                let prefix = if anchored { "^" } else { "^(?:.*/)?" };
+                let pattern = Regex::new(&format!("{}{}$", prefix, glob_regex(line))).ok()?;
+                Some(IgnoreRule {
+                    pattern,
+                    negated,
+                    dir_only,
+                })
+            })
+            .collect();
+        Self {
+            base: base.into(),
+            rules,
+        }
+    }
+}
+
+/// Whether `path` (relative to the root) is ignored; the last matching rule
+/// wins, deeper `.gitignore` files after shallower ones.
+fn is_ignored(ignores: &[Gitignore], path: &str, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for gitignore in ignores {
+        let rel = match gitignore.base.as_str() {
+            "" => path,
+            base => match path
+                .strip_prefix(base)
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                Some(rel) => rel,
+                None => continue,
+            },
+        };
+        for rule in &gitignore.rules {
+            if (is_dir || !rule.dir_only) && rule.pattern.is_match(rel) {
+                ignored = !rule.negated;
+            }
+        }
+    }
+    ignored
+}
+
+/// A gitignore glob as a regex: `*` and `?` stay within a path segment,
+/// `**` crosses segments and `[...]` is a character class.
+fn glob_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let mut class = String::from("[");
+                if chars.next_if(|&c| c == '!' || c == '^').is_some() {
+                    class.push('^');
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        class.push('\\');
+                    }
+                    class.push(c);
+                }
+                if closed {
+                    regex.push_str(&class);
+                    regex.push(']');
+                } else {
+                    regex.push_str(&regex::escape(&class));
+                }
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    regex.push_str(&regex::escape(&next.to_string()));
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+fn language_of(file: &str) -> Option<&'static str> {
+    let (_, extension) = file.rsplit_once('.')?;
+    LANGUAGES
+        .iter()
+        .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+        .map(|(_, language)| *language)
+}
+
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 * 1024 => format!("{:.1} GB", b as f64 / (1024.0 * 1024.0 * 1024.0)),
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b => format!("{:.1} KB", b as f64 / 1024.0),
+    }
+}
+
+/// Lists the workspace (or a directory in it) as a condensed tree with
+/// file counts per language, for orienting before code changes.
+#[derive(Clone, Debug, Default)]
+pub struct ProjectMapTool {
+    workspace: Option<Workspace>,
+    map: ProjectMap,
+}
+
+impl ProjectMapTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `workspace`, refusing directories outside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    pub fn with_map(mut self, map: ProjectMap) -> Self {
+        self.map = map;
+        self
+    }
+
+    fn map_dir(&self, path: &str, max_depth: Option<usize>) -> ToolResult {
+        let dir = match &self.workspace {
+            Some(workspace) => match workspace.resolve(path) {
+                Ok(dir) => dir,
+                Err(e) => return ToolResult::failure(&e),
+            },
+            None => PathBuf::from(path),
+        };
+        let map = match max_depth {
+            Some(depth) => self.map.clone().with_max_depth(depth),
+            None => self.map.clone(),
+        };
+        match map.scan(&dir) {
+            Ok(summary) => ToolResult::success(&summary.render()),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+}
+
+impl Tool for ProjectMapTool {
+    fn name(&self) -> &str {
+        "project_map"
+    }
+
+    fn description(&self) -> &str {
+        "Lists the project's files as a condensed tree (honouring .gitignore) with file counts per language and the build manifests."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        // Input format: a directory to map, or nothing for the whole project.
+        let path = input.trim();
+        self.map_dir(if path.is_empty() { "." } else { path }, None)
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        if let Some(input) = args.as_str() {
+            return self.execute(input);
+        }
+        let depth = args["max_depth"].as_u64().map(|depth| depth as usize);
+        self.map_dir(args["path"].as_str().unwrap_or("."), depth)
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        let path = input.trim();
+        SimulationOutcome::read_only(&format!(
+            "would list the files in {}",
+            if path.is_empty() { "." } else { path }
+        ))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.preview(args["path"].as_str().or(args.as_str()).unwrap_or("."))
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Directory to map (e.g. 'src'), or empty for the whole project".into(),
+            tags: vec!["code".into(), "files".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "max_depth": { "type": "integer", "minimum": 1 }
+                }
+            })),
+            timeout: None,
+            idempotent: true,
+            examples: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_map_honours_gitignore_and_limits() {
+        let dir = std::env::temp_dir().join(format!("project-map-{}", std::process::id()));
+        let write = |path: &str, contents: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write(".gitignore", "target/\n*.log\n!keep.log\n/notes.md\n");
+        write("Cargo.toml", "[package]");
+        write("notes.md", "");
+        write("debug.log", "");
+        write("keep.log", "");
+        write("target/debug/app", "");
+        write("src/lib.rs", "");
+        write("src/tools/a.rs", "");
+        write("src/tools/b.rs", "");
+        write("src/tools/.gitignore", "b.rs\n");
+        write("docs/notes.md", &"x".repeat(2048));
+
+        let summary = ProjectMap::new()
+            .with_max_depth(2)
+            .with_large_file(1024)
+            .scan(&dir)
+            .unwrap();
+        assert_eq!(summary.files, 7);
+        assert_eq!(summary.manifests, ["Cargo.toml"]);
+        assert_eq!(summary.languages[0], ("Rust".to_string(), 2));
+        assert_eq!(
+            summary.tree,
+            [
+                "docs/",
+                "  notes.md (2.0 KB)",
+                "src/",
+                "  tools/ (2 files)",
+                "  lib.rs",
+                ".gitignore",
+                "Cargo.toml",
+                "keep.log",
+            ]
+        );
+
+        let short = ProjectMap::new().with_max_entries(3).scan(&dir).unwrap();
+        assert_eq!(short.tree.len(), 3);
+        assert!(short.render().ends_with("… 6 more files"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,15 +1,23 @@
 // src/tools/reflector.rs
 
+use crate::llm::{Conversation, ModelTier};
+use crate::protocol::SimulationOutcome;
 use crate::tools::llm::LLMTool;
 use crate::tools::{Tool, ToolResult, ToolSpec};
 
+const SYSTEM_PROMPT: &str = "You are a reflection module embedded in an autonomous agent runtime. \
+Given a memory log, produce a structured reflection that summarizes what the agent tried to do, \
+what happened, what failed (if anything), and what could be improved next time.";
+
 pub struct ReflectorTool {
     pub llm: LLMTool,
 }
 
 impl ReflectorTool {
     pub fn new(llm: LLMTool) -> Self {
-        Self { llm }
+        Self {
+            llm: llm.for_component("reflector").for_tier(ModelTier::Cheap),
+        }
     }
 }
 
@@ -24,13 +32,7 @@ impl Tool for ReflectorTool {
 
     fn execute(&self, input: &str) -> ToolResult {
         let prompt = format!(
-            r#"You are a reflection module embedded in an autonomous agent runtime.
-
-Given the following memory log, produce a structured reflection that summarizes what the agent tried to do, what happened, what failed (if anything), and what could be improved next time.
-
----
-
-# 🧠 Reflection Summary
+            r#"# 🧠 Reflection Summary
 
 ## Memory Log
 {input}
@@ -54,19 +56,26 @@ Given the following memory log, produce a structured reflection that summarizes
 "#
         );
 
-        let result = self.llm.execute(&prompt);
-        match result.success {
-            true => ToolResult::success(&result.output.unwrap_or_else(|| "(no output)".into())),
-            false => ToolResult::failure("LLM failed to generate reflection."),
+        let conversation = Conversation::new()
+            .with_system(SYSTEM_PROMPT)
+            .with_user(&prompt);
+        match self.llm.chat(&conversation) {
+            Ok(reflection) => ToolResult::success(&reflection),
+            Err(_) => ToolResult::failure("LLM failed to generate reflection."),
         }
     }
 
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!("{} would call the LLM", self.name()))
+    }
+
     fn spec(&self) -> ToolSpec {
         ToolSpec {
             name: self.name().into(),
             description: self.description().into(),
             input_hint: "Pass memory log and goal as plain text.".into(),
             tags: vec!["introspection".into(), "reflection".into(), "llm".into()],
+            ..Default::default()
         }
     }
 }
@@ -0,0 +1,125 @@
+// src/tools/registry.rs
+
+use crate::tools::{Tool, ToolSpec};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Tools available to a run, keyed by name. Planners discover tools through
+/// `list_specs()`/`render_prompt_section()`, so a newly registered tool shows
+/// up in prompts and validation without prompt edits.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: HashMap<String, Arc<dyn Tool + Send + Sync>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `tool` under its own name, returning any tool it replaced.
+    pub fn register(
+        &mut self,
+        tool: Arc<dyn Tool + Send + Sync>,
+    ) -> Option<Arc<dyn Tool + Send + Sync>> {
+        self.tools.insert(tool.name().to_string(), tool)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Arc<dyn Tool + Send + Sync>> {
+        self.tools.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn Tool + Send + Sync>> {
+        self.tools.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Registered tool names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.tools.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Specs of every registered tool, sorted by name.
+    pub fn list_specs(&self) -> Vec<ToolSpec> {
+        let mut specs: Vec<_> = self.tools.values().map(|tool| tool.spec()).collect();
+        specs.sort_by(|a, b| a.name.cmp(&b.name));
+        specs
+    }
+
+    /// Argument schemas declared by registered tools, keyed by tool name.
+    pub fn schemas(&self) -> HashMap<String, Value> {
+        self.tools
+            .values()
+            .filter_map(|tool| {
+                let spec = tool.spec();
+                spec.input_schema.map(|schema| (spec.name, schema))
+            })
+            .collect()
+    }
+
+    /// The "AVAILABLE TOOLS" prompt section: one entry per tool with its
+    /// description, an example input, its argument schema and sample calls
+    /// if it declares them.
+    pub fn render_prompt_section(&self) -> String {
+        render_specs(self.list_specs())
+    }
+
+    /// Like `render_prompt_section`, for the tools in `names` only (see
+    /// `ToolSelector`).
+    pub fn render_prompt_section_for(&self, names: &[String]) -> String {
+        let specs = self
+            .list_specs()
+            .into_iter()
+            .filter(|spec| names.contains(&spec.name))
+            .collect();
+        render_specs(specs)
+    }
+}
+
+fn render_specs(specs: Vec<ToolSpec>) -> String {
+    if specs.is_empty() {
+        return "AVAILABLE TOOLS: none\n\n".into();
+    }
+
+    let mut section = String::from("AVAILABLE TOOLS:\n");
+    for spec in specs {
+        section.push_str(&format!("- {}: {}\n", spec.name, spec.description));
+        if !spec.input_hint.is_empty() {
+            section.push_str(&format!("  input example: {}\n", spec.input_hint));
+        }
+        if let Some(schema) = &spec.input_schema {
+            section.push_str(&format!(
+                "  args schema (pass an \"args\" object matching it instead of \"input\"): {}\n",
+                schema
+            ));
+        }
+        for example in &spec.examples {
+            section.push_str(&format!(
+                "  example: {} → {}\n",
+                example.input, example.output_summary
+            ));
+        }
+    }
+    section.push('\n');
+    section
+}
+
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
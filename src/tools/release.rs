@@ -0,0 +1,314 @@
+// src/tools/release.rs
+
+use crate::protocol::{RiskLevel, SimulationOutcome};
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use crate::workspace::Workspace;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Bumps the `[package] version` in a Cargo.toml following semver.
+#[derive(Clone, Debug, Default)]
+pub struct VersionBumpTool {
+    workspace: Option<Workspace>,
+}
+
+impl VersionBumpTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves manifest paths inside `workspace`, refusing any outside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+}
+
+/// `path` resolved inside `workspace`, or as given without one.
+fn workspace_path(workspace: Option<&Workspace>, path: &str) -> Result<PathBuf, String> {
+    match workspace {
+        Some(workspace) => workspace.resolve(path),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+impl Tool for VersionBumpTool {
+    fn name(&self) -> &str {
+        "bump_version"
+    }
+
+    fn description(&self) -> &str {
+        "Bumps the package version in Cargo.toml (patch, minor, or major) and returns the new version."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        // Input format: "<level> [path/to/Cargo.toml]"
+        let mut parts = input.split_whitespace();
+        let level = parts.next().unwrap_or("patch");
+        let path = parts.next().unwrap_or("Cargo.toml");
+        let file = match workspace_path(self.workspace.as_ref(), path) {
+            Ok(file) => file,
+            Err(e) => return ToolResult::failure(&e),
+        };
+
+        let manifest = match std::fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) => return ToolResult::failure(&format!("Failed to read {}: {}", path, e)),
+        };
+
+        let Some((line_idx, current)) = find_package_version(&manifest) else {
+            return ToolResult::failure(&format!("No [package] version found in {}", path));
+        };
+
+        let next = match bump_version(&current, level) {
+            Ok(v) => v,
+            Err(e) => return ToolResult::failure(&e),
+        };
+
+        let updated = manifest
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                if i == line_idx {
+                    line.replacen(&format!("\"{}\"", current), &format!("\"{}\"", next), 1)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let updated = if manifest.ends_with('\n') {
+            updated + "\n"
+        } else {
+            updated
+        };
+
+        match std::fs::write(&file, updated) {
+            Ok(()) => ToolResult::success(&next),
+            Err(e) => ToolResult::failure(&format!("Failed to write {}: {}", path, e)),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        let path = input.split_whitespace().nth(1).unwrap_or("Cargo.toml");
+        SimulationOutcome::mutating(&format!("would bump the version in {}", path))
+            .with_risk(RiskLevel::High)
+            .with_paths(vec![path.into()])
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Bump level and optional manifest path (e.g. 'patch' or 'minor crates/foo/Cargo.toml')".into(),
+            tags: vec!["release".into(), "version".into(), "destructive".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "level": { "type": "string", "enum": ["major", "minor", "patch"] },
+                    "path": { "type": "string" }
+                },
+                "required": ["level"]
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        let Some(level) = args.get("level").and_then(Value::as_str) else {
+            return match args.as_str() {
+                Some(input) => self.execute(input),
+                None => ToolResult::failure("Missing 'level' argument"),
+            };
+        };
+        match args.get("path").and_then(Value::as_str) {
+            Some(path) => self.execute(&format!("{} {}", level, path)),
+            None => self.execute(level),
+        }
+    }
+}
+
+/// Creates an annotated `v<version>` git tag.
+#[derive(Clone, Debug, Default)]
+pub struct GitTagTool {
+    workspace: Option<Workspace>,
+}
+
+impl GitTagTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tags the repository at `workspace`'s root.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+}
+
+impl Tool for GitTagTool {
+    fn name(&self) -> &str {
+        "git_tag"
+    }
+
+    fn description(&self) -> &str {
+        "Creates an annotated git tag v<version> for a release."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        let version = input.trim().trim_start_matches('v');
+        if version.is_empty()
+            || !version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".-+".contains(c))
+        {
+            return ToolResult::failure(&format!("Invalid version for tag: '{}'", input));
+        }
+
+        let tag = format!("v{}", version);
+        let mut command = Command::new("git");
+        command.args(["tag", "-a", &tag, "-m", &format!("Release {}", tag)]);
+        if let Some(workspace) = &self.workspace {
+            command.current_dir(workspace.root());
+        }
+        let output = command.output();
+
+        match output {
+            Ok(out) if out.status.success() => ToolResult::success(&tag),
+            Ok(out) => ToolResult::failure(&format!(
+                "git tag failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            )),
+            Err(e) => ToolResult::failure(&format!("Command execution failed: {e}")),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        let version = input.trim().trim_start_matches('v');
+        SimulationOutcome::mutating(&format!("would create git tag v{}", version))
+            .with_risk(RiskLevel::High)
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Version to tag (e.g. '0.2.1' or '$output[bump_version]')".into(),
+            tags: vec!["release".into(), "git".into(), "destructive".into()],
+            ..Default::default()
+        }
+    }
+}
+
+/// Runs `cargo publish --dry-run` to check a crate is publishable without uploading it.
+#[derive(Clone, Debug, Default)]
+pub struct PublishDryRunTool {
+    workspace: Option<Workspace>,
+}
+
+impl PublishDryRunTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Packages the crate at `workspace`'s root; manifest paths must be inside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+}
+
+impl Tool for PublishDryRunTool {
+    fn name(&self) -> &str {
+        "publish_dry_run"
+    }
+
+    fn description(&self) -> &str {
+        "Runs `cargo publish --dry-run` to verify the crate packages and builds for release."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        let mut command = Command::new("cargo");
+        command.args(["publish", "--dry-run", "--allow-dirty"]);
+        if let Some(workspace) = &self.workspace {
+            command.current_dir(workspace.root());
+        }
+        if !input.trim().is_empty() {
+            match workspace_path(self.workspace.as_ref(), input.trim()) {
+                Ok(manifest) => command.arg("--manifest-path").arg(manifest),
+                Err(e) => return ToolResult::failure(&e),
+            };
+        }
+
+        match command.output() {
+            Ok(out) => {
+                let mut result = String::from_utf8_lossy(&out.stdout).to_string();
+                result.push_str(&String::from_utf8_lossy(&out.stderr));
+                if out.status.success() {
+                    ToolResult::success(result.trim())
+                } else {
+                    ToolResult::failure(&format!("Publish dry-run failed: {}", result.trim()))
+                }
+            }
+            Err(e) => ToolResult::failure(&format!("Command execution failed: {e}")),
+        }
+    }
+
+    fn preview(&self, _input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only("would run `cargo publish --dry-run` (uploads nothing)")
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "Optional manifest path; empty for the current crate".into(),
+            tags: vec!["release".into(), "cargo".into()],
+            ..Default::default()
+        }
+    }
+}
+
+/// Returns the line index and value of `version = "..."` inside `[package]`.
+fn find_package_version(manifest: &str) -> Option<(usize, String)> {
+    let mut in_package = false;
+    for (i, line) in manifest.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+            continue;
+        }
+        if in_package
+            && let Some(rest) = trimmed.strip_prefix("version")
+            && let Some(value) = rest.trim_start().strip_prefix('=')
+        {
+            return Some((i, value.trim().trim_matches('"').to_string()));
+        }
+    }
+    None
+}
+
+/// Computes the next semver version. Pre-release/build suffixes are dropped.
+pub fn bump_version(current: &str, level: &str) -> Result<String, String> {
+    let core = current.split(['-', '+']).next().unwrap_or(current);
+    let parts: Vec<u64> = core
+        .split('.')
+        .map(|p| p.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("Not a semver version: '{}'", current))?;
+
+    let [major, minor, patch] = parts[..] else {
+        return Err(format!("Not a semver version: '{}'", current));
+    };
+
+    match level {
+        "major" => Ok(format!("{}.0.0", major + 1)),
+        "minor" => Ok(format!("{}.{}.0", major, minor + 1)),
+        "patch" => Ok(format!("{}.{}.{}", major, minor, patch + 1)),
+        other => Err(format!(
+            "Unknown bump level '{}', expected patch, minor, or major",
+            other
+        )),
+    }
+}
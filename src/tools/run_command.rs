@@ -1,7 +1,271 @@
-use crate::tools::{Tool, ToolResult, ToolSpec};
-use std::process::Command;
+use crate::context::ContextHandle;
+use crate::protocol::SimulationOutcome;
+use crate::tools::cancel::{CancellationToken, POLL_INTERVAL};
+use crate::tools::docker::{CONTAINER_WORKDIR, ContainerLimits, docker_run};
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec};
+use crate::workspace::Workspace;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-pub struct RunCommandTool;
+/// Runs shell commands in the current directory, or confined to a
+/// `Workspace` when built with `in_workspace`, optionally inside a container.
+///
+/// Results carry the exit code, stdout, stderr and duration as structured
+/// data. A non-zero exit fails the call unless the tool is built with
+/// `with_nonzero_exit_ok` or the call passes `"allow_failure": true`, for
+/// commands like `grep` or `diff` that exit 1 on a normal outcome.
+///
+/// Run through a context, output is streamed line by line as it arrives
+/// (see `ContextHandle::output_line`), so long builds show progress. A
+/// `background` plan step starts the command and leaves it running instead
+/// (see `ProcessManager`), e.g. a dev server for later steps to test.
+///
+/// Calls may also pass a `cwd` (relative to the workspace root, which it
+/// may not leave) and extra `env` variables, so plans need no `cd X && ...`.
+/// Variables that change how programs are found or loaded (`PATH`, `LD_*`,
+/// ...) are never set; `with_allowed_env` narrows the rest to a list.
+#[derive(Clone, Debug, Default)]
+pub struct RunCommandTool {
+    workspace: Option<Workspace>,
+    container: Option<(String, ContainerLimits)>,
+    nonzero_exit_ok: bool,
+    /// Variables calls may set; any but the protected ones when empty.
+    allowed_env: Vec<String>,
+}
+
+/// Variables a call may never set, and prefixes of such variables.
+const PROTECTED_ENV: [&str; 9] = [
+    "PATH",
+    "LD_",
+    "DYLD_",
+    "BASH_ENV",
+    "ENV",
+    "IFS",
+    "SHELLOPTS",
+    "PS4",
+    "PROMPT_COMMAND",
+];
+
+/// What a call asks for besides its command.
+struct CallOptions {
+    cwd: Option<String>,
+    env: Vec<(String, String)>,
+    nonzero_exit_ok: bool,
+}
+
+impl RunCommandTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs commands in `workspace`'s root and rejects paths outside it.
+    pub fn in_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Runs each command in a fresh `image` container under `limits`, with
+    /// the workspace (or current directory) mounted as its working directory.
+    pub fn in_container(mut self, image: &str, limits: ContainerLimits) -> Self {
+        self.container = Some((image.into(), limits));
+        self
+    }
+
+    /// Reports commands that exit non-zero as successes (with their exit
+    /// code in the data) instead of failures.
+    pub fn with_nonzero_exit_ok(mut self, ok: bool) -> Self {
+        self.nonzero_exit_ok = ok;
+        self
+    }
+
+    /// Only these variables may be set by calls (protected ones never are).
+    pub fn with_allowed_env(mut self, vars: &[&str]) -> Self {
+        self.allowed_env = vars.iter().map(|var| var.to_string()).collect();
+        self
+    }
+
+    fn check(&self, input: &str) -> Result<(), String> {
+        match &self.workspace {
+            Some(workspace) => workspace.check_command(input),
+            None => Ok(()),
+        }
+    }
+
+    fn default_options(&self) -> CallOptions {
+        CallOptions {
+            cwd: None,
+            env: Vec::new(),
+            nonzero_exit_ok: self.nonzero_exit_ok,
+        }
+    }
+
+    /// The options in `args`, or why they are not allowed.
+    fn options(&self, args: &Value) -> Result<CallOptions, String> {
+        let cwd = match &args["cwd"] {
+            Value::Null => None,
+            Value::String(cwd) => Some(cwd.clone()),
+            _ => return Err("'cwd' must be a string".into()),
+        };
+        let mut env = Vec::new();
+        match &args["env"] {
+            Value::Null => {}
+            Value::Object(vars) => {
+                for (var, value) in vars {
+                    let Some(value) = value.as_str() else {
+                        return Err(format!("'env' value of {} must be a string", var));
+                    };
+                    self.check_env_var(var)?;
+                    env.push((var.clone(), value.to_string()));
+                }
+            }
+            _ => return Err("'env' must be an object of strings".into()),
+        }
+        Ok(CallOptions {
+            cwd,
+            env,
+            nonzero_exit_ok: args["allow_failure"]
+                .as_bool()
+                .unwrap_or(self.nonzero_exit_ok),
+        })
+    }
+
+    fn check_env_var(&self, var: &str) -> Result<(), String> {
+        let valid = var
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!(
+                "'{}' is not a valid environment variable name",
+                var
+            ));
+        }
+        let protected = PROTECTED_ENV
+            .iter()
+            .any(|name| match name.strip_suffix('_') {
+                Some(_) => var.starts_with(name),
+                None => var == *name,
+            });
+        if protected {
+            return Err(format!("Environment variable '{}' may not be set", var));
+        }
+        if !self.allowed_env.is_empty() && !self.allowed_env.iter().any(|allowed| allowed == var) {
+            return Err(format!(
+                "Environment variable '{}' is not allowed (allowed: {})",
+                var,
+                self.allowed_env.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// The root commands run in and `cwd` resolved against it; `cwd` may not
+    /// leave the workspace and must be an existing directory.
+    fn directories(&self, cwd: Option<&str>) -> Result<(PathBuf, PathBuf), String> {
+        let root = match &self.workspace {
+            Some(workspace) => workspace.root().to_path_buf(),
+            None => std::env::current_dir().unwrap_or_else(|_| ".".into()),
+        };
+        let Some(cwd) = cwd.filter(|cwd| !cwd.is_empty()) else {
+            return Ok((root.clone(), root));
+        };
+        let dir = match &self.workspace {
+            Some(workspace) => workspace.resolve(cwd)?,
+            None => root.join(cwd),
+        };
+        if !dir.is_dir() {
+            return Err(format!("Working directory '{}' does not exist", cwd));
+        }
+        Ok((root, dir))
+    }
+
+    fn run_args(
+        &self,
+        args: &Value,
+        cancel: &CancellationToken,
+        context: Option<&ContextHandle>,
+    ) -> ToolResult {
+        let Some(command) = args
+            .get("command")
+            .and_then(Value::as_str)
+            .or(args.as_str())
+        else {
+            return ToolResult::failure("Missing 'command' argument");
+        };
+        match self.options(args) {
+            Ok(options) => self.run(command, options, cancel, context),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    /// The command running `input` with `options`, or why it may not run.
+    fn command(&self, input: &str, options: &CallOptions) -> Result<Command, String> {
+        let blocked = |reason: String| format!("Blocked by workspace: {}", reason);
+        self.check(input).map_err(blocked)?;
+        let (root, dir) = self.directories(options.cwd.as_deref()).map_err(blocked)?;
+        Ok(match &self.container {
+            Some((image, limits)) => {
+                let mut command = docker_run(limits, Some(&root));
+                if let Ok(relative) = dir.strip_prefix(&root)
+                    && !relative.as_os_str().is_empty()
+                {
+                    let workdir = format!("{}/{}", CONTAINER_WORKDIR, relative.display());
+                    command.args(["-w", &workdir]);
+                }
+                for (var, value) in &options.env {
+                    command.arg("--env").arg(format!("{}={}", var, value));
+                }
+                command.args(["--", image, "sh", "-c", input]);
+                command
+            }
+            None => {
+                let mut command = Command::new("sh");
+                command
+                    .arg("-c")
+                    .arg(input)
+                    .current_dir(&dir)
+                    .envs(options.env.iter().cloned());
+                command
+            }
+        })
+    }
+
+    /// Runs `input`, streaming its output to `context` if given.
+    fn run(
+        &self,
+        input: &str,
+        options: CallOptions,
+        cancel: &CancellationToken,
+        context: Option<&ContextHandle>,
+    ) -> ToolResult {
+        let mut command = match self.command(input, &options) {
+            Ok(command) => command,
+            Err(e) => return ToolResult::failure(&e),
+        };
+        let started = Instant::now();
+        let mut on_line = |stream: &str, line: &str| {
+            if let Some(context) = context {
+                context.output_line(self.name(), stream, line);
+            }
+        };
+        match stream_process(&mut command, cancel, &mut on_line) {
+            Ok((status, stdout, stderr)) => command_result(
+                status,
+                &stdout,
+                &stderr,
+                started.elapsed(),
+                options.nonzero_exit_ok,
+            ),
+            Err(result) => result,
+        }
+    }
+}
 
 impl Tool for RunCommandTool {
     fn name(&self) -> &str {
@@ -9,33 +273,57 @@ impl Tool for RunCommandTool {
     }
 
     fn description(&self) -> &str {
-        "Runs a shell command and returns its stdout/stderr output."
+        "Runs a shell command and returns its stdout/stderr output; fails if it exits non-zero unless 'allow_failure' is set."
     }
 
     fn execute(&self, input: &str) -> ToolResult {
-        let output = Command::new("sh").arg("-c").arg(input).output();
-
-        match output {
-            Ok(out) => {
-                let stdout = String::from_utf8_lossy(&out.stdout);
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                let mut result = String::new();
-                result.push_str(&stdout);
-                result.push_str(&stderr);
-
-                // Check if command succeeded (exit code 0)
-                if out.status.success() {
-                    ToolResult::success(result.trim())
-                } else {
-                    // Command failed - return as error with output for context
-                    ToolResult::failure(&format!(
-                        "Command failed (exit code {}): {}",
-                        out.status.code().unwrap_or(-1),
-                        result.trim()
-                    ))
-                }
+        self.execute_cancellable(input, &CancellationToken::new())
+    }
+
+    /// Polls the child so a timeout or aborted run kills the command instead
+    /// of leaving it running.
+    fn execute_cancellable(&self, input: &str, cancel: &CancellationToken) -> ToolResult {
+        self.run(input, self.default_options(), cancel, None)
+    }
+
+    fn execute_with(&self, input: &str, context: &ContextHandle) -> ToolResult {
+        self.run(
+            input,
+            self.default_options(),
+            context.cancel_token(),
+            Some(context),
+        )
+    }
+
+    fn simulate(&self, input: &str) -> ToolResult {
+        match (self.check(input), &self.workspace) {
+            (Err(reason), _) => ToolResult::failure(&format!("Blocked by workspace: {}", reason)),
+            (Ok(()), Some(workspace)) => ToolResult::success(&format!(
+                "[dry-run] would run `{}` in {}",
+                input,
+                workspace.root().display()
+            )),
+            (Ok(()), None) => ToolResult::success(&format!("[dry-run] would run `{}`", input)),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        match self.check(input) {
+            Ok(()) => preview_command(input),
+            Err(reason) => {
+                SimulationOutcome::read_only(&format!("would be blocked by workspace: {}", reason))
             }
-            Err(e) => ToolResult::failure(&format!("Command execution failed: {e}")),
+        }
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args
+            .get("command")
+            .and_then(Value::as_str)
+            .or(args.as_str())
+        {
+            Some(command) => self.preview(command),
+            None => SimulationOutcome::read_only("would fail: missing 'command' argument"),
         }
     }
 
@@ -45,6 +333,433 @@ impl Tool for RunCommandTool {
             description: self.description().into(),
             input_hint: "Shell command to run (e.g. 'cargo check')".into(),
             tags: vec!["shell".into(), "command".into(), "execution".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string" },
+                    "cwd": {
+                        "type": "string",
+                        "description": "Directory to run in, relative to the workspace root"
+                    },
+                    "env": {
+                        "type": "object",
+                        "description": "Extra environment variables",
+                        "additionalProperties": { "type": "string" }
+                    },
+                    "allow_failure": {
+                        "type": "boolean",
+                        "description": "Succeed even if the command exits non-zero"
+                    }
+                },
+                "required": ["command"]
+            })),
+            timeout: Some(Duration::from_secs(600)),
+            // Only commands `preview` finds read-only are cached.
+            idempotent: true,
+            examples: vec![
+                ToolExample::new(
+                    "git status --porcelain",
+                    "one line per changed file, empty if clean",
+                ),
+                ToolExample::new(
+                    "{\"command\": \"cargo test\"}",
+                    "test output; fails with the output if any test fails",
+                ),
+                ToolExample::new(
+                    "{\"command\": \"npm test\", \"cwd\": \"web\", \"env\": {\"CI\": \"1\"}}",
+                    "test output of the package in web/",
+                ),
+                ToolExample::new(
+                    "{\"command\": \"grep -c TODO src/main.rs\", \"allow_failure\": true}",
+                    "the count; $output[id.exit_code] is 1 if there were none",
+                ),
+            ],
         }
     }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        self.run_args(args, &CancellationToken::new(), None)
+    }
+
+    fn execute_args_with(&self, args: &Value, context: &ContextHandle) -> ToolResult {
+        self.run_args(args, context.cancel_token(), Some(context))
+    }
+
+    fn background_command(&self, input: &str, args: Option<&Value>) -> Result<Command, String> {
+        let Some(args) = args else {
+            return self.command(input, &self.default_options());
+        };
+        let command = args
+            .get("command")
+            .and_then(Value::as_str)
+            .or(args.as_str())
+            .ok_or("Missing 'command' argument")?;
+        self.command(command, &self.options(args)?)
+    }
+}
+
+/// Runs `command` to completion (or until `cancel` fires, killing it); see
+/// `command_result` for the result.
+pub(crate) fn run_process(command: &mut Command, cancel: &CancellationToken) -> ToolResult {
+    let started = Instant::now();
+    match capture_process(command, cancel) {
+        Ok((status, stdout, stderr)) => {
+            command_result(status, &stdout, &stderr, started.elapsed(), false)
+        }
+        Err(result) => result,
+    }
+}
+
+/// A finished command as a `ToolResult`: stdout then stderr as the text, and
+/// `{exit_code, stdout, stderr, duration_ms}` as the data. Fails on a
+/// non-zero exit unless `nonzero_exit_ok`, and always when killed by a
+/// signal (`exit_code` null).
+pub(crate) fn command_result(
+    status: ExitStatus,
+    stdout: &str,
+    stderr: &str,
+    duration: Duration,
+    nonzero_exit_ok: bool,
+) -> ToolResult {
+    let data = json!({
+        "exit_code": status.code(),
+        "stdout": stdout,
+        "stderr": stderr,
+        "duration_ms": duration.as_millis() as u64,
+    });
+    let text = format!("{}{}", stdout, stderr);
+    let text = text.trim();
+    match status.code() {
+        Some(0) => ToolResult::success(text),
+        Some(_) if nonzero_exit_ok => ToolResult::success(text),
+        code => ToolResult::failure(&format!(
+            "Command failed (exit code {}): {}",
+            code.unwrap_or(-1),
+            text
+        )),
+    }
+    .with_data(data)
+}
+
+/// Runs `command` to completion and returns its exit status, stdout and
+/// stderr; a failure or cancelled result if it could not run to the end.
+pub(crate) fn capture_process(
+    command: &mut Command,
+    cancel: &CancellationToken,
+) -> Result<(ExitStatus, String, String), ToolResult> {
+    stream_process(command, cancel, &mut |_, _| {})
+}
+
+/// `capture_process`, calling `on_line` with the stream (`stdout` or
+/// `stderr`) and text of each output line as it arrives.
+pub(crate) fn stream_process(
+    command: &mut Command,
+    cancel: &CancellationToken,
+    on_line: &mut dyn FnMut(&str, &str),
+) -> Result<(ExitStatus, String, String), ToolResult> {
+    let spawned = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            return Err(ToolResult::failure(&format!(
+                "Command execution failed: {e}"
+            )));
+        }
+    };
+
+    let (sender, lines) = mpsc::channel();
+    let stdout = read_lines(child.stdout.take(), "stdout", sender.clone());
+    let stderr = read_lines(child.stderr.take(), "stderr", sender);
+
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if cancel.is_cancelled() => {
+                // `sh -c` may have forked the actual command.
+                #[cfg(unix)]
+                kill_descendants(child.id());
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(ToolResult::cancelled());
+            }
+            // Wait for output rather than sleeping, so lines show promptly.
+            Ok(None) => match lines.recv_timeout(POLL_INTERVAL) {
+                Ok((stream, line)) => on_line(stream, &line),
+                Err(RecvTimeoutError::Timeout) => {}
+                // Both pipes closed, but the process is still running.
+                Err(RecvTimeoutError::Disconnected) => thread::sleep(POLL_INTERVAL),
+            },
+            Err(e) => {
+                return Err(ToolResult::failure(&format!(
+                    "Command execution failed: {e}"
+                )));
+            }
+        }
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    for (stream, line) in lines.try_iter() {
+        on_line(stream, &line);
+    }
+    Ok((status, stdout, stderr))
+}
+
+/// Kills every process below `pid`, deepest first.
+#[cfg(unix)]
+fn kill_descendants(pid: u32) {
+    let Ok(children) = Command::new("pgrep")
+        .args(["-P", &pid.to_string()])
+        .output()
+    else {
+        return;
+    };
+    for child in String::from_utf8_lossy(&children.stdout).split_whitespace() {
+        let Ok(child_pid) = child.parse() else {
+            continue;
+        };
+        kill_descendants(child_pid);
+        let _ = Command::new("kill")
+            .args(["-KILL", child])
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Drains a child pipe on a background thread so the child never blocks on a
+/// full pipe, sending each line to `lines` as it arrives and returning the
+/// whole text.
+fn read_lines<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    stream: &'static str,
+    lines: Sender<(&'static str, String)>,
+) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let Some(pipe) = pipe else {
+            return String::new();
+        };
+        let mut bytes = Vec::new();
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+            let text = String::from_utf8_lossy(&line);
+            // A dropped receiver just means nobody is listening any more.
+            let _ = lines.send((stream, text.trim_end_matches(['\n', '\r']).to_string()));
+            bytes.append(&mut line);
+        }
+        String::from_utf8_lossy(&bytes).to_string()
+    })
+}
+
+/// Programs that only read state, whatever their arguments.
+const READ_ONLY_PROGRAMS: [&str; 34] = [
+    "basename", "cat", "cut", "date", "df", "diff", "dirname", "du", "echo", "false", "file",
+    "grep", "head", "hostname", "id", "jq", "less", "ls", "printenv", "printf", "ps", "pwd",
+    "realpath", "rg", "sleep", "sort", "stat", "tail", "test", "tree", "true", "uname", "wc",
+    "which",
+];
+
+/// Subcommands that only read state (build artifacts aside).
+const READ_ONLY_SUBCOMMANDS: [(&str, &[&str]); 2] = [
+    (
+        "git",
+        &[
+            "blame",
+            "describe",
+            "diff",
+            "grep",
+            "log",
+            "ls-files",
+            "rev-parse",
+            "shortlog",
+            "show",
+            "status",
+        ],
+    ),
+    (
+        "cargo",
+        &[
+            "bench",
+            "build",
+            "check",
+            "doc",
+            "metadata",
+            "search",
+            "test",
+            "tree",
+            "--version",
+        ],
+    ),
+];
+
+/// Programs whose non-flag arguments are the paths they change.
+const PATH_WRITERS: [&str; 10] = [
+    "chmod", "chown", "cp", "ln", "mkdir", "mv", "rm", "rmdir", "tee", "touch",
+];
+
+/// Classifies a shell command as read-only or mutating, listing the paths it
+/// would write (redirections and the targets of `rm`, `mv`, `touch`, ...).
+pub(crate) fn preview_command(command: &str) -> SimulationOutcome {
+    // Substituted commands are not classified, so they could do anything.
+    let mut read_only = !["$(", "`", "<(", ">("]
+        .iter()
+        .any(|substitution| command.contains(substitution));
+    let mut paths = Vec::new();
+    for segment in shell_segments(command) {
+        let (words, targets) = split_redirections(&segment);
+        paths.extend(targets);
+        if !segment_is_read_only(&words) {
+            read_only = false;
+            paths.extend(written_paths(&words));
+        }
+    }
+    paths.dedup();
+
+    let summary = format!("would run `{}`", command);
+    if read_only && paths.is_empty() {
+        SimulationOutcome::read_only(&format!("{} (read-only)", summary))
+    } else if paths.is_empty() {
+        SimulationOutcome::mutating(&format!("{} (changes state)", summary))
+    } else {
+        SimulationOutcome::mutating(&format!("{} (writes {})", summary, paths.join(", ")))
+            .with_paths(paths)
+    }
+}
+
+/// Splits a command line into simple commands at `;`, `&&`, `||`, `|`, `&`
+/// and newlines, tokenizing each one with shell quoting rules.
+fn shell_segments(command: &str) -> Vec<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => word.extend(chars.next()),
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '\\') => word.extend(chars.next()),
+            (None, ';' | '&' | '|' | '\n') => {
+                if c == '&' && word.is_empty() && words.last().is_some_and(|w| w.ends_with('>')) {
+                    // `2>&1` duplicates a descriptor rather than backgrounding.
+                    word.push(c);
+                    continue;
+                }
+                words.extend((!word.is_empty()).then(|| std::mem::take(&mut word)));
+                segments.extend((!words.is_empty()).then(|| std::mem::take(&mut words)));
+            }
+            (None, '>') => {
+                // A bare fd number before `>` belongs to the redirection.
+                if !word.chars().all(|c| c.is_ascii_digit()) {
+                    words.push(std::mem::take(&mut word));
+                }
+                word.clear();
+                words.push(
+                    if chars.next_if_eq(&'>').is_some() {
+                        ">>"
+                    } else {
+                        ">"
+                    }
+                    .into(),
+                );
+            }
+            (None, c) if c.is_whitespace() => {
+                words.extend((!word.is_empty()).then(|| std::mem::take(&mut word)));
+            }
+            (None, c) => word.push(c),
+        }
+    }
+    words.extend((!word.is_empty()).then_some(word));
+    segments.extend((!words.is_empty()).then_some(words));
+    segments
+}
+
+/// Separates `> file` / `>> file` redirections from the command words,
+/// returning the words and the files written (`/dev/null` and `&fd` excluded).
+fn split_redirections(segment: &[String]) -> (Vec<&str>, Vec<String>) {
+    let mut words = Vec::new();
+    let mut targets = Vec::new();
+    let mut tokens = segment.iter();
+    while let Some(token) = tokens.next() {
+        if token == ">" || token == ">>" {
+            if let Some(target) = tokens.next()
+                && !target.starts_with('&')
+                && target != "/dev/null"
+            {
+                targets.push(target.clone());
+            }
+        } else {
+            words.push(token.as_str());
+        }
+    }
+    (words, targets)
+}
+
+/// The program and its arguments, without leading `VAR=value` assignments.
+fn command_words<'a, 'b>(words: &'b [&'a str]) -> &'b [&'a str] {
+    let start = words
+        .iter()
+        .position(|word| !word.contains('=') || word.starts_with('-'))
+        .unwrap_or(words.len());
+    &words[start..]
+}
+
+fn segment_is_read_only(words: &[&str]) -> bool {
+    let words = command_words(words);
+    let Some(program) = words.first().map(|p| p.rsplit('/').next().unwrap_or(p)) else {
+        return true;
+    };
+    let args = &words[1..];
+    match program {
+        // Wrappers run their arguments as another command.
+        "env" | "nice" | "nohup" | "timeout" | "xargs" => false,
+        "find" => !args
+            .iter()
+            .any(|a| matches!(*a, "-delete" | "-exec" | "-execdir")),
+        "sed" => !args
+            .iter()
+            .any(|a| a.starts_with("-i") || *a == "--in-place"),
+        "cargo" if args.first() == Some(&"clippy") => !args.contains(&"--fix"),
+        "cargo" if args.first() == Some(&"fmt") => args.contains(&"--check"),
+        "git" if args.first() == Some(&"branch") => args[1..].iter().all(|a| a.starts_with('-')),
+        "git" if args.first() == Some(&"remote") => args.len() == 1 || args[1] == "-v",
+        _ => {
+            READ_ONLY_PROGRAMS.contains(&program)
+                || READ_ONLY_SUBCOMMANDS.iter().any(|(name, subcommands)| {
+                    *name == program
+                        && args
+                            .iter()
+                            .find(|a| !a.starts_with('-') || **a == "--version")
+                            .is_some_and(|sub| subcommands.contains(sub))
+                })
+        }
+    }
+}
+
+fn written_paths(words: &[&str]) -> Vec<String> {
+    let words = command_words(words);
+    let Some((program, args)) = words.split_first() else {
+        return Vec::new();
+    };
+    let mut operands = args.iter().filter(|a| !a.starts_with('-'));
+    match program.rsplit('/').next().unwrap_or(program) {
+        // The first operand of chmod/chown is the mode or owner.
+        "chmod" | "chown" => operands.skip(1).map(|a| a.to_string()).collect(),
+        // Copies and links only write their destination.
+        "cp" | "ln" => operands
+            .next_back()
+            .map(|a| a.to_string())
+            .into_iter()
+            .collect(),
+        name if PATH_WRITERS.contains(&name) => operands.map(|a| a.to_string()).collect(),
+        "sed" => operands.skip(1).map(|a| a.to_string()).collect(),
+        "cargo" if args.first() == Some(&"fmt") => vec![".".into()],
+        "cargo" if matches!(args.first(), Some(&"add" | &"remove")) => vec!["Cargo.toml".into()],
+        _ => Vec::new(),
+    }
 }
@@ -0,0 +1,322 @@
+// src/tools/sandboxed_command.rs
+
+use crate::protocol::SimulationOutcome;
+use crate::tools::cancel::CancellationToken;
+use crate::tools::docker::{ContainerLimits, docker_run};
+use crate::tools::run_command::{preview_command, run_process};
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use crate::workspace::check_command_paths;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Command fragments rejected by default: destructive, privilege-escalating,
+/// or piping remote scripts into a shell.
+pub const DEFAULT_DENY_LIST: [&str; 14] = [
+    "rm -rf /",
+    "rm -rf ~",
+    "rm -rf *",
+    "mkfs",
+    "dd if=",
+    ":(){",
+    "shutdown",
+    "reboot",
+    "sudo ",
+    "chmod -R 777 /",
+    "> /dev/sd",
+    "| sh",
+    "| bash",
+    "git push --force",
+];
+
+/// Shell constructs refused outright when an allow list is set.
+const UNCHECKABLE: [&str; 6] = ["$(", "`", "<(", ">(", ">", "<"];
+
+/// Splits a command into the segments the allow list checks.
+fn separator_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"&&|\|\||[;|&\n]").unwrap())
+}
+
+/// Where sandboxed commands actually run.
+#[derive(Clone, Debug, Default)]
+pub enum SandboxBackend {
+    /// A plain `sh -c` confined to the working directory.
+    #[default]
+    Host,
+    /// `docker run --rm` under `limits` with the working directory mounted at `/workspace`.
+    Docker {
+        image: String,
+        limits: ContainerLimits,
+    },
+    /// `chroot <root>`; the working directory is interpreted inside the new root.
+    Chroot { root: PathBuf },
+}
+
+/// Guarded drop-in replacement for `RunCommandTool` (registered under the
+/// same `run_command` name by default).
+///
+/// Commands run inside `working_dir`, must not reference paths outside it,
+/// are checked against allow/deny lists, and have their output capped.
+#[derive(Clone, Debug)]
+pub struct SandboxedCommandTool {
+    pub name: String,
+    pub working_dir: PathBuf,
+    /// Allowed command prefixes; empty allows anything not denied. Each
+    /// command of a list or pipeline must match one, and substitutions and
+    /// redirections are refused.
+    pub allow: Vec<String>,
+    /// Rejected command fragments.
+    pub deny: Vec<String>,
+    pub max_output_bytes: usize,
+    pub backend: SandboxBackend,
+}
+
+impl SandboxedCommandTool {
+    pub fn new(working_dir: impl Into<PathBuf>) -> Self {
+        let working_dir = working_dir.into();
+        Self {
+            name: "run_command".into(),
+            working_dir: working_dir.canonicalize().unwrap_or(working_dir),
+            allow: Vec::new(),
+            deny: DEFAULT_DENY_LIST.iter().map(|d| d.to_string()).collect(),
+            max_output_bytes: 64 * 1024,
+            backend: SandboxBackend::Host,
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_allow(mut self, prefixes: &[&str]) -> Self {
+        self.allow.extend(prefixes.iter().map(|p| p.to_string()));
+        self
+    }
+
+    pub fn with_deny(mut self, fragments: &[&str]) -> Self {
+        self.deny.extend(fragments.iter().map(|f| f.to_string()));
+        self
+    }
+
+    pub fn with_max_output_bytes(mut self, max: usize) -> Self {
+        self.max_output_bytes = max;
+        self
+    }
+
+    /// Runs commands in `image` under the default `ContainerLimits` (offline).
+    pub fn in_docker(self, image: &str) -> Self {
+        self.in_docker_with_limits(image, ContainerLimits::default())
+    }
+
+    pub fn in_docker_with_limits(mut self, image: &str, limits: ContainerLimits) -> Self {
+        self.backend = SandboxBackend::Docker {
+            image: image.into(),
+            limits,
+        };
+        self
+    }
+
+    pub fn in_chroot(mut self, root: impl Into<PathBuf>) -> Self {
+        self.backend = SandboxBackend::Chroot { root: root.into() };
+        self
+    }
+
+    /// Why `input` may not run, if it is rejected.
+    pub fn check(&self, input: &str) -> Result<(), String> {
+        let command = input.trim();
+        if command.is_empty() {
+            return Err("Empty command".into());
+        }
+
+        if let Some(denied) = self.deny.iter().find(|d| command.contains(d.as_str())) {
+            return Err(format!("Command matches deny list entry '{}'", denied));
+        }
+
+        if !self.allow.is_empty() {
+            // Substitutions and redirections hide programs and files the
+            // segment prefixes below never see.
+            if let Some(construct) = UNCHECKABLE.iter().find(|c| command.contains(*c)) {
+                return Err(format!(
+                    "Command uses '{}', which the allow list cannot check",
+                    construct
+                ));
+            }
+            for segment in separator_regex()
+                .split(command)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+            {
+                if !self.allow.iter().any(|a| segment.starts_with(a.as_str())) {
+                    return Err(format!("Command '{}' is not in the allow list", segment));
+                }
+            }
+        }
+
+        check_command_paths(&self.working_dir, command)
+    }
+
+    fn command(&self, input: &str) -> Command {
+        match &self.backend {
+            SandboxBackend::Host => {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(input).current_dir(&self.working_dir);
+                command
+            }
+            SandboxBackend::Docker { image, limits } => {
+                let mut command = docker_run(limits, Some(&self.working_dir));
+                command.args(["--", image, "sh", "-c", input]);
+                command
+            }
+            SandboxBackend::Chroot { root } => {
+                let mut command = Command::new("chroot");
+                // Quoted for `sh`, so a `'` in the path cannot end the string.
+                let dir = self
+                    .working_dir
+                    .display()
+                    .to_string()
+                    .replace('\'', r"'\''");
+                command
+                    .arg(root)
+                    .args(["sh", "-c"])
+                    .arg(format!("cd '{}' && {}", dir, input));
+                command
+            }
+        }
+    }
+
+    fn cap(&self, text: String) -> String {
+        if text.len() <= self.max_output_bytes {
+            return text;
+        }
+        let mut end = self.max_output_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}\n… [truncated {} bytes]", &text[..end], text.len() - end)
+    }
+}
+
+impl Tool for SandboxedCommandTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Runs a shell command inside a sandboxed working directory with allow/deny filtering and capped output."
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.execute_cancellable(input, &CancellationToken::new())
+    }
+
+    fn execute_cancellable(&self, input: &str, cancel: &CancellationToken) -> ToolResult {
+        if let Err(reason) = self.check(input) {
+            return ToolResult::failure(&format!("Blocked by sandbox: {}", reason));
+        }
+
+        let mut result = run_process(&mut self.command(input), cancel);
+        result.output = result.output.map(|text| self.cap(text));
+        result.error = result.error.map(|text| self.cap(text));
+        if let Some(data) = &mut result.data {
+            for stream in ["stdout", "stderr"] {
+                if let Some(Value::String(text)) = data.get_mut(stream) {
+                    *text = self.cap(std::mem::take(text));
+                }
+            }
+        }
+        result
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        match args
+            .get("command")
+            .and_then(Value::as_str)
+            .or(args.as_str())
+        {
+            Some(command) => self.execute(command),
+            None => ToolResult::failure("Missing 'command' argument"),
+        }
+    }
+
+    fn simulate(&self, input: &str) -> ToolResult {
+        match self.check(input) {
+            Ok(()) => ToolResult::success(&format!(
+                "[dry-run] would run `{}` in {}",
+                input,
+                self.working_dir.display()
+            )),
+            Err(reason) => ToolResult::failure(&format!("Blocked by sandbox: {}", reason)),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        match self.check(input) {
+            Ok(()) => {
+                let outcome = preview_command(input);
+                SimulationOutcome {
+                    summary: format!("{} in {}", outcome.summary, self.working_dir.display()),
+                    ..outcome
+                }
+            }
+            Err(reason) => {
+                SimulationOutcome::read_only(&format!("would be blocked by sandbox: {}", reason))
+            }
+        }
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        match args
+            .get("command")
+            .and_then(Value::as_str)
+            .or(args.as_str())
+        {
+            Some(command) => self.preview(command),
+            None => SimulationOutcome::read_only("would fail: missing 'command' argument"),
+        }
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name.clone(),
+            description: self.description().into(),
+            input_hint: format!(
+                "Shell command to run inside {} (e.g. 'cargo check')",
+                self.working_dir.display()
+            ),
+            tags: vec![
+                "shell".into(),
+                "command".into(),
+                "execution".into(),
+                "sandboxed".into(),
+            ],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": { "command": { "type": "string" } },
+                "required": ["command"]
+            })),
+            timeout: Some(Duration::from_secs(600)),
+            // Only commands `preview` finds read-only are cached.
+            idempotent: true,
+            examples: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chroot_commands_quote_the_working_directory() {
+        let tool = SandboxedCommandTool::new("/srv/it's here").in_chroot("/jail");
+        let command = tool.command("ls");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args[..3], ["/jail", "sh", "-c"]);
+        assert_eq!(args[3], r"cd '/srv/it'\''s here' && ls");
+    }
+}
@@ -0,0 +1,199 @@
+// src/tools/selection.rs
+
+use crate::tools::ToolSpec;
+
+/// Narrows the tools shown to the planner to those whose `ToolSpec::tags`
+/// fit the goal, so prompts stay short and the model is not tempted by
+/// tools the goal has no use for.
+///
+/// Each rule maps goal keywords to capability tags; a keyword matches a
+/// word of the goal or its goal type that starts with it ("test" matches
+/// "tests"). A tool is kept if it has a core tag (running commands,
+/// reasoning), a tag some matching rule asks for, or no tag the selector
+/// knows at all, such as MCP and custom tools. A goal no rule matches keeps
+/// every tool.
+#[derive(Clone, Debug)]
+pub struct ToolSelector {
+    rules: Vec<(Vec<String>, Vec<String>)>,
+    core_tags: Vec<String>,
+}
+
+const CORE_TAGS: &[&str] = &[
+    "shell",
+    "command",
+    "execution",
+    "llm",
+    "reasoning",
+    "introspection",
+    "verification",
+    "meta",
+    "planning",
+];
+
+const RULES: &[(&[&str], &[&str])] = &[
+    (
+        &[
+            "git", "commit", "branch", "merge", "rebase", "diff", "push", "pull", "stash",
+        ],
+        &["git", "diff"],
+    ),
+    (
+        &[
+            "test", "build", "compil", "cargo", "clippy", "crate", "rust", "lint", "bench",
+        ],
+        &["cargo", "rust", "diagnostics"],
+    ),
+    (
+        &["docker", "container", "image", "compose"],
+        &["docker", "container"],
+    ),
+    (
+        &[
+            "kubernetes",
+            "k8s",
+            "kubectl",
+            "pod",
+            "deploy",
+            "cluster",
+            "helm",
+        ],
+        &["kubernetes", "ops"],
+    ),
+    (
+        &["release", "version", "publish", "tag", "changelog"],
+        &["release", "version", "git"],
+    ),
+    (&["json", "api", "field", "extract"], &["json", "transform"]),
+    (
+        &[
+            "report", "summar", "describ", "document", "readme", "message", "template",
+        ],
+        &["text", "transform"],
+    ),
+    (
+        &[
+            "calculat",
+            "arithmetic",
+            "date",
+            "days",
+            "average",
+            "percent",
+        ],
+        &["math", "date"],
+    ),
+    (
+        &[
+            "refactor",
+            "rename",
+            "edit",
+            "fix",
+            "implement",
+            "code",
+            "bug",
+            "search",
+            "find",
+            "file",
+            "function",
+        ],
+        &["code", "edit", "search", "files"],
+    ),
+    (
+        &["error", "fail", "debug", "crash", "broken"],
+        &["error", "analysis", "fix", "diagnostics"],
+    ),
+    (
+        &[
+            "clipboard",
+            "copy",
+            "paste",
+            "notify",
+            "notification",
+            "alert",
+        ],
+        &["desktop", "clipboard", "notification"],
+    ),
+    (
+        &["subtask", "delegat", "parallel"],
+        &["agent", "delegation"],
+    ),
+];
+
+impl Default for ToolSelector {
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+        Self {
+            rules: RULES
+                .iter()
+                .map(|(keywords, tags)| (strings(keywords), strings(tags)))
+                .collect(),
+            core_tags: strings(CORE_TAGS),
+        }
+    }
+}
+
+impl ToolSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shows tools tagged with any of `tags` for goals mentioning any of
+    /// `keywords`.
+    pub fn with_rule(mut self, keywords: &[&str], tags: &[&str]) -> Self {
+        self.rules.push((
+            keywords.iter().map(|k| k.to_lowercase()).collect(),
+            tags.iter().map(|tag| tag.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Tools with any of `tags` are always shown.
+    pub fn with_core_tags(mut self, tags: &[&str]) -> Self {
+        self.core_tags
+            .extend(tags.iter().map(|tag| tag.to_string()));
+        self
+    }
+
+    /// The capability tags `goal` (classified as `goal_type`) calls for;
+    /// empty if no rule matches.
+    pub fn capabilities(&self, goal: &str, goal_type: &str) -> Vec<String> {
+        let text = format!("{} {}", goal, goal_type).to_lowercase();
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let mut tags: Vec<String> = Vec::new();
+        for (keywords, rule_tags) in &self.rules {
+            let matches = keywords
+                .iter()
+                .any(|keyword| words.iter().any(|word| word.starts_with(keyword.as_str())));
+            if matches {
+                for tag in rule_tags {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+            }
+        }
+        tags
+    }
+
+    /// The names of the `specs` to show the planner for `goal`, in order.
+    pub fn select(&self, specs: &[ToolSpec], goal: &str, goal_type: &str) -> Vec<String> {
+        let capabilities = self.capabilities(goal, goal_type);
+        specs
+            .iter()
+            .filter(|spec| capabilities.is_empty() || self.fits(spec, &capabilities))
+            .map(|spec| spec.name.clone())
+            .collect()
+    }
+
+    fn fits(&self, spec: &ToolSpec, capabilities: &[String]) -> bool {
+        let known = |tag: &String| {
+            self.core_tags.contains(tag) || self.rules.iter().any(|(_, tags)| tags.contains(tag))
+        };
+        spec.tags
+            .iter()
+            .any(|tag| self.core_tags.contains(tag) || capabilities.contains(tag))
+            || !spec.tags.iter().any(known)
+    }
+}
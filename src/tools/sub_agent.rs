@@ -0,0 +1,113 @@
+// src/tools/sub_agent.rs
+
+use crate::agent::BasicAgent;
+use crate::context::ContextHandle;
+use crate::model::TaskModel;
+use crate::policy::replan::ReplanPolicy;
+use crate::report::RunReport;
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use std::sync::{Mutex, PoisonError};
+
+/// Delegates a subtask to a child `BasicAgent` and returns its `RunReport`
+/// (as JSON) as the tool output.
+///
+/// The child keeps its own context (see `Context::subset`), so its tools,
+/// memory and LLM budget are separate from the parent's. Calls are
+/// serialized: the child runs one subtask at a time.
+pub struct SubAgentTool {
+    name: String,
+    description: String,
+    agent: Mutex<BasicAgent>,
+    policy: ReplanPolicy,
+}
+
+impl SubAgentTool {
+    /// Wraps `agent`; the step input becomes the child's goal, or its own
+    /// goal is used when the input is empty.
+    pub fn new(name: &str, description: &str, agent: BasicAgent) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            agent: Mutex::new(agent),
+            policy: ReplanPolicy::default(),
+        }
+    }
+
+    /// Follow-up plans the child may try after an unsuccessful run (default 1).
+    pub fn with_max_replans(mut self, max_replans: usize) -> Self {
+        self.policy.max_replans = max_replans;
+        self
+    }
+
+    /// Replaces the replan limit and escalations the child runs under.
+    pub fn with_replan_policy(mut self, policy: ReplanPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Plans, executes and (if needed) replans `goal` with the child agent.
+    pub fn run(&self, goal: &str) -> RunReport {
+        let mut agent = self.agent.lock().unwrap_or_else(PoisonError::into_inner);
+        if !goal.trim().is_empty() {
+            let limits = agent.model.limits.clone();
+            agent.model = TaskModel::new(goal.trim()).with_limits(limits);
+        }
+        agent.run(&self.policy)
+    }
+
+    /// The report as JSON output; failed if the child missed its goal.
+    fn tool_result(&self, report: &RunReport) -> ToolResult {
+        let output = serde_json::to_string_pretty(&report.to_json()).unwrap_or_default();
+        if report.success() {
+            ToolResult::success(&output)
+        } else {
+            ToolResult {
+                output: Some(output),
+                ..ToolResult::failure(&format!(
+                    "Sub-agent '{}' did not achieve: {}",
+                    self.name, report.goal
+                ))
+            }
+        }
+    }
+}
+
+impl Tool for SubAgentTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn execute(&self, input: &str) -> ToolResult {
+        self.tool_result(&self.run(input))
+    }
+
+    /// Like `execute`, and notes the child's outcome in the parent's memory.
+    fn execute_with(&self, input: &str, context: &ContextHandle) -> ToolResult {
+        let report = self.run(input);
+        context.log(
+            "sub_agent",
+            &format!(
+                "{} '{}' finished \"{}\" after {} iteration(s)",
+                if report.success() { "✅" } else { "❌" },
+                self.name,
+                report.goal,
+                report.iterations.len()
+            ),
+        );
+        self.tool_result(&report)
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            input_hint: "The subtask to delegate, e.g. \"fix the failing tests\"".into(),
+            tags: vec!["agent".into(), "delegation".into()],
+            ..Default::default()
+        }
+    }
+}
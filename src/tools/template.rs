@@ -0,0 +1,135 @@
+// src/tools/template.rs
+
+use crate::protocol::{SimulationOutcome, missing_output_regex, template_placeholder_regex};
+use crate::tools::{Tool, ToolExample, ToolResult, ToolSpec};
+use serde_json::{Map, Value, json};
+
+/// Fills `{{ name }}` placeholders in a template with named values, usually
+/// previous step outputs (`"values": {"diff": "$output[diff]"}`), to compose
+/// things like a PR description from a diff and test results. Rendering fails
+/// if a placeholder has no value or a value's reference had no output, rather
+/// than passing a half-filled document on.
+#[derive(Clone, Debug, Default)]
+pub struct TemplateTool;
+
+impl TemplateTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tool for TemplateTool {
+    fn name(&self) -> &str {
+        "render"
+    }
+
+    fn description(&self) -> &str {
+        "Renders a text template, filling {{name}} placeholders from 'values' (e.g. {\"diff\": \"$output[diff]\"}); use it to compose reports, PR descriptions or messages from several earlier outputs."
+    }
+
+    /// Freeform input is a template whose only references are `$output[...]`.
+    fn execute(&self, input: &str) -> ToolResult {
+        self.execute_args(&json!({ "template": input }))
+    }
+
+    fn execute_args(&self, args: &Value) -> ToolResult {
+        let Some(template) = args["template"].as_str().or(args.as_str()) else {
+            return ToolResult::failure("Missing 'template' argument");
+        };
+        let empty = Map::new();
+        let values = args["values"].as_object().unwrap_or(&empty);
+        match render(template, values) {
+            Ok(text) => ToolResult::success(&text),
+            Err(e) => ToolResult::failure(&e),
+        }
+    }
+
+    fn preview(&self, input: &str) -> SimulationOutcome {
+        SimulationOutcome::read_only(&format!(
+            "would render a {}-character template",
+            input.chars().count()
+        ))
+    }
+
+    fn preview_args(&self, args: &Value) -> SimulationOutcome {
+        self.preview(
+            args["template"]
+                .as_str()
+                .or(args.as_str())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.name().into(),
+            description: self.description().into(),
+            input_hint: "{\"template\": \"## Changes\\n{{diff}}\\n## Tests\\n{{tests}}\", \"values\": {\"diff\": \"$output[diff]\", \"tests\": \"$output[tests]\"}}".into(),
+            tags: vec!["text".into(), "transform".into()],
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "template": { "type": "string" },
+                    "values": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" }
+                    }
+                },
+                "required": ["template"]
+            })),
+            timeout: None,
+            idempotent: true,
+            examples: vec![ToolExample::new(
+                "{\"template\": \"Tests: {{tests}}\", \"values\": {\"tests\": \"$output[cargo]\"}}",
+                "the filled template; fails if a placeholder has no value",
+            )],
+        }
+    }
+}
+
+/// The placeholder names in `template`, in order of first use.
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for caps in template_placeholder_regex().captures_iter(template) {
+        if !names.iter().any(|name| name == &caps[1]) {
+            names.push(caps[1].to_string());
+        }
+    }
+    names
+}
+
+/// Fills every placeholder in `template` from `values`. Non-string values are
+/// inserted as JSON.
+pub fn render(template: &str, values: &Map<String, Value>) -> Result<String, String> {
+    let missing: Vec<String> = placeholders(template)
+        .into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Template placeholders without a value: {}",
+            missing.join(", ")
+        ));
+    }
+    let text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let mut unresolved: Vec<String> = Vec::new();
+    for value in values.values().map(text).chain([template.to_string()]) {
+        for caps in missing_output_regex().captures_iter(&value) {
+            if !unresolved.iter().any(|key| key == &caps[1]) {
+                unresolved.push(caps[1].to_string());
+            }
+        }
+    }
+    if !unresolved.is_empty() {
+        return Err(format!(
+            "Template references steps without output: {}",
+            unresolved.join(", ")
+        ));
+    }
+    Ok(template_placeholder_regex()
+        .replace_all(template, |caps: &regex::Captures| text(&values[&caps[1]]))
+        .into_owned())
+}
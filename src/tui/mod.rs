@@ -0,0 +1,381 @@
+// src/tui/mod.rs
+
+use crate::context::Context;
+use crate::events::AgentEvent;
+use crate::memory::SharedMemory;
+use crate::policy::user::UserInterface;
+use serde_json::Value;
+use std::io::{Write, stdin, stdout};
+use std::sync::{Arc, Mutex};
+
+const ENTER_SCREEN: &str = "\x1b[?1049h";
+const LEAVE_SCREEN: &str = "\x1b[?1049l";
+const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// A full-screen terminal view of a run (`agentic tui`): the plan as a
+/// checklist, the latest tool output, the memory log and approval prompts,
+/// redrawn on every `AgentEvent`. Plain ANSI escapes on stdout, sized from
+/// `COLUMNS`/`LINES` when set.
+///
+/// Clones share the view, so one can be attached to a context while another
+/// enters and leaves the screen.
+#[derive(Clone)]
+pub struct Dashboard {
+    view: Arc<Mutex<DashboardView>>,
+    memory: SharedMemory,
+}
+
+impl Dashboard {
+    pub fn new(goal: &str, memory: SharedMemory) -> Self {
+        Self {
+            view: Arc::new(Mutex::new(DashboardView::new(goal))),
+            memory,
+        }
+    }
+
+    /// Redraws on the context's events and takes over its approvals and
+    /// `AskUser` questions.
+    pub fn attach(&self, context: &mut Context) {
+        let dashboard = self.clone();
+        context.events.subscribe(move |event: &AgentEvent| {
+            dashboard.view.lock().unwrap().apply(event);
+            dashboard.draw();
+        });
+        context.user = Arc::new(self.clone());
+    }
+
+    /// Switches to the terminal's alternate screen and draws.
+    pub fn enter(&self) {
+        print!("{}", ENTER_SCREEN);
+        self.draw();
+    }
+
+    /// Restores the normal screen, leaving the scrollback as it was.
+    pub fn leave(&self) {
+        print!("{}", LEAVE_SCREEN);
+        let _ = stdout().flush();
+    }
+
+    pub fn draw(&self) {
+        let (width, height) = terminal_size();
+        let memory = self.memory.read().read_all();
+        let frame = self.view.lock().unwrap().render(&memory, width, height);
+        print!("{}{}", CLEAR, frame);
+        let _ = stdout().flush();
+    }
+
+    /// Shows `prompt` at the bottom of the screen and reads a line of reply.
+    fn prompt(&self, prompt: &str) -> Option<String> {
+        self.view.lock().unwrap().prompt = Some(prompt.to_string());
+        self.draw();
+        let mut line = String::new();
+        let answer = match stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        };
+        self.view.lock().unwrap().prompt = None;
+        self.draw();
+        answer
+    }
+}
+
+impl UserInterface for Dashboard {
+    fn ask(&self, question: &str, _variable: &str) -> Option<String> {
+        self.prompt(question)
+    }
+
+    /// High-risk calls (with `reasons`) need a typed `yes`, as on the plain terminal.
+    fn approve(&self, action: &str, reasons: &[String]) -> Option<bool> {
+        if reasons.is_empty() {
+            let answer = self.prompt(&format!("{}? (Y/n)", action))?;
+            return Some(!answer.eq_ignore_ascii_case("n"));
+        }
+        let prompt = format!(
+            "⚠️ HIGH-RISK: {} ({}). Type 'yes' to run it anyway:",
+            action,
+            reasons.join("; ")
+        );
+        Some(self.prompt(&prompt)? == "yes")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl ItemStatus {
+    fn icon(self) -> &'static str {
+        match self {
+            ItemStatus::Pending => "⬜",
+            ItemStatus::Running => "⏳",
+            ItemStatus::Succeeded => "✅",
+            ItemStatus::Failed => "❌",
+        }
+    }
+}
+
+/// A plan step on the checklist; `depth` indents branch and loop bodies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChecklistItem {
+    pub id: Option<String>,
+    pub tool: String,
+    pub label: String,
+    pub depth: usize,
+    pub status: ItemStatus,
+}
+
+/// What the dashboard shows, built up from events.
+#[derive(Clone, Debug, Default)]
+pub struct DashboardView {
+    pub goal: String,
+    pub checklist: Vec<ChecklistItem>,
+    /// The tool and output (or error) of the last finished step, or the
+    /// lines a running step has printed so far.
+    pub output: Option<(String, String)>,
+    /// Set while `output` holds a running step's lines.
+    pub streaming: bool,
+    /// Replans, reviews and failures, newest last.
+    pub notes: Vec<String>,
+    pub prompt: Option<String>,
+    /// Set by `RunCompleted`: whether the goal was achieved.
+    pub finished: Option<bool>,
+}
+
+impl DashboardView {
+    pub fn new(goal: &str) -> Self {
+        Self {
+            goal: goal.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn apply(&mut self, event: &AgentEvent) {
+        match event {
+            AgentEvent::PlanGenerated { plan, replan, .. } => {
+                if *replan {
+                    self.notes.push("🔁 New follow-up plan".into());
+                }
+                self.checklist.clear();
+                self.finished = None;
+                flatten_plan(plan, 0, &mut self.checklist);
+            }
+            AgentEvent::PlanReviewed {
+                critic,
+                approved,
+                concerns,
+                ..
+            } => self.notes.push(format!(
+                "🧐 {} {}{}",
+                critic,
+                if *approved { "approved" } else { "rejected" },
+                if concerns.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", concerns.join("; "))
+                }
+            )),
+            AgentEvent::StepStarted { id, tool, input } => {
+                // Loop bodies run their items again.
+                let repeat = self.checklist.iter().rposition(|item| item.tool == *tool);
+                match (
+                    self.find_item(id.as_deref(), tool, ItemStatus::Pending),
+                    repeat,
+                ) {
+                    (Some(item), _) => item.status = ItemStatus::Running,
+                    (None, Some(i)) => self.checklist[i].status = ItemStatus::Running,
+                    (None, None) => self.checklist.push(ChecklistItem {
+                        id: id.clone(),
+                        tool: tool.clone(),
+                        label: input.clone(),
+                        depth: 0,
+                        status: ItemStatus::Running,
+                    }),
+                }
+            }
+            AgentEvent::StepOutput { tool, line, .. } => match &mut self.output {
+                Some((current, text)) if self.streaming && current == tool => {
+                    text.push('\n');
+                    text.push_str(line);
+                }
+                _ => {
+                    self.output = Some((tool.clone(), line.clone()));
+                    self.streaming = true;
+                }
+            },
+            AgentEvent::StepFinished { step } => {
+                self.streaming = false;
+                if let Some(item) =
+                    self.find_item(step.id.as_deref(), &step.tool, ItemStatus::Running)
+                {
+                    item.status = if step.success {
+                        ItemStatus::Succeeded
+                    } else {
+                        ItemStatus::Failed
+                    };
+                }
+                let text = step.output.as_deref().or(step.error.as_deref());
+                self.output = Some((step.tool.clone(), text.unwrap_or_default().to_string()));
+            }
+            AgentEvent::ToolFailed { tool, error, .. } => {
+                self.notes.push(format!("❌ {}: {}", tool, error));
+            }
+            AgentEvent::ReplanTriggered { reason, .. } => {
+                self.notes.push(format!("🔁 Replanning: {}", reason));
+            }
+            AgentEvent::RunCompleted { goal_achieved, .. } => {
+                self.finished = Some(*goal_achieved);
+            }
+        }
+    }
+
+    /// The first item for `tool` (and `id`, when given) with `status`.
+    fn find_item(
+        &mut self,
+        id: Option<&str>,
+        tool: &str,
+        status: ItemStatus,
+    ) -> Option<&mut ChecklistItem> {
+        self.checklist.iter_mut().find(|item| {
+            item.status == status && item.tool == tool && (id.is_none() || item.id.as_deref() == id)
+        })
+    }
+
+    /// The screen as text, `width` columns by at most `height` lines.
+    pub fn render(&self, memory: &[(String, String)], width: usize, height: usize) -> String {
+        let status = match self.finished {
+            None => "running",
+            Some(true) => "achieved",
+            Some(false) => "not achieved",
+        };
+        let mut lines = vec![format!("agentic — {} [{}]", self.goal, status)];
+
+        // Three rules and the prompt are fixed; the panes share the rest.
+        let prompt_lines = self.prompt.as_ref().map_or(0, |_| 2);
+        let budget = height.saturating_sub(lines.len() + prompt_lines + 3);
+        let (plan_rows, output_rows) = (budget / 3, budget / 3);
+        let memory_rows = budget - plan_rows - output_rows;
+
+        lines.push(rule("Plan", width));
+        let done = self
+            .checklist
+            .iter()
+            .filter(|item| item.status != ItemStatus::Pending && item.status != ItemStatus::Running)
+            .count();
+        let first = done.saturating_sub(plan_rows / 2);
+        for item in self.checklist.iter().skip(first).take(plan_rows) {
+            lines.push(format!(
+                "{}{} {} {}",
+                "  ".repeat(item.depth),
+                item.status.icon(),
+                item.tool,
+                one_line(&item.label)
+            ));
+        }
+
+        let title = match &self.output {
+            Some((tool, _)) => format!("Output: {}", tool),
+            None => "Output".into(),
+        };
+        lines.push(rule(&title, width));
+        if let Some((_, output)) = &self.output {
+            let output: Vec<_> = output.lines().collect();
+            for line in output.iter().skip(output.len().saturating_sub(output_rows)) {
+                lines.push(line.to_string());
+            }
+        }
+
+        lines.push(rule("Memory", width));
+        let log = memory
+            .iter()
+            .map(|(label, content)| format!("{}: {}", label, one_line(content)))
+            .chain(self.notes.iter().cloned())
+            .collect::<Vec<_>>();
+        lines.extend(
+            log.iter()
+                .skip(log.len().saturating_sub(memory_rows))
+                .cloned(),
+        );
+
+        if let Some(prompt) = &self.prompt {
+            lines.push(rule("Input needed", width));
+            lines.push(format!("❓ {}", prompt));
+        }
+
+        lines
+            .iter()
+            .map(|line| truncate(line, width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Appends the steps of a plan in the planner's JSON format, recursing into
+/// branch and loop bodies.
+fn flatten_plan(steps: &Value, depth: usize, items: &mut Vec<ChecklistItem>) {
+    for step in steps.as_array().into_iter().flatten() {
+        let field = |key: &str| step[key].as_str().unwrap_or_default().to_string();
+        let item = |tool: &str, label: String| ChecklistItem {
+            id: step["id"].as_str().map(str::to_string),
+            tool: tool.into(),
+            label,
+            depth,
+            status: ItemStatus::Pending,
+        };
+        match step["type"].as_str() {
+            Some("tool") => {
+                let input = match &step["input"] {
+                    Value::String(input) => input.clone(),
+                    Value::Null => step["args"].to_string(),
+                    other => other.to_string(),
+                };
+                items.push(item(&field("name"), input));
+            }
+            Some("condition") => {
+                items.push(item("if", step["predicate"].to_string()));
+                flatten_plan(&step["then"], depth + 1, items);
+                flatten_plan(&step["else"], depth + 1, items);
+            }
+            Some("loop") => {
+                items.push(item("loop", format!("up to {}×", step["max_iterations"])));
+                flatten_plan(&step["steps"], depth + 1, items);
+            }
+            Some("ask") => items.push(item("ask", field("question"))),
+            _ => {}
+        }
+    }
+}
+
+fn rule(title: &str, width: usize) -> String {
+    let title = format!("── {} ", title);
+    let fill = width.saturating_sub(title.chars().count());
+    format!("{}{}", title, "─".repeat(fill))
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn truncate(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+    let mut line: String = line.chars().take(width.saturating_sub(1)).collect();
+    line.push('…');
+    line
+}
+
+/// `COLUMNS` × `LINES`, or 100 × 30 when the shell does not export them.
+fn terminal_size() -> (usize, usize) {
+    let var = |name: &str, default: usize| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|&value| value > 0)
+            .unwrap_or(default)
+    };
+    (var("COLUMNS", 100), var("LINES", 30))
+}
@@ -0,0 +1,168 @@
+// src/validation/command.rs
+
+use regex::Regex;
+use serde::Deserialize;
+
+/// Built-in rules as `(name, pattern, reason, hint)`.
+const BUILTIN_RULES: [(&str, &str, &str, &str); 4] = [
+    (
+        "root_delete",
+        r"\brm\s+(?:-\S+\s+)*(?:/|/\*|~/?|\$HOME/?)(?:\s|;|&|\||$)|--no-preserve-root",
+        "deletes the root or home directory",
+        "remove only the specific files or directories the goal needs, by relative path",
+    ),
+    (
+        "sudo",
+        r"(?:^|[;&|(`]\s*|\s)(?:sudo|doas)\s",
+        "runs with elevated privileges",
+        "drop sudo; the agent runs unprivileged, so install into user or project directories",
+    ),
+    (
+        "curl_pipe_shell",
+        r"\b(?:curl|wget)\b[^|;&]*\|\s*(?:sudo\s+)?(?:ba|z|da)?sh\b|\b(?:ba|z)?sh\s+<\(\s*(?:curl|wget)\b",
+        "pipes a downloaded script into a shell",
+        "download the script to a file in one step and inspect or run it in a later step",
+    ),
+    (
+        "env_exfiltration",
+        r"\b(?:env|printenv|export\s+-p)\b[^;&]*\|\s*(?:curl|wget|nc|ncat)\b|/proc/(?:self|\d+)/environ|\b(?:curl|wget|nc|ncat)\b.*\$\{?[A-Z0-9_]*(?:KEY|TOKEN|SECRET|PASSWORD|PASSWD)[A-Z0-9_]*\}?",
+        "sends environment variables or credentials over the network",
+        "reference credentials as $secret[NAME] in the tool that needs them instead of sending them anywhere",
+    ),
+];
+
+/// A pattern that `run_command` inputs must not match.
+#[derive(Clone, Debug)]
+pub struct CommandRule {
+    pub name: String,
+    pub pattern: Regex,
+    /// What a matching command would do.
+    pub reason: String,
+    /// How to get the same work done without matching; shown to planners
+    /// when they are asked to correct a plan.
+    pub hint: String,
+}
+
+impl CommandRule {
+    pub fn new(name: &str, pattern: &str, reason: &str, hint: &str) -> Result<Self, String> {
+        let pattern =
+            Regex::new(pattern).map_err(|e| format!("Invalid rule pattern '{}': {}", name, e))?;
+        Ok(Self {
+            name: name.into(),
+            pattern,
+            reason: reason.into(),
+            hint: hint.into(),
+        })
+    }
+
+    /// The plan validation message for a command that matches.
+    pub fn message(&self) -> String {
+        match self.hint.is_empty() {
+            true => format!("{} (rule '{}')", self.reason, self.name),
+            false => format!("{} (rule '{}'): {}", self.reason, self.name, self.hint),
+        }
+    }
+}
+
+/// Rules checked against `run_command` inputs when plans are validated. The
+/// default set denies deleting `/` or `~`, `sudo`, piping downloads into a
+/// shell and sending environment variables over the network.
+#[derive(Clone, Debug)]
+pub struct CommandRules {
+    rules: Vec<CommandRule>,
+}
+
+impl Default for CommandRules {
+    fn default() -> Self {
+        Self {
+            rules: BUILTIN_RULES
+                .iter()
+                .map(|(name, pattern, reason, hint)| {
+                    CommandRule::new(name, pattern, reason, hint).unwrap()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CommandRules {
+    /// No rules at all.
+    pub fn none() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    pub fn with_rule(mut self, rule: CommandRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Drops the rule called `name`, e.g. `sudo` on a machine where the agent
+    /// is trusted with it.
+    pub fn without(mut self, name: &str) -> Self {
+        self.rules.retain(|rule| rule.name != name);
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.rules.iter().map(|rule| rule.name.as_str()).collect()
+    }
+
+    /// Every rule `command` breaks.
+    pub fn check(&self, command: &str) -> Vec<&CommandRule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(command))
+            .collect()
+    }
+}
+
+/// `[command_rules]` in `agent.toml`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommandRulesConfig {
+    /// Built-in rules to switch off by name.
+    pub disabled: Vec<String>,
+    /// Extra rules.
+    pub deny: Vec<CommandRuleConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandRuleConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub pattern: String,
+    #[serde(default = "default_reason")]
+    pub reason: String,
+    #[serde(default)]
+    pub hint: String,
+}
+
+fn default_reason() -> String {
+    "matches a denied command pattern".into()
+}
+
+impl CommandRulesConfig {
+    pub fn rules(&self) -> Result<CommandRules, String> {
+        let mut rules = CommandRules::default();
+        for name in &self.disabled {
+            if !rules.names().contains(&name.as_str()) {
+                return Err(format!("Unknown command rule '{}'", name));
+            }
+            rules = rules.without(name);
+        }
+        for (i, rule) in self.deny.iter().enumerate() {
+            let name = rule
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("deny_{}", i + 1));
+            rules = rules.with_rule(CommandRule::new(
+                &name,
+                &rule.pattern,
+                &rule.reason,
+                &rule.hint,
+            )?);
+        }
+        Ok(rules)
+    }
+}
@@ -1,3 +1,5 @@
 // src/validation/mod.rs
 
+pub mod command;
 pub mod plan;
+pub mod schema;
@@ -1,6 +1,45 @@
 // src/validation/plan.rs
 
+#[cfg(feature = "runtime")]
+use crate::context::Context;
+use crate::protocol::transform::OutputTransform;
+use crate::protocol::{
+    MAX_LOOP_ITERATIONS, env_reference_regex, output_reference_regex, template_placeholder_regex,
+};
+use crate::validation::command::CommandRules;
+use crate::validation::schema::validate_against_schema;
+use regex::Regex;
 use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Matches arithmetic worked out outside the `calc` tool: `12 * 7`-style
+/// expressions, and `bc`, `expr` or `$((...))` in commands.
+fn arithmetic_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"\d+(?:\.\d+)?(?:\s+[-+*/^%]\s+|\s*[*^]\s*)\(?\d+(?:\.\d+)?|\$\(\(|\|\s*bc\b|\bexpr\s+\d")
+            .unwrap()
+    })
+}
+
+/// Whether a validation problem blocks execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Worth fixing, but the plan can still run.
+    Warning,
+    /// The plan must not run until this is fixed.
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
 
 #[derive(Debug)]
 pub enum PlanValidationError {
@@ -9,12 +48,32 @@ pub enum PlanValidationError {
     MissingField(&'static str),
     InvalidTool(String),
     InvalidReference(String),
-    ToolInputMismatch { tool: String, reason: String },
+    DuplicateStepId(String),
+    ToolInputMismatch {
+        tool: String,
+        reason: String,
+    },
     RegexError(String),
     StyleWarning(String),
+    /// A state-changing step that already succeeded in an earlier plan.
+    RepeatedStep {
+        tool: String,
+        input: String,
+    },
 }
 
 impl PlanValidationError {
+    pub fn severity(&self) -> Severity {
+        match self {
+            PlanValidationError::StyleWarning(_) => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity() == Severity::Error
+    }
+
     pub fn hint(&self) -> (String, Option<Value>) {
         match self {
             PlanValidationError::UnknownType(_) => (
@@ -34,9 +93,13 @@ impl PlanValidationError {
                 Some(json!({ "name": name, "input": "..." })),
             ),
             PlanValidationError::InvalidReference(var) => (
-                "Reference to output of nonexistent step.".to_string(),
+                "Reference to output of a step that does not run earlier in the plan. Use the id (or, without one, the tool name) of an earlier step.".to_string(),
                 Some(json!({ "reference": var })),
             ),
+            PlanValidationError::DuplicateStepId(id) => (
+                "Step id is already used by an earlier step. Step ids must be unique.".to_string(),
+                Some(json!({ "id": id })),
+            ),
             PlanValidationError::ToolInputMismatch { tool, reason } => (
                 "Tool input is invalid or unsafe.".to_string(),
                 Some(json!({ "tool": tool, "reason": reason })),
@@ -46,13 +109,208 @@ impl PlanValidationError {
                 Some(json!({ "error": desc })),
             ),
             PlanValidationError::StyleWarning(msg) => (msg.clone(), None),
+            PlanValidationError::RepeatedStep { tool, input } => (
+                "Step already completed in an earlier plan. Reference its output as $output[...] instead of running it again.".to_string(),
+                Some(json!({ "tool": tool, "input": input })),
+            ),
+        }
+    }
+}
+
+/// Asks a planner to fix `errors` in the plan it just returned.
+pub fn correction_prompt(errors: &[PlanValidationError]) -> String {
+    let mut prompt = String::from("That plan failed validation:\n");
+    for error in errors {
+        let (message, hint) = error.hint();
+        prompt.push_str(&format!("- {:?}: {}", error, message));
+        if let Some(hint) = hint {
+            prompt.push_str(&format!(" (e.g. {})", hint));
         }
+        prompt.push('\n');
     }
+    prompt.push_str("Reply with the complete corrected JSON plan, fixing every error above.");
+    prompt
 }
 
 pub fn validate_plan(plan: &[Value], registered_tools: &[&str]) -> Vec<PlanValidationError> {
+    validate_plan_after(plan, registered_tools, &[])
+}
+
+/// `validate_plan` for a plan that continues earlier work: `$output[key]`
+/// may also reference the keys in `prior_refs`.
+pub fn validate_plan_after(
+    plan: &[Value],
+    registered_tools: &[&str],
+    prior_refs: &[String],
+) -> Vec<PlanValidationError> {
+    let mut errors = Vec::new();
+    let mut seen_refs = prior_refs.to_vec();
+    validate_steps(plan, registered_tools, &mut seen_refs, &mut errors);
+    errors
+}
+
+/// Every check for a plan run in `context`: structure and references
+/// (`validate_plan_after`), tool input schemas (`validate_plan_args`) and the
+/// context's `run_command` rules (`validate_commands`).
+#[cfg(feature = "runtime")]
+pub fn validate_in_context(
+    plan: &[Value],
+    context: &Context,
+    prior_refs: &[String],
+) -> Vec<PlanValidationError> {
+    let mut tool_names = context.tools.names();
+    tool_names.extend(context.async_tools.keys().cloned());
+    let registered_tools: Vec<&str> = tool_names.iter().map(String::as_str).collect();
+    let mut errors = validate_plan_after(plan, &registered_tools, prior_refs);
+    errors.extend(validate_plan_args(plan, &context.tool_schemas()));
+    errors.extend(validate_commands(plan, &context.command_rules));
+    errors.extend(validate_env_references(plan, &context.env_vars));
+    if registered_tools.contains(&"calc") {
+        errors.extend(suggest_calc(plan));
+    }
+    if registered_tools.contains(&"render") {
+        errors.extend(validate_templates(plan));
+    }
+    errors
+}
+
+/// Every `$env[VAR]` in a tool step's input or args must name a variable in
+/// `allowed`.
+pub fn validate_env_references(plan: &[Value], allowed: &[String]) -> Vec<PlanValidationError> {
+    let mut errors = Vec::new();
+    for step in flatten_steps(plan) {
+        let Some(name) = step.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let text = [step.get("input"), step.get("args")]
+            .into_iter()
+            .flatten()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        for caps in env_reference_regex().captures_iter(&text) {
+            if !allowed.iter().any(|var| var == &caps[1]) {
+                errors.push(PlanValidationError::ToolInputMismatch {
+                    tool: name.to_string(),
+                    reason: format!(
+                        "$env[{}] is not an allowed environment variable (allowed: {})",
+                        &caps[1],
+                        if allowed.is_empty() {
+                            "none".to_string()
+                        } else {
+                            allowed.join(", ")
+                        }
+                    ),
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// Checks `render` steps: every `{{ name }}` placeholder in the template needs
+/// an entry in `values`, and values the template never uses are flagged.
+pub fn validate_templates(plan: &[Value]) -> Vec<PlanValidationError> {
     let mut errors = Vec::new();
+    for step in flatten_steps(plan) {
+        if step.get("name").and_then(|v| v.as_str()) != Some("render") {
+            continue;
+        }
+        let Some(args) = step
+            .get("args")
+            .or(step.get("input"))
+            .filter(|v| v.is_object())
+        else {
+            continue;
+        };
+        let Some(template) = args.get("template").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let values = args.get("values").and_then(|v| v.as_object());
+        let mut used = Vec::new();
+        for caps in template_placeholder_regex().captures_iter(template) {
+            let name = caps[1].to_string();
+            if used.contains(&name) {
+                continue;
+            }
+            if !values.is_some_and(|values| values.contains_key(&name)) {
+                errors.push(PlanValidationError::ToolInputMismatch {
+                    tool: "render".to_string(),
+                    reason: format!(
+                        "Template placeholder '{{{{{}}}}}' has no entry in 'values'",
+                        name
+                    ),
+                });
+            }
+            used.push(name);
+        }
+        for name in values.into_iter().flat_map(|values| values.keys()) {
+            if !used.contains(name) {
+                errors.push(PlanValidationError::StyleWarning(format!(
+                    "Render value '{}' is not used by its template",
+                    name
+                )));
+            }
+        }
+    }
+    errors
+}
+
+/// Warnings for tool steps, at any depth, that compute something the `calc`
+/// tool would evaluate reliably: arithmetic in an LLM prompt, or `bc`, `expr` or
+/// `$((...))` in a command.
+pub fn suggest_calc(plan: &[Value]) -> Vec<PlanValidationError> {
+    flatten_steps(plan)
+        .into_iter()
+        .filter_map(|step| {
+            let name = step.get("name")?.as_str()?;
+            if name == "calc" {
+                return None;
+            }
+            let input = step.get("args").or(step.get("input"))?;
+            let text = input.as_str().map_or_else(|| input.to_string(), str::to_string);
+            let found = arithmetic_regex().find(&text)?;
+            Some(PlanValidationError::StyleWarning(format!(
+                "Step '{}' computes '{}' itself; use the calc tool for arithmetic and date math (e.g. {{\"type\": \"tool\", \"name\": \"calc\", \"input\": \"12 * 7\"}}) and reference its $output",
+                name,
+                found.as_str().trim()
+            )))
+        })
+        .collect()
+}
 
+/// Tool steps, at any depth, that repeat one of the `completed` `(tool,
+/// input)` calls verbatim.
+pub fn find_repeated_steps(
+    plan: &[Value],
+    completed: &[(String, String)],
+) -> Vec<PlanValidationError> {
+    flatten_steps(plan)
+        .into_iter()
+        .filter_map(|step| {
+            let tool = step.get("name")?.as_str()?;
+            let input = step.get("input")?.as_str()?;
+            completed
+                .iter()
+                .any(|(done_tool, done_input)| done_tool == tool && done_input == input)
+                .then(|| PlanValidationError::RepeatedStep {
+                    tool: tool.to_string(),
+                    input: input.to_string(),
+                })
+        })
+        .collect()
+}
+
+fn validate_steps(
+    plan: &[Value],
+    registered_tools: &[&str],
+    seen_refs: &mut Vec<String>,
+    errors: &mut Vec<PlanValidationError>,
+) {
     for step in plan {
         let Some(step_type) = step.get("type") else {
             errors.push(PlanValidationError::MissingField("type"));
@@ -77,29 +335,263 @@ pub fn validate_plan(plan: &[Value], registered_tools: &[&str]) -> Vec<PlanValid
                     errors.push(PlanValidationError::InvalidTool(name.to_string()));
                 }
 
-                if name != "git_status" && step.get("input").is_none() {
-                    errors.push(PlanValidationError::MissingField("input"));
+                if let Some(input) = step.get("input").and_then(|v| v.as_str())
+                    && input.contains('<')
+                    && input.contains('>')
+                {
+                    errors.push(PlanValidationError::ToolInputMismatch {
+                        tool: name.to_string(),
+                        reason: "Input contains placeholder like <file>".to_string(),
+                    });
+                }
+
+                validate_references(step, seen_refs, errors);
+                if let Some(transform) = step.get("transform") {
+                    validate_transform(name, transform, errors);
                 }
 
-                if let Some(input) = step.get("input").and_then(|v| v.as_str()) {
-                    if input.contains('<') && input.contains('>') {
-                        errors.push(PlanValidationError::ToolInputMismatch {
-                            tool: name.to_string(),
-                            reason: "Input contains placeholder like <file>".to_string(),
-                        });
+                if let Some(id) = step.get("id").and_then(|v| v.as_str()) {
+                    if seen_refs.iter().any(|seen| seen == id) {
+                        errors.push(PlanValidationError::DuplicateStepId(id.to_string()));
                     }
+                    seen_refs.push(id.to_string());
                 }
+                seen_refs.push(name.to_string());
+            }
+            "condition" => {
+                validate_condition(step, registered_tools, seen_refs, errors);
+            }
+            "loop" => {
+                validate_loop(step, registered_tools, seen_refs, errors);
             }
             "info" => {
                 if step.get("message").is_none() {
                     errors.push(PlanValidationError::MissingField("message"));
                 }
             }
+            "ask" => {
+                match step.get("question").and_then(|v| v.as_str()) {
+                    Some(question) => check_references(question, seen_refs, errors),
+                    None => errors.push(PlanValidationError::MissingField("question")),
+                }
+                if step.get("variable").is_none() {
+                    errors.push(PlanValidationError::MissingField("variable"));
+                }
+            }
             unknown => {
                 errors.push(PlanValidationError::UnknownType(unknown.to_string()));
             }
         }
     }
+}
+
+fn validate_condition(
+    step: &Value,
+    registered_tools: &[&str],
+    seen_refs: &mut Vec<String>,
+    errors: &mut Vec<PlanValidationError>,
+) {
+    match step.get("predicate") {
+        Some(predicate) => validate_predicate(predicate, seen_refs, errors),
+        None => errors.push(PlanValidationError::MissingField("predicate")),
+    }
 
+    // Steps in either branch may have run by the time later steps execute.
+    let mut branch_refs = Vec::new();
+    for branch in ["then", "else"] {
+        let mut scoped = seen_refs.clone();
+        if let Some(steps) = step.get(branch).and_then(|v| v.as_array()) {
+            validate_steps(steps, registered_tools, &mut scoped, errors);
+        }
+        branch_refs.extend(scoped.into_iter().skip(seen_refs.len()));
+    }
+    seen_refs.extend(branch_refs);
+}
+
+fn validate_loop(
+    step: &Value,
+    registered_tools: &[&str],
+    seen_refs: &mut Vec<String>,
+    errors: &mut Vec<PlanValidationError>,
+) {
+    match step.get("max_iterations").and_then(|v| v.as_u64()) {
+        Some(0) => errors.push(PlanValidationError::StyleWarning(
+            "Loop has max_iterations 0 and will never run".to_string(),
+        )),
+        Some(n) if n as usize > MAX_LOOP_ITERATIONS => {
+            errors.push(PlanValidationError::StyleWarning(format!(
+                "Loop max_iterations {} exceeds the limit of {} and will be capped",
+                n, MAX_LOOP_ITERATIONS
+            )))
+        }
+        Some(_) => {}
+        None => errors.push(PlanValidationError::MissingField("max_iterations")),
+    }
+
+    match step.get("steps").and_then(|v| v.as_array()) {
+        Some(body) => validate_steps(body, registered_tools, seen_refs, errors),
+        None => errors.push(PlanValidationError::MissingField("steps")),
+    }
+
+    // The exit condition may refer to tools run inside the loop body.
+    if let Some(until) = step.get("until") {
+        validate_predicate(until, seen_refs, errors);
+    }
+}
+
+/// Every `$output[key]` in a step's input or args must name an earlier step id
+/// (or, for steps without ids, an earlier tool). Steps later in the same loop
+/// body, or in the other branch of a condition, do not count as earlier.
+fn validate_references(step: &Value, seen_refs: &[String], errors: &mut Vec<PlanValidationError>) {
+    let mut texts = Vec::new();
+    if let Some(input) = step.get("input") {
+        texts.push(
+            input
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| input.to_string()),
+        );
+    }
+    if let Some(args) = step.get("args") {
+        texts.push(args.to_string());
+    }
+
+    for text in texts {
+        check_references(&text, seen_refs, errors);
+    }
+}
+
+/// A tool step's `transform` must be a list of known output transforms with
+/// valid regex patterns.
+fn validate_transform(tool: &str, transform: &Value, errors: &mut Vec<PlanValidationError>) {
+    match serde_json::from_value::<Vec<OutputTransform>>(transform.clone()) {
+        Ok(transforms) => {
+            for transform in transforms {
+                if let Err(reason) = transform.check() {
+                    errors.push(PlanValidationError::ToolInputMismatch {
+                        tool: tool.to_string(),
+                        reason,
+                    });
+                }
+            }
+        }
+        Err(e) => errors.push(PlanValidationError::ToolInputMismatch {
+            tool: tool.to_string(),
+            reason: format!(
+                "Invalid 'transform' ({}); use a list of {{\"head\": n}}, {{\"tail\": n}}, \"strip_ansi\", {{\"regex\": pattern}} or {{\"json\": \"field.path\"}}",
+                e
+            ),
+        }),
+    }
+}
+
+fn check_references(text: &str, seen_refs: &[String], errors: &mut Vec<PlanValidationError>) {
+    for caps in output_reference_regex().captures_iter(text) {
+        let key = &caps[1];
+        // `$output[key.field]` reads a field of `key`'s structured data.
+        let step = key.split_once('.').map_or(key, |(step, _)| step);
+        if !seen_refs.iter().any(|seen| seen == key || seen == step) {
+            errors.push(PlanValidationError::InvalidReference(key.to_string()));
+        }
+    }
+}
+
+fn validate_predicate(
+    predicate: &Value,
+    seen_refs: &[String],
+    errors: &mut Vec<PlanValidationError>,
+) {
+    match predicate.get("output_of").and_then(|v| v.as_str()) {
+        Some(tool) if !seen_refs.iter().any(|seen| seen == tool) => {
+            errors.push(PlanValidationError::InvalidReference(tool.to_string()));
+        }
+        Some(_) => {}
+        None => errors.push(PlanValidationError::MissingField("output_of")),
+    }
+
+    if let Some(pattern) = predicate.get("matches").and_then(|v| v.as_str())
+        && let Err(e) = regex::Regex::new(pattern)
+    {
+        errors.push(PlanValidationError::RegexError(e.to_string()));
+    }
+}
+
+/// Validates each tool step's input against the `input_schema` its tool
+/// declares: structured `args` (or object-valued `input`) must match it, and a
+/// step without any input is checked as `{}`. Steps for tools without a schema
+/// need a freeform `input`.
+pub fn validate_plan_args(
+    plan: &[Value],
+    schemas: &HashMap<String, Value>,
+) -> Vec<PlanValidationError> {
+    let mut errors = Vec::new();
+
+    for step in flatten_steps(plan) {
+        let Some(name) = step.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let schema = schemas.get(name);
+        let args = match (step.get("args"), step.get("input")) {
+            (Some(args), _) => args.clone(),
+            (None, Some(input)) if input.is_object() => input.clone(),
+            (None, Some(_)) => continue,
+            (None, None) if schema.is_some() => json!({}),
+            (None, None) => {
+                errors.push(PlanValidationError::MissingField("input"));
+                continue;
+            }
+        };
+        let Some(schema) = schema else {
+            continue;
+        };
+
+        for reason in validate_against_schema(&args, schema) {
+            errors.push(PlanValidationError::ToolInputMismatch {
+                tool: name.to_string(),
+                reason,
+            });
+        }
+    }
+
+    errors
+}
+
+/// Every step in `plan`, including those nested in conditions and loops.
+fn flatten_steps(plan: &[Value]) -> Vec<&Value> {
+    let mut steps = Vec::new();
+    for step in plan {
+        steps.push(step);
+        for branch in ["then", "else", "steps"] {
+            if let Some(nested) = step.get(branch).and_then(|v| v.as_array()) {
+                steps.extend(flatten_steps(nested));
+            }
+        }
+    }
+    steps
+}
+
+/// `run_command` steps, at any depth, whose command breaks one of `rules`.
+/// The rule's hint is part of the reason, so correction prompts say how to
+/// avoid it.
+pub fn validate_commands(plan: &[Value], rules: &CommandRules) -> Vec<PlanValidationError> {
+    let mut errors = Vec::new();
+    for step in flatten_steps(plan) {
+        if step.get("name").and_then(|v| v.as_str()) != Some("run_command") {
+            continue;
+        }
+        let command = step
+            .get("args")
+            .or(step.get("input"))
+            .and_then(|v| v.get("command").unwrap_or(v).as_str());
+        let Some(command) = command else {
+            continue;
+        };
+        for rule in rules.check(command) {
+            errors.push(PlanValidationError::ToolInputMismatch {
+                tool: "run_command".to_string(),
+                reason: rule.message(),
+            });
+        }
+    }
     errors
 }
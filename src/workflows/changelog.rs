@@ -0,0 +1,198 @@
+// src/workflows/changelog.rs
+
+use crate::context::Context;
+use crate::llm::json_repair;
+use crate::tools::Tool;
+use crate::tools::llm::LLMTool;
+use crate::workflows::{approve, run_command, shell_quote};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct ChangelogReport {
+    pub since_tag: Option<String>,
+    pub groups: BTreeMap<String, Vec<String>>,
+    pub section: String,
+    pub written: bool,
+}
+
+/// Drafts a changelog section from the commits since the last tag.
+///
+/// Writing and committing `CHANGELOG.md` is gated by the context's `ApprovalPolicy`,
+/// prompting through its `UserInterface`, and skipped entirely in dry-run mode.
+/// The commit holds only the changelog, whatever else is staged.
+pub struct ChangelogWorkflow {
+    llm: LLMTool,
+    path: String,
+    heading: String,
+}
+
+impl ChangelogWorkflow {
+    pub fn new(llm: LLMTool) -> Self {
+        Self {
+            llm,
+            path: "CHANGELOG.md".into(),
+            heading: "Unreleased".into(),
+        }
+    }
+
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Heading for the drafted section, e.g. a version number like `0.2.0`.
+    pub fn with_heading(mut self, heading: &str) -> Self {
+        self.heading = heading.into();
+        self
+    }
+
+    pub fn run(&self, context: &mut Context) -> Result<ChangelogReport, String> {
+        let since_tag = run_command(context, "git describe --tags --abbrev=0")
+            .ok()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty());
+
+        let range = match &since_tag {
+            Some(tag) => format!("{}..HEAD", tag),
+            None => "HEAD".into(),
+        };
+        let log = run_command(context, &format!("git log {} --pretty=format:%s", range))?;
+        let subjects: Vec<&str> = log.lines().filter(|l| !l.trim().is_empty()).collect();
+
+        if subjects.is_empty() {
+            context.log("changelog", "No commits since last tag");
+            return Ok(ChangelogReport {
+                since_tag,
+                groups: BTreeMap::new(),
+                section: String::new(),
+                written: false,
+            });
+        }
+
+        let groups = group_commits(&subjects);
+        let section = self.draft_section(context, &groups)?;
+
+        let written = if context.dry_run {
+            context.log(
+                "changelog",
+                &format!("[dry-run] Would write {}:\n{}", self.path, section),
+            );
+            false
+        } else if approve(context, &format!("Write and commit {}", self.path)) {
+            self.write_section(&section)?;
+            run_command(context, &format!("git add {}", shell_quote(&self.path)))?;
+            run_command(
+                context,
+                &format!(
+                    "git commit -m {} -- {}",
+                    shell_quote(&format!("docs: update changelog for {}", self.heading)),
+                    shell_quote(&self.path)
+                ),
+            )?;
+            true
+        } else {
+            context.log("changelog", "Changelog write rejected by approval policy");
+            false
+        };
+
+        Ok(ChangelogReport {
+            since_tag,
+            groups,
+            section,
+            written,
+        })
+    }
+
+    fn draft_section(
+        &self,
+        context: &mut Context,
+        groups: &BTreeMap<String, Vec<String>>,
+    ) -> Result<String, String> {
+        let grouped = groups
+            .iter()
+            .map(|(kind, items)| {
+                let lines = items
+                    .iter()
+                    .map(|i| format!("  - {}", i))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("{}:\n{}", kind, lines)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let prompt = format!(
+            r#"You are writing release notes. Turn the grouped commit subjects below into a changelog section.
+
+HEADING: ## [{}]
+
+COMMITS BY TYPE:
+{}
+
+Rules:
+- Start with the exact heading above
+- Use ### Added, ### Fixed, ### Changed, ### Documentation and ### Other subsections as appropriate
+- One concise, user-facing bullet per change; merge duplicates
+- Output ONLY the markdown section, no explanations"#,
+            self.heading, grouped
+        );
+
+        let result = self.llm.execute(&prompt);
+        if !result.success {
+            return Err(format!("Changelog LLM failed: {:?}", result.error));
+        }
+
+        let response = result.output.unwrap_or_default();
+        let section = json_repair::strip_think(&response).trim().to_string();
+
+        context.log("changelog", &section);
+        Ok(section)
+    }
+
+    fn write_section(&self, section: &str) -> Result<(), String> {
+        let existing = std::fs::read_to_string(&self.path).unwrap_or_default();
+
+        // Keep a leading "# Changelog" title on top; new sections go right below it.
+        let updated = match existing.strip_prefix("# Changelog") {
+            Some(rest) => format!(
+                "# Changelog\n\n{}\n{}",
+                section,
+                rest.trim_start_matches('\n')
+            ),
+            None if existing.is_empty() => format!("# Changelog\n\n{}\n", section),
+            None => format!("{}\n\n{}", section, existing),
+        };
+
+        std::fs::write(&self.path, updated)
+            .map_err(|e| format!("Failed to write {}: {}", self.path, e))
+    }
+}
+
+/// Groups commit subjects by conventional-commit type (`feat`, `fix`, ...).
+/// Subjects that don't follow the convention land in `other`.
+pub fn group_commits(subjects: &[&str]) -> BTreeMap<String, Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for subject in subjects {
+        let (kind, description) = match subject.split_once(':') {
+            Some((prefix, rest)) => {
+                let kind = prefix
+                    .split('(')
+                    .next()
+                    .unwrap_or(prefix)
+                    .trim_end_matches('!')
+                    .trim();
+                if !kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphabetic()) {
+                    (kind.to_lowercase(), rest.trim().to_string())
+                } else {
+                    ("other".to_string(), subject.trim().to_string())
+                }
+            }
+            None => ("other".to_string(), subject.trim().to_string()),
+        };
+
+        groups.entry(kind).or_default().push(description);
+    }
+
+    groups
+}
@@ -0,0 +1,65 @@
+// src/workflows/mod.rs
+
+pub mod changelog;
+pub mod release;
+pub mod review;
+
+use crate::context::{Context, ToolCallRecord};
+use crate::policy::ApprovalPolicy;
+use std::time::Instant;
+
+pub use changelog::{ChangelogReport, ChangelogWorkflow, group_commits};
+pub use release::ReleaseWorkflow;
+pub use review::{ReviewFinding, ReviewReport, ReviewSeverity, ReviewWorkflow};
+
+/// Runs a shell command through the context's `run_command` tool, recording it
+/// in the tool history.
+pub(crate) fn run_command(context: &mut Context, command: &str) -> Result<String, String> {
+    if !context.allows("run_command", command) {
+        return Err(format!("Command not allowed by context: {}", command));
+    }
+
+    let started = Instant::now();
+    let result = match context.get_tool("run_command") {
+        Some(tool) => tool.execute(command),
+        None => return Err("run_command tool is not registered".into()),
+    };
+
+    context.record_tool_call(ToolCallRecord {
+        step_id: None,
+        tool: "run_command".into(),
+        input: command.into(),
+        output: result
+            .output
+            .clone()
+            .or_else(|| result.error.clone())
+            .unwrap_or_default(),
+        duration: started.elapsed(),
+        success: result.success,
+    });
+
+    if result.success {
+        Ok(result.output.unwrap_or_default())
+    } else {
+        Err(result.error.unwrap_or_else(|| "Unknown error".into()))
+    }
+}
+
+/// Whether the context's approval policy allows `action`, prompting through
+/// its `UserInterface` first and the policy's terminal prompt otherwise, the
+/// way plan steps are approved.
+pub(crate) fn approve(context: &Context, action: &str) -> bool {
+    let policy = context.approval;
+    let decided = match policy {
+        ApprovalPolicy::AutoApprove => Some(true),
+        ApprovalPolicy::DenyAll => Some(false),
+        ApprovalPolicy::AlwaysAsk | ApprovalPolicy::AskDestructive => None,
+    };
+    decided
+        .or_else(|| context.user.approve(action, &[]))
+        .unwrap_or_else(|| policy.approve(action))
+}
+
+pub(crate) fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
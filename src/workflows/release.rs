@@ -0,0 +1,54 @@
+// src/workflows/release.rs
+
+use crate::protocol::{Plan, PlanStep};
+use serde_json::json;
+
+/// Plan template for cutting a release: test → bump → commit → tag → publish dry-run.
+///
+/// Needs the `bump_version`, `git_commit`, `git_tag` and `publish_dry_run`
+/// tools registered. `bump_version` and `git_tag` are tagged `destructive`,
+/// so under `ApprovalPolicy::AskDestructive` only those steps prompt for
+/// confirmation.
+pub struct ReleaseWorkflow {
+    level: String,
+}
+
+impl ReleaseWorkflow {
+    pub fn new(level: &str) -> Self {
+        Self {
+            level: level.into(),
+        }
+    }
+
+    /// Recognizes goals like "cut a patch release" or "release a new minor version".
+    pub fn from_goal(goal: &str) -> Option<Self> {
+        let goal = goal.to_lowercase();
+        if !goal.contains("release") {
+            return None;
+        }
+        ["major", "minor", "patch"]
+            .iter()
+            .find(|level| goal.contains(*level))
+            .map(|level| Self::new(level))
+    }
+
+    pub fn plan(&self) -> Plan {
+        Plan {
+            steps: vec![
+                PlanStep::Info(format!("Cut a {} release", self.level)),
+                PlanStep::tool("run_command", "cargo test"),
+                PlanStep::tool("bump_version", &self.level).with_id("version"),
+                PlanStep::tool_with_args(
+                    "git_commit",
+                    json!({
+                        "message": format!("chore: {} release", self.level),
+                        "paths": ["Cargo.toml", "Cargo.lock"],
+                    }),
+                ),
+                PlanStep::tool("git_tag", "$output[version]"),
+                PlanStep::tool("publish_dry_run", ""),
+                PlanStep::Info("Release prepared; push the tag to publish".into()),
+            ],
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// src/workflows/review.rs
+
+use crate::context::Context;
+use crate::llm::json_repair;
+use crate::tools::Tool;
+use crate::tools::llm::LLMTool;
+use crate::workflows::{run_command, shell_quote};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A single structured finding produced by the reviewer LLM.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewFinding {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    pub severity: ReviewSeverity,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReviewReport {
+    pub pr_number: u32,
+    pub findings: Vec<ReviewFinding>,
+    pub posted: bool,
+}
+
+#[derive(Deserialize)]
+struct ReviewResponse {
+    #[serde(default)]
+    findings: Vec<ReviewFinding>,
+}
+
+/// End-to-end pull-request review: fetch diff → run analysis → LLM findings → post review.
+///
+/// Findings with a line number are posted as inline comments through the
+/// pull-request reviews API; the rest are listed in the review body.
+///
+/// Shell access goes through the `run_command` tool registered on the `Context`,
+/// so the workflow sees the same whitelist and memory logging as a normal plan.
+/// `gh` is not on the default whitelist; enable it via `Context::enable_unsafe_shell`.
+pub struct ReviewWorkflow {
+    llm: LLMTool,
+    analysis_commands: Vec<String>,
+    max_diff_chars: usize,
+}
+
+impl ReviewWorkflow {
+    pub fn new(llm: LLMTool) -> Self {
+        Self {
+            llm,
+            analysis_commands: Vec::new(),
+            max_diff_chars: 20_000,
+        }
+    }
+
+    pub fn with_analysis_command(mut self, command: &str) -> Self {
+        self.analysis_commands.push(command.into());
+        self
+    }
+
+    pub fn with_max_diff_chars(mut self, max: usize) -> Self {
+        self.max_diff_chars = max;
+        self
+    }
+
+    pub fn run(&self, context: &mut Context, pr_number: u32) -> Result<ReviewReport, String> {
+        context.log("review", &format!("Starting review of PR #{}", pr_number));
+
+        let diff = run_command(context, &format!("gh pr diff {}", pr_number))?;
+        let diff: String = diff.chars().take(self.max_diff_chars).collect();
+
+        let mut analysis = String::new();
+        for command in &self.analysis_commands {
+            // Analysis failures are still useful signal for the reviewer, so keep the text.
+            let output = run_command(context, command).unwrap_or_else(|e| e);
+            analysis.push_str(&format!("$ {}\n{}\n\n", command, output));
+        }
+
+        let findings = self.request_findings(context, &diff, &analysis)?;
+
+        if findings.is_empty() {
+            context.log("review", "No findings reported");
+            return Ok(ReviewReport {
+                pr_number,
+                findings,
+                posted: false,
+            });
+        }
+
+        let command = review_command(pr_number, &findings);
+        let posted = if context.dry_run {
+            context.log(
+                "review",
+                &format!("[dry-run] Would post review:\n{}", command),
+            );
+            false
+        } else {
+            run_command(context, &command)?;
+            true
+        };
+
+        Ok(ReviewReport {
+            pr_number,
+            findings,
+            posted,
+        })
+    }
+
+    fn request_findings(
+        &self,
+        context: &mut Context,
+        diff: &str,
+        analysis: &str,
+    ) -> Result<Vec<ReviewFinding>, String> {
+        let prompt = format!(
+            r#"You are a meticulous code reviewer. Review the pull request diff below.
+
+DIFF:
+{}
+
+STATIC ANALYSIS OUTPUT:
+{}
+
+Report only concrete problems (bugs, unsafe behavior, missing error handling, unclear code).
+Use the file path and new-side line number from the diff.
+
+Respond with ONLY a JSON object in this format:
+{{
+  "findings": [
+    {{"file": "src/lib.rs", "line": 42, "severity": "warning", "suggestion": "What to change and why"}}
+  ]
+}}
+
+Valid severities: "info", "warning", "error". Return an empty findings array if the diff looks good."#,
+            diff,
+            if analysis.is_empty() {
+                "(none)"
+            } else {
+                analysis
+            }
+        );
+
+        let result = self.llm.execute(&prompt);
+        if !result.success {
+            return Err(format!("Review LLM failed: {:?}", result.error));
+        }
+
+        let response = result.output.unwrap_or_default();
+        context.log("review", &response);
+
+        json_repair::parse_json::<ReviewResponse>(&response)
+            .map(|parsed| parsed.findings)
+            .map_err(|e| format!("Failed to parse review findings: {}", e))
+    }
+}
+
+/// Builds the `gh api` call that posts `findings` as one review, with a
+/// right-side inline comment for every finding that has a line.
+fn review_command(pr_number: u32, findings: &[ReviewFinding]) -> String {
+    let mut command = format!(
+        "gh api --method POST 'repos/{{owner}}/{{repo}}/pulls/{}/reviews' -f event=COMMENT -f body={}",
+        pr_number,
+        shell_quote(&render_review_body(findings))
+    );
+    for finding in findings {
+        let Some(line) = finding.line else { continue };
+        command.push_str(&format!(
+            " -f {} -F 'comments[][line]={}' -f 'comments[][side]=RIGHT' -f {}",
+            shell_quote(&format!("comments[][path]={}", finding.file)),
+            line,
+            shell_quote(&format!(
+                "comments[][body]=**{:?}** — {}",
+                finding.severity, finding.suggestion
+            ))
+        ));
+    }
+    command
+}
+
+fn render_review_body(findings: &[ReviewFinding]) -> String {
+    let mut body = String::from("Automated review findings");
+    let unplaced: Vec<&ReviewFinding> = findings.iter().filter(|f| f.line.is_none()).collect();
+    if unplaced.len() == findings.len() {
+        body.push_str(":\n\n");
+    } else {
+        body.push_str(" (line-specific findings are inline):\n\n");
+    }
+    for finding in unplaced {
+        body.push_str(&format!(
+            "- **{:?}** `{}` — {}\n",
+            finding.severity, finding.file, finding.suggestion
+        ));
+    }
+    body
+}
@@ -0,0 +1,358 @@
+// src/workspace/mod.rs
+
+use serde::Deserialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directories a copy snapshot neither saves nor restores (VCS data and
+/// build output).
+const COPY_SKIPPED: [&str; 2] = [".git", "target"];
+
+/// The directory a run works in. Tools built with a workspace run there and
+/// refuse paths that resolve outside it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Workspace {
+    root: PathBuf,
+}
+
+impl Workspace {
+    /// A workspace rooted at `root`, which must be an existing directory.
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, String> {
+        let root = root.as_ref();
+        let canonical = root
+            .canonicalize()
+            .map_err(|e| format!("Invalid workspace {}: {}", root.display(), e))?;
+        if !canonical.is_dir() {
+            return Err(format!(
+                "Invalid workspace {}: not a directory",
+                root.display()
+            ));
+        }
+        Ok(Self { root: canonical })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `path` (relative to the root, or absolute) as an absolute path inside
+    /// the workspace. `..` components and symlinks may not lead outside it.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Result<PathBuf, String> {
+        let path = path.as_ref();
+        let resolved = normalize(&self.root.join(path));
+        if !resolved.starts_with(&self.root) {
+            return Err(format!(
+                "Path '{}' is outside the workspace {}",
+                path.display(),
+                self.root.display()
+            ));
+        }
+        // The lexical check passed; make sure no existing symlink escapes.
+        let existing = resolved
+            .ancestors()
+            .find(|ancestor| ancestor.exists())
+            .and_then(|ancestor| ancestor.canonicalize().ok());
+        match existing {
+            Some(real) if !real.starts_with(&self.root) => Err(format!(
+                "Path '{}' leads outside the workspace {} (via {})",
+                path.display(),
+                self.root.display(),
+                real.display()
+            )),
+            _ => Ok(resolved),
+        }
+    }
+
+    /// Why shell `command` may not run here: it steps out with `..`, names
+    /// an absolute path outside the workspace, or starts a path at `~` or a
+    /// variable. A lexical check of the words of the command, so it catches
+    /// paths spelled out in it but not ones a program computes.
+    pub fn check_command(&self, command: &str) -> Result<(), String> {
+        check_command_paths(&self.root, command)
+    }
+}
+
+/// Token-level path confinement for shell commands run in `root`. Tokens
+/// are split at whitespace and at shell operators, so `cd ..;ls` and
+/// `cat</etc/passwd` are seen as the paths they use.
+pub(crate) fn check_command_paths(root: &Path, command: &str) -> Result<(), String> {
+    let operators = |c: char| c.is_whitespace() || ";&|<>()`".contains(c);
+    for token in command.split(operators).filter(|token| !token.is_empty()) {
+        let token = token.trim_matches(|c| c == '\'' || c == '"');
+        if token == ".." || token.starts_with("../") || token.contains("/../") {
+            return Err(format!("Path '{}' escapes the working directory", token));
+        }
+        if token.starts_with('~') || is_variable_path(token) {
+            return Err(format!(
+                "Path '{}' may be outside the working directory",
+                token
+            ));
+        }
+        let path = Path::new(token);
+        if token.starts_with('/') && path != Path::new("/dev/null") && !path.starts_with(root) {
+            return Err(format!("Path '{}' is outside the working directory", token));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `token` is a path starting at a variable (`$HOME/x`, `${HOME}`),
+/// which can point anywhere.
+fn is_variable_path(token: &str) -> bool {
+    let Some(rest) = token.strip_prefix('$') else {
+        return false;
+    };
+    let (name, rest) = match rest.strip_prefix('{') {
+        Some(braced) => braced.split_once('}').unwrap_or((braced, "")),
+        None => rest.split_at(
+            rest.find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len()),
+        ),
+    };
+    rest.starts_with('/') || matches!(name, "HOME" | "OLDPWD" | "TMPDIR")
+}
+
+/// Removes `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// How `WorkspaceSnapshot` saves the workspace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotStrategy {
+    /// `GitStash` when the workspace is the top of a git work tree, else `Copy`.
+    #[default]
+    Auto,
+    /// Stashes tracked and untracked changes (ignored files are untouched)
+    /// and remembers `HEAD`.
+    GitStash,
+    /// Copies the directory, minus `.git` and `target`, to a temp directory.
+    Copy,
+}
+
+/// A saved state of a workspace, taken before a risky run so its changes
+/// can be rolled back. Either `restore` or `discard` it.
+#[derive(Debug)]
+pub struct WorkspaceSnapshot {
+    root: PathBuf,
+    saved: Saved,
+}
+
+#[derive(Debug)]
+enum Saved {
+    Git {
+        head: String,
+        /// Stash commit holding uncommitted changes; `None` if there were none.
+        stash: Option<String>,
+    },
+    Copy {
+        dir: PathBuf,
+    },
+}
+
+impl WorkspaceSnapshot {
+    pub fn take(workspace: &Workspace) -> Result<Self, String> {
+        Self::take_with(workspace, SnapshotStrategy::Auto)
+    }
+
+    pub fn take_with(workspace: &Workspace, strategy: SnapshotStrategy) -> Result<Self, String> {
+        let root = workspace.root().to_path_buf();
+        let saved = match strategy {
+            SnapshotStrategy::GitStash => take_git(&root)?,
+            SnapshotStrategy::Copy => take_copy(&root)?,
+            SnapshotStrategy::Auto if is_git_top_level(&root) => match take_git(&root) {
+                Ok(saved) => saved,
+                // E.g. a repository without commits yet.
+                Err(_) => take_copy(&root)?,
+            },
+            SnapshotStrategy::Auto => take_copy(&root)?,
+        };
+        Ok(Self { root, saved })
+    }
+
+    /// The strategy that was actually used.
+    pub fn strategy(&self) -> SnapshotStrategy {
+        match self.saved {
+            Saved::Git { .. } => SnapshotStrategy::GitStash,
+            Saved::Copy { .. } => SnapshotStrategy::Copy,
+        }
+    }
+
+    /// Puts the workspace back as it was when the snapshot was taken,
+    /// discarding everything changed since.
+    pub fn restore(self) -> Result<(), String> {
+        match &self.saved {
+            Saved::Git { head, stash } => {
+                git(&self.root, &["reset", "--hard", head])?;
+                git(&self.root, &["clean", "-fd"])?;
+                if let Some(stash) = stash {
+                    git(&self.root, &["stash", "apply", "--index", stash])?;
+                    drop_stash(&self.root, stash)?;
+                }
+                Ok(())
+            }
+            Saved::Copy { dir } => {
+                for entry in read_dir(&self.root)? {
+                    let path = entry.path();
+                    if skipped(&path) {
+                        continue;
+                    }
+                    let removed = if path.is_dir() && !path.is_symlink() {
+                        fs::remove_dir_all(&path)
+                    } else {
+                        fs::remove_file(&path)
+                    };
+                    removed.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+                }
+                copy_tree(dir, &self.root)?;
+                let _ = fs::remove_dir_all(dir);
+                Ok(())
+            }
+        }
+    }
+
+    /// Forgets the snapshot, keeping the workspace as it is.
+    pub fn discard(self) -> Result<(), String> {
+        match &self.saved {
+            Saved::Git {
+                stash: Some(stash), ..
+            } => drop_stash(&self.root, stash),
+            Saved::Git { stash: None, .. } => Ok(()),
+            Saved::Copy { dir } => fs::remove_dir_all(dir)
+                .map_err(|e| format!("Failed to remove snapshot {}: {}", dir.display(), e)),
+        }
+    }
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn is_git_top_level(root: &Path) -> bool {
+    git(root, &["rev-parse", "--show-toplevel"])
+        .ok()
+        .and_then(|top| Path::new(&top).canonicalize().ok())
+        .is_some_and(|top| top == root)
+}
+
+fn take_git(root: &Path) -> Result<Saved, String> {
+    if !is_git_top_level(root) {
+        return Err(format!(
+            "{} is not the top of a git work tree",
+            root.display()
+        ));
+    }
+    let head = git(root, &["rev-parse", "HEAD"])?;
+    let before = git(root, &["rev-parse", "-q", "--verify", "refs/stash"]).ok();
+    git(
+        root,
+        &[
+            "stash",
+            "push",
+            "--include-untracked",
+            "-m",
+            "agentic-runtime snapshot",
+        ],
+    )?;
+    let after = git(root, &["rev-parse", "-q", "--verify", "refs/stash"]).ok();
+    let stash = after.filter(|after| before.as_ref() != Some(after));
+    if let Some(stash) = &stash {
+        // `stash push` cleaned the tree; put the changes back for the run.
+        git(root, &["stash", "apply", "--index", stash]).map_err(|e| {
+            format!(
+                "{}; your changes are saved in stash {} (`git stash apply {}`)",
+                e, stash, stash
+            )
+        })?;
+    }
+    Ok(Saved::Git { head, stash })
+}
+
+fn drop_stash(root: &Path, stash: &str) -> Result<(), String> {
+    let list = git(root, &["stash", "list", "--format=%H"])?;
+    match list.lines().position(|hash| hash == stash) {
+        Some(index) => git(root, &["stash", "drop", &format!("stash@{{{}}}", index)]).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+fn take_copy(root: &Path) -> Result<Saved, String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let dir =
+        std::env::temp_dir().join(format!("agentic-snapshot-{}-{}", std::process::id(), nanos));
+    if let Err(e) = copy_tree(root, &dir) {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(Saved::Copy { dir })
+}
+
+fn skipped(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| COPY_SKIPPED.contains(&name))
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<fs::DirEntry>, String> {
+    fs::read_dir(dir)
+        .and_then(|entries| entries.collect())
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))
+}
+
+/// Copies `from` into `to` (created if missing), skipping `COPY_SKIPPED`.
+fn copy_tree(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    for entry in read_dir(from)? {
+        let source = entry.path();
+        if skipped(&source) {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        if file_type.is_dir() {
+            copy_tree(&source, &target)?;
+        } else if file_type.is_symlink() {
+            #[cfg(unix)]
+            {
+                let link = fs::read_link(&source)
+                    .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+                std::os::unix::fs::symlink(link, &target)
+                    .map_err(|e| format!("Failed to link {}: {}", target.display(), e))?;
+            }
+        } else {
+            fs::copy(&source, &target)
+                .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+        }
+    }
+    Ok(())
+}